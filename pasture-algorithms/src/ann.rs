@@ -0,0 +1,367 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{BinaryHeap, HashSet},
+};
+
+use pasture_core::nalgebra::Vector3;
+use rand::{prelude::StdRng, Rng, SeedableRng};
+
+use crate::kdtree::{Candidate, DistanceMode};
+
+/// Parameters of the approximate nearest neighbor search of a [KdForest]. More trees and more checks give more accurate
+/// neighbors at the cost of a slower search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApproximateSearchParameters {
+    /// Number of randomized kd-trees of the forest
+    pub trees: usize,
+    /// Maximum number of points whose distance to the query position is calculated per query, over all trees. The
+    /// search checks more points if fewer than `k` neighbors were found
+    pub max_checks: usize,
+    /// Seed for the random choice of the split axes, so that the neighbors are reproducible
+    pub seed: u64,
+}
+
+impl Default for ApproximateSearchParameters {
+    fn default() -> Self {
+        Self {
+            trees: 4,
+            max_checks: 128,
+            seed: 0,
+        }
+    }
+}
+
+/// A branch of one of the trees of a [KdForest] that was not visited yet. Ordered by the lower bound of the distance
+/// between the query position and the points of the branch
+#[derive(Debug, Clone, Copy)]
+struct Branch {
+    distance_squared: f64,
+    tree: usize,
+    start: usize,
+    end: usize,
+}
+
+impl PartialEq for Branch {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Branch {}
+
+impl PartialOrd for Branch {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Branch {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_squared
+            .partial_cmp(&other.distance_squared)
+            .unwrap_or(Ordering::Equal)
+            .then(self.tree.cmp(&other.tree))
+            .then(self.start.cmp(&other.start))
+    }
+}
+
+/// One randomized kd-tree of a [KdForest], stored implicitly like a [KdTree](crate::kdtree::KdTree)
+struct RandomizedTree {
+    order: Vec<usize>,
+    split_axes: Vec<u8>,
+}
+
+/// A forest of randomized kd-trees for approximate nearest neighbor queries, following Silpa-Anan and Hartley
+/// ("Optimised KD-trees for fast image descriptor matching"). Each tree splits its cells at the median along an axis
+/// that is chosen randomly among the axes with a large extent, so the trees partition the points differently. A query
+/// descends all trees at once and visits the closest unvisited branches of all trees first, until `max_checks` points
+/// were checked. On large point clouds, this finds almost all true neighbors in a fraction of the time of an exact
+/// search, which is good enough for e.g. normal estimation or neighborhood features
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_algorithms::ann::{ApproximateSearchParameters, KdForest};
+/// let positions = (0..1000)
+///     .map(|i| Vector3::new((i % 10) as f64, ((i / 10) % 10) as f64, (i / 100) as f64))
+///     .collect();
+/// let forest = KdForest::new(positions, &ApproximateSearchParameters::default());
+/// let neighbors = forest.nearest_neighbors(&Vector3::new(4.9, 5.0, 5.1), 1);
+/// assert_eq!(vec![555], neighbors.iter().map(|(index, _)| *index).collect::<Vec<_>>());
+/// ```
+pub struct KdForest {
+    positions: Vec<Vector3<f64>>,
+    trees: Vec<RandomizedTree>,
+    max_checks: usize,
+    distance_mode: DistanceMode,
+}
+
+impl KdForest {
+    /// Builds a forest of randomized kd-trees over the given `positions`. The points are identified by their index
+    /// within `positions`
+    ///
+    /// # Panics
+    ///
+    /// If `parameters.trees` is zero
+    pub fn new(positions: Vec<Vector3<f64>>, parameters: &ApproximateSearchParameters) -> Self {
+        Self::with_distance_mode(positions, parameters, DistanceMode::Xyz)
+    }
+
+    /// Like [new](KdForest::new), but the neighbor queries measure distances according to `distance_mode`
+    ///
+    /// # Panics
+    ///
+    /// If `parameters.trees` is zero
+    pub fn with_distance_mode(
+        positions: Vec<Vector3<f64>>,
+        parameters: &ApproximateSearchParameters,
+        distance_mode: DistanceMode,
+    ) -> Self {
+        if parameters.trees == 0 {
+            panic!("KdForest::with_distance_mode: parameters.trees must be greater than zero");
+        }
+        let mut rng = StdRng::seed_from_u64(parameters.seed);
+        let trees = (0..parameters.trees)
+            .map(|_| {
+                let mut tree = RandomizedTree {
+                    order: (0..positions.len()).collect(),
+                    split_axes: vec![0; positions.len()],
+                };
+                build_randomized(
+                    &mut tree,
+                    &positions,
+                    0,
+                    positions.len(),
+                    distance_mode,
+                    &mut rng,
+                );
+                tree
+            })
+            .collect();
+        Self {
+            positions,
+            trees,
+            max_checks: parameters.max_checks,
+            distance_mode,
+        }
+    }
+
+    /// Returns the number of points in this forest
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns true if this forest contains no points
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Returns the positions of all points in this forest, in their original order
+    pub fn positions(&self) -> &[Vector3<f64>] {
+        &self.positions
+    }
+
+    /// Returns the indices of (approximately) the `k` points that are closest to `position`, together with their
+    /// distances to `position`, sorted by ascending distance. Some of the returned points might be farther away than
+    /// the true `k` nearest neighbors
+    pub fn nearest_neighbors(&self, position: &Vector3<f64>, k: usize) -> Vec<(usize, f64)> {
+        self.search_nearest(position, None, k)
+            .into_iter()
+            .map(|candidate| (candidate.index, candidate.distance_squared.sqrt()))
+            .collect()
+    }
+
+    /// Returns (approximately) the `k` nearest neighbors of the point at `query_index`, excluding the point itself,
+    /// sorted by ascending distance
+    pub(crate) fn nearest_neighbors_of_point(
+        &self,
+        query_index: usize,
+        k: usize,
+    ) -> Vec<Candidate> {
+        self.search_nearest(&self.positions[query_index], Some(query_index), k)
+    }
+
+    fn search_nearest(
+        &self,
+        query: &Vector3<f64>,
+        excluded_index: Option<usize>,
+        k: usize,
+    ) -> Vec<Candidate> {
+        if self.is_empty() || k == 0 {
+            return vec![];
+        }
+        let mut candidates = BinaryHeap::with_capacity(k + 1);
+        // The same point is reached through every tree, but has to be checked only once
+        let mut checked = HashSet::new();
+        let mut branches = (0..self.trees.len())
+            .map(|tree| {
+                Reverse(Branch {
+                    distance_squared: 0.0,
+                    tree,
+                    start: 0,
+                    end: self.len(),
+                })
+            })
+            .collect::<BinaryHeap<_>>();
+
+        while let Some(Reverse(branch)) = branches.pop() {
+            if candidates.len() == k {
+                let farthest: &Candidate = candidates.peek().unwrap();
+                if checked.len() >= self.max_checks
+                    || branch.distance_squared > farthest.distance_squared
+                {
+                    break;
+                }
+            }
+
+            // Descend to a leaf along the near children, remembering the far children for later
+            let tree = &self.trees[branch.tree];
+            let (mut start, mut end) = (branch.start, branch.end);
+            while start < end {
+                let center = (start + end) / 2;
+                let index = tree.order[center];
+                let position = &self.positions[index];
+                if excluded_index != Some(index) && checked.insert(index) {
+                    let candidate = Candidate {
+                        distance_squared: self.distance_mode.distance_squared(position, query),
+                        index,
+                    };
+                    if candidates.len() < k {
+                        candidates.push(candidate);
+                    } else if candidate < *candidates.peek().unwrap() {
+                        candidates.pop();
+                        candidates.push(candidate);
+                    }
+                }
+
+                let axis = tree.split_axes[center] as usize;
+                let offset = query[axis] - position[axis];
+                let (near, far) = if offset < 0.0 {
+                    ((start, center), (center + 1, end))
+                } else {
+                    ((center + 1, end), (start, center))
+                };
+                if far.0 < far.1 {
+                    branches.push(Reverse(Branch {
+                        distance_squared: branch.distance_squared.max(offset * offset),
+                        tree: branch.tree,
+                        start: far.0,
+                        end: far.1,
+                    }));
+                }
+                start = near.0;
+                end = near.1;
+            }
+        }
+        candidates.into_sorted_vec()
+    }
+}
+
+/// Builds the cells of `tree` within `start..end` like `KdTree::build`, but splits along a random axis among all axes
+/// whose extent is at least half of the largest extent
+fn build_randomized(
+    tree: &mut RandomizedTree,
+    positions: &[Vector3<f64>],
+    start: usize,
+    end: usize,
+    distance_mode: DistanceMode,
+    rng: &mut StdRng,
+) {
+    if end - start <= 1 {
+        return;
+    }
+    let mut min = positions[tree.order[start]];
+    let mut max = min;
+    for index in &tree.order[start..end] {
+        min = min.inf(&positions[*index]);
+        max = max.sup(&positions[*index]);
+    }
+    let extent = max - min;
+    let axis_count = match distance_mode {
+        DistanceMode::Xyz => 3,
+        DistanceMode::Xy => 2,
+    };
+    let largest_extent = (0..axis_count).map(|axis| extent[axis]).fold(0.0, f64::max);
+    let split_candidates = (0..axis_count)
+        .filter(|axis| extent[*axis] >= 0.5 * largest_extent)
+        .collect::<Vec<_>>();
+    let axis = split_candidates[rng.gen_range(0..split_candidates.len())];
+
+    let center = (start + end) / 2;
+    tree.order[start..end].select_nth_unstable_by(center - start, |a, b| {
+        positions[*a][axis]
+            .partial_cmp(&positions[*b][axis])
+            .unwrap_or(Ordering::Equal)
+    });
+    tree.split_axes[center] = axis as u8;
+    build_randomized(tree, positions, start, center, distance_mode, rng);
+    build_randomized(tree, positions, center + 1, end, distance_mode, rng);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kdtree::KdTree;
+
+    fn random_positions(count: usize) -> Vec<Vector3<f64>> {
+        let mut rng = StdRng::seed_from_u64(77);
+        (0..count)
+            .map(|_| {
+                Vector3::new(
+                    rng.gen_range(-50.0..50.0),
+                    rng.gen_range(-50.0..50.0),
+                    rng.gen_range(-5.0..5.0),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_approximate_neighbors_have_high_recall() {
+        let positions = random_positions(5000);
+        let tree = KdTree::new(positions.clone());
+        let forest = KdForest::new(positions, &ApproximateSearchParameters::default());
+
+        let k = 10;
+        let mut found = 0;
+        for query_index in (0..forest.len()).step_by(50) {
+            let exact = tree
+                .nearest_neighbors_of_point(query_index, k)
+                .into_iter()
+                .map(|candidate| candidate.index)
+                .collect::<HashSet<_>>();
+            let approximate = forest.nearest_neighbors_of_point(query_index, k);
+            assert_eq!(k, approximate.len());
+            assert!(approximate
+                .iter()
+                .all(|candidate| candidate.index != query_index));
+            found += approximate
+                .iter()
+                .filter(|candidate| exact.contains(&candidate.index))
+                .count();
+        }
+        let recall = found as f64 / (100 * k) as f64;
+        assert!(recall > 0.9, "recall is only {}", recall);
+    }
+
+    #[test]
+    fn test_approximate_neighbors_are_exact_with_enough_checks() {
+        let positions = random_positions(500);
+        let tree = KdTree::new(positions.clone());
+        let parameters = ApproximateSearchParameters {
+            trees: 2,
+            max_checks: 500,
+            seed: 3,
+        };
+        let forest = KdForest::new(positions, &parameters);
+        let position = Vector3::new(10.0, -20.0, 0.0);
+        assert_eq!(
+            tree.nearest_neighbors(&position, 20),
+            forest.nearest_neighbors(&position, 20)
+        );
+        assert!(KdForest::new(vec![], &parameters)
+            .nearest_neighbors(&position, 3)
+            .is_empty());
+    }
+}
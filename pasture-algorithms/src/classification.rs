@@ -0,0 +1,387 @@
+use anyhow::{anyhow, Result};
+use pasture_core::{
+    containers::{PointBufferExt, PointBufferWriteable, PointBufferWriteableExt},
+    layout::{attributes::CLASSIFICATION, PointAttributeDataType},
+};
+use rayon::prelude::*;
+
+use crate::features::{feature_matrix, Feature, FeatureChunk, FeatureNormalization};
+
+/// A table that translates classification codes from one classification scheme into another, for use in
+/// [remap_classification]. Codes that are not part of the table are mapped to the default code, if one is set, and are
+/// kept unchanged otherwise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClassificationMapping {
+    table: [Option<u8>; 256],
+    default: Option<u8>,
+}
+
+impl ClassificationMapping {
+    /// Creates a new empty `ClassificationMapping` that keeps all classification codes unchanged
+    pub fn new() -> Self {
+        Self {
+            table: [None; 256],
+            default: None,
+        }
+    }
+
+    /// Creates a new `ClassificationMapping` that maps the first code of each of the given `pairs` to the second code
+    pub fn from_pairs<I: IntoIterator<Item = (u8, u8)>>(pairs: I) -> Self {
+        let mut mapping = Self::new();
+        for (from, to) in pairs {
+            mapping.insert(from, to);
+        }
+        mapping
+    }
+
+    /// Mapping from the classification codes of LAS 1.1 to LAS 1.3 to the ASPRS standard classes of LAS 1.4. LAS 1.4
+    /// turned the 'Model Key-point' (8) and 'Overlap Points' (12) classes into flags, so model key-points become ground
+    /// points (2) and overlap points become unclassified points (1). The formerly reserved codes 10 and 11 are defined
+    /// as 'Rail' and 'Road Surface' in LAS 1.4, so they are mapped to unclassified (1) as well
+    pub fn legacy_las_to_asprs() -> Self {
+        Self::from_pairs(vec![(8, 2), (10, 1), (11, 1), (12, 1)])
+    }
+
+    /// Parses a `ClassificationMapping` from CSV text. Each line contains a source code and a target code, separated
+    /// by a comma, semicolon or whitespace. Empty lines, lines starting with `#` and a header line that does not
+    /// start with a number are ignored. A source code of `*` sets the default code for all unmapped codes
+    ///
+    /// ```
+    /// # use pasture_algorithms::classification::ClassificationMapping;
+    /// let mapping = ClassificationMapping::from_csv("from,to\n8,2\n# Overlap points\n12,1\n").unwrap();
+    /// assert_eq!(2, mapping.map(8));
+    /// assert_eq!(1, mapping.map(12));
+    /// assert_eq!(6, mapping.map(6));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If a line does not contain exactly two values, or if a value is no valid classification code
+    pub fn from_csv(text: &str) -> Result<Self> {
+        let mut mapping = Self::new();
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let values = line
+                .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+                .map(|value| value.trim())
+                .filter(|value| !value.is_empty())
+                .collect::<Vec<_>>();
+            let is_header = line_index == 0
+                && !values
+                    .first()
+                    .map(|value| *value == "*" || value.starts_with(|c: char| c.is_ascii_digit()))
+                    .unwrap_or(false);
+            if is_header {
+                continue;
+            }
+            if values.len() != 2 {
+                return Err(anyhow!(
+                    "Line {} of classification mapping: Expected two values but found {}",
+                    line_index + 1,
+                    values.len()
+                ));
+            }
+            let parse_code = |value: &str| {
+                value.parse::<u8>().map_err(|e| {
+                    anyhow!(
+                        "Line {} of classification mapping: Invalid classification code {}: {}",
+                        line_index + 1,
+                        value,
+                        e
+                    )
+                })
+            };
+            let to = parse_code(values[1])?;
+            if values[0] == "*" {
+                mapping.set_default(Some(to));
+            } else {
+                mapping.insert(parse_code(values[0])?, to);
+            }
+        }
+        Ok(mapping)
+    }
+
+    /// Maps the classification code `from` to `to`
+    pub fn insert(&mut self, from: u8, to: u8) {
+        self.table[from as usize] = Some(to);
+    }
+
+    /// Sets the code that all codes which are not part of this mapping are mapped to. If `default` is `None`, these
+    /// codes are kept unchanged
+    pub fn set_default(&mut self, default: Option<u8>) {
+        self.default = default;
+    }
+
+    /// Returns the code that all codes which are not part of this mapping are mapped to
+    pub fn default_code(&self) -> Option<u8> {
+        self.default
+    }
+
+    /// Returns the code that the given classification `code` is mapped to
+    pub fn map(&self, code: u8) -> u8 {
+        self.table[code as usize].or(self.default).unwrap_or(code)
+    }
+}
+
+impl Default for ClassificationMapping {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Translates the `CLASSIFICATION` attribute of all points in `buffer` using the given `mapping`. Returns the number of
+/// points whose classification changed. Buffers without a `CLASSIFICATION` attribute of type `u8` are not changed
+///
+/// ```
+/// # use pasture_algorithms::classification::{remap_classification, ClassificationMapping};
+/// # use pasture_core::containers::{InterleavedVecPointStorage, PointBufferExt};
+/// # use pasture_core::layout::{attributes::CLASSIFICATION, PointType};
+/// # use pasture_derive::PointType;
+/// #[repr(C)]
+/// #[derive(PointType, Debug)]
+/// struct ClassifiedPoint {
+///     #[pasture(BUILTIN_CLASSIFICATION)]
+///     pub classification: u8,
+/// }
+///
+/// let mut points = InterleavedVecPointStorage::new(ClassifiedPoint::layout());
+/// points.push_points(&[ClassifiedPoint { classification: 8 }, ClassifiedPoint { classification: 2 }]);
+/// assert_eq!(1, remap_classification(&mut points, &ClassificationMapping::legacy_las_to_asprs()));
+/// assert_eq!(2, points.get_attribute::<u8>(&CLASSIFICATION, 0));
+/// ```
+pub fn remap_classification<T: PointBufferWriteable + ?Sized>(
+    buffer: &mut T,
+    mapping: &ClassificationMapping,
+) -> usize {
+    let has_classification = buffer
+        .point_layout()
+        .get_attribute_by_name(CLASSIFICATION.name())
+        .map(|attribute| attribute.datatype() == PointAttributeDataType::U8)
+        .unwrap_or(false);
+    if !has_classification {
+        return 0;
+    }
+
+    let mut changed_count = 0;
+    for index in 0..buffer.len() {
+        let code = buffer.get_attribute::<u8>(&CLASSIFICATION, index);
+        let new_code = mapping.map(code);
+        if new_code != code {
+            buffer.set_attribute(&CLASSIFICATION, index, new_code);
+            changed_count += 1;
+        }
+    }
+    changed_count
+}
+
+/// Classifies all points in `buffer` with a user-supplied `model`, e.g. the inference of a trained machine learning
+/// model. The given `features` are extracted from `buffer` and normalized as in
+/// [feature_matrix](crate::features::feature_matrix), then split into chunks of at most `chunk_size` points, which are
+/// passed to `model` in parallel. `model` returns one label per row of the chunk, and the labels are written into the
+/// `CLASSIFICATION` attribute of the corresponding points
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::{InterleavedVecPointStorage, PointBufferExt};
+/// # use pasture_core::layout::{attributes::CLASSIFICATION, PointType};
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::classification::classify_with;
+/// # use pasture_algorithms::features::{Feature, FeatureNormalization};
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct ClassifiedPoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+///     #[pasture(BUILTIN_CLASSIFICATION)]
+///     pub classification: u8,
+/// }
+/// let mut points = InterleavedVecPointStorage::new(ClassifiedPoint::layout());
+/// for z in 0..10 {
+///     points.push_point(ClassifiedPoint{ position: Vector3::new(0.0, 0.0, z as f64), classification: 0 });
+/// }
+///
+/// // A trivial 'model' that classifies all points that are more than 2 units above the lowest point as vegetation
+/// classify_with(&mut points, &[Feature::HeightAboveMinimum], FeatureNormalization::None, 4, |chunk| {
+///     Ok((0..chunk.row_count).map(|row| if chunk.row(row)[0] > 2.0 { 5 } else { 2 }).collect())
+/// }).unwrap();
+/// assert_eq!(2, points.get_attribute::<u8>(&CLASSIFICATION, 2));
+/// assert_eq!(5, points.get_attribute::<u8>(&CLASSIFICATION, 3));
+/// ```
+///
+/// # Errors
+///
+/// If `buffer` has no `CLASSIFICATION` attribute of type `u8`, if `buffer` lacks an attribute that is required for one
+/// of the `features`, if `model` returns an error, or if `model` returns the wrong number of labels for a chunk
+///
+/// # Panics
+///
+/// If `chunk_size` is zero
+pub fn classify_with<T, F>(
+    buffer: &mut T,
+    features: &[Feature],
+    normalization: FeatureNormalization,
+    chunk_size: usize,
+    model: F,
+) -> Result<()>
+where
+    T: PointBufferWriteable + ?Sized,
+    F: Fn(&FeatureChunk) -> Result<Vec<u8>> + Sync,
+{
+    if chunk_size == 0 {
+        panic!("classify_with: chunk_size must be > 0");
+    }
+    let has_classification = buffer
+        .point_layout()
+        .get_attribute_by_name(CLASSIFICATION.name())
+        .map(|attribute| attribute.datatype() == PointAttributeDataType::U8)
+        .unwrap_or(false);
+    if !has_classification {
+        return Err(anyhow!(
+            "Point buffer has no {} attribute of type u8",
+            CLASSIFICATION.name()
+        ));
+    }
+
+    let matrix = feature_matrix(&*buffer, features, normalization)?;
+    let chunks = matrix.chunks(chunk_size).collect::<Vec<_>>();
+    let labels = chunks
+        .par_iter()
+        .map(|chunk| {
+            let labels = model(chunk)?;
+            if labels.len() != chunk.row_count {
+                return Err(anyhow!(
+                    "Model returned {} labels for a chunk of {} points",
+                    labels.len(),
+                    chunk.row_count
+                ));
+            }
+            Ok(labels)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for (chunk, labels) in chunks.iter().zip(labels.iter()) {
+        for (offset, label) in labels.iter().enumerate() {
+            buffer.set_attribute(&CLASSIFICATION, chunk.first_point + offset, *label);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::PerAttributeVecPointStorage,
+        layout::{attributes::POSITION_3D, PointType},
+        nalgebra::Vector3,
+    };
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct ClassifiedPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_CLASSIFICATION)]
+        pub classification: u8,
+    }
+
+    #[test]
+    fn test_remap_classification() {
+        let mut points = PerAttributeVecPointStorage::new(ClassifiedPoint::layout());
+        for classification in 0..16 {
+            points.push_point(ClassifiedPoint {
+                position: Vector3::new(classification as f64, 0.0, 0.0),
+                classification,
+            });
+        }
+
+        let mut mapping = ClassificationMapping::from_pairs(vec![(3, 4), (5, 4)]);
+        assert_eq!(2, remap_classification(&mut points, &mapping));
+        let classifications = points
+            .iter_attribute::<u8>(&CLASSIFICATION)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![0, 1, 2, 4, 4, 4, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15],
+            classifications
+        );
+
+        mapping.set_default(Some(1));
+        remap_classification(&mut points, &mapping);
+        let classifications = points
+            .iter_attribute::<u8>(&CLASSIFICATION)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1],
+            classifications
+        );
+    }
+
+    #[test]
+    fn test_legacy_las_to_asprs() {
+        let mapping = ClassificationMapping::legacy_las_to_asprs();
+        let mapped = (0..=12).map(|code| mapping.map(code)).collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 2, 3, 4, 5, 6, 7, 2, 9, 1, 1, 1], mapped);
+    }
+
+    #[test]
+    fn test_classification_mapping_from_csv() {
+        let mapping =
+            ClassificationMapping::from_csv("source;target\n1 ; 2\n\n# Comment\n7 18\n*,0")
+                .unwrap();
+        assert_eq!(2, mapping.map(1));
+        assert_eq!(18, mapping.map(7));
+        assert_eq!(0, mapping.map(42));
+        assert_eq!(Some(0), mapping.default_code());
+
+        assert!(ClassificationMapping::from_csv("1,2\n3").is_err());
+        assert!(ClassificationMapping::from_csv("1,2\n3,256").is_err());
+        assert!(ClassificationMapping::from_csv("1,2\nfrom,to").is_err());
+    }
+
+    #[test]
+    fn test_classify_with() {
+        let mut points = PerAttributeVecPointStorage::new(ClassifiedPoint::layout());
+        for index in 0..10 {
+            points.push_point(ClassifiedPoint {
+                position: Vector3::new(index as f64, 0.0, 0.0),
+                classification: 0,
+            });
+        }
+
+        let features = [Feature::Attribute(POSITION_3D)];
+        classify_with(
+            &mut points,
+            &features,
+            FeatureNormalization::None,
+            3,
+            |chunk| {
+                assert!(chunk.row_count <= 3);
+                assert_eq!(3, chunk.column_count);
+                Ok((0..chunk.row_count)
+                    .map(|row| chunk.row(row)[0] as u8 + chunk.first_point as u8)
+                    .collect())
+            },
+        )
+        .unwrap();
+        let classifications = points
+            .iter_attribute::<u8>(&CLASSIFICATION)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![0, 1, 2, 6, 7, 8, 12, 13, 14, 18], classifications);
+
+        let result = classify_with(
+            &mut points,
+            &features,
+            FeatureNormalization::None,
+            3,
+            |_| Ok(vec![1]),
+        );
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use pasture_core::nalgebra::Vector3;
+
+use crate::tin::Tin;
+
+/// A contour line at a single elevation, as calculated by [contours]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contour {
+    /// The elevation of this contour
+    pub elevation: f64,
+    /// The vertices of this contour. Contours are oriented so that the higher terrain is on the left
+    pub points: Vec<Vector3<f64>>,
+    /// Is this contour a closed ring? The first point of a closed contour is not repeated at the end of `points`
+    pub closed: bool,
+}
+
+/// An edge of the TIN, identified by the indices of its two vertices in ascending order
+type EdgeKey = (usize, usize);
+
+fn edge_key(a: usize, b: usize) -> EdgeKey {
+    (a.min(b), a.max(b))
+}
+
+/// Calculates the contour lines of `tin` at all multiples of `interval` between the lowest and highest vertex of `tin`
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_algorithms::tin::triangulate_positions;
+/// # use pasture_algorithms::contours::contours;
+/// // A plane that rises from a height of 0 at x = 0 to a height of 10 at x = 1
+/// let tin = triangulate_positions(vec![
+///     Vector3::new(0.0, 0.0, 0.0),
+///     Vector3::new(1.0, 0.0, 10.0),
+///     Vector3::new(1.0, 1.0, 10.0),
+///     Vector3::new(0.0, 1.0, 0.0),
+/// ]);
+/// let contours = contours(&tin, 3.0);
+/// assert_eq!(vec![3.0, 6.0, 9.0], contours.iter().map(|contour| contour.elevation).collect::<Vec<_>>());
+/// assert!(contours[0].points.iter().all(|point| (point.x - 0.3).abs() < 1e-9));
+/// ```
+///
+/// # Panics
+///
+/// If `interval` is not greater than zero.
+pub fn contours(tin: &Tin, interval: f64) -> Vec<Contour> {
+    if interval <= 0.0 {
+        panic!("contours: interval must be > 0");
+    }
+    let (min, max) = tin
+        .triangles
+        .iter()
+        .flatten()
+        .map(|vertex| tin.vertices[*vertex].z)
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), z| {
+            (min.min(z), max.max(z))
+        });
+    if min > max {
+        return vec![];
+    }
+    let elevations = ((min / interval).ceil() as i64..=(max / interval).floor() as i64)
+        .map(|step| step as f64 * interval)
+        .collect::<Vec<_>>();
+    contours_at_elevations(tin, &elevations)
+}
+
+/// Calculates the contour lines of `tin` at the given `elevations`. Vertices of `tin` that lie exactly at an elevation
+/// count as above that elevation
+pub fn contours_at_elevations(tin: &Tin, elevations: &[f64]) -> Vec<Contour> {
+    elevations
+        .iter()
+        .flat_map(|elevation| contours_at_elevation(tin, *elevation))
+        .collect()
+}
+
+fn contours_at_elevation(tin: &Tin, elevation: f64) -> Vec<Contour> {
+    let is_above = |vertex: usize| tin.vertices[vertex].z >= elevation;
+
+    // Each triangle that has vertices on both sides of the elevation contributes one segment between the two edges
+    // that cross the elevation. The segment is oriented from the edge that goes from above to below (in
+    // counter-clockwise order) to the edge that goes from below to above, which puts the higher vertices on the left
+    let mut segments_by_start: HashMap<EdgeKey, EdgeKey> = HashMap::new();
+    for triangle in tin.triangles.iter() {
+        let mut start = None;
+        let mut end = None;
+        for edge in 0..3 {
+            let (a, b) = (triangle[edge], triangle[(edge + 1) % 3]);
+            match (is_above(a), is_above(b)) {
+                (true, false) => start = Some(edge_key(a, b)),
+                (false, true) => end = Some(edge_key(a, b)),
+                _ => (),
+            }
+        }
+        if let (Some(start), Some(end)) = (start, end) {
+            segments_by_start.insert(start, end);
+        }
+    }
+    let mut segments_by_end: HashMap<EdgeKey, EdgeKey> = segments_by_start
+        .iter()
+        .map(|(start, end)| (*end, *start))
+        .collect();
+
+    let crossing = |(a, b): EdgeKey| {
+        let (low, high) = if tin.vertices[a].z < tin.vertices[b].z {
+            (&tin.vertices[a], &tin.vertices[b])
+        } else {
+            (&tin.vertices[b], &tin.vertices[a])
+        };
+        let t = (elevation - low.z) / (high.z - low.z);
+        Vector3::new(
+            low.x + t * (high.x - low.x),
+            low.y + t * (high.y - low.y),
+            elevation,
+        )
+    };
+
+    // Chain the segments into polylines by following the shared edges in both directions. Sorting the start edges
+    // makes the order of the resulting contours deterministic
+    let mut starts = segments_by_start.keys().copied().collect::<Vec<_>>();
+    starts.sort_unstable();
+    let mut contours = vec![];
+    for first_edge in starts {
+        let mut edge = match segments_by_start.remove(&first_edge) {
+            Some(end) => end,
+            None => continue,
+        };
+        segments_by_end.remove(&edge);
+        let mut edges = vec![first_edge, edge];
+        while let Some(next) = segments_by_start.remove(&edge) {
+            segments_by_end.remove(&next);
+            edges.push(next);
+            edge = next;
+        }
+
+        let closed = edge == first_edge;
+        if closed {
+            edges.pop();
+        } else {
+            let mut previous_edges = vec![];
+            let mut edge = first_edge;
+            while let Some(previous) = segments_by_end.remove(&edge) {
+                segments_by_start.remove(&previous);
+                previous_edges.push(previous);
+                edge = previous;
+            }
+            previous_edges.reverse();
+            previous_edges.append(&mut edges);
+            edges = previous_edges;
+        }
+
+        let mut points: Vec<Vector3<f64>> = Vec::with_capacity(edges.len());
+        for edge in edges {
+            let point = crossing(edge);
+            // Edges that end in the same vertex on the elevation yield identical points
+            if points.last() != Some(&point) {
+                points.push(point);
+            }
+        }
+        contours.push(Contour {
+            elevation,
+            points,
+            closed,
+        });
+    }
+    contours
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tin::triangulate_positions;
+
+    use super::*;
+
+    fn signed_area(points: &[Vector3<f64>]) -> f64 {
+        (0..points.len())
+            .map(|index| {
+                let (a, b) = (&points[index], &points[(index + 1) % points.len()]);
+                a.x * b.y - b.x * a.y
+            })
+            .sum::<f64>()
+            / 2.0
+    }
+
+    #[test]
+    fn test_closed_contours_around_peak() {
+        // A pyramid with its peak at (2, 2) on a 5x5 grid
+        let mut positions = vec![];
+        for y in 0..5 {
+            for x in 0..5 {
+                let height = 2.0 - (x as f64 - 2.0).abs().max((y as f64 - 2.0).abs());
+                positions.push(Vector3::new(x as f64, y as f64, height));
+            }
+        }
+        let tin = triangulate_positions(positions);
+
+        let contours = contours_at_elevations(&tin, &[0.5, 1.5]);
+        assert_eq!(2, contours.len());
+        for contour in contours.iter() {
+            assert!(contour.closed);
+            // The peak is inside the ring and on the left, so the ring is counter-clockwise
+            assert!(signed_area(&contour.points) > 0.0);
+            let distance = 2.0 - contour.elevation;
+            for point in contour.points.iter() {
+                assert_eq!(contour.elevation, point.z);
+                let distance_to_peak = (point.x - 2.0).abs().max((point.y - 2.0).abs());
+                assert!((distance_to_peak - distance).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn test_open_contours_on_slope() {
+        // A plane with a height of x on a 5x5 grid
+        let mut positions = vec![];
+        for y in 0..5 {
+            for x in 0..5 {
+                positions.push(Vector3::new(x as f64, y as f64, x as f64));
+            }
+        }
+        let tin = triangulate_positions(positions);
+
+        let contours = contours(&tin, 1.5);
+        assert_eq!(2, contours.len());
+        for contour in contours.iter() {
+            assert!(!contour.closed);
+            assert!(contour
+                .points
+                .iter()
+                .all(|point| (point.x - contour.elevation).abs() < 1e-9));
+            // The higher terrain is on the left, so the contour runs in negative y-direction
+            assert_eq!(4.0, contour.points.first().unwrap().y);
+            assert_eq!(0.0, contour.points.last().unwrap().y);
+        }
+    }
+
+    #[test]
+    fn test_contours_through_vertices() {
+        let tin = triangulate_positions(vec![
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.0, 2.0),
+            Vector3::new(2.0, 2.0, 2.0),
+            Vector3::new(0.0, 2.0, 0.0),
+            Vector3::new(1.0, 1.0, 1.0),
+        ]);
+        let contours = contours_at_elevations(&tin, &[1.0]);
+        assert_eq!(1, contours.len());
+        assert_eq!(3, contours[0].points.len());
+        assert_eq!(Vector3::new(1.0, 1.0, 1.0), contours[0].points[1]);
+    }
+}
@@ -0,0 +1,252 @@
+use pasture_core::{
+    containers::{PointBufferExt, PointBufferWriteable, PointBufferWriteableExt},
+    layout::attributes::{GPS_TIME, POSITION_3D},
+    nalgebra::{Isometry3, Translation3, Vector3},
+};
+
+use crate::normals::positions_of;
+
+/// The pose of a sensor at a point in time, i.e. the transformation from the coordinate frame of the sensor into the
+/// world (or odometry) frame at `time`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimedPose {
+    /// Time of the pose, in the same time base as the `GPS_TIME` attribute of the points
+    pub time: f64,
+    /// Transformation from the coordinate frame of the sensor into the world frame
+    pub pose: Isometry3<f64>,
+}
+
+impl TimedPose {
+    /// Creates a new `TimedPose` from the given `time` and `pose`
+    pub fn new(time: f64, pose: Isometry3<f64>) -> Self {
+        Self { time, pose }
+    }
+}
+
+/// A sequence of sensor poses ordered by time, e.g. from the odometry of a SLAM system, a trajectory from an INS, or
+/// just the poses at the start and the end of a lidar frame. Poses in between are interpolated linearly for the
+/// translation and spherically (slerp) for the rotation
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoseTrajectory {
+    poses: Vec<TimedPose>,
+}
+
+impl PoseTrajectory {
+    /// Creates a new `PoseTrajectory` from the given `poses`. The poses are sorted by time
+    ///
+    /// # Panics
+    ///
+    /// If `poses` is empty, or if the time of a pose is NaN
+    pub fn new(mut poses: Vec<TimedPose>) -> Self {
+        if poses.is_empty() {
+            panic!("PoseTrajectory::new: poses must not be empty");
+        }
+        poses.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+        Self { poses }
+    }
+
+    /// Creates a new `PoseTrajectory` from the poses of the sensor at the start and at the end of a frame, which
+    /// assumes that the sensor moved with constant velocity during the frame
+    pub fn from_frame_poses(start: TimedPose, end: TimedPose) -> Self {
+        Self::new(vec![start, end])
+    }
+
+    /// Returns the poses of this trajectory, sorted by time
+    pub fn poses(&self) -> &[TimedPose] {
+        &self.poses
+    }
+
+    /// Returns the time of the first and the last pose
+    pub fn time_range(&self) -> (f64, f64) {
+        (
+            self.poses.first().unwrap().time,
+            self.poses.last().unwrap().time,
+        )
+    }
+
+    /// Returns the interpolated pose at the given `time`. Times outside of the time range of this trajectory are clamped
+    /// to the first or last pose
+    pub fn pose_at(&self, time: f64) -> Isometry3<f64> {
+        let next = self.poses.partition_point(|pose| pose.time < time);
+        if next == 0 {
+            return self.poses[0].pose;
+        }
+        if next == self.poses.len() {
+            return self.poses[next - 1].pose;
+        }
+        let before = &self.poses[next - 1];
+        let after = &self.poses[next];
+        if after.time == time {
+            return after.pose;
+        }
+        let t = (time - before.time) / (after.time - before.time);
+        let translation = before
+            .pose
+            .translation
+            .vector
+            .lerp(&after.pose.translation.vector, t);
+        // Slerp is undefined for rotations that are half a turn apart, which poses of a moving sensor never are
+        let rotation = before
+            .pose
+            .rotation
+            .try_slerp(&after.pose.rotation, t, f64::EPSILON)
+            .unwrap_or(before.pose.rotation);
+        Isometry3::from_parts(Translation3::from(translation), rotation)
+    }
+}
+
+/// Removes the motion distortion (skew) of the points of a lidar frame that a moving sensor captured over time, e.g.
+/// during one revolution of a spinning lidar. The `POSITION_3D` of each point is given in the coordinate frame of the
+/// sensor at the `GPS_TIME` of the point. It is transformed with the pose of the sensor at this time, interpolated
+/// from `trajectory`, and then into the coordinate frame of the sensor at `reference_time`, typically the start or
+/// the end of the frame. Afterwards, all points of the frame are in one consistent coordinate frame, as if the sensor
+/// had captured them all at once. To get the points in the world frame instead, transform them with the pose at
+/// `reference_time` afterwards.
+///
+/// Points outside of the time range of `trajectory` are transformed with the first or last pose. Returns the number of
+/// these points, which is a hint that the trajectory doesn't cover the frame
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::{Isometry3, Vector3};
+/// # use pasture_core::containers::{InterleavedVecPointStorage, PointBufferExt};
+/// # use pasture_core::layout::{attributes, PointType};
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::deskew::{deskew_points, PoseTrajectory, TimedPose};
+/// #[repr(C)]
+/// #[derive(PointType, Default)]
+/// struct Point {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+///     #[pasture(BUILTIN_GPS_TIME)]
+///     pub gps_time: f64,
+/// }
+/// let mut buffer = InterleavedVecPointStorage::new(Point::layout());
+/// buffer.push_point(Point {
+///     position: Vector3::new(10.0, 0.0, 0.0),
+///     gps_time: 0.05,
+/// });
+///
+/// // The sensor moves 1 meter along the x axis during the frame
+/// let trajectory = PoseTrajectory::from_frame_poses(
+///     TimedPose::new(0.0, Isometry3::identity()),
+///     TimedPose::new(0.1, Isometry3::translation(1.0, 0.0, 0.0)),
+/// );
+/// assert_eq!(0, deskew_points(&mut buffer, &trajectory, 0.0));
+/// assert_eq!(
+///     Vector3::new(10.5, 0.0, 0.0),
+///     buffer.get_attribute::<Vector3<f64>>(&attributes::POSITION_3D, 0)
+/// );
+/// ```
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute with the datatype `Vec3f64` and a
+/// `GPS_TIME` attribute with the datatype `F64`
+pub fn deskew_points<T: PointBufferWriteable + ?Sized>(
+    buffer: &mut T,
+    trajectory: &PoseTrajectory,
+    reference_time: f64,
+) -> usize {
+    if !buffer.point_layout().has_attribute(&POSITION_3D)
+        || !buffer.point_layout().has_attribute(&GPS_TIME)
+    {
+        panic!("point buffer contains no position attribute with datatype Vec3f64 or no GPS time attribute with datatype F64");
+    }
+    let positions = positions_of(buffer);
+    let gps_times = buffer.iter_attribute::<f64>(&GPS_TIME).collect::<Vec<_>>();
+    let (start, end) = trajectory.time_range();
+    let to_reference_frame = trajectory.pose_at(reference_time).inverse();
+
+    let mut points_outside = 0;
+    for (index, (position, gps_time)) in positions.iter().zip(gps_times.iter()).enumerate() {
+        if *gps_time < start || *gps_time > end {
+            points_outside += 1;
+        }
+        let pose = to_reference_frame * trajectory.pose_at(*gps_time);
+        let deskewed: Vector3<f64> = pose.transform_point(&(*position).into()).coords;
+        buffer.set_attribute(&POSITION_3D, index, deskewed);
+    }
+    points_outside
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use super::*;
+    use pasture_core::{containers::PerAttributeVecPointStorage, layout::PointType};
+    use pasture_derive::PointType;
+
+    #[repr(C)]
+    #[derive(PointType, Debug, Default)]
+    struct TestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_GPS_TIME)]
+        pub gps_time: f64,
+    }
+
+    #[test]
+    fn test_interpolate_poses() {
+        let trajectory = PoseTrajectory::new(vec![
+            TimedPose::new(
+                2.0,
+                Isometry3::new(Vector3::new(2.0, 4.0, 0.0), Vector3::z() * FRAC_PI_2),
+            ),
+            TimedPose::new(0.0, Isometry3::identity()),
+        ]);
+        assert_eq!(0.0, trajectory.poses()[0].time);
+        assert_eq!((0.0, 2.0), trajectory.time_range());
+
+        let pose = trajectory.pose_at(1.0);
+        assert!((pose.translation.vector - Vector3::new(1.0, 2.0, 0.0)).amax() < 1e-12);
+        assert!((pose.rotation.angle() - FRAC_PI_2 / 2.0).abs() < 1e-12);
+
+        assert_eq!(Isometry3::identity(), trajectory.pose_at(-1.0));
+        assert_eq!(trajectory.poses()[1].pose, trajectory.pose_at(3.0));
+    }
+
+    #[test]
+    fn test_deskew_points() {
+        // The sensor turns by 90 degrees around the z axis during the frame. A wall at x = 10 in the world frame is
+        // seen at different angles over time
+        let trajectory = PoseTrajectory::from_frame_poses(
+            TimedPose::new(0.0, Isometry3::identity()),
+            TimedPose::new(
+                1.0,
+                Isometry3::new(Vector3::zeros(), Vector3::z() * FRAC_PI_2),
+            ),
+        );
+        let mut buffer = PerAttributeVecPointStorage::new(TestPoint::layout());
+        for (gps_time, world_position) in [
+            (0.0, Vector3::new(10.0, 0.0, 0.0)),
+            (0.5, Vector3::new(10.0, 1.0, 0.0)),
+            (1.5, Vector3::new(10.0, 2.0, 0.0)),
+        ]
+        .iter()
+        {
+            let sensor_position = trajectory
+                .pose_at(*gps_time)
+                .inverse_transform_point(&(*world_position).into());
+            buffer.push_point(TestPoint {
+                position: sensor_position.coords,
+                gps_time: *gps_time,
+            });
+        }
+
+        assert_eq!(1, deskew_points(&mut buffer, &trajectory, 0.0));
+        let expected = [
+            Vector3::new(10.0, 0.0, 0.0),
+            Vector3::new(10.0, 1.0, 0.0),
+            Vector3::new(10.0, 2.0, 0.0),
+        ];
+        for (position, expected) in buffer
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .zip(expected.iter())
+        {
+            assert!((position - expected).amax() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use std::collections::{HashMap, HashSet};
+
+use pasture_core::{
+    containers::{PointBuffer, PointBufferExt},
+    layout::attributes::POSITION_3D,
+    nalgebra::Vector3,
+};
+
+use crate::kdtree::DistanceMode;
+
+fn positions<T: PointBuffer>(buffer: &T) -> Box<dyn Iterator<Item = Vector3<f64>> + '_> {
+    let position_attribute = match buffer
+        .point_layout()
+        .get_attribute_by_name(POSITION_3D.name())
+    {
+        Some(a) => a,
+        None => panic!("point buffer contains no position attribute"),
+    };
+    if position_attribute.datatype() == POSITION_3D.datatype() {
+        Box::new(buffer.iter_attribute::<Vector3<f64>>(&POSITION_3D))
+    } else {
+        Box::new(buffer.iter_attribute_as::<Vector3<f64>>(&POSITION_3D))
+    }
+}
+
+fn cell_of(position: &Vector3<f64>, cell_size: f64) -> (i64, i64, i64) {
+    (
+        (position.x / cell_size).floor() as i64,
+        (position.y / cell_size).floor() as i64,
+        (position.z / cell_size).floor() as i64,
+    )
+}
+
+/// Streaming voxel grid downsampling. Keeps the first point that falls into each cell of a regular grid with cubic
+/// cells, over all chunks that are passed to [sample](StreamingVoxelGridSampler::sample). In contrast to
+/// [voxel_grid_filter](crate::voxel_grid::voxel_grid_filter), this never needs the whole point cloud in memory, only the
+/// set of occupied cells.
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::downsampling::StreamingVoxelGridSampler;
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct SimplePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+/// }
+/// let mut sampler = StreamingVoxelGridSampler::new(1.0);
+///
+/// let mut first_chunk = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// first_chunk.push_points(&[SimplePoint{ position: Vector3::new(0.1, 0.1, 0.1) }, SimplePoint{ position: Vector3::new(0.2, 0.2, 0.2) }]);
+/// assert_eq!(sampler.sample(&first_chunk), vec![0]);
+///
+/// let mut second_chunk = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// second_chunk.push_points(&[SimplePoint{ position: Vector3::new(0.3, 0.3, 0.3) }, SimplePoint{ position: Vector3::new(1.5, 0.5, 0.5) }]);
+/// assert_eq!(sampler.sample(&second_chunk), vec![1]);
+/// ```
+pub struct StreamingVoxelGridSampler {
+    cell_size: f64,
+    occupied_cells: HashSet<(i64, i64, i64)>,
+}
+
+impl StreamingVoxelGridSampler {
+    /// Creates a new `StreamingVoxelGridSampler` with the given `cell_size`
+    ///
+    /// # Panics
+    ///
+    /// If `cell_size` is not strictly positive
+    pub fn new(cell_size: f64) -> Self {
+        if cell_size <= 0.0 {
+            panic!("StreamingVoxelGridSampler::new: cell_size must be > 0");
+        }
+        Self {
+            cell_size,
+            occupied_cells: HashSet::new(),
+        }
+    }
+
+    /// Samples the next chunk of points. Returns the indices of all points within `chunk` that fall into a previously
+    /// unoccupied cell, in ascending order
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `chunk` doesn't contain a `POSITION_3D` attribute
+    pub fn sample<T: PointBuffer>(&mut self, chunk: &T) -> Vec<usize> {
+        let cell_size = self.cell_size;
+        let occupied_cells = &mut self.occupied_cells;
+        positions(chunk)
+            .enumerate()
+            .filter(|(_, position)| occupied_cells.insert(cell_of(position, cell_size)))
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+/// Streaming Poisson disk downsampling. Keeps a point only if no previously kept point lies within a distance of
+/// `radius`, over all chunks that are passed to [sample](PoissonDiskSampler::sample). The result is a subset of the
+/// points with a guaranteed minimum spacing. Which points are kept depends on the order of the points.
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::downsampling::PoissonDiskSampler;
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct SimplePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+/// }
+/// let mut sampler = PoissonDiskSampler::new(1.0);
+/// let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// buffer.push_points(&[
+///     SimplePoint{ position: Vector3::new(0.0, 0.0, 0.0) },
+///     SimplePoint{ position: Vector3::new(0.5, 0.0, 0.0) },
+///     SimplePoint{ position: Vector3::new(1.5, 0.0, 0.0) },
+/// ]);
+/// assert_eq!(sampler.sample(&buffer), vec![0, 2]);
+/// ```
+pub struct PoissonDiskSampler {
+    radius: f64,
+    cell_size: f64,
+    distance_mode: DistanceMode,
+    accepted_points: HashMap<(i64, i64, i64), Vec<Vector3<f64>>>,
+}
+
+impl PoissonDiskSampler {
+    /// Creates a new `PoissonDiskSampler` with the given minimum distance `radius` between points
+    ///
+    /// # Panics
+    ///
+    /// If `radius` is not strictly positive
+    pub fn new(radius: f64) -> Self {
+        if radius <= 0.0 {
+            panic!("PoissonDiskSampler::new: radius must be > 0");
+        }
+        Self {
+            radius,
+            // With cells of size radius, all neighbours within radius are found in the 27 adjacent cells
+            cell_size: radius,
+            distance_mode: DistanceMode::Xyz,
+            accepted_points: HashMap::new(),
+        }
+    }
+
+    /// Measures the distances between points according to `distance_mode`. With [DistanceMode::Xy], the kept points
+    /// have the minimum spacing in XY, so that e.g. a thinned terrain model has no points stacked above each other
+    pub fn with_distance_mode(mut self, distance_mode: DistanceMode) -> Self {
+        self.distance_mode = distance_mode;
+        self
+    }
+
+    /// Returns the cell of the accepted points that contains `position`. In XY mode, the cells are columns
+    fn accepted_cell_of(&self, position: &Vector3<f64>) -> (i64, i64, i64) {
+        let cell = cell_of(position, self.cell_size);
+        match self.distance_mode {
+            DistanceMode::Xyz => cell,
+            DistanceMode::Xy => (cell.0, cell.1, 0),
+        }
+    }
+
+    fn has_neighbour_within_radius(&self, position: &Vector3<f64>) -> bool {
+        let cell = self.accepted_cell_of(position);
+        let radius_squared = self.radius * self.radius;
+        let dz_range = match self.distance_mode {
+            DistanceMode::Xyz => -1..=1,
+            DistanceMode::Xy => 0..=0,
+        };
+        for dz in dz_range {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let neighbour_cell = (cell.0 + dx, cell.1 + dy, cell.2 + dz);
+                    if let Some(points) = self.accepted_points.get(&neighbour_cell) {
+                        if points.iter().any(|point| {
+                            self.distance_mode.distance_squared(point, position) < radius_squared
+                        }) {
+                            return true;
+                        }
+                    }
+                }
+            }
+        }
+        false
+    }
+
+    /// Samples the next chunk of points. Returns the indices of all points within `chunk` that have no previously kept
+    /// point within `radius`, in ascending order
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `chunk` doesn't contain a `POSITION_3D` attribute
+    pub fn sample<T: PointBuffer>(&mut self, chunk: &T) -> Vec<usize> {
+        let mut indices = vec![];
+        for (index, position) in positions(chunk).enumerate() {
+            if self.has_neighbour_within_radius(&position) {
+                continue;
+            }
+            self.accepted_points
+                .entry(self.accepted_cell_of(&position))
+                .or_default()
+                .push(position);
+            indices.push(index);
+        }
+        indices
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{containers::PerAttributeVecPointStorage, layout::PointType};
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct SimplePoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+    }
+
+    fn grid_points(count_per_axis: usize, spacing: f64) -> PerAttributeVecPointStorage {
+        let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+        for x in 0..count_per_axis {
+            for y in 0..count_per_axis {
+                for z in 0..count_per_axis {
+                    buffer.push_point(SimplePoint {
+                        position: Vector3::new(
+                            x as f64 * spacing,
+                            y as f64 * spacing,
+                            z as f64 * spacing,
+                        ),
+                    });
+                }
+            }
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_streaming_voxel_grid_sampler_matches_across_chunks() {
+        let buffer = grid_points(10, 0.25);
+        let mut sampler = StreamingVoxelGridSampler::new(1.0);
+        let kept_in_first_pass = sampler.sample(&buffer).len();
+        assert_eq!(27, kept_in_first_pass);
+        // All cells are occupied now, so sampling the same points again keeps nothing
+        assert!(sampler.sample(&buffer).is_empty());
+    }
+
+    #[test]
+    fn test_poisson_disk_sampler_minimum_distance() {
+        let buffer = grid_points(10, 0.25);
+        let radius = 0.6;
+        let mut sampler = PoissonDiskSampler::new(radius);
+        let indices = sampler.sample(&buffer);
+        assert!(!indices.is_empty());
+
+        let kept_positions = indices
+            .iter()
+            .map(|index| buffer.get_attribute::<Vector3<f64>>(&POSITION_3D, *index))
+            .collect::<Vec<_>>();
+        for (i, a) in kept_positions.iter().enumerate() {
+            for b in kept_positions.iter().skip(i + 1) {
+                assert!((a - b).norm() >= radius);
+            }
+        }
+    }
+
+    #[test]
+    fn test_poisson_disk_sampler_in_xy() {
+        let buffer = grid_points(10, 0.25);
+        let radius = 0.6;
+        let mut sampler = PoissonDiskSampler::new(radius).with_distance_mode(DistanceMode::Xy);
+        let indices = sampler.sample(&buffer);
+
+        let kept_positions = indices
+            .iter()
+            .map(|index| buffer.get_attribute::<Vector3<f64>>(&POSITION_3D, *index))
+            .collect::<Vec<_>>();
+        // The grid has 10 points per axis with a spacing of 0.25, so every third column is kept in each direction
+        assert_eq!(16, kept_positions.len());
+        for (i, a) in kept_positions.iter().enumerate() {
+            for b in kept_positions.iter().skip(i + 1) {
+                assert!((a.xy() - b.xy()).norm() >= radius);
+            }
+        }
+    }
+}
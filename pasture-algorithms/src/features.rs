@@ -0,0 +1,436 @@
+use anyhow::{anyhow, Result};
+use pasture_core::{
+    containers::{PointBuffer, PointBufferExt},
+    layout::{
+        attributes::{CLASSIFICATION, NUMBER_OF_RETURNS, POSITION_3D, RETURN_NUMBER},
+        component_names, decode_components, PointAttributeDataType, PointAttributeDefinition,
+    },
+    math::RunningStatistics,
+};
+
+use crate::{
+    kdtree::DistanceMode,
+    knn::{knn_graph_with_search, NeighborSearch},
+};
+
+/// A single feature of a [FeatureMatrix]. Each feature contributes one or more columns to the matrix
+#[derive(Debug, Clone, PartialEq)]
+pub enum Feature {
+    /// The values of the attribute with the name of the given attribute, converted to `f32`. The datatype of the
+    /// attribute is taken from the buffer, so the datatype of the given definition is ignored. Vector attributes
+    /// contribute one column per component
+    Attribute(PointAttributeDefinition),
+    /// Height of each point above the lowest point of the buffer
+    HeightAboveMinimum,
+    /// `RETURN_NUMBER` divided by `NUMBER_OF_RETURNS`, which is `1` for the last return of each pulse
+    ReturnRatio,
+    /// Mean distance of each point to its `k` nearest neighbors, which is a measure of the local point density
+    MeanNeighborDistance { k: usize },
+}
+
+/// How the columns of a [FeatureMatrix] are normalized
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum FeatureNormalization {
+    /// Keep the values as they are
+    None,
+    /// Scale the values of each column into the range `[0;1]`
+    MinMax,
+    /// Scale the values of each column to zero mean and unit standard deviation
+    Standardize,
+}
+
+/// A dense matrix of features with one row per point and one column per feature component, as calculated by
+/// [feature_matrix]. The values are stored in row-major order, which is the layout that most machine learning libraries
+/// expect (e.g. `ndarray::Array2::from_shape_vec((row_count, column_count), data)`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureMatrix {
+    /// Number of rows, which is the number of points
+    pub row_count: usize,
+    /// Number of columns
+    pub column_count: usize,
+    /// The feature values in row-major order
+    pub data: Vec<f32>,
+    /// Name of each column, e.g. `Position3D.x` for the first component of the positions
+    pub column_names: Vec<String>,
+    /// The `CLASSIFICATION` of each point, for use as training labels. `None` if the buffer has no `CLASSIFICATION`
+    /// attribute of type `u8`
+    pub labels: Option<Vec<u8>>,
+}
+
+impl FeatureMatrix {
+    /// Returns the features of the point at `index`
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds
+    pub fn row(&self, index: usize) -> &[f32] {
+        &self.data[index * self.column_count..(index + 1) * self.column_count]
+    }
+
+    /// Returns an iterator over consecutive chunks of at most `chunk_size` rows of this matrix
+    ///
+    /// # Panics
+    ///
+    /// If `chunk_size` is zero
+    pub fn chunks(&self, chunk_size: usize) -> impl Iterator<Item = FeatureChunk<'_>> + '_ {
+        if chunk_size == 0 {
+            panic!("FeatureMatrix::chunks: chunk_size must be > 0");
+        }
+        // A matrix without columns still has rows, so the chunks are calculated from the rows instead of the data
+        (0..self.row_count)
+            .step_by(chunk_size)
+            .map(move |first_row| {
+                let row_count = chunk_size.min(self.row_count - first_row);
+                FeatureChunk {
+                    first_point: first_row,
+                    row_count,
+                    column_count: self.column_count,
+                    data: &self.data[first_row * self.column_count
+                        ..(first_row + row_count) * self.column_count],
+                    column_names: &self.column_names,
+                }
+            })
+    }
+}
+
+/// A range of consecutive rows of a [FeatureMatrix], as passed to the model in
+/// [classify_with](crate::classification::classify_with)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeatureChunk<'a> {
+    /// Index of the point of the first row of this chunk
+    pub first_point: usize,
+    /// Number of rows in this chunk
+    pub row_count: usize,
+    /// Number of columns
+    pub column_count: usize,
+    /// The feature values of all rows of this chunk in row-major order
+    pub data: &'a [f32],
+    /// Name of each column
+    pub column_names: &'a [String],
+}
+
+impl<'a> FeatureChunk<'a> {
+    /// Returns the features of the row at `index` within this chunk
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds
+    pub fn row(&self, index: usize) -> &'a [f32] {
+        &self.data[index * self.column_count..(index + 1) * self.column_count]
+    }
+}
+
+/// Decodes the components of all values of an attribute with the given `datatype` in the tightly packed `bytes` and
+/// appends them to one column per component
+fn decode_attribute_columns(datatype: PointAttributeDataType, bytes: &[u8]) -> Vec<Vec<f64>> {
+    let (_, component_count) = datatype.components();
+    let value_count = bytes.len() / datatype.size() as usize;
+    let mut columns = (0..component_count)
+        .map(|_| Vec::with_capacity(value_count))
+        .collect::<Vec<_>>();
+    for (index, value) in decode_components(datatype, bytes).enumerate() {
+        columns[index % component_count].push(value);
+    }
+    columns
+}
+
+/// Returns the values of the attribute with the name of `attribute` in `buffer` as one column per component, together
+/// with the column names
+pub(crate) fn attribute_columns<T: PointBuffer + ?Sized>(
+    buffer: &T,
+    attribute: &PointAttributeDefinition,
+) -> Result<(Vec<Vec<f64>>, Vec<String>)> {
+    let member = buffer
+        .point_layout()
+        .get_attribute_by_name(attribute.name())
+        .ok_or_else(|| anyhow!("Point buffer has no {} attribute", attribute.name()))?;
+    let attribute = attribute.with_custom_datatype(member.datatype());
+    let mut bytes = vec![0; buffer.len() * attribute.size() as usize];
+    buffer.get_raw_attribute_range(0..buffer.len(), &attribute, &mut bytes);
+    let columns = decode_attribute_columns(attribute.datatype(), &bytes);
+    let names = component_names(attribute.name(), columns.len());
+    Ok((columns, names))
+}
+
+/// Normalizes the values of `column` in place
+fn normalize_column(column: &mut [f64], normalization: FeatureNormalization) {
+    if column.is_empty() {
+        return;
+    }
+    match normalization {
+        FeatureNormalization::None => (),
+        FeatureNormalization::MinMax => {
+            let min = column.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = column.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+            let range = max - min;
+            for value in column.iter_mut() {
+                *value = if range > 0.0 {
+                    (*value - min) / range
+                } else {
+                    0.0
+                };
+            }
+        }
+        FeatureNormalization::Standardize => {
+            let statistics: RunningStatistics = column.iter().copied().collect();
+            let mean = statistics.mean().unwrap_or(0.0);
+            let standard_deviation = statistics.standard_deviation().unwrap_or(0.0);
+            for value in column.iter_mut() {
+                *value = if standard_deviation > 0.0 {
+                    (*value - mean) / standard_deviation
+                } else {
+                    0.0
+                };
+            }
+        }
+    }
+}
+
+/// Assembles the given `features` of all points in `buffer` into a dense [FeatureMatrix], with the columns in the order
+/// of `features`. Each column is normalized independently using `normalization`. If `buffer` has a `CLASSIFICATION`
+/// attribute, its values are returned as the labels of the matrix.
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::{attributes, PointType};
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::features::{feature_matrix, Feature, FeatureNormalization};
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct ClassifiedPoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+///     #[pasture(BUILTIN_INTENSITY)]
+///     pub intensity: u16,
+///     #[pasture(BUILTIN_CLASSIFICATION)]
+///     pub classification: u8,
+/// }
+/// let mut buffer = PerAttributeVecPointStorage::new(ClassifiedPoint::layout());
+/// buffer.push_point(ClassifiedPoint{ position: Vector3::new(0.0, 0.0, 10.0), intensity: 100, classification: 2 });
+/// buffer.push_point(ClassifiedPoint{ position: Vector3::new(1.0, 0.0, 12.0), intensity: 300, classification: 5 });
+///
+/// let features = [Feature::Attribute(attributes::INTENSITY), Feature::HeightAboveMinimum];
+/// let matrix = feature_matrix(&buffer, &features, FeatureNormalization::None).unwrap();
+/// assert_eq!(vec![100.0, 0.0, 300.0, 2.0], matrix.data);
+/// assert_eq!(Some(vec![2, 5]), matrix.labels);
+/// ```
+///
+/// # Errors
+///
+/// If `buffer` lacks an attribute that is required for one of the `features`
+pub fn feature_matrix<T: PointBuffer + ?Sized>(
+    buffer: &T,
+    features: &[Feature],
+    normalization: FeatureNormalization,
+) -> Result<FeatureMatrix> {
+    feature_matrix_with_search(buffer, features, normalization, NeighborSearch::Exact)
+}
+
+/// Like [feature_matrix], but finds the neighbors of neighborhood features (like [Feature::MeanNeighborDistance]) with
+/// the given `search`. Approximate neighbors make these features affordable on massive point clouds
+///
+/// # Errors
+///
+/// If `buffer` lacks an attribute that is required for one of the `features`
+pub fn feature_matrix_with_search<T: PointBuffer + ?Sized>(
+    buffer: &T,
+    features: &[Feature],
+    normalization: FeatureNormalization,
+    search: NeighborSearch,
+) -> Result<FeatureMatrix> {
+    let layout = buffer.point_layout();
+    let mut columns: Vec<Vec<f64>> = vec![];
+    let mut column_names: Vec<String> = vec![];
+    for feature in features {
+        match feature {
+            Feature::Attribute(attribute) => {
+                let (attribute_columns, names) = attribute_columns(buffer, attribute)?;
+                columns.extend(attribute_columns);
+                column_names.extend(names);
+            }
+            Feature::HeightAboveMinimum => {
+                let (positions, _) = attribute_columns(buffer, &POSITION_3D)?;
+                let heights = &positions[2];
+                let min = heights.iter().copied().fold(f64::INFINITY, f64::min);
+                columns.push(heights.iter().map(|height| height - min).collect());
+                column_names.push("HeightAboveMinimum".to_owned());
+            }
+            Feature::ReturnRatio => {
+                let (return_numbers, _) = attribute_columns(buffer, &RETURN_NUMBER)?;
+                let (number_of_returns, _) = attribute_columns(buffer, &NUMBER_OF_RETURNS)?;
+                columns.push(
+                    return_numbers[0]
+                        .iter()
+                        .zip(number_of_returns[0].iter())
+                        .map(|(return_number, number_of_returns)| {
+                            if *number_of_returns > 0.0 {
+                                return_number / number_of_returns
+                            } else {
+                                0.0
+                            }
+                        })
+                        .collect(),
+                );
+                column_names.push("ReturnRatio".to_owned());
+            }
+            Feature::MeanNeighborDistance { k } => {
+                if !layout.has_attribute_with_name(POSITION_3D.name()) {
+                    return Err(anyhow!(
+                        "Point buffer has no {} attribute",
+                        POSITION_3D.name()
+                    ));
+                }
+                let graph = knn_graph_with_search(buffer, *k, DistanceMode::Xyz, search);
+                let distances = if graph.k == 0 {
+                    vec![0.0; buffer.len()]
+                } else {
+                    graph
+                        .distances
+                        .chunks_exact(graph.k)
+                        .map(|distances| distances.iter().sum::<f64>() / graph.k as f64)
+                        .collect()
+                };
+                columns.push(distances);
+                column_names.push(format!("MeanNeighborDistance{}", k));
+            }
+        }
+    }
+
+    for column in columns.iter_mut() {
+        normalize_column(column, normalization);
+    }
+
+    let row_count = buffer.len();
+    let column_count = columns.len();
+    let mut data = Vec::with_capacity(row_count * column_count);
+    for row in 0..row_count {
+        data.extend(columns.iter().map(|column| column[row] as f32));
+    }
+
+    let has_labels = layout
+        .get_attribute_by_name(CLASSIFICATION.name())
+        .map(|attribute| attribute.datatype() == PointAttributeDataType::U8)
+        .unwrap_or(false);
+    let labels = if has_labels {
+        Some(buffer.iter_attribute::<u8>(&CLASSIFICATION).collect())
+    } else {
+        None
+    };
+
+    Ok(FeatureMatrix {
+        row_count,
+        column_count,
+        data,
+        column_names,
+        labels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::InterleavedVecPointStorage,
+        layout::{attributes::COLOR_RGB, PointType},
+        nalgebra::Vector3,
+    };
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct TestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_COLOR_RGB)]
+        pub color: Vector3<u16>,
+        #[pasture(BUILTIN_RETURN_NUMBER)]
+        pub return_number: u8,
+        #[pasture(BUILTIN_NUMBER_OF_RETURNS)]
+        pub number_of_returns: u8,
+    }
+
+    fn test_points() -> InterleavedVecPointStorage {
+        let mut buffer = InterleavedVecPointStorage::new(TestPoint::layout());
+        buffer.push_point(TestPoint {
+            position: Vector3::new(0.0, 0.0, 1.0),
+            color: Vector3::new(0, 10, 20),
+            return_number: 1,
+            number_of_returns: 2,
+        });
+        buffer.push_point(TestPoint {
+            position: Vector3::new(2.0, 0.0, 3.0),
+            color: Vector3::new(100, 10, 40),
+            return_number: 2,
+            number_of_returns: 2,
+        });
+        buffer.push_point(TestPoint {
+            position: Vector3::new(3.0, 0.0, 5.0),
+            color: Vector3::new(50, 10, 60),
+            return_number: 1,
+            number_of_returns: 1,
+        });
+        buffer
+    }
+
+    #[test]
+    fn test_feature_matrix() -> Result<()> {
+        let features = [
+            Feature::Attribute(COLOR_RGB),
+            Feature::ReturnRatio,
+            Feature::MeanNeighborDistance { k: 1 },
+        ];
+        let matrix = feature_matrix(&test_points(), &features, FeatureNormalization::None)?;
+        assert_eq!(3, matrix.row_count);
+        assert_eq!(5, matrix.column_count);
+        assert_eq!(
+            vec![
+                "ColorRGB.x",
+                "ColorRGB.y",
+                "ColorRGB.z",
+                "ReturnRatio",
+                "MeanNeighborDistance1"
+            ],
+            matrix.column_names
+        );
+        assert_eq!(
+            &[100.0, 10.0, 40.0, 1.0, 5.0_f64.sqrt() as f32],
+            matrix.row(1)
+        );
+        assert_eq!(
+            &[0.0, 10.0, 20.0, 0.5, 8.0_f64.sqrt() as f32],
+            matrix.row(0)
+        );
+        assert_eq!(None, matrix.labels);
+        Ok(())
+    }
+
+    #[test]
+    fn test_feature_matrix_normalization() -> Result<()> {
+        let features = [Feature::Attribute(COLOR_RGB), Feature::HeightAboveMinimum];
+        let matrix = feature_matrix(&test_points(), &features, FeatureNormalization::MinMax)?;
+        assert_eq!(&[0.0, 0.0, 0.0, 0.0], matrix.row(0));
+        assert_eq!(&[1.0, 0.0, 0.5, 0.5], matrix.row(1));
+        assert_eq!(&[0.5, 0.0, 1.0, 1.0], matrix.row(2));
+
+        let matrix = feature_matrix(
+            &test_points(),
+            &[Feature::HeightAboveMinimum],
+            FeatureNormalization::Standardize,
+        )?;
+        let expected = [-1.5_f32.sqrt(), 0.0, 1.5_f32.sqrt()];
+        for (value, expected) in matrix.data.iter().zip(expected.iter()) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_feature_matrix_missing_attribute() {
+        let features = [Feature::Attribute(CLASSIFICATION)];
+        assert!(feature_matrix(&test_points(), &features, FeatureNormalization::None).is_err());
+    }
+}
@@ -0,0 +1,542 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use pasture_core::{
+    containers::{PointBuffer, PointBufferExt, PointBufferWriteable, PointBufferWriteableExt},
+    layout::attributes::{
+        CLASSIFICATION, CLASSIFICATION_FLAGS, GPS_TIME, POINT_SOURCE_ID, POSITION_3D, SCAN_ANGLE,
+        SCAN_ANGLE_RANK,
+    },
+    math::AABB,
+    nalgebra::{Point3, Vector3},
+};
+
+/// Size of one unit of the `SCAN_ANGLE` attribute in degrees, as defined by the LAS 1.4 specification
+pub const SCAN_ANGLE_UNIT_IN_DEGREES: f64 = 0.006;
+
+/// Parameters for [calculate_flightline_statistics]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlightlineStatisticsParameters {
+    /// Edge length of the cells of the 2D grid that is used to calculate the area covered by each flightline and the
+    /// overlap between flightlines
+    pub cell_size: f64,
+    /// Width of the bins of the scan angle histogram in degrees
+    pub scan_angle_bin_size: f64,
+}
+
+impl Default for FlightlineStatisticsParameters {
+    fn default() -> Self {
+        Self {
+            cell_size: 1.0,
+            scan_angle_bin_size: 5.0,
+        }
+    }
+}
+
+/// A single non-empty bin of a scan angle histogram
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScanAngleBin {
+    /// Lower bound of the bin in degrees (inclusive)
+    pub min: f64,
+    /// Upper bound of the bin in degrees (exclusive)
+    pub max: f64,
+    /// Number of points whose scan angle falls into this bin
+    pub count: usize,
+}
+
+/// Statistics of a single flightline, i.e. of all points with the same `POINT_SOURCE_ID`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlightlineStatistics {
+    /// The `POINT_SOURCE_ID` of all points of this flightline
+    pub point_source_id: u16,
+    /// Number of points in this flightline
+    pub point_count: usize,
+    /// Bounding box of all points in this flightline
+    pub bounds: AABB<f64>,
+    /// Area covered by this flightline, which is the area of all cells of the 2D grid that contain points of this
+    /// flightline
+    pub covered_area: f64,
+    /// Number of points per unit of `covered_area`
+    pub density: f64,
+    /// Minimum and maximum GPS time of the points of this flightline, or `None` if the points have no GPS time
+    pub gps_time_range: Option<(f64, f64)>,
+    /// Histogram of the scan angles of the points of this flightline, sorted by angle. Empty bins are omitted. Empty if
+    /// the points have no scan angle
+    pub scan_angle_histogram: Vec<ScanAngleBin>,
+    /// Percentage (in `[0;100]`) of `covered_area` that is also covered by at least one other flightline
+    pub overlap_percentage: f64,
+}
+
+/// Returns the scan angles of all points in `buffer` in degrees, or `None` if the `PointLayout` of `buffer` contains
+/// neither a `SCAN_ANGLE` nor a `SCAN_ANGLE_RANK` attribute. `SCAN_ANGLE` (as used by the extended LAS point formats)
+/// takes precedence over `SCAN_ANGLE_RANK`
+pub fn scan_angles_in_degrees<T: PointBuffer + ?Sized>(buffer: &T) -> Option<Vec<f64>> {
+    let layout = buffer.point_layout();
+    if layout.has_attribute_with_name(SCAN_ANGLE.name()) {
+        Some(
+            buffer
+                .iter_attribute_as::<i16>(&SCAN_ANGLE)
+                .map(|angle| angle as f64 * SCAN_ANGLE_UNIT_IN_DEGREES)
+                .collect(),
+        )
+    } else if layout.has_attribute_with_name(SCAN_ANGLE_RANK.name()) {
+        Some(
+            buffer
+                .iter_attribute_as::<i8>(&SCAN_ANGLE_RANK)
+                .map(|angle| angle as f64)
+                .collect(),
+        )
+    } else {
+        None
+    }
+}
+
+/// Calculates statistics for each flightline in `buffer`. Flightlines are identified by the `POINT_SOURCE_ID`
+/// attribute; if `buffer` has no such attribute, all points are treated as a single flightline with ID 0. The result
+/// is sorted by `point_source_id`. The covered area and the overlap between flightlines are approximated using a 2D
+/// grid with cells of size `cell_size`
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::flightlines::{calculate_flightline_statistics, FlightlineStatisticsParameters};
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct FlightlinePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+///     #[pasture(BUILTIN_POINT_SOURCE_ID)]
+///     pub point_source_id: u16,
+/// }
+/// let mut buffer = PerAttributeVecPointStorage::new(FlightlinePoint::layout());
+/// // Two flightlines that each cover three cells and overlap in one cell
+/// for x in 0..4 {
+///     buffer.push_point(FlightlinePoint{ position: Vector3::new(x as f64 + 0.5, 0.5, 0.0), point_source_id: 1 + x / 2 });
+///     buffer.push_point(FlightlinePoint{ position: Vector3::new(x as f64 + 1.5, 0.5, 0.0), point_source_id: 1 + x / 2 });
+/// }
+///
+/// let statistics = calculate_flightline_statistics(&buffer, &FlightlineStatisticsParameters::default());
+/// assert_eq!(2, statistics.len());
+/// assert_eq!(4, statistics[0].point_count);
+/// assert_eq!(3.0, statistics[0].covered_area);
+/// ```
+///
+/// # Panics
+///
+/// If `cell_size` or `scan_angle_bin_size` are not strictly positive, or if the `PointLayout` of `buffer` doesn't
+/// contain a `POSITION_3D` attribute.
+pub fn calculate_flightline_statistics<T: PointBuffer + ?Sized>(
+    buffer: &T,
+    parameters: &FlightlineStatisticsParameters,
+) -> Vec<FlightlineStatistics> {
+    if parameters.cell_size <= 0.0 {
+        panic!("calculate_flightline_statistics: cell_size must be > 0");
+    }
+    if parameters.scan_angle_bin_size <= 0.0 {
+        panic!("calculate_flightline_statistics: scan_angle_bin_size must be > 0");
+    }
+    let layout = buffer.point_layout();
+    if !layout.has_attribute_with_name(POSITION_3D.name()) {
+        panic!("point buffer contains no position attribute");
+    }
+
+    let positions: Vec<Vector3<f64>> = buffer
+        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+        .collect();
+    let point_source_ids: Vec<u16> = if layout.has_attribute_with_name(POINT_SOURCE_ID.name()) {
+        buffer.iter_attribute_as::<u16>(&POINT_SOURCE_ID).collect()
+    } else {
+        vec![0; positions.len()]
+    };
+    let gps_times: Option<Vec<f64>> = if layout.has_attribute_with_name(GPS_TIME.name()) {
+        Some(buffer.iter_attribute_as::<f64>(&GPS_TIME).collect())
+    } else {
+        None
+    };
+    let scan_angles = scan_angles_in_degrees(buffer);
+
+    struct Accumulator {
+        point_count: usize,
+        bounds: AABB<f64>,
+        cells: HashSet<(i64, i64)>,
+        gps_time_range: Option<(f64, f64)>,
+        scan_angle_bins: BTreeMap<i64, usize>,
+    }
+
+    let mut flightlines: BTreeMap<u16, Accumulator> = BTreeMap::new();
+    for (index, position) in positions.iter().enumerate() {
+        let point: Point3<f64> = (*position).into();
+        let flightline = flightlines
+            .entry(point_source_ids[index])
+            .or_insert_with(|| Accumulator {
+                point_count: 0,
+                bounds: AABB::from_min_max_unchecked(point, point),
+                cells: HashSet::new(),
+                gps_time_range: None,
+                scan_angle_bins: BTreeMap::new(),
+            });
+        flightline.point_count += 1;
+        flightline.bounds = AABB::extend_with_point(&flightline.bounds, &point);
+        flightline.cells.insert((
+            (position.x / parameters.cell_size).floor() as i64,
+            (position.y / parameters.cell_size).floor() as i64,
+        ));
+        if let Some(gps_times) = &gps_times {
+            let gps_time = gps_times[index];
+            flightline.gps_time_range = Some(match flightline.gps_time_range {
+                Some((min, max)) => (min.min(gps_time), max.max(gps_time)),
+                None => (gps_time, gps_time),
+            });
+        }
+        if let Some(scan_angles) = &scan_angles {
+            let bin = (scan_angles[index] / parameters.scan_angle_bin_size).floor() as i64;
+            *flightline.scan_angle_bins.entry(bin).or_insert(0) += 1;
+        }
+    }
+
+    // Number of flightlines that cover each cell
+    let mut cell_coverage: HashMap<(i64, i64), usize> = HashMap::new();
+    for flightline in flightlines.values() {
+        for cell in flightline.cells.iter() {
+            *cell_coverage.entry(*cell).or_insert(0) += 1;
+        }
+    }
+
+    let cell_area = parameters.cell_size * parameters.cell_size;
+    flightlines
+        .into_iter()
+        .map(|(point_source_id, flightline)| {
+            let covered_area = flightline.cells.len() as f64 * cell_area;
+            let overlapping_cells = flightline
+                .cells
+                .iter()
+                .filter(|cell| cell_coverage[*cell] > 1)
+                .count();
+            FlightlineStatistics {
+                point_source_id,
+                point_count: flightline.point_count,
+                bounds: flightline.bounds,
+                covered_area,
+                density: flightline.point_count as f64 / covered_area,
+                gps_time_range: flightline.gps_time_range,
+                scan_angle_histogram: flightline
+                    .scan_angle_bins
+                    .into_iter()
+                    .map(|(bin, count)| ScanAngleBin {
+                        min: bin as f64 * parameters.scan_angle_bin_size,
+                        max: (bin + 1) as f64 * parameters.scan_angle_bin_size,
+                        count,
+                    })
+                    .collect(),
+                overlap_percentage: 100.0 * overlapping_cells as f64
+                    / flightline.cells.len() as f64,
+            }
+        })
+        .collect()
+}
+
+/// Bit of the `CLASSIFICATION_FLAGS` attribute that marks overlap points, as defined by the LAS 1.4 specification
+pub const OVERLAP_FLAG: u8 = 0b1000;
+/// Classification code of overlap points in the LAS point formats 0 to 5, which have no overlap flag
+pub const LEGACY_OVERLAP_CLASSIFICATION: u8 = 12;
+
+/// Identifies the points of `buffer` that lie within the overlap region of two or more flightlines. The footprints of
+/// the flightlines (identified by `POINT_SOURCE_ID`) are approximated with a 2D grid with cells of size `cell_size`.
+/// Within each cell that is covered by more than one flightline, the flightline with the smallest mean absolute scan
+/// angle (i.e. the one that is closest to nadir) keeps its points, and the points of all other flightlines are overlap
+/// points. If `buffer` has no scan angles, the flightline with the most points in the cell keeps its points instead.
+/// Returns one entry per point of `buffer`, which is `true` for overlap points
+///
+/// # Panics
+///
+/// If `cell_size` is not strictly positive, or if the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` or a
+/// `POINT_SOURCE_ID` attribute.
+pub fn detect_overlap<T: PointBuffer + ?Sized>(buffer: &T, cell_size: f64) -> Vec<bool> {
+    if cell_size <= 0.0 {
+        panic!("detect_overlap: cell_size must be > 0");
+    }
+    let layout = buffer.point_layout();
+    if !layout.has_attribute_with_name(POSITION_3D.name()) {
+        panic!("point buffer contains no position attribute");
+    }
+    if !layout.has_attribute_with_name(POINT_SOURCE_ID.name()) {
+        panic!("point buffer contains no point source ID attribute");
+    }
+
+    let cells = buffer
+        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+        .map(|position| {
+            (
+                (position.x / cell_size).floor() as i64,
+                (position.y / cell_size).floor() as i64,
+            )
+        })
+        .collect::<Vec<_>>();
+    let point_source_ids = buffer
+        .iter_attribute_as::<u16>(&POINT_SOURCE_ID)
+        .collect::<Vec<_>>();
+    let scan_angles = scan_angles_in_degrees(buffer);
+
+    // Number of points and sum of absolute scan angles per flightline within each cell
+    let mut cell_flightlines: HashMap<(i64, i64), BTreeMap<u16, (usize, f64)>> = HashMap::new();
+    for (index, cell) in cells.iter().enumerate() {
+        let entry = cell_flightlines
+            .entry(*cell)
+            .or_default()
+            .entry(point_source_ids[index])
+            .or_insert((0, 0.0));
+        entry.0 += 1;
+        if let Some(scan_angles) = &scan_angles {
+            entry.1 += scan_angles[index].abs();
+        }
+    }
+
+    let cell_owners = cell_flightlines
+        .into_iter()
+        .filter(|(_, flightlines)| flightlines.len() > 1)
+        .map(|(cell, flightlines)| {
+            let owner = flightlines
+                .into_iter()
+                .fold(
+                    None,
+                    |best: Option<(u16, usize, f64)>, (id, (count, angle_sum))| {
+                        let mean_angle = angle_sum / count as f64;
+                        let is_better = match best {
+                            None => true,
+                            Some((_, best_count, best_angle)) => {
+                                if scan_angles.is_some() {
+                                    mean_angle < best_angle
+                                } else {
+                                    count > best_count
+                                }
+                            }
+                        };
+                        if is_better {
+                            Some((id, count, mean_angle))
+                        } else {
+                            best
+                        }
+                    },
+                )
+                .map(|(id, _, _)| id)
+                .unwrap();
+            (cell, owner)
+        })
+        .collect::<HashMap<_, _>>();
+
+    cells
+        .iter()
+        .zip(point_source_ids.iter())
+        .map(|(cell, id)| {
+            cell_owners
+                .get(cell)
+                .map(|owner| owner != id)
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Detects overlap points in `buffer` using [detect_overlap] and marks them as overlap points. If `buffer` has a
+/// `CLASSIFICATION_FLAGS` attribute (as the LAS point formats 6 to 10 do), the `OVERLAP_FLAG` is set for all overlap
+/// points and cleared for all other points. Otherwise, overlap points are classified as
+/// `LEGACY_OVERLAP_CLASSIFICATION`, which requires a `CLASSIFICATION` attribute. Returns the number of overlap points
+///
+/// # Panics
+///
+/// If `cell_size` is not strictly positive, if the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` or a
+/// `POINT_SOURCE_ID` attribute, or if it contains neither a `CLASSIFICATION_FLAGS` nor a `CLASSIFICATION` attribute.
+pub fn flag_overlap<T: PointBufferWriteable + ?Sized>(buffer: &mut T, cell_size: f64) -> usize {
+    let layout = buffer.point_layout();
+    let has_flags = layout.has_attribute_with_name(CLASSIFICATION_FLAGS.name());
+    if !has_flags && !layout.has_attribute_with_name(CLASSIFICATION.name()) {
+        panic!(
+            "point buffer contains neither a classification flags nor a classification attribute"
+        );
+    }
+
+    let is_overlap = detect_overlap(&*buffer, cell_size);
+    for (index, is_overlap) in is_overlap.iter().enumerate() {
+        if has_flags {
+            let flags = buffer.get_attribute::<u8>(&CLASSIFICATION_FLAGS, index);
+            let flags = if *is_overlap {
+                flags | OVERLAP_FLAG
+            } else {
+                flags & !OVERLAP_FLAG
+            };
+            buffer.set_attribute(&CLASSIFICATION_FLAGS, index, flags);
+        } else if *is_overlap {
+            buffer.set_attribute(&CLASSIFICATION, index, LEGACY_OVERLAP_CLASSIFICATION);
+        }
+    }
+    is_overlap.iter().filter(|is_overlap| **is_overlap).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{containers::PerAttributeVecPointStorage, layout::PointType};
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct FlightlinePoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_POINT_SOURCE_ID)]
+        pub point_source_id: u16,
+        #[pasture(BUILTIN_GPS_TIME)]
+        pub gps_time: f64,
+        #[pasture(BUILTIN_SCAN_ANGLE)]
+        pub scan_angle: i16,
+    }
+
+    #[test]
+    fn test_flightline_statistics() {
+        let mut buffer = PerAttributeVecPointStorage::new(FlightlinePoint::layout());
+        // Flightline 7 covers the cells x=0..4, flightline 3 covers the cells x=3..5, both at y=0. So they overlap in
+        // the cell x=3
+        for x in 0..4 {
+            buffer.push_point(FlightlinePoint {
+                position: Vector3::new(x as f64 + 0.5, 0.5, x as f64),
+                point_source_id: 7,
+                gps_time: 100.0 + x as f64,
+                scan_angle: -2000 + x as i16 * 1000,
+            });
+        }
+        for x in 3..5 {
+            buffer.push_point(FlightlinePoint {
+                position: Vector3::new(x as f64 + 0.25, 0.75, 0.0),
+                point_source_id: 3,
+                gps_time: 10.0,
+                scan_angle: 0,
+            });
+        }
+
+        let statistics = calculate_flightline_statistics(
+            &buffer,
+            &FlightlineStatisticsParameters {
+                cell_size: 1.0,
+                scan_angle_bin_size: 10.0,
+            },
+        );
+        assert_eq!(2, statistics.len());
+
+        let second = &statistics[0];
+        assert_eq!(3, second.point_source_id);
+        assert_eq!(2, second.point_count);
+        assert_eq!(2.0, second.covered_area);
+        assert_eq!(1.0, second.density);
+        assert_eq!(Some((10.0, 10.0)), second.gps_time_range);
+        assert_eq!(50.0, second.overlap_percentage);
+
+        let first = &statistics[1];
+        assert_eq!(7, first.point_source_id);
+        assert_eq!(4, first.point_count);
+        assert_eq!(Point3::new(0.5, 0.5, 0.0), *first.bounds.min());
+        assert_eq!(Point3::new(3.5, 0.5, 3.0), *first.bounds.max());
+        assert_eq!(4.0, first.covered_area);
+        assert_eq!(Some((100.0, 103.0)), first.gps_time_range);
+        assert_eq!(25.0, first.overlap_percentage);
+        // Scan angles are -12, -6, 0 and 6 degrees
+        assert_eq!(
+            vec![
+                ScanAngleBin {
+                    min: -20.0,
+                    max: -10.0,
+                    count: 1
+                },
+                ScanAngleBin {
+                    min: -10.0,
+                    max: 0.0,
+                    count: 1
+                },
+                ScanAngleBin {
+                    min: 0.0,
+                    max: 10.0,
+                    count: 2
+                },
+            ],
+            first.scan_angle_histogram
+        );
+    }
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct OverlapPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_POINT_SOURCE_ID)]
+        pub point_source_id: u16,
+        #[pasture(BUILTIN_SCAN_ANGLE)]
+        pub scan_angle: i16,
+        #[pasture(BUILTIN_CLASSIFICATION_FLAGS)]
+        pub classification_flags: u8,
+    }
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct LegacyFlightlinePoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_POINT_SOURCE_ID)]
+        pub point_source_id: u16,
+        #[pasture(BUILTIN_CLASSIFICATION)]
+        pub classification: u8,
+    }
+
+    #[test]
+    fn test_flag_overlap() {
+        let mut buffer = PerAttributeVecPointStorage::new(OverlapPoint::layout());
+        // Flightline 1 is at nadir in the cell x=0 and flightline 2 in the cell x=2, both cover the cells x=0..3. In
+        // the cell x=1, both have the same scan angle, so the flightline with the smaller ID keeps its points
+        for id in 1..=2_u16 {
+            let nadir_x: i16 = if id == 1 { 0 } else { 2 };
+            for x in 0..3_i16 {
+                buffer.push_point(OverlapPoint {
+                    position: Vector3::new(x as f64 + 0.5, 0.5, 0.0),
+                    point_source_id: id,
+                    scan_angle: (x - nadir_x) * 1000,
+                    classification_flags: OVERLAP_FLAG,
+                });
+            }
+        }
+
+        let expected = vec![false, false, true, true, true, false];
+        assert_eq!(expected, detect_overlap(&buffer, 1.0));
+
+        assert_eq!(3, flag_overlap(&mut buffer, 1.0));
+        let flags = buffer
+            .iter_attribute::<u8>(&CLASSIFICATION_FLAGS)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![0, 0, OVERLAP_FLAG, OVERLAP_FLAG, OVERLAP_FLAG, 0],
+            flags
+        );
+    }
+
+    #[test]
+    fn test_flag_overlap_legacy_classification() {
+        // Without scan angles, the flightline with more points in a cell keeps its points
+        let mut buffer = PerAttributeVecPointStorage::new(LegacyFlightlinePoint::layout());
+        let points = [(0.5, 1), (0.5, 1), (0.5, 2), (1.5, 2)];
+        for (x, id) in points.iter() {
+            buffer.push_point(LegacyFlightlinePoint {
+                position: Vector3::new(*x, 0.5, 0.0),
+                point_source_id: *id,
+                classification: 2,
+            });
+        }
+        assert_eq!(1, flag_overlap(&mut buffer, 1.0));
+        let classifications = buffer
+            .iter_attribute::<u8>(&CLASSIFICATION)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![2, 2, LEGACY_OVERLAP_CLASSIFICATION, 2],
+            classifications
+        );
+    }
+}
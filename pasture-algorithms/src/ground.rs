@@ -0,0 +1,253 @@
+use pasture_core::{
+    containers::{Grid2D, PointBuffer, PointBufferExt},
+    layout::attributes::POSITION_3D,
+    nalgebra::{Vector2, Vector3},
+};
+
+/// Parameters for the ground classification in [classify_ground]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GroundFilterParameters {
+    /// Edge length of the cells of the 2D grid that is used to estimate the terrain surface
+    pub cell_size: f64,
+    /// Radius (in cells) of the window for the morphological opening of the terrain surface. Objects that are larger
+    /// than this window (e.g. very large buildings) might be classified as ground
+    pub window_radius: usize,
+    /// Maximum height above the estimated terrain surface at which a point is still classified as ground
+    pub height_threshold: f64,
+}
+
+impl Default for GroundFilterParameters {
+    fn default() -> Self {
+        Self {
+            cell_size: 1.0,
+            window_radius: 10,
+            height_threshold: 0.5,
+        }
+    }
+}
+
+/// Result of [classify_ground]. Both vectors contain one entry per point of the classified buffer
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroundClassification {
+    /// `true` for all points that are classified as ground
+    pub is_ground: Vec<bool>,
+    /// Height of each point above the estimated terrain surface
+    pub height_above_ground: Vec<f64>,
+}
+
+/// Applies a 1D minimum or maximum filter with the given `radius` to all rows (`along_x == true`) or columns of `grid`.
+/// Empty cells (which are `f64::INFINITY`) are ignored
+fn filter_grid(grid: &Grid2D<f64>, radius: usize, along_x: bool, use_min: bool) -> Grid2D<f64> {
+    let mut filtered = grid.map(|_| f64::INFINITY);
+    for ((x, y), filtered_value) in filtered.iter_mut() {
+        let (center, len) = if along_x {
+            (x, grid.size_x())
+        } else {
+            (y, grid.size_y())
+        };
+        let first = center.saturating_sub(radius);
+        let last = (center + radius).min(len - 1);
+        let values = (first..=last)
+            .map(|idx| {
+                if along_x {
+                    grid[(idx, y)]
+                } else {
+                    grid[(x, idx)]
+                }
+            })
+            .filter(|value| value.is_finite());
+        let result = if use_min {
+            values.fold(f64::INFINITY, f64::min)
+        } else {
+            values.fold(f64::NEG_INFINITY, f64::max)
+        };
+        if result.is_finite() {
+            *filtered_value = result;
+        }
+    }
+    filtered
+}
+
+/// Classifies the points in `buffer` into ground and non-ground points and calculates the height of each point above
+/// the ground. This is a simple morphological filter: The terrain surface is estimated from the lowest point within
+/// each cell of a 2D grid, followed by a morphological opening (erosion and dilation) with a square window that
+/// removes objects like buildings and vegetation. All points that are at most `height_threshold` above this surface
+/// are classified as ground.
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::ground::{classify_ground, GroundFilterParameters};
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct SimplePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+/// }
+/// let mut points = vec![];
+/// for x in 0..20 {
+///     for y in 0..20 {
+///         points.push(SimplePoint{ position: Vector3::new(x as f64, y as f64, 0.0) });
+///     }
+/// }
+/// // A single point floating above the ground
+/// points.push(SimplePoint{ position: Vector3::new(10.0, 10.0, 5.0) });
+/// let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// buffer.push_points(&points);
+///
+/// let classification = classify_ground(&buffer, &GroundFilterParameters::default());
+/// assert!(classification.is_ground[..400].iter().all(|is_ground| *is_ground));
+/// assert!(!classification.is_ground[400]);
+/// assert_eq!(5.0, classification.height_above_ground[400]);
+/// ```
+///
+/// # Panics
+///
+/// If `cell_size` is not strictly positive, or if the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+pub fn classify_ground<T: PointBuffer>(
+    buffer: &T,
+    parameters: &GroundFilterParameters,
+) -> GroundClassification {
+    if parameters.cell_size <= 0.0 {
+        panic!("classify_ground: cell_size must be > 0");
+    }
+    let position_attribute = match buffer
+        .point_layout()
+        .get_attribute_by_name(POSITION_3D.name())
+    {
+        Some(a) => a,
+        None => panic!("point buffer contains no position attribute"),
+    };
+
+    let positions: Vec<Vector3<f64>> = if position_attribute.datatype() == POSITION_3D.datatype() {
+        buffer
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .collect()
+    } else {
+        buffer
+            .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+            .collect()
+    };
+    if positions.is_empty() {
+        return GroundClassification {
+            is_ground: vec![],
+            height_above_ground: vec![],
+        };
+    }
+
+    let min_x = positions.iter().map(|p| p.x).fold(f64::INFINITY, f64::min);
+    let min_y = positions.iter().map(|p| p.y).fold(f64::INFINITY, f64::min);
+    let max_x = positions
+        .iter()
+        .map(|p| p.x)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let max_y = positions
+        .iter()
+        .map(|p| p.y)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let size_x = ((max_x - min_x) / parameters.cell_size).floor() as usize + 1;
+    let size_y = ((max_y - min_y) / parameters.cell_size).floor() as usize + 1;
+    let cell_of = |position: &Vector3<f64>| -> (usize, usize) {
+        let x = (((position.x - min_x) / parameters.cell_size).floor() as usize).min(size_x - 1);
+        let y = (((position.y - min_y) / parameters.cell_size).floor() as usize).min(size_y - 1);
+        (x, y)
+    };
+
+    let mut lowest_points = Grid2D::new(
+        Vector2::new(min_x, min_y),
+        parameters.cell_size,
+        size_x,
+        size_y,
+        f64::INFINITY,
+    );
+    for position in positions.iter() {
+        let cell = cell_of(position);
+        lowest_points[cell] = lowest_points[cell].min(position.z);
+    }
+
+    // Morphological opening with a square window, which is separable into a row and a column pass
+    let radius = parameters.window_radius;
+    let eroded = filter_grid(&lowest_points, radius, true, true);
+    let eroded = filter_grid(&eroded, radius, false, true);
+    let opened = filter_grid(&eroded, radius, true, false);
+    let surface = filter_grid(&opened, radius, false, false);
+
+    let height_above_ground = positions
+        .iter()
+        .map(|position| position.z - surface[cell_of(position)])
+        .collect::<Vec<_>>();
+    let is_ground = height_above_ground
+        .iter()
+        .map(|height| *height <= parameters.height_threshold)
+        .collect();
+
+    GroundClassification {
+        is_ground,
+        height_above_ground,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{containers::PerAttributeVecPointStorage, layout::PointType};
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct SimplePoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+    }
+
+    #[test]
+    fn test_classify_ground_with_building() {
+        // Sloped terrain with a 4x4 meter building on top of it
+        let mut points = vec![];
+        for x in 0..100 {
+            for y in 0..100 {
+                let (x, y) = (x as f64 * 0.5, y as f64 * 0.5);
+                let terrain_height = 0.1 * x;
+                points.push(SimplePoint {
+                    position: Vector3::new(x, y, terrain_height),
+                });
+                if (20.0..24.0).contains(&x) && (20.0..24.0).contains(&y) {
+                    points.push(SimplePoint {
+                        position: Vector3::new(x, y, terrain_height + 8.0),
+                    });
+                }
+            }
+        }
+        let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+        buffer.push_points(&points);
+
+        let parameters = GroundFilterParameters {
+            cell_size: 1.0,
+            window_radius: 4,
+            height_threshold: 0.5,
+        };
+        let classification = classify_ground(&buffer, &parameters);
+        assert_eq!(points.len(), classification.is_ground.len());
+        for (idx, point) in points.iter().enumerate() {
+            let is_building = point.position.z > 0.1 * point.position.x + 1.0;
+            assert_eq!(!is_building, classification.is_ground[idx]);
+            assert!(classification.height_above_ground[idx] >= 0.0);
+            if is_building {
+                assert!(classification.height_above_ground[idx] >= 7.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_classify_ground_on_empty_buffer() {
+        let buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+        let classification = classify_ground(&buffer, &GroundFilterParameters::default());
+        assert!(classification.is_ground.is_empty());
+        assert!(classification.height_above_ground.is_empty());
+    }
+}
@@ -0,0 +1,784 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use anyhow::Result;
+use pasture_core::{
+    containers::{
+        InterleavedPointView, PointBuffer, PointBufferExt, PointBufferWriteable, SpatialQuery,
+        SpatialQueryable,
+    },
+    layout::{attributes::POSITION_3D, PointLayout},
+    math::{Frustum, Intersection, Ray, AABB},
+    nalgebra::Vector3,
+};
+
+/// A neighbor candidate during the search. Ordered by squared distance, so that a `BinaryHeap` of candidates has the
+/// farthest candidate on top
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Candidate {
+    pub distance_squared: f64,
+    pub index: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_squared
+            .partial_cmp(&other.distance_squared)
+            .unwrap_or(Ordering::Equal)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+/// How distances between points are measured by the neighbor queries of a [KdTree]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DistanceMode {
+    /// Euclidean distance in 3D
+    Xyz,
+    /// Euclidean distance of the XY positions, ignoring Z. This is usually what terrain analysis wants, as the
+    /// neighborhood of a point on a steep slope should not depend on the slope
+    Xy,
+}
+
+impl DistanceMode {
+    /// Returns the squared distance between `a` and `b` in this mode
+    pub fn distance_squared(&self, a: &Vector3<f64>, b: &Vector3<f64>) -> f64 {
+        match self {
+            DistanceMode::Xyz => (a - b).norm_squared(),
+            DistanceMode::Xy => (a.xy() - b.xy()).norm_squared(),
+        }
+    }
+}
+
+impl Default for DistanceMode {
+    fn default() -> Self {
+        DistanceMode::Xyz
+    }
+}
+
+/// The point that was hit by a ray, as returned by [KdTree::ray_cast]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    /// Index of the point that was hit
+    pub index: usize,
+    /// Distance from the origin of the ray to the point on the ray that is closest to the point that was hit
+    pub distance_along_ray: f64,
+    /// Distance between the point that was hit and the ray
+    pub distance_to_ray: f64,
+}
+
+/// A balanced kd-tree over a set of positions, which supports nearest neighbor queries, frustum culling and ray
+/// casting. The tree is stored implicitly in a permutation of the point indices: For each range of the permutation,
+/// the point at the center of the range splits the remaining points of the range along its split axis at the same
+/// position. The split axis is the axis with the largest extent, which keeps the cells of the tree close to cubic even
+/// if the point density differs strongly between the axes (as for airborne scans)
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::math::Ray;
+/// # use pasture_core::nalgebra::{Point3, Vector3};
+/// # use pasture_algorithms::kdtree::KdTree;
+/// let tree = KdTree::new(vec![
+///     Vector3::new(0.0, 0.0, 0.0),
+///     Vector3::new(5.0, 0.1, 0.0),
+///     Vector3::new(10.0, 0.0, 0.0),
+///     Vector3::new(5.0, 3.0, 0.0),
+/// ]);
+/// // Pick the first point along a ray in x-direction, starting right behind the first point
+/// let ray = Ray::new(Point3::new(1.0, 0.0, 0.0), Vector3::x());
+/// let hit = tree.ray_cast(&ray, 0.5).unwrap();
+/// assert_eq!(1, hit.index);
+/// assert_eq!(vec![(3, 2.0)], tree.nearest_neighbors(&Vector3::new(5.0, 5.0, 0.0), 1));
+/// ```
+pub struct KdTree {
+    positions: Vec<Vector3<f64>>,
+    order: Vec<usize>,
+    split_axes: Vec<usize>,
+    bounds: Option<AABB<f64>>,
+    distance_mode: DistanceMode,
+}
+
+impl KdTree {
+    /// Builds a kd-tree over the given `positions`. The points are identified by their index within `positions`
+    pub fn new(positions: Vec<Vector3<f64>>) -> Self {
+        Self::with_distance_mode(positions, DistanceMode::Xyz)
+    }
+
+    /// Builds a kd-tree over the given `positions` whose neighbor queries measure distances according to
+    /// `distance_mode`. With [DistanceMode::Xy], the tree only splits along the X and Y axes
+    pub fn with_distance_mode(positions: Vec<Vector3<f64>>, distance_mode: DistanceMode) -> Self {
+        let bounds = positions.split_first().map(|(first, rest)| {
+            let (min, max) = rest.iter().fold((*first, *first), |(min, max), position| {
+                (min.inf(position), max.sup(position))
+            });
+            AABB::from_min_max_unchecked(min.into(), max.into())
+        });
+        let mut tree = Self {
+            order: (0..positions.len()).collect(),
+            split_axes: vec![0; positions.len()],
+            positions,
+            bounds,
+            distance_mode,
+        };
+        tree.build(0, tree.positions.len());
+        tree
+    }
+
+    /// Builds a kd-tree over the positions of all points in `buffer`. The points are identified by their index within
+    /// `buffer`
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+    pub fn from_buffer<T: PointBuffer + ?Sized>(buffer: &T) -> Self {
+        if !buffer
+            .point_layout()
+            .has_attribute_with_name(POSITION_3D.name())
+        {
+            panic!("point buffer contains no position attribute");
+        }
+        Self::new(
+            buffer
+                .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+                .collect(),
+        )
+    }
+
+    /// Returns the number of points in this tree
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns true if this tree contains no points
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Returns the positions of all points in this tree, in their original order
+    pub fn positions(&self) -> &[Vector3<f64>] {
+        &self.positions
+    }
+
+    /// Returns how the neighbor queries of this tree measure distances
+    pub fn distance_mode(&self) -> DistanceMode {
+        self.distance_mode
+    }
+
+    fn build(&mut self, start: usize, end: usize) {
+        if end - start <= 1 {
+            return;
+        }
+        let mut min = self.positions[self.order[start]];
+        let mut max = min;
+        for index in &self.order[start..end] {
+            min = min.inf(&self.positions[*index]);
+            max = max.sup(&self.positions[*index]);
+        }
+        let axis = match self.distance_mode {
+            DistanceMode::Xyz => (max - min).imax(),
+            DistanceMode::Xy => (max - min).xy().imax(),
+        };
+
+        let center = (start + end) / 2;
+        let positions = &self.positions;
+        self.order[start..end].select_nth_unstable_by(center - start, |a, b| {
+            positions[*a][axis]
+                .partial_cmp(&positions[*b][axis])
+                .unwrap_or(Ordering::Equal)
+        });
+        self.split_axes[center] = axis;
+        self.build(start, center);
+        self.build(center + 1, end);
+    }
+
+    /// Returns the indices of the `k` points that are closest to `position`, together with their distances to
+    /// `position`, sorted by ascending distance
+    pub fn nearest_neighbors(&self, position: &Vector3<f64>, k: usize) -> Vec<(usize, f64)> {
+        let mut candidates = BinaryHeap::with_capacity(k + 1);
+        self.search_nearest(0, self.len(), position, None, k, &mut candidates);
+        candidates
+            .into_sorted_vec()
+            .into_iter()
+            .map(|candidate| (candidate.index, candidate.distance_squared.sqrt()))
+            .collect()
+    }
+
+    /// Returns the `k` nearest neighbors of the point at `query_index`, excluding the point itself, sorted by ascending
+    /// distance
+    pub(crate) fn nearest_neighbors_of_point(
+        &self,
+        query_index: usize,
+        k: usize,
+    ) -> Vec<Candidate> {
+        let mut candidates = BinaryHeap::with_capacity(k + 1);
+        self.search_nearest(
+            0,
+            self.len(),
+            &self.positions[query_index],
+            Some(query_index),
+            k,
+            &mut candidates,
+        );
+        candidates.into_sorted_vec()
+    }
+
+    fn search_nearest(
+        &self,
+        start: usize,
+        end: usize,
+        query: &Vector3<f64>,
+        excluded_index: Option<usize>,
+        k: usize,
+        candidates: &mut BinaryHeap<Candidate>,
+    ) {
+        if start >= end || k == 0 {
+            return;
+        }
+        let center = (start + end) / 2;
+        let index = self.order[center];
+        let position = &self.positions[index];
+
+        if excluded_index != Some(index) {
+            let candidate = Candidate {
+                distance_squared: self.distance_mode.distance_squared(position, query),
+                index,
+            };
+            if candidates.len() < k {
+                candidates.push(candidate);
+            } else if candidate < *candidates.peek().unwrap() {
+                candidates.pop();
+                candidates.push(candidate);
+            }
+        }
+
+        let axis = self.split_axes[center];
+        let offset = query[axis] - position[axis];
+        let (near, far) = if offset < 0.0 {
+            ((start, center), (center + 1, end))
+        } else {
+            ((center + 1, end), (start, center))
+        };
+        self.search_nearest(near.0, near.1, query, excluded_index, k, candidates);
+        let must_search_far =
+            candidates.len() < k || offset * offset <= candidates.peek().unwrap().distance_squared;
+        if must_search_far {
+            self.search_nearest(far.0, far.1, query, excluded_index, k, candidates);
+        }
+    }
+
+    /// Returns the indices of all points whose distance to `position` is at most `radius`, together with their
+    /// distances, sorted by ascending distance
+    pub fn points_within_radius(&self, position: &Vector3<f64>, radius: f64) -> Vec<(usize, f64)> {
+        let mut candidates = vec![];
+        self.search_radius(0, self.len(), position, radius * radius, &mut candidates);
+        candidates.sort_unstable();
+        candidates
+            .into_iter()
+            .map(|candidate| (candidate.index, candidate.distance_squared.sqrt()))
+            .collect()
+    }
+
+    fn search_radius(
+        &self,
+        start: usize,
+        end: usize,
+        query: &Vector3<f64>,
+        radius_squared: f64,
+        candidates: &mut Vec<Candidate>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let center = (start + end) / 2;
+        let index = self.order[center];
+        let position = &self.positions[index];
+        let distance_squared = self.distance_mode.distance_squared(position, query);
+        if distance_squared <= radius_squared {
+            candidates.push(Candidate {
+                distance_squared,
+                index,
+            });
+        }
+
+        let axis = self.split_axes[center];
+        let offset = query[axis] - position[axis];
+        if offset <= 0.0 || offset * offset <= radius_squared {
+            self.search_radius(start, center, query, radius_squared, candidates);
+        }
+        if offset >= 0.0 || offset * offset <= radius_squared {
+            self.search_radius(center + 1, end, query, radius_squared, candidates);
+        }
+    }
+
+    /// Returns the indices of all points inside of `frustum` in ascending order. Subtrees that are completely inside
+    /// or outside of `frustum` are accepted or rejected as a whole, which makes this query fast enough for
+    /// view-dependent culling of large point clouds
+    pub fn points_in_frustum(&self, frustum: &Frustum) -> Vec<usize> {
+        let mut indices = vec![];
+        if let Some(bounds) = &self.bounds {
+            self.search_frustum(
+                0,
+                self.len(),
+                bounds.min().coords,
+                bounds.max().coords,
+                frustum,
+                &mut indices,
+            );
+        }
+        indices.sort_unstable();
+        indices
+    }
+
+    fn search_frustum(
+        &self,
+        start: usize,
+        end: usize,
+        min: Vector3<f64>,
+        max: Vector3<f64>,
+        frustum: &Frustum,
+        indices: &mut Vec<usize>,
+    ) {
+        if start >= end {
+            return;
+        }
+        match frustum.intersect_aabb(&AABB::from_min_max_unchecked(min.into(), max.into())) {
+            Intersection::Outside => return,
+            Intersection::Inside => {
+                indices.extend_from_slice(&self.order[start..end]);
+                return;
+            }
+            Intersection::Intersecting => (),
+        }
+
+        let center = (start + end) / 2;
+        let index = self.order[center];
+        let position = &self.positions[index];
+        if frustum.contains(&(*position).into()) {
+            indices.push(index);
+        }
+
+        let axis = self.split_axes[center];
+        let mut lower_max = max;
+        lower_max[axis] = position[axis];
+        let mut upper_min = min;
+        upper_min[axis] = position[axis];
+        self.search_frustum(start, center, min, lower_max, frustum, indices);
+        self.search_frustum(center + 1, end, upper_min, max, frustum, indices);
+    }
+
+    /// Returns the indices of all points inside of `bounds` in ascending order
+    pub fn points_in_bounds(&self, bounds: &AABB<f64>) -> Vec<usize> {
+        let mut indices = vec![];
+        if let Some(tree_bounds) = &self.bounds {
+            self.search_bounds(
+                0,
+                self.len(),
+                tree_bounds.min().coords,
+                tree_bounds.max().coords,
+                bounds,
+                &mut indices,
+            );
+        }
+        indices.sort_unstable();
+        indices
+    }
+
+    fn search_bounds(
+        &self,
+        start: usize,
+        end: usize,
+        min: Vector3<f64>,
+        max: Vector3<f64>,
+        bounds: &AABB<f64>,
+        indices: &mut Vec<usize>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let cell = AABB::from_min_max_unchecked(min.into(), max.into());
+        if !cell.intersects(bounds) {
+            return;
+        }
+        if bounds.contains(cell.min()) && bounds.contains(cell.max()) {
+            indices.extend_from_slice(&self.order[start..end]);
+            return;
+        }
+
+        let center = (start + end) / 2;
+        let index = self.order[center];
+        let position = &self.positions[index];
+        if bounds.contains(&(*position).into()) {
+            indices.push(index);
+        }
+
+        let axis = self.split_axes[center];
+        let mut lower_max = max;
+        lower_max[axis] = position[axis];
+        let mut upper_min = min;
+        upper_min[axis] = position[axis];
+        self.search_bounds(start, center, min, lower_max, bounds, indices);
+        self.search_bounds(center + 1, end, upper_min, max, bounds, indices);
+    }
+
+    /// Returns the first point along `ray` whose distance to `ray` is at most `tolerance`, i.e. the point that would be
+    /// picked when clicking on a rendered point cloud. Points behind the origin of the ray are ignored. Returns `None`
+    /// if there is no such point
+    pub fn ray_cast(&self, ray: &Ray, tolerance: f64) -> Option<RayHit> {
+        let mut hit = None;
+        if let Some(bounds) = &self.bounds {
+            self.search_ray(
+                0,
+                self.len(),
+                bounds.min().coords,
+                bounds.max().coords,
+                ray,
+                tolerance,
+                &mut hit,
+            );
+        }
+        hit
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn search_ray(
+        &self,
+        start: usize,
+        end: usize,
+        min: Vector3<f64>,
+        max: Vector3<f64>,
+        ray: &Ray,
+        tolerance: f64,
+        hit: &mut Option<RayHit>,
+    ) {
+        if start >= end {
+            return;
+        }
+        // Every point within `tolerance` of the ray is inside the cell grown by `tolerance`, so the ray can't hit any
+        // point of this subtree before it enters the grown cell
+        let grown_cell = AABB::from_min_max_unchecked(
+            (min - Vector3::repeat(tolerance)).into(),
+            (max + Vector3::repeat(tolerance)).into(),
+        );
+        let entry = match ray.intersect_aabb(&grown_cell) {
+            Some((entry, _)) => entry,
+            None => return,
+        };
+        if let Some(hit) = hit {
+            if entry > hit.distance_along_ray {
+                return;
+            }
+        }
+
+        let center = (start + end) / 2;
+        let index = self.order[center];
+        let position = &self.positions[index];
+        let (distance_along_ray, distance_to_ray) = ray.closest_approach(&(*position).into());
+        let is_closer = hit
+            .map(|hit| distance_along_ray < hit.distance_along_ray)
+            .unwrap_or(true);
+        if distance_along_ray >= 0.0 && distance_to_ray <= tolerance && is_closer {
+            *hit = Some(RayHit {
+                index,
+                distance_along_ray,
+                distance_to_ray,
+            });
+        }
+
+        // Visit the child on the side of the ray origin first, as its points are usually closer along the ray
+        let axis = self.split_axes[center];
+        let mut lower_max = max;
+        lower_max[axis] = position[axis];
+        let mut upper_min = min;
+        upper_min[axis] = position[axis];
+        let lower = (start, center, min, lower_max);
+        let upper = (center + 1, end, upper_min, max);
+        let (first, second) = if ray.origin()[axis] < position[axis] {
+            (lower, upper)
+        } else {
+            (upper, lower)
+        };
+        self.search_ray(first.0, first.1, first.2, first.3, ray, tolerance, hit);
+        self.search_ray(second.0, second.1, second.2, second.3, ray, tolerance, hit);
+    }
+}
+
+/// A point buffer together with a [KdTree] over its positions, which answers [SpatialQuery]s on the points of the
+/// buffer. Points are always returned at full resolution
+pub struct IndexedBuffer<'a, T: PointBuffer + ?Sized> {
+    buffer: &'a T,
+    tree: KdTree,
+}
+
+impl<'a, T: PointBuffer + ?Sized> IndexedBuffer<'a, T> {
+    /// Builds a [KdTree] over the positions of all points in `buffer`
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+    pub fn new(buffer: &'a T) -> Self {
+        Self {
+            buffer,
+            tree: KdTree::from_buffer(buffer),
+        }
+    }
+
+    /// Returns the indexed buffer
+    pub fn buffer(&self) -> &'a T {
+        self.buffer
+    }
+
+    /// Returns the [KdTree] over the positions of the buffer
+    pub fn tree(&self) -> &KdTree {
+        &self.tree
+    }
+
+    /// Returns the indices of all points in the buffer that match `query`. The indices are in ascending order, except
+    /// for `Nearest` queries, where they are sorted by ascending distance
+    pub fn query_indices(&self, query: &SpatialQuery) -> Vec<usize> {
+        match query {
+            SpatialQuery::Nearest { position, k } => self
+                .tree
+                .nearest_neighbors(position, *k)
+                .into_iter()
+                .map(|(index, _)| index)
+                .collect(),
+            _ => match query.search_bounds() {
+                Some(search_bounds) => self
+                    .tree
+                    .points_in_bounds(&search_bounds)
+                    .into_iter()
+                    .filter(|index| query.contains(&self.tree.positions()[*index]))
+                    .collect(),
+                None => vec![],
+            },
+        }
+    }
+}
+
+impl<'a, T: PointBuffer + ?Sized> SpatialQueryable for IndexedBuffer<'a, T> {
+    fn point_layout(&self) -> &PointLayout {
+        self.buffer.point_layout()
+    }
+
+    fn query(
+        &mut self,
+        query: &SpatialQuery,
+        _resolution: Option<f64>,
+        points: &mut dyn PointBufferWriteable,
+    ) -> Result<usize> {
+        let indices = self.query_indices(query);
+        push_points_at_indices(self.buffer, &indices, points);
+        Ok(indices.len())
+    }
+}
+
+/// Pushes the points at the given `indices` of `buffer` to `points`, in the order of `indices`
+pub(crate) fn push_points_at_indices<T: PointBuffer + ?Sized>(
+    buffer: &T,
+    indices: &[usize],
+    points: &mut dyn PointBufferWriteable,
+) {
+    let point_size = buffer.point_layout().size_of_point_entry() as usize;
+    let mut data = vec![0; indices.len() * point_size];
+    for (index, point) in indices.iter().zip(data.chunks_exact_mut(point_size)) {
+        buffer.get_raw_point(*index, point);
+    }
+    points.push(&InterleavedPointView::from_raw_slice(
+        &data,
+        buffer.point_layout().clone(),
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::InterleavedVecPointStorage,
+        layout::PointType,
+        nalgebra::{Matrix4, Point3, Vector2},
+    };
+    use pasture_derive::PointType;
+    use rand::{prelude::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug, Copy, Clone)]
+    struct TestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        position: Vector3<f64>,
+    }
+
+    fn random_positions(count: usize) -> Vec<Vector3<f64>> {
+        let mut rng = StdRng::seed_from_u64(1234);
+        (0..count)
+            .map(|_| {
+                Vector3::new(
+                    rng.gen_range(-50.0..50.0),
+                    rng.gen_range(-50.0..50.0),
+                    rng.gen_range(-5.0..5.0),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_points_in_frustum_matches_brute_force() {
+        let positions = random_positions(2000);
+        let tree = KdTree::new(positions.clone());
+
+        let view = Matrix4::look_at_rh(
+            &Point3::new(0.0, -80.0, 40.0),
+            &Point3::new(10.0, 0.0, 0.0),
+            &Vector3::z(),
+        );
+        let projection = Matrix4::new_perspective(1.5, 0.6, 1.0, 120.0);
+        let frustum = Frustum::from_view_projection_gl(&(projection * view));
+
+        let expected = positions
+            .iter()
+            .enumerate()
+            .filter(|(_, position)| frustum.contains(&(**position).into()))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        assert!(!expected.is_empty() && expected.len() < positions.len());
+        assert_eq!(expected, tree.points_in_frustum(&frustum));
+    }
+
+    #[test]
+    fn test_spatial_queries_match_brute_force() -> Result<()> {
+        let positions = random_positions(2000);
+        let tree = KdTree::new(positions.clone());
+
+        let bounds =
+            AABB::from_min_max(Point3::new(-10.0, 5.0, -1.0), Point3::new(20.0, 30.0, 2.0));
+        let expected = positions
+            .iter()
+            .enumerate()
+            .filter(|(_, position)| bounds.contains(&(**position).into()))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        assert!(!expected.is_empty());
+        assert_eq!(expected, tree.points_in_bounds(&bounds));
+
+        let buffer = positions
+            .iter()
+            .map(|position| TestPoint {
+                position: *position,
+            })
+            .collect::<InterleavedVecPointStorage>();
+        let mut indexed = IndexedBuffer::new(&buffer);
+        let polygon = SpatialQuery::Polygon(vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(40.0, 0.0),
+            Vector2::new(0.0, 40.0),
+        ]);
+        let expected = positions
+            .iter()
+            .filter(|position| {
+                position.x >= 0.0 && position.y >= 0.0 && position.x + position.y < 40.0
+            })
+            .copied()
+            .collect::<Vec<_>>();
+        let mut result = InterleavedVecPointStorage::new(TestPoint::layout());
+        assert_eq!(expected.len(), indexed.query(&polygon, None, &mut result)?);
+        assert_eq!(
+            expected,
+            result
+                .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                .collect::<Vec<_>>()
+        );
+
+        let nearest = SpatialQuery::Nearest {
+            position: Vector3::zeros(),
+            k: 5,
+        };
+        let expected = tree
+            .nearest_neighbors(&Vector3::zeros(), 5)
+            .into_iter()
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        assert_eq!(expected, indexed.query_indices(&nearest));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ray_cast_matches_brute_force() {
+        let positions = random_positions(2000);
+        let tree = KdTree::new(positions.clone());
+        let tolerance = 1.0;
+
+        let mut rng = StdRng::seed_from_u64(99);
+        for _ in 0..50 {
+            let origin = Point3::new(rng.gen_range(-60.0..60.0), -60.0, rng.gen_range(-5.0..5.0));
+            let direction = Vector3::new(rng.gen_range(-0.5..0.5), 1.0, rng.gen_range(-0.1..0.1));
+            let ray = Ray::new(origin, direction);
+
+            let expected = positions
+                .iter()
+                .enumerate()
+                .map(|(index, position)| (index, ray.closest_approach(&(*position).into())))
+                .filter(|(_, (along, to))| *along >= 0.0 && *to <= tolerance)
+                .min_by(|(_, (a, _)), (_, (b, _))| a.partial_cmp(b).unwrap())
+                .map(|(index, _)| index);
+            assert_eq!(
+                expected,
+                tree.ray_cast(&ray, tolerance).map(|hit| hit.index)
+            );
+        }
+    }
+
+    #[test]
+    fn test_neighbor_queries_in_xy_match_brute_force() {
+        let positions = random_positions(2000);
+        let position = Vector3::new(3.0, -7.0, 40.0);
+        for distance_mode in &[DistanceMode::Xyz, DistanceMode::Xy] {
+            let tree = KdTree::with_distance_mode(positions.clone(), *distance_mode);
+            let mut expected = positions
+                .iter()
+                .enumerate()
+                .map(|(index, other)| {
+                    (
+                        index,
+                        distance_mode.distance_squared(other, &position).sqrt(),
+                    )
+                })
+                .collect::<Vec<_>>();
+            expected.sort_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+
+            assert_eq!(expected[..10], tree.nearest_neighbors(&position, 10)[..]);
+            let radius = (expected[25].1 + expected[26].1) / 2.0;
+            assert_eq!(
+                expected[..26],
+                tree.points_within_radius(&position, radius)[..]
+            );
+        }
+    }
+
+    #[test]
+    fn test_queries_on_empty_tree() {
+        let tree = KdTree::new(vec![]);
+        assert!(tree.is_empty());
+        assert!(tree.nearest_neighbors(&Vector3::zeros(), 3).is_empty());
+        assert!(tree.points_within_radius(&Vector3::zeros(), 1.0).is_empty());
+        assert!(tree
+            .points_in_frustum(&Frustum::from_view_projection(&Matrix4::identity()))
+            .is_empty());
+        assert_eq!(
+            None,
+            tree.ray_cast(&Ray::new(Point3::origin(), Vector3::x()), 1.0)
+        );
+    }
+}
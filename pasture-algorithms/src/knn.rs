@@ -0,0 +1,322 @@
+use pasture_core::{
+    containers::{PointBuffer, PointBufferExt},
+    layout::attributes::POSITION_3D,
+    nalgebra::Vector3,
+};
+use rayon::prelude::*;
+
+use crate::{
+    ann::{ApproximateSearchParameters, KdForest},
+    kdtree::{Candidate, DistanceMode, KdTree},
+};
+
+/// How the nearest neighbors of the points are found by [knn_graph_with_search] and the algorithms that build on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NeighborSearch {
+    /// Exact neighbors from a [KdTree]
+    Exact,
+    /// Approximate neighbors from a [KdForest], which is much faster on massive point clouds where exact neighbors are
+    /// not needed
+    Approximate(ApproximateSearchParameters),
+}
+
+impl Default for NeighborSearch {
+    fn default() -> Self {
+        NeighborSearch::Exact
+    }
+}
+
+/// A k-nearest neighbor graph over the points of a buffer, as calculated by [knn_graph]. The neighbors of all points
+/// are stored in two flat arrays with `k` entries per point, so that the neighbors of the point at index `i` are at the
+/// indices `i * k..(i + 1) * k`. This is the layout that most graph and machine learning libraries expect for their
+/// edge lists
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnnGraph {
+    /// Number of neighbors per point
+    pub k: usize,
+    /// Indices of the neighbors of each point, sorted by ascending distance
+    pub indices: Vec<usize>,
+    /// Euclidean distances to the neighbors of each point (in 3D or in XY, depending on the [DistanceMode]), with the
+    /// same layout as `indices`
+    pub distances: Vec<f64>,
+}
+
+impl KnnGraph {
+    /// Returns the number of points in this graph
+    pub fn point_count(&self) -> usize {
+        if self.k == 0 {
+            0
+        } else {
+            self.indices.len() / self.k
+        }
+    }
+
+    /// Returns the indices of the neighbors of the point at `index`, sorted by ascending distance
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds
+    pub fn neighbors(&self, index: usize) -> &[usize] {
+        &self.indices[index * self.k..(index + 1) * self.k]
+    }
+
+    /// Returns the distances to the neighbors of the point at `index`, in the same order as `neighbors(index)`
+    ///
+    /// # Panics
+    ///
+    /// If `index` is out of bounds
+    pub fn neighbor_distances(&self, index: usize) -> &[f64] {
+        &self.distances[index * self.k..(index + 1) * self.k]
+    }
+
+    /// Returns an iterator over all edges of this graph as `(source, target, distance)` tuples
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize, f64)> + '_ {
+        let k = self.k;
+        self.indices
+            .iter()
+            .zip(self.distances.iter())
+            .enumerate()
+            .map(move |(edge, (target, distance))| (edge / k, *target, *distance))
+    }
+}
+
+/// Calculates the k-nearest neighbor graph of the points in `buffer`, which contains the `k` nearest neighbors of every
+/// point (excluding the point itself). If `buffer` contains `k` or fewer points, `k` is reduced to the number of points
+/// minus one. The neighbors are found using a balanced kd-tree that splits along the axis of largest extent and the
+/// queries run in parallel.
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::knn::knn_graph;
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct SimplePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+/// }
+/// let points = vec![
+///     SimplePoint{ position: Vector3::new(0.0, 0.0, 0.0) },
+///     SimplePoint{ position: Vector3::new(1.0, 0.0, 0.0) },
+///     SimplePoint{ position: Vector3::new(3.0, 0.0, 0.0) },
+/// ];
+/// let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// buffer.push_points(&points);
+/// let graph = knn_graph(&buffer, 1);
+/// assert_eq!(vec![1, 0, 1], graph.indices);
+/// assert_eq!(vec![1.0, 1.0, 2.0], graph.distances);
+/// ```
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+pub fn knn_graph<T: PointBuffer + ?Sized>(buffer: &T, k: usize) -> KnnGraph {
+    knn_graph_with_distance_mode(buffer, k, DistanceMode::Xyz)
+}
+
+/// Like [knn_graph], but measures the distances between points according to `distance_mode`. With
+/// [DistanceMode::Xy], the neighborhoods ignore the Z coordinate, which is what terrain analysis usually wants
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+pub fn knn_graph_with_distance_mode<T: PointBuffer + ?Sized>(
+    buffer: &T,
+    k: usize,
+    distance_mode: DistanceMode,
+) -> KnnGraph {
+    knn_graph_with_search(buffer, k, distance_mode, NeighborSearch::Exact)
+}
+
+/// Like [knn_graph_with_distance_mode], but finds the neighbors with the given `search`, which allows to trade
+/// accuracy for speed on large point clouds
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::ann::ApproximateSearchParameters;
+/// # use pasture_algorithms::kdtree::DistanceMode;
+/// # use pasture_algorithms::knn::{knn_graph_with_search, NeighborSearch};
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct SimplePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+/// }
+/// let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// for i in 0..100 {
+///     buffer.push_point(SimplePoint{ position: Vector3::new(i as f64, 0.0, 0.0) });
+/// }
+/// let search = NeighborSearch::Approximate(ApproximateSearchParameters::default());
+/// let graph = knn_graph_with_search(&buffer, 2, DistanceMode::Xyz, search);
+/// assert_eq!(&[49, 51], graph.neighbors(50));
+/// ```
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+pub fn knn_graph_with_search<T: PointBuffer + ?Sized>(
+    buffer: &T,
+    k: usize,
+    distance_mode: DistanceMode,
+    search: NeighborSearch,
+) -> KnnGraph {
+    if !buffer
+        .point_layout()
+        .has_attribute_with_name(POSITION_3D.name())
+    {
+        panic!("point buffer contains no position attribute");
+    }
+    let positions: Vec<Vector3<f64>> = buffer
+        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+        .collect();
+    let k = k.min(positions.len().saturating_sub(1));
+    if k == 0 {
+        return KnnGraph {
+            k,
+            indices: vec![],
+            distances: vec![],
+        };
+    }
+
+    let point_count = positions.len();
+    let neighbors = nearest_neighbors_of_all_points(positions, k, distance_mode, search);
+
+    let mut indices = Vec::with_capacity(point_count * k);
+    let mut distances = Vec::with_capacity(point_count * k);
+    for candidate in neighbors.iter().flatten() {
+        indices.push(candidate.index);
+        distances.push(candidate.distance_squared.sqrt());
+    }
+    KnnGraph {
+        k,
+        indices,
+        distances,
+    }
+}
+
+/// Returns the `k` nearest neighbors of each of the given `positions`, excluding the point itself, sorted by ascending
+/// distance. The queries run in parallel
+pub(crate) fn nearest_neighbors_of_all_points(
+    positions: Vec<Vector3<f64>>,
+    k: usize,
+    distance_mode: DistanceMode,
+    search: NeighborSearch,
+) -> Vec<Vec<Candidate>> {
+    match search {
+        NeighborSearch::Exact => {
+            let tree = KdTree::with_distance_mode(positions, distance_mode);
+            (0..tree.len())
+                .into_par_iter()
+                .map(|index| tree.nearest_neighbors_of_point(index, k))
+                .collect()
+        }
+        NeighborSearch::Approximate(parameters) => {
+            let forest = KdForest::with_distance_mode(positions, &parameters, distance_mode);
+            (0..forest.len())
+                .into_par_iter()
+                .map(|index| forest.nearest_neighbors_of_point(index, k))
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{containers::PerAttributeVecPointStorage, layout::PointType};
+    use pasture_derive::PointType;
+    use rand::{prelude::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct SimplePoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+    }
+
+    #[test]
+    fn test_knn_graph_matches_brute_force() {
+        let mut rng = StdRng::seed_from_u64(42);
+        let positions = (0..500)
+            .map(|_| {
+                Vector3::new(
+                    rng.gen_range(0.0..100.0),
+                    rng.gen_range(0.0..10.0),
+                    rng.gen_range(0.0..1.0),
+                )
+            })
+            .collect::<Vec<_>>();
+        let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+        for position in positions.iter() {
+            buffer.push_point(SimplePoint {
+                position: *position,
+            });
+        }
+
+        let k = 8;
+        let graph = knn_graph(&buffer, k);
+        assert_eq!(k, graph.k);
+        assert_eq!(positions.len(), graph.point_count());
+        for (index, position) in positions.iter().enumerate() {
+            let mut expected = positions
+                .iter()
+                .enumerate()
+                .filter(|(other_index, _)| *other_index != index)
+                .map(|(_, other)| (other - position).norm())
+                .collect::<Vec<_>>();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            expected.truncate(k);
+            assert_eq!(expected.as_slice(), graph.neighbor_distances(index));
+            assert!(!graph.neighbors(index).contains(&index));
+        }
+        assert_eq!(positions.len() * k, graph.edges().count());
+    }
+
+    #[test]
+    fn test_knn_graph_in_xy() {
+        // A steep slope, where the 3D neighbors of a point are the points above and below it
+        let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+        for x in 0..5 {
+            for z in 0..3 {
+                buffer.push_point(SimplePoint {
+                    position: Vector3::new(x as f64, 0.0, x as f64 * 0.5 + z as f64 * 0.1),
+                });
+            }
+        }
+        let graph = knn_graph(&buffer, 2);
+        assert_eq!(&[0, 2], graph.neighbors(1));
+
+        let graph = knn_graph_with_distance_mode(&buffer, 2, DistanceMode::Xy);
+        assert_eq!(&[0.0, 0.0], graph.neighbor_distances(1));
+        let graph = knn_graph_with_distance_mode(&buffer, 3, DistanceMode::Xy);
+        assert_eq!(1.0, graph.neighbor_distances(1)[2]);
+    }
+
+    #[test]
+    fn test_knn_graph_with_few_points() {
+        let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+        buffer.push_point(SimplePoint {
+            position: Vector3::new(1.0, 2.0, 3.0),
+        });
+        let graph = knn_graph(&buffer, 4);
+        assert_eq!(0, graph.k);
+        assert_eq!(0, graph.point_count());
+
+        buffer.push_point(SimplePoint {
+            position: Vector3::new(1.0, 2.0, 5.0),
+        });
+        let graph = knn_graph(&buffer, 4);
+        assert_eq!(1, graph.k);
+        assert_eq!(
+            vec![(0, 1, 2.0), (1, 0, 2.0)],
+            graph.edges().collect::<Vec<_>>()
+        );
+    }
+}
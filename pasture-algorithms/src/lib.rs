@@ -12,4 +12,47 @@ pub mod minmax;
 pub mod convexhull;
 // Contains ransac line- and plane-segmentation algorithms in serial and parallel that can be used
 // to get the best line-/plane-model and the corresponding inlier indices.
-pub mod segmentation;
\ No newline at end of file
+pub mod segmentation;
+// Voxel grid filter that keeps one point per cell of a regular grid.
+pub mod voxel_grid;
+// Sparse voxelization with per-voxel point counts and attribute statistics, and export as occupancy grid or binary file.
+pub mod voxelization;
+// Streaming downsampling strategies (voxel grid and Poisson disk) that operate on chunks of points.
+pub mod downsampling;
+// Morphological ground filter that classifies ground points and calculates the height above ground.
+pub mod ground;
+// Translation of classification codes between classification schemes and classification with user-supplied models.
+pub mod classification;
+// Per-flightline statistics (point counts, density, scan angle distribution, overlap) and overlap flagging.
+pub mod flightlines;
+// Balanced kd-tree over point positions with nearest neighbor, frustum culling and ray casting queries.
+pub mod kdtree;
+// Quadtree over the XY positions of points, a cheaper spatial index than a kd-tree or an octree for airborne data.
+pub mod quadtree;
+// Octree over point positions with bounds and nearest neighbor queries.
+pub mod octree;
+// k-nearest neighbor graph over all points of a buffer, stored as flat index and distance arrays.
+pub mod knn;
+// Approximate nearest neighbor search with a forest of randomized kd-trees, for massive point clouds.
+pub mod ann;
+// Consistent orientation of normals, by minimum spanning tree propagation or towards sensor positions.
+pub mod normals;
+// Range and incidence angle of points relative to the sensor, and radiometric calibration of intensities.
+pub mod radiometry;
+// Gaussian decomposition of full-waveform data into echoes, which are extracted as additional points.
+pub mod waveform;
+// Dense feature matrices with labels from the classification, e.g. as training data for machine learning.
+pub mod features;
+// Single-band rasters such as masks and DEMs, and tests of points against them.
+pub mod raster;
+// 2D Delaunay triangulation of point positions as a triangulated irregular network (TIN).
+pub mod tin;
+// Contour lines at regular elevation intervals, extracted from a TIN.
+pub mod contours;
+// Motion compensation (deskewing) of lidar frames by interpolating sensor poses over the point timestamps.
+pub mod deskew;
+// Levels of detail of points for progressive rendering, from octree grids or spacing-based sampling.
+pub mod lod;
+// Reprojection of point positions between coordinate reference systems using PROJ.
+#[cfg(feature = "proj")]
+pub mod reprojection;
\ No newline at end of file
@@ -0,0 +1,308 @@
+use std::collections::{HashMap, HashSet};
+
+use pasture_core::{
+    containers::{PointBufferWriteable, PointBufferWriteableExt},
+    layout::attributes::LOD_LEVEL,
+    nalgebra::Vector3,
+};
+
+use crate::normals::positions_of;
+
+/// How [compute_lod_levels] selects the points of each level of detail
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LodSampling {
+    /// Level 0 contains at most one point per cell of a grid with `grid_size`^3 cubic cells over the bounding cube of
+    /// the points, and each following level doubles the resolution of the grid. This matches the nodes of an octree
+    /// whose nodes have a sampling grid with `grid_size`^3 cells, like the one that `StreamingOctreeBuilder` in
+    /// pasture-io builds, so level `n` contains the points of the nodes at depth `n`
+    OctreeGrid { grid_size: u64 },
+    /// Level 0 contains points with a minimum distance of `spacing` (Poisson disk sampling), and each following level
+    /// halves the spacing, like the levels of a Potree octree. The spacing applies to the points of a level together
+    /// with the points of all coarser levels
+    Spacing { spacing: f64 },
+}
+
+/// Returns the index of the cell that contains `position` within a grid of `cells_per_axis`^3 cells over the cube at
+/// `min` with the given `size`
+fn grid_cell(
+    position: &Vector3<f64>,
+    min: &Vector3<f64>,
+    size: f64,
+    cells_per_axis: u64,
+) -> (u64, u64, u64) {
+    let to_cell = |value: f64, min: f64| -> u64 {
+        if size <= 0.0 {
+            return 0;
+        }
+        let cell = ((value - min) / size * cells_per_axis as f64).floor();
+        (cell.max(0.0) as u64).min(cells_per_axis - 1)
+    };
+    (
+        to_cell(position.x, min.x),
+        to_cell(position.y, min.y),
+        to_cell(position.z, min.z),
+    )
+}
+
+/// Returns the cell of a grid with cubic cells of the given `cell_size` that contains `position`
+fn spacing_cell(position: &Vector3<f64>, cell_size: f64) -> (i64, i64, i64) {
+    (
+        (position.x / cell_size).floor() as i64,
+        (position.y / cell_size).floor() as i64,
+        (position.z / cell_size).floor() as i64,
+    )
+}
+
+/// Returns the points among `candidates` that are at least `spacing` away from all `accepted` points and from each
+/// other, in the order of `candidates`
+fn poisson_disk_sample(
+    positions: &[Vector3<f64>],
+    accepted: &[usize],
+    candidates: &[usize],
+    spacing: f64,
+) -> Vec<usize> {
+    // With cells of size spacing, all neighbours within spacing are found in the 27 adjacent cells
+    let mut grid: HashMap<(i64, i64, i64), Vec<Vector3<f64>>> = HashMap::new();
+    for index in accepted {
+        grid.entry(spacing_cell(&positions[*index], spacing))
+            .or_default()
+            .push(positions[*index]);
+    }
+    let spacing_squared = spacing * spacing;
+    let mut sampled = vec![];
+    for index in candidates {
+        let position = positions[*index];
+        let cell = spacing_cell(&position, spacing);
+        let mut has_neighbour = false;
+        'neighbours: for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    if let Some(points) = grid.get(&(cell.0 + dx, cell.1 + dy, cell.2 + dz)) {
+                        if points
+                            .iter()
+                            .any(|point| (point - position).norm_squared() < spacing_squared)
+                        {
+                            has_neighbour = true;
+                            break 'neighbours;
+                        }
+                    }
+                }
+            }
+        }
+        if !has_neighbour {
+            grid.entry(cell).or_default().push(position);
+            sampled.push(*index);
+        }
+    }
+    sampled
+}
+
+/// Calculates the `LOD_LEVEL` of all points in `buffer`, i.e. the level of detail at which a progressive renderer
+/// should display each point. The points of level 0 are a spatially uniform subset of the point cloud, and each
+/// following level adds points until the full resolution is reached, as selected by `sampling`. Points that are not
+/// part of any level up to `max_level` get the level `max_level`. Which points are selected for a level depends on the
+/// order of the points in `buffer`. Viewers can display all points up to a level, and writers can order the points by
+/// their level, so that every prefix of the points is a uniform subset. Returns the highest level of all points, or
+/// `None` if `buffer` is empty
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::{PerAttributeVecPointStorage, PointBufferExt};
+/// # use pasture_core::layout::{attributes, PointType};
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::lod::{compute_lod_levels, LodSampling};
+/// #[repr(C)]
+/// #[derive(PointType, Default)]
+/// struct Point {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+///     #[pasture(BUILTIN_LOD_LEVEL)]
+///     pub lod_level: u8,
+/// }
+/// let mut buffer = PerAttributeVecPointStorage::new(Point::layout());
+/// for x in &[0.0, 0.2, 1.0, 1.2] {
+///     buffer.push_point(Point {
+///         position: Vector3::new(*x, 0.0, 0.0),
+///         ..Default::default()
+///     });
+/// }
+///
+/// let max_level = compute_lod_levels(&mut buffer, LodSampling::Spacing { spacing: 0.5 }, 4);
+/// assert_eq!(Some(2), max_level);
+/// assert_eq!(
+///     vec![0, 2, 0, 2],
+///     buffer.iter_attribute::<u8>(&attributes::LOD_LEVEL).collect::<Vec<_>>()
+/// );
+/// ```
+///
+/// # Panics
+///
+/// If the `grid_size` or the `spacing` of `sampling` is not strictly positive, or if the `PointLayout` of `buffer`
+/// doesn't contain a `POSITION_3D` attribute and a `LOD_LEVEL` attribute with the datatype `U8`
+pub fn compute_lod_levels<T: PointBufferWriteable + ?Sized>(
+    buffer: &mut T,
+    sampling: LodSampling,
+    max_level: u8,
+) -> Option<u8> {
+    match sampling {
+        LodSampling::OctreeGrid { grid_size } if grid_size == 0 => {
+            panic!("compute_lod_levels: grid_size must be > 0")
+        }
+        LodSampling::Spacing { spacing } if spacing.is_nan() || spacing <= 0.0 => {
+            panic!("compute_lod_levels: spacing must be > 0")
+        }
+        _ => (),
+    }
+    if !buffer.point_layout().has_attribute(&LOD_LEVEL) {
+        panic!("point buffer contains no LOD level attribute with datatype U8");
+    }
+    let positions = positions_of(buffer);
+    if positions.is_empty() {
+        return None;
+    }
+
+    let mut min = positions[0];
+    let mut max = positions[0];
+    for position in positions.iter() {
+        min = min.inf(position);
+        max = max.sup(position);
+    }
+    let cube_size = (max - min).max();
+
+    let mut levels = vec![max_level; positions.len()];
+    let mut accepted = vec![];
+    let mut remaining = (0..positions.len()).collect::<Vec<_>>();
+    let mut highest_level = 0;
+    for level in 0..max_level {
+        if remaining.is_empty() {
+            break;
+        }
+        let level_points = match sampling {
+            LodSampling::OctreeGrid { grid_size } => {
+                let cells_per_axis = grid_size.saturating_mul(1u64 << level.min(63));
+                let mut occupied_cells = HashSet::new();
+                remaining
+                    .iter()
+                    .copied()
+                    .filter(|index| {
+                        occupied_cells.insert(grid_cell(
+                            &positions[*index],
+                            &min,
+                            cube_size,
+                            cells_per_axis,
+                        ))
+                    })
+                    .collect::<Vec<_>>()
+            }
+            LodSampling::Spacing { spacing } => poisson_disk_sample(
+                &positions,
+                &accepted,
+                &remaining,
+                spacing / 2.0_f64.powi(level as i32),
+            ),
+        };
+        if level_points.is_empty() {
+            continue;
+        }
+        highest_level = level;
+        for index in level_points.iter() {
+            levels[*index] = level;
+        }
+        let level_points = level_points.into_iter().collect::<HashSet<_>>();
+        remaining.retain(|index| !level_points.contains(index));
+        accepted.extend(level_points);
+    }
+    if !remaining.is_empty() {
+        highest_level = max_level;
+    }
+
+    for (index, level) in levels.into_iter().enumerate() {
+        buffer.set_attribute(&LOD_LEVEL, index, level);
+    }
+    Some(highest_level)
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::{PerAttributeVecPointStorage, PointBufferExt},
+        layout::PointType,
+    };
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug, Default)]
+    struct LodPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_LOD_LEVEL)]
+        pub lod_level: u8,
+    }
+
+    fn buffer_of(positions: &[Vector3<f64>]) -> PerAttributeVecPointStorage {
+        let mut buffer = PerAttributeVecPointStorage::new(LodPoint::layout());
+        for position in positions {
+            buffer.push_point(LodPoint {
+                position: *position,
+                ..Default::default()
+            });
+        }
+        buffer
+    }
+
+    fn levels_of(buffer: &PerAttributeVecPointStorage) -> Vec<u8> {
+        buffer.iter_attribute::<u8>(&LOD_LEVEL).collect()
+    }
+
+    #[test]
+    fn test_lod_levels_from_octree_grid() {
+        // The bounding cube is [0; 4]^3, so the grid of level 0 has a single cell, level 1 has cells of size 2 and
+        // level 2 has cells of size 1. Each level only samples the points that no coarser level contains
+        let mut buffer = buffer_of(&[
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.1, 0.1, 0.1),
+            Vector3::new(1.5, 0.0, 0.0),
+            Vector3::new(4.0, 4.0, 4.0),
+            Vector3::new(0.2, 0.2, 0.2),
+        ]);
+        assert_eq!(
+            Some(2),
+            compute_lod_levels(&mut buffer, LodSampling::OctreeGrid { grid_size: 1 }, 3)
+        );
+        assert_eq!(vec![0, 1, 2, 1, 2], levels_of(&buffer));
+
+        assert_eq!(
+            Some(1),
+            compute_lod_levels(&mut buffer, LodSampling::OctreeGrid { grid_size: 1 }, 1)
+        );
+        assert_eq!(vec![0, 1, 1, 1, 1], levels_of(&buffer));
+    }
+
+    #[test]
+    fn test_lod_levels_from_spacing() {
+        let mut buffer = buffer_of(&[
+            Vector3::new(0.0, 0.0, 0.0),
+            Vector3::new(0.3, 0.0, 0.0),
+            Vector3::new(0.5, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+        ]);
+        assert_eq!(
+            Some(3),
+            compute_lod_levels(&mut buffer, LodSampling::Spacing { spacing: 1.0 }, 8)
+        );
+        assert_eq!(vec![0, 3, 1, 0], levels_of(&buffer));
+    }
+
+    #[test]
+    fn test_lod_levels_of_empty_buffer() {
+        let mut buffer = buffer_of(&[]);
+        assert_eq!(
+            None,
+            compute_lod_levels(&mut buffer, LodSampling::Spacing { spacing: 1.0 }, 8)
+        );
+    }
+}
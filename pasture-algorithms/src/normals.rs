@@ -0,0 +1,425 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+};
+
+use pasture_core::{
+    containers::{PointBuffer, PointBufferExt, PointBufferWriteable, PointBufferWriteableExt},
+    layout::{
+        attributes::{NORMAL, POSITION_3D, SENSOR_POSITION},
+        PointAttributeDataType,
+    },
+    nalgebra::Vector3,
+};
+
+use crate::{
+    kdtree::DistanceMode,
+    knn::{nearest_neighbors_of_all_points, NeighborSearch},
+};
+
+/// An edge of the Riemannian graph during the propagation of the orientation in [orient_normals_mst]. Ordered by
+/// weight, so that a `BinaryHeap` of `Reverse<PropagationEdge>` has the cheapest edge on top
+#[derive(Debug, Clone, Copy)]
+struct PropagationEdge {
+    weight: f64,
+    target: usize,
+}
+
+impl PartialEq for PropagationEdge {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for PropagationEdge {}
+
+impl PartialOrd for PropagationEdge {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PropagationEdge {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.weight
+            .partial_cmp(&other.weight)
+            .unwrap_or(Ordering::Equal)
+            .then(self.target.cmp(&other.target))
+    }
+}
+
+/// Returns the positions of all points in `buffer` with double precision
+pub(crate) fn positions_of<T: PointBuffer + ?Sized>(buffer: &T) -> Vec<Vector3<f64>> {
+    let position_attribute = match buffer
+        .point_layout()
+        .get_attribute_by_name(POSITION_3D.name())
+    {
+        Some(a) => a,
+        None => panic!("point buffer contains no position attribute"),
+    };
+    if position_attribute.datatype() == POSITION_3D.datatype() {
+        buffer
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .collect()
+    } else {
+        buffer
+            .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+            .collect()
+    }
+}
+
+/// Returns the normals of all points in `buffer` with double precision
+pub(crate) fn normals_of<T: PointBuffer + ?Sized>(buffer: &T) -> Vec<Vector3<f64>> {
+    match normal_datatype(buffer) {
+        PointAttributeDataType::Vec3f32 => buffer
+            .iter_attribute::<Vector3<f32>>(&NORMAL)
+            .map(|normal| Vector3::new(normal.x as f64, normal.y as f64, normal.z as f64))
+            .collect(),
+        _ => buffer
+            .iter_attribute::<Vector3<f64>>(
+                &NORMAL.with_custom_datatype(PointAttributeDataType::Vec3f64),
+            )
+            .collect(),
+    }
+}
+
+fn normal_datatype<T: PointBuffer + ?Sized>(buffer: &T) -> PointAttributeDataType {
+    match buffer
+        .point_layout()
+        .get_attribute_by_name(NORMAL.name())
+        .map(|attribute| attribute.datatype())
+    {
+        Some(datatype @ PointAttributeDataType::Vec3f32)
+        | Some(datatype @ PointAttributeDataType::Vec3f64) => datatype,
+        _ => panic!("point buffer contains no normal attribute with datatype Vec3f32 or Vec3f64"),
+    }
+}
+
+/// Flips the normals of all points for which `flip` is `true` and returns the number of flipped normals
+fn flip_normals<T: PointBufferWriteable + ?Sized>(buffer: &mut T, flip: &[bool]) -> usize {
+    let datatype = normal_datatype(buffer);
+    let mut flipped = 0;
+    for (index, _) in flip.iter().enumerate().filter(|(_, flip)| **flip) {
+        match datatype {
+            PointAttributeDataType::Vec3f32 => {
+                let normal = buffer.get_attribute::<Vector3<f32>>(&NORMAL, index);
+                buffer.set_attribute(&NORMAL, index, -normal);
+            }
+            _ => {
+                let attribute = NORMAL.with_custom_datatype(PointAttributeDataType::Vec3f64);
+                let normal = buffer.get_attribute::<Vector3<f64>>(&attribute, index);
+                buffer.set_attribute(&attribute, index, -normal);
+            }
+        }
+        flipped += 1;
+    }
+    flipped
+}
+
+/// Orients the normals of all points in `buffer` towards the position of the sensor that captured each point, i.e. the
+/// normal of a point is flipped if it points away from its sensor position. `sensor_position` is called with the
+/// index of each point and returns the position of the sensor at the time the point was captured, e.g. from a
+/// trajectory. Returns the number of flipped normals
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute or a `NORMAL` attribute with the datatype
+/// `Vec3f32` or `Vec3f64`.
+pub fn orient_normals_towards_sensor<T, F>(buffer: &mut T, sensor_position: F) -> usize
+where
+    T: PointBufferWriteable + ?Sized,
+    F: Fn(usize) -> Vector3<f64>,
+{
+    let positions = positions_of(buffer);
+    let normals = normals_of(buffer);
+    let flip = positions
+        .iter()
+        .zip(normals.iter())
+        .enumerate()
+        .map(|(index, (position, normal))| normal.dot(&(sensor_position(index) - position)) < 0.0)
+        .collect::<Vec<_>>();
+    flip_normals(buffer, &flip)
+}
+
+/// Orients the normals of all points in `buffer` towards the position of the sensor in the `SENSOR_POSITION` attribute
+/// of each point, e.g. after joining a trajectory to the points. Returns the number of flipped normals
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute, a `SENSOR_POSITION` attribute with the
+/// datatype `Vec3f64` or a `NORMAL` attribute with the datatype `Vec3f32` or `Vec3f64`.
+pub fn orient_normals_towards_sensor_positions<T: PointBufferWriteable + ?Sized>(
+    buffer: &mut T,
+) -> usize {
+    let sensor_positions = buffer
+        .iter_attribute::<Vector3<f64>>(&SENSOR_POSITION)
+        .collect::<Vec<_>>();
+    orient_normals_towards_sensor(buffer, |index| sensor_positions[index])
+}
+
+/// Orients the normals of all points in `buffer` towards a single `viewpoint`, like the position of a terrestrial
+/// scanner. Returns the number of flipped normals
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::{PerAttributeVecPointStorage, PointBufferExt};
+/// # use pasture_core::layout::{attributes::NORMAL, PointType};
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::normals::orient_normals_towards_viewpoint;
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct PointWithNormal {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+///     #[pasture(BUILTIN_NORMAL)]
+///     pub normal: Vector3<f32>,
+/// }
+/// let points = vec![
+///     PointWithNormal{ position: Vector3::new(0.0, 0.0, 0.0), normal: Vector3::new(0.0, 0.0, 1.0) },
+///     PointWithNormal{ position: Vector3::new(1.0, 0.0, 0.0), normal: Vector3::new(0.0, 0.0, -1.0) },
+/// ];
+/// let mut buffer = PerAttributeVecPointStorage::new(PointWithNormal::layout());
+/// buffer.push_points(&points);
+///
+/// assert_eq!(1, orient_normals_towards_viewpoint(&mut buffer, &Vector3::new(0.0, 0.0, 10.0)));
+/// assert_eq!(Vector3::new(0.0, 0.0, 1.0), buffer.get_attribute::<Vector3<f32>>(&NORMAL, 1));
+/// ```
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute or a `NORMAL` attribute with the datatype
+/// `Vec3f32` or `Vec3f64`.
+pub fn orient_normals_towards_viewpoint<T: PointBufferWriteable + ?Sized>(
+    buffer: &mut T,
+    viewpoint: &Vector3<f64>,
+) -> usize {
+    orient_normals_towards_sensor(buffer, |_| *viewpoint)
+}
+
+/// Orients the (unoriented) normals of all points in `buffer` consistently by propagating the orientation along a
+/// minimum spanning tree of the `k`-nearest neighbor graph, as described by Hoppe et al. in "Surface reconstruction
+/// from unorganized points". The edges of the graph are weighted with `1 - |n_i · n_j|`, so the orientation is
+/// propagated between points with almost parallel normals first, which avoids flipping across sharp edges. The
+/// propagation starts at the highest point, whose normal is oriented upwards (towards +Z). If the neighbor graph has
+/// more than one connected component, each component is seeded at its highest point. Returns the number of flipped
+/// normals
+///
+/// # Panics
+///
+/// If `k` is zero, or if the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute or a `NORMAL`
+/// attribute with the datatype `Vec3f32` or `Vec3f64`.
+pub fn orient_normals_mst<T: PointBufferWriteable + ?Sized>(buffer: &mut T, k: usize) -> usize {
+    orient_normals_mst_with_search(buffer, k, NeighborSearch::Exact)
+}
+
+/// Like [orient_normals_mst], but finds the `k` nearest neighbors with the given `search`. On massive point clouds,
+/// approximate neighbors are usually good enough to propagate the orientation
+///
+/// # Panics
+///
+/// If `k` is zero, or if the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute or a `NORMAL`
+/// attribute with the datatype `Vec3f32` or `Vec3f64`.
+pub fn orient_normals_mst_with_search<T: PointBufferWriteable + ?Sized>(
+    buffer: &mut T,
+    k: usize,
+    search: NeighborSearch,
+) -> usize {
+    if k == 0 {
+        panic!("orient_normals_mst: k must be > 0");
+    }
+    let positions = positions_of(buffer);
+    let mut normals = normals_of(buffer);
+    let point_count = positions.len();
+    if point_count == 0 {
+        return 0;
+    }
+    let k = k.min(point_count - 1);
+
+    let mut seeds = (0..point_count).collect::<Vec<_>>();
+    seeds.sort_by(|a, b| {
+        positions[*b]
+            .z
+            .partial_cmp(&positions[*a].z)
+            .unwrap_or(Ordering::Equal)
+    });
+
+    // The k-nearest neighbor relation is not symmetric, but the orientation has to be propagated along both directions
+    // of each edge
+    let mut neighbors = vec![vec![]; point_count];
+    let nearest_neighbors =
+        nearest_neighbors_of_all_points(positions, k, DistanceMode::Xyz, search);
+    for (index, candidates) in nearest_neighbors.into_iter().enumerate() {
+        for candidate in candidates {
+            neighbors[index].push(candidate.index);
+            neighbors[candidate.index].push(index);
+        }
+    }
+
+    let mut flip = vec![false; point_count];
+    let mut visited = vec![false; point_count];
+    let mut best_weight = vec![f64::INFINITY; point_count];
+    let mut best_parent = vec![usize::MAX; point_count];
+    let mut heap = BinaryHeap::new();
+    for seed in seeds {
+        if visited[seed] {
+            continue;
+        }
+        if normals[seed].z < 0.0 {
+            normals[seed] = -normals[seed];
+            flip[seed] = true;
+        }
+        heap.push(Reverse(PropagationEdge {
+            weight: 0.0,
+            target: seed,
+        }));
+        // Prim's algorithm, orienting each point relative to its parent in the spanning tree once it is reached
+        while let Some(Reverse(edge)) = heap.pop() {
+            let current = edge.target;
+            if visited[current] {
+                continue;
+            }
+            visited[current] = true;
+            let parent = best_parent[current];
+            if parent != usize::MAX && normals[parent].dot(&normals[current]) < 0.0 {
+                normals[current] = -normals[current];
+                flip[current] = !flip[current];
+            }
+
+            for neighbor in neighbors[current].iter().copied() {
+                if visited[neighbor] {
+                    continue;
+                }
+                let weight = 1.0 - normals[current].dot(&normals[neighbor]).abs();
+                if weight < best_weight[neighbor] {
+                    best_weight[neighbor] = weight;
+                    best_parent[neighbor] = current;
+                    heap.push(Reverse(PropagationEdge {
+                        weight,
+                        target: neighbor,
+                    }));
+                }
+            }
+        }
+    }
+
+    flip_normals(buffer, &flip)
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::PerAttributeVecPointStorage,
+        layout::{PointLayout, PointType},
+    };
+    use pasture_derive::PointType;
+    use rand::{prelude::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct PointWithNormal {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_NORMAL)]
+        pub normal: Vector3<f32>,
+    }
+
+    /// Points on the unit sphere (Fibonacci lattice) with their outward normals, of which roughly half are flipped
+    fn sphere_points(count: usize) -> PerAttributeVecPointStorage {
+        let mut rng = StdRng::seed_from_u64(42);
+        let golden_angle = std::f64::consts::PI * (3.0 - 5.0_f64.sqrt());
+        let mut buffer = PerAttributeVecPointStorage::new(PointWithNormal::layout());
+        for index in 0..count {
+            let z = 1.0 - 2.0 * (index as f64 + 0.5) / count as f64;
+            let radius = (1.0 - z * z).sqrt();
+            let angle = golden_angle * index as f64;
+            let position = Vector3::new(radius * angle.cos(), radius * angle.sin(), z);
+            let sign = if rng.gen_bool(0.5) { -1.0 } else { 1.0 };
+            buffer.push_point(PointWithNormal {
+                position,
+                normal: Vector3::new(position.x as f32, position.y as f32, position.z as f32)
+                    * sign,
+            });
+        }
+        buffer
+    }
+
+    fn assert_normals_point_outwards(buffer: &PerAttributeVecPointStorage) {
+        for (position, normal) in buffer
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .zip(buffer.iter_attribute::<Vector3<f32>>(&NORMAL))
+        {
+            let normal = Vector3::new(normal.x as f64, normal.y as f64, normal.z as f64);
+            assert!(normal.dot(&position) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_orient_normals_mst() {
+        let mut buffer = sphere_points(500);
+        let flipped_before = normals_of(&buffer)
+            .iter()
+            .zip(positions_of(&buffer).iter())
+            .filter(|(normal, position)| normal.dot(position) < 0.0)
+            .count();
+
+        assert_eq!(flipped_before, orient_normals_mst(&mut buffer, 10));
+        assert_normals_point_outwards(&buffer);
+        // Orienting again doesn't change anything
+        assert_eq!(0, orient_normals_mst(&mut buffer, 10));
+
+        // Approximate neighbors are good enough to orient the normals of the sphere
+        let mut buffer = sphere_points(500);
+        let search = NeighborSearch::Approximate(Default::default());
+        orient_normals_mst_with_search(&mut buffer, 10, search);
+        assert_normals_point_outwards(&buffer);
+    }
+
+    #[test]
+    fn test_orient_normals_towards_sensor() {
+        let mut buffer = sphere_points(100);
+        // Sensors far outside of the sphere, in the direction of each point
+        let positions = positions_of(&buffer);
+        orient_normals_towards_sensor(&mut buffer, |index| positions[index] * 10.0);
+        assert_normals_point_outwards(&buffer);
+
+        // The same sensor positions from the SENSOR_POSITION attribute
+        let mut points_with_sensor_positions =
+            PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[
+                POSITION_3D,
+                NORMAL,
+                SENSOR_POSITION,
+            ]));
+        points_with_sensor_positions.resize(positions.len());
+        for (index, position) in positions.iter().enumerate() {
+            points_with_sensor_positions.set_attribute(&POSITION_3D, index, *position);
+            points_with_sensor_positions.set_attribute(
+                &NORMAL,
+                index,
+                -Vector3::new(position.x as f32, position.y as f32, position.z as f32),
+            );
+            points_with_sensor_positions.set_attribute(&SENSOR_POSITION, index, position * 10.0);
+        }
+        assert_eq!(
+            positions.len(),
+            orient_normals_towards_sensor_positions(&mut points_with_sensor_positions)
+        );
+
+        // A viewpoint in the center of the sphere orients all normals inwards
+        assert_eq!(
+            100,
+            orient_normals_towards_viewpoint(&mut buffer, &Vector3::zeros())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_orient_normals_without_normals() {
+        let mut buffer =
+            PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[POSITION_3D]));
+        orient_normals_towards_viewpoint(&mut buffer, &Vector3::zeros());
+    }
+}
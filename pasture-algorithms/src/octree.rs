@@ -0,0 +1,476 @@
+use std::{cmp::Reverse, collections::BinaryHeap, ops::Range};
+
+use anyhow::Result;
+use pasture_core::{
+    containers::{
+        PointBuffer, PointBufferExt, PointBufferWriteable, SpatialQuery, SpatialQueryable,
+    },
+    layout::{attributes::POSITION_3D, PointLayout},
+    math::AABB,
+    nalgebra::Vector3,
+};
+
+use crate::{
+    kdtree::{push_points_at_indices, Candidate},
+    quadtree::distance_squared_to_bounds,
+};
+
+/// Default maximum number of points in a leaf node of an [Octree]
+pub const DEFAULT_MAX_POINTS_PER_NODE: usize = 64;
+
+/// Maximum depth of an [Octree]. Nodes at this depth are not split any further, even if they contain more points than
+/// allowed, which happens if many points share the same position
+const MAX_DEPTH: usize = 32;
+
+/// A node of an [Octree], which covers a range of the point order of the tree
+#[derive(Debug, Clone)]
+struct Node {
+    /// Tight bounds of all points of this node
+    bounds: AABB<f64>,
+    /// Range of this node within the point order of the tree
+    points: Range<usize>,
+    /// Range of the non-empty children of this node within the nodes of the tree. Empty for leaf nodes
+    children: Range<usize>,
+}
+
+/// An octree over a set of positions. Nodes are split into eight cubic octants until they contain at most
+/// `max_points_per_node` points. Each node stores the tight bounds of its points, so that queries are pruned as early
+/// as possible. Compared to a [KdTree](crate::kdtree::KdTree), the split positions don't depend on the distribution of
+/// the points, which makes the nodes of an octree line up with the nodes of the octree of a COPC or EPT dataset
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::math::AABB;
+/// # use pasture_core::nalgebra::{Point3, Vector3};
+/// # use pasture_algorithms::octree::Octree;
+/// let tree = Octree::new(vec![
+///     Vector3::new(0.0, 0.0, 0.0),
+///     Vector3::new(5.0, 0.1, 1.0),
+///     Vector3::new(10.0, 0.0, 0.0),
+///     Vector3::new(5.0, 3.0, 2.0),
+/// ]);
+/// let bounds = AABB::from_min_max(Point3::new(4.0, 0.0, 0.0), Point3::new(6.0, 5.0, 1.5));
+/// assert_eq!(vec![1], tree.points_in_bounds(&bounds));
+/// assert_eq!(vec![(3, 2.0)], tree.nearest_neighbors(&Vector3::new(5.0, 5.0, 2.0), 1));
+/// ```
+pub struct Octree {
+    positions: Vec<Vector3<f64>>,
+    order: Vec<usize>,
+    nodes: Vec<Node>,
+    max_points_per_node: usize,
+}
+
+impl Octree {
+    /// Builds an octree over the given `positions` with at most [DEFAULT_MAX_POINTS_PER_NODE] points per leaf node.
+    /// The points are identified by their index within `positions`
+    pub fn new(positions: Vec<Vector3<f64>>) -> Self {
+        Self::with_max_points_per_node(positions, DEFAULT_MAX_POINTS_PER_NODE)
+    }
+
+    /// Builds an octree over the given `positions` with at most `max_points_per_node` points per leaf node. The
+    /// points are identified by their index within `positions`
+    ///
+    /// # Panics
+    ///
+    /// If `max_points_per_node` is zero
+    pub fn with_max_points_per_node(
+        positions: Vec<Vector3<f64>>,
+        max_points_per_node: usize,
+    ) -> Self {
+        if max_points_per_node == 0 {
+            panic!(
+                "Octree::with_max_points_per_node: max_points_per_node must be greater than zero"
+            );
+        }
+        let mut tree = Self {
+            order: (0..positions.len()).collect(),
+            positions,
+            nodes: vec![],
+            max_points_per_node,
+        };
+        if !tree.positions.is_empty() {
+            let root = tree.make_node(0..tree.positions.len());
+            let cube = root.bounds.as_cubic();
+            tree.nodes.push(root);
+            tree.build(0, cube.min().coords, cube.extent().x, 0);
+        }
+        tree
+    }
+
+    /// Builds an octree over the positions of all points in `buffer`. The points are identified by their index within
+    /// `buffer`
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+    pub fn from_buffer<T: PointBuffer + ?Sized>(buffer: &T) -> Self {
+        if !buffer
+            .point_layout()
+            .has_attribute_with_name(POSITION_3D.name())
+        {
+            panic!("point buffer contains no position attribute");
+        }
+        Self::new(
+            buffer
+                .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+                .collect(),
+        )
+    }
+
+    /// Returns the number of points in this tree
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Returns true if this tree contains no points
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Returns the positions of all points in this tree, in their original order
+    pub fn positions(&self) -> &[Vector3<f64>] {
+        &self.positions
+    }
+
+    /// Returns the maximum number of points in a leaf node of this tree
+    pub fn max_points_per_node(&self) -> usize {
+        self.max_points_per_node
+    }
+
+    /// Returns the tight bounds of all points in this tree, or `None` if the tree is empty
+    pub fn bounds(&self) -> Option<AABB<f64>> {
+        self.nodes.first().map(|root| root.bounds)
+    }
+
+    /// Returns the number of nodes of this tree, including the root node
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn make_node(&self, points: Range<usize>) -> Node {
+        let positions = &self.positions;
+        let first = positions[self.order[points.start]];
+        let (min, max) = self.order[points.clone()]
+            .iter()
+            .fold((first, first), |(min, max), index| {
+                (min.inf(&positions[*index]), max.sup(&positions[*index]))
+            });
+        Node {
+            bounds: AABB::from_min_max_unchecked(min.into(), max.into()),
+            children: 0..0,
+            points,
+        }
+    }
+
+    fn build(&mut self, node: usize, cell_min: Vector3<f64>, cell_size: f64, depth: usize) {
+        let points = self.nodes[node].points.clone();
+        if points.len() <= self.max_points_per_node || depth >= MAX_DEPTH {
+            return;
+        }
+
+        let half_size = cell_size / 2.0;
+        let center = cell_min + Vector3::new(half_size, half_size, half_size);
+        let positions = &self.positions;
+        let octant = |index: &usize| {
+            let position = &positions[*index];
+            ((position.x >= center.x) as usize)
+                | (((position.y >= center.y) as usize) << 1)
+                | (((position.z >= center.z) as usize) << 2)
+        };
+        // A stable sort keeps the indices within each octant in ascending order
+        self.order[points.clone()].sort_by_key(octant);
+
+        let first_child = self.nodes.len();
+        let mut child_cells = vec![];
+        let mut child_start = points.start;
+        for child_octant in 0..8 {
+            let child_end = child_start
+                + self.order[child_start..points.end]
+                    .iter()
+                    .take_while(|index| octant(*index) == child_octant)
+                    .count();
+            if child_end > child_start {
+                let child_min = cell_min
+                    + Vector3::new(
+                        (child_octant & 1) as f64,
+                        ((child_octant >> 1) & 1) as f64,
+                        (child_octant >> 2) as f64,
+                    ) * half_size;
+                child_cells.push(child_min);
+                let child = self.make_node(child_start..child_end);
+                self.nodes.push(child);
+            }
+            child_start = child_end;
+        }
+        self.nodes[node].children = first_child..self.nodes.len();
+
+        for (child, child_min) in (first_child..self.nodes.len()).zip(child_cells) {
+            self.build(child, child_min, half_size, depth + 1);
+        }
+    }
+
+    /// Returns the indices of all points inside of `bounds` in ascending order
+    pub fn points_in_bounds(&self, bounds: &AABB<f64>) -> Vec<usize> {
+        let mut indices = vec![];
+        if !self.is_empty() {
+            self.search_bounds(0, bounds, &mut indices);
+        }
+        indices.sort_unstable();
+        indices
+    }
+
+    fn search_bounds(&self, node: usize, bounds: &AABB<f64>, indices: &mut Vec<usize>) {
+        let node = &self.nodes[node];
+        if !node.bounds.intersects(bounds) {
+            return;
+        }
+        if bounds.contains(node.bounds.min()) && bounds.contains(node.bounds.max()) {
+            indices.extend_from_slice(&self.order[node.points.clone()]);
+            return;
+        }
+        if node.children.is_empty() {
+            indices.extend(
+                self.order[node.points.clone()]
+                    .iter()
+                    .filter(|index| bounds.contains(&self.positions[**index].into())),
+            );
+            return;
+        }
+        for child in node.children.clone() {
+            self.search_bounds(child, bounds, indices);
+        }
+    }
+
+    /// Returns the indices of the `k` points that are closest to `position`, together with their distances to
+    /// `position`, sorted by ascending distance. Nodes are visited in the order of their distance to `position`
+    pub fn nearest_neighbors(&self, position: &Vector3<f64>, k: usize) -> Vec<(usize, f64)> {
+        if self.is_empty() || k == 0 {
+            return vec![];
+        }
+        let mut candidates = BinaryHeap::with_capacity(k + 1);
+        let mut nodes = BinaryHeap::new();
+        nodes.push(Reverse(Candidate {
+            distance_squared: distance_squared_to_bounds(position, &self.nodes[0].bounds),
+            index: 0,
+        }));
+        while let Some(Reverse(node_candidate)) = nodes.pop() {
+            if candidates.len() == k
+                && node_candidate.distance_squared > candidates.peek().unwrap().distance_squared
+            {
+                break;
+            }
+            let node = &self.nodes[node_candidate.index];
+            if node.children.is_empty() {
+                for index in &self.order[node.points.clone()] {
+                    let candidate = Candidate {
+                        distance_squared: (self.positions[*index] - position).norm_squared(),
+                        index: *index,
+                    };
+                    if candidates.len() < k {
+                        candidates.push(candidate);
+                    } else if candidate < *candidates.peek().unwrap() {
+                        candidates.pop();
+                        candidates.push(candidate);
+                    }
+                }
+            } else {
+                nodes.extend(node.children.clone().map(|child| {
+                    Reverse(Candidate {
+                        distance_squared: distance_squared_to_bounds(
+                            position,
+                            &self.nodes[child].bounds,
+                        ),
+                        index: child,
+                    })
+                }));
+            }
+        }
+        candidates
+            .into_sorted_vec()
+            .into_iter()
+            .map(|candidate| (candidate.index, candidate.distance_squared.sqrt()))
+            .collect()
+    }
+}
+
+/// A point buffer together with an [Octree] over its positions, which answers [SpatialQuery]s on the points of the
+/// buffer, like the kd-tree based [IndexedBuffer](crate::kdtree::IndexedBuffer). Points are always returned at full
+/// resolution
+pub struct OctreeIndexedBuffer<'a, T: PointBuffer + ?Sized> {
+    buffer: &'a T,
+    tree: Octree,
+}
+
+impl<'a, T: PointBuffer + ?Sized> OctreeIndexedBuffer<'a, T> {
+    /// Builds an [Octree] over the positions of all points in `buffer`
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+    pub fn new(buffer: &'a T) -> Self {
+        Self {
+            buffer,
+            tree: Octree::from_buffer(buffer),
+        }
+    }
+
+    /// Returns the indexed buffer
+    pub fn buffer(&self) -> &'a T {
+        self.buffer
+    }
+
+    /// Returns the [Octree] over the positions of the buffer
+    pub fn tree(&self) -> &Octree {
+        &self.tree
+    }
+
+    /// Returns the indices of all points in the buffer that match `query`. The indices are in ascending order, except
+    /// for `Nearest` queries, where they are sorted by ascending distance
+    pub fn query_indices(&self, query: &SpatialQuery) -> Vec<usize> {
+        match query {
+            SpatialQuery::Nearest { position, k } => self
+                .tree
+                .nearest_neighbors(position, *k)
+                .into_iter()
+                .map(|(index, _)| index)
+                .collect(),
+            _ => match query.search_bounds() {
+                Some(search_bounds) => self
+                    .tree
+                    .points_in_bounds(&search_bounds)
+                    .into_iter()
+                    .filter(|index| query.contains(&self.tree.positions()[*index]))
+                    .collect(),
+                None => vec![],
+            },
+        }
+    }
+}
+
+impl<'a, T: PointBuffer + ?Sized> SpatialQueryable for OctreeIndexedBuffer<'a, T> {
+    fn point_layout(&self) -> &PointLayout {
+        self.buffer.point_layout()
+    }
+
+    fn query(
+        &mut self,
+        query: &SpatialQuery,
+        _resolution: Option<f64>,
+        points: &mut dyn PointBufferWriteable,
+    ) -> Result<usize> {
+        let indices = self.query_indices(query);
+        push_points_at_indices(self.buffer, &indices, points);
+        Ok(indices.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::InterleavedVecPointStorage,
+        layout::PointType,
+        nalgebra::{Point3, Vector2},
+    };
+    use pasture_derive::PointType;
+    use rand::{prelude::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::kdtree::KdTree;
+
+    #[repr(C)]
+    #[derive(PointType, Debug, Copy, Clone)]
+    struct TestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        position: Vector3<f64>,
+    }
+
+    fn random_positions(count: usize) -> Vec<Vector3<f64>> {
+        let mut rng = StdRng::seed_from_u64(8765);
+        (0..count)
+            .map(|_| {
+                Vector3::new(
+                    rng.gen_range(-50.0..50.0),
+                    rng.gen_range(-20.0..20.0),
+                    rng.gen_range(0.0..30.0),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_spatial_queries_match_brute_force() -> Result<()> {
+        let positions = random_positions(3000);
+        let buffer = positions
+            .iter()
+            .map(|position| TestPoint {
+                position: *position,
+            })
+            .collect::<InterleavedVecPointStorage>();
+        let mut indexed = OctreeIndexedBuffer::new(&buffer);
+        assert!(indexed.tree().node_count() > 8);
+
+        let bounds =
+            AABB::from_min_max(Point3::new(-30.0, 5.0, 10.0), Point3::new(20.0, 15.0, 12.0));
+        let expected = positions
+            .iter()
+            .enumerate()
+            .filter(|(_, position)| bounds.contains(&(**position).into()))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+        assert!(!expected.is_empty());
+        assert_eq!(expected, indexed.tree().points_in_bounds(&bounds));
+
+        let polygon = SpatialQuery::Polygon(vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(40.0, 0.0),
+            Vector2::new(0.0, 15.0),
+        ]);
+        let expected = positions
+            .iter()
+            .filter(|position| polygon.contains(position))
+            .copied()
+            .collect::<Vec<_>>();
+        let mut result = InterleavedVecPointStorage::new(TestPoint::layout());
+        assert_eq!(expected.len(), indexed.query(&polygon, None, &mut result)?);
+        assert_eq!(
+            expected,
+            result
+                .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                .collect::<Vec<_>>()
+        );
+
+        let kdtree = KdTree::new(positions);
+        for position in &[Vector3::zeros(), Vector3::new(45.0, -18.0, 40.0)] {
+            assert_eq!(
+                kdtree.nearest_neighbors(position, 7),
+                indexed.tree().nearest_neighbors(position, 7)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_octree_with_duplicate_positions() {
+        let mut positions = vec![Vector3::new(1.0, 2.0, 3.0); 100];
+        positions.push(Vector3::new(1.0, 2.0, 5.0));
+        let tree = Octree::with_max_points_per_node(positions, 4);
+
+        let bounds = AABB::from_min_max(Point3::new(0.0, 0.0, 0.0), Point3::new(2.0, 4.0, 4.0));
+        assert_eq!((0..100).collect::<Vec<_>>(), tree.points_in_bounds(&bounds));
+        assert_eq!(
+            vec![(100, 0.0)],
+            tree.nearest_neighbors(&Vector3::new(1.0, 2.0, 5.0), 1)
+        );
+    }
+
+    #[test]
+    fn test_queries_on_empty_tree() {
+        let tree = Octree::new(vec![]);
+        assert!(tree.is_empty());
+        assert_eq!(None, tree.bounds());
+        assert!(tree.nearest_neighbors(&Vector3::zeros(), 3).is_empty());
+        let bounds = AABB::from_min_max(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        assert!(tree.points_in_bounds(&bounds).is_empty());
+    }
+}
@@ -0,0 +1,327 @@
+use pasture_core::{
+    containers::{PointBufferExt, PointBufferWriteable, PointBufferWriteableExt},
+    layout::{
+        attributes::{INCIDENCE_ANGLE, INTENSITY, RANGE, SENSOR_POSITION},
+        PointAttributeDataType, PointAttributeDefinition,
+    },
+    nalgebra::Vector3,
+};
+
+use crate::normals::{normals_of, positions_of};
+
+/// Attribute for the calibrated reflectance that [calibrate_intensity] calculates from the intensity of a point
+pub const REFLECTANCE: PointAttributeDefinition =
+    PointAttributeDefinition::custom("Reflectance", PointAttributeDataType::F32);
+
+/// How the intensity of a point depends on the incidence angle of the laser beam, as used by [calibrate_intensity]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IncidenceAngleModel {
+    /// The intensity doesn't depend on the incidence angle, e.g. if there are no normals
+    None,
+    /// Lambertian reflection, where the intensity is proportional to the cosine of the incidence angle
+    Lambertian,
+}
+
+/// Parameters of the radiometric calibration in [calibrate_intensity]. The calibrated reflectance of a point with the
+/// intensity `I`, the range `R` and the incidence angle `a` is
+/// `I * (R / reference_range)^range_exponent * 10^(2 * R * atmospheric_attenuation / 10000) / f(a)`, where `f` is
+/// given by the `incidence_angle_model`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IntensityCalibration {
+    /// The range (in meters) at which the reflectance equals the intensity, typically the mean flight height
+    pub reference_range: f64,
+    /// Exponent of the range correction. 2 for extended targets that are larger than the laser footprint (the default),
+    /// lower values for small targets like leaves or power lines
+    pub range_exponent: f64,
+    /// Two-way atmospheric attenuation in dB per kilometer, zero by default
+    pub atmospheric_attenuation: f64,
+    /// Dependency of the intensity on the incidence angle, `IncidenceAngleModel::Lambertian` by default
+    pub incidence_angle_model: IncidenceAngleModel,
+    /// Incidence angles (in radians) are clamped to this value, so that grazing angles don't inflate the reflectance.
+    /// 80 degrees by default
+    pub max_incidence_angle: f64,
+}
+
+impl Default for IntensityCalibration {
+    fn default() -> Self {
+        Self {
+            reference_range: 1000.0,
+            range_exponent: 2.0,
+            atmospheric_attenuation: 0.0,
+            incidence_angle_model: IncidenceAngleModel::Lambertian,
+            max_incidence_angle: 80.0_f64.to_radians(),
+        }
+    }
+}
+
+impl IntensityCalibration {
+    /// Returns the calibrated reflectance for the given `intensity`, `range` and `incidence_angle`
+    pub fn reflectance(&self, intensity: f64, range: f64, incidence_angle: f64) -> f64 {
+        let range_correction = (range / self.reference_range).powf(self.range_exponent);
+        let atmospheric_correction =
+            10.0_f64.powf(2.0 * range * self.atmospheric_attenuation / 10_000.0);
+        let incidence_correction = match self.incidence_angle_model {
+            IncidenceAngleModel::None => 1.0,
+            IncidenceAngleModel::Lambertian => {
+                incidence_angle.abs().min(self.max_incidence_angle).cos()
+            }
+        };
+        intensity * range_correction * atmospheric_correction / incidence_correction
+    }
+}
+
+/// Returns the angle (in radians) between the direction from `position` to `sensor_position` and `normal`. The
+/// orientation of `normal` doesn't matter, so the angle is in `[0, PI/2]`. Returns zero if `normal` or the direction has
+/// zero length
+fn incidence_angle(
+    position: &Vector3<f64>,
+    sensor_position: &Vector3<f64>,
+    normal: &Vector3<f64>,
+) -> f64 {
+    let lengths = (sensor_position - position).norm() * normal.norm();
+    if lengths == 0.0 {
+        return 0.0;
+    }
+    let cos_angle = (sensor_position - position).dot(normal).abs() / lengths;
+    cos_angle.min(1.0).acos()
+}
+
+/// Calculates the `RANGE` (distance to the sensor) and the `INCIDENCE_ANGLE` (angle between the laser beam and the
+/// surface normal, in radians) of all points in `buffer` from their `SENSOR_POSITION`, e.g. after joining a trajectory
+/// to the points. Only the attributes that are part of the `PointLayout` of `buffer` are calculated. The incidence angle
+/// requires a `NORMAL` attribute, whose orientation doesn't matter. Both attributes are the inputs for the radiometric
+/// calibration of intensity values
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::{PerAttributeVecPointStorage, PointBufferExt};
+/// # use pasture_core::layout::{attributes, PointType};
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::radiometry::compute_range_and_incidence_angle;
+/// #[repr(C)]
+/// #[derive(PointType, Default)]
+/// struct Point {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+///     #[pasture(BUILTIN_SENSOR_POSITION)]
+///     pub sensor_position: Vector3<f64>,
+///     #[pasture(BUILTIN_NORMAL)]
+///     pub normal: Vector3<f32>,
+///     #[pasture(BUILTIN_RANGE)]
+///     pub range: f32,
+///     #[pasture(BUILTIN_INCIDENCE_ANGLE)]
+///     pub incidence_angle: f32,
+/// }
+/// let mut buffer = PerAttributeVecPointStorage::new(Point::layout());
+/// buffer.push_point(Point {
+///     normal: Vector3::new(0.0, 0.0, 1.0),
+///     sensor_position: Vector3::new(0.0, 0.0, 100.0),
+///     ..Default::default()
+/// });
+///
+/// compute_range_and_incidence_angle(&mut buffer);
+/// assert_eq!(100.0, buffer.get_attribute::<f32>(&attributes::RANGE, 0));
+/// assert_eq!(0.0, buffer.get_attribute::<f32>(&attributes::INCIDENCE_ANGLE, 0));
+/// ```
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` contains neither `RANGE` nor `INCIDENCE_ANGLE` with the datatype `F32`, if it
+/// doesn't contain a `POSITION_3D` attribute or a `SENSOR_POSITION` attribute with the datatype `Vec3f64`, or if it
+/// contains `INCIDENCE_ANGLE` but no `NORMAL` attribute with the datatype `Vec3f32` or `Vec3f64`.
+pub fn compute_range_and_incidence_angle<T: PointBufferWriteable + ?Sized>(buffer: &mut T) {
+    let has_range = buffer.point_layout().has_attribute(&RANGE);
+    let has_incidence_angle = buffer.point_layout().has_attribute(&INCIDENCE_ANGLE);
+    if !has_range && !has_incidence_angle {
+        panic!("point buffer contains neither a range nor an incidence angle attribute");
+    }
+    let positions = positions_of(buffer);
+    let sensor_positions = buffer
+        .iter_attribute::<Vector3<f64>>(&SENSOR_POSITION)
+        .collect::<Vec<_>>();
+
+    if has_range {
+        for (index, (position, sensor_position)) in
+            positions.iter().zip(sensor_positions.iter()).enumerate()
+        {
+            let range = (sensor_position - position).norm();
+            buffer.set_attribute(&RANGE, index, range as f32);
+        }
+    }
+    if has_incidence_angle {
+        let normals = normals_of(buffer);
+        for (index, ((position, sensor_position), normal)) in positions
+            .iter()
+            .zip(sensor_positions.iter())
+            .zip(normals.iter())
+            .enumerate()
+        {
+            let angle = incidence_angle(position, sensor_position, normal);
+            buffer.set_attribute(&INCIDENCE_ANGLE, index, angle as f32);
+        }
+    }
+}
+
+/// Calculates the calibrated [REFLECTANCE] of all points in `buffer` from their `INTENSITY`, `RANGE` and (depending on
+/// the `IncidenceAngleModel`) `INCIDENCE_ANGLE` (see [compute_range_and_incidence_angle]) using the given
+/// `calibration`. Unlike the raw intensities, the reflectance is comparable between flights at different heights and
+/// between points that are hit at different angles, which is required for intensity-based classification
+///
+/// # Panics
+///
+/// If `reference_range` is not strictly positive, if the `PointLayout` of `buffer` doesn't contain the attributes
+/// `INTENSITY`, `RANGE` and [REFLECTANCE] with the datatypes `U16`, `F32` and `F32`, or if it doesn't contain the
+/// `INCIDENCE_ANGLE` attribute with the datatype `F32` although the `IncidenceAngleModel` requires it.
+pub fn calibrate_intensity<T: PointBufferWriteable + ?Sized>(
+    buffer: &mut T,
+    calibration: &IntensityCalibration,
+) {
+    if calibration.reference_range <= 0.0 {
+        panic!("calibrate_intensity: reference_range must be > 0");
+    }
+    if !buffer.point_layout().has_attribute(&REFLECTANCE) {
+        panic!("point buffer contains no reflectance attribute");
+    }
+    let intensities = buffer.iter_attribute::<u16>(&INTENSITY).collect::<Vec<_>>();
+    let ranges = buffer.iter_attribute::<f32>(&RANGE).collect::<Vec<_>>();
+    let incidence_angles = match calibration.incidence_angle_model {
+        IncidenceAngleModel::None => vec![0.0; intensities.len()],
+        _ => buffer
+            .iter_attribute::<f32>(&INCIDENCE_ANGLE)
+            .collect::<Vec<_>>(),
+    };
+
+    for (index, ((intensity, range), incidence_angle)) in intensities
+        .iter()
+        .zip(ranges.iter())
+        .zip(incidence_angles.iter())
+        .enumerate()
+    {
+        let reflectance =
+            calibration.reflectance(*intensity as f64, *range as f64, *incidence_angle as f64);
+        buffer.set_attribute(&REFLECTANCE, index, reflectance as f32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::PerAttributeVecPointStorage,
+        layout::{
+            attributes::{NORMAL, POSITION_3D},
+            PointLayout,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_compute_range_and_incidence_angle() {
+        let mut buffer = PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[
+            POSITION_3D,
+            NORMAL,
+            SENSOR_POSITION,
+            RANGE,
+            INCIDENCE_ANGLE,
+        ]));
+        buffer.resize(3);
+        let sensor_position = Vector3::new(0.0, 0.0, 10.0);
+        for index in 0..3 {
+            buffer.set_attribute(&SENSOR_POSITION, index, sensor_position);
+        }
+        // Point below the sensor, on a horizontal surface
+        buffer.set_attribute(&NORMAL, 0, Vector3::new(0.0_f32, 0.0, 1.0));
+        // Point at 45 degrees, on a horizontal surface with a downwards normal
+        buffer.set_attribute(&POSITION_3D, 1, Vector3::new(10.0, 0.0, 0.0));
+        buffer.set_attribute(&NORMAL, 1, Vector3::new(0.0_f32, 0.0, -1.0));
+        // Point on a vertical wall that is parallel to the beam
+        buffer.set_attribute(&POSITION_3D, 2, Vector3::new(0.0, 5.0, 0.0));
+        buffer.set_attribute(&NORMAL, 2, Vector3::new(1.0_f32, 0.0, 0.0));
+
+        compute_range_and_incidence_angle(&mut buffer);
+
+        let ranges = buffer.iter_attribute::<f32>(&RANGE).collect::<Vec<_>>();
+        let expected_ranges = [10.0, 200.0_f32.sqrt(), 125.0_f32.sqrt()];
+        for (expected, actual) in expected_ranges.iter().zip(ranges.iter()) {
+            assert!((expected - actual).abs() < 1e-5);
+        }
+
+        let angles = buffer
+            .iter_attribute::<f32>(&INCIDENCE_ANGLE)
+            .collect::<Vec<_>>();
+        let expected_angles = [
+            0.0,
+            std::f32::consts::FRAC_PI_4,
+            std::f32::consts::FRAC_PI_2,
+        ];
+        for (expected, actual) in expected_angles.iter().zip(angles.iter()) {
+            assert!((expected - actual).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_calibrate_intensity() {
+        let mut buffer = PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[
+            INTENSITY,
+            RANGE,
+            INCIDENCE_ANGLE,
+            REFLECTANCE,
+        ]));
+        buffer.resize(3);
+        // The same target at the reference range, at twice the reference range and at an incidence angle of 60 degrees
+        buffer.set_attribute(&INTENSITY, 0, 400_u16);
+        buffer.set_attribute(&RANGE, 0, 500.0_f32);
+        buffer.set_attribute(&INTENSITY, 1, 100_u16);
+        buffer.set_attribute(&RANGE, 1, 1000.0_f32);
+        buffer.set_attribute(&INTENSITY, 2, 200_u16);
+        buffer.set_attribute(&RANGE, 2, 500.0_f32);
+        buffer.set_attribute(&INCIDENCE_ANGLE, 2, 60.0_f32.to_radians());
+
+        let calibration = IntensityCalibration {
+            reference_range: 500.0,
+            ..Default::default()
+        };
+        calibrate_intensity(&mut buffer, &calibration);
+        for reflectance in buffer.iter_attribute::<f32>(&REFLECTANCE) {
+            assert!((400.0 - reflectance).abs() < 1e-3);
+        }
+
+        let calibration = IntensityCalibration {
+            reference_range: 500.0,
+            incidence_angle_model: IncidenceAngleModel::None,
+            ..Default::default()
+        };
+        calibrate_intensity(&mut buffer, &calibration);
+        assert_eq!(200.0, buffer.get_attribute::<f32>(&REFLECTANCE, 2));
+    }
+
+    #[test]
+    fn test_reflectance_with_atmospheric_attenuation() {
+        let calibration = IntensityCalibration {
+            reference_range: 1000.0,
+            atmospheric_attenuation: 5.0,
+            incidence_angle_model: IncidenceAngleModel::None,
+            ..Default::default()
+        };
+        // 1 km range and 5 dB/km two-way attenuation is a loss of 10 dB
+        assert!((1000.0 - calibration.reflectance(100.0, 1000.0, 0.0)).abs() < 1e-9);
+        // Grazing angles are clamped
+        let calibration = IntensityCalibration::default();
+        assert_eq!(
+            calibration.reflectance(100.0, 1000.0, 85.0_f64.to_radians()),
+            calibration.reflectance(100.0, 1000.0, 80.0_f64.to_radians())
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_compute_range_and_incidence_angle_without_target_attributes() {
+        let mut buffer = PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[
+            POSITION_3D,
+            SENSOR_POSITION,
+        ]));
+        compute_range_and_incidence_angle(&mut buffer);
+    }
+}
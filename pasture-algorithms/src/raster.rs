@@ -0,0 +1,191 @@
+use pasture_core::{
+    containers::{PointBuffer, PointBufferExt},
+    layout::attributes::POSITION_3D,
+    nalgebra::Vector3,
+};
+
+/// A single-band raster with square or rectangular cells that are aligned with the x- and y-axis, such as a mask or a
+/// digital elevation model (DEM). Row 0 is the northernmost row of the raster, as in most raster formats
+#[derive(Debug, Clone, PartialEq)]
+pub struct Raster {
+    /// x-coordinate of the west edge of the raster
+    pub min_x: f64,
+    /// y-coordinate of the north edge of the raster
+    pub max_y: f64,
+    /// Width of a cell in x-direction
+    pub cell_size_x: f64,
+    /// Height of a cell in y-direction
+    pub cell_size_y: f64,
+    /// Number of columns
+    pub size_x: usize,
+    /// Number of rows
+    pub size_y: usize,
+    /// The values of all cells in row-major order, starting with the northernmost row
+    pub values: Vec<f64>,
+    /// Value of cells that contain no data
+    pub no_data: Option<f64>,
+}
+
+impl Raster {
+    /// Returns the value of the cell that contains the location `(x, y)`, or `None` if the location is outside of
+    /// this raster or if the cell contains no data
+    pub fn value_at(&self, x: f64, y: f64) -> Option<f64> {
+        let column = ((x - self.min_x) / self.cell_size_x).floor();
+        let row = ((self.max_y - y) / self.cell_size_y).floor();
+        if column < 0.0 || row < 0.0 {
+            return None;
+        }
+        let (column, row) = (column as usize, row as usize);
+        if column >= self.size_x || row >= self.size_y {
+            return None;
+        }
+        let value = self.values[row * self.size_x + column];
+        match self.no_data {
+            Some(no_data) if value == no_data => None,
+            _ if value.is_nan() => None,
+            _ => Some(value),
+        }
+    }
+}
+
+fn positions<T: PointBuffer + ?Sized>(buffer: &T) -> Vec<Vector3<f64>> {
+    if !buffer
+        .point_layout()
+        .has_attribute_with_name(POSITION_3D.name())
+    {
+        panic!("point buffer contains no position attribute");
+    }
+    buffer
+        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+        .collect()
+}
+
+/// Tests all points in `buffer` against the raster `mask`. Returns one entry per point, which is `true` if the mask
+/// cell that contains the point has a value other than zero. Points outside of the mask or in cells without data are
+/// not part of the mask
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+pub fn points_in_mask<T: PointBuffer + ?Sized>(buffer: &T, mask: &Raster) -> Vec<bool> {
+    positions(buffer)
+        .iter()
+        .map(|position| {
+            mask.value_at(position.x, position.y)
+                .map(|value| value != 0.0)
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// Calculates the vertical distance of all points in `buffer` to the digital elevation model `dem`, which is positive
+/// for points above the DEM. Returns one entry per point, which is `None` for points outside of the DEM or in cells
+/// without data
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::raster::{height_above_raster, Raster};
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct SimplePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+/// }
+/// // A 2x1 DEM with a height of 10 in the west cell and 20 in the east cell
+/// let dem = Raster {
+///     min_x: 0.0,
+///     max_y: 1.0,
+///     cell_size_x: 1.0,
+///     cell_size_y: 1.0,
+///     size_x: 2,
+///     size_y: 1,
+///     values: vec![10.0, 20.0],
+///     no_data: None,
+/// };
+/// let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// buffer.push_point(SimplePoint{ position: Vector3::new(0.5, 0.5, 12.0) });
+/// buffer.push_point(SimplePoint{ position: Vector3::new(1.5, 0.5, 12.0) });
+/// buffer.push_point(SimplePoint{ position: Vector3::new(2.5, 0.5, 12.0) });
+/// assert_eq!(vec![Some(2.0), Some(-8.0), None], height_above_raster(&buffer, &dem));
+/// ```
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+pub fn height_above_raster<T: PointBuffer + ?Sized>(buffer: &T, dem: &Raster) -> Vec<Option<f64>> {
+    positions(buffer)
+        .iter()
+        .map(|position| {
+            dem.value_at(position.x, position.y)
+                .map(|height| position.z - height)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{containers::PerAttributeVecPointStorage, layout::PointType};
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct SimplePoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+    }
+
+    fn test_mask() -> Raster {
+        // 3x2 mask with 2 meter cells between (10, 20) and (16, 24). The south-east cell contains no data
+        Raster {
+            min_x: 10.0,
+            max_y: 24.0,
+            cell_size_x: 2.0,
+            cell_size_y: 2.0,
+            size_x: 3,
+            size_y: 2,
+            values: vec![1.0, 0.0, 1.0, 0.0, 1.0, -9999.0],
+            no_data: Some(-9999.0),
+        }
+    }
+
+    #[test]
+    fn test_raster_value_at() {
+        let mask = test_mask();
+        assert_eq!(Some(1.0), mask.value_at(10.0, 24.0));
+        assert_eq!(Some(0.0), mask.value_at(13.0, 23.0));
+        assert_eq!(Some(1.0), mask.value_at(13.0, 21.0));
+        assert_eq!(None, mask.value_at(15.0, 21.0));
+        assert_eq!(None, mask.value_at(9.0, 21.0));
+        assert_eq!(None, mask.value_at(11.0, 19.0));
+        assert_eq!(None, mask.value_at(16.5, 23.0));
+    }
+
+    #[test]
+    fn test_points_in_mask() {
+        let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+        for (x, y) in [
+            (11.0, 23.0),
+            (13.0, 23.0),
+            (13.0, 21.0),
+            (15.0, 21.0),
+            (0.0, 0.0),
+        ]
+        .iter()
+        {
+            buffer.push_point(SimplePoint {
+                position: Vector3::new(*x, *y, 0.0),
+            });
+        }
+        assert_eq!(
+            vec![true, false, true, false, false],
+            points_in_mask(&buffer, &test_mask())
+        );
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{anyhow, Result};
+use pasture_core::{
+    containers::{PointBufferExt, PointBufferWriteable, PointBufferWriteableExt},
+    layout::attributes::POSITION_3D,
+    nalgebra::Vector3,
+};
+use proj::Proj;
+
+/// Reprojection of point positions between two coordinate reference systems using PROJ. Only the horizontal
+/// components of the positions are transformed, z-values are kept as they are.
+pub struct Reprojection {
+    proj: Proj,
+}
+
+impl Reprojection {
+    /// Creates a new `Reprojection` from `source_crs` to `target_crs`. Both can be any coordinate reference system
+    /// definition that PROJ understands, e.g. `EPSG:4326` or a WKT string
+    ///
+    /// # Errors
+    ///
+    /// If PROJ can't create a transformation between the two coordinate reference systems
+    pub fn new(source_crs: &str, target_crs: &str) -> Result<Self> {
+        let proj = Proj::new_known_crs(source_crs, target_crs, None).map_err(|e| {
+            anyhow!(
+                "Could not create transformation from {} to {}: {}",
+                source_crs,
+                target_crs,
+                e
+            )
+        })?;
+        Ok(Self { proj })
+    }
+
+    /// Reprojects a single `position`
+    pub fn reproject_position(&self, position: &Vector3<f64>) -> Result<Vector3<f64>> {
+        let (x, y) = self.proj.convert((position.x, position.y))?;
+        Ok(Vector3::new(x, y, position.z))
+    }
+
+    /// Reprojects the `POSITION_3D` attribute of all points in `buffer` in-place
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute with the default datatype `Vector3<f64>`
+    pub fn reproject<T: PointBufferWriteable>(&self, buffer: &mut T) -> Result<()> {
+        if !buffer.point_layout().has_attribute(&POSITION_3D) {
+            panic!(
+                "Reprojection::reproject: PointLayout of buffer does not contain attribute {}",
+                POSITION_3D
+            );
+        }
+        for index in 0..buffer.len() {
+            let position = buffer.get_attribute::<Vector3<f64>>(&POSITION_3D, index);
+            let reprojected = self.reproject_position(&position)?;
+            buffer.set_attribute(&POSITION_3D, index, reprojected);
+        }
+        Ok(())
+    }
+}
+
+/// Returns `true` if `crs` is a geographic coordinate reference system, i.e. one whose coordinates are longitudes and
+/// latitudes in degrees. `crs` can be any coordinate reference system definition that PROJ understands. The type of
+/// the coordinate reference system is taken from the PROJ string that PROJ derives from its definition
+///
+/// # Errors
+///
+/// If PROJ doesn't know `crs`
+pub fn is_geographic_crs(crs: &str) -> Result<bool> {
+    let proj = Proj::new(crs).map_err(|e| {
+        anyhow!(
+            "Could not create coordinate reference system {}: {}",
+            crs,
+            e
+        )
+    })?;
+    let definition = proj.def()?;
+    Ok(definition.split_whitespace().any(|parameter| {
+        matches!(
+            parameter.trim_start_matches('+'),
+            "proj=longlat" | "proj=latlong" | "proj=lonlat" | "proj=latlon"
+        )
+    }))
+}
@@ -1,11 +1,15 @@
 use std::vec;
 
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::{anyhow, Result};
 use pasture_core::{
     containers::{PointBuffer, PointBufferExt},
     layout::attributes::POSITION_3D,
     nalgebra::Vector3,
+    util::Progress,
 };
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use rayon::prelude::*;
 
 /// Represents a line between two points
@@ -45,9 +49,8 @@ fn distance_point_line(point: &Vector3<f64>, line: &Line) -> f64 {
 }
 
 /// generates a random plane from three points of the buffer
-fn generate_rng_plane<T: PointBuffer>(buffer: &T) -> Plane {
+fn generate_rng_plane<T: PointBuffer, R: Rng>(buffer: &T, rng: &mut R) -> Plane {
     // choose three random points from the pointcloud
-    let mut rng = rand::thread_rng();
     let rand1 = rng.gen_range(0..buffer.len());
     let mut rand2 = rng.gen_range(0..buffer.len());
     while rand1 == rand2 {
@@ -77,9 +80,8 @@ fn generate_rng_plane<T: PointBuffer>(buffer: &T) -> Plane {
 }
 
 /// generates a random line from two points of the buffer
-fn generate_rng_line<T: PointBuffer>(buffer: &T) -> Line {
+fn generate_rng_line<T: PointBuffer, R: Rng>(buffer: &T, rng: &mut R) -> Line {
     // choose two random points from the pointcloud
-    let mut rng = rand::thread_rng();
     let rand1 = rng.gen_range(0..buffer.len());
     let mut rand2 = rng.gen_range(0..buffer.len());
     // make sure we have two unique points
@@ -94,9 +96,13 @@ fn generate_rng_line<T: PointBuffer>(buffer: &T) -> Line {
     }
 }
 
-fn generate_line_model<T: PointBuffer>(buffer: &T, distance_threshold: f64) -> (Line, Vec<usize>) {
+fn generate_line_model<T: PointBuffer, R: Rng>(
+    buffer: &T,
+    distance_threshold: f64,
+    rng: &mut R,
+) -> (Line, Vec<usize>) {
     // generate random line from three points in the buffer
-    let mut curr_hypo = generate_rng_line(buffer);
+    let mut curr_hypo = generate_rng_line(buffer, rng);
     let mut curr_positions = vec![];
     // find all points that belong to the line
     for (index, p) in buffer
@@ -114,12 +120,13 @@ fn generate_line_model<T: PointBuffer>(buffer: &T, distance_threshold: f64) -> (
     (curr_hypo, curr_positions)
 }
 
-fn generate_plane_model<T: PointBuffer>(
+fn generate_plane_model<T: PointBuffer, R: Rng>(
     buffer: &T,
     distance_threshold: f64,
+    rng: &mut R,
 ) -> (Plane, Vec<usize>) {
     // generate random plane from three points in the buffer
-    let mut curr_hypo = generate_rng_plane(buffer);
+    let mut curr_hypo = generate_rng_plane(buffer, rng);
     // find all points that belong to the plane
     let mut curr_positions = vec![];
 
@@ -137,6 +144,16 @@ fn generate_plane_model<T: PointBuffer>(
     (curr_hypo, curr_positions)
 }
 
+/// Creates the random number generator for the iteration with index `iteration` of a RANSAC run with the given `seed`.
+/// Every iteration gets its own generator, so the model of an iteration does not depend on which thread runs it or in
+/// which order the iterations are run
+fn iteration_rng(seed: u64, iteration: usize) -> StdRng {
+    let mut rng_seed = <StdRng as SeedableRng>::Seed::default();
+    rng_seed[..8].copy_from_slice(&seed.to_le_bytes());
+    rng_seed[8..16].copy_from_slice(&(iteration as u64).to_le_bytes());
+    StdRng::from_seed(rng_seed)
+}
+
 /// Ransac Plane Segmentation in parallel.
 /// Returns the plane with the highest rating/most inliers and the associated indices of the inliers.
 /// Iterates over all points in the `buffer`.
@@ -182,6 +199,54 @@ pub fn ransac_plane_par<T: PointBuffer + Sync>(
     buffer: &T,
     distance_threshold: f64,
     num_of_iterations: usize,
+) -> (Plane, Vec<usize>) {
+    ransac_plane_par_seeded(buffer, distance_threshold, num_of_iterations, rand::random())
+}
+
+/// Ransac Plane Segmentation in parallel with an explicit `seed` for the random number generation.
+/// Works like [ransac_plane_par], but the result only depends on the `buffer`, the parameters and the `seed`.
+///
+/// # Determinism
+///
+/// Each iteration draws its random points from its own generator that is derived from `seed` and the index of the
+/// iteration, and on equal ranking the model of the earliest iteration wins. The same `seed` therefore yields the same
+/// plane and inliers on every run and platform, independent of the number of threads, as long as the version of the
+/// `rand` crate does not change. The result is also identical to that of [ransac_plane_serial_seeded] with the same
+/// `seed`.
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::segmentation::ransac_plane_par_seeded;
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct SimplePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///    pub position: Vector3<f64>,
+/// }
+/// let mut points = vec![];
+/// for i in 0..200{
+///     points.push(SimplePoint{position: Vector3::new(0.0, f64::from(i), f64::from(i % 7))});
+/// }
+/// let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// buffer.push_points(&points);
+/// let (_, first_inliers) = ransac_plane_par_seeded(&buffer, 0.5, 10, 42);
+/// let (_, second_inliers) = ransac_plane_par_seeded(&buffer, 0.5, 10, 42);
+/// assert_eq!(first_inliers, second_inliers);
+/// ```
+///
+/// # Panics
+///
+/// If the size of the buffer is < 3.
+pub fn ransac_plane_par_seeded<T: PointBuffer + Sync>(
+    buffer: &T,
+    distance_threshold: f64,
+    num_of_iterations: usize,
+    seed: u64,
 ) -> (Plane, Vec<usize>) {
     if buffer.len() < 3 {
         panic!("buffer needs to include at least 3 points to generate a plane.");
@@ -189,12 +254,14 @@ pub fn ransac_plane_par<T: PointBuffer + Sync>(
     // iterate in parallel over num_of_iterations
     (0..num_of_iterations)
         .into_par_iter()
-        .map(|_x| {
+        .map(|iteration| {
             // generate one model for the current iteration
-            generate_plane_model(buffer, distance_threshold)
+            let mut rng = iteration_rng(seed, iteration);
+            (iteration, generate_plane_model(buffer, distance_threshold, &mut rng))
         })
-        // get the best plane-model from all iterations (highest ranking)
-        .max_by(|(x, _y), (a, _b)| x.ranking.cmp(&a.ranking))
+        // get the best plane-model from all iterations (highest ranking, earliest iteration on ties)
+        .max_by(|(i, (x, _y)), (j, (a, _b))| x.ranking.cmp(&a.ranking).then(j.cmp(i)))
+        .map(|(_iteration, model)| model)
         .unwrap()
 }
 
@@ -243,18 +310,36 @@ pub fn ransac_plane_serial<T: PointBuffer>(
     buffer: &T,
     distance_threshold: f64,
     num_of_iterations: usize,
+) -> (Plane, Vec<usize>) {
+    ransac_plane_serial_seeded(buffer, distance_threshold, num_of_iterations, rand::random())
+}
+
+/// Ransac Plane Segmentation in serial with an explicit `seed` for the random number generation.
+/// Works like [ransac_plane_serial] and returns the same result as [ransac_plane_par_seeded] with the same `seed`, see
+/// there for the determinism guarantees.
+///
+/// # Panics
+///
+/// If the size of the buffer is < 3.
+pub fn ransac_plane_serial_seeded<T: PointBuffer>(
+    buffer: &T,
+    distance_threshold: f64,
+    num_of_iterations: usize,
+    seed: u64,
 ) -> (Plane, Vec<usize>) {
     if buffer.len() < 3 {
         panic!("buffer needs to include at least 3 points to generate a plane.");
     }
     (0..num_of_iterations)
         .into_iter()
-        .map(|_x| 
+        .map(|iteration| {
             // generate one model for the current iteration
-            generate_plane_model(buffer, distance_threshold)
-        )
-        // get the best plane-model from all iterations (highest ranking)
-        .max_by(|(x, _y), (a, _b)| x.ranking.cmp(&a.ranking))
+            let mut rng = iteration_rng(seed, iteration);
+            (iteration, generate_plane_model(buffer, distance_threshold, &mut rng))
+        })
+        // get the best plane-model from all iterations (highest ranking, earliest iteration on ties)
+        .max_by(|(i, (x, _y)), (j, (a, _b))| x.ranking.cmp(&a.ranking).then(j.cmp(i)))
+        .map(|(_iteration, model)| model)
         .unwrap()
 }
 
@@ -303,6 +388,23 @@ pub fn ransac_line_par<T: PointBuffer + Sync>(
     buffer: &T,
     distance_threshold: f64,
     num_of_iterations: usize,
+) -> (Line, Vec<usize>) {
+    ransac_line_par_seeded(buffer, distance_threshold, num_of_iterations, rand::random())
+}
+
+/// Ransac Line Segmentation in parallel with an explicit `seed` for the random number generation.
+/// Works like [ransac_line_par], but the same `seed` always yields the same line and inliers. The guarantees are the
+/// same as for [ransac_plane_par_seeded], and the result is identical to that of [ransac_line_serial_seeded] with the
+/// same `seed`.
+///
+/// # Panics
+///
+/// If the size of the buffer is < 2.
+pub fn ransac_line_par_seeded<T: PointBuffer + Sync>(
+    buffer: &T,
+    distance_threshold: f64,
+    num_of_iterations: usize,
+    seed: u64,
 ) -> (Line, Vec<usize>) {
     if buffer.len() < 2 {
         panic!("buffer needs to include at least 2 points to generate a line.");
@@ -310,11 +412,14 @@ pub fn ransac_line_par<T: PointBuffer + Sync>(
     // iterate num_of_iterations in parallel
     (0..num_of_iterations)
         .into_par_iter()
-        .map(|_x| 
+        .map(|iteration| {
             // generate one model for the current iteration
-            generate_line_model(buffer, distance_threshold))
-        // get the best line-model from all iterations (highest ranking)
-        .max_by(|(x, _y), (a, _b)| x.ranking.cmp(&a.ranking))
+            let mut rng = iteration_rng(seed, iteration);
+            (iteration, generate_line_model(buffer, distance_threshold, &mut rng))
+        })
+        // get the best line-model from all iterations (highest ranking, earliest iteration on ties)
+        .max_by(|(i, (x, _y)), (j, (a, _b))| x.ranking.cmp(&a.ranking).then(j.cmp(i)))
+        .map(|(_iteration, model)| model)
         .unwrap()
 }
 
@@ -363,23 +468,126 @@ pub fn ransac_line_serial<T: PointBuffer>(
     buffer: &T,
     distance_threshold: f64,
     num_of_iterations: usize,
+) -> (Line, Vec<usize>) {
+    ransac_line_serial_seeded(buffer, distance_threshold, num_of_iterations, rand::random())
+}
+
+/// Ransac Line Segmentation in serial with an explicit `seed` for the random number generation.
+/// Works like [ransac_line_serial] and returns the same result as [ransac_line_par_seeded] with the same `seed`, see
+/// [ransac_plane_par_seeded] for the determinism guarantees.
+///
+/// # Panics
+///
+/// If the size of the buffer is < 2.
+pub fn ransac_line_serial_seeded<T: PointBuffer>(
+    buffer: &T,
+    distance_threshold: f64,
+    num_of_iterations: usize,
+    seed: u64,
 ) -> (Line, Vec<usize>) {
     if buffer.len() < 2 {
         panic!("buffer needs to include at least 2 points to generate a line.");
     }
 
-    // iterate num_of_iterations in parallel
     (0..num_of_iterations)
         .into_iter()
-        .map(|_x| 
+        .map(|iteration| {
             // generate one model for the current iteration
-            generate_line_model(buffer, distance_threshold))
-        // get the best line-model from all iterations (highest ranking)
-        .max_by(|(x, _y), (a, _b)| x.ranking.cmp(&a.ranking))
+            let mut rng = iteration_rng(seed, iteration);
+            (iteration, generate_line_model(buffer, distance_threshold, &mut rng))
+        })
+        // get the best line-model from all iterations (highest ranking, earliest iteration on ties)
+        .max_by(|(i, (x, _y)), (j, (a, _b))| x.ranking.cmp(&a.ranking).then(j.cmp(i)))
+        .map(|(_iteration, model)| model)
         .unwrap()
 }
 
 
+/// Runs `num_of_iterations` iterations of `generate_model` in parallel and returns the model with the highest ranking.
+/// Reports the number of finished iterations to `progress` and stops early if `progress` is cancelled. `generate_model`
+/// receives the random number generator of the current iteration, derived from `seed`
+fn ransac_par_with_progress<M: Send, F: Fn(&mut StdRng) -> (M, Vec<usize>) + Sync>(
+    num_of_iterations: usize,
+    progress: &Progress,
+    seed: u64,
+    generate_model: F,
+    ranking: fn(&M) -> usize,
+) -> Result<(M, Vec<usize>)> {
+    let finished_iterations = AtomicUsize::new(0);
+    let best_model = (0..num_of_iterations)
+        .into_par_iter()
+        .map(|iteration| {
+            if progress.is_cancelled() {
+                return None;
+            }
+            let model = generate_model(&mut iteration_rng(seed, iteration));
+            let finished = finished_iterations.fetch_add(1, Ordering::Relaxed) + 1;
+            progress.report(finished, Some(num_of_iterations));
+            Some((iteration, model))
+        })
+        .while_some()
+        .max_by(|(i, (x, _y)), (j, (a, _b))| ranking(x).cmp(&ranking(a)).then(j.cmp(i)))
+        .map(|(_iteration, model)| model);
+    progress.check_cancelled()?;
+    best_model.ok_or_else(|| anyhow!("num_of_iterations must be > 0"))
+}
+
+/// Ransac Plane Segmentation in parallel with progress reporting and cancellation.
+/// Works like [ransac_plane_par], but reports the number of finished iterations to `progress`.
+///
+/// # Errors
+///
+/// If `progress` is cancelled before all iterations are finished, a [Cancelled](pasture_core::util::Cancelled) error is returned.
+///
+/// # Panics
+///
+/// If the size of the buffer is < 3.
+pub fn ransac_plane_par_with_progress<T: PointBuffer + Sync>(
+    buffer: &T,
+    distance_threshold: f64,
+    num_of_iterations: usize,
+    progress: &Progress,
+) -> Result<(Plane, Vec<usize>)> {
+    if buffer.len() < 3 {
+        panic!("buffer needs to include at least 3 points to generate a plane.");
+    }
+    ransac_par_with_progress(
+        num_of_iterations,
+        progress,
+        rand::random(),
+        |rng| generate_plane_model(buffer, distance_threshold, rng),
+        |plane| plane.ranking,
+    )
+}
+
+/// Ransac Line Segmentation in parallel with progress reporting and cancellation.
+/// Works like [ransac_line_par], but reports the number of finished iterations to `progress`.
+///
+/// # Errors
+///
+/// If `progress` is cancelled before all iterations are finished, a [Cancelled](pasture_core::util::Cancelled) error is returned.
+///
+/// # Panics
+///
+/// If the size of the buffer is < 2.
+pub fn ransac_line_par_with_progress<T: PointBuffer + Sync>(
+    buffer: &T,
+    distance_threshold: f64,
+    num_of_iterations: usize,
+    progress: &Progress,
+) -> Result<(Line, Vec<usize>)> {
+    if buffer.len() < 2 {
+        panic!("buffer needs to include at least 2 points to generate a line.");
+    }
+    ransac_par_with_progress(
+        num_of_iterations,
+        progress,
+        rand::random(),
+        |rng| generate_line_model(buffer, distance_threshold, rng),
+        |line| line.ranking,
+    )
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -465,6 +673,42 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_ransac_plane_par_with_progress(){
+        let buffer = setup_point_cloud();
+        let finished_iterations = std::sync::Arc::new(AtomicUsize::new(0));
+        let finished_iterations_clone = finished_iterations.clone();
+        let progress = Progress::default().with_callback(move |finished: usize, _total: Option<usize>| {
+            finished_iterations_clone.fetch_max(finished, Ordering::SeqCst);
+        });
+        let (_plane, indices) = ransac_plane_par_with_progress(&buffer, 0.1, 300, &progress).unwrap();
+        assert!(indices.len() == 1600);
+        assert_eq!(300, finished_iterations.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_ransac_line_par_cancelled(){
+        let buffer = setup_point_cloud();
+        let token = pasture_core::util::CancellationToken::new();
+        token.cancel();
+        let progress = Progress::default().with_cancellation_token(token);
+        let error = ransac_line_par_with_progress(&buffer, 0.1, 300, &progress).unwrap_err();
+        assert!(error.is::<pasture_core::util::Cancelled>());
+    }
+
+    #[test]
+    fn test_ransac_seeded_is_deterministic(){
+        let buffer = setup_point_cloud();
+        let (_plane, plane_indices) = ransac_plane_par_seeded(&buffer, 0.1, 50, 1234);
+        for _ in 0..3 {
+            assert_eq!(plane_indices, ransac_plane_par_seeded(&buffer, 0.1, 50, 1234).1);
+        }
+        assert_eq!(plane_indices, ransac_plane_serial_seeded(&buffer, 0.1, 50, 1234).1);
+
+        let (_line, line_indices) = ransac_line_par_seeded(&buffer, 0.1, 50, 1234);
+        assert_eq!(line_indices, ransac_line_serial_seeded(&buffer, 0.1, 50, 1234).1);
+    }
+
     #[test]
     fn test_ransac_line_serial(){
         let buffer = setup_point_cloud();
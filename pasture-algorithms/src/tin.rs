@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+
+use pasture_core::{
+    containers::{PointBuffer, PointBufferExt},
+    layout::attributes::POSITION_3D,
+    nalgebra::{Vector2, Vector3},
+};
+
+/// A triangulated irregular network (TIN), i.e. a 2.5D triangle mesh over a set of points, as calculated by
+/// [triangulate]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tin {
+    /// Positions of all vertices. This includes points that are not part of any triangle, e.g. duplicate points
+    pub vertices: Vec<Vector3<f64>>,
+    /// Vertex indices of all triangles, in counter-clockwise order when viewed from above
+    pub triangles: Vec<[usize; 3]>,
+}
+
+/// A triangle during the triangulation. `neighbors[i]` is the triangle on the other side of the edge opposite to
+/// `vertices[i]`
+#[derive(Debug, Clone)]
+struct Triangle {
+    vertices: [usize; 3],
+    neighbors: [Option<usize>; 3],
+    is_alive: bool,
+}
+
+/// Twice the signed area of the triangle `(a, b, c)`, which is positive if the triangle is counter-clockwise
+fn orientation(a: &Vector2<f64>, b: &Vector2<f64>, c: &Vector2<f64>) -> f64 {
+    (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x)
+}
+
+/// Is `p` strictly inside the circumcircle of the counter-clockwise triangle `(a, b, c)`?
+fn is_in_circumcircle(
+    a: &Vector2<f64>,
+    b: &Vector2<f64>,
+    c: &Vector2<f64>,
+    p: &Vector2<f64>,
+) -> bool {
+    let (ax, ay) = (a.x - p.x, a.y - p.y);
+    let (bx, by) = (b.x - p.x, b.y - p.y);
+    let (cx, cy) = (c.x - p.x, c.y - p.y);
+    let determinant = (ax * ax + ay * ay) * (bx * cy - cx * by)
+        - (bx * bx + by * by) * (ax * cy - cx * ay)
+        + (cx * cx + cy * cy) * (ax * by - bx * ay);
+    determinant > 0.0
+}
+
+/// Interleaves the lower 16 bits of `x` and `y` into a Morton code
+fn morton_code(x: u32, y: u32) -> u32 {
+    let spread = |mut value: u32| {
+        value &= 0xffff;
+        value = (value | (value << 8)) & 0x00ff_00ff;
+        value = (value | (value << 4)) & 0x0f0f_0f0f;
+        value = (value | (value << 2)) & 0x3333_3333;
+        (value | (value << 1)) & 0x5555_5555
+    };
+    spread(x) | (spread(y) << 1)
+}
+
+/// Incremental Delaunay triangulation using the Bowyer-Watson algorithm
+struct Triangulation {
+    vertices: Vec<Vector2<f64>>,
+    triangles: Vec<Triangle>,
+    last_triangle: usize,
+}
+
+impl Triangulation {
+    /// Creates a new triangulation for the given points, which only contains a super triangle with three additional
+    /// vertices that encloses all points
+    fn new(mut vertices: Vec<Vector2<f64>>) -> Self {
+        let (min, max) = vertices.iter().fold(
+            (
+                Vector2::new(f64::INFINITY, f64::INFINITY),
+                Vector2::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+            ),
+            |(min, max), vertex| (min.inf(vertex), max.sup(vertex)),
+        );
+        let center = (min + max) / 2.0;
+        let size = (max.x - min.x).max(max.y - min.y).max(1.0);
+        let first_super_vertex = vertices.len();
+        vertices.push(Vector2::new(center.x - 20.0 * size, center.y - size));
+        vertices.push(Vector2::new(center.x + 20.0 * size, center.y - size));
+        vertices.push(Vector2::new(center.x, center.y + 20.0 * size));
+
+        Self {
+            vertices,
+            triangles: vec![Triangle {
+                vertices: [
+                    first_super_vertex,
+                    first_super_vertex + 1,
+                    first_super_vertex + 2,
+                ],
+                neighbors: [None, None, None],
+                is_alive: true,
+            }],
+            last_triangle: 0,
+        }
+    }
+
+    /// Finds the triangle that contains `point` by walking from the last created triangle towards `point`
+    fn locate(&self, point: &Vector2<f64>) -> usize {
+        let mut current = self.last_triangle;
+        'walk: loop {
+            let triangle = &self.triangles[current];
+            for edge in 0..3 {
+                let a = &self.vertices[triangle.vertices[(edge + 1) % 3]];
+                let b = &self.vertices[triangle.vertices[(edge + 2) % 3]];
+                if orientation(a, b, point) < 0.0 {
+                    if let Some(neighbor) = triangle.neighbors[edge] {
+                        current = neighbor;
+                        continue 'walk;
+                    }
+                }
+            }
+            return current;
+        }
+    }
+
+    /// Inserts the vertex with the given index into the triangulation. Vertices that coincide with an existing vertex
+    /// are skipped
+    fn insert(&mut self, vertex: usize) {
+        let point = self.vertices[vertex];
+        let containing_triangle = self.locate(&point);
+        if self.triangles[containing_triangle]
+            .vertices
+            .iter()
+            .any(|other| self.vertices[*other] == point)
+        {
+            return;
+        }
+
+        // All triangles whose circumcircle contains the new vertex form a connected cavity around it
+        let mut cavity = vec![containing_triangle];
+        self.triangles[containing_triangle].is_alive = false;
+        let mut next = 0;
+        while next < cavity.len() {
+            let neighbors = self.triangles[cavity[next]].neighbors;
+            next += 1;
+            for neighbor in neighbors.iter().flatten() {
+                let neighbor_triangle = &self.triangles[*neighbor];
+                if !neighbor_triangle.is_alive {
+                    continue;
+                }
+                let [a, b, c] = neighbor_triangle.vertices;
+                if is_in_circumcircle(
+                    &self.vertices[a],
+                    &self.vertices[b],
+                    &self.vertices[c],
+                    &point,
+                ) {
+                    self.triangles[*neighbor].is_alive = false;
+                    cavity.push(*neighbor);
+                }
+            }
+        }
+
+        // Connect the new vertex to all edges on the boundary of the cavity
+        let mut new_triangles_by_start = HashMap::new();
+        let mut new_triangles_by_end = HashMap::new();
+        for triangle in cavity.iter() {
+            let Triangle {
+                vertices,
+                neighbors,
+                ..
+            } = self.triangles[*triangle].clone();
+            for edge in 0..3 {
+                let outer = neighbors[edge];
+                if outer
+                    .map(|outer| !self.triangles[outer].is_alive)
+                    .unwrap_or(false)
+                {
+                    continue;
+                }
+                let start = vertices[(edge + 1) % 3];
+                let end = vertices[(edge + 2) % 3];
+                let new_triangle = self.triangles.len();
+                self.triangles.push(Triangle {
+                    vertices: [start, end, vertex],
+                    neighbors: [None, None, outer],
+                    is_alive: true,
+                });
+                if let Some(outer) = outer {
+                    for outer_neighbor in self.triangles[outer].neighbors.iter_mut() {
+                        if *outer_neighbor == Some(*triangle) {
+                            *outer_neighbor = Some(new_triangle);
+                        }
+                    }
+                }
+                new_triangles_by_start.insert(start, new_triangle);
+                new_triangles_by_end.insert(end, new_triangle);
+            }
+        }
+
+        for new_triangle in new_triangles_by_start.values() {
+            let [start, end, _] = self.triangles[*new_triangle].vertices;
+            // The edge (end, vertex) is shared with the new triangle that starts at `end`, the edge (vertex, start)
+            // with the new triangle that ends at `start`
+            self.triangles[*new_triangle].neighbors[0] = new_triangles_by_start.get(&end).copied();
+            self.triangles[*new_triangle].neighbors[1] = new_triangles_by_end.get(&start).copied();
+            self.last_triangle = *new_triangle;
+        }
+    }
+}
+
+/// Calculates the 2D Delaunay triangulation of the given `positions`, using only their x- and y-coordinates. Of all
+/// positions with the same x- and y-coordinates, only the first one becomes part of the triangulation
+pub fn triangulate_positions(positions: Vec<Vector3<f64>>) -> Tin {
+    if positions.len() < 3 {
+        return Tin {
+            vertices: positions,
+            triangles: vec![],
+        };
+    }
+
+    // Moving the points close to the origin improves the precision of the geometric predicates
+    let origin = positions[0];
+    let vertices = positions
+        .iter()
+        .map(|position| Vector2::new(position.x - origin.x, position.y - origin.y))
+        .collect::<Vec<_>>();
+
+    // Inserting the points in Morton order keeps the walks in `locate` short
+    let (min, max) = vertices.iter().fold(
+        (
+            Vector2::new(f64::INFINITY, f64::INFINITY),
+            Vector2::new(f64::NEG_INFINITY, f64::NEG_INFINITY),
+        ),
+        |(min, max), vertex| (min.inf(vertex), max.sup(vertex)),
+    );
+    let extent = (max.x - min.x).max(max.y - min.y).max(f64::MIN_POSITIVE);
+    let mut order = (0..vertices.len()).collect::<Vec<_>>();
+    order.sort_by_cached_key(|index| {
+        let normalized = (vertices[*index] - min) / extent * 65535.0;
+        morton_code(normalized.x as u32, normalized.y as u32)
+    });
+
+    let point_count = vertices.len();
+    let mut triangulation = Triangulation::new(vertices);
+    for index in order {
+        triangulation.insert(index);
+    }
+
+    let triangles = triangulation
+        .triangles
+        .into_iter()
+        .filter(|triangle| {
+            triangle.is_alive && triangle.vertices.iter().all(|vertex| *vertex < point_count)
+        })
+        .map(|triangle| triangle.vertices)
+        .collect();
+    Tin {
+        vertices: positions,
+        triangles,
+    }
+}
+
+/// Calculates the 2D Delaunay triangulation of the positions of all points in `buffer`. The vertices of the resulting
+/// [Tin] are the positions of the points in `buffer` in the same order, so vertex indices are point indices
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::tin::triangulate;
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct SimplePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+/// }
+/// let points = vec![
+///     SimplePoint{ position: Vector3::new(0.0, 0.0, 0.0) },
+///     SimplePoint{ position: Vector3::new(1.0, 0.0, 0.0) },
+///     SimplePoint{ position: Vector3::new(1.0, 1.0, 0.0) },
+///     SimplePoint{ position: Vector3::new(0.0, 1.0, 0.0) },
+/// ];
+/// let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// buffer.push_points(&points);
+/// let tin = triangulate(&buffer);
+/// assert_eq!(2, tin.triangles.len());
+/// ```
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+pub fn triangulate<T: PointBuffer + ?Sized>(buffer: &T) -> Tin {
+    if !buffer
+        .point_layout()
+        .has_attribute_with_name(POSITION_3D.name())
+    {
+        panic!("point buffer contains no position attribute");
+    }
+    triangulate_positions(
+        buffer
+            .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{prelude::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    fn to_2d(position: &Vector3<f64>) -> Vector2<f64> {
+        Vector2::new(position.x, position.y)
+    }
+
+    #[test]
+    fn test_triangulation_is_delaunay() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let positions = (0..300)
+            .map(|_| {
+                Vector3::new(
+                    rng.gen_range(0.0..50.0),
+                    rng.gen_range(0.0..20.0),
+                    rng.gen_range(0.0..5.0),
+                )
+            })
+            .collect::<Vec<_>>();
+        let tin = triangulate_positions(positions.clone());
+
+        // A triangulation of n points in general position with h points on the convex hull has 2n - 2 - h triangles,
+        // so the number of triangles is bounded by 2n - 5
+        assert!(tin.triangles.len() <= 2 * positions.len() - 5);
+        let mut area = 0.0;
+        for [a, b, c] in tin.triangles.iter() {
+            let (a, b, c) = (
+                to_2d(&tin.vertices[*a]),
+                to_2d(&tin.vertices[*b]),
+                to_2d(&tin.vertices[*c]),
+            );
+            assert!(orientation(&a, &b, &c) > 0.0);
+            area += orientation(&a, &b, &c) / 2.0;
+            for other in positions.iter() {
+                assert!(!is_in_circumcircle(&a, &b, &c, &to_2d(other)));
+            }
+        }
+        // The area of the triangulation is the area of the convex hull, which is close to the area of the sampled
+        // rectangle
+        assert!(area > 0.9 * 50.0 * 20.0 && area <= 50.0 * 20.0);
+    }
+
+    #[test]
+    fn test_triangulation_of_grid_with_duplicates() {
+        let mut positions = vec![];
+        for y in 0..5 {
+            for x in 0..5 {
+                positions.push(Vector3::new(x as f64, y as f64, 0.0));
+            }
+        }
+        positions.push(Vector3::new(2.0, 2.0, 1.0));
+
+        let tin = triangulate_positions(positions);
+        assert_eq!(32, tin.triangles.len());
+        assert!(tin.triangles.iter().all(|triangle| !triangle.contains(&25)));
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use pasture_core::{
+    containers::{PointBuffer, PointBufferExt},
+    layout::attributes::POSITION_3D,
+    nalgebra::Vector3,
+};
+
+/// Voxel grid filter. Overlays the points in `buffer` with a regular grid of cubic cells with an edge length of
+/// `cell_size` and keeps exactly one point per occupied cell, namely the point that is closest to the center of
+/// the cell. Returns the indices of all kept points within `buffer` in ascending order.
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::voxel_grid::voxel_grid_filter;
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct SimplePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+/// }
+/// let points = vec![
+///     SimplePoint{ position: Vector3::new(0.1, 0.1, 0.1) },
+///     SimplePoint{ position: Vector3::new(0.5, 0.5, 0.5) },
+///     SimplePoint{ position: Vector3::new(1.5, 0.5, 0.5) },
+/// ];
+/// let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// buffer.push_points(&points);
+/// let indices = voxel_grid_filter(&buffer, 1.0);
+/// assert_eq!(indices, vec![1, 2]);
+/// ```
+///
+/// # Panics
+///
+/// If `cell_size` is not strictly positive, or if the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+pub fn voxel_grid_filter<T: PointBuffer>(buffer: &T, cell_size: f64) -> Vec<usize> {
+    if cell_size <= 0.0 {
+        panic!("voxel_grid_filter: cell_size must be > 0");
+    }
+    let position_attribute = match buffer
+        .point_layout()
+        .get_attribute_by_name(POSITION_3D.name())
+    {
+        Some(a) => a,
+        None => panic!("point buffer contains no position attribute"),
+    };
+
+    // maps each occupied cell to the index and squared center distance of the best point found so far
+    let mut cells: HashMap<(i64, i64, i64), (usize, f64)> = HashMap::new();
+    let mut insert_point = |index: usize, position: Vector3<f64>| {
+        let cell = (
+            (position.x / cell_size).floor() as i64,
+            (position.y / cell_size).floor() as i64,
+            (position.z / cell_size).floor() as i64,
+        );
+        let center = Vector3::new(
+            (cell.0 as f64 + 0.5) * cell_size,
+            (cell.1 as f64 + 0.5) * cell_size,
+            (cell.2 as f64 + 0.5) * cell_size,
+        );
+        let distance = (position - center).norm_squared();
+        let entry = cells.entry(cell).or_insert((index, distance));
+        if distance < entry.1 {
+            *entry = (index, distance);
+        }
+    };
+
+    if position_attribute.datatype() == POSITION_3D.datatype() {
+        for (index, position) in buffer
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .enumerate()
+        {
+            insert_point(index, position);
+        }
+    } else {
+        for (index, position) in buffer
+            .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+            .enumerate()
+        {
+            insert_point(index, position);
+        }
+    }
+
+    let mut indices = cells.values().map(|(index, _)| *index).collect::<Vec<_>>();
+    indices.sort_unstable();
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{containers::PerAttributeVecPointStorage, layout::PointType};
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct SimplePoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+    }
+
+    #[test]
+    fn test_voxel_grid_filter_keeps_one_point_per_cell() {
+        let points = (0..1000)
+            .map(|idx| SimplePoint {
+                position: Vector3::new(
+                    (idx % 10) as f64 * 0.25,
+                    ((idx / 10) % 10) as f64 * 0.25,
+                    (idx / 100) as f64 * 0.25,
+                ),
+            })
+            .collect::<Vec<_>>();
+        let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+        buffer.push_points(&points);
+
+        // 10 points per axis with a spacing of 0.25 fall into 3 cells of size 1 per axis
+        let indices = voxel_grid_filter(&buffer, 1.0);
+        assert_eq!(27, indices.len());
+        assert!(indices.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_voxel_grid_filter_on_empty_buffer() {
+        let buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+        assert!(voxel_grid_filter(&buffer, 1.0).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_voxel_grid_filter_invalid_cell_size() {
+        let buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+        voxel_grid_filter(&buffer, 0.0);
+    }
+}
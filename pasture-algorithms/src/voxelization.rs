@@ -0,0 +1,341 @@
+use std::{collections::BTreeMap, io::Write};
+
+use anyhow::{anyhow, Result};
+use pasture_core::{
+    containers::{Grid3D, PointBuffer, PointBufferExt},
+    layout::{attributes::POSITION_3D, PointAttributeDefinition},
+    math::{RunningStatistics, AABB},
+    nalgebra::Vector3,
+};
+
+use crate::features::attribute_columns;
+
+/// Index of a voxel in a [Voxelization]. The voxel `(x, y, z)` covers the cube from `(x, y, z) * voxel_size` to
+/// `(x + 1, y + 1, z + 1) * voxel_size`
+pub type VoxelIndex = (i64, i64, i64);
+
+/// A single occupied voxel of a [Voxelization]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Voxel {
+    /// Number of points inside of this voxel
+    pub point_count: usize,
+    /// Statistics of the aggregated attribute of all points inside of this voxel. This is empty if the voxelization
+    /// has no aggregated attribute
+    pub attribute: RunningStatistics,
+}
+
+/// A sparse voxelization of a point cloud, as calculated by [voxelize] or [voxelize_with_attribute]. Only occupied
+/// voxels are stored. The voxels are aligned with the origin of the coordinate system instead of the bounds of the
+/// point cloud, so the voxelizations of neighboring tiles fit together
+#[derive(Debug, Clone, PartialEq)]
+pub struct Voxelization {
+    voxel_size: f64,
+    voxels: BTreeMap<VoxelIndex, Voxel>,
+}
+
+impl Voxelization {
+    /// Magic bytes at the start of the binary format of [write_binary](Voxelization::write_binary)
+    pub const BINARY_MAGIC: &[u8; 4] = b"PVOX";
+    /// Version of the binary format of [write_binary](Voxelization::write_binary)
+    pub const BINARY_VERSION: u32 = 1;
+
+    /// Returns the edge length of the voxels
+    pub fn voxel_size(&self) -> f64 {
+        self.voxel_size
+    }
+
+    /// Returns the number of occupied voxels
+    pub fn len(&self) -> usize {
+        self.voxels.len()
+    }
+
+    /// Returns true if no voxel is occupied
+    pub fn is_empty(&self) -> bool {
+        self.voxels.is_empty()
+    }
+
+    /// Returns the voxel at `index`, or `None` if the voxel is not occupied
+    pub fn get(&self, index: &VoxelIndex) -> Option<&Voxel> {
+        self.voxels.get(index)
+    }
+
+    /// Returns an iterator over all occupied voxels, sorted by their index with x varying slowest
+    pub fn iter(&self) -> impl Iterator<Item = (&VoxelIndex, &Voxel)> {
+        self.voxels.iter()
+    }
+
+    /// Returns the center of the voxel at `index`
+    pub fn voxel_center(&self, index: &VoxelIndex) -> Vector3<f64> {
+        Vector3::new(
+            index.0 as f64 + 0.5,
+            index.1 as f64 + 0.5,
+            index.2 as f64 + 0.5,
+        ) * self.voxel_size
+    }
+
+    /// Returns the total volume of all occupied voxels, which is a simple estimate of the volume of the scanned
+    /// objects (e.g. of a stockpile or of the vegetation)
+    pub fn occupied_volume(&self) -> f64 {
+        self.voxels.len() as f64 * self.voxel_size.powi(3)
+    }
+
+    /// Returns the bounds of all occupied voxels, or `None` if no voxel is occupied
+    pub fn bounds(&self) -> Option<AABB<f64>> {
+        let (min, max) = self.index_bounds()?;
+        let to_point = |index: VoxelIndex| {
+            Vector3::new(index.0 as f64, index.1 as f64, index.2 as f64) * self.voxel_size
+        };
+        Some(AABB::from_min_max_unchecked(
+            to_point(min).into(),
+            to_point((max.0 + 1, max.1 + 1, max.2 + 1)).into(),
+        ))
+    }
+
+    /// Converts this sparse voxelization into a dense occupancy grid that covers all occupied voxels. Returns `None` if
+    /// no voxel is occupied
+    pub fn to_occupancy_grid(&self) -> Option<Grid3D<bool>> {
+        let (min, max) = self.index_bounds()?;
+        let mut grid = Grid3D::new(
+            Vector3::new(min.0 as f64, min.1 as f64, min.2 as f64) * self.voxel_size,
+            self.voxel_size,
+            (max.0 - min.0 + 1) as usize,
+            (max.1 - min.1 + 1) as usize,
+            (max.2 - min.2 + 1) as usize,
+            false,
+        );
+        for index in self.voxels.keys() {
+            grid[(
+                (index.0 - min.0) as usize,
+                (index.1 - min.1) as usize,
+                (index.2 - min.2) as usize,
+            )] = true;
+        }
+        Some(grid)
+    }
+
+    /// Writes all occupied voxels in a flat little-endian binary format to `writer`. The format starts with a header
+    /// that consists of the magic bytes `PVOX`, the format version as `u32`, the voxel size as `f64` and the number of
+    /// voxels as `u64`. It is followed by one record per voxel in the order of [iter](Voxelization::iter), which
+    /// consists of the voxel index as three `i64` values, the point count as `u64` and the minimum, maximum and mean of
+    /// the aggregated attribute as three `f64` values (which are NaN if there is no aggregated attribute)
+    pub fn write_binary<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_all(Self::BINARY_MAGIC)?;
+        writer.write_all(&Self::BINARY_VERSION.to_le_bytes())?;
+        writer.write_all(&self.voxel_size.to_le_bytes())?;
+        writer.write_all(&(self.voxels.len() as u64).to_le_bytes())?;
+        for (index, voxel) in self.voxels.iter() {
+            writer.write_all(&index.0.to_le_bytes())?;
+            writer.write_all(&index.1.to_le_bytes())?;
+            writer.write_all(&index.2.to_le_bytes())?;
+            writer.write_all(&(voxel.point_count as u64).to_le_bytes())?;
+            for value in [
+                voxel.attribute.min(),
+                voxel.attribute.max(),
+                voxel.attribute.mean(),
+            ]
+            .iter()
+            {
+                writer.write_all(&value.unwrap_or(f64::NAN).to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn index_bounds(&self) -> Option<(VoxelIndex, VoxelIndex)> {
+        let mut indices = self.voxels.keys();
+        let first = *indices.next()?;
+        Some(indices.fold((first, first), |(min, max), index| {
+            (
+                (min.0.min(index.0), min.1.min(index.1), min.2.min(index.2)),
+                (max.0.max(index.0), max.1.max(index.1), max.2.max(index.2)),
+            )
+        }))
+    }
+}
+
+fn voxelize_values<T: PointBuffer + ?Sized>(
+    buffer: &T,
+    voxel_size: f64,
+    values: Option<&[f64]>,
+) -> Voxelization {
+    if voxel_size <= 0.0 {
+        panic!("voxelize: voxel_size must be > 0");
+    }
+    if !buffer
+        .point_layout()
+        .has_attribute_with_name(POSITION_3D.name())
+    {
+        panic!("point buffer contains no position attribute");
+    }
+    let mut voxels = BTreeMap::new();
+    for (point_index, position) in buffer
+        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+        .enumerate()
+    {
+        let index = (
+            (position.x / voxel_size).floor() as i64,
+            (position.y / voxel_size).floor() as i64,
+            (position.z / voxel_size).floor() as i64,
+        );
+        let voxel = voxels.entry(index).or_insert(Voxel {
+            point_count: 0,
+            attribute: RunningStatistics::new(),
+        });
+        voxel.point_count += 1;
+        if let Some(values) = values {
+            voxel.attribute.add(values[point_index]);
+        }
+    }
+    Voxelization { voxel_size, voxels }
+}
+
+/// Voxelizes the points in `buffer` into cubic voxels with the given `voxel_size`, which yields an occupancy map
+/// together with the number of points per voxel.
+///
+/// # Examples
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_core::containers::PerAttributeVecPointStorage;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_algorithms::voxelization::voxelize;
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct SimplePoint {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     pub position: Vector3<f64>,
+/// }
+/// let points = vec![
+///     SimplePoint{ position: Vector3::new(0.1, 0.1, 0.1) },
+///     SimplePoint{ position: Vector3::new(0.9, 0.2, 0.3) },
+///     SimplePoint{ position: Vector3::new(-0.5, 0.2, 0.3) },
+/// ];
+/// let mut buffer = PerAttributeVecPointStorage::new(SimplePoint::layout());
+/// buffer.push_points(&points);
+/// let voxelization = voxelize(&buffer, 1.0);
+/// assert_eq!(2, voxelization.len());
+/// assert_eq!(2, voxelization.get(&(0, 0, 0)).unwrap().point_count);
+/// assert_eq!(1, voxelization.get(&(-1, 0, 0)).unwrap().point_count);
+/// assert_eq!(2.0, voxelization.occupied_volume());
+/// ```
+///
+/// # Panics
+///
+/// If `voxel_size` is not strictly positive, or if the `PointLayout` of `buffer` doesn't contain a `POSITION_3D`
+/// attribute.
+pub fn voxelize<T: PointBuffer + ?Sized>(buffer: &T, voxel_size: f64) -> Voxelization {
+    voxelize_values(buffer, voxel_size, None)
+}
+
+/// Like [voxelize], but also aggregates the values of the scalar `attribute` of all points within each voxel (e.g. the
+/// mean intensity or the maximum GPS time). The datatype of `attribute` is taken from the buffer, so the datatype of
+/// the given definition is ignored. Returns an error if `buffer` has no such attribute or if the attribute is a vector
+/// attribute
+///
+/// # Panics
+///
+/// If `voxel_size` is not strictly positive, or if the `PointLayout` of `buffer` doesn't contain a `POSITION_3D`
+/// attribute.
+pub fn voxelize_with_attribute<T: PointBuffer + ?Sized>(
+    buffer: &T,
+    voxel_size: f64,
+    attribute: &PointAttributeDefinition,
+) -> Result<Voxelization> {
+    let (mut columns, _) = attribute_columns(buffer, attribute)?;
+    if columns.len() != 1 {
+        return Err(anyhow!(
+            "Can't aggregate vector attribute {} per voxel",
+            attribute.name()
+        ));
+    }
+    let values = columns.remove(0);
+    Ok(voxelize_values(buffer, voxel_size, Some(&values)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use pasture_core::{
+        containers::PerAttributeVecPointStorage,
+        layout::{attributes::INTENSITY, PointType},
+    };
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug)]
+    struct IntensityPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        pub intensity: u16,
+    }
+
+    fn test_points() -> PerAttributeVecPointStorage {
+        let mut buffer = PerAttributeVecPointStorage::new(IntensityPoint::layout());
+        for (x, y, z, intensity) in [
+            (0.25, 0.25, 0.25, 10),
+            (0.75, 0.25, 0.25, 20),
+            (1.25, 0.25, 0.25, 30),
+            (1.25, 1.25, 1.75, 40),
+        ]
+        .iter()
+        {
+            buffer.push_point(IntensityPoint {
+                position: Vector3::new(*x, *y, *z),
+                intensity: *intensity,
+            });
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_voxelize_with_attribute() -> Result<()> {
+        let voxelization = voxelize_with_attribute(&test_points(), 0.5, &INTENSITY)?;
+        assert_eq!(4, voxelization.len());
+        let voxel = voxelization.get(&(2, 2, 3)).unwrap();
+        assert_eq!(1, voxel.point_count);
+        assert_eq!(Some(40.0), voxel.attribute.mean());
+
+        let voxelization = voxelize_with_attribute(&test_points(), 1.0, &INTENSITY)?;
+        assert_eq!(3, voxelization.len());
+        let voxel = voxelization.get(&(0, 0, 0)).unwrap();
+        assert_eq!(2, voxel.point_count);
+        assert_eq!(Some(15.0), voxel.attribute.mean());
+        assert_eq!(Some(20.0), voxel.attribute.max());
+        assert!(voxelize_with_attribute(&test_points(), 1.0, &POSITION_3D).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_voxelization_to_occupancy_grid() {
+        let voxelization = voxelize(&test_points(), 1.0);
+        let bounds = voxelization.bounds().unwrap();
+        assert_eq!(Vector3::new(0.0, 0.0, 0.0), bounds.min().coords);
+        assert_eq!(Vector3::new(2.0, 2.0, 2.0), bounds.max().coords);
+
+        let grid = voxelization.to_occupancy_grid().unwrap();
+        assert_eq!(&Vector3::new(2, 2, 2), grid.size());
+        assert_eq!(3, grid.cells().iter().filter(|occupied| **occupied).count());
+        assert!(grid[(1, 1, 1)]);
+        assert!(!grid[(0, 1, 0)]);
+    }
+
+    #[test]
+    fn test_voxelization_write_binary() -> Result<()> {
+        let voxelization = voxelize(&test_points(), 1.0);
+        let mut bytes = vec![];
+        voxelization.write_binary(&mut bytes)?;
+        assert_eq!(24 + 3 * 56, bytes.len());
+        assert_eq!(b"PVOX", &bytes[0..4]);
+        assert_eq!(3, u64::from_le_bytes(bytes[16..24].try_into()?));
+        // The first voxel is (0, 0, 0) with two points
+        assert_eq!(0, i64::from_le_bytes(bytes[24..32].try_into()?));
+        assert_eq!(2, u64::from_le_bytes(bytes[48..56].try_into()?));
+        assert!(f64::from_le_bytes(bytes[56..64].try_into()?).is_nan());
+        Ok(())
+    }
+}
@@ -0,0 +1,365 @@
+use anyhow::Result;
+use pasture_core::{
+    containers::{PointBuffer, PointBufferExt},
+    layout::{
+        attributes::{GPS_TIME, POSITION_3D, RETURN_POINT_WAVEFORM_LOCATION, WAVEFORM_PARAMETERS},
+        PointAttributeDataType, PointAttributeDefinition, PointType,
+    },
+    nalgebra::Vector3,
+};
+use pasture_derive::PointType;
+
+use crate::normals::positions_of;
+
+/// Attribute for the amplitude of an echo that was extracted from a waveform, in the unit of the waveform samples
+pub const ECHO_AMPLITUDE: PointAttributeDefinition =
+    PointAttributeDefinition::custom("EchoAmplitude", PointAttributeDataType::F32);
+/// Attribute for the width (standard deviation of the Gaussian) of an echo that was extracted from a waveform, in
+/// picoseconds
+pub const ECHO_WIDTH: PointAttributeDefinition =
+    PointAttributeDefinition::custom("EchoWidth", PointAttributeDataType::F32);
+/// Attribute for the index of the point whose waveform an echo was extracted from
+pub const SOURCE_POINT_INDEX: PointAttributeDefinition =
+    PointAttributeDefinition::custom("SourcePointIndex", PointAttributeDataType::U64);
+
+/// A digitized waveform, e.g. as read by the `WaveformDataReader` of pasture-io
+#[derive(Debug, Clone, PartialEq)]
+pub struct Waveform {
+    /// The samples of the waveform
+    pub samples: Vec<f64>,
+    /// Time between two samples, in picoseconds
+    pub temporal_sample_spacing: f64,
+}
+
+/// A single echo of a waveform, modelled as a Gaussian
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GaussianEcho {
+    /// Location of the center of the echo, in samples from the start of the waveform
+    pub location: f64,
+    /// Amplitude of the echo above the background of the waveform
+    pub amplitude: f64,
+    /// Standard deviation of the Gaussian, in samples
+    pub width: f64,
+}
+
+impl GaussianEcho {
+    /// Returns the value of the Gaussian of this echo at the given sample `location`
+    pub fn evaluate(&self, location: f64) -> f64 {
+        let distance = location - self.location;
+        self.amplitude * (-distance * distance / (2.0 * self.width * self.width)).exp()
+    }
+}
+
+/// Parameters for [decompose_waveform]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaveformDecompositionParameters {
+    /// Echoes must be at least this many times higher than the noise level of the waveform, which is estimated from
+    /// the median absolute deviation of the samples. 3 by default
+    pub noise_factor: f64,
+    /// Echoes must have at least this fraction of the amplitude of the strongest echo. 0.05 by default
+    pub min_relative_amplitude: f64,
+    /// Maximum number of echoes per waveform. 8 by default
+    pub max_echoes: usize,
+}
+
+impl Default for WaveformDecompositionParameters {
+    fn default() -> Self {
+        Self {
+            noise_factor: 3.0,
+            min_relative_amplitude: 0.05,
+            max_echoes: 8,
+        }
+    }
+}
+
+/// A point for an echo that was extracted from a waveform by [extract_echoes]
+#[repr(C)]
+#[derive(PointType, Debug, Clone, Copy, PartialEq)]
+pub struct WaveformEchoPoint {
+    #[pasture(BUILTIN_POSITION_3D)]
+    pub position: Vector3<f64>,
+    #[pasture(BUILTIN_GPS_TIME)]
+    pub gps_time: f64,
+    #[pasture(attribute = "SourcePointIndex")]
+    pub source_point_index: u64,
+    #[pasture(attribute = "EchoAmplitude")]
+    pub amplitude: f32,
+    #[pasture(attribute = "EchoWidth")]
+    pub width: f32,
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values[values.len() / 2]
+}
+
+/// Fits a Gaussian to the peak at `index` of `residual` through a parabola on the logarithm of the peak sample and its
+/// neighbors. Falls back to a Gaussian with a width of half a sample at the peak sample if the neighbors are not
+/// positive or the peak is not curved downwards
+fn fit_peak(residual: &[f64], index: usize) -> GaussianEcho {
+    let fallback = GaussianEcho {
+        location: index as f64,
+        amplitude: residual[index],
+        width: 0.5,
+    };
+    if index == 0 || index + 1 == residual.len() {
+        return fallback;
+    }
+    let (before, peak, after) = (residual[index - 1], residual[index], residual[index + 1]);
+    if before <= 0.0 || after <= 0.0 {
+        return fallback;
+    }
+    let (before, peak, after) = (before.ln(), peak.ln(), after.ln());
+    let curvature = before - 2.0 * peak + after;
+    if curvature >= 0.0 {
+        return fallback;
+    }
+    // ln(g(x)) = ln(a) - (x - m)^2 / (2 s^2) is a parabola with the second difference -1 / s^2
+    let offset = (before - after) / (2.0 * curvature);
+    let variance = -1.0 / curvature;
+    GaussianEcho {
+        location: index as f64 + offset,
+        amplitude: (peak + offset * offset / (2.0 * variance)).exp(),
+        width: variance.sqrt(),
+    }
+}
+
+/// Decomposes the given waveform `samples` into a sum of Gaussian echoes on top of a constant background. The
+/// background is the median of the samples. The echoes are found by repeatedly fitting a Gaussian to the strongest
+/// remaining peak and subtracting it from the waveform, until the remaining peaks are within the noise or
+/// `max_echoes` echoes are found. The echoes are returned in the order of their location
+///
+/// ```
+/// # use pasture_algorithms::waveform::*;
+/// let echo = GaussianEcho { location: 10.0, amplitude: 50.0, width: 1.5 };
+/// let samples = (0..32).map(|index| 2.0 + echo.evaluate(index as f64)).collect::<Vec<_>>();
+///
+/// let echoes = decompose_waveform(&samples, &WaveformDecompositionParameters::default());
+/// assert_eq!(1, echoes.len());
+/// assert!((echoes[0].location - 10.0).abs() < 1e-6);
+/// assert!((echoes[0].amplitude - 50.0).abs() < 1e-3);
+/// ```
+pub fn decompose_waveform(
+    samples: &[f64],
+    parameters: &WaveformDecompositionParameters,
+) -> Vec<GaussianEcho> {
+    if samples.is_empty() {
+        return vec![];
+    }
+    let background = median(&mut samples.to_vec());
+    let mut residual = samples
+        .iter()
+        .map(|sample| sample - background)
+        .collect::<Vec<_>>();
+    let noise_level =
+        1.4826 * median(&mut residual.iter().map(|value| value.abs()).collect::<Vec<_>>());
+
+    let mut echoes: Vec<GaussianEcho> = vec![];
+    while echoes.len() < parameters.max_echoes {
+        let (peak_index, peak_value) = residual.iter().copied().enumerate().fold(
+            (0, f64::NEG_INFINITY),
+            |max, (index, value)| {
+                if value > max.1 {
+                    (index, value)
+                } else {
+                    max
+                }
+            },
+        );
+        let strongest_amplitude = echoes.first().map(|echo| echo.amplitude).unwrap_or(0.0);
+        if peak_value <= parameters.noise_factor * noise_level
+            || peak_value <= parameters.min_relative_amplitude * strongest_amplitude
+            || peak_value <= 0.0
+        {
+            break;
+        }
+        let echo = fit_peak(&residual, peak_index);
+        for (index, value) in residual.iter_mut().enumerate() {
+            *value -= echo.evaluate(index as f64);
+        }
+        echoes.push(echo);
+    }
+    echoes.sort_by(|a, b| a.location.partial_cmp(&b.location).unwrap());
+    echoes
+}
+
+/// Extracts the echoes of the waveforms of all points in `buffer` as new points. `waveform_of` is called with the index
+/// of each point and returns its waveform, or `None` if the point has no waveform. Each waveform is decomposed with
+/// [decompose_waveform], and the position of each echo is calculated from the `RETURN_POINT_WAVEFORM_LOCATION` and
+/// `WAVEFORM_PARAMETERS` of the point, as defined by the LAS specification. If `skip_existing_returns` is `true`, echoes
+/// that are within one echo width of the return point location are skipped, since the point itself is already the
+/// discrete return for these echoes
+///
+/// # Errors
+///
+/// If `waveform_of` returns an error, this error is returned
+///
+/// # Panics
+///
+/// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute, a `RETURN_POINT_WAVEFORM_LOCATION`
+/// attribute with the datatype `F32` or a `WAVEFORM_PARAMETERS` attribute with the datatype `Vec3f32`.
+pub fn extract_echoes<T, F>(
+    buffer: &T,
+    mut waveform_of: F,
+    parameters: &WaveformDecompositionParameters,
+    skip_existing_returns: bool,
+) -> Result<Vec<WaveformEchoPoint>>
+where
+    T: PointBuffer + ?Sized,
+    F: FnMut(usize) -> Result<Option<Waveform>>,
+{
+    let positions = positions_of(buffer);
+    let return_locations = buffer
+        .iter_attribute::<f32>(&RETURN_POINT_WAVEFORM_LOCATION)
+        .collect::<Vec<_>>();
+    let directions = buffer
+        .iter_attribute::<Vector3<f32>>(&WAVEFORM_PARAMETERS)
+        .collect::<Vec<_>>();
+    let gps_times = if buffer.point_layout().has_attribute(&GPS_TIME) {
+        buffer.iter_attribute::<f64>(&GPS_TIME).collect::<Vec<_>>()
+    } else {
+        vec![0.0; positions.len()]
+    };
+
+    let mut echo_points = vec![];
+    for index in 0..positions.len() {
+        let waveform = match waveform_of(index)? {
+            Some(waveform) => waveform,
+            None => continue,
+        };
+        let return_location = return_locations[index] as f64;
+        let direction = Vector3::new(
+            directions[index].x as f64,
+            directions[index].y as f64,
+            directions[index].z as f64,
+        );
+        for echo in decompose_waveform(&waveform.samples, parameters) {
+            let echo_time = echo.location * waveform.temporal_sample_spacing;
+            let echo_width = echo.width * waveform.temporal_sample_spacing;
+            if skip_existing_returns && (echo_time - return_location).abs() <= echo_width {
+                continue;
+            }
+            echo_points.push(WaveformEchoPoint {
+                position: positions[index] + direction * (return_location - echo_time),
+                gps_time: gps_times[index],
+                source_point_index: index as u64,
+                amplitude: echo.amplitude as f32,
+                width: echo_width as f32,
+            });
+        }
+    }
+    Ok(echo_points)
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::{PerAttributeVecPointStorage, PointBufferWriteable, PointBufferWriteableExt},
+        layout::PointLayout,
+    };
+
+    use super::*;
+
+    fn two_echo_waveform() -> Vec<f64> {
+        let echoes = [
+            GaussianEcho {
+                location: 20.3,
+                amplitude: 100.0,
+                width: 2.0,
+            },
+            GaussianEcho {
+                location: 36.0,
+                amplitude: 40.0,
+                width: 1.5,
+            },
+        ];
+        (0..64)
+            .map(|index| {
+                5.0 + echoes
+                    .iter()
+                    .map(|echo| echo.evaluate(index as f64))
+                    .sum::<f64>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_decompose_waveform() {
+        let echoes = decompose_waveform(
+            &two_echo_waveform(),
+            &WaveformDecompositionParameters::default(),
+        );
+        assert_eq!(2, echoes.len());
+        assert!((echoes[0].location - 20.3).abs() < 0.05);
+        assert!((echoes[0].amplitude - 100.0).abs() < 1.0);
+        assert!((echoes[0].width - 2.0).abs() < 0.05);
+        assert!((echoes[1].location - 36.0).abs() < 0.05);
+        assert!((echoes[1].amplitude - 40.0).abs() < 1.0);
+
+        let echoes = decompose_waveform(
+            &two_echo_waveform(),
+            &WaveformDecompositionParameters {
+                max_echoes: 1,
+                ..Default::default()
+            },
+        );
+        assert_eq!(1, echoes.len());
+        assert!(decompose_waveform(&[], &Default::default()).is_empty());
+    }
+
+    #[test]
+    fn test_extract_echoes() -> Result<()> {
+        let mut buffer = PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[
+            POSITION_3D,
+            GPS_TIME,
+            RETURN_POINT_WAVEFORM_LOCATION,
+            WAVEFORM_PARAMETERS,
+        ]));
+        buffer.resize(2);
+        // The first echo is the discrete return of the point, at 20.3 samples of 1000 ps. The beam points downwards and
+        // travels 0.15 m per 1000 ps
+        for index in 0..2 {
+            buffer.set_attribute(&POSITION_3D, index, Vector3::new(0.0, 0.0, 10.0));
+            buffer.set_attribute(&GPS_TIME, index, 42.0 + index as f64);
+            buffer.set_attribute(&RETURN_POINT_WAVEFORM_LOCATION, index, 20300.0_f32);
+            buffer.set_attribute(
+                &WAVEFORM_PARAMETERS,
+                index,
+                Vector3::new(0.0_f32, 0.0, 0.00015),
+            );
+        }
+
+        let echoes = extract_echoes(
+            &buffer,
+            |index| {
+                Ok(match index {
+                    0 => Some(Waveform {
+                        samples: two_echo_waveform(),
+                        temporal_sample_spacing: 1000.0,
+                    }),
+                    _ => None,
+                })
+            },
+            &WaveformDecompositionParameters::default(),
+            true,
+        )?;
+        assert_eq!(1, echoes.len());
+        let echo = echoes[0];
+        assert_eq!(0, echo.source_point_index);
+        assert_eq!(42.0, echo.gps_time);
+        // 15.7 samples behind the return point
+        assert!((echo.position - Vector3::new(0.0, 0.0, 10.0 - 15.7 * 0.15)).norm() < 0.01);
+        assert!((echo.width - 1500.0).abs() < 50.0);
+        assert_eq!(
+            &WaveformEchoPoint::layout(),
+            &PointLayout::from_attributes(&[
+                POSITION_3D,
+                GPS_TIME,
+                SOURCE_POINT_INDEX,
+                ECHO_AMPLITUDE,
+                ECHO_WIDTH
+            ])
+        );
+        Ok(())
+    }
+}
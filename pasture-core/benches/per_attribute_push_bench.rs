@@ -0,0 +1,125 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use pasture_core::{
+    containers::{
+        InterleavedPointBuffer, InterleavedVecPointStorage, PerAttributePointBuffer,
+        PerAttributeVecPointStorage, PointBuffer, PointBufferWriteable,
+    },
+    layout::PointType,
+    nalgebra::Vector3,
+};
+use pasture_derive::PointType;
+use rand::{distributions::Uniform, thread_rng, Rng};
+
+const NUM_POINTS: usize = 100_000;
+
+#[derive(PointType, Default, Clone, Copy)]
+#[repr(C)]
+struct CustomPointTypeBig {
+    #[pasture(BUILTIN_GPS_TIME)]
+    pub gps_time: f64,
+    #[pasture(BUILTIN_COLOR_RGB)]
+    pub color: Vector3<u16>,
+    #[pasture(BUILTIN_POSITION_3D)]
+    pub position: Vector3<f64>,
+    #[pasture(BUILTIN_CLASSIFICATION)]
+    pub classification: u8,
+    #[pasture(BUILTIN_INTENSITY)]
+    pub intensity: i16,
+}
+
+fn random_custom_point_big<R: Rng + ?Sized>(rng: &mut R) -> CustomPointTypeBig {
+    CustomPointTypeBig {
+        gps_time: rng.sample(Uniform::new(0.0, 1000.0)),
+        color: Vector3::new(
+            rng.sample(Uniform::new(0, 1 << 14)),
+            rng.sample(Uniform::new(0, 1 << 14)),
+            rng.sample(Uniform::new(0, 1 << 14)),
+        ),
+        position: Vector3::new(
+            rng.sample(Uniform::new(-100.0, 100.0)),
+            rng.sample(Uniform::new(-100.0, 100.0)),
+            rng.sample(Uniform::new(-100.0, 100.0)),
+        ),
+        classification: rng.sample(Uniform::new(0, 8)),
+        intensity: rng.sample(Uniform::new(-256, 256)),
+    }
+}
+
+fn get_dummy_points() -> Vec<CustomPointTypeBig> {
+    let mut rng = thread_rng();
+    (0..NUM_POINTS)
+        .map(|_| random_custom_point_big(&mut rng))
+        .collect()
+}
+
+fn bench(c: &mut Criterion) {
+    let points = get_dummy_points();
+    let mut interleaved_points =
+        InterleavedVecPointStorage::with_capacity(NUM_POINTS, CustomPointTypeBig::layout());
+    interleaved_points.push_points(&points);
+    let mut per_attribute_points =
+        PerAttributeVecPointStorage::with_capacity(NUM_POINTS, CustomPointTypeBig::layout());
+    per_attribute_points.push_points(&points);
+    let layout = CustomPointTypeBig::layout();
+    let attribute_slices = layout
+        .attributes()
+        .map(|attribute| {
+            per_attribute_points.get_raw_attribute_range_ref(0..NUM_POINTS, &attribute.into())
+        })
+        .collect::<Vec<_>>();
+
+    let new_buffer = || PerAttributeVecPointStorage::new(CustomPointTypeBig::layout());
+
+    c.bench_function("per_attribute_push_point", |b| {
+        b.iter_batched(
+            new_buffer,
+            |mut buffer| {
+                for point in points.iter() {
+                    buffer.push_point(*point);
+                }
+                buffer.len()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("per_attribute_push_points", |b| {
+        b.iter_batched(
+            new_buffer,
+            |mut buffer| {
+                buffer.push_points(&points);
+                buffer.len()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("per_attribute_push_interleaved", |b| {
+        b.iter_batched(
+            new_buffer,
+            |mut buffer| {
+                buffer.push_interleaved(&interleaved_points as &dyn InterleavedPointBuffer);
+                buffer.len()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+
+    c.bench_function("per_attribute_push_points_from_slices", |b| {
+        b.iter_batched(
+            new_buffer,
+            |mut buffer| {
+                buffer.push_points_from_slices(&attribute_slices);
+                buffer.len()
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+criterion_group! {
+    name = per_attribute_push;
+    config = Criterion::default().sample_size(40);
+    targets = bench
+}
+criterion_main!(per_attribute_push);
@@ -4,8 +4,9 @@ use crate::layout::PointAttributeDefinition;
 use crate::layout::PrimitiveType;
 use crate::util::view_raw_bytes_mut;
 
-use std::marker::PhantomData;
-use std::mem::MaybeUninit;
+use alloc::{vec, vec::Vec};
+use core::marker::PhantomData;
+use core::mem::MaybeUninit;
 
 // The iterators for a single point attribute are implemented without macros, because we want them to return just T instead of a tuple (T)
 
@@ -50,7 +51,7 @@ pub mod attr1 {
         }
 
         fn refill_internal_buffer(&mut self) {
-            let remaining_points = std::cmp::min(
+            let remaining_points = core::cmp::min(
                 Self::INTERNAL_BUFFER_SIZE,
                 self.buffer_length - self.current_index,
             );
@@ -65,9 +66,9 @@ pub mod attr1 {
 
             let buffer_slice = &mut self.internal_buffer[0..remaining_points];
             let buffer_slice_untyped = unsafe {
-                std::slice::from_raw_parts_mut(
+                core::slice::from_raw_parts_mut(
                     buffer_slice.as_mut_ptr() as *mut u8,
-                    remaining_points * std::mem::size_of::<T>(),
+                    remaining_points * core::mem::size_of::<T>(),
                 )
             };
 
@@ -156,9 +157,9 @@ pub mod attr1 {
 
             let mut target_attribute = MaybeUninit::<T>::uninit();
             unsafe {
-                let target_attribute_byte_slice = std::slice::from_raw_parts_mut(
+                let target_attribute_byte_slice = core::slice::from_raw_parts_mut(
                     target_attribute.as_mut_ptr() as *mut u8,
-                    std::mem::size_of::<T>(),
+                    core::mem::size_of::<T>(),
                 );
                 self.buffer.get_raw_attribute(
                     self.current_index,
@@ -202,7 +203,7 @@ pub mod attr1 {
 
             let buffer_len = buffer.len();
             let attribute_data = unsafe {
-                std::slice::from_raw_parts(
+                core::slice::from_raw_parts(
                     buffer
                         .get_raw_attribute_range_ref(0..buffer_len, attribute)
                         .as_ptr() as *const T,
@@ -253,7 +254,7 @@ pub mod attr1 {
 
             let buffer_len = buffer.len();
             let attribute_data = unsafe {
-                std::slice::from_raw_parts_mut(
+                core::slice::from_raw_parts_mut(
                     buffer
                         .get_raw_attribute_range_mut(0..buffer_len, attribute)
                         .as_mut_ptr() as *mut T,
@@ -426,7 +427,7 @@ macro_rules! attributes_iter {
                     let buffer_len = buffer.len();
                     let attribute_data = (
                         $(unsafe {
-                        std::slice::from_raw_parts(
+                        core::slice::from_raw_parts(
                             buffer
                                 .get_raw_attribute_range_ref(0..buffer_len, attributes[$idx])
                                 .as_ptr() as *const $t,
@@ -472,7 +473,7 @@ macro_rules! attributes_iter {
                     let buffer_len = buffer.len();
                     let attribute_data = (
                         $(unsafe {
-                        std::slice::from_raw_parts_mut(
+                        core::slice::from_raw_parts_mut(
                             buffer
                                 .get_raw_attribute_range_mut(0..buffer_len, attributes[$idx])
                                 .as_ptr() as *mut $t,
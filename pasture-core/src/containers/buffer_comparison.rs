@@ -0,0 +1,562 @@
+use std::{collections::HashMap, convert::TryInto, fmt::Display};
+
+use crate::layout::{PointAttributeDataType, PointAttributeDefinition};
+
+use super::PointBuffer;
+
+/// Decodes the raw memory of a single attribute value with the given `datatype` into its components, converted to
+/// `f64`. Scalar attributes have a single component, vector and array attributes have one component per element.
+/// The components are written into `components`, which is cleared first
+///
+/// ```
+/// # use pasture_core::containers::decode_attribute_components;
+/// # use pasture_core::layout::PointAttributeDataType;
+/// let mut components = vec![];
+/// decode_attribute_components(PointAttributeDataType::U16, &42_u16.to_ne_bytes(), &mut components);
+/// assert_eq!(vec![42.0], components);
+/// ```
+///
+/// # Panics
+///
+/// If the size of `bytes` does not match the size of `datatype`
+pub fn decode_attribute_components(
+    datatype: PointAttributeDataType,
+    bytes: &[u8],
+    components: &mut Vec<f64>,
+) {
+    if bytes.len() != datatype.size() as usize {
+        panic!(
+            "decode_attribute_components: Expected {} bytes for datatype {}, but got {}",
+            datatype.size(),
+            datatype,
+            bytes.len()
+        );
+    }
+    components.clear();
+    let (component_type, _) = datatype.components();
+    let component_size = component_type.size() as usize;
+    components.extend(
+        bytes
+            .chunks_exact(component_size)
+            .map(|component| match component_type {
+                PointAttributeDataType::U8 => component[0] as f64,
+                PointAttributeDataType::I8 => component[0] as i8 as f64,
+                PointAttributeDataType::Bool => {
+                    if component[0] != 0 {
+                        1.0
+                    } else {
+                        0.0
+                    }
+                }
+                PointAttributeDataType::U16 => {
+                    u16::from_ne_bytes(component.try_into().unwrap()) as f64
+                }
+                PointAttributeDataType::I16 => {
+                    i16::from_ne_bytes(component.try_into().unwrap()) as f64
+                }
+                PointAttributeDataType::U32 => {
+                    u32::from_ne_bytes(component.try_into().unwrap()) as f64
+                }
+                PointAttributeDataType::I32 => {
+                    i32::from_ne_bytes(component.try_into().unwrap()) as f64
+                }
+                PointAttributeDataType::U64 => {
+                    u64::from_ne_bytes(component.try_into().unwrap()) as f64
+                }
+                PointAttributeDataType::I64 => {
+                    i64::from_ne_bytes(component.try_into().unwrap()) as f64
+                }
+                PointAttributeDataType::F32 => {
+                    f32::from_ne_bytes(component.try_into().unwrap()) as f64
+                }
+                PointAttributeDataType::F64 => f64::from_ne_bytes(component.try_into().unwrap()),
+                _ => unreachable!("Components of a datatype are scalars"),
+            }),
+    );
+}
+
+/// Returns the largest absolute difference between the components of two attribute values, or `None` if the values
+/// have a different number of components and can't be compared. Two NaN components are considered equal, a NaN
+/// component and a number have an infinite difference
+pub fn max_component_difference(first: &[f64], second: &[f64]) -> Option<f64> {
+    if first.len() != second.len() {
+        return None;
+    }
+    let max_difference = first
+        .iter()
+        .zip(second.iter())
+        .map(|(a, b)| {
+            if a == b || (a.is_nan() && b.is_nan()) {
+                0.0
+            } else if a.is_nan() || b.is_nan() {
+                f64::INFINITY
+            } else {
+                (a - b).abs()
+            }
+        })
+        .fold(0.0, f64::max);
+    Some(max_difference)
+}
+
+/// Returns `true` if the two buffers have the same `PointLayout`, the same number of points and bitwise identical
+/// values for all attributes. Padding bytes between the attributes are ignored
+///
+/// ```
+/// # use pasture_core::containers::*;
+/// # use pasture_core::layout::*;
+/// let layout = PointLayout::from_attributes(&[attributes::INTENSITY]);
+/// let mut first = InterleavedVecPointStorage::new(layout.clone());
+/// first.resize(1);
+/// first.set_attribute(&attributes::INTENSITY, 0, 42_u16);
+/// let mut second = PerAttributeVecPointStorage::new(layout);
+/// second.resize(1);
+/// second.set_attribute(&attributes::INTENSITY, 0, 42_u16);
+/// assert!(buffers_equal(&first, &second));
+/// ```
+pub fn buffers_equal(first: &dyn PointBuffer, second: &dyn PointBuffer) -> bool {
+    if first.len() != second.len() || first.point_layout() != second.point_layout() {
+        return false;
+    }
+    let mut first_bytes = vec![];
+    let mut second_bytes = vec![];
+    first.point_layout().attributes().all(|attribute| {
+        let attribute: PointAttributeDefinition = attribute.into();
+        first_bytes.resize(attribute.size() as usize, 0);
+        second_bytes.resize(attribute.size() as usize, 0);
+        (0..first.len()).all(|index| {
+            first.get_raw_attribute(index, &attribute, &mut first_bytes);
+            second.get_raw_attribute(index, &attribute, &mut second_bytes);
+            first_bytes == second_bytes
+        })
+    })
+}
+
+/// A single attribute value that differs between two buffers by more than the tolerance
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeMismatch {
+    /// Index of the point
+    pub point_index: usize,
+    /// Name of the attribute
+    pub attribute_name: &'static str,
+    /// Components of the value in the first buffer
+    pub first: Vec<f64>,
+    /// Components of the value in the second buffer
+    pub second: Vec<f64>,
+}
+
+impl Display for AttributeMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Point {}: {} differs: {} vs. {}",
+            self.point_index,
+            self.attribute_name,
+            format_components(&self.first),
+            format_components(&self.second)
+        )
+    }
+}
+
+fn format_components(components: &[f64]) -> String {
+    if components.len() == 1 {
+        format!("{}", components[0])
+    } else {
+        format!(
+            "({})",
+            components
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Comparison results for a single attribute that both buffers have in common
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeComparison {
+    /// The attribute in the first buffer
+    pub first_attribute: PointAttributeDefinition,
+    /// The attribute with the same name in the second buffer. Its datatype may differ from the datatype of
+    /// `first_attribute`, in which case the values are compared after converting them to `f64`
+    pub second_attribute: PointAttributeDefinition,
+    /// Maximum absolute difference at which two values are considered equal
+    pub tolerance: f64,
+    /// Number of points whose values differ by more than `tolerance`
+    pub mismatch_count: usize,
+    /// Index of the first point whose values differ by more than `tolerance`
+    pub first_mismatch: Option<usize>,
+    /// Largest absolute difference between the values of all points
+    pub max_difference: f64,
+}
+
+impl AttributeComparison {
+    /// Are the values of all points equal within the tolerance?
+    pub fn is_match(&self) -> bool {
+        self.mismatch_count == 0
+    }
+}
+
+/// Detailed result of comparing two buffers with a [BufferComparison]
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonReport {
+    /// Number of points in the first buffer
+    pub first_len: usize,
+    /// Number of points in the second buffer
+    pub second_len: usize,
+    /// Attributes that only exist in the first buffer
+    pub only_in_first: Vec<PointAttributeDefinition>,
+    /// Attributes that only exist in the second buffer
+    pub only_in_second: Vec<PointAttributeDefinition>,
+    /// Comparison results for all attributes that both buffers have in common, in the order of the attributes in the
+    /// first buffer
+    pub attributes: Vec<AttributeComparison>,
+    /// The first mismatching values, up to the maximum number of reported mismatches of the `BufferComparison`
+    pub mismatches: Vec<AttributeMismatch>,
+}
+
+impl ComparisonReport {
+    /// Are the two buffers equal within the tolerances? This requires that both buffers have the same number of points
+    /// and the same attributes
+    pub fn is_match(&self) -> bool {
+        self.first_len == self.second_len
+            && self.only_in_first.is_empty()
+            && self.only_in_second.is_empty()
+            && self.attributes.iter().all(AttributeComparison::is_match)
+    }
+
+    /// Total number of mismatching values over all attributes
+    pub fn mismatch_count(&self) -> usize {
+        self.attributes
+            .iter()
+            .map(|attribute| attribute.mismatch_count)
+            .sum()
+    }
+}
+
+impl Display for ComparisonReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.first_len != self.second_len {
+            writeln!(
+                f,
+                "Point counts differ: {} vs. {}",
+                self.first_len, self.second_len
+            )?;
+        }
+        for attribute in self.only_in_first.iter() {
+            writeln!(f, "Attribute {} only exists in the first buffer", attribute)?;
+        }
+        for attribute in self.only_in_second.iter() {
+            writeln!(
+                f,
+                "Attribute {} only exists in the second buffer",
+                attribute
+            )?;
+        }
+        for mismatch in self.mismatches.iter() {
+            writeln!(f, "{}", mismatch)?;
+        }
+        for attribute in self.attributes.iter() {
+            match attribute.first_mismatch {
+                None => writeln!(
+                    f,
+                    "{}: equal (max difference {})",
+                    attribute.first_attribute.name(),
+                    attribute.max_difference
+                )?,
+                Some(first_mismatch) => writeln!(
+                    f,
+                    "{}: {} mismatches, first at point {}, max difference {} (tolerance {})",
+                    attribute.first_attribute.name(),
+                    attribute.mismatch_count,
+                    first_mismatch,
+                    attribute.max_difference,
+                    attribute.tolerance
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Compares two buffers attribute by attribute with configurable tolerances. Attributes are matched by name, so the
+/// buffers can have different `PointLayout`s. If the datatypes of an attribute differ between the buffers, the values
+/// are compared after converting each component to `f64`. Only the first `min(first.len(), second.len())` points are
+/// compared
+///
+/// ```
+/// # use pasture_core::containers::*;
+/// # use pasture_core::layout::*;
+/// let layout = PointLayout::from_attributes(&[attributes::GPS_TIME]);
+/// let mut first = PerAttributeVecPointStorage::new(layout.clone());
+/// first.resize(1);
+/// first.set_attribute(&attributes::GPS_TIME, 0, 1.0_f64);
+/// let mut second = PerAttributeVecPointStorage::new(layout);
+/// second.resize(1);
+/// second.set_attribute(&attributes::GPS_TIME, 0, 1.0001_f64);
+///
+/// assert!(!BufferComparison::new().compare(&first, &second).is_match());
+/// let report = BufferComparison::new()
+///     .with_attribute_tolerance(attributes::GPS_TIME.name(), 0.001)
+///     .compare(&first, &second);
+/// assert!(report.is_match());
+/// ```
+#[derive(Debug, Clone)]
+pub struct BufferComparison {
+    tolerance: f64,
+    attribute_tolerances: HashMap<String, f64>,
+    max_reported_mismatches: usize,
+}
+
+impl BufferComparison {
+    /// Creates a new `BufferComparison` that compares exactly and reports up to 10 mismatching values
+    pub fn new() -> Self {
+        Self {
+            tolerance: 0.0,
+            attribute_tolerances: HashMap::new(),
+            max_reported_mismatches: 10,
+        }
+    }
+
+    /// Sets the maximum absolute difference at which two values of any attribute are still considered equal
+    ///
+    /// # Panics
+    ///
+    /// If `tolerance` is negative or NaN
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        if tolerance < 0.0 || tolerance.is_nan() {
+            panic!("BufferComparison::with_tolerance: tolerance must be >= 0");
+        }
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the tolerance for the attribute with the given name, overriding the tolerance set with `with_tolerance`
+    ///
+    /// # Panics
+    ///
+    /// If `tolerance` is negative or NaN
+    pub fn with_attribute_tolerance(mut self, attribute_name: &str, tolerance: f64) -> Self {
+        if tolerance < 0.0 || tolerance.is_nan() {
+            panic!("BufferComparison::with_attribute_tolerance: tolerance must be >= 0");
+        }
+        self.attribute_tolerances
+            .insert(attribute_name.to_owned(), tolerance);
+        self
+    }
+
+    /// Sets the maximum number of mismatching values that are stored in the `ComparisonReport`. All mismatches are
+    /// still counted
+    pub fn with_max_reported_mismatches(mut self, max_reported_mismatches: usize) -> Self {
+        self.max_reported_mismatches = max_reported_mismatches;
+        self
+    }
+
+    /// Returns the tolerance for the attribute with the given name
+    pub fn tolerance_for(&self, attribute_name: &str) -> f64 {
+        self.attribute_tolerances
+            .get(attribute_name)
+            .copied()
+            .unwrap_or(self.tolerance)
+    }
+
+    /// Compares the two buffers and returns a detailed report of all differences
+    pub fn compare(&self, first: &dyn PointBuffer, second: &dyn PointBuffer) -> ComparisonReport {
+        let first_layout = first.point_layout();
+        let second_layout = second.point_layout();
+        let mut report = ComparisonReport {
+            first_len: first.len(),
+            second_len: second.len(),
+            only_in_first: first_layout
+                .attributes()
+                .filter(|attribute| !second_layout.has_attribute_with_name(attribute.name()))
+                .map(|attribute| attribute.into())
+                .collect(),
+            only_in_second: second_layout
+                .attributes()
+                .filter(|attribute| !first_layout.has_attribute_with_name(attribute.name()))
+                .map(|attribute| attribute.into())
+                .collect(),
+            attributes: vec![],
+            mismatches: vec![],
+        };
+
+        let count = first.len().min(second.len());
+        let mut first_bytes = vec![];
+        let mut second_bytes = vec![];
+        let mut first_components = vec![];
+        let mut second_components = vec![];
+        for attribute in first_layout.attributes() {
+            let second_attribute = match second_layout.get_attribute_by_name(attribute.name()) {
+                Some(second_attribute) => second_attribute,
+                None => continue,
+            };
+            let mut comparison = AttributeComparison {
+                first_attribute: attribute.into(),
+                second_attribute: second_attribute.into(),
+                tolerance: self.tolerance_for(attribute.name()),
+                mismatch_count: 0,
+                first_mismatch: None,
+                max_difference: 0.0,
+            };
+            let same_datatype = attribute.datatype() == second_attribute.datatype();
+            first_bytes.resize(attribute.size() as usize, 0);
+            second_bytes.resize(second_attribute.size() as usize, 0);
+
+            for index in 0..count {
+                first.get_raw_attribute(index, &comparison.first_attribute, &mut first_bytes);
+                second.get_raw_attribute(index, &comparison.second_attribute, &mut second_bytes);
+                // Identical memory means identical values
+                if same_datatype && first_bytes == second_bytes {
+                    continue;
+                }
+
+                decode_attribute_components(
+                    attribute.datatype(),
+                    &first_bytes,
+                    &mut first_components,
+                );
+                decode_attribute_components(
+                    second_attribute.datatype(),
+                    &second_bytes,
+                    &mut second_components,
+                );
+                let difference = max_component_difference(&first_components, &second_components)
+                    .unwrap_or(f64::INFINITY);
+                comparison.max_difference = comparison.max_difference.max(difference);
+                if difference <= comparison.tolerance {
+                    continue;
+                }
+
+                comparison.mismatch_count += 1;
+                if comparison.first_mismatch.is_none() {
+                    comparison.first_mismatch = Some(index);
+                }
+                if report.mismatches.len() < self.max_reported_mismatches {
+                    report.mismatches.push(AttributeMismatch {
+                        point_index: index,
+                        attribute_name: attribute.name(),
+                        first: first_components.clone(),
+                        second: second_components.clone(),
+                    });
+                }
+            }
+
+            report.attributes.push(comparison);
+        }
+
+        report
+    }
+}
+
+impl Default for BufferComparison {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::containers::{
+        InterleavedVecPointStorage, PerAttributeVecPointStorage, PointBufferWriteable,
+        PointBufferWriteableExt,
+    };
+    use crate::layout::{attributes, PointLayout, PointType};
+    use nalgebra::Vector3;
+    use pasture_derive::PointType;
+
+    #[derive(PointType, Debug, Copy, Clone)]
+    #[repr(C)]
+    struct TestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        pub intensity: u16,
+    }
+
+    fn test_points() -> Vec<TestPoint> {
+        (0..4)
+            .map(|index| TestPoint {
+                position: Vector3::new(index as f64, 1.0, 2.0),
+                intensity: index as u16 * 10,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_buffers_equal() {
+        let mut interleaved = InterleavedVecPointStorage::new(TestPoint::layout());
+        interleaved.push_points(&test_points());
+        let mut per_attribute = PerAttributeVecPointStorage::new(TestPoint::layout());
+        per_attribute.push_points(&test_points());
+        assert!(buffers_equal(&interleaved, &per_attribute));
+
+        per_attribute.set_attribute(&attributes::INTENSITY, 2, 21_u16);
+        assert!(!buffers_equal(&interleaved, &per_attribute));
+
+        let mut shorter = InterleavedVecPointStorage::new(TestPoint::layout());
+        shorter.push_points(&test_points()[..3]);
+        assert!(!buffers_equal(&interleaved, &shorter));
+    }
+
+    #[test]
+    fn test_compare_with_tolerance() {
+        let mut first = InterleavedVecPointStorage::new(TestPoint::layout());
+        first.push_points(&test_points());
+        let mut second = InterleavedVecPointStorage::new(TestPoint::layout());
+        second.push_points(&test_points());
+        second.set_attribute(&attributes::POSITION_3D, 1, Vector3::new(1.01, 1.0, 2.0));
+        second.set_attribute(&attributes::INTENSITY, 3, 31_u16);
+
+        let report = BufferComparison::new().compare(&first, &second);
+        assert!(!report.is_match());
+        assert_eq!(2, report.mismatch_count());
+        assert_eq!(Some(1), report.attributes[0].first_mismatch);
+        assert_eq!(Some(3), report.attributes[1].first_mismatch);
+        assert_eq!(
+            AttributeMismatch {
+                point_index: 3,
+                attribute_name: attributes::INTENSITY.name(),
+                first: vec![30.0],
+                second: vec![31.0],
+            },
+            report.mismatches[1]
+        );
+
+        let report = BufferComparison::new()
+            .with_tolerance(0.1)
+            .with_attribute_tolerance(attributes::INTENSITY.name(), 1.0)
+            .compare(&first, &second);
+        assert!(report.is_match(), "{}", report);
+        assert!((report.attributes[0].max_difference - 0.01).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_compare_different_layouts() {
+        let mut first = InterleavedVecPointStorage::new(TestPoint::layout());
+        first.push_points(&test_points());
+
+        let second_layout = PointLayout::from_attributes(&[
+            attributes::INTENSITY.with_custom_datatype(PointAttributeDataType::U32)
+        ]);
+        let mut second = PerAttributeVecPointStorage::new(second_layout);
+        second.resize(4);
+        for (index, point) in test_points().iter().enumerate() {
+            second.set_attribute(
+                &attributes::INTENSITY.with_custom_datatype(PointAttributeDataType::U32),
+                index,
+                point.intensity as u32,
+            );
+        }
+
+        let report = BufferComparison::new()
+            .with_max_reported_mismatches(0)
+            .compare(&first, &second);
+        assert!(!report.is_match());
+        assert_eq!(0, report.mismatch_count());
+        assert_eq!(1, report.only_in_first.len());
+        assert!(report.only_in_second.is_empty());
+        assert_eq!(1, report.attributes.len());
+    }
+}
@@ -0,0 +1,307 @@
+use std::{marker::PhantomData, ops::Range};
+
+use crate::{
+    layout::{FieldAlignment, PointAttributeDefinition, PointLayout, PrimitiveType},
+    util::view_raw_bytes,
+};
+
+use super::PointBuffer;
+
+/// A view over a `PointBuffer` that adds a derived attribute, such as the height above a plane, the distance to the
+/// origin or a normalized intensity. The values of the derived attribute are computed on the fly from the other
+/// attributes of the buffer whenever they are accessed, so no new column is materialized. Since the view is a
+/// `PointBuffer` itself, it can be passed to writers, iterated with the extension traits and used for statistics like
+/// any other buffer.
+///
+/// ```
+/// # use pasture_core::containers::*;
+/// # use pasture_core::layout::*;
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_derive::PointType;
+/// #[repr(C)]
+/// #[derive(PointType, Clone, Copy, Debug)]
+/// struct MyPointType {
+///   #[pasture(BUILTIN_POSITION_3D)]
+///   pub position: Vector3<f64>,
+/// }
+///
+/// let points = InterleavedVecPointStorage::from(
+///     vec![MyPointType { position: Vector3::new(3.0, 4.0, 0.0) }].as_slice(),
+/// );
+/// let distance = PointAttributeDefinition::custom("DistanceToOrigin", PointAttributeDataType::F64);
+/// let view = ComputedAttributeView::new(&points, &distance, |points, index| {
+///     points
+///         .get_attribute::<Vector3<f64>>(&attributes::POSITION_3D, index)
+///         .norm()
+/// });
+/// assert_eq!(5.0, view.get_attribute::<f64>(&distance, 0));
+/// ```
+pub struct ComputedAttributeView<'a, B, T, F>
+where
+    B: PointBuffer + ?Sized,
+    T: PrimitiveType,
+    F: Fn(&B, usize) -> T,
+{
+    buffer: &'a B,
+    attribute: PointAttributeDefinition,
+    point_layout: PointLayout,
+    compute: F,
+    _unused: PhantomData<T>,
+}
+
+impl<'a, B, T, F> ComputedAttributeView<'a, B, T, F>
+where
+    B: PointBuffer + ?Sized,
+    T: PrimitiveType,
+    F: Fn(&B, usize) -> T,
+{
+    /// Creates a new `ComputedAttributeView` over `buffer` that adds the given `attribute`. The value of `attribute`
+    /// for the point at an index is computed by calling `compute` with `buffer` and the index. The `PointLayout` of the
+    /// view contains all attributes of `buffer` at the same offsets, followed by `attribute`
+    ///
+    /// # Panics
+    ///
+    /// If the datatype of `attribute` is not the datatype of `T`.<br>
+    /// If `buffer` already has an attribute with the name of `attribute`
+    pub fn new(buffer: &'a B, attribute: &PointAttributeDefinition, compute: F) -> Self {
+        if attribute.datatype() != T::data_type() {
+            panic!(
+                "ComputedAttributeView::new: Datatype of attribute {} does not match the computed type {}",
+                attribute,
+                T::data_type()
+            );
+        }
+        if buffer
+            .point_layout()
+            .has_attribute_with_name(attribute.name())
+        {
+            panic!(
+                "ComputedAttributeView::new: Attribute {} is already part of the PointLayout of the buffer",
+                attribute
+            );
+        }
+
+        let mut point_layout = buffer.point_layout().clone();
+        point_layout.add_attribute(attribute.clone(), FieldAlignment::Default);
+
+        Self {
+            buffer,
+            attribute: attribute.clone(),
+            point_layout,
+            compute,
+            _unused: Default::default(),
+        }
+    }
+
+    /// Returns the computed attribute of this view
+    pub fn computed_attribute(&self) -> &PointAttributeDefinition {
+        &self.attribute
+    }
+
+    /// Returns the value of the computed attribute for the point at `point_index`
+    pub fn compute(&self, point_index: usize) -> T {
+        (self.compute)(self.buffer, point_index)
+    }
+
+    /// Returns the underlying buffer of this view
+    pub fn buffer(&self) -> &'a B {
+        self.buffer
+    }
+
+    fn get_computed_raw_attribute(&self, point_index: usize, buf: &mut [u8]) {
+        let value = self.compute(point_index);
+        let value_bytes = unsafe { view_raw_bytes(&value) };
+        buf[..value_bytes.len()].copy_from_slice(value_bytes);
+    }
+}
+
+impl<'a, B, T, F> PointBuffer for ComputedAttributeView<'a, B, T, F>
+where
+    B: PointBuffer + ?Sized,
+    T: PrimitiveType,
+    F: Fn(&B, usize) -> T,
+{
+    fn get_raw_point(&self, point_index: usize, buf: &mut [u8]) {
+        let source_point_size = self.buffer.point_layout().size_of_point_entry() as usize;
+        self.buffer
+            .get_raw_point(point_index, &mut buf[..source_point_size]);
+
+        let computed_attribute = self
+            .point_layout
+            .get_attribute_by_name(self.attribute.name())
+            .expect("Computed attribute is missing in PointLayout of view");
+        let offset = computed_attribute.offset() as usize;
+        let size = computed_attribute.size() as usize;
+        self.get_computed_raw_attribute(point_index, &mut buf[offset..offset + size]);
+    }
+
+    fn get_raw_attribute(
+        &self,
+        point_index: usize,
+        attribute: &PointAttributeDefinition,
+        buf: &mut [u8],
+    ) {
+        if attribute.name() != self.attribute.name() {
+            self.buffer.get_raw_attribute(point_index, attribute, buf);
+            return;
+        }
+        if attribute.datatype() != self.attribute.datatype() {
+            panic!(
+                "Attribute {} does not match the computed attribute {} of this view",
+                attribute, self.attribute
+            );
+        }
+        if point_index >= self.len() {
+            panic!("Point index {} out of bounds!", point_index);
+        }
+        self.get_computed_raw_attribute(point_index, buf);
+    }
+
+    fn get_raw_points(&self, index_range: Range<usize>, buf: &mut [u8]) {
+        let point_size = self.point_layout.size_of_point_entry() as usize;
+        for (point_index, point_buf) in index_range.zip(buf.chunks_exact_mut(point_size)) {
+            self.get_raw_point(point_index, point_buf);
+        }
+    }
+
+    fn get_raw_attribute_range(
+        &self,
+        index_range: Range<usize>,
+        attribute: &PointAttributeDefinition,
+        buf: &mut [u8],
+    ) {
+        if attribute.name() != self.attribute.name() {
+            self.buffer
+                .get_raw_attribute_range(index_range, attribute, buf);
+            return;
+        }
+        let attribute_size = attribute.size() as usize;
+        for (point_index, attribute_buf) in index_range.zip(buf.chunks_exact_mut(attribute_size)) {
+            self.get_raw_attribute(point_index, attribute, attribute_buf);
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    fn point_layout(&self) -> &PointLayout {
+        &self.point_layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector3;
+    use pasture_derive::PointType;
+
+    use super::*;
+    use crate::{
+        containers::{
+            buffers_equal, InterleavedVecPointStorage, PerAttributeVecPointStorage, PointBufferExt,
+        },
+        layout::{attributes, PointAttributeDataType, PointType},
+    };
+
+    // We need this, otherwise we can't use the derive(PointType) macro from within pasture_core because the macro
+    // doesn't recognize the name 'pasture_core' :/
+    use crate as pasture_core;
+
+    #[derive(Debug, Copy, Clone, PartialEq, PointType)]
+    #[repr(C)]
+    struct TestPointType {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        pub intensity: u16,
+    }
+
+    #[derive(Debug, Copy, Clone, PartialEq, PointType)]
+    #[repr(C)]
+    struct TestPointTypeWithHeight {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        pub intensity: u16,
+        #[pasture(attribute = "Height")]
+        pub height: f64,
+    }
+
+    fn test_points() -> Vec<TestPointType> {
+        vec![
+            TestPointType {
+                position: Vector3::new(1.0, 2.0, 3.0),
+                intensity: 100,
+            },
+            TestPointType {
+                position: Vector3::new(4.0, 5.0, 6.0),
+                intensity: 200,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_computed_attribute_view() {
+        const HEIGHT: PointAttributeDefinition =
+            PointAttributeDefinition::custom("Height", PointAttributeDataType::F64);
+
+        let points = PerAttributeVecPointStorage::from(test_points().as_slice());
+        let view = ComputedAttributeView::new(&points, &HEIGHT, |points, index| {
+            points
+                .get_attribute::<Vector3<f64>>(&attributes::POSITION_3D, index)
+                .z
+                - 1.0
+        });
+
+        assert_eq!(2, view.len());
+        assert_eq!(&TestPointTypeWithHeight::layout(), view.point_layout());
+        assert_eq!(
+            vec![2.0, 5.0],
+            view.iter_attribute::<f64>(&HEIGHT).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![100, 200],
+            view.iter_attribute::<u16>(&attributes::INTENSITY)
+                .collect::<Vec<_>>()
+        );
+
+        let expected_points = vec![
+            TestPointTypeWithHeight {
+                position: Vector3::new(1.0, 2.0, 3.0),
+                intensity: 100,
+                height: 2.0,
+            },
+            TestPointTypeWithHeight {
+                position: Vector3::new(4.0, 5.0, 6.0),
+                intensity: 200,
+                height: 5.0,
+            },
+        ];
+        assert_eq!(
+            expected_points,
+            view.iter_point::<TestPointTypeWithHeight>()
+                .collect::<Vec<_>>()
+        );
+
+        let expected_buffer = InterleavedVecPointStorage::from(expected_points.as_slice());
+        assert!(buffers_equal(&view, &expected_buffer));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_computed_attribute_view_wrong_datatype() {
+        let points = InterleavedVecPointStorage::from(test_points().as_slice());
+        let normalized_intensity =
+            PointAttributeDefinition::custom("NormalizedIntensity", PointAttributeDataType::F64);
+        ComputedAttributeView::new(&points, &normalized_intensity, |points, index| {
+            points.get_attribute::<u16>(&attributes::INTENSITY, index) as f32 / 65535.0
+        });
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_computed_attribute_view_existing_attribute() {
+        let points = InterleavedVecPointStorage::from(test_points().as_slice());
+        ComputedAttributeView::new(&points, &attributes::INTENSITY, |_, _| 0_u16);
+    }
+}
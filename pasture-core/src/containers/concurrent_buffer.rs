@@ -0,0 +1,333 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex, PoisonError,
+};
+
+use crate::layout::{PointLayout, PointType};
+
+use super::{InterleavedVecPointStorage, PointBuffer, PointBufferWriteable};
+
+/// Default number of shards of a [ConcurrentAppendBuffer]
+pub const DEFAULT_SHARD_COUNT: usize = 16;
+
+/// Default number of points that a [ConcurrentAppendWriter] collects before it hands them over to its
+/// [ConcurrentAppendBuffer]
+pub const DEFAULT_SEGMENT_CAPACITY: usize = 65_536;
+
+/// Points of a single [ConcurrentAppendWriter] that were handed over to the [ConcurrentAppendBuffer]. Segments are
+/// ordered by the key of their writer first and the order in which the writer handed them over second
+struct Segment {
+    key: usize,
+    sequence: usize,
+    points: InterleavedVecPointStorage,
+}
+
+/// A buffer that multiple threads can append points to at the same time, e.g. parallel readers for the chunks of a
+/// file or parallel point generators that all write into a single point cloud. Each thread appends through its own
+/// [ConcurrentAppendWriter], which collects points into a local segment without any synchronization. Full segments are
+/// handed over to one of several shards of the buffer, which is the only point where a lock is taken, so threads rarely
+/// wait for each other. Once all writers are dropped, [into_interleaved](ConcurrentAppendBuffer::into_interleaved)
+/// consolidates all segments into a single `InterleavedVecPointStorage`.
+///
+/// The points of each writer stay in the order in which they were pushed. Writers that are created with
+/// [writer_with_key](ConcurrentAppendBuffer::writer_with_key) are ordered by their key, so e.g. parallel readers can use
+/// the chunk index as key to get the points in the order of the file, regardless of which thread finished first
+/// ```
+/// # use pasture_core::containers::*;
+/// # use pasture_core::layout::*;
+/// # use pasture_derive::PointType;
+/// #[repr(C)]
+/// #[derive(PointType)]
+/// struct Point(#[pasture(BUILTIN_INTENSITY)] u16);
+///
+/// let buffer = std::sync::Arc::new(ConcurrentAppendBuffer::new(Point::layout()));
+/// let threads = (0..4)
+///     .map(|chunk| {
+///         let buffer = buffer.clone();
+///         std::thread::spawn(move || {
+///             let mut writer = buffer.writer_with_key(chunk);
+///             for index in 0..100 {
+///                 writer.push_point(Point((chunk * 100 + index) as u16));
+///             }
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// for thread in threads {
+///     thread.join().unwrap();
+/// }
+/// let buffer = std::sync::Arc::try_unwrap(buffer).ok().unwrap();
+/// let points = buffer.into_interleaved();
+/// assert_eq!(400, points.len());
+/// assert_eq!(123, points.get_attribute::<u16>(&attributes::INTENSITY, 123));
+/// ```
+pub struct ConcurrentAppendBuffer {
+    layout: PointLayout,
+    shards: Vec<Mutex<Vec<Segment>>>,
+    segment_capacity: usize,
+    next_key: AtomicUsize,
+    len: AtomicUsize,
+}
+
+impl ConcurrentAppendBuffer {
+    /// Creates a new empty `ConcurrentAppendBuffer` for points with the given `PointLayout`, with [DEFAULT_SHARD_COUNT]
+    /// shards and writers that hand over segments of [DEFAULT_SEGMENT_CAPACITY] points
+    pub fn new(layout: PointLayout) -> Self {
+        Self::with_shards_and_segment_capacity(
+            layout,
+            DEFAULT_SHARD_COUNT,
+            DEFAULT_SEGMENT_CAPACITY,
+        )
+    }
+
+    /// Creates a new empty `ConcurrentAppendBuffer` for points with the given `PointLayout`, with `shard_count` shards
+    /// and writers that hand over segments of `segment_capacity` points. More shards mean less contention if many
+    /// threads write at the same time, larger segments mean fewer hand-overs but more memory per writer
+    ///
+    /// # Panics
+    ///
+    /// If `shard_count` or `segment_capacity` is zero
+    pub fn with_shards_and_segment_capacity(
+        layout: PointLayout,
+        shard_count: usize,
+        segment_capacity: usize,
+    ) -> Self {
+        if shard_count == 0 {
+            panic!("ConcurrentAppendBuffer::with_shards_and_segment_capacity: shard_count must be greater than zero");
+        }
+        if segment_capacity == 0 {
+            panic!("ConcurrentAppendBuffer::with_shards_and_segment_capacity: segment_capacity must be greater than zero");
+        }
+        Self {
+            layout,
+            shards: (0..shard_count).map(|_| Mutex::new(vec![])).collect(),
+            segment_capacity,
+            next_key: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Returns the `PointLayout` of the points in this buffer
+    pub fn point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    /// Returns the number of points that writers have handed over to this buffer so far. Points that are still in the
+    /// local segment of a writer are not included
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns true if no writer has handed over any points to this buffer so far
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns a new writer for appending points to this buffer. The points of writers created with this method are
+    /// ordered by the creation of the writers, which is unspecified if the writers are created from different threads.
+    /// Don't mix this with [writer_with_key](ConcurrentAppendBuffer::writer_with_key) on the same buffer
+    pub fn writer(&self) -> ConcurrentAppendWriter<'_> {
+        self.writer_with_key(self.next_key.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Returns a new writer for appending points to this buffer, whose points come before the points of all writers
+    /// with a larger `key` in [into_interleaved](ConcurrentAppendBuffer::into_interleaved). If multiple writers use the
+    /// same key, the order of their points is unspecified
+    pub fn writer_with_key(&self, key: usize) -> ConcurrentAppendWriter<'_> {
+        ConcurrentAppendWriter {
+            buffer: self,
+            key,
+            next_sequence: 0,
+            points: InterleavedVecPointStorage::with_capacity(
+                self.segment_capacity,
+                self.layout.clone(),
+            ),
+        }
+    }
+
+    /// Consolidates all segments of this buffer into a single `InterleavedVecPointStorage`, ordered by the keys of the
+    /// writers that appended them. All writers have to be dropped before, which the borrow checker enforces
+    pub fn into_interleaved(self) -> InterleavedVecPointStorage {
+        let mut segments = self
+            .shards
+            .into_iter()
+            .flat_map(|shard| shard.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .collect::<Vec<_>>();
+        segments.sort_by_key(|segment| (segment.key, segment.sequence));
+        if segments.len() == 1 {
+            return segments.pop().unwrap().points;
+        }
+
+        let count = segments.iter().map(|segment| segment.points.len()).sum();
+        let mut points = InterleavedVecPointStorage::with_capacity(count, self.layout);
+        for segment in &segments {
+            points.push(&segment.points);
+        }
+        points
+    }
+
+    fn hand_over(&self, segment: Segment) {
+        let count = segment.points.len();
+        let shard = &self.shards[segment.key % self.shards.len()];
+        shard
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(segment);
+        self.len.fetch_add(count, Ordering::AcqRel);
+    }
+}
+
+/// Writer that appends points to a [ConcurrentAppendBuffer] from a single thread. Points are collected in a local
+/// segment, which is handed over to the buffer once it is full, on [flush](ConcurrentAppendWriter::flush) and when the
+/// writer is dropped
+pub struct ConcurrentAppendWriter<'a> {
+    buffer: &'a ConcurrentAppendBuffer,
+    key: usize,
+    next_sequence: usize,
+    points: InterleavedVecPointStorage,
+}
+
+impl<'a> ConcurrentAppendWriter<'a> {
+    /// Appends a single point
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `T` does not match the `PointLayout` of the associated `ConcurrentAppendBuffer`
+    pub fn push_point<T: PointType>(&mut self, point: T) {
+        self.points.push_point(point);
+        self.flush_if_full();
+    }
+
+    /// Appends a range of points
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `T` does not match the `PointLayout` of the associated `ConcurrentAppendBuffer`
+    pub fn push_points<T: PointType>(&mut self, points: &[T]) {
+        self.points.push_points(points);
+        self.flush_if_full();
+    }
+
+    /// Appends all points of the given `PointBuffer`
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `points` does not match the `PointLayout` of the associated `ConcurrentAppendBuffer`
+    pub fn push(&mut self, points: &dyn PointBuffer) {
+        self.points.push(points);
+        self.flush_if_full();
+    }
+
+    /// Hands over all points that were appended to this writer so far to the associated `ConcurrentAppendBuffer`
+    pub fn flush(&mut self) {
+        if self.points.is_empty() {
+            return;
+        }
+        let points = std::mem::replace(
+            &mut self.points,
+            InterleavedVecPointStorage::with_capacity(
+                self.buffer.segment_capacity,
+                self.buffer.layout.clone(),
+            ),
+        );
+        self.buffer.hand_over(Segment {
+            key: self.key,
+            sequence: self.next_sequence,
+            points,
+        });
+        self.next_sequence += 1;
+    }
+
+    fn flush_if_full(&mut self) {
+        if self.points.len() >= self.buffer.segment_capacity {
+            self.flush();
+        }
+    }
+}
+
+impl Drop for ConcurrentAppendWriter<'_> {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{containers::PointBufferExt, layout::attributes::INTENSITY};
+    use pasture_derive::PointType;
+
+    // We need this, otherwise we can't use the derive(PointType) macro from within pasture_core because the macro
+    // doesn't recognize the name 'pasture_core' :/
+    use crate as pasture_core;
+
+    #[repr(C)]
+    #[derive(PointType, Debug, Copy, Clone, PartialEq)]
+    struct TestPoint(#[pasture(BUILTIN_INTENSITY)] u16);
+
+    #[test]
+    fn test_concurrent_append_is_ordered_by_key() {
+        let buffer = Arc::new(ConcurrentAppendBuffer::with_shards_and_segment_capacity(
+            TestPoint::layout(),
+            3,
+            7,
+        ));
+        let threads = (0..8)
+            .rev()
+            .map(|chunk| {
+                let buffer = buffer.clone();
+                std::thread::spawn(move || {
+                    let mut writer = buffer.writer_with_key(chunk);
+                    for index in 0..50 {
+                        writer.push_point(TestPoint((chunk * 100 + index) as u16));
+                    }
+                    writer.push_points(&[
+                        TestPoint((chunk * 100 + 50) as u16),
+                        TestPoint((chunk * 100 + 51) as u16),
+                    ]);
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(8 * 52, buffer.len());
+
+        let points = Arc::try_unwrap(buffer)
+            .ok()
+            .expect("All writers are dropped")
+            .into_interleaved();
+        let expected = (0..8)
+            .flat_map(|chunk| (0..52).map(move |index| (chunk * 100 + index) as u16))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            expected,
+            points.iter_attribute::<u16>(&INTENSITY).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_concurrent_append_writer_flushes_on_drop() {
+        let buffer = ConcurrentAppendBuffer::new(TestPoint::layout());
+        {
+            let mut writer = buffer.writer();
+            writer.push_point(TestPoint(1));
+            assert!(buffer.is_empty());
+            writer.flush();
+            assert_eq!(1, buffer.len());
+            writer.push_point(TestPoint(2));
+        }
+        let mut writer = buffer.writer();
+        writer.push_point(TestPoint(3));
+        drop(writer);
+        assert_eq!(3, buffer.len());
+        assert_eq!(
+            vec![TestPoint(1), TestPoint(2), TestPoint(3)],
+            buffer
+                .into_interleaved()
+                .iter_point::<TestPoint>()
+                .collect::<Vec<_>>()
+        );
+    }
+}
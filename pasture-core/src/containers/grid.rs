@@ -0,0 +1,610 @@
+use std::ops::{Index, IndexMut};
+
+use nalgebra::{Vector2, Vector3};
+
+use crate::math::AABB;
+
+/// A regular 2D grid with square cells that are aligned with the x- and y-axis, such as a raster of point densities or
+/// ground heights. The grid stores one value of type `T` per cell. Cell `(0, 0)` is the cell at the `origin` of the
+/// grid, which is its south-west corner, so the x index increases to the east and the y index increases to the north.
+/// Raster formats that start with the northernmost row have to flip the rows when reading or writing a `Grid2D`
+///
+/// ```
+/// # use pasture_core::containers::Grid2D;
+/// # use pasture_core::nalgebra::Vector2;
+/// let mut grid = Grid2D::new(Vector2::new(100.0, 200.0), 10.0, 3, 2, 0_u32);
+/// let cell = grid.cell_of(&Vector2::new(125.0, 201.0)).unwrap();
+/// assert_eq!((2, 0), cell);
+/// grid[cell] += 1;
+/// assert_eq!(Vector2::new(125.0, 205.0), grid.cell_center(cell));
+/// assert_eq!(1, grid.iter().map(|(_, count)| *count).sum::<u32>());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid2D<T> {
+    origin: Vector2<f64>,
+    cell_size: f64,
+    size_x: usize,
+    size_y: usize,
+    cells: Vec<T>,
+    crs: Option<String>,
+}
+
+impl<T> Grid2D<T> {
+    /// Creates a new `Grid2D` with `size_x` columns and `size_y` rows of cells with the given `cell_size`, starting at
+    /// `origin`. All cells are initialized with `value`
+    ///
+    /// # Panics
+    ///
+    /// If `cell_size` is not strictly positive
+    pub fn new(origin: Vector2<f64>, cell_size: f64, size_x: usize, size_y: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_cells(
+            origin,
+            cell_size,
+            size_x,
+            size_y,
+            vec![value; size_x * size_y],
+        )
+    }
+
+    /// Creates a new `Grid2D` with cells of the given `cell_size` that covers the xy-extent of `bounds`, including its
+    /// maximum. All cells are initialized with `value`
+    ///
+    /// # Panics
+    ///
+    /// If `cell_size` is not strictly positive
+    pub fn from_bounds(bounds: &AABB<f64>, cell_size: f64, value: T) -> Self
+    where
+        T: Clone,
+    {
+        if cell_size <= 0.0 {
+            panic!("Grid2D::from_bounds: cell_size must be > 0");
+        }
+        let extent = bounds.extent();
+        Self::new(
+            bounds.min().coords.xy(),
+            cell_size,
+            (extent.x / cell_size).floor() as usize + 1,
+            (extent.y / cell_size).floor() as usize + 1,
+            value,
+        )
+    }
+
+    /// Creates a new `Grid2D` from the given `cells`, which are stored in row-major order, starting with the
+    /// southernmost row
+    ///
+    /// # Panics
+    ///
+    /// If `cell_size` is not strictly positive, or if the number of `cells` is not `size_x * size_y`
+    pub fn from_cells(
+        origin: Vector2<f64>,
+        cell_size: f64,
+        size_x: usize,
+        size_y: usize,
+        cells: Vec<T>,
+    ) -> Self {
+        if cell_size <= 0.0 {
+            panic!("Grid2D::from_cells: cell_size must be > 0");
+        }
+        if cells.len() != size_x * size_y {
+            panic!(
+                "Grid2D::from_cells: Expected {} cells, but got {}",
+                size_x * size_y,
+                cells.len()
+            );
+        }
+        Self {
+            origin,
+            cell_size,
+            size_x,
+            size_y,
+            cells,
+            crs: None,
+        }
+    }
+
+    /// Sets the coordinate reference system of this `Grid2D`, e.g. `EPSG:25832` or a WKT string
+    pub fn with_crs<S: Into<String>>(mut self, crs: S) -> Self {
+        self.crs = Some(crs.into());
+        self
+    }
+
+    /// Returns the coordinate reference system of this `Grid2D`, if there is one
+    pub fn crs(&self) -> Option<&str> {
+        self.crs.as_deref()
+    }
+
+    /// Returns the origin (i.e. the south-west corner) of this `Grid2D`
+    pub fn origin(&self) -> &Vector2<f64> {
+        &self.origin
+    }
+
+    /// Returns the edge length of the cells of this `Grid2D`
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// Returns the number of columns of this `Grid2D`
+    pub fn size_x(&self) -> usize {
+        self.size_x
+    }
+
+    /// Returns the number of rows of this `Grid2D`
+    pub fn size_y(&self) -> usize {
+        self.size_y
+    }
+
+    /// Returns the area that this `Grid2D` covers as an `AABB` with zero height
+    pub fn bounds(&self) -> AABB<f64> {
+        let max =
+            self.origin + Vector2::new(self.size_x as f64, self.size_y as f64) * self.cell_size;
+        AABB::from_min_max_unchecked(
+            Vector3::new(self.origin.x, self.origin.y, 0.0).into(),
+            Vector3::new(max.x, max.y, 0.0).into(),
+        )
+    }
+
+    /// Returns the values of all cells in row-major order, starting with the southernmost row
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// Mutable version of [cells](Grid2D::cells)
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    /// Consumes this `Grid2D` and returns the values of all cells in the order of [cells](Grid2D::cells)
+    pub fn into_cells(self) -> Vec<T> {
+        self.cells
+    }
+
+    /// Returns the value of the cell `(x, y)`, or `None` if the cell is outside of this `Grid2D`
+    pub fn get(&self, cell: (usize, usize)) -> Option<&T> {
+        self.linear_index(cell).map(move |index| &self.cells[index])
+    }
+
+    /// Mutable version of [get](Grid2D::get)
+    pub fn get_mut(&mut self, cell: (usize, usize)) -> Option<&mut T> {
+        self.linear_index(cell)
+            .map(move |index| &mut self.cells[index])
+    }
+
+    /// Returns the cell that contains `position`, or `None` if `position` is outside of this `Grid2D`. Positions on the
+    /// border between two cells belong to the cell to the north or east
+    pub fn cell_of(&self, position: &Vector2<f64>) -> Option<(usize, usize)> {
+        let x = ((position.x - self.origin.x) / self.cell_size).floor();
+        let y = ((position.y - self.origin.y) / self.cell_size).floor();
+        if x < 0.0 || y < 0.0 || x >= self.size_x as f64 || y >= self.size_y as f64 {
+            return None;
+        }
+        Some((x as usize, y as usize))
+    }
+
+    /// Returns the center of the cell `(x, y)`
+    pub fn cell_center(&self, cell: (usize, usize)) -> Vector2<f64> {
+        self.origin + Vector2::new(cell.0 as f64 + 0.5, cell.1 as f64 + 0.5) * self.cell_size
+    }
+
+    /// Returns the value of the cell that contains `position`, or `None` if `position` is outside of this `Grid2D`
+    pub fn value_at(&self, position: &Vector2<f64>) -> Option<&T> {
+        self.cell_of(position).and_then(|cell| self.get(cell))
+    }
+
+    /// Returns an iterator over all cells of this `Grid2D` together with their values, in the order of
+    /// [cells](Grid2D::cells)
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let size_x = self.size_x;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, value)| ((index % size_x, index / size_x), value))
+    }
+
+    /// Mutable version of [iter](Grid2D::iter)
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = ((usize, usize), &mut T)> {
+        let size_x = self.size_x;
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .map(move |(index, value)| ((index % size_x, index / size_x), value))
+    }
+
+    /// Returns a new `Grid2D` with the same geometry and coordinate reference system as this `Grid2D`, whose cells are
+    /// the values of the cells of this `Grid2D` transformed by `f`
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid2D<U> {
+        Grid2D {
+            origin: self.origin,
+            cell_size: self.cell_size,
+            size_x: self.size_x,
+            size_y: self.size_y,
+            cells: self.cells.iter().map(f).collect(),
+            crs: self.crs.clone(),
+        }
+    }
+
+    fn linear_index(&self, cell: (usize, usize)) -> Option<usize> {
+        if cell.0 >= self.size_x || cell.1 >= self.size_y {
+            None
+        } else {
+            Some(cell.1 * self.size_x + cell.0)
+        }
+    }
+}
+
+impl<T: Copy + Into<f64>> Grid2D<T> {
+    /// Interpolates the values of the four cells around `position` bilinearly, treating each value as the value at
+    /// the center of its cell. Within half a cell of the border of this `Grid2D`, the values of the border cells are
+    /// extended to the border. Returns `None` if `position` is outside of this `Grid2D`, or if any of the interpolated
+    /// values with a non-zero weight is NaN (which is a common marker for cells without data)
+    ///
+    /// ```
+    /// # use pasture_core::containers::Grid2D;
+    /// # use pasture_core::nalgebra::Vector2;
+    /// let grid = Grid2D::from_cells(Vector2::new(0.0, 0.0), 1.0, 2, 1, vec![10.0, 20.0]);
+    /// assert_eq!(Some(15.0), grid.sample_bilinear(&Vector2::new(1.0, 0.5)));
+    /// assert_eq!(Some(10.0), grid.sample_bilinear(&Vector2::new(0.25, 0.5)));
+    /// assert_eq!(None, grid.sample_bilinear(&Vector2::new(2.5, 0.5)));
+    /// ```
+    pub fn sample_bilinear(&self, position: &Vector2<f64>) -> Option<f64> {
+        self.cell_of(position)?;
+        let (x0, x1, fx) =
+            interpolation_cells(position.x - self.origin.x, self.cell_size, self.size_x);
+        let (y0, y1, fy) =
+            interpolation_cells(position.y - self.origin.y, self.cell_size, self.size_y);
+        let mut interpolated = 0.0;
+        for (y, weight_y) in [(y0, 1.0 - fy), (y1, fy)].iter() {
+            for (x, weight_x) in [(x0, 1.0 - fx), (x1, fx)].iter() {
+                // Cells with a weight of zero are skipped, so that a NaN cell doesn't affect its neighbors
+                let weight = weight_x * weight_y;
+                if weight > 0.0 {
+                    let value: f64 = self.cells[y * self.size_x + x].into();
+                    interpolated += value * weight;
+                }
+            }
+        }
+        if interpolated.is_nan() {
+            None
+        } else {
+            Some(interpolated)
+        }
+    }
+}
+
+/// Returns the indices of the two cells along one axis between whose centers the given `offset` from the origin lies,
+/// together with the interpolation weight of the second cell
+fn interpolation_cells(offset: f64, cell_size: f64, size: usize) -> (usize, usize, f64) {
+    let continuous_index = offset / cell_size - 0.5;
+    let first = continuous_index.floor().max(0.0) as usize;
+    let first = first.min(size - 1);
+    let second = (first + 1).min(size - 1);
+    let weight = (continuous_index - first as f64).max(0.0).min(1.0);
+    (first, second, weight)
+}
+
+impl<T> Index<(usize, usize)> for Grid2D<T> {
+    type Output = T;
+
+    fn index(&self, cell: (usize, usize)) -> &Self::Output {
+        self.get(cell).unwrap_or_else(|| {
+            panic!(
+                "Cell {:?} is out of bounds for Grid2D of size {}x{}",
+                cell, self.size_x, self.size_y
+            )
+        })
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid2D<T> {
+    fn index_mut(&mut self, cell: (usize, usize)) -> &mut Self::Output {
+        let (size_x, size_y) = (self.size_x, self.size_y);
+        self.get_mut(cell).unwrap_or_else(|| {
+            panic!(
+                "Cell {:?} is out of bounds for Grid2D of size {}x{}",
+                cell, size_x, size_y
+            )
+        })
+    }
+}
+
+/// A regular 3D grid of cubic voxels that are aligned with the coordinate axes, such as an occupancy grid. The grid
+/// stores one value of type `T` per voxel. Voxel `(0, 0, 0)` is the voxel at the `origin` (i.e. the minimum corner) of
+/// the grid
+///
+/// ```
+/// # use pasture_core::containers::Grid3D;
+/// # use pasture_core::nalgebra::Vector3;
+/// let mut occupancy = Grid3D::new(Vector3::new(0.0, 0.0, 0.0), 0.5, 4, 4, 4, false);
+/// let voxel = occupancy.cell_of(&Vector3::new(1.2, 0.1, 1.9)).unwrap();
+/// occupancy[voxel] = true;
+/// assert_eq!((2, 0, 3), voxel);
+/// assert_eq!(1, occupancy.cells().iter().filter(|occupied| **occupied).count());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid3D<T> {
+    origin: Vector3<f64>,
+    cell_size: f64,
+    size: Vector3<usize>,
+    cells: Vec<T>,
+    crs: Option<String>,
+}
+
+impl<T> Grid3D<T> {
+    /// Creates a new `Grid3D` with `size_x * size_y * size_z` voxels with the given `cell_size`, starting at `origin`.
+    /// All voxels are initialized with `value`
+    ///
+    /// # Panics
+    ///
+    /// If `cell_size` is not strictly positive
+    pub fn new(
+        origin: Vector3<f64>,
+        cell_size: f64,
+        size_x: usize,
+        size_y: usize,
+        size_z: usize,
+        value: T,
+    ) -> Self
+    where
+        T: Clone,
+    {
+        if cell_size <= 0.0 {
+            panic!("Grid3D::new: cell_size must be > 0");
+        }
+        Self {
+            origin,
+            cell_size,
+            size: Vector3::new(size_x, size_y, size_z),
+            cells: vec![value; size_x * size_y * size_z],
+            crs: None,
+        }
+    }
+
+    /// Creates a new `Grid3D` with voxels of the given `cell_size` that covers `bounds`, including its maximum. All
+    /// voxels are initialized with `value`
+    ///
+    /// # Panics
+    ///
+    /// If `cell_size` is not strictly positive
+    pub fn from_bounds(bounds: &AABB<f64>, cell_size: f64, value: T) -> Self
+    where
+        T: Clone,
+    {
+        if cell_size <= 0.0 {
+            panic!("Grid3D::from_bounds: cell_size must be > 0");
+        }
+        let size = bounds
+            .extent()
+            .map(|extent| (extent / cell_size).floor() as usize + 1);
+        Self::new(
+            bounds.min().coords,
+            cell_size,
+            size.x,
+            size.y,
+            size.z,
+            value,
+        )
+    }
+
+    /// Sets the coordinate reference system of this `Grid3D`, e.g. `EPSG:25832` or a WKT string
+    pub fn with_crs<S: Into<String>>(mut self, crs: S) -> Self {
+        self.crs = Some(crs.into());
+        self
+    }
+
+    /// Returns the coordinate reference system of this `Grid3D`, if there is one
+    pub fn crs(&self) -> Option<&str> {
+        self.crs.as_deref()
+    }
+
+    /// Returns the origin (i.e. the minimum corner) of this `Grid3D`
+    pub fn origin(&self) -> &Vector3<f64> {
+        &self.origin
+    }
+
+    /// Returns the edge length of the voxels of this `Grid3D`
+    pub fn cell_size(&self) -> f64 {
+        self.cell_size
+    }
+
+    /// Returns the number of voxels of this `Grid3D` along each axis
+    pub fn size(&self) -> &Vector3<usize> {
+        &self.size
+    }
+
+    /// Returns the volume that this `Grid3D` covers
+    pub fn bounds(&self) -> AABB<f64> {
+        let max = self.origin + self.size.map(|size| size as f64) * self.cell_size;
+        AABB::from_min_max_unchecked(self.origin.into(), max.into())
+    }
+
+    /// Returns the values of all voxels, with x varying fastest and z varying slowest
+    pub fn cells(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// Mutable version of [cells](Grid3D::cells)
+    pub fn cells_mut(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    /// Consumes this `Grid3D` and returns the values of all voxels in the order of [cells](Grid3D::cells)
+    pub fn into_cells(self) -> Vec<T> {
+        self.cells
+    }
+
+    /// Returns the value of the voxel `(x, y, z)`, or `None` if the voxel is outside of this `Grid3D`
+    pub fn get(&self, cell: (usize, usize, usize)) -> Option<&T> {
+        self.linear_index(cell).map(move |index| &self.cells[index])
+    }
+
+    /// Mutable version of [get](Grid3D::get)
+    pub fn get_mut(&mut self, cell: (usize, usize, usize)) -> Option<&mut T> {
+        self.linear_index(cell)
+            .map(move |index| &mut self.cells[index])
+    }
+
+    /// Returns the voxel that contains `position`, or `None` if `position` is outside of this `Grid3D`
+    pub fn cell_of(&self, position: &Vector3<f64>) -> Option<(usize, usize, usize)> {
+        let cell = (position - self.origin).map(|offset| (offset / self.cell_size).floor());
+        let is_inside = (0..3).all(|axis| cell[axis] >= 0.0 && cell[axis] < self.size[axis] as f64);
+        if !is_inside {
+            return None;
+        }
+        Some((cell.x as usize, cell.y as usize, cell.z as usize))
+    }
+
+    /// Returns the center of the voxel `(x, y, z)`
+    pub fn cell_center(&self, cell: (usize, usize, usize)) -> Vector3<f64> {
+        self.origin
+            + Vector3::new(
+                cell.0 as f64 + 0.5,
+                cell.1 as f64 + 0.5,
+                cell.2 as f64 + 0.5,
+            ) * self.cell_size
+    }
+
+    /// Returns the value of the voxel that contains `position`, or `None` if `position` is outside of this `Grid3D`
+    pub fn value_at(&self, position: &Vector3<f64>) -> Option<&T> {
+        self.cell_of(position).and_then(|cell| self.get(cell))
+    }
+
+    /// Returns an iterator over all voxels of this `Grid3D` together with their values, in the order of
+    /// [cells](Grid3D::cells)
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize, usize), &T)> {
+        let size = self.size;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, value)| (voxel_of_linear_index(index, &size), value))
+    }
+
+    /// Mutable version of [iter](Grid3D::iter)
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = ((usize, usize, usize), &mut T)> {
+        let size = self.size;
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .map(move |(index, value)| (voxel_of_linear_index(index, &size), value))
+    }
+
+    /// Returns a new `Grid3D` with the same geometry and coordinate reference system as this `Grid3D`, whose voxels are
+    /// the values of the voxels of this `Grid3D` transformed by `f`
+    pub fn map<U, F: FnMut(&T) -> U>(&self, f: F) -> Grid3D<U> {
+        Grid3D {
+            origin: self.origin,
+            cell_size: self.cell_size,
+            size: self.size,
+            cells: self.cells.iter().map(f).collect(),
+            crs: self.crs.clone(),
+        }
+    }
+
+    fn linear_index(&self, cell: (usize, usize, usize)) -> Option<usize> {
+        if cell.0 >= self.size.x || cell.1 >= self.size.y || cell.2 >= self.size.z {
+            None
+        } else {
+            Some((cell.2 * self.size.y + cell.1) * self.size.x + cell.0)
+        }
+    }
+}
+
+fn voxel_of_linear_index(index: usize, size: &Vector3<usize>) -> (usize, usize, usize) {
+    (
+        index % size.x,
+        (index / size.x) % size.y,
+        index / (size.x * size.y),
+    )
+}
+
+impl<T> Index<(usize, usize, usize)> for Grid3D<T> {
+    type Output = T;
+
+    fn index(&self, cell: (usize, usize, usize)) -> &Self::Output {
+        self.get(cell).unwrap_or_else(|| {
+            panic!(
+                "Voxel {:?} is out of bounds for Grid3D of size {:?}",
+                cell, self.size
+            )
+        })
+    }
+}
+
+impl<T> IndexMut<(usize, usize, usize)> for Grid3D<T> {
+    fn index_mut(&mut self, cell: (usize, usize, usize)) -> &mut Self::Output {
+        let size = self.size;
+        self.get_mut(cell).unwrap_or_else(|| {
+            panic!(
+                "Voxel {:?} is out of bounds for Grid3D of size {:?}",
+                cell, size
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Point3;
+
+    #[test]
+    fn test_grid2d_from_bounds() {
+        let bounds = AABB::from_min_max(Point3::new(10.0, 20.0, 0.0), Point3::new(14.0, 25.0, 1.0));
+        let grid = Grid2D::from_bounds(&bounds, 2.0, 0.0).with_crs("EPSG:25832");
+        assert_eq!((3, 3), (grid.size_x(), grid.size_y()));
+        assert_eq!(Some("EPSG:25832"), grid.crs());
+        assert_eq!(Some((2, 2)), grid.cell_of(&Vector2::new(14.0, 25.0)));
+        assert_eq!(None, grid.cell_of(&Vector2::new(9.9, 25.0)));
+        assert_eq!(None, grid.cell_of(&Vector2::new(16.0, 20.0)));
+
+        let cells = grid.iter().map(|(cell, _)| cell).collect::<Vec<_>>();
+        assert_eq!((0, 0), cells[0]);
+        assert_eq!((1, 0), cells[1]);
+        assert_eq!((0, 1), cells[3]);
+        assert_eq!(Some("EPSG:25832"), grid.map(|value| *value as f32).crs());
+    }
+
+    #[test]
+    fn test_grid2d_bilinear_sampling() {
+        // Values increase by 1 to the east and by 10 to the north, so the interpolation is exact inside the grid
+        let grid = Grid2D::from_cells(
+            Vector2::new(0.0, 0.0),
+            1.0,
+            3,
+            2,
+            vec![0.0_f32, 1.0, 2.0, 10.0, 11.0, 12.0],
+        );
+        let sample = |x: f64, y: f64| grid.sample_bilinear(&Vector2::new(x, y)).unwrap();
+        assert_eq!(0.0, sample(0.5, 0.5));
+        assert_eq!(5.5, sample(1.0, 1.0));
+        assert_eq!(8.25, sample(1.25, 1.25));
+        // Border cells are extended outwards
+        assert_eq!(12.0, sample(2.9, 1.9));
+
+        let mut grid_with_hole = grid.map(|value| *value as f64);
+        grid_with_hole[(1, 1)] = f64::NAN;
+        assert_eq!(
+            None,
+            grid_with_hole.sample_bilinear(&Vector2::new(1.25, 1.25))
+        );
+        assert_eq!(
+            Some(0.0),
+            grid_with_hole.sample_bilinear(&Vector2::new(0.5, 0.5))
+        );
+    }
+
+    #[test]
+    fn test_grid3d_indexing() {
+        let bounds = AABB::from_min_max(Point3::new(0.0, 0.0, 0.0), Point3::new(1.5, 0.5, 2.5));
+        let mut grid = Grid3D::from_bounds(&bounds, 1.0, 0_u32);
+        assert_eq!(&Vector3::new(2, 1, 3), grid.size());
+        for (voxel, value) in grid.iter_mut() {
+            *value = (voxel.0 + 10 * voxel.1 + 100 * voxel.2) as u32;
+        }
+        assert_eq!(201, grid[(1, 0, 2)]);
+        assert_eq!(Some(&201), grid.value_at(&Vector3::new(1.5, 0.5, 2.5)));
+        assert_eq!(None, grid.get((0, 1, 0)));
+        assert_eq!(Vector3::new(0.5, 0.5, 1.5), grid.cell_center((0, 0, 1)));
+    }
+}
@@ -0,0 +1,297 @@
+use nalgebra::Vector3;
+
+use crate::{
+    layout::{
+        attributes::POSITION_3D, PointAttributeDataType, PointAttributeDefinition, PointLayout,
+    },
+    math::AABB,
+};
+
+use super::{
+    InterleavedVecPointStorage, PointBuffer, PointBufferExt, PointBufferWriteable,
+    PointBufferWriteableExt,
+};
+
+/// A local coordinate frame that is defined by an origin in world space. Positions in world space often use
+/// coordinates that are too large for single precision floating point values (e.g. UTM coordinates with 6 or 7 digits
+/// before the decimal point), so storing them as `Vector3<f32>` loses most of their precision. Storing them relative
+/// to a nearby origin instead keeps the precision, which is what most GPU and web pipelines require.
+///
+/// A buffer in a local frame stores the `POSITION_3D` attribute with the datatype `Vec3f32` (see
+/// [local_position_attribute](LocalFrame::local_position_attribute)), and the `LocalFrame` that belongs to the buffer
+/// converts these positions back to world space.
+///
+/// ```
+/// # use pasture_core::containers::LocalFrame;
+/// # use pasture_core::nalgebra::Vector3;
+/// let frame = LocalFrame::new(Vector3::new(500_000.0, 5_000_000.0, 0.0));
+/// let position = Vector3::new(500_123.25, 5_000_456.5, 78.125);
+/// let local_position = frame.to_local(&position);
+/// assert_eq!(Vector3::new(123.25_f32, 456.5, 78.125), local_position);
+/// assert_eq!(position, frame.to_global(&local_position));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalFrame {
+    origin: Vector3<f64>,
+}
+
+impl LocalFrame {
+    /// Creates a new `LocalFrame` with the given `origin` in world space
+    pub fn new(origin: Vector3<f64>) -> Self {
+        Self { origin }
+    }
+
+    /// Creates a `LocalFrame` whose origin is the center of `bounds`, rounded to whole units. Rounding the origin
+    /// makes it easier to read and keeps the conversion between world space and local space exact for coordinates with
+    /// few decimal places
+    pub fn from_bounds(bounds: &AABB<f64>) -> Self {
+        let center = bounds.center();
+        Self::new(Vector3::new(
+            center.x.round(),
+            center.y.round(),
+            center.z.round(),
+        ))
+    }
+
+    /// Returns the origin of this `LocalFrame` in world space
+    pub fn origin(&self) -> &Vector3<f64> {
+        &self.origin
+    }
+
+    /// Returns the `POSITION_3D` attribute with the datatype that buffers in a local frame use for their positions
+    pub fn local_position_attribute() -> PointAttributeDefinition {
+        POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32)
+    }
+
+    /// Converts the given `position` in world space into this `LocalFrame`
+    pub fn to_local(self, position: &Vector3<f64>) -> Vector3<f32> {
+        (position - self.origin).map(|coordinate| coordinate as f32)
+    }
+
+    /// Converts the given `position` in this `LocalFrame` into world space
+    pub fn to_global(self, position: &Vector3<f32>) -> Vector3<f64> {
+        position.map(|coordinate| coordinate as f64) + self.origin
+    }
+
+    /// Returns a `PointLayout` with the same attributes as `layout`, but with the `POSITION_3D` attribute in the local
+    /// datatype `Vec3f32`. This is the layout of buffers that store the points of `layout` in a local frame
+    pub fn local_layout(layout: &PointLayout) -> PointLayout {
+        with_position_datatype(layout, PointAttributeDataType::Vec3f32)
+    }
+
+    /// Returns a `PointLayout` with the same attributes as `layout`, but with the `POSITION_3D` attribute in its default
+    /// datatype `Vec3f64`. This is the inverse of [local_layout](LocalFrame::local_layout)
+    pub fn global_layout(layout: &PointLayout) -> PointLayout {
+        with_position_datatype(layout, POSITION_3D.datatype())
+    }
+
+    /// Converts all points in `points` into this `LocalFrame` and appends them to `target`. The attributes of `target`
+    /// are copied from `points`, except for the `POSITION_3D` attribute, which is stored relative to the origin of this
+    /// `LocalFrame` with the datatype `Vec3f32`
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `points` doesn't contain a `POSITION_3D` attribute, if the `PointLayout` of `target`
+    /// doesn't contain a `POSITION_3D` attribute with datatype `Vec3f32`, or if `target` has any other attribute that
+    /// `points` doesn't have.
+    pub fn append_local<T: PointBuffer + ?Sized, U: PointBufferWriteable + ?Sized>(
+        &self,
+        points: &T,
+        target: &mut U,
+    ) {
+        let first_point = append_attributes_except_position(points, target);
+        let local_position_attribute = Self::local_position_attribute();
+        for (index, position) in points
+            .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+            .enumerate()
+        {
+            target.set_attribute(
+                &local_position_attribute,
+                first_point + index,
+                self.to_local(&position),
+            );
+        }
+    }
+
+    /// Converts all points in `points`, whose positions are in this `LocalFrame`, into world space and appends them to
+    /// `target`. This is the inverse of [append_local](LocalFrame::append_local), so `target` has to store the
+    /// `POSITION_3D` attribute with its default datatype `Vec3f64`
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `points` doesn't contain a `POSITION_3D` attribute, if the `PointLayout` of `target`
+    /// doesn't contain a `POSITION_3D` attribute with datatype `Vec3f64`, or if `target` has any other attribute that
+    /// `points` doesn't have.
+    pub fn append_global<T: PointBuffer + ?Sized, U: PointBufferWriteable + ?Sized>(
+        &self,
+        points: &T,
+        target: &mut U,
+    ) {
+        let first_point = append_attributes_except_position(points, target);
+        for (index, position) in points
+            .iter_attribute_as::<Vector3<f32>>(&Self::local_position_attribute())
+            .enumerate()
+        {
+            target.set_attribute(&POSITION_3D, first_point + index, self.to_global(&position));
+        }
+    }
+
+    /// Converts all points in `points` into this `LocalFrame`. The resulting buffer has the
+    /// [local_layout](LocalFrame::local_layout) of the `PointLayout` of `points`. See
+    /// [append_local](LocalFrame::append_local) for details
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `points` doesn't contain a `POSITION_3D` attribute.
+    pub fn buffer_to_local<T: PointBuffer + ?Sized>(
+        &self,
+        points: &T,
+    ) -> InterleavedVecPointStorage {
+        let mut local_points = InterleavedVecPointStorage::with_capacity(
+            points.len(),
+            Self::local_layout(points.point_layout()),
+        );
+        self.append_local(points, &mut local_points);
+        local_points
+    }
+
+    /// Converts all points in `points`, whose positions are in this `LocalFrame`, into world space. The resulting buffer
+    /// has the [global_layout](LocalFrame::global_layout) of the `PointLayout` of `points`
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `points` doesn't contain a `POSITION_3D` attribute.
+    pub fn buffer_to_global<T: PointBuffer + ?Sized>(
+        &self,
+        points: &T,
+    ) -> InterleavedVecPointStorage {
+        let mut global_points = InterleavedVecPointStorage::with_capacity(
+            points.len(),
+            Self::global_layout(points.point_layout()),
+        );
+        self.append_global(points, &mut global_points);
+        global_points
+    }
+}
+
+fn with_position_datatype(
+    layout: &PointLayout,
+    position_datatype: PointAttributeDataType,
+) -> PointLayout {
+    let attributes = layout
+        .attributes()
+        .map(|attribute| {
+            let attribute: PointAttributeDefinition = attribute.into();
+            if attribute.name() == POSITION_3D.name() {
+                attribute.with_custom_datatype(position_datatype)
+            } else {
+                attribute
+            }
+        })
+        .collect::<Vec<_>>();
+    PointLayout::from_attributes(&attributes)
+}
+
+/// Appends `points.len()` points to `target` and copies all attributes except `POSITION_3D` from `points` into them.
+/// Returns the index of the first new point in `target`
+fn append_attributes_except_position<T: PointBuffer + ?Sized, U: PointBufferWriteable + ?Sized>(
+    points: &T,
+    target: &mut U,
+) -> usize {
+    if !points
+        .point_layout()
+        .has_attribute_with_name(POSITION_3D.name())
+    {
+        panic!("point buffer contains no position attribute");
+    }
+    let first_point = target.len();
+    target.resize(first_point + points.len());
+    let attributes = target
+        .point_layout()
+        .attributes()
+        .map(PointAttributeDefinition::from)
+        .filter(|attribute| attribute.name() != POSITION_3D.name())
+        .collect::<Vec<_>>();
+    for attribute in attributes.iter() {
+        let mut attribute_data = vec![0; attribute.size() as usize];
+        for index in 0..points.len() {
+            points.get_raw_attribute(index, attribute, &mut attribute_data);
+            target.set_raw_attribute(first_point + index, attribute, &attribute_data);
+        }
+    }
+    first_point
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{containers::PerAttributeVecPointStorage, layout::PointType};
+    use nalgebra::Point3;
+    use pasture_derive::PointType;
+
+    #[repr(C)]
+    #[derive(PointType, Debug, Clone, Copy, PartialEq)]
+    struct TestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(BUILTIN_INTENSITY)]
+        pub intensity: u16,
+    }
+
+    #[test]
+    fn test_local_frame_preserves_precision() {
+        let frame = LocalFrame::new(Vector3::new(600_000.0, 5_700_000.0, 100.0));
+        let position = Vector3::new(600_012.345, 5_700_543.21, 123.456);
+        let roundtrip = frame.to_global(&frame.to_local(&position));
+        assert!((roundtrip - position).amax() < 1e-4);
+
+        // Without the local frame, the position is off by decimeters
+        let without_frame = position.map(|coordinate| coordinate as f32 as f64);
+        assert!((without_frame - position).amax() > 0.01);
+    }
+
+    #[test]
+    fn test_local_frame_from_bounds() {
+        let bounds =
+            AABB::from_min_max(Point3::new(10.2, 20.0, -5.0), Point3::new(30.0, 41.0, 5.0));
+        let frame = LocalFrame::from_bounds(&bounds);
+        assert_eq!(&Vector3::new(20.0, 31.0, 0.0), frame.origin());
+    }
+
+    #[test]
+    fn test_buffer_to_local_and_back() {
+        let points = vec![
+            TestPoint {
+                position: Vector3::new(400_001.5, 6_000_002.25, 10.0),
+                intensity: 42,
+            },
+            TestPoint {
+                position: Vector3::new(399_998.0, 5_999_990.75, -2.5),
+                intensity: 1234,
+            },
+        ];
+        let mut buffer = PerAttributeVecPointStorage::new(TestPoint::layout());
+        buffer.push_points(&points);
+
+        let frame = LocalFrame::new(Vector3::new(400_000.0, 6_000_000.0, 0.0));
+        let local_buffer = frame.buffer_to_local(&buffer);
+        assert!(local_buffer
+            .point_layout()
+            .has_attribute(&LocalFrame::local_position_attribute()));
+        assert_eq!(
+            vec![
+                Vector3::new(1.5_f32, 2.25, 10.0),
+                Vector3::new(-2.0, -9.25, -2.5)
+            ],
+            local_buffer
+                .iter_attribute::<Vector3<f32>>(&LocalFrame::local_position_attribute())
+                .collect::<Vec<_>>()
+        );
+
+        let global_buffer = frame.buffer_to_global(&local_buffer);
+        assert_eq!(
+            points,
+            global_buffer.iter_point::<TestPoint>().collect::<Vec<_>>()
+        );
+    }
+}
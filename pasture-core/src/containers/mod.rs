@@ -33,5 +33,42 @@ pub use self::vec_buffers::*;
 mod slice_buffers;
 pub use self::slice_buffers::*;
 
+#[cfg(feature = "std")]
 mod untyped_point;
+#[cfg(feature = "std")]
 pub use self::untyped_point::*;
+
+#[cfg(feature = "std")]
+mod local_frame;
+#[cfg(feature = "std")]
+pub use self::local_frame::*;
+
+#[cfg(feature = "std")]
+mod grid;
+#[cfg(feature = "std")]
+pub use self::grid::*;
+
+#[cfg(feature = "std")]
+mod spatial_query;
+#[cfg(feature = "std")]
+pub use self::spatial_query::*;
+
+#[cfg(feature = "std")]
+mod buffer_comparison;
+#[cfg(feature = "std")]
+pub use self::buffer_comparison::*;
+
+#[cfg(feature = "std")]
+mod computed_view;
+#[cfg(feature = "std")]
+pub use self::computed_view::*;
+
+#[cfg(feature = "std")]
+mod transformed_buffer;
+#[cfg(feature = "std")]
+pub use self::transformed_buffer::*;
+
+#[cfg(feature = "std")]
+mod concurrent_buffer;
+#[cfg(feature = "std")]
+pub use self::concurrent_buffer::*;
@@ -1,4 +1,5 @@
-use std::{mem::MaybeUninit, ops::Range};
+use alloc::{vec, vec::Vec};
+use core::{any::Any, mem::MaybeUninit, ops::Range};
 
 use crate::{
     layout::{
@@ -16,6 +17,7 @@ use super::{
     iterators::PointIteratorByMut,
     iterators::PointIteratorByRef,
     iterators::PointIteratorByValue,
+    iterators::PointIteratorByValueWithConversion,
     PerAttributePointBufferSlice, PerAttributePointBufferSliceMut,
 };
 
@@ -74,6 +76,58 @@ pub trait PointBuffer {
     fn as_per_attribute(&self) -> Option<&dyn PerAttributePointBuffer> {
         None
     }
+
+    /// Try to downcast the associated `PointBuffer` into an `InterleavedPointBufferMut`
+    fn as_interleaved_mut(&mut self) -> Option<&mut dyn InterleavedPointBufferMut> {
+        None
+    }
+
+    /// Returns the associated `PointBuffer` as `Any`, so that it can be downcast into its concrete type through
+    /// [downcast_ref](#method.downcast_ref). Only buffers that own their data can be downcast, all views and slices
+    /// return `None`, since `Any` requires a `'static` type
+    fn as_any(&self) -> Option<&dyn Any> {
+        None
+    }
+
+    /// Mutable version of [as_any](PointBuffer::as_any)
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        None
+    }
+}
+
+impl dyn PointBuffer + '_ {
+    /// Returns true if the concrete type of this `PointBuffer` is `T`
+    pub fn is<T: PointBuffer + 'static>(&self) -> bool {
+        self.as_any().map_or(false, |any| any.is::<T>())
+    }
+
+    /// Try to downcast this `PointBuffer` into the concrete buffer type `T`. Together with
+    /// [as_interleaved](PointBuffer::as_interleaved) and [as_per_attribute](PointBuffer::as_per_attribute), this
+    /// allows algorithms that take a `&dyn PointBuffer` to use a faster code path if the concrete buffer supports it,
+    /// and to fall back to the generic accessors otherwise
+    ///
+    /// ```
+    /// # use pasture_core::containers::*;
+    /// # use pasture_core::layout::*;
+    /// fn reserve_if_possible(points: &mut dyn PointBuffer, additional: usize) {
+    ///     if let Some(points) = points.downcast_mut::<PerAttributeVecPointStorage>() {
+    ///         points.reserve(additional);
+    ///     }
+    /// }
+    ///
+    /// let mut points = PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[attributes::INTENSITY]));
+    /// reserve_if_possible(&mut points, 16);
+    /// assert!(points.capacity() >= 16);
+    /// assert!((&points as &dyn PointBuffer).downcast_ref::<InterleavedVecPointStorage>().is_none());
+    /// ```
+    pub fn downcast_ref<T: PointBuffer + 'static>(&self) -> Option<&T> {
+        self.as_any()?.downcast_ref::<T>()
+    }
+
+    /// Mutable version of [downcast_ref](#method.downcast_ref)
+    pub fn downcast_mut<T: PointBuffer + 'static>(&mut self) -> Option<&mut T> {
+        self.as_any_mut()?.downcast_mut::<T>()
+    }
 }
 
 /// Trait for all mutable `PointBuffer`s, that is all `PointBuffer`s where it is possible to push points into. Distinguishing between
@@ -258,6 +312,16 @@ pub trait PointBufferExt<B: PointBuffer + ?Sized> {
 
     /// Returns an iterator over all points in the associated `PointBuffer`, strongly typed to the `PointType` `T`
     fn iter_point<T: PointType>(&self) -> PointIteratorByValue<'_, T, B>;
+    /// Returns an iterator over all points in the associated `PointBuffer`, converted to the `PointType` `T`. Unlike
+    /// `iter_point`, the `PointLayout` of the buffer does not have to match the `PointLayout` of `T`: Each attribute of
+    /// `T` is read from the attribute with the same name in the buffer and converted to the datatype that `T` uses.
+    /// Regarding conversions, see the [conversions module](crate::layout::conversion).
+    ///
+    /// # Panics
+    ///
+    /// Panics if an attribute of `T` is not part of the `PointLayout` of the buffer.<br>
+    /// Panics if no valid conversion exists from the type that an attribute is stored as inside the buffer into the type of the attribute in `T`.
+    fn iter_as<T: PointType>(&self) -> PointIteratorByValueWithConversion<'_, T, B>;
     /// Returns an iterator over the given `attribute` of all points in the associated `PointBuffer`, strongly typed to the `PrimitiveType` `T`.
     ///
     /// For iterating over multiple attributes at once, use the [attributes!] macro.
@@ -292,9 +356,9 @@ impl<B: PointBuffer + ?Sized> PointBufferExt<B> for B {
         unsafe {
             self.get_raw_point(
                 index,
-                std::slice::from_raw_parts_mut(
+                core::slice::from_raw_parts_mut(
                     point.as_mut_ptr() as *mut u8,
-                    std::mem::size_of::<T>(),
+                    core::mem::size_of::<T>(),
                 ),
             );
             point.assume_init()
@@ -311,9 +375,9 @@ impl<B: PointBuffer + ?Sized> PointBufferExt<B> for B {
             self.get_raw_attribute(
                 index,
                 attribute,
-                std::slice::from_raw_parts_mut(
+                core::slice::from_raw_parts_mut(
                     attribute_data.as_mut_ptr() as *mut u8,
-                    std::mem::size_of::<T>(),
+                    core::mem::size_of::<T>(),
                 ),
             );
             attribute_data.assume_init()
@@ -324,6 +388,10 @@ impl<B: PointBuffer + ?Sized> PointBufferExt<B> for B {
         PointIteratorByValue::new(self)
     }
 
+    fn iter_as<T: PointType>(&self) -> PointIteratorByValueWithConversion<'_, T, B> {
+        PointIteratorByValueWithConversion::new(self)
+    }
+
     fn iter_attribute<'a, T: PrimitiveType>(
         &'a self,
         attribute: &'a PointAttributeDefinition,
@@ -458,9 +526,9 @@ impl<B: PointBufferWriteable + ?Sized> PointBufferWriteableExt<B> for B {
 
                         let mut as_t = MaybeUninit::<T>::uninit();
                         let mut as_t = unsafe {
-                            let tmp_value_bytes = std::slice::from_raw_parts_mut(
+                            let tmp_value_bytes = core::slice::from_raw_parts_mut(
                                 as_t.as_mut_ptr() as *mut u8,
-                                std::mem::size_of::<T>(),
+                                core::mem::size_of::<T>(),
                             );
                             convert_to_t(buffer.as_slice(), tmp_value_bytes);
                             as_t.assume_init()
@@ -522,7 +590,7 @@ impl<B: InterleavedPointBuffer + ?Sized> InterleavedPointBufferExt for B {
     fn get_points_ref<T: PointType>(&self, range: Range<usize>) -> &[T] {
         let num_points = range.len();
         let raw_points = self.get_raw_points_ref(range);
-        unsafe { std::slice::from_raw_parts(raw_points.as_ptr() as *const T, num_points) }
+        unsafe { core::slice::from_raw_parts(raw_points.as_ptr() as *const T, num_points) }
     }
 
     fn iter_point_ref<T: PointType>(&self) -> PointIteratorByRef<'_, T> {
@@ -564,7 +632,7 @@ impl<B: InterleavedPointBufferMut + ?Sized> InterleavedPointBufferMutExt for B {
     fn get_points_mut<T: PointType>(&mut self, range: Range<usize>) -> &mut [T] {
         let num_points = range.len();
         let raw_points = self.get_raw_points_mut(range);
-        unsafe { std::slice::from_raw_parts_mut(raw_points.as_ptr() as *mut T, num_points) }
+        unsafe { core::slice::from_raw_parts_mut(raw_points.as_ptr() as *mut T, num_points) }
     }
 
     fn iter_point_mut<T: PointType>(&mut self) -> PointIteratorByMut<'_, T> {
@@ -625,7 +693,7 @@ impl<B: PerAttributePointBuffer + ?Sized> PerAttributePointBufferExt for B {
     ) -> &[T] {
         let num_points = range.len();
         let raw_attributes = self.get_raw_attribute_range_ref(range, attribute);
-        unsafe { std::slice::from_raw_parts(raw_attributes.as_ptr() as *const T, num_points) }
+        unsafe { core::slice::from_raw_parts(raw_attributes.as_ptr() as *const T, num_points) }
     }
 
     fn iter_attribute_ref<'a, T: PrimitiveType>(
@@ -689,7 +757,7 @@ impl<'b, B: PerAttributePointBufferMut<'b> + ?Sized> PerAttributePointBufferMutE
     ) -> &mut [T] {
         let num_points = range.len();
         let raw_attributes = self.get_raw_attribute_range_mut(range, attribute);
-        unsafe { std::slice::from_raw_parts_mut(raw_attributes.as_ptr() as *mut T, num_points) }
+        unsafe { core::slice::from_raw_parts_mut(raw_attributes.as_ptr() as *mut T, num_points) }
     }
 
     fn iter_attribute_mut<'a, T: PrimitiveType>(
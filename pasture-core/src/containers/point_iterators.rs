@@ -1,3 +1,5 @@
+use crate::{containers::InterleavedVecPointStorage, layout::PointType};
+
 pub mod iterators {
 
     //! Contains `Iterator` implementations through which the untyped contents of `PointBuffer` structures
@@ -5,11 +7,16 @@ pub mod iterators {
 
     use crate::{
         containers::{InterleavedPointBuffer, InterleavedPointBufferMut, PointBuffer},
-        layout::PointType,
+        layout::{
+            conversion::{get_converter_for_attributes, AttributeConversionFn},
+            PointAttributeDefinition, PointType,
+        },
     };
 
-    use std::marker::PhantomData;
-    use std::mem::MaybeUninit;
+    use alloc::{vec, vec::Vec};
+    use core::marker::PhantomData;
+    use core::mem::MaybeUninit;
+    use core::ops::Range;
 
     /// Iterator over an arbitrary `PointBuffer` that yields strongly typed points by value
     pub struct PointIteratorByValue<'a, T: PointType, B: PointBuffer + ?Sized> {
@@ -43,9 +50,9 @@ pub mod iterators {
             // Create an uninitialized T which is filled by the call to `buffer.get_raw_point`
             let mut point = MaybeUninit::<T>::uninit();
             unsafe {
-                let point_byte_slice = std::slice::from_raw_parts_mut(
+                let point_byte_slice = core::slice::from_raw_parts_mut(
                     point.as_mut_ptr() as *mut u8,
-                    std::mem::size_of::<T>(),
+                    core::mem::size_of::<T>(),
                 );
                 self.buffer
                     .get_raw_point(self.current_index, point_byte_slice);
@@ -57,6 +64,103 @@ pub mod iterators {
         }
     }
 
+    /// Iterator over an arbitrary `PointBuffer` that yields points converted to the `PointType` `T` by value. Each
+    /// attribute of `T` is read from the attribute with the same name in the buffer and converted to the datatype of
+    /// `T`, so the buffer can have a different `PointLayout` than `T`, as long as it contains all attributes of `T`
+    pub struct PointIteratorByValueWithConversion<'a, T: PointType, B: PointBuffer + ?Sized> {
+        buffer: &'a B,
+        current_index: usize,
+        /// Byte range in the buffer, byte range in `T` and converter (if the datatypes differ) for each attribute of `T`
+        attribute_converters: Vec<(Range<usize>, Range<usize>, Option<AttributeConversionFn>)>,
+        source_point_buffer: Vec<u8>,
+        unused: PhantomData<T>,
+    }
+
+    impl<'a, T: PointType, B: PointBuffer + ?Sized> PointIteratorByValueWithConversion<'a, T, B> {
+        /// Creates a new `PointIteratorByValueWithConversion` over all points in the given `PointBuffer`
+        pub fn new(buffer: &'a B) -> Self {
+            let source_layout = buffer.point_layout();
+            let attribute_converters = T::layout()
+                .attributes()
+                .map(|target_attribute| {
+                    let source_attribute =
+                        match source_layout.get_attribute_by_name(target_attribute.name()) {
+                            Some(a) => a,
+                            None => panic!(
+                                "Attribute {} of type T not contained in PointLayout of buffer ({})",
+                                target_attribute, source_layout
+                            ),
+                        };
+                    let converter = if source_attribute.datatype() == target_attribute.datatype() {
+                        None
+                    } else {
+                        let source_definition: PointAttributeDefinition = source_attribute.into();
+                        let target_definition: PointAttributeDefinition = target_attribute.into();
+                        match get_converter_for_attributes(&source_definition, &target_definition) {
+                            Some(c) => Some(c),
+                            None => panic!("Can't convert from attribute {} to attribute {} because no valid conversion exists", source_definition, target_definition),
+                        }
+                    };
+                    let source_offset = source_attribute.offset() as usize;
+                    let target_offset = target_attribute.offset() as usize;
+                    (
+                        source_offset..source_offset + source_attribute.size() as usize,
+                        target_offset..target_offset + target_attribute.size() as usize,
+                        converter,
+                    )
+                })
+                .collect();
+
+            Self {
+                buffer,
+                current_index: 0,
+                attribute_converters,
+                source_point_buffer: vec![0; source_layout.size_of_point_entry() as usize],
+                unused: Default::default(),
+            }
+        }
+    }
+
+    impl<'a, T: PointType, B: PointBuffer + ?Sized> Iterator
+        for PointIteratorByValueWithConversion<'a, T, B>
+    {
+        type Item = T;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.current_index == self.buffer.len() {
+                return None;
+            }
+
+            // Start from a zeroed T, so that padding bytes between the attributes of T are initialized as well
+            let mut point = MaybeUninit::<T>::zeroed();
+            unsafe {
+                let point_byte_slice = core::slice::from_raw_parts_mut(
+                    point.as_mut_ptr() as *mut u8,
+                    core::mem::size_of::<T>(),
+                );
+                self.buffer
+                    .get_raw_point(self.current_index, self.source_point_buffer.as_mut_slice());
+                for (source_range, target_range, converter) in self.attribute_converters.iter() {
+                    let source = &self.source_point_buffer[source_range.clone()];
+                    let target = &mut point_byte_slice[target_range.clone()];
+                    match converter {
+                        Some(convert) => convert(source, target),
+                        None => target.copy_from_slice(source),
+                    }
+                }
+            }
+
+            self.current_index += 1;
+
+            unsafe { Some(point.assume_init()) }
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            let remaining = self.buffer.len() - self.current_index;
+            (remaining, Some(remaining))
+        }
+    }
+
     /// Iterator over an interleaved `PointBuffer` that yields strongly typed points by reference
     pub struct PointIteratorByRef<'a, T: PointType + 'a> {
         point_data: &'a [T],
@@ -71,7 +175,7 @@ pub mod iterators {
             }
             let buffer_len = buffer.len();
             let point_data = unsafe {
-                std::slice::from_raw_parts(
+                core::slice::from_raw_parts(
                     buffer.get_raw_points_ref(0..buffer_len).as_ptr() as *const T,
                     buffer_len,
                 )
@@ -110,7 +214,7 @@ pub mod iterators {
             }
             let buffer_len = buffer.len();
             let point_data = unsafe {
-                std::slice::from_raw_parts_mut(
+                core::slice::from_raw_parts_mut(
                     buffer.get_raw_points_mut(0..buffer_len).as_mut_ptr() as *mut T,
                     buffer_len,
                 )
@@ -144,13 +248,51 @@ pub mod iterators {
     }
 }
 
+/// Extension trait that collects the points of an iterator into a point buffer, so that iterator chains can start at a
+/// buffer (see [iter_as](crate::containers::PointBufferExt::iter_as)) and end in a buffer:
+///
+/// ```
+/// # use pasture_core::containers::*;
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// #[repr(C)]
+/// #[derive(PointType, Clone, Copy, Debug, PartialEq)]
+/// struct MyPointType {
+///   #[pasture(BUILTIN_INTENSITY)]
+///   pub intensity: u16,
+/// }
+///
+/// let points = vec![MyPointType { intensity: 42 }, MyPointType { intensity: 43 }];
+/// let buffer = points.into_iter().collect_into_buffer::<MyPointType>();
+/// let bright_points = buffer
+///     .iter_as::<MyPointType>()
+///     .filter(|point| point.intensity > 42)
+///     .collect_into_buffer::<MyPointType>();
+/// assert_eq!(1, bright_points.len());
+/// ```
+///
+/// To collect into other buffer types such as `PerAttributeVecPointStorage`, use `collect`
+pub trait CollectIntoBuffer: Iterator + Sized {
+    /// Collects all points of the associated iterator into an `InterleavedVecPointStorage` with the `PointLayout` of
+    /// the `PointType` `T`
+    fn collect_into_buffer<T: PointType>(self) -> InterleavedVecPointStorage
+    where
+        Self: Iterator<Item = T>,
+    {
+        self.collect()
+    }
+}
+
+impl<I: Iterator> CollectIntoBuffer for I {}
+
 #[cfg(test)]
 mod tests {
 
     use crate::containers::{
-        InterleavedPointBufferExt, InterleavedPointBufferMutExt, InterleavedVecPointStorage,
-        PerAttributeVecPointStorage, PointBufferExt,
+        CollectIntoBuffer, InterleavedPointBufferExt, InterleavedPointBufferMutExt,
+        InterleavedVecPointStorage, PerAttributeVecPointStorage, PointBuffer, PointBufferExt,
     };
+    use crate::layout::PointType;
     use pasture_derive::PointType;
 
     // We need this, otherwise we can't use the derive(PointType) macro from within pasture_core because the macro
@@ -166,6 +308,15 @@ mod tests {
         pub gps_time: f64,
     }
 
+    #[derive(Debug, Copy, Clone, PartialEq, PointType)]
+    #[repr(C)]
+    struct ConvertedTestPointType {
+        #[pasture(BUILTIN_GPS_TIME)]
+        pub gps_time: f64,
+        #[pasture(BUILTIN_INTENSITY)]
+        pub intensity: u32,
+    }
+
     #[test]
     fn test_points_iterator_from_interleaved() {
         let reference_points = vec![
@@ -229,4 +380,106 @@ mod tests {
 
         assert_eq!(reference_points, collected_points);
     }
+
+    #[test]
+    fn test_points_iterator_with_conversion() {
+        let reference_points = vec![
+            TestPointType {
+                intensity: 42,
+                gps_time: 0.123,
+            },
+            TestPointType {
+                intensity: 43,
+                gps_time: 0.456,
+            },
+        ];
+        let expected_points = vec![
+            ConvertedTestPointType {
+                gps_time: 0.123,
+                intensity: 42,
+            },
+            ConvertedTestPointType {
+                gps_time: 0.456,
+                intensity: 43,
+            },
+        ];
+
+        let interleaved = InterleavedVecPointStorage::from(reference_points.as_slice());
+        let iter = interleaved.iter_as::<ConvertedTestPointType>();
+        assert_eq!((2, Some(2)), iter.size_hint());
+        assert_eq!(expected_points, iter.collect::<Vec<_>>());
+
+        let per_attribute = PerAttributeVecPointStorage::from(reference_points.as_slice());
+        assert_eq!(
+            expected_points,
+            per_attribute
+                .iter_as::<ConvertedTestPointType>()
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            reference_points,
+            per_attribute.iter_as::<TestPointType>().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_points_iterator_with_conversion_missing_attribute() {
+        #[derive(Debug, Copy, Clone, PartialEq, PointType)]
+        #[repr(C)]
+        struct PointWithClassification {
+            #[pasture(BUILTIN_CLASSIFICATION)]
+            pub classification: u8,
+        }
+
+        let storage = InterleavedVecPointStorage::from(
+            vec![TestPointType {
+                intensity: 42,
+                gps_time: 0.123,
+            }]
+            .as_slice(),
+        );
+        storage.iter_as::<PointWithClassification>().count();
+    }
+
+    #[test]
+    fn test_collect_into_buffer() {
+        let reference_points = vec![
+            TestPointType {
+                intensity: 42,
+                gps_time: 0.123,
+            },
+            TestPointType {
+                intensity: 43,
+                gps_time: 0.456,
+            },
+        ];
+
+        let buffer = reference_points
+            .iter()
+            .copied()
+            .filter(|point| point.intensity > 42)
+            .collect_into_buffer::<TestPointType>();
+        assert_eq!(&TestPointType::layout(), buffer.point_layout());
+        assert_eq!(
+            vec![reference_points[1]],
+            buffer.iter_point::<TestPointType>().collect::<Vec<_>>()
+        );
+
+        let per_attribute = reference_points
+            .iter()
+            .map(|point| TestPointType {
+                intensity: point.intensity * 2,
+                gps_time: point.gps_time,
+            })
+            .collect::<PerAttributeVecPointStorage>();
+        assert_eq!(2, per_attribute.len());
+        assert_eq!(
+            vec![84, 86],
+            per_attribute
+                .iter_as::<ConvertedTestPointType>()
+                .map(|point| point.intensity)
+                .collect::<Vec<_>>()
+        );
+    }
 }
@@ -1,4 +1,5 @@
-use std::ops::Range;
+use alloc::{vec, vec::Vec};
+use core::ops::Range;
 
 use crate::layout::{
     FieldAlignment, PointAttributeDefinition, PointLayout, PointType, PrimitiveType,
@@ -35,9 +36,9 @@ impl<'d> InterleavedPointView<'d> {
     /// ```
     pub fn from_slice<T: PointType>(points: &'d [T]) -> Self {
         let raw_points_data = unsafe {
-            std::slice::from_raw_parts(
+            core::slice::from_raw_parts(
                 points.as_ptr() as *const u8,
-                points.len() * std::mem::size_of::<T>(),
+                points.len() * core::mem::size_of::<T>(),
             )
         };
         let point_layout = T::layout();
@@ -91,7 +92,7 @@ impl<'d> InterleavedPointView<'d> {
             panic!("InterleavedPointView::get_typed_data: Point layout does not match type T!");
         }
         unsafe {
-            std::slice::from_raw_parts(self.point_data.as_ptr() as *const T, self.point_count)
+            core::slice::from_raw_parts(self.point_data.as_ptr() as *const T, self.point_count)
         }
     }
 }
@@ -138,14 +139,14 @@ impl<'d> PointBuffer for InterleavedPointView<'d> {
         }
     }
 
-    fn get_raw_points(&self, index_range: std::ops::Range<usize>, buf: &mut [u8]) {
+    fn get_raw_points(&self, index_range: core::ops::Range<usize>, buf: &mut [u8]) {
         let points_ref = self.get_raw_points_ref(index_range);
         buf[0..points_ref.len()].copy_from_slice(points_ref);
     }
 
     fn get_raw_attribute_range(
         &self,
-        index_range: std::ops::Range<usize>,
+        index_range: core::ops::Range<usize>,
         attribute: &PointAttributeDefinition,
         buf: &mut [u8],
     ) {
@@ -203,7 +204,7 @@ impl<'d> InterleavedPointBuffer for InterleavedPointView<'d> {
         &self.point_data[offset_to_point..offset_to_point + self.size_of_point_entry as usize]
     }
 
-    fn get_raw_points_ref(&self, index_range: std::ops::Range<usize>) -> &[u8] {
+    fn get_raw_points_ref(&self, index_range: core::ops::Range<usize>) -> &[u8] {
         if index_range.end > self.len() {
             panic!(
                 "InterleavedPointView::get_raw_points_ref: Point indices {:?} out of bounds!",
@@ -363,7 +364,7 @@ impl<'d> PerAttributePointView<'d> {
             .map(|attribute_index| {
                 let raw_slice = self.point_data[attribute_index];
                 unsafe {
-                    std::slice::from_raw_parts(raw_slice.as_ptr() as *const T, self.point_count)
+                    core::slice::from_raw_parts(raw_slice.as_ptr() as *const T, self.point_count)
                 }
             })
     }
@@ -412,9 +413,9 @@ impl<'d> PerAttributePointView<'d> {
         self.point_count = attribute.len();
 
         let attribute_bytes = unsafe {
-            std::slice::from_raw_parts(
+            core::slice::from_raw_parts(
                 attribute.as_ptr() as *const u8,
-                attribute.len() * std::mem::size_of::<T>(),
+                attribute.len() * core::mem::size_of::<T>(),
             )
         };
         self.point_data.push(attribute_bytes);
@@ -432,9 +433,9 @@ impl<'d> PerAttributePointView<'d> {
         }
 
         let attribute_bytes = unsafe {
-            std::slice::from_raw_parts(
+            core::slice::from_raw_parts(
                 attribute.as_ptr() as *const u8,
-                attribute.len() * std::mem::size_of::<T>(),
+                attribute.len() * core::mem::size_of::<T>(),
             )
         };
         self.point_data.push(attribute_bytes);
@@ -499,7 +500,7 @@ impl<'d> PointBuffer for PerAttributePointView<'d> {
         buf.copy_from_slice(attribute_slice);
     }
 
-    fn get_raw_points(&self, index_range: std::ops::Range<usize>, buf: &mut [u8]) {
+    fn get_raw_points(&self, index_range: core::ops::Range<usize>, buf: &mut [u8]) {
         if index_range.end > self.len() {
             panic!(
                 "PerAttributePointView::get_raw_points: Point indices {:?} out of bounds!",
@@ -530,7 +531,7 @@ impl<'d> PointBuffer for PerAttributePointView<'d> {
 
     fn get_raw_attribute_range(
         &self,
-        index_range: std::ops::Range<usize>,
+        index_range: core::ops::Range<usize>,
         attribute: &PointAttributeDefinition,
         buf: &mut [u8],
     ) {
@@ -0,0 +1,135 @@
+use anyhow::Result;
+use nalgebra::{Point3, Vector2, Vector3};
+
+use crate::{layout::PointLayout, math::AABB};
+
+use super::PointBufferWriteable;
+
+/// A spatial query that can be run on any [SpatialQueryable] source
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpatialQuery {
+    /// All points within the given bounding box
+    Bounds(AABB<f64>),
+    /// All points whose XY position lies within the polygon with the given vertices, independent of their Z position.
+    /// The polygon is closed implicitly, i.e. the last vertex is connected to the first vertex
+    Polygon(Vec<Vector2<f64>>),
+    /// The `k` points that are closest to `position`, sorted by ascending distance
+    Nearest {
+        /// Position to which the distances are measured
+        position: Vector3<f64>,
+        /// Number of points to return
+        k: usize,
+    },
+}
+
+impl SpatialQuery {
+    /// Returns a bounding box that contains all points that can match this query, which sources can use to skip
+    /// whole parts of their index. For `Polygon` queries, the bounding box is unbounded in Z direction. Returns `None`
+    /// for `Nearest` queries and for polygons without vertices
+    pub fn search_bounds(&self) -> Option<AABB<f64>> {
+        match self {
+            SpatialQuery::Bounds(bounds) => Some(*bounds),
+            SpatialQuery::Polygon(vertices) => {
+                let (first, rest) = vertices.split_first()?;
+                let (min, max) = rest.iter().fold((*first, *first), |(min, max), vertex| {
+                    (min.inf(vertex), max.sup(vertex))
+                });
+                Some(AABB::from_min_max_unchecked(
+                    Point3::new(min.x, min.y, f64::NEG_INFINITY),
+                    Point3::new(max.x, max.y, f64::INFINITY),
+                ))
+            }
+            SpatialQuery::Nearest { .. } => None,
+        }
+    }
+
+    /// Does the given `position` match this query? Always returns `false` for `Nearest` queries, whose result depends
+    /// on all other points
+    pub fn contains(&self, position: &Vector3<f64>) -> bool {
+        match self {
+            SpatialQuery::Bounds(bounds) => bounds.contains(&Point3::from(*position)),
+            SpatialQuery::Polygon(vertices) => polygon_contains(vertices, position.x, position.y),
+            SpatialQuery::Nearest { .. } => false,
+        }
+    }
+}
+
+/// Point-in-polygon test using the even-odd rule
+fn polygon_contains(vertices: &[Vector2<f64>], x: f64, y: f64) -> bool {
+    let mut inside = false;
+    let mut previous = match vertices.last() {
+        Some(last) => last,
+        None => return false,
+    };
+    for vertex in vertices {
+        if (vertex.y > y) != (previous.y > y)
+            && x < (previous.x - vertex.x) * (y - vertex.y) / (previous.y - vertex.y) + vertex.x
+        {
+            inside = !inside;
+        }
+        previous = vertex;
+    }
+    inside
+}
+
+/// Common interface for all sources of points that support spatial queries, such as in-memory buffers with a spatial
+/// index or files with a spatial index (like COPC files). This allows application code to query any source of points
+/// in the same way
+pub trait SpatialQueryable {
+    /// Returns the `PointLayout` of the points that this source returns from queries
+    fn point_layout(&self) -> &PointLayout;
+
+    /// Runs the given `query` and appends all matching points to `points`, which must have the `PointLayout` of this
+    /// source. Returns the number of points that were appended
+    ///
+    /// `resolution` is the finest point spacing that the caller needs. Sources that store their points in a
+    /// level-of-detail hierarchy only return the points of those levels whose point spacing is at least `resolution`,
+    /// which makes overview queries over large areas cheap. All other sources, and all sources if `resolution` is
+    /// `None`, return the points at full resolution
+    ///
+    /// # Errors
+    ///
+    /// If the points can't be read from the underlying source, an error is returned
+    fn query(
+        &mut self,
+        query: &SpatialQuery,
+        resolution: Option<f64>,
+        points: &mut dyn PointBufferWriteable,
+    ) -> Result<usize>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spatial_query_contains() {
+        let polygon = SpatialQuery::Polygon(vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(4.0, 0.0),
+            Vector2::new(4.0, 4.0),
+            Vector2::new(2.0, 1.0),
+            Vector2::new(0.0, 4.0),
+        ]);
+        assert!(polygon.contains(&Vector3::new(1.0, 0.5, 100.0)));
+        assert!(polygon.contains(&Vector3::new(3.5, 3.0, -5.0)));
+        // In the notch of the polygon
+        assert!(!polygon.contains(&Vector3::new(2.0, 3.0, 0.0)));
+        assert!(!polygon.contains(&Vector3::new(5.0, 1.0, 0.0)));
+
+        let search_bounds = polygon.search_bounds().unwrap();
+        assert_eq!(
+            Point3::new(0.0, 0.0, f64::NEG_INFINITY),
+            *search_bounds.min()
+        );
+        assert_eq!(Point3::new(4.0, 4.0, f64::INFINITY), *search_bounds.max());
+
+        let nearest = SpatialQuery::Nearest {
+            position: Vector3::zeros(),
+            k: 3,
+        };
+        assert_eq!(None, nearest.search_bounds());
+        assert!(!nearest.contains(&Vector3::zeros()));
+        assert!(!SpatialQuery::Polygon(vec![]).contains(&Vector3::zeros()));
+    }
+}
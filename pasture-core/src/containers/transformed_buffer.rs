@@ -0,0 +1,276 @@
+use std::ops::Range;
+
+use nalgebra::{Matrix4, Point3, Vector3};
+
+use crate::{
+    layout::{attributes::POSITION_3D, PointAttributeDataType},
+    meta::Metadata,
+};
+
+use super::{PointBuffer, PointBufferExt, PointBufferWriteable, PointBufferWriteableExt};
+
+/// Applies the given `transform` (a 4x4 matrix in homogeneous coordinates) to the `POSITION_3D` attribute of the points
+/// in `range` of `buffer`. Positions with the datatypes `Vec3f64` and `Vec3f32` are transformed, buffers without
+/// positions or with positions of other datatypes are not changed
+///
+/// # Panics
+///
+/// If `range` is out of bounds for `buffer`
+pub fn transform_positions<B: PointBufferWriteable + ?Sized>(
+    buffer: &mut B,
+    range: Range<usize>,
+    transform: &Matrix4<f64>,
+) {
+    let position_datatype = match buffer
+        .point_layout()
+        .get_attribute_by_name(POSITION_3D.name())
+    {
+        Some(attribute) => attribute.datatype(),
+        None => return,
+    };
+    match position_datatype {
+        PointAttributeDataType::Vec3f64 => {
+            for index in range {
+                let position = buffer.get_attribute::<Vector3<f64>>(&POSITION_3D, index);
+                let transformed = transform.transform_point(&Point3::from(position));
+                buffer.set_attribute(&POSITION_3D, index, transformed.coords);
+            }
+        }
+        PointAttributeDataType::Vec3f32 => {
+            let attribute = POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32);
+            for index in range {
+                let position = buffer.get_attribute::<Vector3<f32>>(&attribute, index);
+                let transformed = transform.transform_point(&Point3::new(
+                    position.x as f64,
+                    position.y as f64,
+                    position.z as f64,
+                ));
+                buffer.set_attribute(
+                    &attribute,
+                    index,
+                    Vector3::new(
+                        transformed.x as f32,
+                        transformed.y as f32,
+                        transformed.z as f32,
+                    ),
+                );
+            }
+        }
+        _ => (),
+    }
+}
+
+/// A `PointBuffer` together with a global transformation of its positions, like the pose of a terrestrial scan in an
+/// E57 or PTX file. The transformation is kept symbolic until [apply_transform](TransformedPointBuffer::apply_transform)
+/// is called, so viewers can upload the untransformed positions together with the transformation, while algorithms that
+/// need world space positions can either use [world_position](TransformedPointBuffer::world_position) or bake the
+/// transformation into the positions.
+///
+/// ```
+/// # use pasture_core::containers::*;
+/// # use pasture_core::layout::*;
+/// # use pasture_core::nalgebra::{Translation3, Vector3};
+/// let mut points = InterleavedVecPointStorage::new(PointLayout::from_attributes(&[attributes::POSITION_3D]));
+/// points.resize(1);
+/// points.set_attribute(&attributes::POSITION_3D, 0, Vector3::new(1.0, 2.0, 3.0));
+///
+/// let mut points = TransformedPointBuffer::with_transform(
+///     points,
+///     Translation3::new(100.0, 0.0, 0.0).to_homogeneous(),
+/// );
+/// assert_eq!(Vector3::new(101.0, 2.0, 3.0), points.world_position(0));
+///
+/// points.apply_transform();
+/// assert!(!points.has_transform());
+/// assert_eq!(
+///     Vector3::new(101.0, 2.0, 3.0),
+///     points.buffer().get_attribute::<Vector3<f64>>(&attributes::POSITION_3D, 0)
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct TransformedPointBuffer<B: PointBuffer> {
+    buffer: B,
+    transform: Matrix4<f64>,
+}
+
+impl<B: PointBuffer> TransformedPointBuffer<B> {
+    /// Creates a new `TransformedPointBuffer` for `buffer` with the identity transformation
+    pub fn new(buffer: B) -> Self {
+        Self::with_transform(buffer, Matrix4::identity())
+    }
+
+    /// Creates a new `TransformedPointBuffer` for `buffer` with the given `transform`
+    pub fn with_transform(buffer: B, transform: Matrix4<f64>) -> Self {
+        Self { buffer, transform }
+    }
+
+    /// Creates a new `TransformedPointBuffer` for `buffer` with the transformation from the given `metadata` (see
+    /// `Metadata::transform`), or the identity transformation if `metadata` has no transformation
+    pub fn from_metadata(buffer: B, metadata: &dyn Metadata) -> Self {
+        Self::with_transform(
+            buffer,
+            metadata.transform().unwrap_or_else(Matrix4::identity),
+        )
+    }
+
+    /// Returns the transformation from the positions in the buffer to world space
+    pub fn transform(&self) -> &Matrix4<f64> {
+        &self.transform
+    }
+
+    /// Sets the transformation from the positions in the buffer to world space, without changing the positions
+    pub fn set_transform(&mut self, transform: Matrix4<f64>) {
+        self.transform = transform;
+    }
+
+    /// Is the transformation something other than the identity?
+    pub fn has_transform(&self) -> bool {
+        self.transform != Matrix4::identity()
+    }
+
+    /// Returns the underlying buffer with the untransformed positions
+    pub fn buffer(&self) -> &B {
+        &self.buffer
+    }
+
+    /// Returns the underlying buffer with the untransformed positions mutably
+    pub fn buffer_mut(&mut self) -> &mut B {
+        &mut self.buffer
+    }
+
+    /// Returns the underlying buffer and the transformation
+    pub fn into_parts(self) -> (B, Matrix4<f64>) {
+        (self.buffer, self.transform)
+    }
+
+    /// Returns the position of the point at `point_index` in world space, i.e. with the transformation applied
+    ///
+    /// # Panics
+    ///
+    /// If the buffer has no `POSITION_3D` attribute with the datatype `Vec3f64` or `Vec3f32`.<br>
+    /// If `point_index` is out of bounds
+    pub fn world_position(&self, point_index: usize) -> Vector3<f64> {
+        let position_datatype = self
+            .buffer
+            .point_layout()
+            .get_attribute_by_name(POSITION_3D.name())
+            .map(|attribute| attribute.datatype());
+        let position = match position_datatype {
+            Some(PointAttributeDataType::Vec3f64) => self
+                .buffer
+                .get_attribute::<Vector3<f64>>(&POSITION_3D, point_index),
+            Some(PointAttributeDataType::Vec3f32) => {
+                let position = self.buffer.get_attribute::<Vector3<f32>>(
+                    &POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32),
+                    point_index,
+                );
+                Vector3::new(position.x as f64, position.y as f64, position.z as f64)
+            }
+            _ => panic!(
+                "TransformedPointBuffer::world_position: Buffer has no POSITION_3D attribute with datatype Vec3f64 or Vec3f32"
+            ),
+        };
+        self.transform
+            .transform_point(&Point3::from(position))
+            .coords
+    }
+}
+
+impl<B: PointBufferWriteable> TransformedPointBuffer<B> {
+    /// Bakes the transformation into the positions of the buffer (see [transform_positions]) and resets the
+    /// transformation to the identity
+    pub fn apply_transform(&mut self) {
+        if self.has_transform() {
+            let point_count = self.buffer.len();
+            transform_positions(&mut self.buffer, 0..point_count, &self.transform);
+            self.transform = Matrix4::identity();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Translation3;
+
+    use super::*;
+    use crate::{
+        containers::{InterleavedVecPointStorage, PerAttributeVecPointStorage},
+        layout::{attributes::INTENSITY, PointLayout},
+    };
+
+    #[test]
+    fn test_transformed_point_buffer() {
+        let mut points = PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[
+            POSITION_3D,
+            INTENSITY,
+        ]));
+        points.resize(2);
+        points.set_attribute(&POSITION_3D, 0, Vector3::new(1.0, 0.0, 0.0));
+        points.set_attribute(&POSITION_3D, 1, Vector3::new(0.0, 1.0, 0.0));
+        points.set_attribute(&INTENSITY, 1, 42_u16);
+
+        // Rotation by 90 degrees around the Z axis, followed by a translation
+        let rotation = Matrix4::new(
+            0.0, -1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+        );
+        let transform = Translation3::new(10.0, 20.0, 30.0).to_homogeneous() * rotation;
+        let mut points = TransformedPointBuffer::new(points);
+        assert!(!points.has_transform());
+        points.set_transform(transform);
+        assert!(points.has_transform());
+
+        let expected = vec![
+            Vector3::new(10.0, 21.0, 30.0),
+            Vector3::new(9.0, 20.0, 30.0),
+        ];
+        assert_eq!(expected[0], points.world_position(0));
+        assert_eq!(expected[1], points.world_position(1));
+        // The positions in the buffer are not changed until the transformation is applied
+        assert_eq!(
+            Vector3::new(1.0, 0.0, 0.0),
+            points
+                .buffer()
+                .get_attribute::<Vector3<f64>>(&POSITION_3D, 0)
+        );
+
+        points.apply_transform();
+        assert_eq!(&Matrix4::identity(), points.transform());
+        let (points, _) = points.into_parts();
+        assert_eq!(
+            expected,
+            points
+                .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(42, points.get_attribute::<u16>(&INTENSITY, 1));
+    }
+
+    #[test]
+    fn test_transform_single_precision_positions() {
+        let local_position = POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32);
+        let mut points =
+            InterleavedVecPointStorage::new(PointLayout::from_attributes(
+                &[local_position.clone()],
+            ));
+        points.resize(3);
+        for index in 0..3 {
+            points.set_attribute(&local_position, index, Vector3::new(index as f32, 0.0, 0.0));
+        }
+
+        transform_positions(
+            &mut points,
+            1..3,
+            &Translation3::new(0.5, 0.0, -1.0).to_homogeneous(),
+        );
+        assert_eq!(
+            vec![
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector3::new(1.5, 0.0, -1.0),
+                Vector3::new(2.5, 0.0, -1.0)
+            ],
+            points
+                .iter_attribute::<Vector3<f32>>(&local_position)
+                .collect::<Vec<_>>()
+        );
+    }
+}
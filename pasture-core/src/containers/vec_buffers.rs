@@ -1,7 +1,8 @@
-use std::{collections::HashMap, iter::FromIterator, ops::Range};
+use alloc::{borrow::ToOwned, collections::BTreeMap, format, vec, vec::Vec};
+use core::{any::Any, iter::FromIterator, ops::Range};
 
 use crate::{
-    layout::{PointAttributeDefinition, PointLayout, PointType, PrimitiveType},
+    layout::{AttributeAliases, PointAttributeDefinition, PointLayout, PointType, PrimitiveType},
     util::{sort_untyped_slice_by_permutation, view_raw_bytes},
 };
 
@@ -10,6 +11,7 @@ use super::{
     PerAttributePointBuffer, PerAttributePointBufferMut, PerAttributePointBufferSlice,
     PerAttributePointBufferSliceMut, PointBuffer, PointBufferWriteable,
 };
+#[cfg(feature = "rayon")]
 use rayon::prelude::*;
 
 /// `PointBuffer` type that uses Interleaved memory layout and `Vec`-based owning storage for point data
@@ -63,6 +65,54 @@ impl InterleavedVecPointStorage {
         }
     }
 
+    /// Returns the number of points that the associated `InterleavedVecPointStorage` can hold without reallocating
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pasture_core::containers::*;
+    /// # use pasture_core::layout::*;
+    /// let layout = PointLayout::from_attributes(&[attributes::POSITION_3D]);
+    /// let storage = InterleavedVecPointStorage::with_capacity(16, layout);
+    /// assert!(storage.capacity() >= 16);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        if self.size_of_point_entry == 0 {
+            return 0;
+        }
+        self.points.capacity() / self.size_of_point_entry as usize
+    }
+
+    /// Renames the attribute `old_name` of the associated `InterleavedVecPointStorage` to `new_name` without copying
+    /// any point data, see `PointLayout::rename_attribute`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pasture_core::containers::*;
+    /// # use pasture_core::layout::*;
+    /// let layout = PointLayout::from_attributes(&[attributes::POSITION_3D, attributes::INTENSITY]);
+    /// let mut storage = InterleavedVecPointStorage::new(layout);
+    /// storage.rename_attribute(attributes::INTENSITY.name(), "Amplitude");
+    /// assert!(storage.point_layout().has_attribute_with_name("Amplitude"));
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If there is no attribute with the name `old_name`, or if an attribute with the name `new_name` already exists
+    pub fn rename_attribute(&mut self, old_name: &str, new_name: &'static str) {
+        self.layout.rename_attribute(old_name, new_name);
+    }
+
+    /// Renames all attributes of the associated `InterleavedVecPointStorage` whose names are aliases to their
+    /// canonical names without copying any point data, see `PointLayout::apply_aliases`
+    pub fn apply_aliases(
+        &mut self,
+        aliases: &AttributeAliases,
+    ) -> Vec<(&'static str, &'static str)> {
+        self.layout.apply_aliases(aliases)
+    }
+
     /// Pushes a single point into the associated `InterleavedVecPointStorage`. *Note:* For safety
     /// reasons this function performs a `PointLayout` check. If you want to add many points quickly, either use
     /// the `push_points` variant which takes a range, or use the `push_point_unchecked` variant to circumvent checks.
@@ -159,9 +209,9 @@ impl InterleavedVecPointStorage {
         }
 
         let points_as_bytes = unsafe {
-            std::slice::from_raw_parts(
+            core::slice::from_raw_parts(
                 points.as_ptr() as *const u8,
-                points.len() * std::mem::size_of::<T>(),
+                points.len() * core::mem::size_of::<T>(),
             )
         };
         self.points.extend_from_slice(points_as_bytes);
@@ -190,7 +240,7 @@ impl InterleavedVecPointStorage {
         }
 
         let typed_points = unsafe {
-            std::slice::from_raw_parts_mut(self.points.as_mut_ptr() as *mut T, self.len())
+            core::slice::from_raw_parts_mut(self.points.as_mut_ptr() as *mut T, self.len())
         };
         typed_points.sort();
     }
@@ -200,13 +250,16 @@ impl InterleavedVecPointStorage {
     /// # Panics
     ///
     /// If the `PointLayout` of `T` does not match the underlying layout
-    pub fn sort_by<T: PointType, C: FnMut(&T, &T) -> std::cmp::Ordering>(&mut self, comparator: C) {
+    pub fn sort_by<T: PointType, C: FnMut(&T, &T) -> core::cmp::Ordering>(
+        &mut self,
+        comparator: C,
+    ) {
         if self.layout != T::layout() {
             panic!("InterleavedVecPointStorage::sort_by: Point type `T` does not match layout of this buffer!");
         }
 
         let typed_points = unsafe {
-            std::slice::from_raw_parts_mut(self.points.as_mut_ptr() as *mut T, self.len())
+            core::slice::from_raw_parts_mut(self.points.as_mut_ptr() as *mut T, self.len())
         };
         typed_points.sort_by(comparator);
     }
@@ -413,6 +466,18 @@ impl PointBuffer for InterleavedVecPointStorage {
     fn as_interleaved(&self) -> Option<&dyn InterleavedPointBuffer> {
         Some(self)
     }
+
+    fn as_interleaved_mut(&mut self) -> Option<&mut dyn InterleavedPointBufferMut> {
+        Some(self)
+    }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
 }
 
 impl PointBufferWriteable for InterleavedVecPointStorage {
@@ -548,7 +613,8 @@ impl InterleavedPointBufferMut for InterleavedVecPointStorage {
 
 impl<T: PointType> FromIterator<T> for InterleavedVecPointStorage {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut buffer = Self::new(T::layout());
+        let iter = iter.into_iter();
+        let mut buffer = Self::with_capacity(iter.size_hint().0, T::layout());
         for point in iter {
             buffer.push_point_unchecked(point);
         }
@@ -581,10 +647,34 @@ impl<T: PointType> From<Vec<T>> for InterleavedVecPointStorage {
     }
 }
 
+/// Appends one attribute of all points in the interleaved `points` to `attribute_data`. The attribute has `size` bytes
+/// and starts at `offset` within each point of `stride` bytes. The memory for all points is allocated once, and the
+/// copy loop has no capacity checks, so this is much faster than extending `attribute_data` point by point
+fn append_strided_attribute(
+    attribute_data: &mut Vec<u8>,
+    points: &[u8],
+    stride: usize,
+    offset: usize,
+    size: usize,
+) {
+    if size == 0 || stride == 0 {
+        return;
+    }
+    let num_points = points.len() / stride;
+    let old_len = attribute_data.len();
+    attribute_data.resize(old_len + num_points * size, 0);
+    for (target, point) in attribute_data[old_len..]
+        .chunks_exact_mut(size)
+        .zip(points.chunks_exact(stride))
+    {
+        target.copy_from_slice(&point[offset..offset + size]);
+    }
+}
+
 /// `PointBuffer` type that uses PerAttribute memory layout and `Vec`-based owning storage for point data
 pub struct PerAttributeVecPointStorage {
     layout: PointLayout,
-    attributes: HashMap<&'static str, Vec<u8>>,
+    attributes: BTreeMap<&'static str, Vec<u8>>,
 }
 
 impl PerAttributeVecPointStorage {
@@ -603,7 +693,7 @@ impl PerAttributeVecPointStorage {
         let attributes = layout
             .attributes()
             .map(|attribute| (attribute.name(), vec![]))
-            .collect::<HashMap<_, _>>();
+            .collect::<BTreeMap<_, _>>();
         Self { layout, attributes }
     }
 
@@ -627,10 +717,33 @@ impl PerAttributeVecPointStorage {
                 let attribute_bytes = capacity * attribute.size() as usize;
                 (attribute.name(), Vec::with_capacity(attribute_bytes))
             })
-            .collect::<HashMap<_, _>>();
+            .collect::<BTreeMap<_, _>>();
         Self { layout, attributes }
     }
 
+    /// Returns the number of points that the associated `PerAttributeVecPointStorage` can hold without reallocating
+    /// any of its attribute buffers
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pasture_core::containers::*;
+    /// # use pasture_core::layout::*;
+    /// let layout = PointLayout::from_attributes(&[attributes::POSITION_3D]);
+    /// let storage = PerAttributeVecPointStorage::with_capacity(16, layout);
+    /// assert!(storage.capacity() >= 16);
+    /// ```
+    pub fn capacity(&self) -> usize {
+        self.layout
+            .attributes()
+            .filter(|attribute| attribute.size() > 0)
+            .map(|attribute| {
+                self.attributes[attribute.name()].capacity() / attribute.size() as usize
+            })
+            .min()
+            .unwrap_or(0)
+    }
+
     /// Pushes a single point into the associated `PerAttributeVecPointStorage`.
     ///
     /// # Examples
@@ -656,9 +769,8 @@ impl PerAttributeVecPointStorage {
     pub fn push_point<T: PointType>(&mut self, point: T) {
         // We don't care for the attribute offsets in T::layout(), because PerAttributeVecPointStorage stores each
         // attribute in a separate Vec. So we only compare that all attributes of T::layout() exist and that their
-        // types are equal. This is done implicitly with the 'self.attributes.get_mut' call below
-
-        self.reserve(1);
+        // types are equal. This is done implicitly with the 'self.attributes.get_mut' call below. There is no call to
+        // 'reserve', because it would look up every attribute vector a second time for each point
 
         let point_bytes = unsafe { view_raw_bytes(&point) };
         let point_layout = T::layout();
@@ -695,21 +807,78 @@ impl PerAttributeVecPointStorage {
     ///
     /// If the `PointLayout` of type `T` does not match the layout of the associated `PerAttributeVecPointStorage`.
     pub fn push_points<T: PointType>(&mut self, points: &[T]) {
-        self.reserve(points.len());
-
+        let points_bytes = unsafe {
+            core::slice::from_raw_parts(
+                points.as_ptr() as *const u8,
+                points.len() * core::mem::size_of::<T>(),
+            )
+        };
         let point_layout = T::layout();
         for attribute in point_layout.attributes() {
             let offset_to_attribute_in_point = attribute.offset() as usize;
             let attribute_buffer = self.attributes.get_mut(attribute.name()).unwrap_or_else(|| panic!("PerAttributeVecPointStorage::push_points: Attribute {} of points does not exist in the PointLayout of this buffer!", attribute));
 
-            for point in points {
-                let point_bytes = unsafe { view_raw_bytes(point) };
-                let point_slice = &point_bytes[offset_to_attribute_in_point
-                    ..offset_to_attribute_in_point + attribute.size() as usize];
+            append_strided_attribute(
+                attribute_buffer,
+                points_bytes,
+                core::mem::size_of::<T>(),
+                offset_to_attribute_in_point,
+                attribute.size() as usize,
+            );
+        }
+    }
 
-                attribute_buffer.extend_from_slice(point_slice);
+    /// Pushes a range of points into the associated `PerAttributeVecPointStorage`, given as one slice of raw bytes for
+    /// each attribute, in the order of the attributes in the `PointLayout` of this buffer. Each slice is appended to
+    /// its attribute at once, which is much faster than pushing the points one by one if the data is already stored
+    /// per attribute, e.g. when it was decoded from a file format that stores each attribute separately.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use pasture_core::containers::*;
+    /// # use pasture_core::layout::*;
+    /// # use pasture_derive::PointType;
+    ///
+    /// #[repr(C)]
+    /// #[derive(PointType)]
+    /// struct MyPointType(#[pasture(BUILTIN_INTENSITY)] u16, #[pasture(BUILTIN_CLASSIFICATION)] u8);
+    ///
+    /// {
+    ///   let mut storage = PerAttributeVecPointStorage::new(MyPointType::layout());
+    ///   let intensities = [42_u16, 43_u16];
+    ///   let intensity_bytes = unsafe { std::slice::from_raw_parts(intensities.as_ptr() as *const u8, 4) };
+    ///   storage.push_points_from_slices(&[intensity_bytes, &[1, 2]]);
+    ///
+    ///   assert_eq!(2, storage.len());
+    /// }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the number of slices does not match the number of attributes in the `PointLayout` of this buffer, or if the
+    /// slices don't contain data for the same number of points
+    pub fn push_points_from_slices(&mut self, attribute_data: &[&[u8]]) {
+        if attribute_data.len() != self.layout.attributes().count() {
+            panic!("PerAttributeVecPointStorage::push_points_from_slices: Expected one slice for each of the {} attributes of this buffer, but got {} slices!", self.layout.attributes().count(), attribute_data.len());
+        }
+        let mut num_new_points = None;
+        for (attribute, data) in self.layout.attributes().zip(attribute_data.iter()) {
+            let attribute_size = attribute.size() as usize;
+            if data.len() % attribute_size != 0
+                || *num_new_points.get_or_insert(data.len() / attribute_size)
+                    != data.len() / attribute_size
+            {
+                panic!("PerAttributeVecPointStorage::push_points_from_slices: Slices must contain data for the same number of points!");
             }
         }
+
+        for (attribute, data) in self.layout.attributes().zip(attribute_data.iter()) {
+            self.attributes
+                .get_mut(attribute.name())
+                .unwrap()
+                .extend_from_slice(data);
+        }
     }
 
     /// Create a builder for pushing attribute data into the associated `PerAttributeVecPointStorage` one by one
@@ -734,7 +903,7 @@ impl PerAttributeVecPointStorage {
 
         let typed_attribute = self.attributes.get(attribute.name()).map(|untyped_attribute| {
             return unsafe {
-                std::slice::from_raw_parts(untyped_attribute.as_ptr() as *const T, self.len())
+                core::slice::from_raw_parts(untyped_attribute.as_ptr() as *const T, self.len())
             };
         }).expect(&format!("PerAttributePointBuffer:sort_by_attribute: Attribute {:?} not contained in this buffers PointLayout!", attribute));
 
@@ -760,7 +929,9 @@ impl PerAttributeVecPointStorage {
             });
     }
 
-    /// Like `sort_by_attribute`, but sorts each attribute in parallel. Uses the [`rayon`]() crate for parallelization
+    /// Like `sort_by_attribute`, but sorts each attribute in parallel. Uses the [`rayon`]() crate for parallelization,
+    /// so it is only available with the `rayon` feature (which is enabled by default)
+    #[cfg(feature = "rayon")]
     pub fn par_sort_by_attribute<T: PrimitiveType + Ord>(
         &mut self,
         attribute: &PointAttributeDefinition,
@@ -771,7 +942,7 @@ impl PerAttributeVecPointStorage {
 
         let typed_attribute = self.attributes.get(attribute.name()).map(|untyped_attribute| {
             return unsafe {
-                std::slice::from_raw_parts(untyped_attribute.as_ptr() as *const T, self.len())
+                core::slice::from_raw_parts(untyped_attribute.as_ptr() as *const T, self.len())
             };
         }).expect(&format!("PerAttributePointBuffer:sort_by_attribute: Attribute {:?} not contained in this buffers PointLayout!", attribute));
 
@@ -787,7 +958,7 @@ impl PerAttributeVecPointStorage {
                     self.layout.get_attribute_by_name(key).unwrap().size(),
                 )
             })
-            .collect::<HashMap<_, _>>();
+            .collect::<BTreeMap<_, _>>();
 
         self.attributes
             .par_iter_mut()
@@ -810,6 +981,36 @@ impl PerAttributeVecPointStorage {
         }
     }
 
+    /// Renames the attribute `old_name` of the associated `PerAttributeVecPointStorage` to `new_name` without copying
+    /// any point data, see `PointLayout::rename_attribute`
+    ///
+    /// # Panics
+    ///
+    /// If there is no attribute with the name `old_name`, or if an attribute with the name `new_name` already exists
+    pub fn rename_attribute(&mut self, old_name: &str, new_name: &'static str) {
+        self.layout.rename_attribute(old_name, new_name);
+        if let Some(attribute_data) = self.attributes.remove(old_name) {
+            self.attributes.insert(new_name, attribute_data);
+        }
+    }
+
+    /// Renames all attributes of the associated `PerAttributeVecPointStorage` whose names are aliases to their
+    /// canonical names without copying any point data, see `PointLayout::apply_aliases`
+    pub fn apply_aliases(
+        &mut self,
+        aliases: &AttributeAliases,
+    ) -> Vec<(&'static str, &'static str)> {
+        let renamed_attributes = self.layout.apply_aliases(aliases);
+        let attribute_data = renamed_attributes
+            .iter()
+            .map(|(old_name, _)| self.attributes.remove(old_name).unwrap())
+            .collect::<Vec<_>>();
+        for ((_, new_name), data) in renamed_attributes.iter().zip(attribute_data) {
+            self.attributes.insert(*new_name, data);
+        }
+        renamed_attributes
+    }
+
     fn push_interleaved(&mut self, points: &dyn InterleavedPointBuffer) {
         if !points
             .point_layout()
@@ -823,15 +1024,13 @@ impl PerAttributeVecPointStorage {
 
         for (attribute_name, attribute_data) in self.attributes.iter_mut() {
             let current_attribute = self.layout.get_attribute_by_name(attribute_name).unwrap();
-            let base_offset = current_attribute.offset() as usize;
-            let attribute_size = current_attribute.size() as usize;
-
-            for idx in 0..points.len() {
-                let attribute_start = base_offset + (idx * stride);
-                let attribute_end = attribute_start + attribute_size;
-                let attribute_slice = &raw_point_data[attribute_start..attribute_end];
-                attribute_data.extend_from_slice(attribute_slice);
-            }
+            append_strided_attribute(
+                attribute_data,
+                raw_point_data,
+                stride,
+                current_attribute.offset() as usize,
+                current_attribute.size() as usize,
+            );
         }
     }
 
@@ -1002,6 +1201,14 @@ impl PointBuffer for PerAttributeVecPointStorage {
     fn as_per_attribute(&self) -> Option<&dyn PerAttributePointBuffer> {
         Some(self)
     }
+
+    fn as_any(&self) -> Option<&dyn Any> {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn Any> {
+        Some(self)
+    }
 }
 
 impl PointBufferWriteable for PerAttributeVecPointStorage {
@@ -1213,7 +1420,8 @@ impl<'p> PerAttributePointBufferMut<'p> for PerAttributeVecPointStorage {
 
 impl<T: PointType> FromIterator<T> for PerAttributeVecPointStorage {
     fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
-        let mut buffer = Self::new(T::layout());
+        let iter = iter.into_iter();
+        let mut buffer = Self::with_capacity(iter.size_hint().0, T::layout());
         for point in iter {
             buffer.push_point(point);
         }
@@ -1251,7 +1459,7 @@ impl<T: PointType> From<Vec<T>> for PerAttributeVecPointStorage {
  */
 pub struct PerAttributeVecPointStoragePusher<'a> {
     buffer: &'a mut PerAttributeVecPointStorage,
-    new_attribute_data: HashMap<&'static str, Vec<u8>>,
+    new_attribute_data: BTreeMap<&'static str, Vec<u8>>,
 }
 
 impl<'a> PerAttributeVecPointStoragePusher<'a> {
@@ -1356,9 +1564,9 @@ impl<'a> PerAttributeVecPointStoragePusher<'a> {
         }
         let attribute_buffer = self.new_attribute_data.get_mut(attribute.name()).unwrap();
         let value_bytes = unsafe {
-            std::slice::from_raw_parts(
+            core::slice::from_raw_parts(
                 values.as_ptr() as *const u8,
-                values.len() * std::mem::size_of::<T>(),
+                values.len() * core::mem::size_of::<T>(),
             )
         };
         attribute_buffer.extend_from_slice(value_bytes);
@@ -2421,6 +2629,35 @@ mod tests {
         buffer.push_points(&[OtherPointType(Vector3::new(0.0, 1.0, 2.0), 23)]);
     }
 
+    #[test]
+    fn test_per_attribute_vec_storage_push_points_from_slices() {
+        let mut storage = PerAttributeVecPointStorage::new(TestPointType::layout());
+        storage.push_points(&[TestPointType(41, 0.5)]);
+
+        let intensities = [42_u16, 43_u16];
+        let gps_times = [0.123_f64, 0.456_f64];
+        let intensity_bytes = unsafe { view_raw_bytes(&intensities) };
+        let gps_time_bytes = unsafe { view_raw_bytes(&gps_times) };
+        storage.push_points_from_slices(&[intensity_bytes, gps_time_bytes]);
+
+        assert_eq!(3, storage.len());
+        assert_eq!(
+            &[41, 42, 43],
+            storage.get_attribute_range_ref::<u16>(0..3, &attributes::INTENSITY)
+        );
+        assert_eq!(
+            &[0.5, 0.123, 0.456],
+            storage.get_attribute_range_ref::<f64>(0..3, &attributes::GPS_TIME)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_per_attribute_vec_storage_push_points_from_slices_wrong_count() {
+        let mut storage = PerAttributeVecPointStorage::new(TestPointType::layout());
+        storage.push_points_from_slices(&[&[0; 4], &[0; 8]]);
+    }
+
     #[test]
     fn test_per_attribute_vec_storage_extend_from_interleaved() {
         let mut per_attribute_buffer = PerAttributeVecPointStorage::new(TestPointType::layout());
@@ -3426,4 +3663,71 @@ mod tests {
 
         buffer.transform_attribute(INTENSITY.name(), |_, _value: &mut Vector3<u16>| {});
     }
+
+    #[test]
+    fn test_rename_attributes_without_copying() {
+        let points = [TestPointType(1, 2.0), TestPointType(3, 4.0)];
+        let amplitude = PointAttributeDefinition::custom("Amplitude", INTENSITY.datatype());
+        let aliases = AttributeAliases::new().with_alias(INTENSITY.name(), "Amplitude");
+
+        let mut interleaved = InterleavedVecPointStorage::new(TestPointType::layout());
+        interleaved.push_points(&points);
+        assert_eq!(
+            vec![(INTENSITY.name(), "Amplitude")],
+            interleaved.apply_aliases(&aliases)
+        );
+        assert_eq!(3, interleaved.get_attribute::<u16>(&amplitude, 1));
+
+        let mut per_attribute = PerAttributeVecPointStorage::new(TestPointType::layout());
+        per_attribute.push_points(&points);
+        per_attribute.apply_aliases(&aliases);
+        assert_eq!(3, per_attribute.get_attribute::<u16>(&amplitude, 1));
+        assert_eq!(
+            vec![1, 3],
+            per_attribute.get_attribute_range_ref::<u16>(&amplitude, 0..2)
+        );
+
+        per_attribute.rename_attribute("Amplitude", INTENSITY.name());
+        assert_eq!(
+            points.to_vec(),
+            per_attribute
+                .iter_point::<TestPointType>()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_downcast_point_buffers() {
+        let mut interleaved: Box<dyn PointBuffer> = Box::new(InterleavedVecPointStorage::from(
+            vec![TestPointType(1, 2.0)],
+        ));
+        assert!(interleaved.is::<InterleavedVecPointStorage>());
+        assert!(!interleaved.is::<PerAttributeVecPointStorage>());
+        assert!(interleaved.as_interleaved_mut().is_some());
+        interleaved
+            .downcast_mut::<InterleavedVecPointStorage>()
+            .unwrap()
+            .push_point(TestPointType(3, 4.0));
+        assert_eq!(2, interleaved.len());
+
+        let per_attribute: Box<dyn PointBuffer> = Box::new(PerAttributeVecPointStorage::from(
+            vec![TestPointType(1, 2.0)],
+        ));
+        assert!(per_attribute.as_per_attribute().is_some());
+        assert!(per_attribute
+            .downcast_ref::<InterleavedVecPointStorage>()
+            .is_none());
+        assert_eq!(
+            1,
+            per_attribute
+                .downcast_ref::<PerAttributeVecPointStorage>()
+                .unwrap()
+                .len()
+        );
+
+        let view = InterleavedPointView::from_slice(&[TestPointType(1, 2.0)]);
+        let view: &dyn PointBuffer = &view;
+        assert!(view.as_interleaved().is_some());
+        assert!(view.as_any().is_none());
+    }
 }
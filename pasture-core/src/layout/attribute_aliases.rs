@@ -0,0 +1,107 @@
+use alloc::{collections::BTreeMap, string::String};
+
+/// Alternative names (aliases) for point attributes. Datasets from different sources often store the same custom
+/// attribute under different names (e.g. `Reflectance`, `reflectance` and `Amplitude`). `AttributeAliases` maps each
+/// alias to a single canonical name, which can be used to look up attributes regardless of the name they were written
+/// with (see `PointLayout::get_attribute_by_name_with_aliases`), or to rename them to their canonical names (see
+/// `PointLayout::apply_aliases`)
+/// ```
+/// # use pasture_core::layout::*;
+/// let aliases = AttributeAliases::new()
+///     .with_alias("reflectance", "Reflectance")
+///     .with_alias("Amplitude", "Reflectance");
+/// assert_eq!("Reflectance", aliases.canonical_name("Amplitude"));
+/// assert_eq!("Intensity", aliases.canonical_name("Intensity"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AttributeAliases {
+    canonical_names: BTreeMap<String, &'static str>,
+}
+
+impl AttributeAliases {
+    /// Creates a new `AttributeAliases` without any aliases
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds `alias` as an alias for the attribute with the canonical name `canonical_name`
+    ///
+    /// # Panics
+    ///
+    /// If `alias` is already an alias for a different canonical name, or if `canonical_name` is itself an alias
+    pub fn with_alias<S: Into<String>>(mut self, alias: S, canonical_name: &'static str) -> Self {
+        let alias = alias.into();
+        if self.canonical_names.contains_key(canonical_name) {
+            panic!(
+                "AttributeAliases::with_alias: {} is an alias and can't be a canonical name",
+                canonical_name
+            );
+        }
+        if let Some(existing) = self.canonical_names.get(&alias) {
+            if *existing != canonical_name {
+                panic!(
+                    "AttributeAliases::with_alias: {} is already an alias for {}",
+                    alias, existing
+                );
+            }
+        }
+        if alias != canonical_name {
+            self.canonical_names.insert(alias, canonical_name);
+        }
+        self
+    }
+
+    /// Returns the canonical name for the given attribute `name`. If `name` is not an alias, `name` itself is returned
+    pub fn canonical_name<'a>(&self, name: &'a str) -> &'a str {
+        self.canonical_names.get(name).copied().unwrap_or(name)
+    }
+
+    /// Returns the canonical name for the given attribute `name`, if `name` is an alias
+    pub fn canonical_name_of_alias(&self, name: &str) -> Option<&'static str> {
+        self.canonical_names.get(name).copied()
+    }
+
+    /// Returns all names that refer to the same attribute as `name`: `name` itself first, followed by its canonical name
+    /// and all other aliases of the canonical name
+    pub fn equivalent_names<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        let canonical_name = self.canonical_name(name);
+        let other_aliases = self
+            .canonical_names
+            .iter()
+            .filter(move |(alias, canonical)| **canonical == canonical_name && *alias != name)
+            .map(|(alias, _)| alias.as_str());
+        core::iter::once(name)
+            .chain(core::iter::once(canonical_name).filter(move |canonical| *canonical != name))
+            .chain(other_aliases)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_equivalent_names() {
+        let aliases = AttributeAliases::new()
+            .with_alias("reflectance", "Reflectance")
+            .with_alias("Amplitude", "Reflectance");
+
+        let mut names = aliases.equivalent_names("Amplitude").collect::<Vec<_>>();
+        assert_eq!(vec!["Amplitude", "Reflectance", "reflectance"], names);
+        names = aliases.equivalent_names("Reflectance").collect();
+        names[1..].sort_unstable();
+        assert_eq!(vec!["Reflectance", "Amplitude", "reflectance"], names);
+        assert_eq!(
+            vec!["Intensity"],
+            aliases.equivalent_names("Intensity").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_conflicting_alias_panics() {
+        let _ = AttributeAliases::new()
+            .with_alias("Amplitude", "Reflectance")
+            .with_alias("Amplitude", "Intensity");
+    }
+}
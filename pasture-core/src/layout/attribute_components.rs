@@ -0,0 +1,90 @@
+use alloc::{borrow::ToOwned, format, string::String, vec, vec::Vec};
+use core::convert::TryInto;
+
+use super::PointAttributeDataType;
+
+/// Decodes a single scalar component of the given `component_type` from its native-endian `bytes` and converts it to
+/// `f64`. `Bool` components are decoded as 0 or 1
+///
+/// ```
+/// # use pasture_core::layout::*;
+/// assert_eq!(-2.0, decode_component(PointAttributeDataType::I16, &(-2i16).to_ne_bytes()));
+/// assert_eq!(1.0, decode_component(PointAttributeDataType::Bool, &[1]));
+/// ```
+///
+/// # Panics
+///
+/// If `component_type` is a vector or array type, or if `bytes` doesn't have the size of `component_type`
+pub fn decode_component(component_type: PointAttributeDataType, bytes: &[u8]) -> f64 {
+    match component_type {
+        PointAttributeDataType::U8 => u8::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+        PointAttributeDataType::Bool => {
+            (u8::from_ne_bytes(bytes.try_into().unwrap()) != 0) as u8 as f64
+        }
+        PointAttributeDataType::I8 => i8::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+        PointAttributeDataType::U16 => u16::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+        PointAttributeDataType::I16 => i16::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+        PointAttributeDataType::U32 => u32::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+        PointAttributeDataType::I32 => i32::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+        PointAttributeDataType::U64 => u64::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+        PointAttributeDataType::I64 => i64::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+        PointAttributeDataType::F32 => f32::from_ne_bytes(bytes.try_into().unwrap()) as f64,
+        PointAttributeDataType::F64 => f64::from_ne_bytes(bytes.try_into().unwrap()),
+        other => panic!("decode_component: {} is no scalar datatype", other),
+    }
+}
+
+/// Returns an iterator over the components of all tightly packed values of the given `datatype` in `bytes`, converted
+/// to `f64`. Scalar values have a single component, vector and array values have one component per element (see
+/// [components](PointAttributeDataType::components)). With `n` components per value, the `i`-th item of the iterator
+/// is component `i % n` of value `i / n`. Incomplete values at the end of `bytes` are ignored
+///
+/// ```
+/// # use pasture_core::layout::*;
+/// let bytes = [1u16, 2, 3, 4, 5, 6]
+///     .iter()
+///     .flat_map(|value| value.to_ne_bytes().to_vec())
+///     .collect::<Vec<_>>();
+/// assert_eq!(
+///     vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+///     decode_components(PointAttributeDataType::Vec3u16, &bytes).collect::<Vec<_>>()
+/// );
+/// ```
+pub fn decode_components(
+    datatype: PointAttributeDataType,
+    bytes: &[u8],
+) -> impl Iterator<Item = f64> + '_ {
+    let (component_type, component_count) = datatype.components();
+    let value_size = datatype.size() as usize;
+    let complete_values = bytes.len() - bytes.len() % value_size;
+    bytes[..complete_values]
+        .chunks_exact(component_type.size() as usize)
+        .take(complete_values / value_size * component_count)
+        .map(move |component| decode_component(component_type, component))
+}
+
+/// Returns names for the components of an attribute with the given `attribute_name` and `component_count` components:
+/// The name of the attribute for scalar attributes, `name.x` to `name.w` for attributes with up to four components and
+/// `name[index]` for larger arrays
+///
+/// ```
+/// # use pasture_core::layout::*;
+/// assert_eq!(vec!["Intensity"], component_names("Intensity", 1));
+/// assert_eq!(vec!["Position3D.x", "Position3D.y", "Position3D.z"], component_names("Position3D", 3));
+/// assert_eq!("Echoes[5]", component_names("Echoes", 6)[5]);
+/// ```
+pub fn component_names(attribute_name: &str, component_count: usize) -> Vec<String> {
+    if component_count == 1 {
+        return vec![attribute_name.to_owned()];
+    }
+    if component_count > 4 {
+        return (0..component_count)
+            .map(|index| format!("{}[{}]", attribute_name, index))
+            .collect();
+    }
+    ["x", "y", "z", "w"]
+        .iter()
+        .take(component_count)
+        .map(|component| format!("{}.{}", attribute_name, component))
+        .collect()
+}
@@ -13,9 +13,9 @@
 //! The conversion then operates on these two buffers. As this is a *highly* unsafe operation where all sorts of things
 //! could go wrong, any conversion is only valid together with the *exact* `PointLayout` of both `A` and `B`!
 
-use lazy_static::lazy_static;
+use alloc::vec::Vec;
+use core::ops::Range;
 use nalgebra::{Scalar, Vector3};
-use std::{collections::HashMap, ops::Range};
 
 use crate::layout::{PointAttributeDataType, PointAttributeDefinition, PointLayout};
 
@@ -135,139 +135,78 @@ fn get_position_converter(
     from_type: PointAttributeDataType,
     to_type: PointAttributeDataType,
 ) -> Option<AttributeConversionFn> {
-    lazy_static! {
-        static ref POSITION_CONVERTERS: HashMap<(PointAttributeDataType, PointAttributeDataType), AttributeConversionFn> = {
-            let mut converters = HashMap::<
-                (PointAttributeDataType, PointAttributeDataType),
-                AttributeConversionFn,
-            >::new();
-            converters.insert(
-                (
-                    PointAttributeDataType::Vec3f64,
-                    PointAttributeDataType::Vec3f32,
-                ),
-                convert_position_from_vec3f64_to_vec3f32,
-            );
-            converters.insert(
-                (
-                    PointAttributeDataType::Vec3f32,
-                    PointAttributeDataType::Vec3f64,
-                ),
-                convert_position_from_vec3f32_to_vec3f64,
-            );
-            converters
-        };
+    match (from_type, to_type) {
+        (PointAttributeDataType::Vec3f64, PointAttributeDataType::Vec3f32) => {
+            Some(convert_position_from_vec3f64_to_vec3f32)
+        }
+        (PointAttributeDataType::Vec3f32, PointAttributeDataType::Vec3f64) => {
+            Some(convert_position_from_vec3f32_to_vec3f64)
+        }
+        _ => None,
     }
-
-    let key = (from_type, to_type);
-    POSITION_CONVERTERS.get(&key).map(|&fptr| fptr)
 }
 
 fn get_color_rgb_converter(
     from_type: PointAttributeDataType,
     to_type: PointAttributeDataType,
 ) -> Option<AttributeConversionFn> {
-    lazy_static! {
-        static ref COLOR_RGB_CONVERTERS: HashMap<(PointAttributeDataType, PointAttributeDataType), AttributeConversionFn> = {
-            let mut converters = HashMap::<
-                (PointAttributeDataType, PointAttributeDataType),
-                AttributeConversionFn,
-            >::new();
-            converters.insert(
-                (
-                    PointAttributeDataType::Vec3u16,
-                    PointAttributeDataType::Vec3u8,
-                ),
-                convert_color_rgb_from_vec3u16_to_vec3u8,
-            );
-            converters.insert(
-                (
-                    PointAttributeDataType::Vec3u8,
-                    PointAttributeDataType::Vec3u16,
-                ),
-                convert_color_rgb_from_vec3u8_to_vec3u16,
-            );
-            converters
-        };
+    match (from_type, to_type) {
+        (PointAttributeDataType::Vec3u16, PointAttributeDataType::Vec3u8) => {
+            Some(convert_color_rgb_from_vec3u16_to_vec3u8)
+        }
+        (PointAttributeDataType::Vec3u8, PointAttributeDataType::Vec3u16) => {
+            Some(convert_color_rgb_from_vec3u8_to_vec3u16)
+        }
+        _ => None,
     }
-
-    let key = (from_type, to_type);
-    COLOR_RGB_CONVERTERS.get(&key).map(|&fptr| fptr)
-}
-
-macro_rules! insert_converter_using_into {
-    ($prim_from:ident, $prim_to:ident, $type_from:ident, $type_to:ident, $map:expr) => {
-        ($map).insert(
-            (
-                PointAttributeDataType::$type_from,
-                PointAttributeDataType::$type_to,
-            ),
-            convert_using_into::<$prim_from, $prim_to>,
-        )
-    };
-}
-
-macro_rules! insert_converter_using_as {
-    ($type_from:ident, $type_to:ident, $convert_fn:ident, $map:expr) => {
-        ($map).insert(
-            (
-                PointAttributeDataType::$type_from,
-                PointAttributeDataType::$type_to,
-            ),
-            $convert_fn,
-        )
-    };
 }
 
 /// Returns a generic converter that can convert between primitive types. Going from smaller to larger types is realized
-/// through `.into()` calls, while going from larger to smaller types is done through coercions (using `as`) where possible
+/// through `.into()` calls, while going from larger to smaller types is done through coercions (using `as`) where possible.
+/// The lookup is a plain `match` instead of a lazily initialized map, so that it works without `std`
+///
+/// # Panics
+///
+/// If there is no conversion from `from_type` to `to_type`
 fn get_generic_converter(
     from_type: PointAttributeDataType,
     to_type: PointAttributeDataType,
 ) -> Option<AttributeConversionFn> {
-    lazy_static! {
-        static ref GENERIC_CONVERTERS: HashMap<(PointAttributeDataType, PointAttributeDataType), AttributeConversionFn> = {
-            let mut converters = HashMap::<
-                (PointAttributeDataType, PointAttributeDataType),
-                AttributeConversionFn,
-            >::new();
-            insert_converter_using_into!(u8, u16, U8, U16, converters);
-            insert_converter_using_into!(u8, u32, U8, U32, converters);
-            insert_converter_using_into!(u8, u64, U8, U64, converters);
-            insert_converter_using_into!(u16, u32, U16, U32, converters);
-            insert_converter_using_into!(u16, u64, U16, U64, converters);
-            insert_converter_using_into!(u32, u64, U32, U64, converters);
-
-            insert_converter_using_into!(i8, i16, I8, I16, converters);
-            insert_converter_using_into!(i8, i32, I8, I32, converters);
-            insert_converter_using_into!(i8, i64, I8, I64, converters);
-            insert_converter_using_into!(i16, i32, I16, I32, converters);
-            insert_converter_using_into!(i16, i64, I16, I64, converters);
-            insert_converter_using_into!(i32, i64, I32, I64, converters);
-
-            insert_converter_using_as!(U16, U8, convert_u16_to_u8, converters);
-            insert_converter_using_as!(U32, U8, convert_u32_to_u8, converters);
-            insert_converter_using_as!(U64, U8, convert_u64_to_u8, converters);
-            insert_converter_using_as!(U32, U16, convert_u32_to_u16, converters);
-            insert_converter_using_as!(U64, U16, convert_u64_to_u16, converters);
-            insert_converter_using_as!(U64, U32, convert_u64_to_u32, converters);
-
-            insert_converter_using_as!(I16, I8, convert_i16_to_i8, converters);
-            insert_converter_using_as!(I32, I8, convert_i32_to_i8, converters);
-            insert_converter_using_as!(I64, I8, convert_i64_to_i8, converters);
-            insert_converter_using_as!(I32, I16, convert_i32_to_i16, converters);
-            insert_converter_using_as!(I64, I16, convert_i64_to_i16, converters);
-            insert_converter_using_as!(I64, I32, convert_i64_to_i32, converters);
-
-            insert_converter_using_as!(F64, F32, convert_f64_to_f32, converters);
-
-            converters
-        };
-    }
-
-    let key = (from_type, to_type);
-    let f = GENERIC_CONVERTERS.get(&key).expect("Invalid conversion");
-    Some(*f)
+    use PointAttributeDataType::*;
+    let converter: AttributeConversionFn = match (from_type, to_type) {
+        (U8, U16) => convert_using_into::<u8, u16>,
+        (U8, U32) => convert_using_into::<u8, u32>,
+        (U8, U64) => convert_using_into::<u8, u64>,
+        (U16, U32) => convert_using_into::<u16, u32>,
+        (U16, U64) => convert_using_into::<u16, u64>,
+        (U32, U64) => convert_using_into::<u32, u64>,
+
+        (I8, I16) => convert_using_into::<i8, i16>,
+        (I8, I32) => convert_using_into::<i8, i32>,
+        (I8, I64) => convert_using_into::<i8, i64>,
+        (I16, I32) => convert_using_into::<i16, i32>,
+        (I16, I64) => convert_using_into::<i16, i64>,
+        (I32, I64) => convert_using_into::<i32, i64>,
+
+        (U16, U8) => convert_u16_to_u8,
+        (U32, U8) => convert_u32_to_u8,
+        (U64, U8) => convert_u64_to_u8,
+        (U32, U16) => convert_u32_to_u16,
+        (U64, U16) => convert_u64_to_u16,
+        (U64, U32) => convert_u64_to_u32,
+
+        (I16, I8) => convert_i16_to_i8,
+        (I32, I8) => convert_i32_to_i8,
+        (I64, I8) => convert_i64_to_i8,
+        (I32, I16) => convert_i32_to_i16,
+        (I64, I16) => convert_i64_to_i16,
+        (I64, I32) => convert_i64_to_i32,
+
+        (F64, F32) => convert_f64_to_f32,
+
+        _ => panic!("Invalid conversion"),
+    };
+    Some(converter)
 }
 
 /// Unit conversion function (when from and to represent the same datatype)
@@ -4,5 +4,11 @@ pub use self::point_layout::*;
 mod point_type;
 pub use self::point_type::*;
 
+mod attribute_aliases;
+pub use self::attribute_aliases::*;
+
+mod attribute_components;
+pub use self::attribute_components::*;
+
 pub mod conversion;
 //pub use self::conversion;
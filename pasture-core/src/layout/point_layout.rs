@@ -1,11 +1,13 @@
-use std::{alloc::Layout, fmt::Display};
+use alloc::{collections::BTreeSet, vec, vec::Vec};
+use core::{alloc::Layout, fmt::Display};
 
-use itertools::Itertools;
 use nalgebra::{Vector3, Vector4};
 use static_assertions::const_assert;
 
 use crate::math::Alignable;
 
+use super::AttributeAliases;
+
 mod private {
     use super::*;
 
@@ -27,6 +29,67 @@ mod private {
     impl Sealed for Vector3<f32> {}
     impl Sealed for Vector3<f64> {}
     impl Sealed for Vector4<u8> {}
+    #[cfg(feature = "glam")]
+    impl Sealed for glam::Vec3 {}
+    #[cfg(feature = "glam")]
+    impl Sealed for glam::DVec3 {}
+    impl<T: ArrayElement, const N: usize> Sealed for [T; N] {}
+}
+
+/// Possible data types for the elements of a fixed-size array attribute (see `PointAttributeDataType::Array`). These
+/// are the scalar `PointAttributeDataType`s
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ArrayElementType {
+    /// An unsigned 8-bit integer value, corresponding to Rusts `u8` type
+    U8,
+    /// A signed 8-bit integer value, corresponding to Rusts `i8` type
+    I8,
+    /// An unsigned 16-bit integer value, corresponding to Rusts `u16` type
+    U16,
+    /// A signed 16-bit integer value, corresponding to Rusts `i16` type
+    I16,
+    /// An unsigned 32-bit integer value, corresponding to Rusts `u32` type
+    U32,
+    /// A signed 32-bit integer value, corresponding to Rusts `i32` type
+    I32,
+    /// An unsigned 64-bit integer value, corresponding to Rusts `u64` type
+    U64,
+    /// A signed 64-bit integer value, corresponding to Rusts `i64` type
+    I64,
+    /// A single-precision floating point value, corresponding to Rusts `f32` type
+    F32,
+    /// A double-precision floating point value, corresponding to Rusts `f64` type
+    F64,
+    /// A boolean value, corresponding to Rusts `bool` type
+    Bool,
+}
+
+impl ArrayElementType {
+    /// Returns the scalar `PointAttributeDataType` of the associated `ArrayElementType`
+    /// ```
+    /// # use pasture_core::layout::*;
+    /// assert_eq!(PointAttributeDataType::F32, ArrayElementType::F32.data_type());
+    /// ```
+    pub fn data_type(&self) -> PointAttributeDataType {
+        match self {
+            ArrayElementType::U8 => PointAttributeDataType::U8,
+            ArrayElementType::I8 => PointAttributeDataType::I8,
+            ArrayElementType::U16 => PointAttributeDataType::U16,
+            ArrayElementType::I16 => PointAttributeDataType::I16,
+            ArrayElementType::U32 => PointAttributeDataType::U32,
+            ArrayElementType::I32 => PointAttributeDataType::I32,
+            ArrayElementType::U64 => PointAttributeDataType::U64,
+            ArrayElementType::I64 => PointAttributeDataType::I64,
+            ArrayElementType::F32 => PointAttributeDataType::F32,
+            ArrayElementType::F64 => PointAttributeDataType::F64,
+            ArrayElementType::Bool => PointAttributeDataType::Bool,
+        }
+    }
+
+    /// Size of the associated `ArrayElementType`
+    pub fn size(&self) -> u64 {
+        self.data_type().size()
+    }
 }
 
 /// Possible data types for individual point attributes
@@ -64,6 +127,10 @@ pub enum PointAttributeDataType {
     Vec3f64,
     /// A 4-component vector storing unsigned 8-bit integer values. Corresponding to the `Vector4<u8>` type of the [nalgebra crate](https://crates.io/crates/nalgebra)
     Vec4u8,
+    /// A fixed-size array of scalar values, corresponding to Rusts `[T; N]` type. Stores the type of the elements
+    /// and the number of elements. This is useful for attributes with many values per point, such as feature vectors
+    /// (`[f32; 7]`) or descriptors (`[u8; 16]`)
+    Array(ArrayElementType, u32),
     //TODO REFACTOR Vector types should probably be Point3 instead, or at least use nalgebra::Point3 as their underlying type!
     //TODO Instead of representing each VecN<T> type as a separate literal, might it be possible to do: Vec3(PointAttributeDataType)?
     //Not in that way of course, because of recursive datastructures, but something like that?
@@ -89,35 +156,60 @@ impl PointAttributeDataType {
             PointAttributeDataType::Vec3f32 => 12,
             PointAttributeDataType::Vec3f64 => 24,
             PointAttributeDataType::Vec4u8 => 4,
+            PointAttributeDataType::Array(element, len) => element.size() * *len as u64,
         }
     }
 
     /// Minimum required alignment of the associated `PointAttributeDataType`
     pub fn min_alignment(&self) -> u64 {
         let align = match self {
-            PointAttributeDataType::U8 => std::mem::align_of::<u8>(),
-            PointAttributeDataType::I8 => std::mem::align_of::<i8>(),
-            PointAttributeDataType::U16 => std::mem::align_of::<u16>(),
-            PointAttributeDataType::I16 => std::mem::align_of::<i16>(),
-            PointAttributeDataType::U32 => std::mem::align_of::<u32>(),
-            PointAttributeDataType::I32 => std::mem::align_of::<i32>(),
-            PointAttributeDataType::U64 => std::mem::align_of::<u64>(),
-            PointAttributeDataType::I64 => std::mem::align_of::<i64>(),
-            PointAttributeDataType::F32 => std::mem::align_of::<f32>(),
-            PointAttributeDataType::F64 => std::mem::align_of::<f64>(),
-            PointAttributeDataType::Bool => std::mem::align_of::<bool>(),
-            PointAttributeDataType::Vec3u8 => std::mem::align_of::<Vector3<u8>>(),
-            PointAttributeDataType::Vec3u16 => std::mem::align_of::<Vector3<u16>>(),
-            PointAttributeDataType::Vec3f32 => std::mem::align_of::<Vector3<f32>>(),
-            PointAttributeDataType::Vec3f64 => std::mem::align_of::<Vector3<f64>>(),
-            PointAttributeDataType::Vec4u8 => std::mem::align_of::<Vector4<u8>>(),
+            PointAttributeDataType::U8 => core::mem::align_of::<u8>(),
+            PointAttributeDataType::I8 => core::mem::align_of::<i8>(),
+            PointAttributeDataType::U16 => core::mem::align_of::<u16>(),
+            PointAttributeDataType::I16 => core::mem::align_of::<i16>(),
+            PointAttributeDataType::U32 => core::mem::align_of::<u32>(),
+            PointAttributeDataType::I32 => core::mem::align_of::<i32>(),
+            PointAttributeDataType::U64 => core::mem::align_of::<u64>(),
+            PointAttributeDataType::I64 => core::mem::align_of::<i64>(),
+            PointAttributeDataType::F32 => core::mem::align_of::<f32>(),
+            PointAttributeDataType::F64 => core::mem::align_of::<f64>(),
+            PointAttributeDataType::Bool => core::mem::align_of::<bool>(),
+            PointAttributeDataType::Vec3u8 => core::mem::align_of::<Vector3<u8>>(),
+            PointAttributeDataType::Vec3u16 => core::mem::align_of::<Vector3<u16>>(),
+            PointAttributeDataType::Vec3f32 => core::mem::align_of::<Vector3<f32>>(),
+            PointAttributeDataType::Vec3f64 => core::mem::align_of::<Vector3<f64>>(),
+            PointAttributeDataType::Vec4u8 => core::mem::align_of::<Vector4<u8>>(),
+            PointAttributeDataType::Array(element, _) => {
+                return element.data_type().min_alignment();
+            }
         };
         align as u64
     }
+
+    /// Returns the scalar type and the number of the components of the associated `PointAttributeDataType`. Vector
+    /// and array types consist of multiple components of the same scalar type, all other types consist of a single
+    /// component
+    /// ```
+    /// # use pasture_core::layout::*;
+    /// assert_eq!((PointAttributeDataType::U16, 3), PointAttributeDataType::Vec3u16.components());
+    /// assert_eq!((PointAttributeDataType::F32, 7), PointAttributeDataType::Array(ArrayElementType::F32, 7).components());
+    /// assert_eq!((PointAttributeDataType::F64, 1), PointAttributeDataType::F64.components());
+    /// ```
+    pub fn components(&self) -> (PointAttributeDataType, usize) {
+        match self {
+            PointAttributeDataType::Vec3u8 => (PointAttributeDataType::U8, 3),
+            PointAttributeDataType::Vec3u16 => (PointAttributeDataType::U16, 3),
+            PointAttributeDataType::Vec3f32 => (PointAttributeDataType::F32, 3),
+            PointAttributeDataType::Vec3f64 => (PointAttributeDataType::F64, 3),
+            PointAttributeDataType::Vec4u8 => (PointAttributeDataType::U8, 4),
+            PointAttributeDataType::Array(element, len) => (element.data_type(), *len as usize),
+            scalar => (*scalar, 1),
+        }
+    }
 }
 
 impl Display for PointAttributeDataType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             PointAttributeDataType::U8 => write!(f, "U8"),
             PointAttributeDataType::I8 => write!(f, "I8"),
@@ -135,6 +227,9 @@ impl Display for PointAttributeDataType {
             PointAttributeDataType::Vec3f32 => write!(f, "Vec3<f32>"),
             PointAttributeDataType::Vec3f64 => write!(f, "Vec3<f64>"),
             &PointAttributeDataType::Vec4u8 => write!(f, "Vec4<u8>"),
+            PointAttributeDataType::Array(element, len) => {
+                write!(f, "[{}; {}]", element.data_type(), len)
+            }
         }
     }
 }
@@ -228,13 +323,67 @@ impl PrimitiveType for Vector4<u8> {
     }
 }
 
+/// Marker trait for the scalar `PrimitiveType`s that can be the elements of a fixed-size array attribute. For all
+/// these types `T`, `[T; N]` is a `PrimitiveType` as well
+pub trait ArrayElement: PrimitiveType {
+    /// Returns the corresponding `ArrayElementType` for the implementing type
+    fn element_type() -> ArrayElementType;
+}
+
+macro_rules! impl_array_element {
+    ($type:ty, $element_type:ident) => {
+        impl ArrayElement for $type {
+            fn element_type() -> ArrayElementType {
+                ArrayElementType::$element_type
+            }
+        }
+    };
+}
+
+impl_array_element!(u8, U8);
+impl_array_element!(i8, I8);
+impl_array_element!(u16, U16);
+impl_array_element!(i16, I16);
+impl_array_element!(u32, U32);
+impl_array_element!(i32, I32);
+impl_array_element!(u64, U64);
+impl_array_element!(i64, I64);
+impl_array_element!(f32, F32);
+impl_array_element!(f64, F64);
+impl_array_element!(bool, Bool);
+
+impl<T: ArrayElement, const N: usize> PrimitiveType for [T; N] {
+    fn data_type() -> PointAttributeDataType {
+        PointAttributeDataType::Array(T::element_type(), N as u32)
+    }
+}
+
+// glam vectors have the same memory layout as the corresponding nalgebra vectors, so they map to the same datatypes.
+// This makes it possible to read and write e.g. positions and normals as glam vectors directly
+#[cfg(feature = "glam")]
+impl PrimitiveType for glam::Vec3 {
+    fn data_type() -> PointAttributeDataType {
+        PointAttributeDataType::Vec3f32
+    }
+}
+#[cfg(feature = "glam")]
+impl PrimitiveType for glam::DVec3 {
+    fn data_type() -> PointAttributeDataType {
+        PointAttributeDataType::Vec3f64
+    }
+}
+
 // Assert sizes of vector types are as we expect. Primitive types always are the same size, but we don't know
 // what nalgebra does with the Vector3 types on the target machine...
-const_assert!(std::mem::size_of::<Vector3<u8>>() == 3);
-const_assert!(std::mem::size_of::<Vector3<u16>>() == 6);
-const_assert!(std::mem::size_of::<Vector3<f32>>() == 12);
-const_assert!(std::mem::size_of::<Vector3<f64>>() == 24);
-const_assert!(std::mem::size_of::<Vector4<u8>>() == 4);
+const_assert!(core::mem::size_of::<Vector3<u8>>() == 3);
+const_assert!(core::mem::size_of::<Vector3<u16>>() == 6);
+const_assert!(core::mem::size_of::<Vector3<f32>>() == 12);
+const_assert!(core::mem::size_of::<Vector3<f64>>() == 24);
+const_assert!(core::mem::size_of::<Vector4<u8>>() == 4);
+#[cfg(feature = "glam")]
+const_assert!(core::mem::size_of::<glam::Vec3>() == 12);
+#[cfg(feature = "glam")]
+const_assert!(core::mem::size_of::<glam::DVec3>() == 24);
 
 /// A definition for a single point attribute of a point cloud. Point attributes are things like the position,
 /// GPS time, intensity etc. In Pasture, attributes are identified by a unique name together with the data type
@@ -319,7 +468,7 @@ impl PointAttributeDefinition {
 }
 
 impl Display for PointAttributeDefinition {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "[{};{}]", self.name, self.datatype)
     }
 }
@@ -425,7 +574,7 @@ impl PointAttributeMember {
 }
 
 impl Display for PointAttributeMember {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "[{};{} @ offset {}]",
@@ -585,6 +734,40 @@ pub mod attributes {
         name: "Normal",
         datatype: PointAttributeDataType::Vec3f32,
     };
+
+    /// Attribute definition for the position of the sensor at the time a point was captured, e.g. from a trajectory.
+    /// Default datatype is Vec3f64
+    pub const SENSOR_POSITION: PointAttributeDefinition = PointAttributeDefinition {
+        name: "SensorPosition",
+        datatype: PointAttributeDataType::Vec3f64,
+    };
+
+    /// Attribute definition for the distance between a point and the sensor that captured it. Default datatype is F32
+    pub const RANGE: PointAttributeDefinition = PointAttributeDefinition {
+        name: "Range",
+        datatype: PointAttributeDataType::F32,
+    };
+
+    /// Attribute definition for the angle (in radians) between the laser beam and the normal of the surface that it hit.
+    /// Default datatype is F32
+    pub const INCIDENCE_ANGLE: PointAttributeDefinition = PointAttributeDefinition {
+        name: "IncidenceAngle",
+        datatype: PointAttributeDataType::F32,
+    };
+
+    /// Attribute definition for the ring of a multi-beam lidar sensor, i.e. the index of the laser beam that captured a
+    /// point, numbered from the lowest to the highest beam. Default datatype is U16
+    pub const RING: PointAttributeDefinition = PointAttributeDefinition {
+        name: "Ring",
+        datatype: PointAttributeDataType::U16,
+    };
+
+    /// Attribute definition for the level of detail of a point, i.e. the coarsest level of a progressive (e.g. octree)
+    /// representation of the point cloud that contains the point. Level 0 is the coarsest level. Default datatype is U8
+    pub const LOD_LEVEL: PointAttributeDefinition = PointAttributeDefinition {
+        name: "LODLevel",
+        datatype: PointAttributeDataType::U8,
+    };
 }
 
 /// How is a field within the associated in-memory type of a `PointLayout` aligned?
@@ -714,8 +897,11 @@ impl PointLayout {
     ) -> Self {
         // Conduct extensive checks for uniqueness and non-overlap. The checks are a bit expensive, however
         // they are absolutely necessary because this method is dangerous!
-        let unique_names = attributes.iter().map(|a| a.name()).unique();
-        if unique_names.count() != attributes.len() {
+        let unique_names = attributes
+            .iter()
+            .map(|a| a.name())
+            .collect::<BTreeSet<_>>();
+        if unique_names.len() != attributes.len() {
             panic!(
                 "PointLayout::from_attributes_and_offsets: All attributes must have unique names!"
             );
@@ -782,7 +968,7 @@ impl PointLayout {
         let alignment_requirement_of_field = match field_alignment {
             FieldAlignment::Default => point_attribute.datatype().min_alignment(),
             FieldAlignment::Packed(max_alignment) => {
-                std::cmp::min(max_alignment, point_attribute.datatype().min_alignment())
+                core::cmp::min(max_alignment, point_attribute.datatype().min_alignment())
             }
         };
         let offset = self
@@ -791,12 +977,12 @@ impl PointLayout {
 
         let current_max_alignment = self.memory_layout.align() as u64;
         let new_max_alignment = match field_alignment {
-            FieldAlignment::Default => std::cmp::max(
+            FieldAlignment::Default => core::cmp::max(
                 current_max_alignment,
                 point_attribute.datatype().min_alignment(),
             ),
             FieldAlignment::Packed(max_alignment) => {
-                std::cmp::min(max_alignment, current_max_alignment)
+                core::cmp::min(max_alignment, current_max_alignment)
             }
         };
 
@@ -805,7 +991,7 @@ impl PointLayout {
 
         let old_size = self.memory_layout.size() as u64;
         let attribute_end = offset + point_attribute.size();
-        let new_size_unaligned = std::cmp::max(old_size, attribute_end);
+        let new_size_unaligned = core::cmp::max(old_size, attribute_end);
         self.memory_layout = Layout::from_size_align(
             new_size_unaligned.align_to(new_max_alignment) as usize,
             new_max_alignment as usize,
@@ -882,6 +1068,87 @@ impl PointLayout {
             .find(|attribute| attribute.name() == attribute_name)
     }
 
+    /// Returns the attribute with the given name or with one of its aliases (see `AttributeAliases::equivalent_names`)
+    /// from this PointLayout. Attributes with the exact name are preferred. Returns None if no such attribute exists.
+    /// ```
+    /// # use pasture_core::layout::*;
+    /// let layout = PointLayout::from_attributes(&[PointAttributeDefinition::custom("Amplitude", PointAttributeDataType::F32)]);
+    /// let aliases = AttributeAliases::new().with_alias("Amplitude", "Reflectance");
+    /// let attribute = layout.get_attribute_by_name_with_aliases("Reflectance", &aliases);
+    /// assert_eq!("Amplitude", attribute.unwrap().name());
+    /// ```
+    pub fn get_attribute_by_name_with_aliases(
+        &self,
+        attribute_name: &str,
+        aliases: &AttributeAliases,
+    ) -> Option<&PointAttributeMember> {
+        aliases
+            .equivalent_names(attribute_name)
+            .find_map(|name| self.get_attribute_by_name(name))
+    }
+
+    /// Renames the attribute with the name `old_name` to `new_name`. The datatype and the offset of the attribute are
+    /// not changed, so the memory layout stays the same and point data in this layout is valid for the renamed layout
+    /// as well.
+    ///
+    /// # Panics
+    ///
+    /// If there is no attribute with the name `old_name`, or if an attribute with the name `new_name` already exists
+    /// ```
+    /// # use pasture_core::layout::*;
+    /// let mut layout = PointLayout::from_attributes(&[attributes::POSITION_3D, attributes::INTENSITY]);
+    /// layout.rename_attribute(attributes::INTENSITY.name(), "Amplitude");
+    /// assert!(layout.has_attribute_with_name("Amplitude"));
+    /// assert!(!layout.has_attribute_with_name(attributes::INTENSITY.name()));
+    /// ```
+    pub fn rename_attribute(&mut self, old_name: &str, new_name: &'static str) {
+        if old_name == new_name {
+            return;
+        }
+        if self.has_attribute_with_name(new_name) {
+            panic!(
+                "PointLayout::rename_attribute: Point attribute {} is already present in this PointLayout!",
+                new_name
+            );
+        }
+        let attribute = self
+            .attributes
+            .iter_mut()
+            .find(|attribute| attribute.name() == old_name)
+            .unwrap_or_else(|| {
+                panic!(
+                    "PointLayout::rename_attribute: Point attribute {} is not part of this PointLayout!",
+                    old_name
+                )
+            });
+        attribute.name = new_name;
+    }
+
+    /// Renames all attributes whose names are aliases to their canonical names (see `AttributeAliases`). Returns the
+    /// renamed attributes as pairs of old and new names
+    ///
+    /// # Panics
+    ///
+    /// If the canonical name of an alias is already the name of another attribute in this PointLayout
+    pub fn apply_aliases(
+        &mut self,
+        aliases: &AttributeAliases,
+    ) -> Vec<(&'static str, &'static str)> {
+        let renamed_attributes = self
+            .attributes
+            .iter()
+            .filter_map(|attribute| {
+                aliases
+                    .canonical_name_of_alias(attribute.name())
+                    .map(|canonical_name| (attribute.name(), canonical_name))
+            })
+            .collect::<Vec<_>>();
+        for (old_name, new_name) in renamed_attributes.iter() {
+            self.rename_attribute(old_name, *new_name);
+        }
+        renamed_attributes
+    }
+
     /// Returns the attribute at the given index from the associated `PointLayout`
     ///
     /// # Panics
@@ -988,7 +1255,7 @@ impl PointLayout {
 }
 
 impl Display for PointLayout {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         writeln!(f, "PointLayout {{")?;
 
         for attribute in self.attributes() {
@@ -1048,4 +1315,145 @@ mod tests {
 
         assert_eq!(expected_layout_1, TestPoint1::layout());
     }
+
+    #[cfg(feature = "glam")]
+    #[test]
+    fn test_glam_vectors() {
+        use crate::containers::{
+            InterleavedVecPointStorage, PointBufferExt, PointBufferWriteableExt,
+        };
+        use crate::layout::attributes::NORMAL;
+        use glam::{DVec3, Vec3};
+
+        #[derive(Debug, PointType, Copy, Clone, PartialEq)]
+        #[repr(C)]
+        struct GlamPoint {
+            #[pasture(BUILTIN_POSITION_3D)]
+            position: DVec3,
+            #[pasture(BUILTIN_NORMAL)]
+            normal: Vec3,
+        }
+
+        assert_eq!(
+            PointLayout::from_attributes(&[POSITION_3D, NORMAL]),
+            GlamPoint::layout()
+        );
+
+        let mut buffer = InterleavedVecPointStorage::new(GlamPoint::layout());
+        buffer.push_point(GlamPoint {
+            position: DVec3::new(1.0, 2.0, 3.0),
+            normal: Vec3::Z,
+        });
+        assert_eq!(
+            Vector3::new(1.0, 2.0, 3.0),
+            buffer.get_attribute::<Vector3<f64>>(&POSITION_3D, 0)
+        );
+
+        buffer.set_attribute(&NORMAL, 0, Vec3::X);
+        assert_eq!(Vec3::X, buffer.get_attribute::<Vec3>(&NORMAL, 0));
+        assert_eq!(
+            vec![Vector3::new(1.0f32, 0.0, 0.0)],
+            buffer
+                .iter_attribute::<Vector3<f32>>(&NORMAL)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_array_attributes() {
+        use crate::containers::{
+            PerAttributeVecPointStorage, PointBufferExt, PointBufferWriteableExt,
+        };
+
+        const FEATURES: PointAttributeDefinition = PointAttributeDefinition::custom(
+            "Features",
+            PointAttributeDataType::Array(ArrayElementType::F32, 7),
+        );
+
+        #[derive(Debug, PointType, Copy, Clone, PartialEq)]
+        #[repr(C)]
+        struct FeaturePoint {
+            #[pasture(BUILTIN_POSITION_3D)]
+            position: Vector3<f64>,
+            #[pasture(attribute = "Features")]
+            features: [f32; 7],
+            #[pasture(attribute = "Descriptor")]
+            descriptor: [u8; 16],
+        }
+
+        assert_eq!(28, FEATURES.size());
+        assert_eq!(4, FEATURES.datatype().min_alignment());
+        assert_eq!(FEATURES.datatype(), <[f32; 7]>::data_type());
+        assert_eq!("[F32; 7]", FEATURES.datatype().to_string());
+
+        let layout = FeaturePoint::layout();
+        assert_eq!(
+            PointLayout::from_attributes(&[
+                POSITION_3D,
+                FEATURES,
+                PointAttributeDefinition::custom(
+                    "Descriptor",
+                    PointAttributeDataType::Array(ArrayElementType::U8, 16)
+                ),
+            ]),
+            layout
+        );
+        assert_eq!(
+            std::mem::size_of::<FeaturePoint>() as u64,
+            layout.size_of_point_entry()
+        );
+
+        let features = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+        let mut buffer = PerAttributeVecPointStorage::new(layout);
+        buffer.push_point(FeaturePoint {
+            position: Vector3::new(1.0, 2.0, 3.0),
+            features,
+            descriptor: [42; 16],
+        });
+        assert_eq!(features, buffer.get_attribute::<[f32; 7]>(&FEATURES, 0));
+    }
+
+    #[test]
+    fn test_apply_aliases() {
+        let mut layout = PointLayout::from_attributes(&[
+            POSITION_3D,
+            PointAttributeDefinition::custom("reflectance", PointAttributeDataType::F32),
+            INTENSITY,
+        ]);
+        let size_of_point_entry = layout.size_of_point_entry();
+        let aliases = AttributeAliases::new()
+            .with_alias("reflectance", "Reflectance")
+            .with_alias("Amplitude", "Reflectance");
+
+        assert_eq!(
+            vec![("reflectance", "Reflectance")],
+            layout.apply_aliases(&aliases)
+        );
+        assert_eq!(
+            PointLayout::from_attributes(&[
+                POSITION_3D,
+                PointAttributeDefinition::custom("Reflectance", PointAttributeDataType::F32),
+                INTENSITY,
+            ]),
+            layout
+        );
+        assert_eq!(size_of_point_entry, layout.size_of_point_entry());
+        assert_eq!(
+            24,
+            layout
+                .offset_of(&PointAttributeDefinition::custom(
+                    "Reflectance",
+                    PointAttributeDataType::F32
+                ))
+                .unwrap()
+        );
+        assert!(layout.apply_aliases(&aliases).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rename_attribute_to_existing_name_panics() {
+        let mut layout = PointLayout::from_attributes(&[POSITION_3D, INTENSITY]);
+        layout.rename_attribute(INTENSITY.name(), POSITION_3D.name());
+    }
 }
@@ -1,3 +1,4 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(clippy::all)]
 
 //! Core data structures for working with point cloud data
@@ -5,7 +6,15 @@
 //! Pasture provides data structures for reading, writing and in-memory handling of arbitrary point cloud data.
 //! The best way to get started with Pasture is to look at the [example code](https://github.com/Mortano/pasture/tree/main/pasture-core/examples).
 //! For understanding Pasture, it is best to look at the [PointLayout](crate::layout::PointLayout) type and the [containers](crate::containers) module.
+//!
+//! Without the default `std` feature, pasture-core is `no_std` and only requires `alloc`. In this configuration, it
+//! contains the [layout](crate::layout) module, the point buffers and iterators of the [containers](crate::containers)
+//! module and the memory helpers of the [util](crate::util) module, while the [math](crate::math) module only contains
+//! [Alignable](crate::math::Alignable) and the `meta` module is not available.
 
+extern crate alloc;
+#[cfg(feature = "glam")]
+pub extern crate glam;
 pub extern crate nalgebra;
 extern crate self as pasture_core;
 
@@ -15,6 +24,7 @@ pub mod layout;
 /// Useful mathematical tools when working with point clooud data
 pub mod math;
 /// Data structures for handling point cloud metadata
+#[cfg(feature = "std")]
 pub mod meta;
 /// Utilities
 pub mod util;
@@ -1,10 +1,12 @@
 use float_ord::FloatOrd;
 use nalgebra::{ClosedSub, Point3, Scalar, Vector3};
 
+#[cfg(feature = "serde-serialize")]
 use serde::Serialize;
 
 /// 3D axis-aligned bounding box
-#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize))]
 pub struct AABB<T: Scalar + PartialOrd> {
     min: Point3<T>,
     max: Point3<T>,
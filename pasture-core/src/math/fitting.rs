@@ -0,0 +1,430 @@
+use nalgebra::{Matrix3, Matrix4, Point3, Vector3, Vector4};
+
+use super::Plane;
+
+/// Weighting of the residuals in the least-squares fitting functions of this module. With robust weighting, the fit is
+/// repeated with weights that are calculated from the residuals of the previous fit (iteratively reweighted least
+/// squares), which reduces the influence of outliers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RobustWeighting {
+    /// Ordinary least squares, where every point has the same weight
+    None,
+    /// Huber weighting with the given threshold. Points with a residual up to the threshold get full weight, points
+    /// with a larger residual get a weight that is inversely proportional to their residual
+    Huber(f64),
+    /// Tukey biweight with the given threshold. The weight decreases smoothly with the residual, and points with a
+    /// residual above the threshold are ignored completely
+    Tukey(f64),
+}
+
+impl RobustWeighting {
+    /// Returns the weight of a point with the given `residual`
+    pub fn weight(&self, residual: f64) -> f64 {
+        let residual = residual.abs();
+        match *self {
+            RobustWeighting::None => 1.0,
+            RobustWeighting::Huber(threshold) => {
+                if residual <= threshold {
+                    1.0
+                } else {
+                    threshold / residual
+                }
+            }
+            RobustWeighting::Tukey(threshold) => {
+                if residual >= threshold {
+                    0.0
+                } else {
+                    let ratio = residual / threshold;
+                    (1.0 - ratio * ratio) * (1.0 - ratio * ratio)
+                }
+            }
+        }
+    }
+}
+
+/// An infinite line in 3D, given by a point on the line and a unit direction vector
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Line {
+    point: Point3<f64>,
+    direction: Vector3<f64>,
+}
+
+impl Line {
+    /// Creates a new line through `point` with the given `direction`, which is normalized
+    ///
+    /// # Panics
+    ///
+    /// If `direction` is the zero vector
+    pub fn new(point: Point3<f64>, direction: Vector3<f64>) -> Self {
+        let length = direction.norm();
+        if length == 0.0 {
+            panic!("Line::new: Direction must not be the zero vector!");
+        }
+        Self {
+            point,
+            direction: direction / length,
+        }
+    }
+
+    /// Returns a point on this line
+    pub fn point(&self) -> &Point3<f64> {
+        &self.point
+    }
+
+    /// Returns the unit direction vector of this line
+    pub fn direction(&self) -> &Vector3<f64> {
+        &self.direction
+    }
+
+    /// Returns the distance of `point` to this line
+    pub fn distance(&self, point: &Point3<f64>) -> f64 {
+        let offset = point - self.point;
+        (offset - self.direction * offset.dot(&self.direction)).norm()
+    }
+}
+
+/// A circle in 3D, given by its center, the unit normal vector of the plane that contains the circle and its radius
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Circle {
+    /// Center of the circle
+    pub center: Point3<f64>,
+    /// Unit normal vector of the plane of the circle
+    pub normal: Vector3<f64>,
+    /// Radius of the circle
+    pub radius: f64,
+}
+
+impl Circle {
+    /// Returns the distance of `point` to the closest point on this circle
+    pub fn distance(&self, point: &Point3<f64>) -> f64 {
+        let offset = point - self.center;
+        let distance_to_plane = offset.dot(&self.normal);
+        let distance_in_plane = (offset - self.normal * distance_to_plane).norm();
+        let radial_distance = distance_in_plane - self.radius;
+        (distance_to_plane * distance_to_plane + radial_distance * radial_distance).sqrt()
+    }
+}
+
+/// A sphere, given by its center and radius
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sphere {
+    /// Center of the sphere
+    pub center: Point3<f64>,
+    /// Radius of the sphere
+    pub radius: f64,
+}
+
+impl Sphere {
+    /// Returns the signed distance of `point` to the surface of this sphere, which is negative inside of the sphere
+    pub fn signed_distance(&self, point: &Point3<f64>) -> f64 {
+        (point - self.center).norm() - self.radius
+    }
+}
+
+/// Maximum number of reweighting iterations for robust fitting
+const MAX_ROBUST_ITERATIONS: usize = 20;
+
+/// Fits a model to `points` with `fit`, and refines it by iteratively reweighted least squares using `weighting` and the
+/// residuals calculated by `residual`
+fn fit_robust<M, F: Fn(&[Vector3<f64>], &[f64]) -> Option<M>, R: Fn(&M, &Point3<f64>) -> f64>(
+    points: &[Vector3<f64>],
+    weighting: RobustWeighting,
+    fit: F,
+    residual: R,
+) -> Option<M> {
+    let mut weights = vec![1.0; points.len()];
+    let mut model = fit(points, &weights)?;
+    if weighting == RobustWeighting::None {
+        return Some(model);
+    }
+    for _ in 0..MAX_ROBUST_ITERATIONS {
+        let mut max_change: f64 = 0.0;
+        for (weight, point) in weights.iter_mut().zip(points.iter()) {
+            let new_weight = weighting.weight(residual(&model, &(*point).into()));
+            max_change = max_change.max((new_weight - *weight).abs());
+            *weight = new_weight;
+        }
+        if max_change < 1e-9 {
+            break;
+        }
+        model = match fit(points, &weights) {
+            Some(model) => model,
+            // All points were rejected as outliers, so we keep the last model
+            None => break,
+        };
+    }
+    Some(model)
+}
+
+/// Returns the weighted mean of `points` and their weighted covariance matrix, or `None` if the sum of the weights is zero
+fn weighted_mean_and_covariance(
+    points: &[Vector3<f64>],
+    weights: &[f64],
+) -> Option<(Vector3<f64>, Matrix3<f64>)> {
+    let weight_sum: f64 = weights.iter().sum();
+    if weight_sum <= 0.0 {
+        return None;
+    }
+    let mean = points
+        .iter()
+        .zip(weights.iter())
+        .map(|(point, weight)| point * *weight)
+        .sum::<Vector3<f64>>()
+        / weight_sum;
+    let covariance = points
+        .iter()
+        .zip(weights.iter())
+        .map(|(point, weight)| {
+            let offset = point - mean;
+            offset * offset.transpose() * *weight
+        })
+        .sum::<Matrix3<f64>>()
+        / weight_sum;
+    Some((mean, covariance))
+}
+
+/// Returns the eigenvector of the symmetric matrix `matrix` with the smallest (`smallest == true`) or largest eigenvalue
+fn extremal_eigenvector(matrix: Matrix3<f64>, smallest: bool) -> Vector3<f64> {
+    let eigen = matrix.symmetric_eigen();
+    let index = if smallest {
+        eigen.eigenvalues.imin()
+    } else {
+        eigen.eigenvalues.imax()
+    };
+    eigen.eigenvectors.column(index).normalize()
+}
+
+fn fit_line_weighted(points: &[Vector3<f64>], weights: &[f64]) -> Option<Line> {
+    let (mean, covariance) = weighted_mean_and_covariance(points, weights)?;
+    let direction = extremal_eigenvector(covariance, false);
+    Some(Line::new(mean.into(), direction))
+}
+
+fn fit_plane_weighted(points: &[Vector3<f64>], weights: &[f64]) -> Option<Plane> {
+    let (mean, covariance) = weighted_mean_and_covariance(points, weights)?;
+    let normal = extremal_eigenvector(covariance, true);
+    Some(Plane::new(normal, -normal.dot(&mean)))
+}
+
+fn fit_circle_weighted(points: &[Vector3<f64>], weights: &[f64]) -> Option<Circle> {
+    let (mean, covariance) = weighted_mean_and_covariance(points, weights)?;
+    let normal = extremal_eigenvector(covariance, true);
+    let helper = if normal.x.abs() < 0.9 {
+        Vector3::x()
+    } else {
+        Vector3::y()
+    };
+    let u = normal.cross(&helper).normalize();
+    let v = normal.cross(&u);
+
+    // Algebraic circle fit in the plane of the circle: x² + y² = 2ax + 2by + c, with the center (a, b) and the radius
+    // sqrt(c + a² + b²)
+    let mut normal_matrix = Matrix3::zeros();
+    let mut right_hand_side = Vector3::zeros();
+    for (point, weight) in points.iter().zip(weights.iter()) {
+        let offset = point - mean;
+        let (x, y) = (offset.dot(&u), offset.dot(&v));
+        let row = Vector3::new(2.0 * x, 2.0 * y, 1.0);
+        normal_matrix += row * row.transpose() * *weight;
+        right_hand_side += row * ((x * x + y * y) * *weight);
+    }
+    let solution = normal_matrix.lu().solve(&right_hand_side)?;
+    let radius_squared = solution.z + solution.x * solution.x + solution.y * solution.y;
+    if radius_squared <= 0.0 {
+        return None;
+    }
+    Some(Circle {
+        center: (mean + u * solution.x + v * solution.y).into(),
+        normal,
+        radius: radius_squared.sqrt(),
+    })
+}
+
+fn fit_sphere_weighted(points: &[Vector3<f64>], weights: &[f64]) -> Option<Sphere> {
+    let (mean, _) = weighted_mean_and_covariance(points, weights)?;
+    // Algebraic sphere fit: x² + y² + z² = 2ax + 2by + 2cz + d, with the center (a, b, c) and the radius
+    // sqrt(d + a² + b² + c²). The points are centered at their mean for numerical stability
+    let mut normal_matrix = Matrix4::zeros();
+    let mut right_hand_side = Vector4::zeros();
+    for (point, weight) in points.iter().zip(weights.iter()) {
+        let offset = point - mean;
+        let row = Vector4::new(2.0 * offset.x, 2.0 * offset.y, 2.0 * offset.z, 1.0);
+        normal_matrix += row * row.transpose() * *weight;
+        right_hand_side += row * (offset.norm_squared() * *weight);
+    }
+    let solution = normal_matrix.lu().solve(&right_hand_side)?;
+    let center = solution.xyz();
+    let radius_squared = solution.w + center.norm_squared();
+    if radius_squared <= 0.0 {
+        return None;
+    }
+    Some(Sphere {
+        center: (mean + center).into(),
+        radius: radius_squared.sqrt(),
+    })
+}
+
+/// Fits a line to `points` that minimizes the sum of the squared distances of the points to the line, using the
+/// given `weighting` of the residuals. Returns `None` if there are less than two points
+///
+/// ```
+/// # use pasture_core::math::{fit_line, RobustWeighting};
+/// # use pasture_core::nalgebra::{Point3, Vector3};
+/// let points = vec![
+///     Vector3::new(0.0, 0.0, 1.0),
+///     Vector3::new(1.0, 1.0, 1.0),
+///     Vector3::new(2.0, 2.0, 1.0),
+/// ];
+/// let line = fit_line(&points, RobustWeighting::None).unwrap();
+/// assert!(line.distance(&Point3::new(5.0, 5.0, 1.0)) < 1e-9);
+/// ```
+pub fn fit_line(points: &[Vector3<f64>], weighting: RobustWeighting) -> Option<Line> {
+    if points.len() < 2 {
+        return None;
+    }
+    fit_robust(points, weighting, fit_line_weighted, Line::distance)
+}
+
+/// Fits a plane to `points` that minimizes the sum of the squared distances of the points to the plane, using the given
+/// `weighting` of the residuals. Returns `None` if there are less than three points
+///
+/// ```
+/// # use pasture_core::math::{fit_plane, RobustWeighting};
+/// # use pasture_core::nalgebra::{Point3, Vector3};
+/// let points = vec![
+///     Vector3::new(0.0, 0.0, 2.0),
+///     Vector3::new(1.0, 0.0, 2.0),
+///     Vector3::new(0.0, 1.0, 2.0),
+///     Vector3::new(1.0, 1.0, 2.0),
+/// ];
+/// let plane = fit_plane(&points, RobustWeighting::None).unwrap();
+/// assert!((plane.normal().z.abs() - 1.0).abs() < 1e-9);
+/// assert!(plane.signed_distance(&Point3::new(7.0, -3.0, 2.0)).abs() < 1e-9);
+/// ```
+pub fn fit_plane(points: &[Vector3<f64>], weighting: RobustWeighting) -> Option<Plane> {
+    if points.len() < 3 {
+        return None;
+    }
+    fit_robust(points, weighting, fit_plane_weighted, |plane, point| {
+        plane.signed_distance(point)
+    })
+}
+
+/// Fits a circle in 3D to `points`. The plane of the circle is fitted first, and the circle is then fitted within this
+/// plane by algebraic least squares. The robust `weighting` uses the distances of the points to the circle as
+/// residuals. Returns `None` if there are less than three points, or if the points are collinear
+pub fn fit_circle(points: &[Vector3<f64>], weighting: RobustWeighting) -> Option<Circle> {
+    if points.len() < 3 {
+        return None;
+    }
+    fit_robust(points, weighting, fit_circle_weighted, Circle::distance)
+}
+
+/// Fits a sphere to `points` by algebraic least squares. The robust `weighting` uses the distances of the points to the
+/// surface of the sphere as residuals. Returns `None` if there are less than four points, or if the points are coplanar
+///
+/// ```
+/// # use pasture_core::math::{fit_sphere, RobustWeighting};
+/// # use pasture_core::nalgebra::{Point3, Vector3};
+/// let points = vec![
+///     Vector3::new(12.0, 20.0, 30.0),
+///     Vector3::new(8.0, 20.0, 30.0),
+///     Vector3::new(10.0, 22.0, 30.0),
+///     Vector3::new(10.0, 20.0, 32.0),
+///     Vector3::new(10.0, 20.0, 28.0),
+/// ];
+/// let sphere = fit_sphere(&points, RobustWeighting::None).unwrap();
+/// assert!((sphere.center - Point3::new(10.0, 20.0, 30.0)).norm() < 1e-9);
+/// assert!((sphere.radius - 2.0).abs() < 1e-9);
+/// ```
+pub fn fit_sphere(points: &[Vector3<f64>], weighting: RobustWeighting) -> Option<Sphere> {
+    if points.len() < 4 {
+        return None;
+    }
+    fit_robust(
+        points,
+        weighting,
+        fit_sphere_weighted,
+        Sphere::signed_distance,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{prelude::StdRng, Rng, SeedableRng};
+
+    /// Points on the plane z = 0.5x + 10 with small noise, and a few gross outliers far above the plane
+    fn plane_with_outliers() -> Vec<Vector3<f64>> {
+        let mut rng = StdRng::seed_from_u64(21);
+        let mut points = (0..200)
+            .map(|_| {
+                let x = rng.gen_range(0.0..10.0);
+                let y = rng.gen_range(0.0..10.0);
+                Vector3::new(x, y, 0.5 * x + 10.0 + rng.gen_range(-0.01..0.01))
+            })
+            .collect::<Vec<_>>();
+        for index in 0..20 {
+            let x = index as f64 / 2.0;
+            points.push(Vector3::new(x, 9.0, 0.5 * x + 15.0));
+        }
+        points
+    }
+
+    fn plane_error(plane: &Plane) -> f64 {
+        let expected_normal = Vector3::new(-0.5, 0.0, 1.0).normalize();
+        let normal_error = 1.0 - plane.normal().dot(&expected_normal).abs();
+        let offset_error = plane.signed_distance(&Point3::new(4.0, 4.0, 12.0)).abs();
+        normal_error.max(offset_error)
+    }
+
+    #[test]
+    fn robust_plane_fit_ignores_outliers() {
+        let points = plane_with_outliers();
+        let least_squares = fit_plane(&points, RobustWeighting::None).unwrap();
+        let tukey = fit_plane(&points, RobustWeighting::Tukey(1.0)).unwrap();
+        let huber = fit_plane(&points, RobustWeighting::Huber(0.02)).unwrap();
+        assert!(plane_error(&least_squares) > 0.1);
+        assert!(plane_error(&tukey) < 0.01);
+        assert!(plane_error(&huber) < plane_error(&least_squares));
+    }
+
+    #[test]
+    fn fit_circle_in_tilted_plane() {
+        let normal = Vector3::new(1.0, 1.0, 1.0).normalize();
+        let u = normal.cross(&Vector3::z()).normalize();
+        let v = normal.cross(&u);
+        let center = Vector3::new(100.0, 200.0, 300.0);
+        let mut points = (0..36)
+            .map(|index| {
+                let angle = (index as f64 * 10.0).to_radians();
+                center + (u * angle.cos() + v * angle.sin()) * 3.0
+            })
+            .collect::<Vec<_>>();
+        points.push(center);
+
+        let circle = fit_circle(&points, RobustWeighting::Tukey(0.5)).unwrap();
+        assert!((circle.center - Point3::from(center)).norm() < 1e-6);
+        assert!((circle.radius - 3.0).abs() < 1e-6);
+        assert!((circle.normal.dot(&normal).abs() - 1.0).abs() < 1e-9);
+        assert!(circle.distance(&Point3::from(center + u * 3.0)) < 1e-6);
+    }
+
+    #[test]
+    fn fit_with_too_few_points() {
+        let points = vec![Vector3::new(1.0, 2.0, 3.0), Vector3::new(4.0, 5.0, 6.0)];
+        assert!(fit_line(&points, RobustWeighting::None).is_some());
+        assert_eq!(None, fit_plane(&points, RobustWeighting::None));
+        assert_eq!(None, fit_circle(&points, RobustWeighting::None));
+        assert_eq!(None, fit_sphere(&points, RobustWeighting::None));
+    }
+
+    #[test]
+    fn robust_weights() {
+        assert_eq!(1.0, RobustWeighting::None.weight(100.0));
+        assert_eq!(1.0, RobustWeighting::Huber(2.0).weight(-1.0));
+        assert_eq!(0.5, RobustWeighting::Huber(2.0).weight(4.0));
+        assert_eq!(0.5625, RobustWeighting::Tukey(2.0).weight(1.0));
+        assert_eq!(0.0, RobustWeighting::Tukey(2.0).weight(2.5));
+    }
+}
@@ -0,0 +1,195 @@
+use nalgebra::{Matrix4, Point3, Vector3, Vector4};
+
+use super::AABB;
+
+/// A plane in 3D, given by the equation `normal.dot(p) + distance = 0`. The side of the plane that `normal` points to
+/// is the positive side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    normal: Vector3<f64>,
+    distance: f64,
+}
+
+impl Plane {
+    /// Creates a new plane from the given `normal` and `distance`. The plane equation is normalized, so `normal` does
+    /// not have to be a unit vector
+    ///
+    /// # Panics
+    ///
+    /// If `normal` is the zero vector
+    pub fn new(normal: Vector3<f64>, distance: f64) -> Self {
+        let length = normal.norm();
+        if length == 0.0 {
+            panic!("Plane::new: Normal must not be the zero vector!");
+        }
+        Self {
+            normal: normal / length,
+            distance: distance / length,
+        }
+    }
+
+    /// Creates a plane from the coefficients `(a, b, c, d)` of the plane equation `ax + by + cz + d = 0`
+    ///
+    /// # Panics
+    ///
+    /// If `a`, `b` and `c` are all zero
+    pub fn from_coefficients(coefficients: &Vector4<f64>) -> Self {
+        Self::new(coefficients.xyz(), coefficients.w)
+    }
+
+    /// Returns the unit normal vector of this plane
+    pub fn normal(&self) -> &Vector3<f64> {
+        &self.normal
+    }
+
+    /// Returns the signed distance of the origin to this plane along the normal
+    pub fn distance(&self) -> f64 {
+        self.distance
+    }
+
+    /// Returns the signed distance of `point` to this plane, which is positive on the side that the normal points to
+    pub fn signed_distance(&self, point: &Point3<f64>) -> f64 {
+        self.normal.dot(&point.coords) + self.distance
+    }
+}
+
+/// Result of an intersection test between a volume and a bounding box
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intersection {
+    /// The bounding box is completely outside of the volume
+    Outside,
+    /// The bounding box is completely inside of the volume
+    Inside,
+    /// The bounding box is partially inside of the volume. Conservative tests may also report this for some bounding
+    /// boxes that are outside of the volume
+    Intersecting,
+}
+
+/// A view frustum, i.e. a convex volume that is bounded by six planes whose normals point inwards. Frustums are used
+/// for view-dependent culling of points and bounding boxes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Creates a new frustum from the given `planes`, whose normals must point to the inside of the frustum
+    pub fn new(planes: [Plane; 6]) -> Self {
+        Self { planes }
+    }
+
+    /// Extracts the frustum from a combined view-projection matrix (i.e. `projection * view`), using the method of
+    /// Gribb and Hartmann. Points inside of the frustum are all points `p` with `-w <= x <= w`, `-w <= y <= w` and
+    /// `0 <= z <= w` for the clip space coordinates `(x, y, z, w) = view_projection * p`. This is the depth range of
+    /// wgpu, Vulkan and Direct3D. For matrices with the OpenGL depth range of `-w <= z <= w`, use
+    /// [from_view_projection_gl](Frustum::from_view_projection_gl)
+    ///
+    /// ```
+    /// # use pasture_core::math::Frustum;
+    /// # use pasture_core::nalgebra::{Matrix4, Point3};
+    /// // An orthographic projection of the unit cube [-1,1]x[-1,1]x[0,1]
+    /// let frustum = Frustum::from_view_projection(&Matrix4::identity());
+    /// assert!(frustum.contains(&Point3::new(0.5, -0.5, 0.5)));
+    /// assert!(!frustum.contains(&Point3::new(0.5, -0.5, -0.5)));
+    /// ```
+    pub fn from_view_projection(view_projection: &Matrix4<f64>) -> Self {
+        let row = |index: usize| view_projection.row(index).transpose();
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        Self::from_clip_planes([w + x, w - x, w + y, w - y, z, w - z])
+    }
+
+    /// Like [from_view_projection](Frustum::from_view_projection), but for matrices that use the OpenGL depth range
+    /// `-w <= z <= w` in clip space
+    pub fn from_view_projection_gl(view_projection: &Matrix4<f64>) -> Self {
+        let row = |index: usize| view_projection.row(index).transpose();
+        let (x, y, z, w) = (row(0), row(1), row(2), row(3));
+        Self::from_clip_planes([w + x, w - x, w + y, w - y, w + z, w - z])
+    }
+
+    fn from_clip_planes(coefficients: [Vector4<f64>; 6]) -> Self {
+        let mut planes = [Plane::new(Vector3::x(), 0.0); 6];
+        for (plane, coefficients) in planes.iter_mut().zip(coefficients.iter()) {
+            *plane = Plane::from_coefficients(coefficients);
+        }
+        Self { planes }
+    }
+
+    /// Returns the six planes of this frustum, with their normals pointing inwards
+    pub fn planes(&self) -> &[Plane; 6] {
+        &self.planes
+    }
+
+    /// Returns true if the associated frustum contains the given point. Points on the boundary of the frustum are
+    /// considered to be contained
+    pub fn contains(&self, point: &Point3<f64>) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// Tests `bounds` against this frustum. The test is conservative, so some bounding boxes that are close to the
+    /// corners of the frustum are reported as `Intersection::Intersecting` even though they are outside
+    pub fn intersect_aabb(&self, bounds: &AABB<f64>) -> Intersection {
+        let mut intersection = Intersection::Inside;
+        for plane in self.planes.iter() {
+            // The corners of the box that are the farthest in front of and behind the plane
+            let select = |positive: bool| {
+                Point3::new(
+                    if (plane.normal.x >= 0.0) == positive {
+                        bounds.max().x
+                    } else {
+                        bounds.min().x
+                    },
+                    if (plane.normal.y >= 0.0) == positive {
+                        bounds.max().y
+                    } else {
+                        bounds.min().y
+                    },
+                    if (plane.normal.z >= 0.0) == positive {
+                        bounds.max().z
+                    } else {
+                        bounds.min().z
+                    },
+                )
+            };
+            if plane.signed_distance(&select(true)) < 0.0 {
+                return Intersection::Outside;
+            }
+            if plane.signed_distance(&select(false)) < 0.0 {
+                intersection = Intersection::Intersecting;
+            }
+        }
+        intersection
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frustum_from_perspective_projection() {
+        // Camera at the origin looking along the negative z-axis with a 90 degree field of view
+        let projection = Matrix4::new_perspective(1.0, std::f64::consts::FRAC_PI_2, 1.0, 100.0);
+        let frustum = Frustum::from_view_projection_gl(&projection);
+
+        assert!(frustum.contains(&Point3::new(0.0, 0.0, -10.0)));
+        assert!(frustum.contains(&Point3::new(9.0, -9.0, -10.0)));
+        assert!(!frustum.contains(&Point3::new(11.0, 0.0, -10.0)));
+        assert!(!frustum.contains(&Point3::new(0.0, 0.0, 10.0)));
+        assert!(!frustum.contains(&Point3::new(0.0, 0.0, -0.5)));
+        assert!(!frustum.contains(&Point3::new(0.0, 0.0, -101.0)));
+
+        let inside =
+            AABB::from_min_max(Point3::new(-1.0, -1.0, -11.0), Point3::new(1.0, 1.0, -9.0));
+        let intersecting =
+            AABB::from_min_max(Point3::new(5.0, -1.0, -11.0), Point3::new(15.0, 1.0, -9.0));
+        let outside = AABB::from_min_max(Point3::new(-1.0, -1.0, 1.0), Point3::new(1.0, 1.0, 2.0));
+        assert_eq!(Intersection::Inside, frustum.intersect_aabb(&inside));
+        assert_eq!(
+            Intersection::Intersecting,
+            frustum.intersect_aabb(&intersecting)
+        );
+        assert_eq!(Intersection::Outside, frustum.intersect_aabb(&outside));
+    }
+}
@@ -1,14 +1,52 @@
+#[cfg(feature = "std")]
 mod bounds;
+#[cfg(feature = "std")]
 pub use self::bounds::*;
 
+#[cfg(feature = "std")]
+mod oriented_bounds;
+#[cfg(feature = "std")]
+pub use self::oriented_bounds::*;
+
+#[cfg(feature = "std")]
+mod frustum;
+#[cfg(feature = "std")]
+pub use self::frustum::*;
+
+#[cfg(feature = "std")]
+mod ray;
+#[cfg(feature = "std")]
+pub use self::ray::*;
+
+#[cfg(feature = "std")]
+mod rtree;
+#[cfg(feature = "std")]
+pub use self::rtree::*;
+
+#[cfg(feature = "std")]
+mod fitting;
+#[cfg(feature = "std")]
+pub use self::fitting::*;
+
+#[cfg(feature = "std")]
 mod morton_index;
+#[cfg(feature = "std")]
 pub use self::morton_index::*;
 
+#[cfg(feature = "std")]
 mod bitmanip;
+#[cfg(feature = "std")]
 pub use self::bitmanip::*;
 
 mod arithmetic;
 pub use self::arithmetic::*;
 
+#[cfg(feature = "std")]
 mod minmax;
+#[cfg(feature = "std")]
 pub use self::minmax::*;
+
+#[cfg(feature = "std")]
+mod statistics;
+#[cfg(feature = "std")]
+pub use self::statistics::*;
@@ -0,0 +1,244 @@
+use nalgebra::{Point3, Vector3};
+#[cfg(feature = "serde-serialize")]
+use serde::Serialize;
+
+use super::RunningCovariance;
+use crate::{
+    containers::{PointBuffer, PointBufferExt},
+    layout::attributes::POSITION_3D,
+};
+
+/// 3D oriented bounding box, i.e. a box with arbitrary orientation given by three orthonormal axes. Oriented bounding
+/// boxes fit objects that are not aligned with the coordinate axes (e.g. buildings or vehicles) much tighter than an
+/// [AABB](super::AABB)
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde-serialize", derive(Serialize))]
+pub struct OBB {
+    center: Point3<f64>,
+    axes: [Vector3<f64>; 3],
+    half_extents: Vector3<f64>,
+}
+
+impl OBB {
+    /// Creates a new OBB from the given `center`, `axes` and `half_extents`. `half_extents[i]` is half the size of the
+    /// box along `axes[i]`. The axes are expected to be orthonormal, which is not checked
+    pub fn new(center: Point3<f64>, axes: [Vector3<f64>; 3], half_extents: Vector3<f64>) -> Self {
+        Self {
+            center,
+            axes,
+            half_extents,
+        }
+    }
+
+    /// Calculates the oriented bounding box of the given `positions` using principal component analysis (PCA). The axes
+    /// of the box are the eigenvectors of the covariance matrix of the positions, sorted by descending variance, so
+    /// `axes()[0]` is the direction in which the positions spread the most. The axes form a right-handed coordinate
+    /// system. Returns `None` if `positions` is empty
+    ///
+    /// PCA does not always yield the OBB with the minimum volume, but it is fast and gives good results for elongated
+    /// objects
+    ///
+    /// ```
+    /// # use pasture_core::math::OBB;
+    /// # use pasture_core::nalgebra::{Point3, Vector3};
+    /// // Points along the diagonal of the xy-plane
+    /// let positions = vec![
+    ///     Vector3::new(0.0, 0.0, 0.0),
+    ///     Vector3::new(1.0, 1.0, 0.0),
+    ///     Vector3::new(2.0, 2.0, 0.0),
+    /// ];
+    /// let bounds = OBB::from_positions(&positions).unwrap();
+    /// assert!((bounds.center() - Point3::new(1.0, 1.0, 0.0)).norm() < 1e-9);
+    /// assert!((bounds.axes()[0].x.abs() - 0.5_f64.sqrt()).abs() < 1e-9);
+    /// assert!((bounds.half_extents().x - 2.0_f64.sqrt()).abs() < 1e-9);
+    /// ```
+    pub fn from_positions(positions: &[Vector3<f64>]) -> Option<Self> {
+        let statistics: RunningCovariance = positions.iter().copied().collect();
+        let mean = statistics.mean()?;
+        let covariance = statistics.covariance()?;
+
+        let eigen = covariance.symmetric_eigen();
+        let mut order = [0, 1, 2];
+        order.sort_by(|a, b| {
+            eigen.eigenvalues[*b]
+                .partial_cmp(&eigen.eigenvalues[*a])
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let first_axis: Vector3<f64> = eigen.eigenvectors.column(order[0]).normalize();
+        let second_axis: Vector3<f64> = eigen.eigenvectors.column(order[1]).normalize();
+        let axes = [first_axis, second_axis, first_axis.cross(&second_axis)];
+
+        let mut min = Vector3::repeat(f64::INFINITY);
+        let mut max = Vector3::repeat(f64::NEG_INFINITY);
+        for position in positions {
+            let offset = position - mean;
+            for (axis_index, axis) in axes.iter().enumerate() {
+                let projection = offset.dot(axis);
+                min[axis_index] = min[axis_index].min(projection);
+                max[axis_index] = max[axis_index].max(projection);
+            }
+        }
+
+        let center_in_box = (min + max) / 2.0;
+        let center = mean
+            + axes[0] * center_in_box.x
+            + axes[1] * center_in_box.y
+            + axes[2] * center_in_box.z;
+        Some(Self {
+            center: center.into(),
+            axes,
+            half_extents: (max - min) / 2.0,
+        })
+    }
+
+    /// Calculates the oriented bounding box of the positions of all points in `buffer`. See
+    /// [from_positions](OBB::from_positions) for details. Returns `None` if `buffer` is empty
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute.
+    pub fn from_buffer<T: PointBuffer + ?Sized>(buffer: &T) -> Option<Self> {
+        Self::from_positions(&positions(buffer))
+    }
+
+    /// Calculates the oriented bounding box of the positions of the points with the given `indices` in `buffer`, e.g.
+    /// of the points of a single object after a segmentation. See [from_positions](OBB::from_positions) for details.
+    /// Returns `None` if `indices` is empty
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `buffer` doesn't contain a `POSITION_3D` attribute, or if any index is out of bounds.
+    pub fn from_buffer_selection<T: PointBuffer + ?Sized>(
+        buffer: &T,
+        indices: &[usize],
+    ) -> Option<Self> {
+        let positions = positions(buffer);
+        let selected_positions = indices
+            .iter()
+            .map(|index| positions[*index])
+            .collect::<Vec<_>>();
+        Self::from_positions(&selected_positions)
+    }
+
+    /// Returns the center point of this OBB
+    pub fn center(&self) -> Point3<f64> {
+        self.center
+    }
+
+    /// Returns the three orthonormal axes of this OBB
+    pub fn axes(&self) -> &[Vector3<f64>; 3] {
+        &self.axes
+    }
+
+    /// Returns half the size of this OBB along each of its axes
+    pub fn half_extents(&self) -> &Vector3<f64> {
+        &self.half_extents
+    }
+
+    /// Returns the size of this OBB along each of its axes
+    pub fn extent(&self) -> Vector3<f64> {
+        self.half_extents * 2.0
+    }
+
+    /// Returns the volume of this OBB
+    pub fn volume(&self) -> f64 {
+        let extent = self.extent();
+        extent.x * extent.y * extent.z
+    }
+
+    /// Returns the coordinates of `point` in the coordinate system of this OBB, whose origin is the center of this OBB
+    /// and whose axes are the axes of this OBB
+    pub fn to_box_coordinates(self, point: &Point3<f64>) -> Vector3<f64> {
+        let offset = point - self.center;
+        Vector3::new(
+            offset.dot(&self.axes[0]),
+            offset.dot(&self.axes[1]),
+            offset.dot(&self.axes[2]),
+        )
+    }
+
+    /// Returns true if the associated OBB contains the given point. Points on the boundary of the OBB are considered
+    /// to be contained
+    pub fn contains(&self, point: &Point3<f64>) -> bool {
+        let local = self.to_box_coordinates(point);
+        local.x.abs() <= self.half_extents.x
+            && local.y.abs() <= self.half_extents.y
+            && local.z.abs() <= self.half_extents.z
+    }
+
+    /// Returns the eight corner points of this OBB
+    pub fn corners(&self) -> [Point3<f64>; 8] {
+        let mut corners = [self.center; 8];
+        for (index, corner) in corners.iter_mut().enumerate() {
+            for axis in 0..3 {
+                let sign = if index & (1 << axis) == 0 { -1.0 } else { 1.0 };
+                *corner += self.axes[axis] * (sign * self.half_extents[axis]);
+            }
+        }
+        corners
+    }
+}
+
+fn positions<T: PointBuffer + ?Sized>(buffer: &T) -> Vec<Vector3<f64>> {
+    if !buffer
+        .point_layout()
+        .has_attribute_with_name(POSITION_3D.name())
+    {
+        panic!("point buffer contains no position attribute");
+    }
+    buffer
+        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Rotation3;
+
+    #[test]
+    fn obb_of_rotated_box() {
+        let rotation = Rotation3::from_axis_angle(&Vector3::z_axis(), 30_f64.to_radians());
+        let translation = Vector3::new(100.0, 200.0, 50.0);
+        let mut positions = vec![];
+        for x in 0..=10 {
+            for y in 0..=4 {
+                for z in 0..=1 {
+                    let position = Vector3::new(x as f64 - 5.0, y as f64 - 2.0, z as f64 - 0.5);
+                    positions.push(rotation * position + translation);
+                }
+            }
+        }
+
+        let bounds = OBB::from_positions(&positions).unwrap();
+        assert!((bounds.center() - Point3::from(translation)).norm() < 1e-9);
+        assert!((bounds.half_extents() - Vector3::new(5.0, 2.0, 0.5)).norm() < 1e-9);
+        assert!((bounds.volume() - 10.0 * 4.0 * 1.0).abs() < 1e-9);
+        let expected_axes = [
+            rotation * Vector3::x(),
+            rotation * Vector3::y(),
+            Vector3::z(),
+        ];
+        for (axis, expected_axis) in bounds.axes().iter().zip(expected_axes.iter()) {
+            assert!((axis.dot(expected_axis).abs() - 1.0).abs() < 1e-9);
+        }
+        assert!((bounds.axes()[0].cross(&bounds.axes()[1]) - bounds.axes()[2]).norm() < 1e-9);
+
+        assert!(bounds.contains(&Point3::from(
+            translation + rotation * Vector3::new(4.9, -1.9, 0.4)
+        )));
+        for corner in bounds.corners().iter() {
+            assert!(positions
+                .iter()
+                .any(|position| (Point3::from(*position) - corner).norm() < 1e-9));
+        }
+        assert!(!bounds.contains(&Point3::from(
+            translation + rotation * Vector3::new(5.5, 0.0, 0.0)
+        )));
+    }
+
+    #[test]
+    fn obb_of_no_positions() {
+        assert_eq!(None, OBB::from_positions(&[]));
+    }
+}
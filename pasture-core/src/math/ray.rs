@@ -0,0 +1,114 @@
+use nalgebra::{Point3, Vector3};
+
+use super::AABB;
+
+/// A ray in 3D, starting at `origin` and extending infinitely along `direction`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ray {
+    origin: Point3<f64>,
+    direction: Vector3<f64>,
+}
+
+impl Ray {
+    /// Creates a new ray with the given `origin` and `direction`. The direction is normalized, so distances along the
+    /// ray are always in world units
+    ///
+    /// # Panics
+    ///
+    /// If `direction` is the zero vector
+    pub fn new(origin: Point3<f64>, direction: Vector3<f64>) -> Self {
+        let length = direction.norm();
+        if length == 0.0 {
+            panic!("Ray::new: Direction must not be the zero vector!");
+        }
+        Self {
+            origin,
+            direction: direction / length,
+        }
+    }
+
+    /// Returns the origin of this ray
+    pub fn origin(&self) -> &Point3<f64> {
+        &self.origin
+    }
+
+    /// Returns the unit direction vector of this ray
+    pub fn direction(&self) -> &Vector3<f64> {
+        &self.direction
+    }
+
+    /// Returns the point at the given `distance` along this ray
+    pub fn at(&self, distance: f64) -> Point3<f64> {
+        self.origin + self.direction * distance
+    }
+
+    /// Returns the distance along this ray to the point on this ray that is closest to `point`, together with the
+    /// distance between `point` and that closest point. The distance along the ray is negative for points behind the
+    /// origin of the ray, in which case the second value is still the distance to the infinite line through this ray
+    /// ```
+    /// # use pasture_core::math::Ray;
+    /// # use pasture_core::nalgebra::{Point3, Vector3};
+    /// let ray = Ray::new(Point3::origin(), Vector3::new(2.0, 0.0, 0.0));
+    /// assert_eq!((3.0, 4.0), ray.closest_approach(&Point3::new(3.0, 4.0, 0.0)));
+    /// ```
+    pub fn closest_approach(&self, point: &Point3<f64>) -> (f64, f64) {
+        let offset = point - self.origin;
+        let distance_along_ray = offset.dot(&self.direction);
+        let distance_to_ray = (offset - self.direction * distance_along_ray).norm();
+        (distance_along_ray, distance_to_ray)
+    }
+
+    /// Intersects this ray with `bounds` using the slab method. Returns the distances along this ray at which the ray
+    /// enters and leaves `bounds`, or `None` if the ray misses `bounds`. If the origin of the ray is inside of `bounds`,
+    /// the entry distance is zero
+    pub fn intersect_aabb(&self, bounds: &AABB<f64>) -> Option<(f64, f64)> {
+        let mut entry = 0.0_f64;
+        let mut exit = f64::INFINITY;
+        for axis in 0..3 {
+            let origin = self.origin[axis];
+            let direction = self.direction[axis];
+            let (min, max) = (bounds.min()[axis], bounds.max()[axis]);
+            if direction == 0.0 {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+            let first = (min - origin) / direction;
+            let second = (max - origin) / direction;
+            entry = entry.max(first.min(second));
+            exit = exit.min(first.max(second));
+            if entry > exit {
+                return None;
+            }
+        }
+        Some((entry, exit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_aabb_intersection() {
+        let bounds = AABB::from_min_max(Point3::new(1.0, -1.0, -1.0), Point3::new(3.0, 1.0, 1.0));
+
+        let ray = Ray::new(Point3::origin(), Vector3::x());
+        assert_eq!(Some((1.0, 3.0)), ray.intersect_aabb(&bounds));
+
+        let inside = Ray::new(Point3::new(2.0, 0.0, 0.0), Vector3::x());
+        assert_eq!(Some((0.0, 1.0)), inside.intersect_aabb(&bounds));
+
+        let behind = Ray::new(Point3::new(4.0, 0.0, 0.0), Vector3::x());
+        assert_eq!(None, behind.intersect_aabb(&bounds));
+
+        let parallel = Ray::new(Point3::new(0.0, 2.0, 0.0), Vector3::x());
+        assert_eq!(None, parallel.intersect_aabb(&bounds));
+
+        let diagonal = Ray::new(Point3::new(0.0, -2.0, 0.0), Vector3::new(1.0, 1.0, 0.0));
+        let (entry, exit) = diagonal.intersect_aabb(&bounds).unwrap();
+        assert!((entry - 2.0_f64.sqrt()).abs() < 1e-12);
+        assert!((exit - 3.0 * 2.0_f64.sqrt()).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,318 @@
+use std::ops::Range;
+
+use float_ord::FloatOrd;
+use nalgebra::Point3;
+
+use super::AABB;
+
+/// Default maximum number of children of a node of an [RTree]
+pub const DEFAULT_RTREE_NODE_CAPACITY: usize = 16;
+
+/// A node of an [RTree]
+#[derive(Debug, Clone)]
+struct RTreeNode {
+    /// Bounds of all entries below this node
+    bounds: AABB<f64>,
+    /// Range of the children of this node, either within the entries (for leaf nodes) or the nodes of the tree
+    children: Range<usize>,
+    is_leaf: bool,
+}
+
+/// An R-tree that indexes many bounding boxes, such as the bounds of the tiles of a dataset, of files or of segments,
+/// together with a value of type `T` for each bounding box. The tree is bulk-loaded with the Sort-Tile-Recursive
+/// algorithm and is immutable afterwards, which is the common case for indexing a fixed set of files. Queries return
+/// all entries whose bounding box intersects a query box or contains a point, and [intersecting_pairs](RTree::intersecting_pairs)
+/// joins two trees
+/// ```
+/// # use pasture_core::math::{AABB, RTree};
+/// # use nalgebra::Point3;
+/// let tiles = RTree::new(vec![
+///     (AABB::from_min_max(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0)), "a.laz"),
+///     (AABB::from_min_max(Point3::new(1.0, 0.0, 0.0), Point3::new(2.0, 1.0, 1.0)), "b.laz"),
+/// ]);
+/// let query = AABB::from_min_max(Point3::new(1.5, 0.5, 0.5), Point3::new(3.0, 3.0, 3.0));
+/// assert_eq!(vec![&"b.laz"], tiles.intersecting(&query));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RTree<T> {
+    entries: Vec<(AABB<f64>, T)>,
+    nodes: Vec<RTreeNode>,
+}
+
+impl<T> RTree<T> {
+    /// Bulk-loads an R-tree from the given `entries` with at most [DEFAULT_RTREE_NODE_CAPACITY] children per node
+    pub fn new(entries: Vec<(AABB<f64>, T)>) -> Self {
+        Self::with_node_capacity(entries, DEFAULT_RTREE_NODE_CAPACITY)
+    }
+
+    /// Bulk-loads an R-tree from the given `entries` with at most `node_capacity` children per node
+    ///
+    /// # Panics
+    ///
+    /// If `node_capacity` is less than 2
+    pub fn with_node_capacity(entries: Vec<(AABB<f64>, T)>, node_capacity: usize) -> Self {
+        if node_capacity < 2 {
+            panic!("RTree::with_node_capacity: node_capacity must be at least 2");
+        }
+        let entry_bounds = entries
+            .iter()
+            .map(|(bounds, _)| *bounds)
+            .collect::<Vec<_>>();
+        let order = sort_tile_recursive(&entry_bounds, node_capacity);
+        let mut entries = entries.into_iter().map(Some).collect::<Vec<_>>();
+        let entries = order
+            .iter()
+            .map(|index| entries[*index].take().unwrap())
+            .collect::<Vec<_>>();
+
+        let mut nodes = vec![];
+        let mut level = group_into_nodes(
+            entries.iter().map(|(bounds, _)| *bounds).collect(),
+            0,
+            node_capacity,
+            true,
+        );
+        while level.len() > 1 {
+            let level_bounds = level.iter().map(|node| node.bounds).collect::<Vec<_>>();
+            let order = sort_tile_recursive(&level_bounds, node_capacity);
+            let first_node = nodes.len();
+            nodes.extend(order.iter().map(|index| level[*index].clone()));
+            let sorted_bounds = order.iter().map(|index| level_bounds[*index]).collect();
+            level = group_into_nodes(sorted_bounds, first_node, node_capacity, false);
+        }
+        // The root node is always the last node
+        nodes.extend(level);
+
+        Self { entries, nodes }
+    }
+
+    /// Returns the number of entries in this tree
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if this tree has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the bounds of all entries in this tree, or `None` if the tree is empty
+    pub fn bounds(&self) -> Option<AABB<f64>> {
+        self.nodes.last().map(|root| root.bounds)
+    }
+
+    /// Returns an iterator over all entries of this tree. The order of the entries is unspecified
+    pub fn iter(&self) -> impl Iterator<Item = &(AABB<f64>, T)> {
+        self.entries.iter()
+    }
+
+    /// Returns the values of all entries whose bounds intersect `bounds`. The order of the values is unspecified
+    pub fn intersecting(&self, bounds: &AABB<f64>) -> Vec<&T> {
+        let mut values = vec![];
+        self.search(|node_bounds| node_bounds.intersects(bounds), &mut values);
+        values
+    }
+
+    /// Returns the values of all entries whose bounds contain `point`. The order of the values is unspecified
+    pub fn containing(&self, point: &Point3<f64>) -> Vec<&T> {
+        let mut values = vec![];
+        self.search(|node_bounds| node_bounds.contains(point), &mut values);
+        values
+    }
+
+    /// Returns all pairs of values of this tree and `other` whose bounds intersect, i.e. the spatial join of the two
+    /// trees. Both trees are traversed simultaneously, so that only pairs of nodes that intersect are visited. The order
+    /// of the pairs is unspecified
+    pub fn intersecting_pairs<'a, U>(&'a self, other: &'a RTree<U>) -> Vec<(&'a T, &'a U)> {
+        let mut pairs = vec![];
+        if !self.is_empty() && !other.is_empty() {
+            self.join(
+                self.nodes.len() - 1,
+                other,
+                other.nodes.len() - 1,
+                &mut pairs,
+            );
+        }
+        pairs
+    }
+
+    fn search<'a, F: Fn(&AABB<f64>) -> bool>(&'a self, matches: F, values: &mut Vec<&'a T>) {
+        let mut stack = match self.nodes.len() {
+            0 => vec![],
+            len => vec![len - 1],
+        };
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if !matches(&node.bounds) {
+                continue;
+            }
+            if node.is_leaf {
+                values.extend(
+                    self.entries[node.children.clone()]
+                        .iter()
+                        .filter(|(bounds, _)| matches(bounds))
+                        .map(|(_, value)| value),
+                );
+            } else {
+                stack.extend(node.children.clone());
+            }
+        }
+    }
+
+    fn join<'a, U>(
+        &'a self,
+        node: usize,
+        other: &'a RTree<U>,
+        other_node: usize,
+        pairs: &mut Vec<(&'a T, &'a U)>,
+    ) {
+        let this = &self.nodes[node];
+        let that = &other.nodes[other_node];
+        if !this.bounds.intersects(&that.bounds) {
+            return;
+        }
+        match (this.is_leaf, that.is_leaf) {
+            (true, true) => {
+                for (bounds, value) in &self.entries[this.children.clone()] {
+                    pairs.extend(
+                        other.entries[that.children.clone()]
+                            .iter()
+                            .filter(|(other_bounds, _)| bounds.intersects(other_bounds))
+                            .map(|(_, other_value)| (value, other_value)),
+                    );
+                }
+            }
+            // Descend into the inner node, so that both trees reach their leaves
+            (false, true) => {
+                for child in this.children.clone() {
+                    self.join(child, other, other_node, pairs);
+                }
+            }
+            (_, false) => {
+                for other_child in that.children.clone() {
+                    self.join(node, other, other_child, pairs);
+                }
+            }
+        }
+    }
+}
+
+/// Groups consecutive runs of `node_capacity` bounding boxes into nodes, whose children start at `first_child`
+fn group_into_nodes(
+    bounds: Vec<AABB<f64>>,
+    first_child: usize,
+    node_capacity: usize,
+    is_leaf: bool,
+) -> Vec<RTreeNode> {
+    bounds
+        .chunks(node_capacity)
+        .enumerate()
+        .map(|(index, chunk)| {
+            let start = first_child + index * node_capacity;
+            RTreeNode {
+                bounds: chunk
+                    .iter()
+                    .skip(1)
+                    .fold(chunk[0], |union, bounds| AABB::union(&union, bounds)),
+                children: start..start + chunk.len(),
+                is_leaf,
+            }
+        })
+        .collect()
+}
+
+/// Returns the order of the given `bounds` according to the Sort-Tile-Recursive algorithm: The bounds are sorted by
+/// the X coordinate of their centers and split into vertical slices, then each slice is sorted by the Y coordinate.
+/// Consecutive runs of `node_capacity` bounds in this order form compact nodes
+fn sort_tile_recursive(bounds: &[AABB<f64>], node_capacity: usize) -> Vec<usize> {
+    let center = |index: &usize| nalgebra::center(bounds[*index].min(), bounds[*index].max());
+    let mut order = (0..bounds.len()).collect::<Vec<_>>();
+    order.sort_by_key(|index| FloatOrd(center(index).x));
+    let node_count = (bounds.len() + node_capacity - 1) / node_capacity;
+    let slice_count = (node_count as f64).sqrt().ceil() as usize;
+    let slice_size = slice_count.max(1) * node_capacity;
+    for slice in order.chunks_mut(slice_size) {
+        slice.sort_by_key(|index| FloatOrd(center(index).y));
+    }
+    order
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid_of_tiles(count_x: usize, count_y: usize, size: f64) -> Vec<(AABB<f64>, usize)> {
+        (0..count_x * count_y)
+            .map(|index| {
+                let min = Point3::new(
+                    (index % count_x) as f64 * size,
+                    (index / count_x) as f64 * size,
+                    0.0,
+                );
+                let bounds = AABB::from_min_max(min, min + nalgebra::Vector3::new(size, size, 1.0));
+                (bounds, index)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_rtree_queries_match_brute_force() {
+        let tiles = grid_of_tiles(37, 23, 10.0);
+        let tree = RTree::with_node_capacity(tiles.clone(), 4);
+        assert_eq!(tiles.len(), tree.len());
+        assert_eq!(
+            Some(AABB::from_min_max(
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(370.0, 230.0, 1.0)
+            )),
+            tree.bounds()
+        );
+
+        let query = AABB::from_min_max(Point3::new(55.0, 42.0, 0.5), Point3::new(125.0, 80.0, 3.0));
+        let expected = tiles
+            .iter()
+            .filter(|(bounds, _)| bounds.intersects(&query))
+            .map(|(_, index)| *index)
+            .collect::<Vec<_>>();
+        let mut actual = tree
+            .intersecting(&query)
+            .into_iter()
+            .copied()
+            .collect::<Vec<_>>();
+        actual.sort_unstable();
+        assert_eq!(expected, actual);
+
+        let mut containing = tree.containing(&Point3::new(20.0, 15.0, 0.5));
+        containing.sort_unstable();
+        assert_eq!(vec![&38, &39], containing);
+        assert!(tree.containing(&Point3::new(20.0, 15.0, 2.0)).is_empty());
+    }
+
+    #[test]
+    fn test_rtree_intersecting_pairs() {
+        let tiles = RTree::new(grid_of_tiles(20, 20, 10.0));
+        let coarse_tiles = RTree::with_node_capacity(grid_of_tiles(4, 4, 50.0), 2);
+
+        let mut pairs = tiles
+            .intersecting_pairs(&coarse_tiles)
+            .into_iter()
+            .map(|(tile, coarse_tile)| (*tile, *coarse_tile))
+            .collect::<Vec<_>>();
+        pairs.sort_unstable();
+        let mut expected = vec![];
+        for (bounds, tile) in tiles.iter() {
+            for (coarse_bounds, coarse_tile) in coarse_tiles.iter() {
+                if bounds.intersects(coarse_bounds) {
+                    expected.push((*tile, *coarse_tile));
+                }
+            }
+        }
+        expected.sort_unstable();
+        assert_eq!(expected, pairs);
+
+        let empty: RTree<usize> = RTree::new(vec![]);
+        assert!(empty.bounds().is_none());
+        assert!(empty.intersecting_pairs(&tiles).is_empty());
+        assert!(tiles.intersecting_pairs(&empty).is_empty());
+    }
+}
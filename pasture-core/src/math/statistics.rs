@@ -0,0 +1,393 @@
+use std::iter::FromIterator;
+
+use nalgebra::{Matrix3, Vector3};
+
+use super::MinMax;
+
+/// Streaming statistics (count, minimum, maximum, mean and variance) of a sequence of scalar values. The mean and
+/// variance are calculated with Welford's algorithm, which is numerically stable even for values with a large offset
+/// (like GPS times or projected coordinates). Statistics of separate chunks can be combined using
+/// [merge](RunningStatistics::merge), so the values can be processed in parallel
+///
+/// ```
+/// # use pasture_core::math::RunningStatistics;
+/// let mut first_chunk: RunningStatistics = vec![1.0, 2.0, 3.0].into_iter().collect();
+/// let second_chunk: RunningStatistics = vec![4.0, 5.0].into_iter().collect();
+/// first_chunk.merge(&second_chunk);
+/// assert_eq!(5, first_chunk.count());
+/// assert_eq!(Some(3.0), first_chunk.mean());
+/// assert_eq!(Some(2.0), first_chunk.variance());
+/// assert_eq!(Some(5.0), first_chunk.max());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunningStatistics {
+    count: usize,
+    mean: f64,
+    sum_of_squared_deviations: f64,
+    min: f64,
+    max: f64,
+}
+
+impl RunningStatistics {
+    /// Creates new `RunningStatistics` without any values
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            sum_of_squared_deviations: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    /// Adds the given `value` to these statistics
+    pub fn add(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.sum_of_squared_deviations += delta * (value - self.mean);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Merges `other` into these statistics. The result is the same (up to rounding) as if all values of `other` had
+    /// been added to these statistics
+    pub fn merge(&mut self, other: &RunningStatistics) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = (self.count * other.count) as f64 / count as f64;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.sum_of_squared_deviations += other.sum_of_squared_deviations + delta * delta * weight;
+        self.count = count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    /// Returns the number of values in these statistics
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the minimum value, or `None` if there are no values
+    pub fn min(&self) -> Option<f64> {
+        self.if_not_empty(self.min)
+    }
+
+    /// Returns the maximum value, or `None` if there are no values
+    pub fn max(&self) -> Option<f64> {
+        self.if_not_empty(self.max)
+    }
+
+    /// Returns the arithmetic mean of all values, or `None` if there are no values
+    pub fn mean(&self) -> Option<f64> {
+        self.if_not_empty(self.mean)
+    }
+
+    /// Returns the population variance of all values, or `None` if there are no values
+    pub fn variance(&self) -> Option<f64> {
+        self.if_not_empty(self.sum_of_squared_deviations / self.count as f64)
+    }
+
+    /// Returns the sample variance (with Bessel's correction) of all values, or `None` if there are less than two
+    /// values
+    pub fn sample_variance(&self) -> Option<f64> {
+        if self.count < 2 {
+            None
+        } else {
+            Some(self.sum_of_squared_deviations / (self.count - 1) as f64)
+        }
+    }
+
+    /// Returns the population standard deviation of all values, or `None` if there are no values
+    pub fn standard_deviation(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    fn if_not_empty(&self, value: f64) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(value)
+        }
+    }
+}
+
+impl Default for RunningStatistics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extend<f64> for RunningStatistics {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(value);
+        }
+    }
+}
+
+impl FromIterator<f64> for RunningStatistics {
+    fn from_iter<I: IntoIterator<Item = f64>>(iter: I) -> Self {
+        let mut statistics = Self::new();
+        statistics.extend(iter);
+        statistics
+    }
+}
+
+/// Streaming mean and covariance matrix of a sequence of 3D vectors, e.g. of the positions of the neighborhood of a
+/// point for normal estimation, or of all points of an object for an [OBB](super::OBB). Like [RunningStatistics], this
+/// uses Welford's algorithm and statistics of separate chunks can be combined using
+/// [merge](RunningCovariance::merge)
+///
+/// ```
+/// # use pasture_core::math::RunningCovariance;
+/// # use pasture_core::nalgebra::Vector3;
+/// let covariance: RunningCovariance = vec![
+///     Vector3::new(-1.0, 0.0, 5.0),
+///     Vector3::new(1.0, 0.0, 5.0),
+/// ].into_iter().collect();
+/// assert_eq!(Some(Vector3::new(0.0, 0.0, 5.0)), covariance.mean());
+/// assert_eq!(1.0, covariance.covariance().unwrap()[(0, 0)]);
+/// assert_eq!(0.0, covariance.covariance().unwrap()[(2, 2)]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunningCovariance {
+    count: usize,
+    mean: Vector3<f64>,
+    sum_of_products: Matrix3<f64>,
+}
+
+impl RunningCovariance {
+    /// Creates a new `RunningCovariance` without any values
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            mean: Vector3::zeros(),
+            sum_of_products: Matrix3::zeros(),
+        }
+    }
+
+    /// Adds the given `value` to this `RunningCovariance`
+    pub fn add(&mut self, value: &Vector3<f64>) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.sum_of_products += delta * (value - self.mean).transpose();
+    }
+
+    /// Merges `other` into this `RunningCovariance`. The result is the same (up to rounding) as if all values of
+    /// `other` had been added to this `RunningCovariance`
+    pub fn merge(&mut self, other: &RunningCovariance) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = (self.count * other.count) as f64 / count as f64;
+        self.mean += delta * (other.count as f64 / count as f64);
+        self.sum_of_products += other.sum_of_products + delta * delta.transpose() * weight;
+        self.count = count;
+    }
+
+    /// Returns the number of values in this `RunningCovariance`
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the mean of all values, or `None` if there are no values
+    pub fn mean(&self) -> Option<Vector3<f64>> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.mean)
+        }
+    }
+
+    /// Returns the population covariance matrix of all values, or `None` if there are no values
+    pub fn covariance(&self) -> Option<Matrix3<f64>> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.sum_of_products / self.count as f64)
+        }
+    }
+}
+
+impl Default for RunningCovariance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extend<Vector3<f64>> for RunningCovariance {
+    fn extend<I: IntoIterator<Item = Vector3<f64>>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(&value);
+        }
+    }
+}
+
+impl FromIterator<Vector3<f64>> for RunningCovariance {
+    fn from_iter<I: IntoIterator<Item = Vector3<f64>>>(iter: I) -> Self {
+        let mut covariance = Self::new();
+        covariance.extend(iter);
+        covariance
+    }
+}
+
+/// Streaming minimum and maximum of a sequence of values of any type that implements [MinMax]. For vector types, the
+/// minimum and maximum are component-wise, so `RunningMinMax<Vector3<f64>>` calculates the bounds of a set of
+/// positions
+///
+/// ```
+/// # use pasture_core::math::RunningMinMax;
+/// # use pasture_core::nalgebra::Vector3;
+/// let mut min_max = RunningMinMax::new();
+/// min_max.add(&Vector3::new(1, 5, 3));
+/// min_max.add(&Vector3::new(4, 2, 6));
+/// assert_eq!(Some((Vector3::new(1, 2, 3), Vector3::new(4, 5, 6))), min_max.min_max());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RunningMinMax<T: MinMax + Copy> {
+    min_max: Option<(T, T)>,
+}
+
+impl<T: MinMax + Copy> RunningMinMax<T> {
+    /// Creates a new `RunningMinMax` without any values
+    pub fn new() -> Self {
+        Self { min_max: None }
+    }
+
+    /// Adds the given `value` to this `RunningMinMax`
+    pub fn add(&mut self, value: &T) {
+        self.min_max = Some(match self.min_max {
+            Some((min, max)) => (min.infimum(value), max.supremum(value)),
+            None => (*value, *value),
+        });
+    }
+
+    /// Merges `other` into this `RunningMinMax`
+    pub fn merge(&mut self, other: &RunningMinMax<T>) {
+        if let Some((other_min, other_max)) = other.min_max {
+            self.add(&other_min);
+            self.add(&other_max);
+        }
+    }
+
+    /// Returns the minimum value, or `None` if there are no values
+    pub fn min(&self) -> Option<T> {
+        self.min_max.map(|(min, _)| min)
+    }
+
+    /// Returns the maximum value, or `None` if there are no values
+    pub fn max(&self) -> Option<T> {
+        self.min_max.map(|(_, max)| max)
+    }
+
+    /// Returns the minimum and maximum value, or `None` if there are no values
+    pub fn min_max(&self) -> Option<(T, T)> {
+        self.min_max
+    }
+}
+
+impl<T: MinMax + Copy> Default for RunningMinMax<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: MinMax + Copy> Extend<T> for RunningMinMax<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.add(&value);
+        }
+    }
+}
+
+impl<T: MinMax + Copy> FromIterator<T> for RunningMinMax<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut min_max = Self::new();
+        min_max.extend(iter);
+        min_max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{prelude::StdRng, Rng, SeedableRng};
+
+    #[test]
+    fn merged_statistics_match_sequential_statistics() {
+        let mut rng = StdRng::seed_from_u64(7);
+        // Large offset as for GPS times, which breaks the naive sum-of-squares formula
+        let values = (0..1000)
+            .map(|_| 1e9 + rng.gen_range(-10.0..10.0))
+            .collect::<Vec<f64>>();
+
+        let sequential: RunningStatistics = values.iter().copied().collect();
+        let mut merged = RunningStatistics::new();
+        for chunk in values.chunks(77) {
+            merged.merge(&chunk.iter().copied().collect());
+        }
+
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let variance = values
+            .iter()
+            .map(|value| (value - mean) * (value - mean))
+            .sum::<f64>()
+            / values.len() as f64;
+        for statistics in [sequential, merged].iter() {
+            assert_eq!(values.len(), statistics.count());
+            assert!((statistics.mean().unwrap() - mean).abs() < 1e-6);
+            assert!((statistics.variance().unwrap() - variance).abs() < 1e-6);
+        }
+        assert_eq!(sequential.min(), merged.min());
+        assert_eq!(sequential.max(), merged.max());
+    }
+
+    #[test]
+    fn merged_covariance_matches_sequential_covariance() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let values = (0..500)
+            .map(|_| {
+                let x = rng.gen_range(-1.0..1.0);
+                Vector3::new(
+                    x,
+                    2.0 * x + rng.gen_range(-0.1..0.1),
+                    rng.gen_range(0.0..5.0),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let sequential: RunningCovariance = values.iter().copied().collect();
+        let mut merged = RunningCovariance::new();
+        for chunk in values.chunks(33) {
+            merged.merge(&chunk.iter().copied().collect());
+        }
+        assert_eq!(values.len(), merged.count());
+        assert!((sequential.mean().unwrap() - merged.mean().unwrap()).norm() < 1e-12);
+        assert!((sequential.covariance().unwrap() - merged.covariance().unwrap()).norm() < 1e-12);
+    }
+
+    #[test]
+    fn empty_statistics() {
+        let statistics = RunningStatistics::new();
+        assert_eq!(None, statistics.mean());
+        assert_eq!(None, statistics.variance());
+        assert_eq!(None, statistics.min());
+        assert_eq!(None, RunningCovariance::new().covariance());
+        assert_eq!(None, RunningMinMax::<f64>::new().min_max());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::math::AABB;
+use nalgebra::Matrix4;
 
 use std::{any::Any, fmt::Display};
 
@@ -15,6 +16,14 @@ pub trait Metadata: Display {
     fn number_of_points(&self) -> Option<usize>;
     /// Returns the value of the metadata field named `field_name`, if it exists.
     fn get_named_field(&self, field_name: &str) -> Option<Box<dyn Any>>;
+    /// Returns the transformation from the coordinate system of the positions to world space as a 4x4 matrix in
+    /// homogeneous coordinates, if the positions are stored relative to a pose, like the scanner pose of a terrestrial
+    /// scan or the center of a 3D Tiles tile. The transformation is kept symbolic, i.e. it is not part of the positions
+    /// that are read, see `TransformedPointBuffer` for applying it. Returns `None` if the positions are already in
+    /// world space, which is the default
+    fn transform(&self) -> Option<Matrix4<f64>> {
+        None
+    }
     /// Clone the associated `Metadata` and put it into a `Box`
     fn clone_into_box(&self) -> Box<dyn Metadata>;
 }
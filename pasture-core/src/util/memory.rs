@@ -1,3 +1,5 @@
+use alloc::{vec, vec::Vec};
+
 /// Returns a byte slice that points to the raw bytes of `val`.
 ///
 /// # Safety
@@ -13,7 +15,7 @@
 /// assert_eq!(val_raw_bytes.len(), 8);
 /// ```
 pub unsafe fn view_raw_bytes<T>(val: &T) -> &[u8] {
-    std::slice::from_raw_parts(val as *const T as *const u8, std::mem::size_of::<T>())
+    core::slice::from_raw_parts(val as *const T as *const u8, core::mem::size_of::<T>())
 }
 
 /// Returns a mutable byte slice that points to the raw bytes of `val`.
@@ -31,7 +33,7 @@ pub unsafe fn view_raw_bytes<T>(val: &T) -> &[u8] {
 /// assert_eq!(val_raw_bytes.len(), 8);
 /// ```
 pub unsafe fn view_raw_bytes_mut<T>(val: &mut T) -> &mut [u8] {
-    std::slice::from_raw_parts_mut(val as *mut T as *mut u8, std::mem::size_of::<T>())
+    core::slice::from_raw_parts_mut(val as *mut T as *mut u8, core::mem::size_of::<T>())
 }
 
 /// Push the raw bytes for `val` into `vec` in the endianess of the current machine
@@ -88,7 +90,7 @@ pub fn sort_untyped_slice_by_permutation(slice: &mut [u8], permutation: &[usize]
             unsafe {
                 let old_region = slice.as_mut_ptr().offset((prev_idx * stride) as isize);
                 let new_region = slice.as_mut_ptr().offset((new_idx * stride) as isize);
-                std::ptr::swap_nonoverlapping(old_region, new_region, stride);
+                core::ptr::swap_nonoverlapping(old_region, new_region, stride);
             }
 
             done_indices[new_idx] = true;
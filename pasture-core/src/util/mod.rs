@@ -1,2 +1,10 @@
 mod memory;
 pub use self::memory::*;
+#[cfg(feature = "std")]
+mod progress;
+#[cfg(feature = "std")]
+pub use self::progress::*;
+#[cfg(all(feature = "std", feature = "rerun"))]
+mod rerun_logging;
+#[cfg(all(feature = "std", feature = "rerun"))]
+pub use self::rerun_logging::*;
@@ -0,0 +1,141 @@
+use std::{
+    fmt::Display,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::Result;
+
+/// Callback that gets notified about the progress of a long-running operation, such as reading a large file. The
+/// callback receives the number of processed items (e.g. points or iterations) and the total number of items, if it
+/// is known. This trait is implemented for all matching closures
+pub trait ProgressCallback: Send + Sync {
+    /// Called whenever the operation made progress
+    fn on_progress(&self, processed: usize, total: Option<usize>);
+}
+
+impl<F: Fn(usize, Option<usize>) + Send + Sync> ProgressCallback for F {
+    fn on_progress(&self, processed: usize, total: Option<usize>) {
+        self(processed, total)
+    }
+}
+
+/// Error that is returned by operations that were cancelled through a [CancellationToken]. Since most of pasture uses
+/// `anyhow`, check for cancellation with `error.is::<Cancelled>()`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Operation was cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Token for cancelling long-running operations from another thread (e.g. a Ctrl-C handler). Clones of a token share
+/// the same state, so cancelling one of them cancels all of them
+///
+/// ```
+/// # use pasture_core::util::*;
+/// let token = CancellationToken::new();
+/// let other_token = token.clone();
+/// assert!(!token.is_cancelled());
+/// other_token.cancel();
+/// assert!(token.is_cancelled());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new `CancellationToken` which is not cancelled
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Cancels all operations that use this token or one of its clones
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns `true` if this token was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Progress reporting and cancellation options for long-running operations. The default `Progress` reports nothing and
+/// can't be cancelled
+///
+/// ```
+/// # use pasture_core::util::*;
+/// # use std::sync::{Arc, atomic::{AtomicUsize, Ordering}};
+/// let processed_points = Arc::new(AtomicUsize::new(0));
+/// let processed_points_clone = processed_points.clone();
+/// let token = CancellationToken::new();
+/// let progress = Progress::default()
+///     .with_callback(move |processed: usize, _total: Option<usize>| processed_points_clone.store(processed, Ordering::SeqCst))
+///     .with_cancellation_token(token.clone());
+///
+/// progress.report(42, Some(100));
+/// assert_eq!(42, processed_points.load(Ordering::SeqCst));
+/// assert!(progress.check_cancelled().is_ok());
+/// token.cancel();
+/// assert!(progress.check_cancelled().unwrap_err().is::<Cancelled>());
+/// ```
+#[derive(Clone, Default)]
+pub struct Progress {
+    callback: Option<Arc<dyn ProgressCallback>>,
+    cancellation_token: Option<CancellationToken>,
+}
+
+impl Progress {
+    /// Reports progress to the given `callback`
+    pub fn with_callback<C: ProgressCallback + 'static>(mut self, callback: C) -> Self {
+        self.callback = Some(Arc::new(callback));
+        self
+    }
+
+    /// Makes the operation cancellable through the given `cancellation_token`
+    pub fn with_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = Some(cancellation_token);
+        self
+    }
+
+    /// Reports that `processed` out of `total` items were processed
+    pub fn report(&self, processed: usize, total: Option<usize>) {
+        if let Some(callback) = &self.callback {
+            callback.on_progress(processed, total);
+        }
+    }
+
+    /// Returns `true` if the operation was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token
+            .as_ref()
+            .map(|token| token.is_cancelled())
+            .unwrap_or(false)
+    }
+
+    /// Returns a [Cancelled] error if the operation was cancelled
+    pub fn check_cancelled(&self) -> Result<()> {
+        if self.is_cancelled() {
+            Err(Cancelled.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl std::fmt::Debug for Progress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Progress")
+            .field("has_callback", &self.callback.is_some())
+            .field("cancellation_token", &self.cancellation_token)
+            .finish()
+    }
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use nalgebra::Vector3;
+
+use crate::{
+    containers::{PointBuffer, PointBufferExt},
+    layout::attributes::{CLASSIFICATION, COLOR_RGB, POSITION_3D},
+};
+
+/// Names and colors of the standard ASPRS classes of the LAS 1.4 specification
+const ASPRS_CLASSES: &[(u16, &str, [u8; 3])] = &[
+    (0, "Created, never classified", [160, 160, 160]),
+    (1, "Unclassified", [200, 200, 200]),
+    (2, "Ground", [139, 90, 43]),
+    (3, "Low vegetation", [144, 238, 144]),
+    (4, "Medium vegetation", [34, 139, 34]),
+    (5, "High vegetation", [0, 100, 0]),
+    (6, "Building", [220, 60, 60]),
+    (7, "Low point (noise)", [255, 0, 255]),
+    (8, "Model key-point", [128, 128, 128]),
+    (9, "Water", [0, 90, 255]),
+    (10, "Rail", [139, 69, 19]),
+    (11, "Road surface", [80, 80, 80]),
+    (12, "Overlap", [200, 200, 0]),
+    (13, "Wire - guard", [255, 215, 0]),
+    (14, "Wire - conductor", [255, 165, 0]),
+    (15, "Transmission tower", [128, 0, 128]),
+    (16, "Wire-structure connector", [218, 112, 214]),
+    (17, "Bridge deck", [112, 128, 144]),
+    (18, "High noise", [255, 0, 0]),
+];
+
+/// Logs the points in `buffer` to the [rerun](https://www.rerun.io) viewer that `recording` is connected to, as a
+/// point cloud with the given `entity_path`. Besides the positions, the `COLOR_RGB` and `CLASSIFICATION` attributes are
+/// logged if `buffer` contains them. Classifications are logged as class IDs together with an annotation context with
+/// the names and colors of the ASPRS classes, so points without colors are colored by their classification
+///
+/// ```no_run
+/// # use pasture_core::{containers::*, layout::*};
+/// # use pasture_core::util::log_points_to_rerun;
+/// let recording = rerun::RecordingStreamBuilder::new("pasture").spawn().unwrap();
+/// let buffer = PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[attributes::POSITION_3D]));
+/// // ... run some algorithm on buffer
+/// log_points_to_rerun(&recording, "points/intermediate", &buffer).unwrap();
+/// ```
+///
+/// The viewer uses single-precision floating point values, so for points with large coordinates (e.g. in a UTM
+/// projection) use [log_points_to_rerun_with_origin] instead
+///
+/// # Panics
+///
+/// If `buffer` contains no `POSITION_3D` attribute
+pub fn log_points_to_rerun<T: PointBuffer + ?Sized>(
+    recording: &rerun::RecordingStream,
+    entity_path: &str,
+    buffer: &T,
+) -> Result<()> {
+    log_points_to_rerun_with_origin(recording, entity_path, buffer, &Vector3::zeros())
+}
+
+/// Like [log_points_to_rerun], but logs the positions relative to the given `origin`. Using e.g. the minimum corner of
+/// the bounding box of a point cloud as `origin` avoids precision issues for points with large coordinates
+///
+/// # Panics
+///
+/// If `buffer` contains no `POSITION_3D` attribute
+pub fn log_points_to_rerun_with_origin<T: PointBuffer + ?Sized>(
+    recording: &rerun::RecordingStream,
+    entity_path: &str,
+    buffer: &T,
+    origin: &Vector3<f64>,
+) -> Result<()> {
+    let layout = buffer.point_layout();
+    if !layout.has_attribute_with_name(POSITION_3D.name()) {
+        panic!("point buffer contains no position attribute");
+    }
+
+    let positions = buffer
+        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+        .map(|position| {
+            let local = position - origin;
+            [local.x as f32, local.y as f32, local.z as f32]
+        });
+    let mut points = rerun::Points3D::new(positions);
+
+    if layout.has_attribute_with_name(COLOR_RGB.name()) {
+        // LAS colors use the full 16-bit range, the viewer expects 8-bit colors
+        let colors = buffer
+            .iter_attribute_as::<Vector3<u16>>(&COLOR_RGB)
+            .map(|color| {
+                rerun::Color::from_rgb(
+                    (color.x >> 8) as u8,
+                    (color.y >> 8) as u8,
+                    (color.z >> 8) as u8,
+                )
+            });
+        points = points.with_colors(colors);
+    }
+
+    if layout.has_attribute_with_name(CLASSIFICATION.name()) {
+        let annotation_context = rerun::AnnotationContext::new(
+            ASPRS_CLASSES
+                .iter()
+                .map(|(id, name, [r, g, b])| (*id, *name, rerun::Rgba32::from_rgb(*r, *g, *b))),
+        );
+        recording.log_timeless(entity_path, &annotation_context)?;
+
+        let class_ids = buffer
+            .iter_attribute_as::<u8>(&CLASSIFICATION)
+            .map(|classification| classification as u16);
+        points = points.with_class_ids(class_ids);
+    }
+
+    recording.log(entity_path, &points)?;
+    Ok(())
+}
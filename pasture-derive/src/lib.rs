@@ -7,8 +7,8 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::DeriveInput;
 use syn::{
-    parse_macro_input, Attribute, Data, Error, Field, Fields, GenericArgument, Ident, Lit,
-    NestedMeta, PathArguments, Result, Type, TypePath,
+    parse_macro_input, Attribute, Data, Error, Expr, ExprLit, Field, Fields, GenericArgument,
+    Ident, Lit, NestedMeta, PathArguments, Result, Type, TypeArray, TypePath,
 };
 
 mod layout;
@@ -30,6 +30,8 @@ enum PasturePrimitiveType {
     Vec3f32,
     Vec3f64,
     Vec4u8,
+    /// Fixed-size array `[T; N]` of a scalar type `T`
+    Array(Box<PasturePrimitiveType>, u64),
 }
 
 impl PasturePrimitiveType {
@@ -51,6 +53,7 @@ impl PasturePrimitiveType {
             PasturePrimitiveType::Vec3f32 => 4,
             PasturePrimitiveType::Vec3f64 => 8,
             &PasturePrimitiveType::Vec4u8 => 1,
+            PasturePrimitiveType::Array(element, _) => element.min_alignment(),
         }
     }
 
@@ -72,6 +75,7 @@ impl PasturePrimitiveType {
             PasturePrimitiveType::Vec3f32 => 12,
             PasturePrimitiveType::Vec3f64 => 24,
             &PasturePrimitiveType::Vec4u8 => 4,
+            PasturePrimitiveType::Array(element, len) => element.size() * len,
         }
     }
 
@@ -105,6 +109,29 @@ impl PasturePrimitiveType {
             PasturePrimitiveType::Vec4u8 => {
                 quote! {pasture_core::layout::PointAttributeDataType::Vec4u8}
             }
+            PasturePrimitiveType::Array(element, len) => {
+                let element_type = element.as_array_element_token_stream();
+                let len = *len as u32;
+                quote! {pasture_core::layout::PointAttributeDataType::Array(#element_type, #len)}
+            }
+        }
+    }
+
+    /// Returns the `ArrayElementType` for this type, which must be a scalar type
+    fn as_array_element_token_stream(&self) -> quote::__private::TokenStream {
+        match self {
+            PasturePrimitiveType::U8 => quote! {pasture_core::layout::ArrayElementType::U8},
+            PasturePrimitiveType::I8 => quote! {pasture_core::layout::ArrayElementType::I8},
+            PasturePrimitiveType::U16 => quote! {pasture_core::layout::ArrayElementType::U16},
+            PasturePrimitiveType::I16 => quote! {pasture_core::layout::ArrayElementType::I16},
+            PasturePrimitiveType::U32 => quote! {pasture_core::layout::ArrayElementType::U32},
+            PasturePrimitiveType::I32 => quote! {pasture_core::layout::ArrayElementType::I32},
+            PasturePrimitiveType::U64 => quote! {pasture_core::layout::ArrayElementType::U64},
+            PasturePrimitiveType::I64 => quote! {pasture_core::layout::ArrayElementType::I64},
+            PasturePrimitiveType::F32 => quote! {pasture_core::layout::ArrayElementType::F32},
+            PasturePrimitiveType::F64 => quote! {pasture_core::layout::ArrayElementType::F64},
+            PasturePrimitiveType::Bool => quote! {pasture_core::layout::ArrayElementType::Bool},
+            _ => unreachable!("Array elements are checked to be scalar types"),
         }
     }
 }
@@ -123,6 +150,9 @@ fn get_primitive_type_for_ident_type(ident: &Ident) -> Result<PasturePrimitiveTy
         "f32" => Ok(PasturePrimitiveType::F32),
         "f64" => Ok(PasturePrimitiveType::F64),
         "bool" => Ok(PasturePrimitiveType::Bool),
+        // glam vector types, which are supported by pasture-core with the 'glam' feature
+        "Vec3" => Ok(PasturePrimitiveType::Vec3f32),
+        "DVec3" => Ok(PasturePrimitiveType::Vec3f64),
         _ => Err(Error::new_spanned(
             ident,
             format!("Type {} is no valid Pasture primitive type!", type_name),
@@ -212,6 +242,43 @@ fn type_path_to_primitive_type(type_path: &TypePath) -> Result<PasturePrimitiveT
     // Ok(gen)
 }
 
+fn type_array_to_primitive_type(type_array: &TypeArray) -> Result<PasturePrimitiveType> {
+    let invalid_element_error = || {
+        Error::new_spanned(
+            &type_array.elem,
+            "Arrays in a struct with #[derive(PointType)] must have one of the scalar types u8, i8, u16, i16, u32, i32, u64, i64, f32, f64 or bool as element type",
+        )
+    };
+    let element_type = match type_array.elem.as_ref() {
+        Type::Path(type_path) if type_path.qself.is_none() => type_path
+            .path
+            .get_ident()
+            .and_then(|ident| get_primitive_type_for_ident_type(ident).ok())
+            .ok_or_else(invalid_element_error)?,
+        _ => return Err(invalid_element_error()),
+    };
+    match element_type {
+        PasturePrimitiveType::Vec3f32 | PasturePrimitiveType::Vec3f64 => {
+            return Err(invalid_element_error())
+        }
+        _ => (),
+    }
+
+    let len = match &type_array.len {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(len), ..
+        }) => len.base10_parse::<u64>()?,
+        bad => {
+            return Err(Error::new_spanned(
+                bad,
+                "The length of an array in a struct with #[derive(PointType)] must be an integer literal",
+            ))
+        }
+    };
+
+    Ok(PasturePrimitiveType::Array(Box::new(element_type), len))
+}
+
 fn get_attribute_name_from_field(field: &Field) -> Result<String> {
     if field.attrs.len() != 1 {
         return Err(Error::new_spanned(
@@ -272,6 +339,11 @@ fn get_attribute_name_from_field(field: &Field) -> Result<String> {
                         "BUILTIN_WAVEFORM_PARAMETERS" => Ok("WaveformParameters".into()),
                         "BUILTIN_POINT_ID" => Ok("PointID".into()),
                         "BUILTIN_NORMAL" => Ok("Normal".into()),
+                        "BUILTIN_SENSOR_POSITION" => Ok("SensorPosition".into()),
+                        "BUILTIN_RANGE" => Ok("Range".into()),
+                        "BUILTIN_INCIDENCE_ANGLE" => Ok("IncidenceAngle".into()),
+                        "BUILTIN_RING" => Ok("Ring".into()),
+                        "BUILTIN_LOD_LEVEL" => Ok("LODLevel".into()),
                         // TODO Other attributes
                         _ => {
                             return Err(Error::new_spanned(
@@ -323,6 +395,15 @@ fn get_field_layout_descriptions(fields: &Fields) -> Result<Vec<FieldLayoutDescr
                     primitive_type,
                 })
             }
+            Type::Array(ref type_array) => {
+                let primitive_type = type_array_to_primitive_type(type_array)?;
+                let attribute_name = get_attribute_name_from_field(field)?;
+
+                Ok(FieldLayoutDescription {
+                    attribute_name,
+                    primitive_type,
+                })
+            }
             ref bad => Err(Error::new_spanned(
                 bad,
                 format!("Invalid type in PointType struct"),
@@ -390,6 +471,8 @@ fn calculate_offsets_and_alignment(
 /// Any that that wants to implement `PointType` using this `derive` macro must fulfill the following requirements:
 /// - It must be at least one of `#[repr(C)]` and `#[repr(packed)]`
 /// - All its members may only be [Pasture primitive types](pasture_core::layout::PointAttributeDataType)
+/// - `Vec3` and `DVec3` members are treated as the [glam](https://crates.io/crates/glam) vector types, which requires the `glam` feature of `pasture-core`
+/// - Fixed-size arrays `[T; N]` of scalar types are supported, where `N` has to be an integer literal
 /// - Each member must contain an attribute `#[pasture(X)]`, where `X` is either one of the builtin attributes explained below, or `attribute = "name"` for a custom attribute named `name`
 /// - No two members may share the same attribute name
 ///
@@ -420,6 +503,11 @@ fn calculate_offsets_and_alignment(
 /// - `BUILTIN_WAVEFORM_PARAMETERS` corresponding to the [WAVEFORM_PARAMETERS](pasture_core::layout::attributes::WAVEFORM_PARAMETERS) attribute
 /// - `BUILTIN_POINT_ID` corresponding to the [POINT_ID](pasture_core::layout::attributes::POINT_ID) attribute
 /// - `BUILTIN_NORMAL` corresponding to the [NORMAL](pasture_core::layout::attributes::NORMAL) attribute
+/// - `BUILTIN_SENSOR_POSITION` corresponding to the [SENSOR_POSITION](pasture_core::layout::attributes::SENSOR_POSITION) attribute
+/// - `BUILTIN_RANGE` corresponding to the [RANGE](pasture_core::layout::attributes::RANGE) attribute
+/// - `BUILTIN_INCIDENCE_ANGLE` corresponding to the [INCIDENCE_ANGLE](pasture_core::layout::attributes::INCIDENCE_ANGLE) attribute
+/// - `BUILTIN_RING` corresponding to the [RING](pasture_core::layout::attributes::RING) attribute
+/// - `BUILTIN_LOD_LEVEL` corresponding to the [LOD_LEVEL](pasture_core::layout::attributes::LOD_LEVEL) attribute
 ///
 /// # Custom attributes
 ///
@@ -0,0 +1,658 @@
+use std::{borrow::Cow, convert::TryInto, ops::Range};
+
+use anyhow::{anyhow, bail, Result};
+use pasture_core::{
+    containers::{PointBuffer, PointBufferWriteable, PointBufferWriteableExt},
+    layout::{
+        attributes::{COLOR_RGB, POSITION_3D},
+        PointAttributeDataType, PointAttributeDefinition,
+    },
+    nalgebra::{Matrix4, Vector3, Vector4},
+};
+use wgpu::util::DeviceExt;
+
+/// Default number of points that are processed in a single GPU batch
+pub const DEFAULT_BATCH_SIZE: usize = 1 << 20;
+
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Maximum number of points in a single GPU batch, limited by the number of workgroups per dispatch that wgpu supports
+pub const MAX_BATCH_SIZE: usize = 65535 * WORKGROUP_SIZE as usize;
+
+/// WGSL function for reading scalar attribute values of various datatypes from a tightly packed array of 32-bit words.
+/// The `kind` values are the ones returned by `shader_value_kind`
+const READ_VALUE_WGSL: &str = r#"
+struct Words {
+    words: array<u32>;
+};
+
+[[group(0), binding(1)]] var<storage, read> source: Words;
+
+fn read_value(kind: u32, index: u32) -> f32 {
+    if (kind == 0u || kind == 1u) {
+        let byte_value = (source.words[index / 4u] >> ((index % 4u) * 8u)) & 255u;
+        if (kind == 1u) {
+            return f32(bitcast<i32>(byte_value << 24u) >> 24u);
+        }
+        return f32(byte_value);
+    }
+    if (kind == 2u || kind == 3u) {
+        let short_value = (source.words[index / 2u] >> ((index % 2u) * 16u)) & 65535u;
+        if (kind == 3u) {
+            return f32(bitcast<i32>(short_value << 16u) >> 16u);
+        }
+        return f32(short_value);
+    }
+    let word = source.words[index];
+    if (kind == 4u) {
+        return f32(word);
+    }
+    if (kind == 5u) {
+        return f32(bitcast<i32>(word));
+    }
+    return bitcast<f32>(word);
+}
+"#;
+
+const TRANSFORM_WGSL: &str = r#"
+struct Params {
+    transform: mat4x4<f32>;
+    count: u32;
+};
+
+struct Floats {
+    values: array<f32>;
+};
+
+[[group(0), binding(0)]] var<uniform> params: Params;
+[[group(0), binding(1)]] var<storage, read> positions: Floats;
+[[group(0), binding(2)]] var<storage, read_write> result: Floats;
+
+[[stage(compute), workgroup_size(64)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    let index = id.x;
+    if (index >= params.count) {
+        return;
+    }
+    let position = vec4<f32>(
+        positions.values[3u * index],
+        positions.values[3u * index + 1u],
+        positions.values[3u * index + 2u],
+        0.0
+    );
+    let transformed = params.transform * position;
+    result.values[4u * index] = transformed.x;
+    result.values[4u * index + 1u] = transformed.y;
+    result.values[4u * index + 2u] = transformed.z;
+    result.values[4u * index + 3u] = transformed.w;
+}
+"#;
+
+const CONVERT_WGSL: &str = r#"
+struct Params {
+    kind: u32;
+    count: u32;
+    scale: f32;
+    offset: f32;
+};
+
+struct Floats {
+    values: array<f32>;
+};
+
+[[group(0), binding(0)]] var<uniform> params: Params;
+[[group(0), binding(2)]] var<storage, read_write> result: Floats;
+
+[[stage(compute), workgroup_size(64)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    let index = id.x;
+    if (index >= params.count) {
+        return;
+    }
+    result.values[index] = read_value(params.kind, index) * params.scale + params.offset;
+}
+"#;
+
+const COLORIZE_WGSL: &str = r#"
+struct Params {
+    kind: u32;
+    count: u32;
+    min_value: f32;
+    max_value: f32;
+    color_count: u32;
+};
+
+struct Floats {
+    values: array<f32>;
+};
+
+struct Colors {
+    values: array<u32>;
+};
+
+[[group(0), binding(0)]] var<uniform> params: Params;
+[[group(0), binding(2)]] var<storage, read> ramp: Floats;
+[[group(0), binding(3)]] var<storage, read_write> result: Colors;
+
+[[stage(compute), workgroup_size(64)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    let index = id.x;
+    if (index >= params.count) {
+        return;
+    }
+    let value = read_value(params.kind, index);
+    var t = 0.0;
+    if (params.max_value > params.min_value) {
+        t = clamp((value - params.min_value) / (params.max_value - params.min_value), 0.0, 1.0);
+    }
+    let last_color = params.color_count - 1u;
+    let ramp_position = t * f32(last_color);
+    let lower = min(u32(floor(ramp_position)), last_color);
+    let upper = min(lower + 1u, last_color);
+    let fraction = ramp_position - f32(lower);
+    for (var component: u32 = 0u; component < 3u; component = component + 1u) {
+        let color = mix(ramp.values[3u * lower + component], ramp.values[3u * upper + component], fraction);
+        result.values[3u * index + component] = u32(round(color));
+    }
+}
+"#;
+
+/// A wgpu device together with its queue, used for running compute operations on point data. Points are processed
+/// in batches of [DEFAULT_BATCH_SIZE] points, which can be changed with [with_batch_size](Self::with_batch_size)
+pub struct GpuContext {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    batch_size: usize,
+}
+
+impl GpuContext {
+    /// Creates a new `GpuContext` on the default high-performance adapter of the system. This blocks until the device
+    /// is created and returns an error if no suitable adapter is available
+    pub fn new() -> Result<Self> {
+        pollster::block_on(async {
+            let instance = wgpu::Instance::new(wgpu::Backends::PRIMARY);
+            let adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    force_fallback_adapter: false,
+                    compatible_surface: None,
+                })
+                .await
+                .ok_or_else(|| anyhow!("No suitable GPU adapter found"))?;
+            let (device, queue) = adapter
+                .request_device(
+                    &wgpu::DeviceDescriptor {
+                        label: Some("pasture-gpu"),
+                        features: wgpu::Features::empty(),
+                        limits: wgpu::Limits::default(),
+                    },
+                    None,
+                )
+                .await?;
+            Ok(Self::from_device_and_queue(device, queue))
+        })
+    }
+
+    /// Creates a new `GpuContext` from an existing `device` and `queue`, e.g. the ones that an interactive viewer
+    /// uses for rendering
+    pub fn from_device_and_queue(device: wgpu::Device, queue: wgpu::Queue) -> Self {
+        Self {
+            device,
+            queue,
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Sets the number of points that are processed in a single GPU batch. Larger batches need more GPU memory
+    ///
+    /// # Panics
+    ///
+    /// If `batch_size` is zero or larger than [MAX_BATCH_SIZE]
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        if batch_size == 0 || batch_size > MAX_BATCH_SIZE {
+            panic!(
+                "GpuContext::with_batch_size: batch_size must be in [1;{}]",
+                MAX_BATCH_SIZE
+            );
+        }
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// The wgpu device of this context
+    pub fn device(&self) -> &wgpu::Device {
+        &self.device
+    }
+
+    /// The wgpu queue of this context
+    pub fn queue(&self) -> &wgpu::Queue {
+        &self.queue
+    }
+
+    fn create_pipeline(&self, label: &str, source: &str) -> wgpu::ComputePipeline {
+        let module = self
+            .device
+            .create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+            });
+        self.device
+            .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                label: Some(label),
+                layout: None,
+                module: &module,
+                entry_point: "main",
+            })
+    }
+
+    /// Runs `pipeline` for `count` invocations. Binding 0 is a uniform buffer with `params`, the `inputs` are bound
+    /// as read-only storage buffers starting at binding 1, and the output storage buffer with `output_size` bytes
+    /// is bound after the inputs. Returns the contents of the output buffer
+    fn run(
+        &self,
+        pipeline: &wgpu::ComputePipeline,
+        params: &[u8],
+        inputs: &[&[u8]],
+        output_size: usize,
+        count: usize,
+    ) -> Result<Vec<u8>> {
+        let params_buffer = self
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("pasture-gpu params"),
+                contents: params,
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+        let input_buffers = inputs
+            .iter()
+            .map(|input| {
+                // Storage buffers must not be empty and their size has to be a multiple of 4 bytes
+                let mut contents = input.to_vec();
+                contents.resize(((input.len() + 3) / 4).max(1) * 4, 0);
+                self.device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("pasture-gpu input"),
+                        contents: &contents,
+                        usage: wgpu::BufferUsages::STORAGE,
+                    })
+            })
+            .collect::<Vec<_>>();
+        let output_size = output_size as wgpu::BufferAddress;
+        let output_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pasture-gpu output"),
+            size: output_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging_buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("pasture-gpu staging"),
+            size: output_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let entries = std::iter::once(&params_buffer)
+            .chain(input_buffers.iter())
+            .chain(std::iter::once(&output_buffer))
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>();
+        let bind_group = self.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("pasture-gpu bind group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &entries,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("pasture-gpu encoder"),
+            });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("pasture-gpu pass"),
+            });
+            pass.set_pipeline(pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            let workgroups = (count as u32 + WORKGROUP_SIZE - 1) / WORKGROUP_SIZE;
+            pass.dispatch(workgroups, 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&output_buffer, 0, &staging_buffer, 0, output_size);
+        self.queue.submit(Some(encoder.finish()));
+
+        let slice = staging_buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(mapping)?;
+        let result = slice.get_mapped_range().to_vec();
+        staging_buffer.unmap();
+        Ok(result)
+    }
+}
+
+impl std::fmt::Debug for GpuContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GpuContext")
+            .field("batch_size", &self.batch_size)
+            .finish()
+    }
+}
+
+/// Returns the value of `kind` that `read_value` in the shaders uses for the given `datatype`
+fn shader_value_kind(datatype: PointAttributeDataType) -> Result<u32> {
+    match datatype {
+        PointAttributeDataType::U8 => Ok(0),
+        PointAttributeDataType::I8 => Ok(1),
+        PointAttributeDataType::U16 => Ok(2),
+        PointAttributeDataType::I16 => Ok(3),
+        PointAttributeDataType::U32 => Ok(4),
+        PointAttributeDataType::I32 => Ok(5),
+        PointAttributeDataType::F32 => Ok(6),
+        _ => Err(anyhow!(
+            "Datatype {} is not supported for GPU processing, only scalar 8-bit, 16-bit and 32-bit values are supported",
+            datatype
+        )),
+    }
+}
+
+fn batches(count: usize, batch_size: usize) -> impl Iterator<Item = Range<usize>> {
+    (0..count)
+        .step_by(batch_size)
+        .map(move |start| start..(start + batch_size).min(count))
+}
+
+fn read_attribute_range<T: PointBuffer + ?Sized>(
+    buffer: &T,
+    range: Range<usize>,
+    attribute: &PointAttributeDefinition,
+) -> Vec<u8> {
+    let mut data = vec![0; range.len() * attribute.size() as usize];
+    buffer.get_raw_attribute_range(range, attribute, &mut data);
+    data
+}
+
+fn f32_bytes(values: impl IntoIterator<Item = f32>) -> Vec<u8> {
+    values.into_iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn u32_bytes(values: impl IntoIterator<Item = u32>) -> Vec<u8> {
+    values.into_iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Applies the homogeneous `transform` to the `POSITION_3D` attribute of all points in `buffer` on the GPU. Since most
+/// GPUs only support single-precision floating point arithmetic, the positions of each batch are transformed relative
+/// to the first point of the batch, and the (potentially large) transformed position of this point is added on the
+/// CPU with double precision. This keeps the precision loss small as long as the points within a batch are spatially
+/// close, which is the case for most point cloud files
+///
+/// # Errors
+///
+/// If `buffer` has no `POSITION_3D` attribute with the default `Vec3f64` datatype, or if a GPU operation fails
+pub fn transform_positions<T: PointBufferWriteable + ?Sized>(
+    context: &GpuContext,
+    buffer: &mut T,
+    transform: &Matrix4<f64>,
+) -> Result<()> {
+    if buffer.point_layout().get_attribute(&POSITION_3D).is_none() {
+        bail!("Point buffer contains no POSITION_3D attribute with datatype Vec3f64");
+    }
+
+    let pipeline = context.create_pipeline("pasture-gpu transform", TRANSFORM_WGSL);
+    let transform_f32 = transform.map(|v| v as f32);
+    for batch in batches(buffer.len(), context.batch_size) {
+        let positions = read_attribute_range(&*buffer, batch.clone(), &POSITION_3D)
+            .chunks_exact(8)
+            .map(|bytes| f64::from_ne_bytes(bytes.try_into().unwrap()))
+            .collect::<Vec<_>>();
+        let origin = Vector3::new(positions[0], positions[1], positions[2]);
+        let local_positions = f32_bytes(positions.chunks_exact(3).flat_map(|p| {
+            vec![
+                (p[0] - origin.x) as f32,
+                (p[1] - origin.y) as f32,
+                (p[2] - origin.z) as f32,
+            ]
+        }));
+        // transform * (origin + local, 1) = transform * (local, 0) + transform * (origin, 1)
+        let transformed_origin = transform * Vector4::new(origin.x, origin.y, origin.z, 1.0);
+
+        let mut params = f32_bytes(transform_f32.iter().copied());
+        params.extend(u32_bytes(vec![batch.len() as u32, 0, 0, 0]));
+        let result = context.run(
+            &pipeline,
+            &params,
+            &[&local_positions],
+            batch.len() * 16,
+            batch.len(),
+        )?;
+
+        for (point_index, transformed) in batch.zip(result.chunks_exact(16)) {
+            let component = |index: usize| {
+                f32::from_le_bytes(transformed[4 * index..4 * index + 4].try_into().unwrap()) as f64
+            };
+            let homogeneous = Vector4::new(component(0), component(1), component(2), component(3))
+                + transformed_origin;
+            let position = homogeneous.xyz() / homogeneous.w;
+            buffer.set_attribute(&POSITION_3D, point_index, position);
+        }
+    }
+    Ok(())
+}
+
+/// Converts the values of `source_attribute` in `source` to `f32` on the GPU and stores `value * scale + offset` in
+/// `target_attribute` of `target`. `source` and `target` can have different `PointLayout`s, which allows converting
+/// points into a layout that is suitable for rendering (see [PointVertexBufferLayout](crate::vertex_layout::PointVertexBufferLayout))
+///
+/// # Errors
+///
+/// If `source_attribute` is not a scalar attribute with 8, 16 or 32 bits, if `target_attribute` does not have the
+/// `F32` datatype, if `source` and `target` have a different length, or if a GPU operation fails
+pub fn convert_attribute<S: PointBuffer + ?Sized, T: PointBufferWriteable + ?Sized>(
+    context: &GpuContext,
+    source: &S,
+    source_attribute: &PointAttributeDefinition,
+    target: &mut T,
+    target_attribute: &PointAttributeDefinition,
+    scale: f32,
+    offset: f32,
+) -> Result<()> {
+    let kind = shader_value_kind(source_attribute.datatype())?;
+    if target_attribute.datatype() != PointAttributeDataType::F32 {
+        bail!(
+            "Target attribute {} must have datatype F32",
+            target_attribute.name()
+        );
+    }
+    if source.len() != target.len() {
+        bail!(
+            "Source buffer has {} points but target buffer has {} points",
+            source.len(),
+            target.len()
+        );
+    }
+
+    let pipeline = context.create_pipeline(
+        "pasture-gpu convert",
+        &format!("{}{}", READ_VALUE_WGSL, CONVERT_WGSL),
+    );
+    for batch in batches(source.len(), context.batch_size) {
+        let values = read_attribute_range(source, batch.clone(), source_attribute);
+        let mut params = u32_bytes(vec![kind, batch.len() as u32]);
+        params.extend(f32_bytes(vec![scale, offset]));
+        let result = context.run(&pipeline, &params, &[&values], batch.len() * 4, batch.len())?;
+
+        for (point_index, value) in batch.zip(result.chunks_exact(4)) {
+            let value = f32::from_le_bytes(value.try_into().unwrap());
+            target.set_attribute(target_attribute, point_index, value);
+        }
+    }
+    Ok(())
+}
+
+/// Recolors all points in `buffer` on the GPU by mapping the values of `attribute` onto the color `ramp`. Values
+/// within `value_range` are linearly mapped onto the ramp, values outside of it are clamped. The resulting colors are
+/// stored in the `COLOR_RGB` attribute of `buffer`
+///
+/// ```no_run
+/// # use pasture_core::{containers::*, layout::*, nalgebra::Vector3};
+/// # use pasture_gpu::compute::*;
+/// let layout = PointLayout::from_attributes(&[attributes::INTENSITY, attributes::COLOR_RGB]);
+/// let mut buffer = InterleavedVecPointStorage::new(layout);
+/// // ... fill buffer
+/// let context = GpuContext::new().unwrap();
+/// let ramp = [Vector3::new(0, 0, 65535), Vector3::new(65535, 0, 0)];
+/// colorize_by_attribute(&context, &mut buffer, &attributes::INTENSITY, 0.0..4096.0, &ramp).unwrap();
+/// ```
+///
+/// # Errors
+///
+/// If `attribute` is not a scalar attribute with 8, 16 or 32 bits, if `buffer` has no `COLOR_RGB` attribute with the
+/// default `Vec3u16` datatype, if `ramp` is empty, or if a GPU operation fails
+pub fn colorize_by_attribute<T: PointBufferWriteable + ?Sized>(
+    context: &GpuContext,
+    buffer: &mut T,
+    attribute: &PointAttributeDefinition,
+    value_range: Range<f32>,
+    ramp: &[Vector3<u16>],
+) -> Result<()> {
+    let kind = shader_value_kind(attribute.datatype())?;
+    if buffer.point_layout().get_attribute(&COLOR_RGB).is_none() {
+        bail!("Point buffer contains no COLOR_RGB attribute with datatype Vec3u16");
+    }
+    if ramp.is_empty() {
+        bail!("Color ramp must contain at least one color");
+    }
+
+    let pipeline = context.create_pipeline(
+        "pasture-gpu colorize",
+        &format!("{}{}", READ_VALUE_WGSL, COLORIZE_WGSL),
+    );
+    let ramp_values = f32_bytes(
+        ramp.iter()
+            .flat_map(|color| vec![color.x as f32, color.y as f32, color.z as f32]),
+    );
+    for batch in batches(buffer.len(), context.batch_size) {
+        let values = read_attribute_range(&*buffer, batch.clone(), attribute);
+        let mut params = u32_bytes(vec![kind, batch.len() as u32]);
+        params.extend(f32_bytes(vec![value_range.start, value_range.end]));
+        params.extend(u32_bytes(vec![ramp.len() as u32, 0, 0, 0]));
+        let result = context.run(
+            &pipeline,
+            &params,
+            &[&values, &ramp_values],
+            batch.len() * 12,
+            batch.len(),
+        )?;
+
+        for (point_index, color) in batch.zip(result.chunks_exact(12)) {
+            let component = |index: usize| {
+                u32::from_le_bytes(color[4 * index..4 * index + 4].try_into().unwrap()) as u16
+            };
+            let color = Vector3::new(component(0), component(1), component(2));
+            buffer.set_attribute(&COLOR_RGB, point_index, color);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasture_core::{
+        containers::{InterleavedVecPointStorage, PointBufferExt},
+        layout::{attributes::INTENSITY, PointLayout},
+    };
+
+    /// Tests need a GPU, so they are skipped on systems without a suitable adapter. The small batch size makes sure
+    /// that the test buffers are split into multiple batches
+    fn get_context() -> Option<GpuContext> {
+        GpuContext::new()
+            .ok()
+            .map(|context| context.with_batch_size(32))
+    }
+
+    fn test_buffer() -> InterleavedVecPointStorage {
+        let layout = PointLayout::from_attributes(&[POSITION_3D, INTENSITY, COLOR_RGB]);
+        let mut buffer = InterleavedVecPointStorage::new(layout);
+        buffer.resize(100);
+        for index in 0..100 {
+            let position = Vector3::new(
+                350000.0 + index as f64,
+                5600000.0 - index as f64 * 0.5,
+                index as f64 * 0.25,
+            );
+            buffer.set_attribute(&POSITION_3D, index, position);
+            buffer.set_attribute(&INTENSITY, index, (index * 10) as u16);
+        }
+        buffer
+    }
+
+    #[test]
+    fn test_transform_positions() -> Result<()> {
+        let context = match get_context() {
+            Some(context) => context,
+            None => return Ok(()),
+        };
+        let mut buffer = test_buffer();
+        let expected = buffer
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .map(|p| Vector3::new(-p.y, p.x, p.z) + Vector3::new(10.0, 20.0, 30.0))
+            .collect::<Vec<_>>();
+
+        let transform = Matrix4::new(
+            0.0, -1.0, 0.0, 10.0, 1.0, 0.0, 0.0, 20.0, 0.0, 0.0, 1.0, 30.0, 0.0, 0.0, 0.0, 1.0,
+        );
+        transform_positions(&context, &mut buffer, &transform)?;
+
+        for (expected, actual) in expected
+            .iter()
+            .zip(buffer.iter_attribute::<Vector3<f64>>(&POSITION_3D))
+        {
+            assert!((expected - actual).norm() < 1e-3);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_and_colorize() -> Result<()> {
+        let context = match get_context() {
+            Some(context) => context,
+            None => return Ok(()),
+        };
+        let mut buffer = test_buffer();
+        let normalized_intensity =
+            PointAttributeDefinition::custom("NormalizedIntensity", PointAttributeDataType::F32);
+        let mut target =
+            InterleavedVecPointStorage::new(PointLayout::from_attributes(&[normalized_intensity]));
+        target.resize(buffer.len());
+        convert_attribute(
+            &context,
+            &buffer,
+            &INTENSITY,
+            &mut target,
+            &normalized_intensity,
+            1.0 / 990.0,
+            0.0,
+        )?;
+        assert_eq!(0.0, target.get_attribute::<f32>(&normalized_intensity, 0));
+        assert!((target.get_attribute::<f32>(&normalized_intensity, 99) - 1.0).abs() < 1e-6);
+
+        let ramp = [Vector3::new(0, 0, 0), Vector3::new(1000, 2000, 3000)];
+        colorize_by_attribute(&context, &mut buffer, &INTENSITY, 0.0..500.0, &ramp)?;
+        assert_eq!(
+            Vector3::new(0, 0, 0),
+            buffer.get_attribute::<Vector3<u16>>(&COLOR_RGB, 0)
+        );
+        assert_eq!(
+            Vector3::new(500, 1000, 1500),
+            buffer.get_attribute::<Vector3<u16>>(&COLOR_RGB, 25)
+        );
+        assert_eq!(
+            Vector3::new(1000, 2000, 3000),
+            buffer.get_attribute::<Vector3<u16>>(&COLOR_RGB, 99)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,12 @@
+#![warn(clippy::all)]
+//! GPU support for pasture.
+//!
+//! Helpers for using pasture point buffers with [wgpu](https://crates.io/crates/wgpu), such as deriving vertex buffer
+//! layouts from a `PointLayout`, uploading interleaved point buffers into GPU buffers and running bulk operations on
+//! point data on the GPU.
+
+// Conversion of PointLayouts into wgpu vertex buffer layouts and upload of interleaved buffers into vertex buffers.
+pub mod vertex_layout;
+// GPU compute execution path for embarrassingly parallel bulk operations such as transforms, datatype conversions
+// and recoloring.
+pub mod compute;
\ No newline at end of file
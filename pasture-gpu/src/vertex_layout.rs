@@ -0,0 +1,298 @@
+use anyhow::{anyhow, bail, Result};
+use pasture_core::{
+    containers::{InterleavedPointBuffer, InterleavedVecPointStorage, LocalFrame, PointBuffer},
+    layout::{
+        attributes::POSITION_3D, PointAttributeDataType, PointAttributeDefinition, PointLayout,
+    },
+};
+use wgpu::util::DeviceExt;
+
+/// Returns the `wgpu::VertexFormat` that matches the memory layout of the given `datatype`. Returns an error if
+/// there is no matching vertex format, which is the case for scalar 8-bit and 16-bit types, 3-component vectors of
+/// 8-bit and 16-bit types, 64-bit integers and `bool`. Attributes with these datatypes have to be converted (e.g.
+/// into `U32` or `F32`) before they can be used as vertex attributes
+///
+/// ```
+/// # use pasture_core::layout::PointAttributeDataType;
+/// # use pasture_gpu::vertex_layout::vertex_format_for_datatype;
+/// assert_eq!(
+///     wgpu::VertexFormat::Float32x3,
+///     vertex_format_for_datatype(PointAttributeDataType::Vec3f32).unwrap()
+/// );
+/// assert!(vertex_format_for_datatype(PointAttributeDataType::U16).is_err());
+/// ```
+pub fn vertex_format_for_datatype(datatype: PointAttributeDataType) -> Result<wgpu::VertexFormat> {
+    match datatype {
+        PointAttributeDataType::U32 => Ok(wgpu::VertexFormat::Uint32),
+        PointAttributeDataType::I32 => Ok(wgpu::VertexFormat::Sint32),
+        PointAttributeDataType::F32 => Ok(wgpu::VertexFormat::Float32),
+        PointAttributeDataType::F64 => Ok(wgpu::VertexFormat::Float64),
+        PointAttributeDataType::Vec3f32 => Ok(wgpu::VertexFormat::Float32x3),
+        PointAttributeDataType::Vec3f64 => Ok(wgpu::VertexFormat::Float64x3),
+        PointAttributeDataType::Vec4u8 => Ok(wgpu::VertexFormat::Uint8x4),
+        _ => Err(anyhow!(
+            "There is no vertex format for datatype {}",
+            datatype
+        )),
+    }
+}
+
+/// Vertex buffer layout for interleaved point buffers, derived from a `PointLayout`. This is the owned counterpart to
+/// `wgpu::VertexBufferLayout`, which borrows its attributes, so use [vertex_buffer_layout](Self::vertex_buffer_layout)
+/// to obtain the layout for a render pipeline
+///
+/// ```
+/// # use pasture_core::layout::*;
+/// # use pasture_gpu::vertex_layout::PointVertexBufferLayout;
+/// let position = attributes::POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32);
+/// let layout = PointLayout::from_attributes(&[position.clone(), attributes::NORMAL]);
+/// let vertex_layout = PointVertexBufferLayout::from_point_layout(&layout, &[position, attributes::NORMAL]).unwrap();
+/// assert_eq!(24, vertex_layout.array_stride());
+///
+/// let buffer_layout = vertex_layout.vertex_buffer_layout(wgpu::VertexStepMode::Vertex);
+/// assert_eq!(2, buffer_layout.attributes.len());
+/// assert_eq!(12, buffer_layout.attributes[1].offset);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointVertexBufferLayout {
+    array_stride: wgpu::BufferAddress,
+    attributes: Vec<wgpu::VertexAttribute>,
+}
+
+impl PointVertexBufferLayout {
+    /// Creates a vertex buffer layout for the given `attributes` of the interleaved `layout`. The shader location of
+    /// each attribute is its index within `attributes`, attributes of `layout` that are not part of `attributes` are
+    /// skipped. Returns an error if any attribute is not part of `layout` (with the same datatype), if there is no
+    /// vertex format for the datatype of an attribute, or if the offsets or the stride violate the alignment rules of
+    /// wgpu. Since `PointLayout`s for file formats such as LAS are usually not aligned, it might be necessary to read
+    /// points into a buffer with a custom layout (e.g. one created with `PointLayout::from_attributes`) first
+    pub fn from_point_layout(
+        layout: &PointLayout,
+        attributes: &[PointAttributeDefinition],
+    ) -> Result<Self> {
+        let array_stride = layout.size_of_point_entry();
+        if array_stride % wgpu::VERTEX_STRIDE_ALIGNMENT != 0 {
+            bail!(
+                "Size of a point in the PointLayout ({} bytes) is not a multiple of {} bytes",
+                array_stride,
+                wgpu::VERTEX_STRIDE_ALIGNMENT
+            );
+        }
+
+        let vertex_attributes = attributes
+            .iter()
+            .enumerate()
+            .map(|(shader_location, attribute)| {
+                let member = layout.get_attribute(attribute).ok_or_else(|| {
+                    anyhow!(
+                        "Attribute {} with datatype {} is not part of the PointLayout",
+                        attribute.name(),
+                        attribute.datatype()
+                    )
+                })?;
+                let format = vertex_format_for_datatype(attribute.datatype())?;
+                // WebGPU requires attribute offsets to be a multiple of min(4, size of the format)
+                let required_alignment = format.size().min(4);
+                if member.offset() % required_alignment != 0 {
+                    bail!(
+                        "Offset {} of attribute {} is not a multiple of {} bytes",
+                        member.offset(),
+                        attribute.name(),
+                        required_alignment
+                    );
+                }
+                Ok(wgpu::VertexAttribute {
+                    format,
+                    offset: member.offset(),
+                    shader_location: shader_location as u32,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            array_stride,
+            attributes: vertex_attributes,
+        })
+    }
+
+    /// Creates a vertex buffer layout for all attributes of `layout`, in the order in which they appear in `layout`.
+    /// See [from_point_layout](Self::from_point_layout) for the possible errors
+    pub fn from_point_layout_all(layout: &PointLayout) -> Result<Self> {
+        let attributes = layout
+            .attributes()
+            .map(|attribute| attribute.into())
+            .collect::<Vec<PointAttributeDefinition>>();
+        Self::from_point_layout(layout, &attributes)
+    }
+
+    /// The stride between two consecutive points in bytes
+    pub fn array_stride(&self) -> wgpu::BufferAddress {
+        self.array_stride
+    }
+
+    /// The vertex attributes of this layout
+    pub fn attributes(&self) -> &[wgpu::VertexAttribute] {
+        &self.attributes
+    }
+
+    /// Returns the device features that are required for this layout. Attributes with 64-bit floating point formats
+    /// (e.g. `POSITION_3D` with its default `Vec3f64` datatype) require `wgpu::Features::VERTEX_ATTRIBUTE_64BIT`
+    pub fn required_features(&self) -> wgpu::Features {
+        let uses_64bit_attributes = self.attributes.iter().any(|attribute| {
+            matches!(
+                attribute.format,
+                wgpu::VertexFormat::Float64
+                    | wgpu::VertexFormat::Float64x2
+                    | wgpu::VertexFormat::Float64x3
+                    | wgpu::VertexFormat::Float64x4
+            )
+        });
+        if uses_64bit_attributes {
+            wgpu::Features::VERTEX_ATTRIBUTE_64BIT
+        } else {
+            wgpu::Features::empty()
+        }
+    }
+
+    /// Returns the `wgpu::VertexBufferLayout` for use in a `wgpu::VertexState`
+    pub fn vertex_buffer_layout(
+        &self,
+        step_mode: wgpu::VertexStepMode,
+    ) -> wgpu::VertexBufferLayout<'_> {
+        wgpu::VertexBufferLayout {
+            array_stride: self.array_stride,
+            step_mode,
+            attributes: &self.attributes,
+        }
+    }
+}
+
+/// Creates a vertex buffer on `device` and uploads all points of the interleaved `points` buffer into it. The
+/// resulting buffer can be used together with a [PointVertexBufferLayout] that was created from the `PointLayout`
+/// of `points`. `usage` is added to `wgpu::BufferUsages::VERTEX`, e.g. to allow `wgpu::BufferUsages::COPY_DST` for
+/// later updates through [write_points_to_buffer]
+pub fn create_vertex_buffer<T: InterleavedPointBuffer + ?Sized>(
+    device: &wgpu::Device,
+    points: &T,
+    usage: wgpu::BufferUsages,
+    label: Option<&str>,
+) -> wgpu::Buffer {
+    device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label,
+        contents: points.get_raw_points_ref(0..points.len()),
+        usage: wgpu::BufferUsages::VERTEX | usage,
+    })
+}
+
+/// Creates a vertex buffer on `device` that contains the given `attributes` of all points in `points`, with the
+/// positions in the given `local_frame`. Most GPUs don't support 64-bit vertex attributes, and converting positions
+/// in world space (e.g. UTM coordinates) directly to 32-bit floats loses most of their precision, so the
+/// `POSITION_3D` attribute is stored as `Vec3f32` relative to the origin of `local_frame` instead. The origin then
+/// has to be added back in the shaders, typically as part of the model matrix. Returns the vertex buffer together with
+/// its layout, in which the shader location of each attribute is its index within `attributes`. Returns an error if
+/// there is no vertex format for the datatype of an attribute or if the resulting layout violates the alignment rules
+/// of wgpu (see [PointVertexBufferLayout::from_point_layout])
+///
+/// # Panics
+///
+/// If `points` doesn't contain a `POSITION_3D` attribute or any of the `attributes`
+pub fn create_vertex_buffer_in_local_frame<T: PointBuffer + ?Sized>(
+    device: &wgpu::Device,
+    points: &T,
+    attributes: &[PointAttributeDefinition],
+    local_frame: &LocalFrame,
+    usage: wgpu::BufferUsages,
+    label: Option<&str>,
+) -> Result<(wgpu::Buffer, PointVertexBufferLayout)> {
+    let attributes = attributes
+        .iter()
+        .map(|attribute| {
+            if attribute.name() == POSITION_3D.name() {
+                LocalFrame::local_position_attribute()
+            } else {
+                attribute.clone()
+            }
+        })
+        .collect::<Vec<_>>();
+    let layout = PointLayout::from_attributes(&attributes);
+    let vertex_layout = PointVertexBufferLayout::from_point_layout(&layout, &attributes)?;
+
+    let mut local_points = InterleavedVecPointStorage::with_capacity(points.len(), layout);
+    local_frame.append_local(points, &mut local_points);
+    let buffer = create_vertex_buffer(device, &local_points, usage, label);
+    Ok((buffer, vertex_layout))
+}
+
+/// Writes all points of the interleaved `points` buffer into `buffer`, starting at the point with index
+/// `first_point_index` within `buffer`. `buffer` must have been created with `wgpu::BufferUsages::COPY_DST`. Returns
+/// an error if the points don't fit into `buffer`
+pub fn write_points_to_buffer<T: InterleavedPointBuffer + ?Sized>(
+    queue: &wgpu::Queue,
+    buffer: &wgpu::Buffer,
+    first_point_index: usize,
+    points: &T,
+) -> Result<()> {
+    let size_of_point = points.point_layout().size_of_point_entry();
+    let offset = first_point_index as u64 * size_of_point;
+    let data = points.get_raw_points_ref(0..points.len());
+    if offset + data.len() as u64 > buffer.size() {
+        bail!(
+            "Writing {} points at point index {} exceeds the size of the GPU buffer ({} bytes)",
+            points.len(),
+            first_point_index,
+            buffer.size()
+        );
+    }
+    queue.write_buffer(buffer, offset, data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasture_core::layout::attributes::{CLASSIFICATION, INTENSITY, NORMAL, POSITION_3D};
+
+    #[test]
+    fn test_vertex_layout_with_f64_positions() -> Result<()> {
+        let layout = PointLayout::from_attributes(&[POSITION_3D, NORMAL]);
+        let vertex_layout = PointVertexBufferLayout::from_point_layout_all(&layout)?;
+        assert_eq!(layout.size_of_point_entry(), vertex_layout.array_stride());
+        assert_eq!(
+            vec![
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float64x3,
+                    offset: 0,
+                    shader_location: 0,
+                },
+                wgpu::VertexAttribute {
+                    format: wgpu::VertexFormat::Float32x3,
+                    offset: 24,
+                    shader_location: 1,
+                },
+            ],
+            vertex_layout.attributes()
+        );
+        assert_eq!(
+            wgpu::Features::VERTEX_ATTRIBUTE_64BIT,
+            vertex_layout.required_features()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_vertex_layout_errors() {
+        // No vertex format for u16
+        let layout = PointLayout::from_attributes(&[NORMAL, INTENSITY]);
+        assert!(PointVertexBufferLayout::from_point_layout(&layout, &[INTENSITY]).is_err());
+        assert!(PointVertexBufferLayout::from_point_layout(&layout, &[POSITION_3D]).is_err());
+
+        // Packed layout with a stride of 13 bytes
+        let layout = PointLayout::from_attributes_packed(&[NORMAL, CLASSIFICATION], 1);
+        assert!(PointVertexBufferLayout::from_point_layout(&layout, &[NORMAL]).is_err());
+
+        // Misaligned offset of the normal attribute
+        let color = PointAttributeDefinition::custom("Color", PointAttributeDataType::Vec3u8);
+        let layout = PointLayout::from_attributes_packed(&[CLASSIFICATION, NORMAL, color], 1);
+        assert!(PointVertexBufferLayout::from_point_layout(&layout, &[NORMAL]).is_err());
+    }
+}
@@ -1,17 +1,41 @@
-use anyhow::Result;
+use pasture_core::containers::InterleavedVecPointStorage;
+use pasture_core::math::AABB;
 use pasture_core::meta::Metadata;
-use pasture_core::{containers::PointBufferWriteable, layout::PointLayout};
+use pasture_core::{
+    containers::PointBufferWriteable,
+    layout::{attributes::POSITION_3D, PointLayout},
+};
 use std::fs::File;
 use std::io::{BufRead, BufReader, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use crate::ascii::RawAsciiReader;
-use crate::base::PointReader;
+use crate::base::{
+    bounds_of_positions, Estimate, EstimateConfidence, EstimateOptions, PointReader, Result,
+};
+
+/// Number of points that are read at once while sampling an ascii file
+const SAMPLE_CHUNK_SIZE: usize = 4096;
+
+/// Source file of an `AsciiReader` that was created with `AsciiReader::from_path`. Used to sample the file
+/// through a separate file handle when estimating the point count and bounds
+struct AsciiSource {
+    path: PathBuf,
+    format: String,
+    delimiter: String,
+}
+
+/// Result of sampling the leading part of an ascii file
+struct AsciiSample {
+    point_count: Estimate<usize>,
+    bounds: Option<Estimate<AABB<f64>>>,
+}
 
 /// `PointReader` implementation for ascii files
 
 pub struct AsciiReader<'a> {
     raw_reader: Box<dyn PointReader + 'a>,
+    source: Option<AsciiSource>,
 }
 impl<'a> AsciiReader<'a> {
     /// Creates a new `AsciiReader` by opening the file at the given `path`.
@@ -38,8 +62,14 @@ impl<'a> AsciiReader<'a> {
     ///
     /// If `format` contains unrecoginzed literals, an error is returned.
     pub fn from_path<P: AsRef<Path>>(path: P, format: &str, delimiter: &str) -> Result<Self> {
-        let file = BufReader::new(File::open(path)?);
-        Self::from_read(file, format, delimiter)
+        let file = BufReader::new(File::open(path.as_ref())?);
+        let mut reader = Self::from_read(file, format, delimiter)?;
+        reader.source = Some(AsciiSource {
+            path: path.as_ref().to_owned(),
+            format: format.to_string(),
+            delimiter: delimiter.to_string(),
+        });
+        Ok(reader)
     }
 
     /// Creates a new `AsciiReader` from the given `read`.
@@ -97,6 +127,7 @@ impl<'a> AsciiReader<'a> {
             Box::new(RawAsciiReader::from_read(read, format, delimiter)?);
         Ok(Self {
             raw_reader: raw_reader,
+            source: None,
         })
     }
 
@@ -123,6 +154,70 @@ impl<'a> AsciiReader<'a> {
             a - scan angle rank"
         );
     }
+
+    /// Samples the leading `options.sample_fraction` of the bytes of the file that this reader was opened from, but at
+    /// most `options.max_sample_count` points. Uses a separate file handle, so this reader is not advanced. Returns
+    /// `None` if the reader was not created from a file
+    fn sample_source(&self, options: &EstimateOptions) -> Result<Option<AsciiSample>> {
+        let source = match &self.source {
+            Some(source) => source,
+            None => return Ok(None),
+        };
+        let file_size = std::fs::metadata(&source.path)?.len();
+        let sample_size = (file_size as f64 * options.sample_fraction).ceil() as u64;
+        let mut reader = RawAsciiReader::from_read(
+            BufReader::new(File::open(&source.path)?),
+            &source.format,
+            &source.delimiter,
+        )?;
+        let has_positions = reader
+            .get_default_point_layout()
+            .has_attribute_with_name(POSITION_3D.name());
+
+        let mut sampled_points = 0;
+        let mut bounds: Option<AABB<f64>> = None;
+        let mut reached_end = false;
+        while reader.bytes_read() < sample_size && sampled_points < options.max_sample_count {
+            let chunk_size = SAMPLE_CHUNK_SIZE.min(options.max_sample_count - sampled_points);
+            let mut chunk = InterleavedVecPointStorage::with_capacity(
+                chunk_size,
+                reader.get_default_point_layout().clone(),
+            );
+            let points_in_chunk = reader.read_into(&mut chunk, chunk_size)?;
+            sampled_points += points_in_chunk;
+            if has_positions {
+                if let Some(chunk_bounds) = bounds_of_positions(&chunk) {
+                    bounds = Some(match bounds {
+                        Some(bounds) => AABB::union(&bounds, &chunk_bounds),
+                        None => chunk_bounds,
+                    });
+                }
+            }
+            if points_in_chunk < chunk_size {
+                reached_end = true;
+                break;
+            }
+        }
+        // The sample might end exactly at the end of the file
+        if !reached_end && reader.bytes_read() >= file_size {
+            reached_end = true;
+        }
+
+        let confidence = if reached_end {
+            EstimateConfidence::Exact
+        } else {
+            EstimateConfidence::Sampled
+        };
+        let point_count = if reached_end || reader.bytes_read() == 0 {
+            sampled_points
+        } else {
+            (sampled_points as f64 * file_size as f64 / reader.bytes_read() as f64).round() as usize
+        };
+        Ok(Some(AsciiSample {
+            point_count: Estimate::new(point_count, confidence),
+            bounds: bounds.map(|bounds| Estimate::new(bounds, confidence)),
+        }))
+    }
 }
 
 impl<'a> PointReader for AsciiReader<'a> {
@@ -145,4 +240,102 @@ impl<'a> PointReader for AsciiReader<'a> {
     fn get_default_point_layout(&self) -> &PointLayout {
         self.raw_reader.get_default_point_layout()
     }
+
+    /// Ascii files have no header, so if this reader was created with `AsciiReader::from_path`, the number of points
+    /// is extrapolated from the number of bytes that the leading points occupy in the file. The reader itself is not
+    /// advanced. Returns `None` for readers created with `AsciiReader::from_read`
+    fn estimate_point_count(
+        &mut self,
+        options: &EstimateOptions,
+    ) -> Result<Option<Estimate<usize>>> {
+        Ok(self
+            .sample_source(options)?
+            .map(|sample| sample.point_count))
+    }
+
+    /// Ascii files have no header, so if this reader was created with `AsciiReader::from_path`, the bounds of the
+    /// leading points in the file are returned. The reader itself is not advanced. For readers created with
+    /// `AsciiReader::from_read`, this falls back to sampling the next points of this reader, which advances it
+    fn estimate_bounds(
+        &mut self,
+        options: &EstimateOptions,
+    ) -> Result<Option<Estimate<AABB<f64>>>> {
+        match self.sample_source(options)? {
+            Some(sample) => Ok(sample.bounds),
+            None => self.raw_reader.estimate_bounds(options),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii::{get_test_file_path, test_data_positions};
+    use pasture_core::nalgebra::Point3;
+
+    #[test]
+    fn test_estimate_from_full_sample() -> Result<()> {
+        let path = get_test_file_path("10_points_ascii_all_attributes.txt");
+        let mut reader = AsciiReader::from_path(path, "xyzirncuRGBtpedaI", ", ")?;
+        let options = EstimateOptions {
+            sample_fraction: 1.0,
+            max_sample_count: 100,
+        };
+
+        let count = reader
+            .estimate_point_count(&options)?
+            .expect("Point count estimate is missing");
+        assert_eq!(Estimate::new(10, EstimateConfidence::Exact), count);
+
+        let bounds = reader
+            .estimate_bounds(&options)?
+            .expect("Bounds estimate is missing");
+        assert_eq!(EstimateConfidence::Exact, bounds.confidence);
+        let positions = test_data_positions();
+        assert_eq!(Point3::from(positions[0]), *bounds.value.min());
+        assert_eq!(Point3::from(positions[9]), *bounds.value.max());
+
+        // Estimating must not advance the reader
+        assert_eq!(10, reader.read(20)?.len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_estimate_from_partial_sample() -> Result<()> {
+        let path = get_test_file_path("10_points_ascii_all_attributes.txt");
+        let mut reader = AsciiReader::from_path(path, "xyzirncuRGBtpedaI", ", ")?;
+        let options = EstimateOptions {
+            sample_fraction: 1.0,
+            max_sample_count: 3,
+        };
+
+        let count = reader
+            .estimate_point_count(&options)?
+            .expect("Point count estimate is missing");
+        assert_eq!(EstimateConfidence::Sampled, count.confidence);
+        assert!(!count.is_exact());
+        // All lines in the test file have roughly the same length
+        assert!(
+            count.value >= 8 && count.value <= 12,
+            "Estimated point count {} is too far off",
+            count.value
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_estimate_from_read() -> Result<()> {
+        let data = "0.0, 1.0, 2.0\n-1.0, 4.0, 0.5\n".as_bytes();
+        let mut reader = AsciiReader::from_read(BufReader::new(data), "xyz", ", ")?;
+        let options = EstimateOptions::default();
+
+        assert_eq!(None, reader.estimate_point_count(&options)?);
+        let bounds = reader
+            .estimate_bounds(&options)?
+            .expect("Bounds estimate is missing");
+        assert_eq!(EstimateConfidence::Sampled, bounds.confidence);
+        assert_eq!(Point3::new(-1.0, 1.0, 0.5), *bounds.value.min());
+        assert_eq!(Point3::new(0.0, 4.0, 2.0), *bounds.value.max());
+        Ok(())
+    }
 }
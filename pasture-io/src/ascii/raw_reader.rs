@@ -1,4 +1,3 @@
-use anyhow::{bail, Context, Result};
 use byteorder::{LittleEndian, WriteBytesExt};
 use itertools::{EitherOrBoth::*, Itertools};
 use pasture_core::layout::attributes;
@@ -14,7 +13,7 @@ use std::iter::FromIterator;
 use std::str::FromStr;
 
 use super::AsciiMetadata;
-use crate::base::PointReader;
+use crate::base::{PastureIoError, PointReader, Result};
 use pasture_core::containers::{UntypedPoint, UntypedPointBuffer};
 
 pub(crate) struct RawAsciiReader<T: Read + BufRead> {
@@ -23,6 +22,7 @@ pub(crate) struct RawAsciiReader<T: Read + BufRead> {
     delimiter: String,
     point_layout: PointLayout,
     parse_layout: Vec<PointDataTypes>,
+    bytes_read: u64,
 }
 
 impl<T: Read + BufRead> RawAsciiReader<T> {
@@ -37,9 +37,15 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
             delimiter: delimiter.to_string(),
             point_layout: layout,
             parse_layout: parse_layout,
+            bytes_read: 0,
         })
     }
 
+    /// Returns the number of bytes that were consumed from the underlying `Read` so far
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read
+    }
+
     fn get_point_layout_from_parse_layout(parse_layout: &[PointDataTypes]) -> PointLayout {
         let hashset = parse_layout
             .iter()
@@ -79,7 +85,7 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
                 Both(value_str, data_type) => match data_type {
                     PointDataTypes::CoordinateX => {
                         Self::parse_to_point_f64(point, &attributes::POSITION_3D, 0, value_str)
-                            .with_context(|| generate_parse_error(data_type, 'x'))?;
+                            .map_err(|e| generate_parse_error(e, data_type, 'x'))?;
                     }
                     PointDataTypes::CoordinateY => {
                         Self::parse_to_point_f64(
@@ -88,7 +94,7 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
                             std::mem::size_of::<f64>() as u64,
                             value_str,
                         )
-                        .with_context(|| generate_parse_error(data_type, 'y'))?;
+                        .map_err(|e| generate_parse_error(e, data_type, 'y'))?;
                     }
                     PointDataTypes::CoordinateZ => {
                         Self::parse_to_point_f64(
@@ -97,15 +103,15 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
                             2 * std::mem::size_of::<f64>() as u64,
                             value_str,
                         )
-                        .with_context(|| generate_parse_error(data_type, 'z'))?;
+                        .map_err(|e| generate_parse_error(e, data_type, 'z'))?;
                     }
                     PointDataTypes::Intensity => {
                         Self::parse_to_point_u16(point, &attributes::INTENSITY, 0, value_str)
-                            .with_context(|| generate_parse_error(data_type, 'i'))?;
+                            .map_err(|e| generate_parse_error(e, data_type, 'i'))?;
                     }
                     PointDataTypes::ReturnNumber => {
                         Self::parse_to_point_u8(point, &attributes::RETURN_NUMBER, 0, value_str)
-                            .with_context(|| generate_parse_error(data_type, 'r'))?;
+                            .map_err(|e| generate_parse_error(e, data_type, 'r'))?;
                     }
                     PointDataTypes::NumberOfReturns => {
                         Self::parse_to_point_u8(
@@ -114,19 +120,19 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
                             0,
                             value_str,
                         )
-                        .with_context(|| generate_parse_error(data_type, 'n'))?;
+                        .map_err(|e| generate_parse_error(e, data_type, 'n'))?;
                     }
                     PointDataTypes::Classification => {
                         Self::parse_to_point_u8(point, &attributes::CLASSIFICATION, 0, value_str)
-                            .with_context(|| generate_parse_error(data_type, 'c'))?;
+                            .map_err(|e| generate_parse_error(e, data_type, 'c'))?;
                     }
                     PointDataTypes::UserData => {
                         Self::parse_to_point_u8(point, &attributes::USER_DATA, 0, value_str)
-                            .with_context(|| generate_parse_error(data_type, 'u'))?;
+                            .map_err(|e| generate_parse_error(e, data_type, 'u'))?;
                     }
                     PointDataTypes::ColorR => {
                         Self::parse_to_point_u16(point, &attributes::COLOR_RGB, 0, value_str)
-                            .with_context(|| generate_parse_error(data_type, 'R'))?;
+                            .map_err(|e| generate_parse_error(e, data_type, 'R'))?;
                     }
                     PointDataTypes::ColorG => {
                         Self::parse_to_point_u16(
@@ -135,7 +141,7 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
                             std::mem::size_of::<u16>() as u64,
                             value_str,
                         )
-                        .with_context(|| generate_parse_error(data_type, 'G'))?;
+                        .map_err(|e| generate_parse_error(e, data_type, 'G'))?;
                     }
                     PointDataTypes::ColorB => {
                         Self::parse_to_point_u16(
@@ -144,15 +150,15 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
                             2 * std::mem::size_of::<u16>() as u64,
                             value_str,
                         )
-                        .with_context(|| generate_parse_error(data_type, 'B'))?;
+                        .map_err(|e| generate_parse_error(e, data_type, 'B'))?;
                     }
                     PointDataTypes::GpsTime => {
                         Self::parse_to_point_f64(point, &attributes::GPS_TIME, 0, value_str)
-                            .with_context(|| generate_parse_error(data_type, 't'))?;
+                            .map_err(|e| generate_parse_error(e, data_type, 't'))?;
                     }
                     PointDataTypes::PointSourceID => {
                         Self::parse_to_point_u16(point, &attributes::POINT_SOURCE_ID, 0, value_str)
-                            .with_context(|| generate_parse_error(data_type, 'p'))?;
+                            .map_err(|e| generate_parse_error(e, data_type, 'p'))?;
                     }
                     PointDataTypes::EdgeOfFlightLine => {
                         Self::parse_to_point_bool(
@@ -161,7 +167,7 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
                             0,
                             value_str,
                         )
-                        .with_context(|| generate_parse_error(data_type, 'e'))?;
+                        .map_err(|e| generate_parse_error(e, data_type, 'e'))?;
                     }
                     PointDataTypes::ScanDirectionFlag => {
                         Self::parse_to_point_bool(
@@ -170,21 +176,24 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
                             0,
                             value_str,
                         )
-                        .with_context(|| generate_parse_error(data_type, 'd'))?;
+                        .map_err(|e| generate_parse_error(e, data_type, 'd'))?;
                     }
                     PointDataTypes::ScanAngleRank => {
                         Self::parse_to_point_i8(point, &attributes::SCAN_ANGLE_RANK, 0, value_str)
-                            .with_context(|| generate_parse_error(data_type, 'a'))?;
+                            .map_err(|e| generate_parse_error(e, data_type, 'a'))?;
                     }
                     PointDataTypes::NIR => {
                         Self::parse_to_point_u16(point, &attributes::NIR, 0, value_str)
-                            .with_context(|| generate_parse_error(data_type, 'I'))?;
+                            .map_err(|e| generate_parse_error(e, data_type, 'I'))?;
                     }
                     PointDataTypes::Skip => {}
                 },
                 Left(_) => continue,
                 Right(_) => {
-                    bail!("Input format string expected more items in the line. Found End-of-Line.")
+                    return Err(PastureIoError::InvalidData(
+                        "Input format string expected more items in the line. Found End-of-Line."
+                            .into(),
+                    ))
                 }
             }
         }
@@ -218,7 +227,10 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
     ) -> Result<()> {
         let data = Self::parse_string::<u8>(value_str)?;
         if !(data == 0 || data == 1) {
-            bail!("ParseError expected bool found '{}'.", data);
+            return Err(PastureIoError::InvalidData(format!(
+                "ParseError expected bool found '{}'.",
+                data
+            )));
         }
         let attribute_offset = point.get_layout().offset_of(attribute);
         match attribute_offset {
@@ -289,13 +301,13 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
         Ok(())
     }
 
-    fn parse_string<V: FromStr>(value_str: &str) -> Result<V, anyhow::Error> {
+    fn parse_string<V: FromStr>(value_str: &str) -> Result<V> {
         value_str.parse::<V>().map_err(|_| {
-            anyhow::anyhow!(
+            PastureIoError::InvalidData(format!(
                 "ParseError expected {} found '{}'.",
                 std::any::type_name::<V>(),
                 value_str
-            )
+            ))
         })
     }
 
@@ -341,11 +353,10 @@ impl<T: Read + BufRead> RawAsciiReader<T> {
                 'e' => parse_layout.push(PointDataTypes::EdgeOfFlightLine),
                 'd' => parse_layout.push(PointDataTypes::ScanDirectionFlag),
                 _ => {
-                    bail!(
+                    return Err(PastureIoError::InvalidArgument(format!(
                         "FormatError can't interpret format literal '{}' in format string '{}'.",
-                        character,
-                        format
-                    );
+                        character, format
+                    )));
                 }
             }
         }
@@ -381,12 +392,17 @@ impl std::fmt::Display for PointDataTypes {
         write!(f, "{:?}", self)
     }
 }
-fn generate_parse_error(datatype: &PointDataTypes, character: char) -> String {
-    format!(
-        "ParseError at parsing {} for format literal '{}'.",
+fn generate_parse_error(
+    error: PastureIoError,
+    datatype: &PointDataTypes,
+    character: char,
+) -> PastureIoError {
+    PastureIoError::InvalidData(format!(
+        "ParseError at parsing {} for format literal '{}'. {}",
         datatype.to_string(),
-        character
-    )
+        character,
+        error
+    ))
 }
 
 impl<T: Read + BufRead> PointReader for RawAsciiReader<T> {
@@ -403,16 +419,29 @@ impl<T: Read + BufRead> PointReader for RawAsciiReader<T> {
     ) -> Result<usize> {
         let layout = point_buffer.point_layout().clone();
         let mut temp_point = UntypedPointBuffer::new(&layout);
+        let mut line = String::new();
+        let mut points_read = 0;
         //read line by line
-        for (index, line) in (&mut self.reader).lines().take(count).enumerate() {
-            let line = line?;
+        while points_read < count {
+            line.clear();
+            let line_length = self.reader.read_line(&mut line)?;
+            if line_length == 0 {
+                break;
+            }
+            self.bytes_read += line_length as u64;
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+            let line = line.strip_suffix('\r').unwrap_or(line);
             //parse the line in an untypedpoint
-            Self::parse_point(&mut temp_point, &line, &self.delimiter, &self.parse_layout)
-                .with_context(|| format!("ReadError in line {}.", index))?;
+            Self::parse_point(&mut temp_point, line, &self.delimiter, &self.parse_layout).map_err(
+                |e| {
+                    PastureIoError::InvalidData(format!("ReadError in line {}. {}", points_read, e))
+                },
+            )?;
             //put it in the buffer
             point_buffer.push(&temp_point.get_interleaved_point_view());
+            points_read += 1;
         }
-        Ok(count)
+        Ok(points_read)
     }
     fn get_default_point_layout(&self) -> &PointLayout {
         &self.point_layout
@@ -443,7 +472,6 @@ mod tests {
         test_data_return_numbers, test_data_scan_angle_ranks, test_data_scan_direction_flags,
         test_data_user_data,
     };
-    use anyhow::Result;
     use pasture_core::containers::PointBufferExt;
     use pasture_core::layout::{attributes, PointType};
     use pasture_core::nalgebra::Vector3;
@@ -458,7 +486,7 @@ mod tests {
         let buffer = ascii_reader.read(10)?;
         let interleaved_buffer = buffer
             .as_interleaved()
-            .ok_or_else(|| anyhow::anyhow!("DowncastError"))?;
+            .ok_or_else(|| PastureIoError::Other("DowncastError".into()))?;
 
         let positions = interleaved_buffer
             .iter_attribute::<Vector3<f64>>(&attributes::POSITION_3D)
@@ -0,0 +1,331 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    io::{Error, ErrorKind, Read, Seek, SeekFrom},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// Counter for the source IDs of `CachedReader`s that don't share their blocks with other readers. Starts at the upper
+/// half of the ID range so that these IDs don't collide with IDs from [source_id_for_path] in practice
+static NEXT_SOURCE_ID: AtomicU64 = AtomicU64::new(1 << 63);
+
+/// Statistics of a [BlockCache], which help to choose the size of the cache and of its blocks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlockCacheStatistics {
+    /// Number of requested blocks that were in the cache
+    pub hits: u64,
+    /// Number of requested blocks that had to be read from their source
+    pub misses: u64,
+    /// Number of blocks that were removed from the cache to make room for other blocks
+    pub evictions: u64,
+    /// Number of bytes that are currently in the cache
+    pub cached_bytes: usize,
+}
+
+impl BlockCacheStatistics {
+    /// Returns the fraction of requested blocks that were in the cache, or zero if no blocks were requested yet
+    pub fn hit_rate(&self) -> f64 {
+        let requests = self.hits + self.misses;
+        if requests == 0 {
+            0.0
+        } else {
+            self.hits as f64 / requests as f64
+        }
+    }
+}
+
+/// A cached block together with the time at which it was last used
+struct CachedBlock {
+    data: Arc<[u8]>,
+    last_used: u64,
+}
+
+/// Size-bounded cache of fixed-size blocks of bytes from one or more sources (files, remote resources etc.). When the
+/// cache is full, the least recently used blocks are evicted. Blocks are identified by the ID of their source and
+/// their index within the source, so a single `BlockCache` can be shared between many readers. Readers use the cache
+/// through a [CachedReader], which is how repeated spatial queries avoid reading the same byte ranges again
+pub struct BlockCache {
+    block_size: usize,
+    max_bytes: usize,
+    blocks: HashMap<(u64, u64), CachedBlock>,
+    clock: u64,
+    statistics: BlockCacheStatistics,
+}
+
+impl BlockCache {
+    /// Creates a new `BlockCache` that stores blocks of `block_size` bytes and holds at most `max_bytes` bytes
+    ///
+    /// # Panics
+    ///
+    /// If `block_size` is zero
+    pub fn new(block_size: usize, max_bytes: usize) -> Self {
+        if block_size == 0 {
+            panic!("BlockCache::new: block_size must be > 0");
+        }
+        Self {
+            block_size,
+            max_bytes,
+            blocks: HashMap::new(),
+            clock: 0,
+            statistics: Default::default(),
+        }
+    }
+
+    /// Creates a new `BlockCache` (see [new](BlockCache::new)) that can be shared between multiple [CachedReader]s
+    pub fn new_shared(block_size: usize, max_bytes: usize) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self::new(block_size, max_bytes)))
+    }
+
+    /// Returns the size of the blocks of this cache in bytes
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    /// Returns the maximum number of bytes in this cache
+    pub fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Returns the statistics of this cache
+    pub fn statistics(&self) -> BlockCacheStatistics {
+        self.statistics
+    }
+
+    /// Resets the hit, miss and eviction counters of this cache
+    pub fn reset_statistics(&mut self) {
+        self.statistics = BlockCacheStatistics {
+            cached_bytes: self.statistics.cached_bytes,
+            ..Default::default()
+        };
+    }
+
+    /// Removes all blocks from this cache
+    pub fn clear(&mut self) {
+        self.blocks.clear();
+        self.statistics.cached_bytes = 0;
+    }
+
+    /// Returns the block with the given `index` of the source with the given `source_id`, if it is in the cache
+    pub fn get(&mut self, source_id: u64, index: u64) -> Option<Arc<[u8]>> {
+        self.clock += 1;
+        match self.blocks.get_mut(&(source_id, index)) {
+            Some(block) => {
+                block.last_used = self.clock;
+                self.statistics.hits += 1;
+                Some(block.data.clone())
+            }
+            None => {
+                self.statistics.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Inserts the block with the given `index` of the source with the given `source_id` into the cache, evicting the
+    /// least recently used blocks if the cache is full. Blocks that are larger than the whole cache are not inserted
+    pub fn insert(&mut self, source_id: u64, index: u64, data: Arc<[u8]>) {
+        if data.len() > self.max_bytes {
+            return;
+        }
+        self.clock += 1;
+        let size = data.len();
+        if let Some(previous) = self.blocks.insert(
+            (source_id, index),
+            CachedBlock {
+                data,
+                last_used: self.clock,
+            },
+        ) {
+            self.statistics.cached_bytes -= previous.data.len();
+        }
+        self.statistics.cached_bytes += size;
+
+        while self.statistics.cached_bytes > self.max_bytes {
+            let oldest = *self
+                .blocks
+                .iter()
+                .min_by_key(|(_, block)| block.last_used)
+                .map(|(key, _)| key)
+                .unwrap();
+            let evicted = self.blocks.remove(&oldest).unwrap();
+            self.statistics.cached_bytes -= evicted.data.len();
+            self.statistics.evictions += 1;
+        }
+    }
+}
+
+/// Returns a source ID for a [CachedReader] of the file at the given `path`. All readers of the same file that use
+/// this ID share the cached blocks of this file. The file must not change while its blocks are cached
+pub fn source_id_for_path<P: AsRef<Path>>(path: P) -> u64 {
+    let path = path.as_ref();
+    let canonical_path = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+    let mut hasher = DefaultHasher::new();
+    canonical_path.hash(&mut hasher);
+    // Keep the upper half of the ID range for the IDs of readers that don't share their blocks
+    hasher.finish() >> 1
+}
+
+/// Wrapper around a `Read + Seek` source that reads the source in blocks through a [BlockCache]. Seeking only changes
+/// the position of the `CachedReader`, the underlying source is only accessed for blocks that are not in the cache
+///
+/// ```no_run
+/// # use std::fs::File;
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::LASReader;
+/// let cache = BlockCache::new_shared(64 * 1024, 256 * 1024 * 1024);
+/// let file = File::open("in.copc.laz").unwrap();
+/// let source_id = source_id_for_path("in.copc.laz");
+/// let cached_file = CachedReader::new(file, cache.clone()).unwrap().with_source_id(source_id);
+/// let reader = LASReader::from_read(cached_file, true).unwrap();
+/// println!("{:?}", cache.lock().unwrap().statistics());
+/// ```
+pub struct CachedReader<R: Read + Seek> {
+    inner: R,
+    cache: Arc<Mutex<BlockCache>>,
+    source_id: u64,
+    position: u64,
+    length: u64,
+}
+
+impl<R: Read + Seek> CachedReader<R> {
+    /// Creates a new `CachedReader` for the given `inner` source that reads through the given `cache`. The blocks of
+    /// this reader are not shared with other readers, use [with_source_id](CachedReader::with_source_id) for this
+    ///
+    /// # Errors
+    ///
+    /// If the length of `inner` can't be determined, an error is returned
+    pub fn new(mut inner: R, cache: Arc<Mutex<BlockCache>>) -> std::io::Result<Self> {
+        let position = inner.seek(SeekFrom::Current(0))?;
+        let length = inner.seek(SeekFrom::End(0))?;
+        Ok(Self {
+            inner,
+            cache,
+            source_id: NEXT_SOURCE_ID.fetch_add(1, Ordering::Relaxed),
+            position,
+            length,
+        })
+    }
+
+    /// Sets the ID of the source of this `CachedReader`. All readers with the same ID share their cached blocks, so
+    /// they must read the same data (see [source_id_for_path])
+    pub fn with_source_id(mut self, source_id: u64) -> Self {
+        self.source_id = source_id;
+        self
+    }
+
+    /// Returns the cache of this `CachedReader`
+    pub fn cache(&self) -> &Arc<Mutex<BlockCache>> {
+        &self.cache
+    }
+
+    /// Returns the wrapped source
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Returns the block with the given `index`, reading it from the underlying source if it is not in the cache
+    fn block(&mut self, index: u64, block_size: usize) -> std::io::Result<Arc<[u8]>> {
+        if let Some(block) = self.cache.lock().unwrap().get(self.source_id, index) {
+            return Ok(block);
+        }
+        // The lock is not held while reading, so that other readers can use the cache in the meantime
+        let start = index * block_size as u64;
+        let size = (self.length - start).min(block_size as u64) as usize;
+        let mut data = vec![0; size];
+        self.inner.seek(SeekFrom::Start(start))?;
+        self.inner.read_exact(&mut data)?;
+        let data: Arc<[u8]> = data.into();
+        self.cache
+            .lock()
+            .unwrap()
+            .insert(self.source_id, index, data.clone());
+        Ok(data)
+    }
+}
+
+impl<R: Read + Seek> Read for CachedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() || self.position >= self.length {
+            return Ok(0);
+        }
+        let block_size = self.cache.lock().unwrap().block_size();
+        let index = self.position / block_size as u64;
+        let block = self.block(index, block_size)?;
+        let offset = (self.position - index * block_size as u64) as usize;
+        let count = buf.len().min(block.len() - offset);
+        buf[..count].copy_from_slice(&block[offset..offset + count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl<R: Read + Seek> Seek for CachedReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::End(offset) => self.length as i128 + offset as i128,
+            SeekFrom::Current(offset) => self.position as i128 + offset as i128,
+        };
+        if position < 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Can't seek before the start of the source",
+            ));
+        }
+        self.position = position as u64;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn cached_reader_reads_same_bytes_as_source() -> std::io::Result<()> {
+        let bytes = (0..1000).map(|value| value as u8).collect::<Vec<_>>();
+        let cache = BlockCache::new_shared(64, 256);
+        let mut reader = CachedReader::new(Cursor::new(bytes.clone()), cache.clone())?;
+
+        let mut read = vec![0; 1000];
+        reader.read_exact(&mut read)?;
+        assert_eq!(bytes, read);
+        let statistics = cache.lock().unwrap().statistics();
+        assert_eq!(16, statistics.misses);
+        assert_eq!(11, statistics.evictions);
+        assert_eq!(3 * 64 + 40, statistics.cached_bytes);
+
+        // The last blocks are still in the cache
+        let mut tail = vec![0; 100];
+        reader.seek(SeekFrom::End(-100))?;
+        reader.read_exact(&mut tail)?;
+        assert_eq!(&bytes[900..], &tail[..]);
+        let statistics = cache.lock().unwrap().statistics();
+        assert_eq!(16, statistics.misses);
+        assert_eq!(2, statistics.hits);
+        Ok(())
+    }
+
+    #[test]
+    fn readers_with_the_same_source_id_share_blocks() -> std::io::Result<()> {
+        let bytes = vec![42; 100];
+        let cache = BlockCache::new_shared(32, 1024);
+        let mut first =
+            CachedReader::new(Cursor::new(bytes.clone()), cache.clone())?.with_source_id(1);
+        let mut second = CachedReader::new(Cursor::new(bytes), cache.clone())?.with_source_id(1);
+        let mut read = vec![0; 100];
+        first.read_exact(&mut read)?;
+        second.read_exact(&mut read)?;
+        let statistics = cache.lock().unwrap().statistics();
+        assert_eq!(4, statistics.misses);
+        assert_eq!(4, statistics.hits);
+        assert_eq!(100, statistics.cached_bytes);
+        assert_eq!(0.5, statistics.hit_rate());
+        Ok(())
+    }
+}
@@ -0,0 +1,155 @@
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PerAttributeVecPointStorage},
+    layout::{PointAttributeDefinition, PointLayout},
+};
+
+use super::{PastureIoError, PointReader, Result};
+
+/// Builder for point buffers that hold the points of a `PointReader`. The buffers are created with enough capacity
+/// for all points of the reader, so loading a whole file does not reallocate the buffer over and over again. The
+/// buffers use the default `PointLayout` of the reader, or a projection of it to some of its attributes
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::LASReader;
+/// # use pasture_core::layout::attributes;
+/// # fn main() -> Result<()> {
+/// let mut reader = LASReader::from_path("input.las")?;
+/// let mut points = PointBufferBuilder::from_reader(&reader)
+///     .project(&[attributes::POSITION_3D.name(), attributes::CLASSIFICATION.name()])?
+///     .per_attribute();
+/// let count = reader.remaining_points();
+/// reader.read_into(&mut points, count)?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PointBufferBuilder {
+    layout: PointLayout,
+    capacity: usize,
+}
+
+impl PointBufferBuilder {
+    /// Creates a new `PointBufferBuilder` for buffers with the given `layout` and `capacity`
+    pub fn new(layout: PointLayout, capacity: usize) -> Self {
+        Self { layout, capacity }
+    }
+
+    /// Creates a new `PointBufferBuilder` for buffers with the default `PointLayout` of the given `reader` and enough
+    /// capacity for all its points. If the number of points is not known from the `Metadata` of `reader`, the
+    /// buffers are created without capacity, use [with_capacity](PointBufferBuilder::with_capacity) in this case
+    pub fn from_reader(reader: &dyn PointReader) -> Self {
+        Self {
+            layout: reader.get_default_point_layout().clone(),
+            capacity: reader.get_metadata().number_of_points().unwrap_or(0),
+        }
+    }
+
+    /// Sets the number of points that the buffers can hold without reallocating
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Uses the given `layout` for the buffers. Readers convert their points into this layout in `read_into`
+    pub fn with_layout(mut self, layout: PointLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Uses a layout with the given attributes for the buffers. The attributes can have different datatypes than in
+    /// the default layout of the reader, in which case the reader converts the values in `read_into`
+    pub fn with_attributes(mut self, attributes: &[PointAttributeDefinition]) -> Self {
+        self.layout = PointLayout::from_attributes(attributes);
+        self
+    }
+
+    /// Projects the current layout to the attributes with the given names, keeping their datatypes. The attributes
+    /// are stored in the given order
+    ///
+    /// # Errors
+    ///
+    /// If the current layout has no attribute with one of the given names, an error is returned
+    pub fn project(mut self, attribute_names: &[&str]) -> Result<Self> {
+        let attributes = attribute_names
+            .iter()
+            .map(|name| {
+                self.layout
+                    .get_attribute_by_name(name)
+                    .map(PointAttributeDefinition::from)
+                    .ok_or_else(|| {
+                        PastureIoError::LayoutMismatch(format!(
+                            "The point layout has no attribute named {}",
+                            name
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        self.layout = PointLayout::from_attributes(&attributes);
+        Ok(self)
+    }
+
+    /// Returns the `PointLayout` of the buffers
+    pub fn layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    /// Returns the number of points that the buffers can hold without reallocating
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Creates an empty `InterleavedVecPointStorage` with the layout and capacity of this builder
+    pub fn interleaved(self) -> InterleavedVecPointStorage {
+        InterleavedVecPointStorage::with_capacity(self.capacity, self.layout)
+    }
+
+    /// Creates an empty `PerAttributeVecPointStorage` with the layout and capacity of this builder
+    pub fn per_attribute(self) -> PerAttributeVecPointStorage {
+        PerAttributeVecPointStorage::with_capacity(self.capacity, self.layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{containers::PointBuffer, layout::attributes};
+
+    use super::*;
+    use crate::las::{compare_to_reference_data_range, get_test_las_path, LASReader};
+
+    #[test]
+    fn test_buffer_builder_from_reader() -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(1))?;
+        let builder = PointBufferBuilder::from_reader(&reader);
+        assert_eq!(10, builder.capacity());
+        assert_eq!(reader.get_default_point_layout(), builder.layout());
+
+        let mut points = builder.interleaved();
+        assert!(points.capacity() >= 10);
+        reader.read_into(&mut points, 10)?;
+        assert_eq!(10, points.len());
+        compare_to_reference_data_range(&points, 1, 0..10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_buffer_builder_projection() -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(1))?;
+        let mut points = PointBufferBuilder::from_reader(&reader)
+            .project(&[attributes::GPS_TIME.name(), attributes::POSITION_3D.name()])?
+            .per_attribute();
+        assert_eq!(
+            &PointLayout::from_attributes(&[attributes::GPS_TIME, attributes::POSITION_3D]),
+            points.point_layout()
+        );
+        assert!(points.capacity() >= 10);
+        reader.read_into(&mut points, 10)?;
+        assert_eq!(10, points.len());
+
+        assert!(PointBufferBuilder::from_reader(&reader)
+            .project(&[attributes::NORMAL.name()])
+            .is_err());
+        Ok(())
+    }
+}
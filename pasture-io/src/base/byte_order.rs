@@ -0,0 +1,219 @@
+use std::io::Read;
+
+use pasture_core::layout::{PointAttributeDataType, PointLayout};
+
+use super::Result;
+
+/// Byte order of binary data in a point cloud file. Point buffers in pasture always store their data in the native
+/// byte order of the current target, so readers and writers for binary formats have to convert between the byte order
+/// of the file and the native byte order, for example using `read_attribute_values_to_native` and
+/// `convert_attribute_byte_order`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ByteOrder {
+    /// Least significant byte first
+    LittleEndian,
+    /// Most significant byte first
+    BigEndian,
+}
+
+impl ByteOrder {
+    /// The native byte order of the current target
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: ByteOrder = ByteOrder::LittleEndian;
+    /// The native byte order of the current target
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: ByteOrder = ByteOrder::BigEndian;
+
+    /// Is this the native byte order of the current target?
+    pub fn is_native(&self) -> bool {
+        *self == Self::NATIVE
+    }
+}
+
+/// Returns the size in bytes of a single component of the given `datatype`, which is the unit whose bytes are swapped
+/// when converting between byte orders. For scalar types this is the size of the type, for vector and array types it is
+/// the size of one component
+pub fn component_size(datatype: PointAttributeDataType) -> usize {
+    let (component_type, _) = datatype.components();
+    component_type.size() as usize
+}
+
+/// Converts the tightly packed values of an attribute with the given `datatype` in `data` from the byte order `from`
+/// into the byte order `to` in place. `data` can contain any number of values
+///
+/// ```
+/// # use pasture_core::layout::PointAttributeDataType;
+/// # use pasture_io::base::{convert_attribute_byte_order, ByteOrder};
+/// let mut data = 1234_u16.to_be_bytes().to_vec();
+/// convert_attribute_byte_order(&mut data, PointAttributeDataType::U16, ByteOrder::BigEndian, ByteOrder::LittleEndian);
+/// assert_eq!(1234_u16.to_le_bytes().to_vec(), data);
+/// ```
+///
+/// # Panics
+///
+/// If the size of `data` is no multiple of the size of `datatype`
+pub fn convert_attribute_byte_order(
+    data: &mut [u8],
+    datatype: PointAttributeDataType,
+    from: ByteOrder,
+    to: ByteOrder,
+) {
+    if data.len() % datatype.size() as usize != 0 {
+        panic!(
+            "convert_attribute_byte_order: Size of data ({} bytes) is no multiple of the size of datatype {}",
+            data.len(),
+            datatype
+        );
+    }
+    let component_size = component_size(datatype);
+    if from == to || component_size == 1 {
+        return;
+    }
+    data.chunks_exact_mut(component_size)
+        .for_each(|component| component.reverse());
+}
+
+/// Converts the interleaved points with the given `layout` in `data` from the byte order `from` into the byte order
+/// `to` in place. Each attribute is converted according to its datatype, padding bytes are left untouched
+///
+/// # Panics
+///
+/// If the size of `data` is no multiple of the size of a single point in `layout`
+pub fn convert_points_byte_order(
+    data: &mut [u8],
+    layout: &PointLayout,
+    from: ByteOrder,
+    to: ByteOrder,
+) {
+    let size_of_point = layout.size_of_point_entry() as usize;
+    if data.len() % size_of_point != 0 {
+        panic!(
+            "convert_points_byte_order: Size of data ({} bytes) is no multiple of the size of a single point ({} bytes)",
+            data.len(),
+            size_of_point
+        );
+    }
+    if from == to {
+        return;
+    }
+    for point in data.chunks_exact_mut(size_of_point) {
+        for attribute in layout.attributes() {
+            let start = attribute.offset() as usize;
+            let end = start + attribute.size() as usize;
+            convert_attribute_byte_order(&mut point[start..end], attribute.datatype(), from, to);
+        }
+    }
+}
+
+/// Reads the tightly packed values of an attribute with the given `datatype`, stored in the given `byte_order`, from
+/// `read` into `buffer` and converts them into the native byte order. Reads as many values as fit into `buffer`
+///
+/// # Errors
+///
+/// If reading from `read` fails, an error is returned
+///
+/// # Panics
+///
+/// If the size of `buffer` is no multiple of the size of `datatype`
+pub fn read_attribute_values_to_native<R: Read + ?Sized>(
+    read: &mut R,
+    buffer: &mut [u8],
+    datatype: PointAttributeDataType,
+    byte_order: ByteOrder,
+) -> Result<()> {
+    read.read_exact(buffer)?;
+    convert_attribute_byte_order(buffer, datatype, byte_order, ByteOrder::NATIVE);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasture_core::layout::attributes;
+
+    #[test]
+    fn test_convert_attribute_byte_order() {
+        let values = [1.5_f64, -2.25, 1e10];
+        let mut data = values
+            .iter()
+            .flat_map(|value| value.to_be_bytes().to_vec())
+            .collect::<Vec<_>>();
+        convert_attribute_byte_order(
+            &mut data,
+            PointAttributeDataType::Vec3f64,
+            ByteOrder::BigEndian,
+            ByteOrder::LittleEndian,
+        );
+        let expected = values
+            .iter()
+            .flat_map(|value| value.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(expected, data);
+
+        // Single-byte components are never swapped
+        let mut colors = vec![1, 2, 3, 4];
+        convert_attribute_byte_order(
+            &mut colors,
+            PointAttributeDataType::Vec4u8,
+            ByteOrder::BigEndian,
+            ByteOrder::LittleEndian,
+        );
+        assert_eq!(vec![1, 2, 3, 4], colors);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_convert_attribute_byte_order_wrong_size() {
+        let mut data = vec![0; 3];
+        convert_attribute_byte_order(
+            &mut data,
+            PointAttributeDataType::U16,
+            ByteOrder::BigEndian,
+            ByteOrder::LittleEndian,
+        );
+    }
+
+    #[test]
+    fn test_convert_points_byte_order() {
+        let layout = PointLayout::from_attributes(&[attributes::INTENSITY, attributes::GPS_TIME]);
+        let intensity_offset = layout
+            .get_attribute_by_name(attributes::INTENSITY.name())
+            .unwrap()
+            .offset() as usize;
+        let gps_time_offset = layout
+            .get_attribute_by_name(attributes::GPS_TIME.name())
+            .unwrap()
+            .offset() as usize;
+
+        let mut data = vec![0; layout.size_of_point_entry() as usize];
+        data[intensity_offset..intensity_offset + 2].copy_from_slice(&42_u16.to_be_bytes());
+        data[gps_time_offset..gps_time_offset + 8].copy_from_slice(&123.5_f64.to_be_bytes());
+
+        convert_points_byte_order(&mut data, &layout, ByteOrder::BigEndian, ByteOrder::NATIVE);
+        assert_eq!(
+            42_u16.to_ne_bytes(),
+            data[intensity_offset..intensity_offset + 2]
+        );
+        assert_eq!(
+            123.5_f64.to_ne_bytes(),
+            data[gps_time_offset..gps_time_offset + 8]
+        );
+    }
+
+    #[test]
+    fn test_read_attribute_values_to_native() -> Result<()> {
+        let file_data = [7_i32.to_be_bytes(), (-3_i32).to_be_bytes()].concat();
+        let mut buffer = vec![0; 8];
+        read_attribute_values_to_native(
+            &mut file_data.as_slice(),
+            &mut buffer,
+            PointAttributeDataType::I32,
+            ByteOrder::BigEndian,
+        )?;
+        assert_eq!(
+            [7_i32.to_ne_bytes(), (-3_i32).to_ne_bytes()].concat(),
+            buffer
+        );
+        Ok(())
+    }
+}
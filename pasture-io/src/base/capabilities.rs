@@ -0,0 +1,95 @@
+use pasture_core::layout::{PointAttributeDefinition, PointLayout};
+
+/// Capabilities of a `PointReader` or `PointWriter`, as returned by `PointReader::capabilities` and
+/// `PointWriter::capabilities`. Generic pipelines can use them to plan their execution at runtime, e.g. to push a
+/// spatial filter down into a reader with a spatial index and to filter the points after reading otherwise
+///
+/// ```no_run
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::LASReader;
+/// # fn main() -> anyhow::Result<()> {
+/// let reader = LASReader::from_path("points.copc.laz")?;
+/// if reader.capabilities().filter_pushdown {
+///     // Query the reader, which reads only the blocks of points that can match
+/// } else {
+///     // Read all points and filter them afterwards
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capabilities {
+    /// The attributes that can be read or written. Attributes of other buffers that are not in this list are ignored
+    /// by writers, and can't be read by readers
+    pub attributes: Vec<PointAttributeDefinition>,
+    /// True if the reader or writer can seek to arbitrary points (see `SeekToPoint`)
+    pub seekable: bool,
+    /// True if the reader can skip points that don't match a spatial query without decoding them, because the file has
+    /// a spatial index (see `SeekToPoint::point_blocks` and `query_point_blocks`). Otherwise, all points have to be read
+    /// and filtered afterwards. Always false for writers
+    pub filter_pushdown: bool,
+    /// True if reading only some of the attributes is cheaper than reading all attributes, e.g. because the attributes
+    /// are stored in separate streams. Otherwise, all attributes of a point are decoded, regardless of the `PointLayout`
+    /// passed to `PointReader::read_into`. Always false for writers
+    pub projection_pushdown: bool,
+    /// True if the writer can append more points after `PointWriter::flush`, so that the output can be written
+    /// incrementally. Writers that finalize their output on `flush` (e.g. LAZ writers) can only be flushed once at the
+    /// end. Always false for readers
+    pub appendable: bool,
+}
+
+impl Capabilities {
+    /// Creates new `Capabilities` that support the attributes of the given `layout` and none of the optional
+    /// capabilities. This is what `PointReader::capabilities` and `PointWriter::capabilities` return by default
+    pub fn new(layout: &PointLayout) -> Self {
+        Self {
+            attributes: layout
+                .attributes()
+                .map(|attribute| attribute.into())
+                .collect(),
+            seekable: false,
+            filter_pushdown: false,
+            projection_pushdown: false,
+            appendable: false,
+        }
+    }
+
+    /// Returns true if an attribute with the name of `attribute` is supported. Its datatype can be different, since
+    /// readers and writers convert the attributes into the datatypes of the buffers
+    pub fn supports_attribute(&self, attribute: &PointAttributeDefinition) -> bool {
+        self.attributes
+            .iter()
+            .any(|supported| supported.name() == attribute.name())
+    }
+
+    /// Returns true if all attributes of `layout` are supported
+    pub fn supports_layout(&self, layout: &PointLayout) -> bool {
+        layout
+            .attributes()
+            .all(|attribute| self.supports_attribute(&attribute.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasture_core::layout::{
+        attributes::{CLASSIFICATION, INTENSITY, POSITION_3D},
+        PointAttributeDataType,
+    };
+
+    #[test]
+    fn test_capabilities_support_attributes_by_name() {
+        let capabilities =
+            Capabilities::new(&PointLayout::from_attributes(&[POSITION_3D, INTENSITY]));
+        assert!(!capabilities.seekable);
+        assert!(capabilities.supports_attribute(&INTENSITY));
+        assert!(capabilities.supports_attribute(
+            &POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32)
+        ));
+        assert!(!capabilities.supports_attribute(&CLASSIFICATION));
+        assert!(capabilities.supports_layout(&PointLayout::from_attributes(&[INTENSITY])));
+        assert!(!capabilities
+            .supports_layout(&PointLayout::from_attributes(&[INTENSITY, CLASSIFICATION])));
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+
+use pasture_core::{
+    containers::PointBuffer,
+    layout::{
+        attributes::{CLASSIFICATION, POSITION_3D},
+        decode_components, PointAttributeDataType, PointAttributeDefinition,
+    },
+    math::AABB,
+    nalgebra::Point3,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{PastureIoError, Result};
+
+/// Statistics of a chunk of points, i.e. the minimum and maximum of selected attributes and the set of
+/// classifications, similar to the row group statistics of Parquet files. A file format that stores these statistics
+/// for each chunk allows readers to skip whole chunks that can't match a [ChunkFilter], without decoding their points
+/// ```
+/// # use pasture_core::containers::InterleavedVecPointStorage;
+/// # use pasture_core::layout::{attributes, PointType};
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_derive::PointType;
+/// # use pasture_io::base::*;
+/// #[repr(C)]
+/// #[derive(PointType, Debug, Copy, Clone)]
+/// struct Point {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     position: Vector3<f64>,
+///     #[pasture(BUILTIN_CLASSIFICATION)]
+///     classification: u8,
+/// }
+/// let points: InterleavedVecPointStorage = vec![
+///     Point { position: Vector3::new(0.0, 0.0, 0.0), classification: 2 },
+///     Point { position: Vector3::new(1.0, 2.0, 3.0), classification: 5 },
+/// ]
+/// .into();
+/// let statistics = ChunkStatistics::from_points(&points, &[attributes::POSITION_3D]).unwrap();
+/// assert_eq!(Some(&[(0.0, 1.0), (0.0, 2.0), (0.0, 3.0)][..]), statistics.range("Position3D"));
+/// assert!(ChunkFilter::Classifications(vec![5, 6]).may_match(&statistics));
+/// assert!(!ChunkFilter::Classifications(vec![6]).may_match(&statistics));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkStatistics {
+    point_count: usize,
+    /// Minimum and maximum of each component of the selected attributes, by attribute name
+    ranges: BTreeMap<String, Vec<(f64, f64)>>,
+    /// Bit set of all classifications in the chunk, or `None` if the points have no `CLASSIFICATION` attribute
+    classifications: Option<[u64; 4]>,
+}
+
+impl ChunkStatistics {
+    /// Calculates the statistics of all `points`, with the ranges of the given `attributes`. If the points have a
+    /// `CLASSIFICATION` attribute of type `u8`, the set of classifications is recorded too
+    ///
+    /// # Errors
+    ///
+    /// If `points` lack one of the `attributes`
+    pub fn from_points<B: PointBuffer + ?Sized>(
+        points: &B,
+        attributes: &[PointAttributeDefinition],
+    ) -> Result<Self> {
+        let layout = points.point_layout();
+        let mut ranges = BTreeMap::new();
+        for attribute in attributes {
+            let member = layout
+                .get_attribute_by_name(attribute.name())
+                .ok_or_else(|| {
+                    PastureIoError::LayoutMismatch(format!(
+                        "Point buffer has no {} attribute",
+                        attribute.name()
+                    ))
+                })?;
+            let attribute = attribute.with_custom_datatype(member.datatype());
+            let mut bytes = vec![0; points.len() * attribute.size() as usize];
+            points.get_raw_attribute_range(0..points.len(), &attribute, &mut bytes);
+            ranges.insert(
+                attribute.name().to_owned(),
+                component_ranges(attribute.datatype(), &bytes),
+            );
+        }
+
+        let classifications = match layout.get_attribute_by_name(CLASSIFICATION.name()) {
+            Some(member) if member.datatype() == PointAttributeDataType::U8 => {
+                let mut bytes = vec![0; points.len()];
+                points.get_raw_attribute_range(0..points.len(), &CLASSIFICATION, &mut bytes);
+                let mut set = [0u64; 4];
+                for classification in bytes {
+                    set[classification as usize / 64] |= 1 << (classification % 64);
+                }
+                Some(set)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            point_count: points.len(),
+            ranges,
+            classifications,
+        })
+    }
+
+    /// Returns the number of points in the chunk
+    pub fn point_count(&self) -> usize {
+        self.point_count
+    }
+
+    /// Returns the minimum and maximum of each component of the attribute with the given `name`, or `None` if the
+    /// statistics contain no range for this attribute. The range of an empty chunk is `(INFINITY, NEG_INFINITY)`
+    pub fn range(&self, name: &str) -> Option<&[(f64, f64)]> {
+        self.ranges.get(name).map(|range| range.as_slice())
+    }
+
+    /// Returns the bounds of the positions in the chunk, if the statistics contain the range of `POSITION_3D` and the
+    /// chunk is not empty
+    pub fn bounds(&self) -> Option<AABB<f64>> {
+        match self.range(POSITION_3D.name()) {
+            Some([x, y, z]) if self.point_count > 0 => Some(AABB::from_min_max_unchecked(
+                Point3::new(x.0, y.0, z.0),
+                Point3::new(x.1, y.1, z.1),
+            )),
+            _ => None,
+        }
+    }
+
+    /// Returns all classifications that occur in the chunk in ascending order, or `None` if the points have no
+    /// `CLASSIFICATION` attribute
+    pub fn classifications(&self) -> Option<Vec<u8>> {
+        self.classifications.map(|set| {
+            (0..=u8::MAX)
+                .filter(|classification| {
+                    set[*classification as usize / 64] & (1 << (classification % 64)) != 0
+                })
+                .collect()
+        })
+    }
+
+    /// Merges the statistics of `other` into these statistics, e.g. to get the statistics of a whole file from the
+    /// statistics of its chunks. Ranges and classifications that are missing in one of the statistics are dropped
+    pub fn merge(&mut self, other: &ChunkStatistics) {
+        self.point_count += other.point_count;
+        self.ranges = std::mem::take(&mut self.ranges)
+            .into_iter()
+            .filter_map(|(name, range)| {
+                let other_range = other.ranges.get(&name)?;
+                if other_range.len() != range.len() {
+                    return None;
+                }
+                let merged = range
+                    .iter()
+                    .zip(other_range.iter())
+                    .map(|(a, b)| (a.0.min(b.0), a.1.max(b.1)))
+                    .collect();
+                Some((name, merged))
+            })
+            .collect();
+        self.classifications = match (self.classifications, other.classifications) {
+            (Some(a), Some(b)) => Some([a[0] | b[0], a[1] | b[1], a[2] | b[2], a[3] | b[3]]),
+            _ => None,
+        };
+    }
+
+    /// Serializes these statistics into a compact binary representation, which can be stored next to the chunk
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(bincode::serialize(self)?)
+    }
+
+    /// Deserializes statistics from the given `bytes`, as written by [to_bytes](ChunkStatistics::to_bytes)
+    ///
+    /// # Errors
+    ///
+    /// If `bytes` are no valid chunk statistics
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        bincode::deserialize(bytes)
+            .map_err(|e| PastureIoError::InvalidData(format!("Invalid chunk statistics: {}", e)))
+    }
+}
+
+/// A filter on the points of a chunk, which is tested against [ChunkStatistics] to decide whether a chunk can be
+/// skipped. The test is conservative: A chunk is only skipped if the statistics prove that none of its points match
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChunkFilter {
+    /// Points whose value of the given component of an attribute lies within `[min; max]`
+    Range {
+        /// Name of the attribute
+        attribute: String,
+        /// Index of the component of the attribute, which is `0` for scalar attributes
+        component: usize,
+        min: f64,
+        max: f64,
+    },
+    /// Points whose position lies within the given bounds
+    Bounds(AABB<f64>),
+    /// Points with one of the given classifications
+    Classifications(Vec<u8>),
+    /// Points that match all of the given filters
+    All(Vec<ChunkFilter>),
+}
+
+impl ChunkFilter {
+    /// Can points of the chunk with the given `statistics` match this filter? Returns `false` only if no point of the
+    /// chunk matches, i.e. if the chunk can be skipped. Filters on attributes that are missing in the statistics never
+    /// skip a chunk
+    pub fn may_match(&self, statistics: &ChunkStatistics) -> bool {
+        if statistics.point_count == 0 {
+            return false;
+        }
+        match self {
+            ChunkFilter::Range {
+                attribute,
+                component,
+                min,
+                max,
+            } => match statistics
+                .range(attribute)
+                .and_then(|range| range.get(*component))
+            {
+                Some((chunk_min, chunk_max)) => chunk_min <= max && chunk_max >= min,
+                None => true,
+            },
+            ChunkFilter::Bounds(bounds) => match statistics.bounds() {
+                Some(chunk_bounds) => chunk_bounds.intersects(bounds),
+                None => true,
+            },
+            ChunkFilter::Classifications(classifications) => match statistics.classifications {
+                Some(set) => classifications.iter().any(|classification| {
+                    set[*classification as usize / 64] & (1 << (classification % 64)) != 0
+                }),
+                None => true,
+            },
+            ChunkFilter::All(filters) => filters.iter().all(|filter| filter.may_match(statistics)),
+        }
+    }
+}
+
+/// Returns the minimum and maximum of each component of the tightly packed values of an attribute with the given
+/// `datatype`
+fn component_ranges(datatype: PointAttributeDataType, bytes: &[u8]) -> Vec<(f64, f64)> {
+    let (_, component_count) = datatype.components();
+    let mut ranges = vec![(f64::INFINITY, f64::NEG_INFINITY); component_count];
+    for (index, value) in decode_components(datatype, bytes).enumerate() {
+        let range = &mut ranges[index % component_count];
+        range.0 = range.0.min(value);
+        range.1 = range.1.max(value);
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::InterleavedVecPointStorage,
+        layout::{
+            attributes::{GPS_TIME, INTENSITY},
+            PointType,
+        },
+        nalgebra::Vector3,
+    };
+    use pasture_derive::PointType;
+
+    use super::*;
+
+    #[repr(C)]
+    #[derive(PointType, Debug, Copy, Clone)]
+    struct TestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        position: Vector3<f64>,
+        #[pasture(BUILTIN_GPS_TIME)]
+        gps_time: f64,
+        #[pasture(BUILTIN_CLASSIFICATION)]
+        classification: u8,
+    }
+
+    fn chunk(first_point: usize, count: usize) -> InterleavedVecPointStorage {
+        (first_point..first_point + count)
+            .map(|index| TestPoint {
+                position: Vector3::new(index as f64, 0.0, -(index as f64)),
+                gps_time: 1000.0 + index as f64,
+                classification: if index % 10 == 0 { 200 } else { 2 },
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunk_filters_skip_chunks() -> Result<()> {
+        let attributes = [POSITION_3D, GPS_TIME];
+        let first = ChunkStatistics::from_points(&chunk(1, 9), &attributes)?;
+        let second = ChunkStatistics::from_points(&chunk(10, 10), &attributes)?;
+        assert_eq!(Some(vec![2]), first.classifications());
+        assert_eq!(Some(vec![2, 200]), second.classifications());
+        assert_eq!(Some(&[(1001.0, 1009.0)][..]), first.range(GPS_TIME.name()));
+
+        let gps_time_filter = ChunkFilter::Range {
+            attribute: GPS_TIME.name().to_owned(),
+            component: 0,
+            min: 1012.5,
+            max: 2000.0,
+        };
+        assert!(!gps_time_filter.may_match(&first));
+        assert!(gps_time_filter.may_match(&second));
+
+        let filter = ChunkFilter::All(vec![
+            ChunkFilter::Classifications(vec![200]),
+            ChunkFilter::Bounds(AABB::from_min_max(
+                Point3::new(0.0, -1.0, -12.0),
+                Point3::new(5.0, 1.0, 0.0),
+            )),
+        ]);
+        assert!(!filter.may_match(&first));
+        assert!(!filter.may_match(&second));
+
+        // Filters on attributes without statistics never skip a chunk
+        let without_ranges = ChunkStatistics::from_points(&chunk(1, 9), &[])?;
+        assert!(gps_time_filter.may_match(&without_ranges));
+        assert!(ChunkStatistics::from_points(&chunk(1, 9), &[INTENSITY]).is_err());
+
+        let mut merged = first.clone();
+        merged.merge(&second);
+        assert_eq!(19, merged.point_count());
+        assert_eq!(
+            Some(AABB::from_min_max(
+                Point3::new(1.0, 0.0, -19.0),
+                Point3::new(19.0, 0.0, -1.0)
+            )),
+            merged.bounds()
+        );
+        assert_eq!(merged, ChunkStatistics::from_bytes(&merged.to_bytes()?)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,621 @@
+use std::collections::HashMap;
+
+use pasture_core::layout::{attributes, PointAttributeDataType, PointAttributeDefinition};
+
+use super::{PastureIoError, Result};
+
+/// A codec that compresses the values of a single point attribute. The values are passed as tightly packed,
+/// little-endian binary data (as in a per-attribute buffer), so a codec can exploit the structure of one attribute
+/// (e.g. small differences between consecutive GPS times or long runs of the same classification) instead of having
+/// to deal with interleaved points
+///
+/// Custom codecs can be added by implementing this trait and registering them in a [CodecRegistry]
+pub trait AttributeCodec: Send + Sync {
+    /// Unique name of this codec. It is stored together with the encoded data, so that the matching codec can be
+    /// looked up from a [CodecRegistry] when decoding
+    fn name(&self) -> &str;
+
+    /// Encodes the given `values` of an attribute with the given `datatype`. The length of `values` must be a
+    /// multiple of the size of `datatype`
+    fn encode(&self, datatype: PointAttributeDataType, values: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decodes `count` values of an attribute with the given `datatype` from `encoded`, which was produced by
+    /// [encode](AttributeCodec::encode)
+    fn decode(
+        &self,
+        datatype: PointAttributeDataType,
+        encoded: &[u8],
+        count: usize,
+    ) -> Result<Vec<u8>>;
+}
+
+/// Codec that stores the values unchanged
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RawCodec;
+
+impl AttributeCodec for RawCodec {
+    fn name(&self) -> &str {
+        "raw"
+    }
+
+    fn encode(&self, datatype: PointAttributeDataType, values: &[u8]) -> Result<Vec<u8>> {
+        check_values(datatype, values)?;
+        Ok(values.to_vec())
+    }
+
+    fn decode(
+        &self,
+        datatype: PointAttributeDataType,
+        encoded: &[u8],
+        count: usize,
+    ) -> Result<Vec<u8>> {
+        if encoded.len() != count * datatype.size() as usize {
+            return Err(PastureIoError::InvalidData(format!(
+                "Expected {} bytes for {} values of type {}, but got {}",
+                count * datatype.size() as usize,
+                count,
+                datatype,
+                encoded.len()
+            )));
+        }
+        Ok(encoded.to_vec())
+    }
+}
+
+/// Codec that stores the difference of each value to the previous value as a variable-length integer. Vector types
+/// are encoded per component. Floating-point values are encoded through their bit patterns, which works well for
+/// slowly changing values like GPS times or the positions of spatially sorted points, since neighbouring values of
+/// the same sign and magnitude have close bit patterns
+#[derive(Debug, Default, Copy, Clone)]
+pub struct DeltaCodec;
+
+impl AttributeCodec for DeltaCodec {
+    fn name(&self) -> &str {
+        "delta"
+    }
+
+    fn encode(&self, datatype: PointAttributeDataType, values: &[u8]) -> Result<Vec<u8>> {
+        check_values(datatype, values)?;
+        let (component_size, components) = component_layout(datatype);
+        let shift = 64 - 8 * component_size as u32;
+        let mut previous = vec![0u64; components];
+        let mut encoded = Vec::with_capacity(values.len() / 2);
+        for (index, component) in values.chunks_exact(component_size).enumerate() {
+            let previous = &mut previous[index % components];
+            let current = read_component(component);
+            // Sign-extend the difference from the size of the component, so that small negative differences stay small
+            let delta = ((current.wrapping_sub(*previous) << shift) as i64) >> shift;
+            write_varint(&mut encoded, zigzag_encode(delta));
+            *previous = current;
+        }
+        Ok(encoded)
+    }
+
+    fn decode(
+        &self,
+        datatype: PointAttributeDataType,
+        encoded: &[u8],
+        count: usize,
+    ) -> Result<Vec<u8>> {
+        let (component_size, components) = component_layout(datatype);
+        let mut previous = vec![0u64; components];
+        let mut values = Vec::with_capacity(count * datatype.size() as usize);
+        let mut cursor = encoded;
+        for index in 0..count * components {
+            let previous = &mut previous[index % components];
+            let delta = zigzag_decode(read_varint(&mut cursor)?);
+            *previous = previous.wrapping_add(delta as u64);
+            values.extend_from_slice(&previous.to_le_bytes()[..component_size]);
+        }
+        check_fully_consumed(cursor)?;
+        Ok(values)
+    }
+}
+
+/// Codec that stores runs of identical values as the length of the run followed by the value. Works well for
+/// attributes that change rarely between consecutive points, like the classification or the point source ID
+#[derive(Debug, Default, Copy, Clone)]
+pub struct RunLengthCodec;
+
+impl AttributeCodec for RunLengthCodec {
+    fn name(&self) -> &str {
+        "rle"
+    }
+
+    fn encode(&self, datatype: PointAttributeDataType, values: &[u8]) -> Result<Vec<u8>> {
+        check_values(datatype, values)?;
+        let size = datatype.size() as usize;
+        let mut encoded = vec![];
+        let mut values = values.chunks_exact(size).peekable();
+        while let Some(value) = values.next() {
+            let mut run_length = 1;
+            while values.next_if_eq(&value).is_some() {
+                run_length += 1;
+            }
+            write_varint(&mut encoded, run_length);
+            encoded.extend_from_slice(value);
+        }
+        Ok(encoded)
+    }
+
+    fn decode(
+        &self,
+        datatype: PointAttributeDataType,
+        encoded: &[u8],
+        count: usize,
+    ) -> Result<Vec<u8>> {
+        let size = datatype.size() as usize;
+        let mut values = Vec::with_capacity(count * size);
+        let mut cursor = encoded;
+        while values.len() < count * size {
+            let run_length = read_varint(&mut cursor)? as usize;
+            if cursor.len() < size {
+                return Err(PastureIoError::InvalidData(
+                    "Run-length encoded data ends in the middle of a value".into(),
+                ));
+            }
+            // Check the length of the run before expanding it, so that corrupt data can't make us allocate
+            // arbitrary amounts of memory
+            let fits = run_length
+                .checked_mul(size)
+                .and_then(|run_size| run_size.checked_add(values.len()))
+                .map_or(false, |new_size| new_size <= count * size);
+            if !fits {
+                return Err(PastureIoError::InvalidData(format!(
+                    "Run-length encoded data contains more than {} values",
+                    count
+                )));
+            }
+            let (value, rest) = cursor.split_at(size);
+            for _ in 0..run_length {
+                values.extend_from_slice(value);
+            }
+            cursor = rest;
+        }
+        check_fully_consumed(cursor)?;
+        Ok(values)
+    }
+}
+
+/// Codec that applies [DeltaCodec] and compresses the resulting variable-length integers with zstd, which removes
+/// the redundancy that remains between the deltas, e.g. of points that were scanned on a regular pattern. Requires
+/// the `zstd` feature
+#[cfg(feature = "zstd")]
+#[derive(Debug, Copy, Clone)]
+pub struct DeltaZstdCodec {
+    level: i32,
+}
+
+#[cfg(feature = "zstd")]
+impl DeltaZstdCodec {
+    /// Default zstd compression level of a `DeltaZstdCodec`
+    pub const DEFAULT_LEVEL: i32 = 3;
+
+    /// Creates a new `DeltaZstdCodec` that compresses with the given zstd compression `level`. The level is only
+    /// used for encoding, data that was encoded with any level can be decoded
+    pub fn with_level(level: i32) -> Self {
+        Self { level }
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl Default for DeltaZstdCodec {
+    fn default() -> Self {
+        Self::with_level(Self::DEFAULT_LEVEL)
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl AttributeCodec for DeltaZstdCodec {
+    fn name(&self) -> &str {
+        "delta-zstd"
+    }
+
+    fn encode(&self, datatype: PointAttributeDataType, values: &[u8]) -> Result<Vec<u8>> {
+        let deltas = DeltaCodec.encode(datatype, values)?;
+        Ok(zstd::stream::encode_all(deltas.as_slice(), self.level)?)
+    }
+
+    fn decode(
+        &self,
+        datatype: PointAttributeDataType,
+        encoded: &[u8],
+        count: usize,
+    ) -> Result<Vec<u8>> {
+        let deltas = zstd::stream::decode_all(encoded).map_err(|e| {
+            PastureIoError::InvalidData(format!("Invalid zstd compressed data: {}", e))
+        })?;
+        DeltaCodec.decode(datatype, &deltas, count)
+    }
+}
+
+/// Codec that packs single-byte values with only a few significant bits (like the return number or the scan
+/// direction flag) densely, using as many bits per value as the largest value needs. Only supports `U8` and `Bool`
+/// attributes
+#[derive(Debug, Default, Copy, Clone)]
+pub struct BitPackCodec;
+
+impl BitPackCodec {
+    fn check_datatype(datatype: PointAttributeDataType) -> Result<()> {
+        match datatype {
+            PointAttributeDataType::U8 | PointAttributeDataType::Bool => Ok(()),
+            other => Err(PastureIoError::UnsupportedFormat(format!(
+                "BitPackCodec does not support datatype {}",
+                other
+            ))),
+        }
+    }
+}
+
+impl AttributeCodec for BitPackCodec {
+    fn name(&self) -> &str {
+        "bitpack"
+    }
+
+    fn encode(&self, datatype: PointAttributeDataType, values: &[u8]) -> Result<Vec<u8>> {
+        Self::check_datatype(datatype)?;
+        let max_value = values.iter().copied().max().unwrap_or(0);
+        let bits = 8 - max_value.leading_zeros() as usize;
+        let mut encoded = vec![0u8; 1 + (values.len() * bits + 7) / 8];
+        encoded[0] = bits as u8;
+        for (index, value) in values.iter().enumerate() {
+            for bit in 0..bits {
+                if value & (1 << bit) != 0 {
+                    let position = index * bits + bit;
+                    encoded[1 + position / 8] |= 1 << (position % 8);
+                }
+            }
+        }
+        Ok(encoded)
+    }
+
+    fn decode(
+        &self,
+        datatype: PointAttributeDataType,
+        encoded: &[u8],
+        count: usize,
+    ) -> Result<Vec<u8>> {
+        Self::check_datatype(datatype)?;
+        let bits = *encoded.first().ok_or_else(|| {
+            PastureIoError::InvalidData("Bit-packed data is missing its header".into())
+        })? as usize;
+        if bits > 8 || encoded.len() != 1 + (count * bits + 7) / 8 {
+            return Err(PastureIoError::InvalidData(format!(
+                "Bit-packed data has the wrong size for {} values",
+                count
+            )));
+        }
+        Ok((0..count)
+            .map(|index| {
+                (0..bits).fold(0u8, |value, bit| {
+                    let position = index * bits + bit;
+                    if encoded[1 + position / 8] & (1 << (position % 8)) != 0 {
+                        value | (1 << bit)
+                    } else {
+                        value
+                    }
+                })
+            })
+            .collect())
+    }
+}
+
+/// Returns the codec that is best suited for the given attribute by default: [DeltaCodec] for positions and GPS
+/// times (or `DeltaZstdCodec` with the `zstd` feature), [RunLengthCodec] for classifications and point source IDs, [BitPackCodec] for the small LAS flags and
+/// [RawCodec] for everything else
+pub fn default_codec_for_attribute(
+    attribute: &PointAttributeDefinition,
+) -> Box<dyn AttributeCodec> {
+    let name = attribute.name();
+    if name == attributes::POSITION_3D.name() || name == attributes::GPS_TIME.name() {
+        #[cfg(feature = "zstd")]
+        return Box::new(DeltaZstdCodec::default());
+        #[cfg(not(feature = "zstd"))]
+        return Box::new(DeltaCodec);
+    }
+    if name == attributes::CLASSIFICATION.name() || name == attributes::POINT_SOURCE_ID.name() {
+        return Box::new(RunLengthCodec);
+    }
+    let is_flag = [
+        &attributes::RETURN_NUMBER,
+        &attributes::NUMBER_OF_RETURNS,
+        &attributes::CLASSIFICATION_FLAGS,
+        &attributes::SCANNER_CHANNEL,
+        &attributes::SCAN_DIRECTION_FLAG,
+        &attributes::EDGE_OF_FLIGHT_LINE,
+    ]
+    .iter()
+    .any(|flag| flag.name() == name);
+    if is_flag && BitPackCodec::check_datatype(attribute.datatype()).is_ok() {
+        return Box::new(BitPackCodec);
+    }
+    Box::new(RawCodec)
+}
+
+/// Collection of [AttributeCodec]s by their name, used to find the codec for decoding attribute data. Contains all
+/// built-in codecs by default, additional codecs can be added with [register](CodecRegistry::register)
+pub struct CodecRegistry {
+    codecs: HashMap<String, Box<dyn AttributeCodec>>,
+}
+
+impl CodecRegistry {
+    /// Creates a new `CodecRegistry` without any codecs
+    pub fn empty() -> Self {
+        Self {
+            codecs: HashMap::new(),
+        }
+    }
+
+    /// Registers the given `codec` under its name. Replaces any codec with the same name that was registered before
+    pub fn register(&mut self, codec: Box<dyn AttributeCodec>) {
+        self.codecs.insert(codec.name().to_owned(), codec);
+    }
+
+    /// Returns the codec with the given `name`, or `None` if no such codec is registered
+    pub fn get(&self, name: &str) -> Option<&dyn AttributeCodec> {
+        self.codecs.get(name).map(|codec| codec.as_ref())
+    }
+
+    /// Decodes `count` values of the given `datatype` from `encoded` using the codec with the given `name`
+    pub fn decode(
+        &self,
+        name: &str,
+        datatype: PointAttributeDataType,
+        encoded: &[u8],
+        count: usize,
+    ) -> Result<Vec<u8>> {
+        self.get(name)
+            .ok_or_else(|| {
+                PastureIoError::UnsupportedFormat(format!(
+                    "No codec with name {} is registered",
+                    name
+                ))
+            })?
+            .decode(datatype, encoded, count)
+    }
+}
+
+impl Default for CodecRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register(Box::new(RawCodec));
+        registry.register(Box::new(DeltaCodec));
+        registry.register(Box::new(RunLengthCodec));
+        registry.register(Box::new(BitPackCodec));
+        #[cfg(feature = "zstd")]
+        registry.register(Box::new(DeltaZstdCodec::default()));
+        registry
+    }
+}
+
+fn check_values(datatype: PointAttributeDataType, values: &[u8]) -> Result<()> {
+    if values.len() % datatype.size() as usize != 0 {
+        return Err(PastureIoError::InvalidArgument(format!(
+            "Length of attribute data ({} bytes) is no multiple of the size of type {}",
+            values.len(),
+            datatype
+        )));
+    }
+    Ok(())
+}
+
+fn check_fully_consumed(rest: &[u8]) -> Result<()> {
+    if !rest.is_empty() {
+        return Err(PastureIoError::InvalidData(format!(
+            "Encoded data has {} trailing bytes",
+            rest.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the size of a single component and the number of components of the given datatype
+fn component_layout(datatype: PointAttributeDataType) -> (usize, usize) {
+    let (component_type, components) = datatype.components();
+    (component_type.size() as usize, components)
+}
+
+fn read_component(bytes: &[u8]) -> u64 {
+    let mut buffer = [0u8; 8];
+    buffer[..bytes.len()].copy_from_slice(bytes);
+    u64::from_le_bytes(buffer)
+}
+
+fn zigzag_encode(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn zigzag_decode(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+fn write_varint(target: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        target.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    target.push(value as u8);
+}
+
+fn read_varint(cursor: &mut &[u8]) -> Result<u64> {
+    let bytes: &[u8] = cursor;
+    let mut value = 0u64;
+    for (index, byte) in bytes.iter().enumerate().take(10) {
+        value |= ((byte & 0x7F) as u64) << (7 * index);
+        if byte & 0x80 == 0 {
+            *cursor = &bytes[index + 1..];
+            return Ok(value);
+        }
+    }
+    Err(PastureIoError::InvalidData(
+        "Encoded data ends in the middle of a value".into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(
+        codec: &dyn AttributeCodec,
+        datatype: PointAttributeDataType,
+        values: &[u8],
+    ) -> usize {
+        let count = values.len() / datatype.size() as usize;
+        let encoded = codec.encode(datatype, values).unwrap();
+        let decoded = CodecRegistry::default()
+            .decode(codec.name(), datatype, &encoded, count)
+            .unwrap();
+        assert_eq!(values, decoded.as_slice());
+        encoded.len()
+    }
+
+    #[test]
+    fn test_delta_codec_compresses_gps_times_and_positions() {
+        let gps_times = (0..1000)
+            .flat_map(|index| (3.5e8 + index as f64 * 1e-5).to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let encoded_size = roundtrip(&DeltaCodec, PointAttributeDataType::F64, &gps_times);
+        assert!(encoded_size * 2 < gps_times.len());
+
+        let positions = (0..1000)
+            .flat_map(|index| {
+                let position = [index as f64 * 0.01, -5.0 - index as f64, 100.0];
+                position
+                    .iter()
+                    .flat_map(|component| component.to_le_bytes().to_vec())
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        roundtrip(&DeltaCodec, PointAttributeDataType::Vec3f64, &positions);
+
+        let decreasing = (0..300u16)
+            .rev()
+            .flat_map(|value| value.to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let encoded_size = roundtrip(&DeltaCodec, PointAttributeDataType::U16, &decreasing);
+        assert_eq!(301, encoded_size);
+    }
+
+    #[test]
+    fn test_run_length_and_bit_pack_codecs() {
+        let classifications = [2u8, 2, 2, 2, 6, 6, 2, 9, 9, 9];
+        let encoded_size = roundtrip(
+            &RunLengthCodec,
+            PointAttributeDataType::U8,
+            &classifications,
+        );
+        assert_eq!(8, encoded_size);
+
+        let return_numbers = (0..100)
+            .map(|index| (index % 5 + 1) as u8)
+            .collect::<Vec<_>>();
+        let encoded_size = roundtrip(&BitPackCodec, PointAttributeDataType::U8, &return_numbers);
+        assert_eq!(1 + 300 / 8 + 1, encoded_size);
+        assert!(BitPackCodec
+            .encode(PointAttributeDataType::U16, &[0, 0])
+            .is_err());
+
+        roundtrip(&RunLengthCodec, PointAttributeDataType::U8, &[]);
+        roundtrip(&BitPackCodec, PointAttributeDataType::Bool, &[]);
+    }
+
+    #[test]
+    fn test_run_length_codec_rejects_too_long_runs() {
+        // A single run of u64::MAX values must fail before it is expanded
+        let mut encoded = vec![];
+        write_varint(&mut encoded, u64::MAX);
+        encoded.push(2);
+        assert!(RunLengthCodec
+            .decode(PointAttributeDataType::U8, &encoded, 10)
+            .is_err());
+
+        let mut encoded = vec![];
+        write_varint(&mut encoded, 3);
+        encoded.push(2);
+        write_varint(&mut encoded, 2);
+        encoded.push(6);
+        assert!(RunLengthCodec
+            .decode(PointAttributeDataType::U8, &encoded, 4)
+            .is_err());
+        assert_eq!(
+            vec![2, 2, 2, 6, 6],
+            RunLengthCodec
+                .decode(PointAttributeDataType::U8, &encoded, 5)
+                .unwrap()
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_delta_zstd_codec() {
+        let gps_times = (0..1000)
+            .flat_map(|index| (3.5e8 + index as f64 * 1e-5).to_le_bytes().to_vec())
+            .collect::<Vec<_>>();
+        let delta_size = roundtrip(&DeltaCodec, PointAttributeDataType::F64, &gps_times);
+        let zstd_size = roundtrip(
+            &DeltaZstdCodec::default(),
+            PointAttributeDataType::F64,
+            &gps_times,
+        );
+        assert!(zstd_size < delta_size);
+        assert_eq!(
+            "delta-zstd",
+            default_codec_for_attribute(&attributes::GPS_TIME).name()
+        );
+        assert!(DeltaZstdCodec::default()
+            .decode(PointAttributeDataType::F64, &[1, 2, 3], 1)
+            .is_err());
+    }
+
+    #[test]
+    fn test_custom_codecs_can_be_registered() {
+        struct InvertCodec;
+        impl AttributeCodec for InvertCodec {
+            fn name(&self) -> &str {
+                "invert"
+            }
+
+            fn encode(&self, _datatype: PointAttributeDataType, values: &[u8]) -> Result<Vec<u8>> {
+                Ok(values.iter().map(|value| !value).collect())
+            }
+
+            fn decode(
+                &self,
+                _datatype: PointAttributeDataType,
+                encoded: &[u8],
+                _count: usize,
+            ) -> Result<Vec<u8>> {
+                Ok(encoded.iter().map(|value| !value).collect())
+            }
+        }
+
+        let mut registry = CodecRegistry::default();
+        assert!(registry.get("invert").is_none());
+        registry.register(Box::new(InvertCodec));
+        assert_eq!(
+            vec![1, 2, 3],
+            registry
+                .decode("invert", PointAttributeDataType::U8, &[!1, !2, !3], 3)
+                .unwrap()
+        );
+
+        #[cfg(not(feature = "zstd"))]
+        assert_eq!(
+            "delta",
+            default_codec_for_attribute(&attributes::GPS_TIME).name()
+        );
+        assert_eq!(
+            "rle",
+            default_codec_for_attribute(&attributes::CLASSIFICATION).name()
+        );
+        assert_eq!(
+            "bitpack",
+            default_codec_for_attribute(&attributes::RETURN_NUMBER).name()
+        );
+        assert_eq!(
+            "raw",
+            default_codec_for_attribute(&attributes::INTENSITY).name()
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use std::ops::Range;
+
+use pasture_core::{
+    containers::{transform_positions, PointBufferWriteable},
+    math::AABB,
+    nalgebra::{Matrix4, Point3, Vector3},
+};
+
+/// An axis of a coordinate system, optionally negated. Used to describe how the axes of a source coordinate system map
+/// to the axes of a target coordinate system, see [AxisMapping]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SignedAxis {
+    X,
+    Y,
+    Z,
+    NegX,
+    NegY,
+    NegZ,
+}
+
+impl SignedAxis {
+    /// Returns the index (0 for X, 1 for Y, 2 for Z) and the sign of this axis
+    pub fn index_and_sign(&self) -> (usize, f64) {
+        match self {
+            SignedAxis::X => (0, 1.0),
+            SignedAxis::Y => (1, 1.0),
+            SignedAxis::Z => (2, 1.0),
+            SignedAxis::NegX => (0, -1.0),
+            SignedAxis::NegY => (1, -1.0),
+            SignedAxis::NegZ => (2, -1.0),
+        }
+    }
+}
+
+/// Mapping from the axes of a source coordinate system to the axes of a target coordinate system, e.g. to convert
+/// between Z-up and Y-up conventions. Each target axis is one of the source axes, optionally negated
+///
+/// ```
+/// # use pasture_io::base::*;
+/// # use pasture_core::nalgebra::Vector3;
+/// let mapping = AxisMapping::y_up_to_z_up();
+/// assert_eq!(Vector3::new(1.0, -3.0, 2.0), mapping.apply(&Vector3::new(1.0, 2.0, 3.0)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AxisMapping {
+    axes: [SignedAxis; 3],
+}
+
+impl AxisMapping {
+    /// Creates a new `AxisMapping` where the X, Y and Z axes of the target coordinate system are the given axes of the
+    /// source coordinate system
+    ///
+    /// # Panics
+    ///
+    /// If the same source axis is used for more than one target axis
+    pub fn new(x: SignedAxis, y: SignedAxis, z: SignedAxis) -> Self {
+        let (x_index, _) = x.index_and_sign();
+        let (y_index, _) = y.index_and_sign();
+        let (z_index, _) = z.index_and_sign();
+        if x_index == y_index || x_index == z_index || y_index == z_index {
+            panic!("AxisMapping::new: Each source axis must be used exactly once");
+        }
+        Self { axes: [x, y, z] }
+    }
+
+    /// Returns the `AxisMapping` that keeps all axes
+    pub fn identity() -> Self {
+        Self::new(SignedAxis::X, SignedAxis::Y, SignedAxis::Z)
+    }
+
+    /// Returns the `AxisMapping` from a right-handed Y-up coordinate system (as used by glTF and many mesh formats) to a
+    /// right-handed Z-up coordinate system (as used by most point cloud formats)
+    pub fn y_up_to_z_up() -> Self {
+        Self::new(SignedAxis::X, SignedAxis::NegZ, SignedAxis::Y)
+    }
+
+    /// Returns the `AxisMapping` from a right-handed Z-up coordinate system to a right-handed Y-up coordinate system.
+    /// This is the inverse of [y_up_to_z_up](AxisMapping::y_up_to_z_up)
+    pub fn z_up_to_y_up() -> Self {
+        Self::new(SignedAxis::X, SignedAxis::Z, SignedAxis::NegY)
+    }
+
+    /// Returns the source axes of the X, Y and Z axes of the target coordinate system
+    pub fn axes(&self) -> &[SignedAxis; 3] {
+        &self.axes
+    }
+
+    /// Applies this `AxisMapping` to the given `vector`
+    pub fn apply(&self, vector: &Vector3<f64>) -> Vector3<f64> {
+        Vector3::from_fn(|target_index, _| {
+            let (source_index, sign) = self.axes[target_index].index_and_sign();
+            sign * vector[source_index]
+        })
+    }
+
+    /// Returns this `AxisMapping` as a transformation matrix in homogeneous coordinates
+    pub fn matrix(&self) -> Matrix4<f64> {
+        let mut matrix = Matrix4::zeros();
+        for (target_index, axis) in self.axes.iter().enumerate() {
+            let (source_index, sign) = axis.index_and_sign();
+            matrix[(target_index, source_index)] = sign;
+        }
+        matrix[(3, 3)] = 1.0;
+        matrix
+    }
+}
+
+impl Default for AxisMapping {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+/// Units of length that point cloud coordinates are commonly stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthUnit {
+    Meters,
+    /// International feet, which are exactly 0.3048 meters
+    InternationalFeet,
+    /// US survey feet, which are exactly 1200/3937 meters. Many US state plane coordinate systems use this unit
+    UsSurveyFeet,
+}
+
+impl LengthUnit {
+    /// Returns the length of one unit in meters
+    pub fn meters_per_unit(&self) -> f64 {
+        match self {
+            LengthUnit::Meters => 1.0,
+            LengthUnit::InternationalFeet => 0.3048,
+            LengthUnit::UsSurveyFeet => 1200.0 / 3937.0,
+        }
+    }
+
+    /// Returns the factor that converts lengths in this unit into lengths in the `target` unit
+    pub fn conversion_factor(&self, target: LengthUnit) -> f64 {
+        if *self == target {
+            return 1.0;
+        }
+        self.meters_per_unit() / target.meters_per_unit()
+    }
+}
+
+/// Transformation of the positions of points at read time, combining an [AxisMapping], a conversion between
+/// [LengthUnit]s and an arbitrary affine transformation. All parts are combined into a single matrix, so applying
+/// the transformation costs one matrix-vector product per point, no matter how many parts it has. The parts are
+/// applied in the order in which they are added:
+///
+/// ```
+/// # use pasture_io::base::*;
+/// # use pasture_core::nalgebra::{Translation3, Vector3};
+/// let transform = CoordinateTransform::identity()
+///     .with_axis_mapping(AxisMapping::y_up_to_z_up())
+///     .with_unit_conversion(LengthUnit::InternationalFeet, LengthUnit::Meters)
+///     .with_matrix(Translation3::new(100.0, 0.0, 0.0).to_homogeneous());
+/// let position = transform.apply(&Vector3::new(10.0, 20.0, 30.0));
+/// assert!((position - Vector3::new(103.048, -9.144, 6.096)).amax() < 1e-9);
+/// ```
+///
+/// Readers that support a `CoordinateTransform` apply it to the `POSITION_3D` attribute of all points that they read.
+/// The metadata of the reader (e.g. the bounds) is not transformed, use [transform_bounds](CoordinateTransform::transform_bounds)
+/// for this
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CoordinateTransform {
+    matrix: Matrix4<f64>,
+}
+
+impl CoordinateTransform {
+    /// Returns the `CoordinateTransform` that does not change the positions
+    pub fn identity() -> Self {
+        Self {
+            matrix: Matrix4::identity(),
+        }
+    }
+
+    /// Creates a new `CoordinateTransform` from the given affine transformation `matrix` in homogeneous coordinates
+    pub fn from_matrix(matrix: Matrix4<f64>) -> Self {
+        Self { matrix }
+    }
+
+    /// Adds the given `AxisMapping` after the current transformation
+    pub fn with_axis_mapping(self, axis_mapping: AxisMapping) -> Self {
+        self.with_matrix(axis_mapping.matrix())
+    }
+
+    /// Adds a conversion of the positions from the `source` unit to the `target` unit after the current transformation
+    pub fn with_unit_conversion(self, source: LengthUnit, target: LengthUnit) -> Self {
+        let factor = source.conversion_factor(target);
+        self.with_matrix(Matrix4::new_nonuniform_scaling(&Vector3::repeat(factor)))
+    }
+
+    /// Adds the given affine transformation `matrix` in homogeneous coordinates after the current transformation
+    pub fn with_matrix(self, matrix: Matrix4<f64>) -> Self {
+        Self {
+            matrix: matrix * self.matrix,
+        }
+    }
+
+    /// Returns the combined transformation matrix in homogeneous coordinates
+    pub fn matrix(&self) -> &Matrix4<f64> {
+        &self.matrix
+    }
+
+    /// Is this the identity transformation?
+    pub fn is_identity(&self) -> bool {
+        self.matrix == Matrix4::identity()
+    }
+
+    /// Applies this `CoordinateTransform` to the given `position`
+    pub fn apply(&self, position: &Vector3<f64>) -> Vector3<f64> {
+        self.matrix.transform_point(&Point3::from(*position)).coords
+    }
+
+    /// Returns the bounds of the transformed corners of `bounds`
+    pub fn transform_bounds(&self, bounds: &AABB<f64>) -> AABB<f64> {
+        let (min, max) = (bounds.min(), bounds.max());
+        let corners = (0..8).map(|corner| {
+            Vector3::new(
+                if corner & 1 == 0 { min.x } else { max.x },
+                if corner & 2 == 0 { min.y } else { max.y },
+                if corner & 4 == 0 { min.z } else { max.z },
+            )
+        });
+        let mut transformed_min = Vector3::repeat(f64::INFINITY);
+        let mut transformed_max = Vector3::repeat(f64::NEG_INFINITY);
+        for corner in corners {
+            let transformed = self.apply(&corner);
+            transformed_min = transformed_min.inf(&transformed);
+            transformed_max = transformed_max.sup(&transformed);
+        }
+        AABB::from_min_max_unchecked(transformed_min.into(), transformed_max.into())
+    }
+
+    /// Applies this `CoordinateTransform` to the `POSITION_3D` attribute of the points in `range` of `buffer`. Positions
+    /// with the datatypes `Vec3f64` and `Vec3f32` are transformed, buffers without positions or with positions of other
+    /// datatypes (such as the raw integer coordinates of LAS files) are not changed
+    ///
+    /// # Panics
+    ///
+    /// If `range` is out of bounds for `buffer`
+    pub fn apply_to_buffer(&self, buffer: &mut dyn PointBufferWriteable, range: Range<usize>) {
+        if !self.is_identity() {
+            transform_positions(buffer, range, &self.matrix);
+        }
+    }
+}
+
+impl Default for CoordinateTransform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::{
+            InterleavedVecPointStorage, PointBuffer, PointBufferExt, PointBufferWriteableExt,
+        },
+        layout::{
+            attributes::{INTENSITY, POSITION_3D},
+            PointLayout,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_axis_mapping() {
+        let position = Vector3::new(1.0, 2.0, 3.0);
+        assert_eq!(position, AxisMapping::identity().apply(&position));
+
+        let z_up = AxisMapping::y_up_to_z_up().apply(&position);
+        assert_eq!(Vector3::new(1.0, -3.0, 2.0), z_up);
+        assert_eq!(position, AxisMapping::z_up_to_y_up().apply(&z_up));
+
+        let mapping = AxisMapping::new(SignedAxis::NegY, SignedAxis::X, SignedAxis::Z);
+        let expected = Vector3::new(-2.0, 1.0, 3.0);
+        assert_eq!(expected, mapping.apply(&position));
+        assert_eq!(
+            expected,
+            CoordinateTransform::identity()
+                .with_axis_mapping(mapping)
+                .apply(&position)
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_axis_mapping_duplicate_axis() {
+        AxisMapping::new(SignedAxis::X, SignedAxis::NegX, SignedAxis::Z);
+    }
+
+    #[test]
+    fn test_unit_conversion() {
+        assert_eq!(
+            1.0,
+            LengthUnit::UsSurveyFeet.conversion_factor(LengthUnit::UsSurveyFeet)
+        );
+        let us_survey_feet_to_meters = CoordinateTransform::identity()
+            .with_unit_conversion(LengthUnit::UsSurveyFeet, LengthUnit::Meters);
+        let position = us_survey_feet_to_meters.apply(&Vector3::new(3937.0, 0.0, -3937.0));
+        assert!((position - Vector3::new(1200.0, 0.0, -1200.0)).amax() < 1e-9);
+
+        let feet = CoordinateTransform::identity()
+            .with_unit_conversion(LengthUnit::Meters, LengthUnit::InternationalFeet)
+            .apply(&Vector3::new(0.3048, 0.6096, 0.0));
+        assert!((feet - Vector3::new(1.0, 2.0, 0.0)).amax() < 1e-9);
+    }
+
+    #[test]
+    fn test_transform_bounds() {
+        let transform =
+            CoordinateTransform::identity().with_axis_mapping(AxisMapping::y_up_to_z_up());
+        let bounds = AABB::from_min_max(Point3::new(0.0, 1.0, 2.0), Point3::new(1.0, 2.0, 4.0));
+        let transformed = transform.transform_bounds(&bounds);
+        assert_eq!(&Point3::new(0.0, -4.0, 1.0), transformed.min());
+        assert_eq!(&Point3::new(1.0, -2.0, 2.0), transformed.max());
+    }
+
+    #[test]
+    fn test_apply_to_buffer() {
+        let transform = CoordinateTransform::identity()
+            .with_axis_mapping(AxisMapping::y_up_to_z_up())
+            .with_unit_conversion(LengthUnit::InternationalFeet, LengthUnit::Meters);
+
+        let mut buffer = InterleavedVecPointStorage::new(PointLayout::from_attributes(&[
+            POSITION_3D,
+            INTENSITY,
+        ]));
+        buffer.resize(3);
+        for index in 0..3 {
+            let value = index as f64;
+            buffer.set_attribute(&POSITION_3D, index, Vector3::new(value, 1.0, 2.0));
+            buffer.set_attribute(&INTENSITY, index, index as u16);
+        }
+
+        // Only the points in the range are transformed
+        transform.apply_to_buffer(&mut buffer, 1..3);
+        assert_eq!(
+            Vector3::new(0.0, 1.0, 2.0),
+            buffer.get_attribute::<Vector3<f64>>(&POSITION_3D, 0)
+        );
+        for index in 1..3 {
+            let expected = Vector3::new(index as f64 * 0.3048, -2.0 * 0.3048, 0.3048);
+            let actual = buffer.get_attribute::<Vector3<f64>>(&POSITION_3D, index);
+            assert!((expected - actual).amax() < 1e-9);
+            assert_eq!(index as u16, buffer.get_attribute::<u16>(&INTENSITY, index));
+        }
+        assert_eq!(3, buffer.len());
+    }
+}
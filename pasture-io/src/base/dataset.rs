@@ -0,0 +1,354 @@
+use std::{
+    cmp::Ordering,
+    fs::read_dir,
+    path::{Path, PathBuf},
+};
+
+use pasture_core::{
+    containers::{
+        InterleavedPointView, InterleavedVecPointStorage, PointBuffer, PointBufferExt,
+        PointBufferWriteable, SpatialQuery, SpatialQueryable,
+    },
+    layout::{attributes::POSITION_3D, PointLayout},
+    math::{RTree, AABB},
+    nalgebra::Vector3,
+};
+
+use super::{bounds_of_positions, distance_to_bounds, IOFactory, PastureIoError, Result};
+
+/// Number of points that are read at once while loading a tile
+const TILE_READ_CHUNK_SIZE: usize = 1_000_000;
+
+/// A single file of a [Dataset]
+#[derive(Debug, Clone, PartialEq)]
+pub struct DatasetTile {
+    /// Path to the file
+    pub path: PathBuf,
+    /// Bounding box of all points in the file
+    pub bounds: AABB<f64>,
+}
+
+/// A collection of point cloud files (tiles) that can be queried like a single point cloud. When the `Dataset` is
+/// created, only the headers of the files are read to obtain their bounds. Spatial queries (see [SpatialQueryable])
+/// then only open the files whose bounds can contain matching points. The points of the most recently used tiles are
+/// kept in memory, up to a configurable number of points, so that repeated queries in the same area don't read the
+/// same files again
+///
+/// All points are read in the `PointLayout` of the `Dataset`, which is the default `PointLayout` of the first tile,
+/// unless it is set with [with_point_layout](Dataset::with_point_layout)
+pub struct Dataset {
+    tiles: Vec<DatasetTile>,
+    // Indices of the tiles by their bounds
+    tile_index: RTree<usize>,
+    layout: PointLayout,
+    factory: IOFactory,
+    max_cached_points: usize,
+    // Loaded tiles as (tile index, points), the most recently used tile is at the end
+    cache: Vec<(usize, InterleavedVecPointStorage)>,
+    cache_hits: usize,
+    cache_misses: usize,
+}
+
+impl Dataset {
+    /// Creates a `Dataset` from all files in the given directory that can be read with the default [IOFactory]. Files
+    /// in subdirectories are ignored
+    ///
+    /// # Errors
+    ///
+    /// If the directory can't be read, if it contains no readable files, or if the bounds of a file can't be
+    /// determined, an error is returned
+    pub fn from_directory<P: AsRef<Path>>(directory: P) -> Result<Self> {
+        let factory: IOFactory = Default::default();
+        let mut files = vec![];
+        for entry in read_dir(directory.as_ref())? {
+            let path = entry?.path();
+            let is_supported = path
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| factory.supports_reading_from(extension))
+                .unwrap_or(false);
+            if path.is_file() && is_supported {
+                files.push(path);
+            }
+        }
+        // Sort the files so that the tile indices don't depend on the order of the directory entries
+        files.sort();
+        Self::from_files(&files)
+    }
+
+    /// Creates a `Dataset` from the given files
+    ///
+    /// # Errors
+    ///
+    /// If `files` is empty, if a file can't be opened, or if the bounds of a file can't be determined, an error is
+    /// returned
+    pub fn from_files<P: AsRef<Path>>(files: &[P]) -> Result<Self> {
+        let factory: IOFactory = Default::default();
+        let mut layout = None;
+        let mut tiles = vec![];
+        for file in files {
+            let path = file.as_ref();
+            let mut reader = factory.make_reader(path)?;
+            let layout = layout.get_or_insert_with(|| reader.get_default_point_layout().clone());
+            let bounds = match reader.get_metadata().bounds() {
+                Some(bounds) => bounds,
+                None => {
+                    // Without bounds in the header, the bounds have to be calculated from all points
+                    let mut points = InterleavedVecPointStorage::new(layout.clone());
+                    while reader.read_into(&mut points, TILE_READ_CHUNK_SIZE)? > 0 {}
+                    bounds_of_positions(&points).ok_or_else(|| {
+                        PastureIoError::InvalidData(format!(
+                            "Can't determine the bounds of {}",
+                            path.display()
+                        ))
+                    })?
+                }
+            };
+            tiles.push(DatasetTile {
+                path: path.to_owned(),
+                bounds,
+            });
+        }
+        let layout = layout.ok_or_else(|| {
+            PastureIoError::InvalidArgument("A Dataset requires at least one file".into())
+        })?;
+        let tile_index = RTree::new(
+            tiles
+                .iter()
+                .enumerate()
+                .map(|(index, tile)| (tile.bounds, index))
+                .collect(),
+        );
+        Ok(Self {
+            tiles,
+            tile_index,
+            layout,
+            factory,
+            max_cached_points: 10_000_000,
+            cache: vec![],
+            cache_hits: 0,
+            cache_misses: 0,
+        })
+    }
+
+    /// Reads all points in the given `layout` instead of the default `PointLayout` of the first tile. The layout must
+    /// contain a `POSITION_3D` attribute
+    pub fn with_point_layout(mut self, layout: PointLayout) -> Self {
+        self.layout = layout;
+        self.cache.clear();
+        self
+    }
+
+    /// Keeps at most (roughly) `max_cached_points` points of recently used tiles in memory. The most recently used
+    /// tile is always kept, even if it has more points. The default is 10 million points
+    pub fn with_cache_size(mut self, max_cached_points: usize) -> Self {
+        self.max_cached_points = max_cached_points;
+        self.evict();
+        self
+    }
+
+    /// Returns all tiles of this `Dataset`
+    pub fn tiles(&self) -> &[DatasetTile] {
+        &self.tiles
+    }
+
+    /// Returns the bounding box of all tiles of this `Dataset`
+    pub fn bounds(&self) -> AABB<f64> {
+        self.tiles
+            .iter()
+            .skip(1)
+            .fold(self.tiles[0].bounds, |bounds, tile| {
+                AABB::union(&bounds, &tile.bounds)
+            })
+    }
+
+    /// Returns the indices of all tiles whose bounds intersect `bounds` in ascending order
+    pub fn tiles_in_bounds(&self, bounds: &AABB<f64>) -> Vec<usize> {
+        let mut tiles = self
+            .tile_index
+            .intersecting(bounds)
+            .into_iter()
+            .copied()
+            .collect::<Vec<_>>();
+        tiles.sort_unstable();
+        tiles
+    }
+
+    /// Returns how often a tile was requested by a query and was already in memory
+    pub fn cache_hits(&self) -> usize {
+        self.cache_hits
+    }
+
+    /// Returns how often a tile was requested by a query and had to be read from its file
+    pub fn cache_misses(&self) -> usize {
+        self.cache_misses
+    }
+
+    /// Returns the points of the tile with the given index, reading them from the file if they are not in memory
+    ///
+    /// # Errors
+    ///
+    /// If the file of the tile can't be read, an error is returned
+    pub fn load_tile(&mut self, index: usize) -> Result<&InterleavedVecPointStorage> {
+        match self.cache.iter().position(|(tile, _)| *tile == index) {
+            Some(position) => {
+                self.cache_hits += 1;
+                let entry = self.cache.remove(position);
+                self.cache.push(entry);
+            }
+            None => {
+                self.cache_misses += 1;
+                let mut reader = self.factory.make_reader(&self.tiles[index].path)?;
+                let mut points = InterleavedVecPointStorage::new(self.layout.clone());
+                while reader.read_into(&mut points, TILE_READ_CHUNK_SIZE)? > 0 {}
+                self.cache.push((index, points));
+                self.evict();
+            }
+        }
+        Ok(&self.cache.last().unwrap().1)
+    }
+
+    /// Removes the least recently used tiles from the cache until it has at most `max_cached_points` points
+    fn evict(&mut self) {
+        let mut cached_points = self
+            .cache
+            .iter()
+            .map(|(_, points)| points.len())
+            .sum::<usize>();
+        while cached_points > self.max_cached_points && self.cache.len() > 1 {
+            let (_, points) = self.cache.remove(0);
+            cached_points -= points.len();
+        }
+    }
+}
+
+impl SpatialQueryable for Dataset {
+    fn point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    /// Runs `query` on all tiles that can contain matching points. The tiles of a `Dataset` have no level-of-detail
+    /// hierarchy, so `resolution` is ignored
+    fn query(
+        &mut self,
+        query: &SpatialQuery,
+        _resolution: Option<f64>,
+        points: &mut dyn PointBufferWriteable,
+    ) -> anyhow::Result<usize> {
+        let point_size = self.layout.size_of_point_entry() as usize;
+        // Raw memory of the matching points
+        let mut matches = vec![];
+        match query {
+            SpatialQuery::Nearest { position, k } => {
+                let mut tiles = self
+                    .tiles
+                    .iter()
+                    .enumerate()
+                    .map(|(index, tile)| (distance_to_bounds(position, &tile.bounds), index))
+                    .collect::<Vec<_>>();
+                tiles.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                // (distance, raw memory) of the k closest points so far
+                let mut candidates: Vec<(f64, Vec<u8>)> = vec![];
+                for (tile_distance, tile) in tiles {
+                    if candidates.len() == *k && (*k == 0 || tile_distance > candidates[*k - 1].0) {
+                        break;
+                    }
+                    let tile_points = self.load_tile(tile)?;
+                    for (index, point_position) in tile_points
+                        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+                        .enumerate()
+                    {
+                        let mut point = vec![0; point_size];
+                        tile_points.get_raw_point(index, &mut point);
+                        candidates.push(((point_position - position).norm(), point));
+                    }
+                    candidates
+                        .sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                    candidates.truncate(*k);
+                }
+                for (_, point) in candidates {
+                    matches.extend_from_slice(&point);
+                }
+            }
+            _ => {
+                let tiles = match query.search_bounds() {
+                    Some(search_bounds) => self.tiles_in_bounds(&search_bounds),
+                    None => vec![],
+                };
+                for tile in tiles {
+                    let tile_points = self.load_tile(tile)?;
+                    for (index, point_position) in tile_points
+                        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+                        .enumerate()
+                    {
+                        if query.contains(&point_position) {
+                            let start = matches.len();
+                            matches.resize(start + point_size, 0);
+                            tile_points.get_raw_point(index, &mut matches[start..]);
+                        }
+                    }
+                }
+            }
+        }
+
+        points.push(&InterleavedPointView::from_raw_slice(
+            &matches,
+            self.layout.clone(),
+        ));
+        Ok(matches.len() / point_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::las::{get_test_las_path, get_test_laz_path};
+
+    #[test]
+    fn test_dataset_queries_load_only_relevant_tiles() -> Result<()> {
+        let mut dataset = Dataset::from_files(&[get_test_las_path(1), get_test_laz_path(1)])?;
+        assert_eq!(2, dataset.tiles().len());
+        let bounds = dataset.bounds();
+
+        let mut points = InterleavedVecPointStorage::new(dataset.point_layout().clone());
+        assert_eq!(
+            20,
+            dataset.query(&SpatialQuery::Bounds(bounds), None, &mut points)?
+        );
+        assert_eq!(2, dataset.cache_misses());
+
+        // A query outside of the dataset opens no files
+        let outside = AABB::from_min_max(
+            bounds.max() + Vector3::new(1.0, 1.0, 1.0),
+            bounds.max() + Vector3::new(2.0, 2.0, 2.0),
+        );
+        assert_eq!(
+            0,
+            dataset.query(&SpatialQuery::Bounds(outside), None, &mut points)?
+        );
+        assert_eq!(2, dataset.cache_misses());
+        assert_eq!(0, dataset.cache_hits());
+
+        // Both tiles contain the same points, so the 4 nearest neighbors are two pairs of identical points
+        let mut nearest = InterleavedVecPointStorage::new(dataset.point_layout().clone());
+        let position = bounds.min().coords;
+        let query = SpatialQuery::Nearest { position, k: 4 };
+        assert_eq!(4, dataset.query(&query, None, &mut nearest)?);
+        let positions = nearest
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .collect::<Vec<_>>();
+        assert_eq!(positions[0], positions[1]);
+        assert_eq!(positions[2], positions[3]);
+        assert!((positions[0] - position).norm() <= (positions[2] - position).norm());
+        assert_eq!(2, dataset.cache_hits());
+
+        // With a cache for only one tile, only the most recently used tile is kept
+        let mut dataset = dataset.with_cache_size(10);
+        dataset.load_tile(1)?;
+        assert_eq!(3, dataset.cache_hits());
+        dataset.load_tile(0)?;
+        dataset.load_tile(1)?;
+        assert_eq!(4, dataset.cache_misses());
+        Ok(())
+    }
+}
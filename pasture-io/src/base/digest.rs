@@ -0,0 +1,454 @@
+use std::{fmt::Display, hash::Hasher, ops::Range, str::FromStr};
+
+use pasture_core::{
+    containers::{PointBuffer, PointBufferWriteable},
+    layout::{PointAttributeDefinition, PointLayout},
+    meta::Metadata,
+};
+use sha2::{Digest, Sha256};
+use twox_hash::XxHash64;
+
+use super::{Capabilities, PastureIoError, PointReader, PointWriter, Result};
+
+/// Algorithms for computing a [ContentDigest]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DigestAlgorithm {
+    /// 64-bit xxHash with seed 0. Very fast, but not cryptographically secure, so it detects accidental corruption but
+    /// not deliberate manipulation
+    XxHash64,
+    /// SHA-256, which is slower but cryptographically secure
+    Sha256,
+}
+
+impl DigestAlgorithm {
+    /// Returns the name of this algorithm, which is also accepted by `from_str`
+    pub fn name(&self) -> &'static str {
+        match self {
+            DigestAlgorithm::XxHash64 => "xxhash64",
+            DigestAlgorithm::Sha256 => "sha256",
+        }
+    }
+}
+
+impl Display for DigestAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for DigestAlgorithm {
+    type Err = PastureIoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "xxhash64" | "xxhash" => Ok(DigestAlgorithm::XxHash64),
+            "sha256" | "sha-256" => Ok(DigestAlgorithm::Sha256),
+            _ => Err(PastureIoError::InvalidArgument(format!(
+                "Unknown digest algorithm {} (supported are xxhash64 and sha256)",
+                s
+            ))),
+        }
+    }
+}
+
+/// A digest of some content, together with the algorithm that computed it. Displayed as `algorithm:hexdigest`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContentDigest {
+    algorithm: DigestAlgorithm,
+    bytes: Vec<u8>,
+}
+
+impl ContentDigest {
+    /// Returns the algorithm that computed this digest
+    pub fn algorithm(&self) -> DigestAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns the bytes of this digest
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns the bytes of this digest as a lowercase hex string
+    pub fn to_hex(&self) -> String {
+        self.bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+impl Display for ContentDigest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.to_hex())
+    }
+}
+
+enum DigesterState {
+    XxHash64(XxHash64),
+    Sha256(Sha256),
+}
+
+/// Incremental computation of a [ContentDigest]. The digest of some content does not depend on how the content is
+/// split into calls to `update`
+pub struct Digester {
+    state: DigesterState,
+}
+
+impl Digester {
+    /// Creates a new `Digester` for the given `algorithm`
+    pub fn new(algorithm: DigestAlgorithm) -> Self {
+        let state = match algorithm {
+            DigestAlgorithm::XxHash64 => DigesterState::XxHash64(XxHash64::with_seed(0)),
+            DigestAlgorithm::Sha256 => DigesterState::Sha256(Sha256::new()),
+        };
+        Self { state }
+    }
+
+    /// Adds the given `bytes` to the digested content
+    pub fn update(&mut self, bytes: &[u8]) {
+        match &mut self.state {
+            DigesterState::XxHash64(hasher) => hasher.write(bytes),
+            DigesterState::Sha256(hasher) => hasher.update(bytes),
+        }
+    }
+
+    /// Returns the digest of all content that was passed to `update`
+    pub fn finish(self) -> ContentDigest {
+        match self.state {
+            DigesterState::XxHash64(hasher) => ContentDigest {
+                algorithm: DigestAlgorithm::XxHash64,
+                bytes: hasher.finish().to_be_bytes().to_vec(),
+            },
+            DigesterState::Sha256(hasher) => ContentDigest {
+                algorithm: DigestAlgorithm::Sha256,
+                bytes: hasher.finalize().to_vec(),
+            },
+        }
+    }
+}
+
+/// Computes one digest per attribute of a `PointLayout` over the decoded values of the attribute for all points, in the
+/// order in which the points are passed to `update`. Each attribute is digested as the contiguous stream of its values
+/// in memory, so the digests do not depend on the memory layout of the buffers or on how the points are split into
+/// buffers, but they do depend on the datatypes of the attributes. Two files with the same attribute digests contain the
+/// same point data, even if their formats or compression differ
+pub struct AttributeDigester {
+    layout: PointLayout,
+    digesters: Vec<Digester>,
+    attribute_data: Vec<u8>,
+}
+
+impl AttributeDigester {
+    /// Creates a new `AttributeDigester` for all attributes in `layout`
+    pub fn new(layout: PointLayout, algorithm: DigestAlgorithm) -> Self {
+        let digesters = layout
+            .attributes()
+            .map(|_| Digester::new(algorithm))
+            .collect();
+        Self {
+            layout,
+            digesters,
+            attribute_data: vec![],
+        }
+    }
+
+    /// Returns the `PointLayout` of the digested points
+    pub fn point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    /// Adds the attributes of the given `points` to the digests
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `points` does not match the `PointLayout` of this `AttributeDigester`
+    pub fn update<B: PointBuffer + ?Sized>(&mut self, points: &B) {
+        self.update_range(points, 0..points.len());
+    }
+
+    /// Adds the attributes of the points in `index_range` within `points` to the digests
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `points` does not match the `PointLayout` of this `AttributeDigester`, or if
+    /// `index_range` is out of bounds
+    pub fn update_range<B: PointBuffer + ?Sized>(&mut self, points: &B, index_range: Range<usize>) {
+        if *points.point_layout() != self.layout {
+            panic!("AttributeDigester::update_range: PointLayout of points does not match the digested PointLayout");
+        }
+        if index_range.is_empty() {
+            return;
+        }
+        for (attribute, digester) in self.layout.attributes().zip(self.digesters.iter_mut()) {
+            let attribute: PointAttributeDefinition = attribute.into();
+            self.attribute_data
+                .resize(index_range.len() * attribute.size() as usize, 0);
+            points.get_raw_attribute_range(
+                index_range.clone(),
+                &attribute,
+                &mut self.attribute_data,
+            );
+            digester.update(&self.attribute_data);
+        }
+    }
+
+    /// Returns the digest of each attribute, in the order of the attributes in the `PointLayout`
+    pub fn finish(self) -> Vec<(PointAttributeDefinition, ContentDigest)> {
+        self.layout
+            .attributes()
+            .map(|attribute| attribute.into())
+            .zip(self.digesters.into_iter().map(Digester::finish))
+            .collect()
+    }
+}
+
+/// Computes the attribute digests of a single buffer of `points`, see [AttributeDigester]
+pub fn attribute_digests(
+    points: &dyn PointBuffer,
+    algorithm: DigestAlgorithm,
+) -> Vec<(PointAttributeDefinition, ContentDigest)> {
+    let mut digester = AttributeDigester::new(points.point_layout().clone(), algorithm);
+    digester.update(points);
+    digester.finish()
+}
+
+/// Wrapper around a `PointReader` that computes the attribute digests (see [AttributeDigester]) of all points that are
+/// read through it. The digests use the `PointLayout` of the buffers that the points are read into, which must be the
+/// same for all reads
+///
+/// ```no_run
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::LASReader;
+/// let reader = LASReader::from_path("in.laz").unwrap();
+/// let mut reader = DigestReader::new(reader, DigestAlgorithm::Sha256);
+/// while reader.read(50_000).unwrap().len() > 0 {}
+/// for (attribute, digest) in reader.finish().0 {
+///     println!("{}: {}", attribute.name(), digest);
+/// }
+/// ```
+pub struct DigestReader<R: PointReader> {
+    reader: R,
+    algorithm: DigestAlgorithm,
+    digester: Option<AttributeDigester>,
+}
+
+impl<R: PointReader> DigestReader<R> {
+    /// Creates a new `DigestReader` that wraps the given `reader` and digests with the given `algorithm`
+    pub fn new(reader: R, algorithm: DigestAlgorithm) -> Self {
+        Self {
+            reader,
+            algorithm,
+            digester: None,
+        }
+    }
+
+    /// Returns a reference to the wrapped reader
+    pub fn inner(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the attribute digests of all points that were read so far, together with the wrapped reader. If no
+    /// points were read, the digests are empty
+    pub fn finish(self) -> (Vec<(PointAttributeDefinition, ContentDigest)>, R) {
+        let digests = self
+            .digester
+            .map(AttributeDigester::finish)
+            .unwrap_or_default();
+        (digests, self.reader)
+    }
+
+    fn digester_for(&mut self, layout: &PointLayout) -> Result<&mut AttributeDigester> {
+        let algorithm = self.algorithm;
+        let digester = self
+            .digester
+            .get_or_insert_with(|| AttributeDigester::new(layout.clone(), algorithm));
+        if digester.point_layout() != layout {
+            return Err(PastureIoError::LayoutMismatch(
+                "DigestReader: All points must be read with the same PointLayout".into(),
+            ));
+        }
+        Ok(digester)
+    }
+}
+
+impl<R: PointReader> PointReader for DigestReader<R> {
+    fn read(&mut self, count: usize) -> Result<Box<dyn PointBuffer>> {
+        let points = self.reader.read(count)?;
+        if !points.is_empty() {
+            self.digester_for(points.point_layout())?
+                .update(points.as_ref());
+        }
+        Ok(points)
+    }
+
+    fn read_into(
+        &mut self,
+        point_buffer: &mut dyn PointBufferWriteable,
+        count: usize,
+    ) -> Result<usize> {
+        let first_new_point = point_buffer.len();
+        let points_read = self.reader.read_into(point_buffer, count)?;
+        if points_read > 0 {
+            let layout = point_buffer.point_layout().clone();
+            self.digester_for(&layout)?.update_range(
+                &*point_buffer,
+                first_new_point..first_new_point + points_read,
+            );
+        }
+        Ok(points_read)
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        self.reader.get_metadata()
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        self.reader.get_default_point_layout()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        // Seeking is not supported, because the digests require that all points are read in order
+        Capabilities {
+            seekable: false,
+            ..self.reader.capabilities()
+        }
+    }
+}
+
+/// Wrapper around a `PointWriter` that computes the attribute digests (see [AttributeDigester]) of all points that are
+/// written through it, in the `PointLayout` of the written buffers. Comparing these digests with the digests of a
+/// [DigestReader] that reads the written file back verifies that the points survived the round trip
+pub struct DigestWriter<W: PointWriter> {
+    writer: W,
+    algorithm: DigestAlgorithm,
+    digester: Option<AttributeDigester>,
+}
+
+impl<W: PointWriter> DigestWriter<W> {
+    /// Creates a new `DigestWriter` that wraps the given `writer` and digests with the given `algorithm`
+    pub fn new(writer: W, algorithm: DigestAlgorithm) -> Self {
+        Self {
+            writer,
+            algorithm,
+            digester: None,
+        }
+    }
+
+    /// Returns a reference to the wrapped writer
+    pub fn inner(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns the attribute digests of all points that were written so far, together with the wrapped writer. If no
+    /// points were written, the digests are empty
+    pub fn finish(self) -> (Vec<(PointAttributeDefinition, ContentDigest)>, W) {
+        let digests = self
+            .digester
+            .map(AttributeDigester::finish)
+            .unwrap_or_default();
+        (digests, self.writer)
+    }
+}
+
+impl<W: PointWriter> PointWriter for DigestWriter<W> {
+    fn write(&mut self, points: &dyn PointBuffer) -> Result<()> {
+        self.writer.write(points)?;
+        if points.is_empty() {
+            return Ok(());
+        }
+        let algorithm = self.algorithm;
+        let digester = self.digester.get_or_insert_with(|| {
+            AttributeDigester::new(points.point_layout().clone(), algorithm)
+        });
+        if digester.point_layout() != points.point_layout() {
+            return Err(PastureIoError::LayoutMismatch(
+                "DigestWriter: All points must be written with the same PointLayout".into(),
+            ));
+        }
+        digester.update(points);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        self.writer.get_default_point_layout()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.writer.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use pasture_core::containers::InterleavedVecPointStorage;
+
+    use super::*;
+    use crate::las::{get_test_las_path, get_test_laz_path, LASReader, LASWriter};
+
+    #[test]
+    fn test_digester_is_independent_of_chunking() {
+        let mut digester = Digester::new(DigestAlgorithm::Sha256);
+        digester.update(b"a");
+        digester.update(b"bc");
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            digester.finish().to_hex()
+        );
+
+        let mut whole = Digester::new(DigestAlgorithm::XxHash64);
+        whole.update(b"point records");
+        let mut split = Digester::new(DigestAlgorithm::XxHash64);
+        split.update(b"point ");
+        split.update(b"records");
+        let digest = whole.finish();
+        assert_eq!(8, digest.bytes().len());
+        assert_eq!(digest, split.finish());
+        assert!(digest.to_string().starts_with("xxhash64:"));
+
+        assert_eq!(
+            DigestAlgorithm::Sha256,
+            "SHA256".parse::<DigestAlgorithm>().unwrap()
+        );
+        assert!("md5".parse::<DigestAlgorithm>().is_err());
+    }
+
+    #[test]
+    fn test_digest_reader_and_writer_agree() -> Result<()> {
+        let las_reader = LASReader::from_path(get_test_las_path(0))?;
+        let header = las_reader.header().clone();
+        let mut reader = DigestReader::new(las_reader, DigestAlgorithm::XxHash64);
+        let mut writer = DigestWriter::new(
+            LASWriter::from_writer_and_header(Cursor::new(vec![]), header)?,
+            DigestAlgorithm::XxHash64,
+        );
+        loop {
+            let points = reader.read(4)?;
+            if points.is_empty() {
+                break;
+            }
+            writer.write(points.as_ref())?;
+        }
+        let (read_digests, _) = reader.finish();
+        let (written_digests, _) = writer.finish();
+        assert!(!read_digests.is_empty());
+        assert_eq!(read_digests, written_digests);
+
+        // Reading into a buffer and reading the compressed file yield the same digests
+        let laz_reader = LASReader::from_path(get_test_laz_path(0))?;
+        let mut points =
+            InterleavedVecPointStorage::new(laz_reader.get_default_point_layout().clone());
+        let mut reader = DigestReader::new(laz_reader, DigestAlgorithm::XxHash64);
+        assert_eq!(6, reader.read_into(&mut points, 6)?);
+        assert_eq!(4, reader.read_into(&mut points, 6)?);
+        assert_eq!(read_digests, reader.finish().0);
+        assert_eq!(
+            read_digests,
+            attribute_digests(&points, DigestAlgorithm::XxHash64)
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt::Display;
+
+/// Errors that can occur while reading or writing point cloud files. All fallible functions of pasture-io return a
+/// `PastureIoError`, so library consumers can match on the kind of failure, e.g. to implement retries or fallbacks:
+///
+/// ```no_run
+/// # use pasture_io::base::*;
+/// # use std::path::Path;
+/// let factory: IOFactory = Default::default();
+/// match factory.make_reader(Path::new("points.xyz")) {
+///     Ok(reader) => (),
+///     Err(PastureIoError::UnsupportedFormat(_)) => println!("Unsupported file format, trying a fallback"),
+///     Err(PastureIoError::Io(io_error)) => println!("I/O error: {}", io_error),
+///     Err(error) => println!("Error: {}", error),
+/// }
+/// ```
+///
+/// `PastureIoError` implements `std::error::Error`, so it converts into `anyhow::Error` and similar error types with
+/// the `?` operator
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PastureIoError {
+    /// The file format, or a feature of the file format (e.g. a specific LAS point format), is not supported
+    UnsupportedFormat(String),
+    /// The `PointLayout` of a buffer does not match the `PointLayout` that a reader or writer expects
+    LayoutMismatch(String),
+    /// A file header (or a record within the header section) is malformed. `offset` is the byte offset of the corrupt
+    /// header or record within the file
+    CorruptHeader { offset: u64, message: String },
+    /// The version of the file format is not supported
+    UnsupportedVersion { major: u32, minor: u32 },
+    /// An I/O error occurred
+    Io(std::io::Error),
+    /// The point data or a record after the header is malformed or inconsistent, e.g. a corrupt compressed chunk or an
+    /// invalid JSON document
+    InvalidData(String),
+    /// A function was called with arguments that it can't handle, e.g. a chunk size of zero or a point index past the
+    /// end of the file
+    InvalidArgument(String),
+    /// Any other error, e.g. an error of a library that pasture-io uses internally or an error that was returned by a
+    /// callback of the caller
+    Other(Box<dyn std::error::Error + Send + Sync + 'static>),
+}
+
+/// `Result` type of all fallible functions of pasture-io
+pub type Result<T, E = PastureIoError> = std::result::Result<T, E>;
+
+impl Display for PastureIoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PastureIoError::UnsupportedFormat(message) => {
+                write!(f, "Unsupported format: {}", message)
+            }
+            PastureIoError::LayoutMismatch(message) => write!(f, "Layout mismatch: {}", message),
+            PastureIoError::CorruptHeader { offset, message } => {
+                write!(f, "Corrupt header at byte offset {}: {}", offset, message)
+            }
+            PastureIoError::UnsupportedVersion { major, minor } => {
+                write!(f, "Unsupported version {}.{}", major, minor)
+            }
+            PastureIoError::Io(io_error) => write!(f, "I/O error: {}", io_error),
+            PastureIoError::InvalidData(message) => write!(f, "Invalid data: {}", message),
+            PastureIoError::InvalidArgument(message) => {
+                write!(f, "Invalid argument: {}", message)
+            }
+            PastureIoError::Other(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for PastureIoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PastureIoError::Io(io_error) => Some(io_error),
+            PastureIoError::Other(error) => error.source(),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for PastureIoError {
+    fn from(io_error: std::io::Error) -> Self {
+        PastureIoError::Io(io_error)
+    }
+}
+
+/// Errors of callbacks and of pasture-core are `anyhow::Error`s. If such an error wraps a `PastureIoError`, e.g.
+/// because a callback used `?` on the result of a pasture-io function, the `PastureIoError` is unwrapped
+impl From<anyhow::Error> for PastureIoError {
+    fn from(error: anyhow::Error) -> Self {
+        match error.downcast::<PastureIoError>() {
+            Ok(error) => error,
+            Err(error) => match error.downcast::<std::io::Error>() {
+                Ok(io_error) => PastureIoError::Io(io_error),
+                Err(error) => PastureIoError::Other(error.into()),
+            },
+        }
+    }
+}
+
+impl From<las::Error> for PastureIoError {
+    fn from(error: las::Error) -> Self {
+        PastureIoError::Other(Box::new(error))
+    }
+}
+
+impl From<serde_json::Error> for PastureIoError {
+    fn from(error: serde_json::Error) -> Self {
+        match error.classify() {
+            serde_json::error::Category::Io => PastureIoError::Io(error.into()),
+            _ => PastureIoError::InvalidData(error.to_string()),
+        }
+    }
+}
+
+impl From<bincode::Error> for PastureIoError {
+    fn from(error: bincode::Error) -> Self {
+        match *error {
+            bincode::ErrorKind::Io(io_error) => PastureIoError::Io(io_error),
+            error => PastureIoError::InvalidData(error.to_string()),
+        }
+    }
+}
+
+impl From<std::string::FromUtf8Error> for PastureIoError {
+    fn from(error: std::string::FromUtf8Error) -> Self {
+        PastureIoError::InvalidData(error.to_string())
+    }
+}
+
+impl From<std::str::Utf8Error> for PastureIoError {
+    fn from(error: std::str::Utf8Error) -> Self {
+        PastureIoError::InvalidData(error.to_string())
+    }
+}
+
+impl From<std::num::ParseIntError> for PastureIoError {
+    fn from(error: std::num::ParseIntError) -> Self {
+        PastureIoError::InvalidData(error.to_string())
+    }
+}
+
+impl From<std::num::ParseFloatError> for PastureIoError {
+    fn from(error: std::num::ParseFloatError) -> Self {
+        PastureIoError::InvalidData(error.to_string())
+    }
+}
+
+impl From<std::num::TryFromIntError> for PastureIoError {
+    fn from(error: std::num::TryFromIntError) -> Self {
+        PastureIoError::InvalidData(error.to_string())
+    }
+}
+
+impl From<std::array::TryFromSliceError> for PastureIoError {
+    fn from(error: std::array::TryFromSliceError) -> Self {
+        PastureIoError::InvalidData(error.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::{
+        base::IOFactory,
+        las::{get_test_las_path, LASReader},
+    };
+
+    #[test]
+    fn test_unsupported_extension_is_unsupported_format() {
+        let factory: IOFactory = Default::default();
+        let error = factory
+            .make_reader(Path::new("points.unsupported"))
+            .err()
+            .unwrap();
+        assert!(matches!(error, PastureIoError::UnsupportedFormat(_)));
+    }
+
+    #[test]
+    fn test_missing_file_is_io_error() {
+        let factory: IOFactory = Default::default();
+        let error = factory
+            .make_reader(Path::new("this_file_does_not_exist.las"))
+            .err()
+            .unwrap();
+        assert!(matches!(error, PastureIoError::Io(_)));
+    }
+
+    #[test]
+    fn test_unsupported_las_version() {
+        let mut data = std::fs::read(get_test_las_path(0)).unwrap();
+        // Version major and minor are at byte offsets 24 and 25 of the LAS header
+        data[24] = 2;
+        let error = LASReader::from_read(std::io::Cursor::new(data), false)
+            .err()
+            .unwrap();
+        assert!(matches!(
+            error,
+            PastureIoError::UnsupportedVersion { major: 2, .. }
+        ));
+    }
+
+    #[test]
+    fn test_error_from_anyhow() {
+        // Errors of pasture-io that pass through a callback keep their kind
+        let error: anyhow::Error = PastureIoError::InvalidArgument("test".into()).into();
+        assert!(matches!(
+            PastureIoError::from(error),
+            PastureIoError::InvalidArgument(_)
+        ));
+
+        let error = PastureIoError::from(anyhow::anyhow!("test"));
+        assert!(matches!(error, PastureIoError::Other(_)));
+        assert_eq!("test", error.to_string());
+    }
+}
@@ -0,0 +1,120 @@
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBuffer, PointBufferExt},
+    layout::{attributes::POSITION_3D, PointLayout},
+    math::AABB,
+    nalgebra::{Point3, Vector3},
+};
+
+use super::{PointReader, Result};
+
+/// How an `Estimate` was obtained, which gives an indication of how much it can be trusted
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum EstimateConfidence {
+    /// The value was taken from the header of the file. It is exact, unless the file was written incorrectly
+    Header,
+    /// The value was calculated from all points of the file, because the file was small enough to be read in full
+    /// while sampling
+    Exact,
+    /// The value was extrapolated from a sample of the points in the file and might be inaccurate
+    Sampled,
+}
+
+/// An estimated value together with its `EstimateConfidence`
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Estimate<T> {
+    /// The estimated value
+    pub value: T,
+    /// How the value was obtained
+    pub confidence: EstimateConfidence,
+}
+
+impl<T> Estimate<T> {
+    /// Creates a new `Estimate` from the given `value` and `confidence`
+    pub fn new(value: T, confidence: EstimateConfidence) -> Self {
+        Self { value, confidence }
+    }
+
+    /// Is the value of this `Estimate` exact, i.e. not extrapolated from a sample?
+    pub fn is_exact(&self) -> bool {
+        self.confidence != EstimateConfidence::Sampled
+    }
+}
+
+/// Options that control how `PointReader::estimate_point_count` and `PointReader::estimate_bounds` sample points if
+/// the file does not store the requested information in its header
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct EstimateOptions {
+    /// Fraction of the file that is sampled, in the range `(0;1]`. If the number of points is known, this is the
+    /// fraction of points, otherwise it is the fraction of the file size (if known)
+    pub sample_fraction: f64,
+    /// Upper limit for the number of points that are read while sampling
+    pub max_sample_count: usize,
+}
+
+impl EstimateOptions {
+    /// Returns the number of points to sample from a file with `point_count` points
+    pub(crate) fn sample_count(&self, point_count: usize) -> usize {
+        let sample_count = (point_count as f64 * self.sample_fraction).ceil() as usize;
+        sample_count
+            .max(1)
+            .min(self.max_sample_count)
+            .min(point_count)
+    }
+}
+
+impl Default for EstimateOptions {
+    fn default() -> Self {
+        Self {
+            sample_fraction: 0.01,
+            max_sample_count: 100_000,
+        }
+    }
+}
+
+/// Reads at most `count` points from `reader` and returns only their positions, or `None` if the default
+/// `PointLayout` of `reader` has no positions. This advances `reader`
+pub(crate) fn read_sample_positions<R: PointReader + ?Sized>(
+    reader: &mut R,
+    count: usize,
+) -> Result<Option<InterleavedVecPointStorage>> {
+    if !reader
+        .get_default_point_layout()
+        .has_attribute_with_name(POSITION_3D.name())
+    {
+        return Ok(None);
+    }
+    let mut positions = InterleavedVecPointStorage::with_capacity(
+        count,
+        PointLayout::from_attributes(&[POSITION_3D]),
+    );
+    reader.read_into(&mut positions, count)?;
+    Ok(Some(positions))
+}
+
+/// Calculates the bounding box of the `POSITION_3D` attribute of all points in `buffer`, or `None` if `buffer` is
+/// empty
+pub(crate) fn bounds_of_positions<B: PointBuffer + ?Sized>(buffer: &B) -> Option<AABB<f64>> {
+    let mut positions = buffer.iter_attribute::<Vector3<f64>>(&POSITION_3D);
+    let first: Point3<f64> = positions.next()?.into();
+    Some(positions.fold(
+        AABB::from_min_max_unchecked(first, first),
+        |bounds, position| AABB::extend_with_point(&bounds, &position.into()),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sample_count() {
+        let options = EstimateOptions {
+            sample_fraction: 0.1,
+            max_sample_count: 50,
+        };
+        assert_eq!(10, options.sample_count(100));
+        assert_eq!(50, options.sample_count(10_000));
+        assert_eq!(1, options.sample_count(3));
+        assert_eq!(0, options.sample_count(0));
+    }
+}
@@ -0,0 +1,428 @@
+use std::{
+    cmp::Ordering,
+    collections::BinaryHeap,
+    fs::{remove_file, File},
+    io::{BufReader, BufWriter, Cursor, ErrorKind, Read, Write},
+    marker::PhantomData,
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering as AtomicOrdering},
+};
+
+use pasture_core::{
+    containers::{InterleavedPointView, PointBuffer, PointBufferWriteable},
+    layout::PointLayout,
+};
+
+use super::{PastureIoError, Result};
+
+/// Counter to give the run files of all `ExternalSorter`s of this process unique names
+static NEXT_SORTER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Temporary files, such as the runs of an `ExternalSorter` or the spilled nodes of a `StreamingOctreeBuilder`, which
+/// are deleted when this is dropped
+pub(crate) struct RunFiles(pub(crate) Vec<PathBuf>);
+
+impl Drop for RunFiles {
+    fn drop(&mut self) {
+        for path in self.0.iter() {
+            // Nothing sensible can be done if a temporary file can't be deleted, so the error is ignored
+            let _ = remove_file(path);
+        }
+    }
+}
+
+/// Sorts point data that does not fit into memory. Points are pushed in chunks with [push](ExternalSorter::push).
+/// Whenever `max_points_in_memory` points are buffered, they are sorted and spilled to a temporary file (a 'run'). After
+/// all points are pushed, [finish](ExternalSorter::finish) returns an [ExternalSortMerger] that merges all runs with a
+/// k-way merge and returns the points in sorted order. The temporary files are deleted once the `ExternalSortMerger`
+/// (or the `ExternalSorter`, if it is never finished) is dropped
+///
+/// The sort key is calculated by `key_fn` from the raw memory of a single point in the `PointLayout` of the sorter.
+/// The offset of an attribute within this memory can be obtained with
+/// [offset_of](pasture_core::layout::PointLayout::offset_of). The sort is stable, so points with equal keys keep the
+/// order in which they were pushed
+///
+/// ```
+/// # use pasture_core::containers::{InterleavedVecPointStorage, PointBufferExt};
+/// # use pasture_core::layout::PointType;
+/// # use pasture_derive::PointType;
+/// # use pasture_io::base::ExternalSorter;
+/// #[repr(C)]
+/// #[derive(PointType, Debug, Copy, Clone)]
+/// struct Point {
+///     #[pasture(BUILTIN_INTENSITY)]
+///     intensity: u16,
+/// }
+/// let points: InterleavedVecPointStorage = vec![
+///     Point { intensity: 3 },
+///     Point { intensity: 1 },
+///     Point { intensity: 2 },
+/// ].into();
+/// let mut sorter = ExternalSorter::new(Point::layout(), 2, |point: &[u8]| {
+///     u16::from_le_bytes([point[0], point[1]])
+/// });
+/// sorter.push(&points).unwrap();
+///
+/// let mut sorted = InterleavedVecPointStorage::new(Point::layout());
+/// let mut merger = sorter.finish().unwrap();
+/// while merger.read_into(&mut sorted, 10).unwrap() > 0 {}
+/// let intensities = sorted.iter_point::<Point>().map(|point| point.intensity).collect::<Vec<_>>();
+/// assert_eq!(vec![1, 2, 3], intensities);
+/// ```
+pub struct ExternalSorter<K: Ord, F: Fn(&[u8]) -> K> {
+    layout: PointLayout,
+    key_fn: F,
+    max_points_in_memory: usize,
+    temp_dir: PathBuf,
+    sorter_id: usize,
+    point_count: usize,
+    points: Vec<u8>,
+    run_files: RunFiles,
+    _key: PhantomData<fn() -> K>,
+}
+
+impl<K: Ord, F: Fn(&[u8]) -> K> ExternalSorter<K, F> {
+    /// Creates a new `ExternalSorter` for points in the given `layout` that keeps at most `max_points_in_memory`
+    /// points in memory and sorts them by the key that `key_fn` calculates. Runs are written to the temporary
+    /// directory of the system, use [with_temp_dir](ExternalSorter::with_temp_dir) to change this
+    ///
+    /// # Panics
+    ///
+    /// If `max_points_in_memory` is zero
+    pub fn new(layout: PointLayout, max_points_in_memory: usize, key_fn: F) -> Self {
+        if max_points_in_memory == 0 {
+            panic!("ExternalSorter::new: max_points_in_memory must be > 0");
+        }
+        Self {
+            layout,
+            key_fn,
+            max_points_in_memory,
+            temp_dir: std::env::temp_dir(),
+            sorter_id: NEXT_SORTER_ID.fetch_add(1, AtomicOrdering::Relaxed),
+            point_count: 0,
+            points: vec![],
+            run_files: RunFiles(vec![]),
+            _key: PhantomData,
+        }
+    }
+
+    /// Writes the runs of this `ExternalSorter` to the given directory instead of the temporary directory of the
+    /// system. The directory needs enough free space for all points
+    pub fn with_temp_dir<P: Into<PathBuf>>(mut self, temp_dir: P) -> Self {
+        self.temp_dir = temp_dir.into();
+        self
+    }
+
+    /// Returns the number of points that have been pushed into this `ExternalSorter`
+    pub fn point_count(&self) -> usize {
+        self.point_count
+    }
+
+    /// Returns the number of runs that have been spilled to disk so far
+    pub fn run_count(&self) -> usize {
+        self.run_files.0.len()
+    }
+
+    /// Pushes the given `points` into this `ExternalSorter`
+    ///
+    /// # Errors
+    ///
+    /// If the `PointLayout` of `points` does not match the `PointLayout` of this `ExternalSorter`, or if a run can't be
+    /// written to disk, an error is returned
+    pub fn push(&mut self, points: &dyn PointBuffer) -> Result<()> {
+        if *points.point_layout() != self.layout {
+            return Err(PastureIoError::LayoutMismatch(
+                "PointLayout of the points does not match the PointLayout of the ExternalSorter"
+                    .into(),
+            ));
+        }
+        let point_size = self.layout.size_of_point_entry() as usize;
+        let mut pushed = 0;
+        while pushed < points.len() {
+            let buffered = self.points.len() / point_size;
+            let count = (self.max_points_in_memory - buffered).min(points.len() - pushed);
+            self.points.resize((buffered + count) * point_size, 0);
+            points.get_raw_points(
+                pushed..pushed + count,
+                &mut self.points[buffered * point_size..],
+            );
+            pushed += count;
+            if buffered + count == self.max_points_in_memory {
+                self.spill()?;
+            }
+        }
+        self.point_count += points.len();
+        Ok(())
+    }
+
+    /// Finishes pushing points and returns an `ExternalSortMerger` that returns all pushed points in sorted order
+    ///
+    /// # Errors
+    ///
+    /// If a run can't be read, an error is returned
+    pub fn finish(mut self) -> Result<ExternalSortMerger<K, F>> {
+        let mut runs = vec![];
+        for path in self.run_files.0.iter() {
+            runs.push(Box::new(BufReader::new(File::open(path)?)) as Box<dyn Read>);
+        }
+        // The points that are still in memory don't need to go through the disk, they become the last run
+        runs.push(Box::new(Cursor::new(self.sort_buffered_points())));
+
+        let mut merger = ExternalSortMerger {
+            point_size: self.layout.size_of_point_entry() as usize,
+            layout: self.layout,
+            key_fn: self.key_fn,
+            remaining_points: self.point_count,
+            runs,
+            heap: BinaryHeap::new(),
+            _run_files: self.run_files,
+        };
+        for run in 0..merger.runs.len() {
+            merger.advance_run(run)?;
+        }
+        Ok(merger)
+    }
+
+    /// Sorts the buffered points and returns their memory in sorted order
+    fn sort_buffered_points(&mut self) -> Vec<u8> {
+        let point_size = self.layout.size_of_point_entry() as usize;
+        let key_fn = &self.key_fn;
+        let mut keyed_points = self
+            .points
+            .chunks_exact(point_size)
+            .enumerate()
+            .map(|(index, point)| (key_fn(point), index))
+            .collect::<Vec<_>>();
+        // sort_by is stable, so points with equal keys stay in the order in which they were pushed
+        keyed_points.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let mut sorted = Vec::with_capacity(self.points.len());
+        for (_, index) in keyed_points {
+            sorted.extend_from_slice(&self.points[index * point_size..(index + 1) * point_size]);
+        }
+        self.points.clear();
+        sorted
+    }
+
+    /// Sorts the buffered points and writes them to a new run file
+    fn spill(&mut self) -> Result<()> {
+        let sorted = self.sort_buffered_points();
+        let path = self.temp_dir.join(format!(
+            "pasture-sort-{}-{}-{}.bin",
+            std::process::id(),
+            self.sorter_id,
+            self.run_files.0.len()
+        ));
+        // Register the file before writing, so that it is deleted even if writing fails
+        self.run_files.0.push(path.clone());
+        let mut writer = BufWriter::new(File::create(&path)?);
+        writer.write_all(&sorted)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// The next point of a run of an `ExternalSortMerger`. Ordered so that the `BinaryHeap` (a max-heap) returns the
+/// point with the smallest key first, and on equal keys the point from the earliest run, which keeps the sort stable
+struct MergeEntry<K: Ord> {
+    key: K,
+    run: usize,
+    point: Vec<u8>,
+}
+
+impl<K: Ord> PartialEq for MergeEntry<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<K: Ord> Eq for MergeEntry<K> {}
+
+impl<K: Ord> PartialOrd for MergeEntry<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord> Ord for MergeEntry<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .key
+            .cmp(&self.key)
+            .then_with(|| other.run.cmp(&self.run))
+    }
+}
+
+/// Merges the sorted runs of an [ExternalSorter] and returns the points in sorted order. Created by
+/// [ExternalSorter::finish]
+pub struct ExternalSortMerger<K: Ord, F: Fn(&[u8]) -> K> {
+    layout: PointLayout,
+    key_fn: F,
+    point_size: usize,
+    remaining_points: usize,
+    runs: Vec<Box<dyn Read>>,
+    heap: BinaryHeap<MergeEntry<K>>,
+    _run_files: RunFiles,
+}
+
+impl<K: Ord, F: Fn(&[u8]) -> K> ExternalSortMerger<K, F> {
+    /// Returns the `PointLayout` of the sorted points
+    pub fn point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    /// Returns the number of points that have not been read yet
+    pub fn remaining_points(&self) -> usize {
+        self.remaining_points
+    }
+
+    /// Reads the next (at most) `count` sorted points into the given `point_buffer`. Returns the number of points that
+    /// were read, which is zero once all points have been read
+    ///
+    /// # Errors
+    ///
+    /// If a run can't be read, an error is returned
+    ///
+    /// # Panics
+    ///
+    /// If the `PointLayout` of `point_buffer` does not match the `PointLayout` of the sorted points
+    pub fn read_into(
+        &mut self,
+        point_buffer: &mut dyn PointBufferWriteable,
+        count: usize,
+    ) -> Result<usize> {
+        let count = count.min(self.remaining_points);
+        let mut points = Vec::with_capacity(count * self.point_size);
+        for _ in 0..count {
+            let entry = self.heap.pop().ok_or_else(|| {
+                PastureIoError::InvalidData(
+                    "Runs of the ExternalSorter contain fewer points than were pushed".into(),
+                )
+            })?;
+            points.extend_from_slice(&entry.point);
+            self.advance_run(entry.run)?;
+        }
+        self.remaining_points -= count;
+        point_buffer.push(&InterleavedPointView::from_raw_slice(
+            &points,
+            self.layout.clone(),
+        ));
+        Ok(count)
+    }
+
+    /// Reads the next point of the given `run` into the heap, if the run has any points left
+    fn advance_run(&mut self, run: usize) -> Result<()> {
+        let mut point = vec![0; self.point_size];
+        let mut read = 0;
+        while read < self.point_size {
+            match self.runs[run].read(&mut point[read..]) {
+                Ok(0) if read == 0 => return Ok(()),
+                Ok(0) => {
+                    return Err(PastureIoError::InvalidData(format!(
+                        "Run {} ends in the middle of a point",
+                        run
+                    )))
+                }
+                Ok(bytes) => read += bytes,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        self.heap.push(MergeEntry {
+            key: (self.key_fn)(&point),
+            run,
+            point,
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasture_core::{
+        containers::{InterleavedVecPointStorage, PointBufferExt},
+        layout::PointType,
+    };
+    use pasture_derive::PointType;
+    use rand::{prelude::StdRng, Rng, SeedableRng};
+
+    #[repr(C, packed)]
+    #[derive(Copy, Clone, PartialEq, PointType, Debug)]
+    struct TestPoint {
+        #[pasture(BUILTIN_CLASSIFICATION)]
+        classification: u8,
+        #[pasture(BUILTIN_POINT_ID)]
+        id: u64,
+    }
+
+    fn classification_key(point: &[u8]) -> u8 {
+        point[0]
+    }
+
+    #[test]
+    fn external_sort_is_stable_across_runs() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(3);
+        let points = (0..1000)
+            .map(|id| TestPoint {
+                classification: rng.gen_range(0..8),
+                id,
+            })
+            .collect::<Vec<_>>();
+
+        let mut sorter = ExternalSorter::new(TestPoint::layout(), 64, classification_key);
+        for chunk in points.chunks(100) {
+            let chunk: InterleavedVecPointStorage = chunk.into();
+            sorter.push(&chunk)?;
+        }
+        assert_eq!(1000, sorter.point_count());
+        assert_eq!(15, sorter.run_count());
+        let run_files = sorter.run_files.0.clone();
+        assert!(run_files.iter().all(|path| path.exists()));
+
+        let mut merger = sorter.finish()?;
+        let mut sorted = InterleavedVecPointStorage::new(TestPoint::layout());
+        while merger.read_into(&mut sorted, 77)? > 0 {}
+        assert_eq!(0, merger.remaining_points());
+        drop(merger);
+        assert!(run_files.iter().all(|path| !path.exists()));
+
+        let mut expected = points;
+        expected.sort_by_key(|point| point.classification);
+        assert_eq!(
+            expected,
+            sorted.iter_point::<TestPoint>().collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn external_sort_in_memory() -> Result<()> {
+        let points: InterleavedVecPointStorage = vec![
+            TestPoint {
+                classification: 2,
+                id: 0,
+            },
+            TestPoint {
+                classification: 1,
+                id: 1,
+            },
+        ]
+        .into();
+        let mut sorter = ExternalSorter::new(TestPoint::layout(), 10, classification_key);
+        sorter.push(&points)?;
+        assert_eq!(0, sorter.run_count());
+
+        let mut merger = sorter.finish()?;
+        let mut sorted = InterleavedVecPointStorage::new(TestPoint::layout());
+        assert_eq!(1, merger.read_into(&mut sorted, 1)?);
+        assert_eq!(1, merger.read_into(&mut sorted, 5)?);
+        assert_eq!(0, merger.read_into(&mut sorted, 5)?);
+        let ids = sorted
+            .iter_point::<TestPoint>()
+            .map(|point| point.id)
+            .collect::<Vec<_>>();
+        assert_eq!(vec![1, 0], ids);
+        Ok(())
+    }
+}
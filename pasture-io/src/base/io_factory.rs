@@ -1,13 +1,79 @@
-use std::{collections::HashMap, path::Path};
+use std::{collections::HashMap, io::SeekFrom, path::Path};
 
-use anyhow::{anyhow, Result};
 use las_rs::Builder;
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBufferExt, PointBufferWriteable},
+    layout::{attributes::GPS_TIME, PointLayout},
+    math::AABB,
+};
 
 use crate::las::{LASReader, LASWriter};
 
-use super::{PointReader, PointWriter, SeekToPoint};
+use super::{PastureIoError, PointBlock, PointReader, PointWriter, Result, SeekToPoint};
 
-pub trait PointReadAndSeek: PointReader + SeekToPoint {}
+pub trait PointReadAndSeek: PointReader + SeekToPoint {
+    /// Seeks to the first point whose GPS time is greater than or equal to `gps_time` and returns its index. This
+    /// requires that the points are sorted by their GPS time, which is the case for most files produced by sequential
+    /// scanners. Uses a binary search, so only a logarithmic number of points is read. If all points have a smaller GPS
+    /// time, this seeks to the end of the stream
+    ///
+    /// # Errors
+    ///
+    /// If the points have no GPS time, a `PastureIoError::LayoutMismatch` is returned. If an error occurs while
+    /// seeking or reading, it is returned as well
+    fn seek_to_gps_time(&mut self, gps_time: f64) -> Result<usize> {
+        if !self
+            .get_default_point_layout()
+            .has_attribute_with_name(GPS_TIME.name())
+        {
+            return Err(PastureIoError::LayoutMismatch(
+                "Can't seek to GPS time because the points have no GPS time attribute".into(),
+            ));
+        }
+
+        let mut buffer =
+            InterleavedVecPointStorage::with_capacity(1, PointLayout::from_attributes(&[GPS_TIME]));
+        let mut low = 0;
+        let mut high = self.point_count()?;
+        while low < high {
+            let mid = low + (high - low) / 2;
+            self.seek_point(SeekFrom::Start(mid as u64))?;
+            buffer.clear();
+            self.read_into(&mut buffer, 1)?;
+            if buffer.get_attribute::<f64>(&GPS_TIME, 0) < gps_time {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        self.seek_point(SeekFrom::Start(low as u64))
+    }
+
+    /// Seeks to the first block of points from the spatial index of the underlying stream that starts at or after the
+    /// current point and whose bounds intersect `bounds`, and returns this block. Returns `None` if there is no such
+    /// block. To extract all points within `bounds`, call this repeatedly and read `point_count` points from each
+    /// returned block. Note that the blocks can also contain points outside of `bounds`
+    ///
+    /// # Errors
+    ///
+    /// If the underlying stream has no spatial index (see `SeekToPoint::point_blocks`), a
+    /// `PastureIoError::UnsupportedFormat` is returned. If an error occurs while seeking, it is returned as well
+    fn seek_to_block(&mut self, bounds: &AABB<f64>) -> Result<Option<PointBlock>> {
+        let blocks = self.point_blocks()?.ok_or_else(|| {
+            PastureIoError::UnsupportedFormat(
+                "Can't seek to a spatial block because the file has no spatial index".into(),
+            )
+        })?;
+        let current_point = self.point_index()?;
+        let block = blocks
+            .into_iter()
+            .find(|block| block.first_point >= current_point && block.bounds.intersects(bounds));
+        if let Some(block) = &block {
+            self.seek_point(SeekFrom::Start(block.first_point as u64))?;
+        }
+        Ok(block)
+    }
+}
 
 impl<T: PointReader + SeekToPoint> PointReadAndSeek for T {}
 
@@ -28,26 +94,26 @@ impl IOFactory {
     /// a format that is unsupported by Pasture, or if there are any I/O errors while trying to access `file`.
     pub fn make_reader(&self, file: &Path) -> Result<Box<dyn PointReadAndSeek>> {
         let extension = file.extension().ok_or_else(|| {
-            anyhow!(
+            PastureIoError::UnsupportedFormat(format!(
                 "File extension could not be determined from path {}",
                 file.display()
-            )
+            ))
         })?;
         let extension_str = extension.to_str().ok_or_else(|| {
-            anyhow!(
+            PastureIoError::UnsupportedFormat(format!(
                 "File extension of path {} is no valid Unicode string",
                 file.display()
-            )
+            ))
         })?;
         let extension_str_lower = extension_str.to_lowercase();
         let factory = self
             .reader_factories
             .get(extension_str_lower.as_str())
             .ok_or_else(|| {
-                anyhow!(
+                PastureIoError::UnsupportedFormat(format!(
                     "Reading from point cloud files with extension {} is not supported",
                     extension_str
-                )
+                ))
             })?;
 
         factory(file)
@@ -57,26 +123,26 @@ impl IOFactory {
     /// a format that is unsupported by Pasture, or if there are any I/O errors while trying to access `file`.
     pub fn make_writer(&self, file: &Path) -> Result<Box<dyn PointWriter>> {
         let extension = file.extension().ok_or_else(|| {
-            anyhow!(
+            PastureIoError::UnsupportedFormat(format!(
                 "File extension could not be determined from path {}",
                 file.display()
-            )
+            ))
         })?;
         let extension_str = extension.to_str().ok_or_else(|| {
-            anyhow!(
+            PastureIoError::UnsupportedFormat(format!(
                 "File extension of path {} is no valid Unicode string",
                 file.display()
-            )
+            ))
         })?;
         let extension_str_lower = extension_str.to_lowercase();
         let factory = self
             .writer_factories
             .get(extension_str_lower.as_str())
             .ok_or_else(|| {
-                anyhow!(
+                PastureIoError::UnsupportedFormat(format!(
                     "Writing to point cloud files with extension {} is not supported",
                     extension_str
-                )
+                ))
             })?;
 
         factory(file)
@@ -161,6 +227,8 @@ impl Default for IOFactory {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::las::get_test_las_path;
+    use pasture_core::nalgebra::Point3;
 
     #[test]
     fn io_factory_ignores_extension_case() {
@@ -179,4 +247,31 @@ mod tests {
         assert!(factory.supports_writing_to("foo"));
         assert!(factory.supports_writing_to("FOO"));
     }
+
+    #[test]
+    fn test_seek_to_gps_time() -> Result<()> {
+        // The GPS times of the test files are 1.0 to 10.0
+        let mut reader: Box<dyn PointReadAndSeek> =
+            Box::new(LASReader::from_path(get_test_las_path(1))?);
+        assert_eq!(4, reader.seek_to_gps_time(5.0)?);
+        let points = reader.read(1)?;
+        assert_eq!(5.0, points.get_attribute::<f64>(&GPS_TIME, 0));
+
+        assert_eq!(5, reader.seek_to_gps_time(5.5)?);
+        assert_eq!(0, reader.seek_to_gps_time(-100.0)?);
+        assert_eq!(10, reader.seek_to_gps_time(100.0)?);
+
+        // Format 0 has no GPS time
+        let mut reader = LASReader::from_path(get_test_las_path(0))?;
+        assert!(reader.seek_to_gps_time(5.0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_seek_to_block_without_spatial_index() -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(0))?;
+        let bounds = AABB::from_min_max(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+        assert!(reader.seek_to_block(&bounds).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,870 @@
+use std::{
+    collections::BTreeMap,
+    fs::{remove_file, File, OpenOptions},
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use pasture_core::{
+    containers::{
+        InterleavedPointView, InterleavedVecPointStorage, PointBuffer, PointBufferExt,
+        PointBufferWriteable,
+    },
+    layout::{
+        attributes::{CLASSIFICATION, POSITION_3D},
+        PointAttributeDataType, PointAttributeDefinition, PointLayout,
+    },
+    math::AABB,
+    nalgebra::{Point3, Vector3},
+};
+
+use super::{Capabilities, PastureIoError, PointWriter, Result};
+
+/// Selects the points of each buffer that is written through a `MaskedWriter`. A `PointSelection` sees the buffers in
+/// the order in which they are written, so it can also select points by their index in the whole stream of points
+pub struct PointSelection {
+    select: Box<dyn FnMut(&dyn PointBuffer) -> Vec<usize> + Send>,
+}
+
+impl PointSelection {
+    /// Creates a `PointSelection` from a function that returns the indices of the selected points within each buffer,
+    /// in ascending order
+    pub fn from_fn<F: FnMut(&dyn PointBuffer) -> Vec<usize> + Send + 'static>(select: F) -> Self {
+        Self {
+            select: Box::new(select),
+        }
+    }
+
+    /// Selects all points for which `predicate` returns `true`. `predicate` is called with each buffer and the index of
+    /// a point within the buffer
+    pub fn from_predicate<F: FnMut(&dyn PointBuffer, usize) -> bool + Send + 'static>(
+        mut predicate: F,
+    ) -> Self {
+        Self::from_fn(move |points| {
+            (0..points.len())
+                .filter(|index| predicate(points, *index))
+                .collect()
+        })
+    }
+
+    /// Selects all points whose `CLASSIFICATION` is one of the given `classes`
+    pub fn classifications(classes: &[u8]) -> Self {
+        let classes = classes.to_vec();
+        Self::from_fn(move |points| {
+            points
+                .iter_attribute_as::<u8>(&CLASSIFICATION)
+                .enumerate()
+                .filter(|(_, class)| classes.contains(class))
+                .map(|(index, _)| index)
+                .collect()
+        })
+    }
+
+    /// Selects all points whose `POSITION_3D` is within `bounds`
+    pub fn within_bounds(bounds: AABB<f64>) -> Self {
+        Self::from_fn(move |points| {
+            points
+                .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+                .enumerate()
+                .filter(|(_, position)| bounds.contains(&Point3::from(*position)))
+                .map(|(index, _)| index)
+                .collect()
+        })
+    }
+
+    /// Selects the points for which `mask` is `true`, where `mask` is indexed by the position of the points in the whole
+    /// stream of written points. Points beyond the end of `mask` are not selected
+    pub fn mask(mask: Vec<bool>) -> Self {
+        let mut first_point_index = 0;
+        Self::from_fn(move |points| {
+            let selected = (0..points.len())
+                .filter(|index| {
+                    mask.get(first_point_index + index)
+                        .copied()
+                        .unwrap_or(false)
+                })
+                .collect();
+            first_point_index += points.len();
+            selected
+        })
+    }
+
+    /// Returns the indices of the selected points within `points`
+    pub fn select(&mut self, points: &dyn PointBuffer) -> Vec<usize> {
+        (self.select)(points)
+    }
+}
+
+/// Copies the points with the given `indices` within `points` into a new buffer with the given `layout`, which must
+/// contain a subset of the attributes of `points`
+fn copy_points(
+    points: &dyn PointBuffer,
+    indices: &[usize],
+    layout: &PointLayout,
+) -> InterleavedVecPointStorage {
+    let mut copied = InterleavedVecPointStorage::with_capacity(indices.len(), layout.clone());
+    copied.resize(indices.len());
+    let mut value = vec![];
+    for attribute in layout.attributes() {
+        let attribute: PointAttributeDefinition = attribute.into();
+        value.resize(attribute.size() as usize, 0);
+        for (target_index, source_index) in indices.iter().enumerate() {
+            points.get_raw_attribute(*source_index, &attribute, &mut value);
+            copied.set_raw_attribute(target_index, &attribute, &value);
+        }
+    }
+    copied
+}
+
+/// Wrapper around a `PointWriter` that writes only some attributes and only some points of each buffer. Attributes
+/// are excluded by name, and points are selected by a `PointSelection`. The filtered points are copied chunk by chunk
+/// while writing, so there is no need to materialize a filtered copy of the whole point cloud first
+///
+/// ```no_run
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::{LASReader, LASWriter};
+/// # use pasture_core::layout::attributes;
+/// let mut reader = LASReader::from_path("in.las").unwrap();
+/// let header = reader.header().clone();
+/// let writer = LASWriter::from_path_and_header("ground.las", header).unwrap();
+/// let mut writer = MaskedWriter::new(writer)
+///     .with_excluded_attributes(&[attributes::GPS_TIME.name()])
+///     .with_selection(PointSelection::classifications(&[2]));
+/// while let Ok(points) = reader.read(50_000) {
+///     if points.is_empty() {
+///         break;
+///     }
+///     writer.write(points.as_ref()).unwrap();
+/// }
+/// ```
+pub struct MaskedWriter<W: PointWriter> {
+    writer: W,
+    excluded_attributes: Vec<String>,
+    selection: Option<PointSelection>,
+}
+
+impl<W: PointWriter> MaskedWriter<W> {
+    /// Creates a new `MaskedWriter` that wraps the given `writer`. Initially, all attributes and all points are written
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            excluded_attributes: vec![],
+            selection: None,
+        }
+    }
+
+    /// Excludes the attributes with the given names from the written points. Writers that require one of these
+    /// attributes write their default value instead
+    pub fn with_excluded_attributes(mut self, attribute_names: &[&str]) -> Self {
+        self.excluded_attributes
+            .extend(attribute_names.iter().map(|name| name.to_string()));
+        self
+    }
+
+    /// Writes only the points that are selected by `selection`
+    pub fn with_selection(mut self, selection: PointSelection) -> Self {
+        self.selection = Some(selection);
+        self
+    }
+
+    /// Returns a reference to the wrapped writer
+    pub fn inner(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns the wrapped writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    fn written_layout(&self, layout: &PointLayout) -> Result<PointLayout> {
+        if let Some(missing) = self
+            .excluded_attributes
+            .iter()
+            .find(|name| !layout.has_attribute_with_name(name))
+        {
+            return Err(PastureIoError::LayoutMismatch(format!(
+                "Can't exclude attribute {}, the points have no attribute with this name",
+                missing
+            )));
+        }
+        let attributes = layout
+            .attributes()
+            .filter(|attribute| {
+                !self
+                    .excluded_attributes
+                    .iter()
+                    .any(|name| name == attribute.name())
+            })
+            .map(PointAttributeDefinition::from)
+            .collect::<Vec<_>>();
+        Ok(PointLayout::from_attributes(&attributes))
+    }
+}
+
+impl<W: PointWriter> PointWriter for MaskedWriter<W> {
+    fn write(&mut self, points: &dyn PointBuffer) -> Result<()> {
+        let selected = self
+            .selection
+            .as_mut()
+            .map(|selection| selection.select(points));
+        if self.excluded_attributes.is_empty() && selected.is_none() {
+            return self.writer.write(points);
+        }
+
+        let layout = self.written_layout(points.point_layout())?;
+        let indices = selected.unwrap_or_else(|| (0..points.len()).collect());
+        if indices.is_empty() {
+            return Ok(());
+        }
+        self.writer.write(&copy_points(points, &indices, &layout))
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        self.writer.get_default_point_layout()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.writer.capabilities()
+    }
+}
+
+/// Reads the value of an integer or boolean attribute from its raw bytes
+fn integer_attribute_value(datatype: PointAttributeDataType, bytes: &[u8]) -> i64 {
+    match datatype {
+        PointAttributeDataType::U8 | PointAttributeDataType::Bool => bytes[0] as i64,
+        PointAttributeDataType::I8 => bytes[0] as i8 as i64,
+        PointAttributeDataType::U16 => u16::from_ne_bytes([bytes[0], bytes[1]]) as i64,
+        PointAttributeDataType::I16 => i16::from_ne_bytes([bytes[0], bytes[1]]) as i64,
+        PointAttributeDataType::U32 => {
+            u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64
+        }
+        PointAttributeDataType::I32 => {
+            i32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as i64
+        }
+        PointAttributeDataType::U64 | PointAttributeDataType::I64 => {
+            let mut value = [0; 8];
+            value.copy_from_slice(&bytes[..8]);
+            i64::from_ne_bytes(value)
+        }
+        other => unreachable!("{} is not an integer datatype", other),
+    }
+}
+
+/// Number of points and bounds of the points that a [SplitWriter] has written for one key
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SplitOutput {
+    /// Number of points written for the key
+    pub point_count: usize,
+    /// Bounds of the points written for the key, or `None` if no points with a `POSITION_3D` attribute were written
+    pub bounds: Option<AABB<f64>>,
+}
+
+impl SplitOutput {
+    fn add_points(&mut self, points: &dyn PointBuffer) {
+        self.point_count += points.len();
+        if !points
+            .point_layout()
+            .has_attribute_with_name(POSITION_3D.name())
+        {
+            return;
+        }
+        for position in points.iter_attribute_as::<Vector3<f64>>(&POSITION_3D) {
+            let position = Point3::from(position);
+            self.bounds = Some(match &self.bounds {
+                Some(bounds) => AABB::extend_with_point(bounds, &position),
+                None => AABB::from_min_max_unchecked(position, position),
+            });
+        }
+    }
+}
+
+/// Counter to give the spill files of all `SplitWriter`s of this process unique names
+static NEXT_SPILL_FILES_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of points that are copied at once from a spill file into the writer of its key
+const SPILL_CONVERSION_CHUNK_SIZE: usize = 1_000_000;
+
+/// Temporary file with the raw memory of the points of one key of a [SplitWriter]
+struct SpillFile {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    last_used: usize,
+}
+
+/// The spill files of a [SplitWriter] that keeps only a bounded number of files open
+struct SpillFiles<K> {
+    temp_dir: PathBuf,
+    max_open_files: usize,
+    id: usize,
+    files: BTreeMap<K, SpillFile>,
+    /// Layout of the spilled points, which is the layout of the first written points
+    layout: Option<PointLayout>,
+    open_files: usize,
+    time: usize,
+}
+
+impl<K: Ord + Clone> SpillFiles<K> {
+    /// Appends `points` to the spill file of `key`, of which `spilled_points` points were written successfully before
+    fn write(&mut self, key: &K, points: &dyn PointBuffer, spilled_points: usize) -> Result<()> {
+        let layout = self
+            .layout
+            .get_or_insert_with(|| points.point_layout().clone());
+        if *points.point_layout() != *layout {
+            return Err(PastureIoError::LayoutMismatch(format!(
+                "SplitWriter::write_to: Points must be in layout {}",
+                layout
+            )));
+        }
+        let point_size = layout.size_of_point_entry() as usize;
+
+        if !self.files.contains_key(key) {
+            let path = self.temp_dir.join(format!(
+                "pasture-split-{}-{}-{}.bin",
+                std::process::id(),
+                self.id,
+                self.files.len()
+            ));
+            self.files.insert(
+                key.clone(),
+                SpillFile {
+                    path,
+                    writer: None,
+                    last_used: 0,
+                },
+            );
+        }
+        if self.files[key].writer.is_none() {
+            self.close_least_recently_used()?;
+            let file = self.files.get_mut(key).unwrap();
+            let spill_file = OpenOptions::new()
+                .create(true)
+                .write(true)
+                .open(&file.path)?;
+            // Removes the points of a previous write that failed in the middle
+            spill_file.set_len((spilled_points * point_size) as u64)?;
+            let mut writer = BufWriter::new(spill_file);
+            writer.seek(SeekFrom::End(0))?;
+            file.writer = Some(writer);
+            self.open_files += 1;
+        }
+        self.time += 1;
+        let file = self.files.get_mut(key).unwrap();
+        file.last_used = self.time;
+
+        let mut raw_points = vec![0; points.len() * point_size];
+        points.get_raw_points(0..points.len(), &mut raw_points);
+        if let Err(e) = file.writer.as_mut().unwrap().write_all(&raw_points) {
+            // The spill file is truncated to the points that were written successfully once it is reopened
+            file.writer = None;
+            self.open_files -= 1;
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// Closes the least recently used spill file if `max_open_files` files are open
+    fn close_least_recently_used(&mut self) -> Result<()> {
+        if self.open_files < self.max_open_files {
+            return Ok(());
+        }
+        let least_recently_used = self
+            .files
+            .values_mut()
+            .filter(|file| file.writer.is_some())
+            .min_by_key(|file| file.last_used);
+        if let Some(file) = least_recently_used {
+            self.open_files -= 1;
+            file.writer.take().unwrap().flush()?;
+        }
+        Ok(())
+    }
+
+    /// Closes the spill file of `key` and returns a reader for its points
+    fn reader(&mut self, key: &K) -> Result<BufReader<File>> {
+        let file = self.files.get_mut(key).unwrap();
+        if let Some(mut writer) = file.writer.take() {
+            self.open_files -= 1;
+            writer.flush()?;
+        }
+        Ok(BufReader::new(File::open(&file.path)?))
+    }
+
+    /// Removes the spill file of `key`
+    fn remove(&mut self, key: &K) {
+        if let Some(mut file) = self.files.remove(key) {
+            if file.writer.take().is_some() {
+                self.open_files -= 1;
+            }
+            // Nothing sensible can be done if a spill file can't be deleted, so the error is ignored
+            let _ = remove_file(&file.path);
+        }
+    }
+}
+
+impl<K> Drop for SpillFiles<K> {
+    fn drop(&mut self) {
+        for file in self.files.values_mut() {
+            // Closes the spill file before removing it
+            file.writer = None;
+            // Nothing sensible can be done if a spill file can't be deleted, so the error is ignored
+            let _ = remove_file(&file.path);
+        }
+    }
+}
+
+/// `PointWriter` that splits the written points into many outputs, e.g. into one file per classification or one file
+/// per tile of a grid. With [new](SplitWriter::new), the points are split by the value of an integer attribute when
+/// they are written through `PointWriter::write`. With [by_key](SplitWriter::by_key), the caller assigns the points to
+/// the outputs with [write_to](SplitWriter::write_to)
+///
+/// By default, the writer for each key is created on the fly when the first points for this key are written, so there
+/// is one open writer per key. With [with_max_open_files](SplitWriter::with_max_open_files), the points of each key are
+/// appended to a temporary spill file instead, and at most `max_open_files` spill files are open at once. The writers
+/// are then created by [finish](SplitWriter::finish), one after another, with the number and the bounds of all points
+/// of their key, which e.g. allows choosing a scale and offset for each LAS file
+///
+/// ```no_run
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::{LASReader, LASWriter};
+/// # use pasture_core::layout::attributes;
+/// let mut reader = LASReader::from_path("in.las").unwrap();
+/// let header = reader.header().clone();
+/// let mut writer = SplitWriter::new(attributes::CLASSIFICATION.name(), move |class, _| {
+///     LASWriter::from_path_and_header(format!("class_{}.las", class), header.clone())
+/// });
+/// let count = reader.remaining_points();
+/// let points = reader.read(count).unwrap();
+/// writer.write(points.as_ref()).unwrap();
+/// writer.finish(|_, writer| writer.finalize()).unwrap();
+/// ```
+pub struct SplitWriter<K: Ord + Clone, W: PointWriter, F: FnMut(&K, &SplitOutput) -> Result<W>> {
+    attribute_name: Option<String>,
+    make_writer: F,
+    writers: BTreeMap<K, W>,
+    outputs: BTreeMap<K, SplitOutput>,
+    spill_files: Option<SpillFiles<K>>,
+    layout: PointLayout,
+}
+
+impl<W: PointWriter, F: FnMut(&i64, &SplitOutput) -> Result<W>> SplitWriter<i64, W, F> {
+    /// Creates a new `SplitWriter` that splits the points by the value of the attribute with the given name.
+    /// `make_writer` is called with each distinct value of the attribute and creates the writer for the points with
+    /// this value
+    pub fn new<S: Into<String>>(attribute_name: S, make_writer: F) -> Self {
+        let mut writer = Self::by_key(make_writer);
+        writer.attribute_name = Some(attribute_name.into());
+        writer
+    }
+}
+
+impl<K: Ord + Clone, W: PointWriter, F: FnMut(&K, &SplitOutput) -> Result<W>> SplitWriter<K, W, F> {
+    /// Creates a new `SplitWriter` for points that are assigned to their keys with [write_to](SplitWriter::write_to).
+    /// `make_writer` is called with each distinct key and creates the writer for the points with this key
+    pub fn by_key(make_writer: F) -> Self {
+        Self {
+            attribute_name: None,
+            make_writer,
+            writers: BTreeMap::new(),
+            outputs: BTreeMap::new(),
+            spill_files: None,
+            layout: PointLayout::default(),
+        }
+    }
+
+    /// Keeps at most `max_open_files` files open by appending the points to temporary spill files, which are
+    /// converted by [finish](SplitWriter::finish). The spill files are written to the temporary directory of the
+    /// system, use [with_temp_dir](SplitWriter::with_temp_dir) to change this. All points must have the same layout
+    ///
+    /// # Panics
+    ///
+    /// If `max_open_files` is zero, or if points were written before
+    pub fn with_max_open_files(mut self, max_open_files: usize) -> Self {
+        if max_open_files == 0 {
+            panic!("SplitWriter::with_max_open_files: max_open_files must be > 0");
+        }
+        if !self.outputs.is_empty() {
+            panic!("SplitWriter::with_max_open_files: Must be called before writing points");
+        }
+        self.spill_files = Some(SpillFiles {
+            temp_dir: std::env::temp_dir(),
+            max_open_files,
+            id: NEXT_SPILL_FILES_ID.fetch_add(1, Ordering::Relaxed),
+            files: BTreeMap::new(),
+            layout: None,
+            open_files: 0,
+            time: 0,
+        });
+        self
+    }
+
+    /// Writes the spill files to the given directory instead of the temporary directory of the system. The directory
+    /// needs enough free space for all points. Does nothing without [with_max_open_files](SplitWriter::with_max_open_files)
+    pub fn with_temp_dir<P: Into<PathBuf>>(mut self, temp_dir: P) -> Self {
+        if let Some(spill_files) = self.spill_files.as_mut() {
+            spill_files.temp_dir = temp_dir.into();
+        }
+        self
+    }
+
+    /// Returns the number and the bounds of the points that were written for each key
+    pub fn outputs(&self) -> &BTreeMap<K, SplitOutput> {
+        &self.outputs
+    }
+
+    /// Returns the number of keys that points were written for
+    pub fn len(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// Returns true if no points were written yet
+    pub fn is_empty(&self) -> bool {
+        self.outputs.is_empty()
+    }
+
+    /// Returns the writers that were created so far, by the key of their points. With spill files, the writers are only
+    /// created by [finish](SplitWriter::finish)
+    pub fn writers(&self) -> &BTreeMap<K, W> {
+        &self.writers
+    }
+
+    /// Returns the writers that were created, by the key of their points. With spill files, the writers are only created
+    /// by [finish](SplitWriter::finish), so this drops all spilled points
+    pub fn into_writers(self) -> BTreeMap<K, W> {
+        self.writers
+    }
+
+    /// Writes `points` to the output with the given `key`
+    ///
+    /// # Errors
+    ///
+    /// If the writer for `key` can't be created or fails to write the points, or if the spill file of `key` can't be
+    /// written, an error is returned
+    pub fn write_to(&mut self, key: K, points: &dyn PointBuffer) -> Result<()> {
+        if points.is_empty() {
+            return Ok(());
+        }
+        let spilled_points = self
+            .outputs
+            .get(&key)
+            .map_or(0, |output| output.point_count);
+        match self.spill_files.as_mut() {
+            Some(spill_files) => spill_files.write(&key, points, spilled_points)?,
+            None => {
+                if !self.writers.contains_key(&key) {
+                    let writer = (self.make_writer)(&key, &SplitOutput::default())?;
+                    if self.writers.is_empty() {
+                        self.layout = writer.get_default_point_layout().clone();
+                    }
+                    self.writers.insert(key.clone(), writer);
+                }
+                self.writers.get_mut(&key).unwrap().write(points)?;
+            }
+        }
+        // Only points that were written successfully are counted
+        self.outputs.entry(key).or_default().add_points(points);
+        Ok(())
+    }
+
+    /// Passes the writer of each key to `finish_writer`, e.g. to finalize LAS files, and returns the number and bounds
+    /// of the points of each key. With spill files, the writer of each key is created and receives the spilled points
+    /// first, one key after another, so that only one writer is open at any time
+    ///
+    /// # Errors
+    ///
+    /// If a writer can't be created, a spill file can't be read, or writing the points or `finish_writer` fails, an
+    /// error is returned
+    pub fn finish<G: FnMut(&K, W) -> Result<()>>(
+        mut self,
+        mut finish_writer: G,
+    ) -> Result<BTreeMap<K, SplitOutput>> {
+        for (key, writer) in std::mem::take(&mut self.writers) {
+            finish_writer(&key, writer)?;
+        }
+        if let Some(mut spill_files) = self.spill_files.take() {
+            let layout = spill_files.layout.clone().unwrap_or_default();
+            let point_size = layout.size_of_point_entry() as usize;
+            for (key, output) in self.outputs.iter() {
+                let mut reader = spill_files.reader(key)?;
+                let mut writer = (self.make_writer)(key, output)?;
+                let mut raw_points = vec![];
+                let mut remaining = output.point_count;
+                while remaining > 0 {
+                    let count = remaining.min(SPILL_CONVERSION_CHUNK_SIZE);
+                    raw_points.resize(count * point_size, 0);
+                    reader.read_exact(&mut raw_points)?;
+                    writer.write(&InterleavedPointView::from_raw_slice(
+                        &raw_points,
+                        layout.clone(),
+                    ))?;
+                    remaining -= count;
+                }
+                finish_writer(key, writer)?;
+                spill_files.remove(key);
+            }
+        }
+        Ok(self.outputs)
+    }
+}
+
+impl<W: PointWriter, F: FnMut(&i64, &SplitOutput) -> Result<W>> PointWriter
+    for SplitWriter<i64, W, F>
+{
+    fn write(&mut self, points: &dyn PointBuffer) -> Result<()> {
+        let attribute_name = self.attribute_name.as_ref().ok_or_else(|| {
+            PastureIoError::InvalidArgument(
+                "SplitWriter::write: Points of a SplitWriter without an attribute must be written with write_to"
+                    .into(),
+            )
+        })?;
+        let attribute: PointAttributeDefinition = points
+            .point_layout()
+            .get_attribute_by_name(attribute_name)
+            .ok_or_else(|| {
+                PastureIoError::LayoutMismatch(format!(
+                    "Can't split by attribute {}, the points have no attribute with this name",
+                    attribute_name
+                ))
+            })?
+            .into();
+        match attribute.datatype() {
+            PointAttributeDataType::U8
+            | PointAttributeDataType::U16
+            | PointAttributeDataType::U32
+            | PointAttributeDataType::U64
+            | PointAttributeDataType::I8
+            | PointAttributeDataType::I16
+            | PointAttributeDataType::I32
+            | PointAttributeDataType::I64
+            | PointAttributeDataType::Bool => (),
+            other => {
+                return Err(PastureIoError::UnsupportedFormat(format!(
+                    "Can't split by attribute {} with datatype {}, only integer attributes are supported",
+                    attribute.name(),
+                    other
+                )))
+            }
+        }
+
+        let mut indices_by_value = BTreeMap::<i64, Vec<usize>>::new();
+        let mut bytes = vec![0; attribute.size() as usize];
+        for index in 0..points.len() {
+            points.get_raw_attribute(index, &attribute, &mut bytes);
+            indices_by_value
+                .entry(integer_attribute_value(attribute.datatype(), &bytes))
+                .or_default()
+                .push(index);
+        }
+
+        for (value, indices) in indices_by_value {
+            if indices.len() == points.len() {
+                self.write_to(value, points)?;
+            } else {
+                self.write_to(value, &copy_points(points, &indices, points.point_layout()))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        for writer in self.writers.values_mut() {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the default `PointLayout` of the first writer that was created, or an empty `PointLayout` if no writer
+    /// was created yet
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use pasture_core::layout::attributes::{GPS_TIME, INTENSITY};
+    use scopeguard::defer;
+
+    use super::*;
+    use crate::{
+        base::PointReader,
+        las::{get_test_las_path, LASReader},
+    };
+
+    /// Writer that appends all written points to a shared buffer
+    struct BufferWriter {
+        points: Arc<Mutex<Option<InterleavedVecPointStorage>>>,
+        layout: PointLayout,
+    }
+
+    impl BufferWriter {
+        fn new() -> (Self, Arc<Mutex<Option<InterleavedVecPointStorage>>>) {
+            let points = Arc::new(Mutex::new(None));
+            let writer = Self {
+                points: points.clone(),
+                layout: PointLayout::default(),
+            };
+            (writer, points)
+        }
+    }
+
+    impl PointWriter for BufferWriter {
+        fn write(&mut self, points: &dyn PointBuffer) -> Result<()> {
+            self.points
+                .lock()
+                .unwrap()
+                .get_or_insert_with(|| {
+                    InterleavedVecPointStorage::new(points.point_layout().clone())
+                })
+                .push(points);
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn get_default_point_layout(&self) -> &PointLayout {
+            &self.layout
+        }
+    }
+
+    #[test]
+    fn test_masked_writer_excludes_attributes_and_selects_points() -> Result<()> {
+        let points = LASReader::from_path(get_test_las_path(1))?.read(10)?;
+        let (writer, written) = BufferWriter::new();
+        let mut writer = MaskedWriter::new(writer)
+            .with_excluded_attributes(&[GPS_TIME.name()])
+            .with_selection(PointSelection::mask(vec![true, false, true]));
+        writer.write(points.as_ref())?;
+        writer.write(points.as_ref())?;
+
+        let written = written.lock().unwrap();
+        let written = written.as_ref().unwrap();
+        assert_eq!(2, written.len());
+        assert!(!written
+            .point_layout()
+            .has_attribute_with_name(GPS_TIME.name()));
+        assert_eq!(
+            written.point_layout().attributes().count() + 1,
+            points.point_layout().attributes().count()
+        );
+        assert_eq!(
+            points.get_attribute::<u16>(&INTENSITY, 2),
+            written.get_attribute::<u16>(&INTENSITY, 1)
+        );
+
+        let mut writer =
+            MaskedWriter::new(BufferWriter::new().0).with_excluded_attributes(&["DoesNotExist"]);
+        assert!(writer.write(points.as_ref()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_writer_by_classification() -> Result<()> {
+        let points = LASReader::from_path(get_test_las_path(0))?.read(10)?;
+        let buffers = Arc::new(Mutex::new(BTreeMap::new()));
+        let writer_buffers = buffers.clone();
+        let mut writer = SplitWriter::new(CLASSIFICATION.name(), move |class, _| {
+            let (writer, points) = BufferWriter::new();
+            writer_buffers.lock().unwrap().insert(*class, points);
+            Ok(writer)
+        });
+        writer.write(points.as_ref())?;
+
+        let mut expected = BTreeMap::<i64, usize>::new();
+        for class in points.iter_attribute::<u8>(&CLASSIFICATION) {
+            *expected.entry(class as i64).or_default() += 1;
+        }
+        let actual = buffers
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(class, points)| {
+                let points = points.lock().unwrap();
+                let points = points.as_ref().unwrap();
+                assert!(points
+                    .iter_attribute::<u8>(&CLASSIFICATION)
+                    .all(|value| value as i64 == *class));
+                (*class, points.len())
+            })
+            .collect::<BTreeMap<_, _>>();
+        assert_eq!(expected, actual);
+        assert_eq!(expected.len(), writer.writers().len());
+
+        let mut writer = SplitWriter::new(POSITION_3D.name(), |_, _| Ok(BufferWriter::new().0));
+        assert!(writer.write(points.as_ref()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_writer_with_few_open_files() -> Result<()> {
+        let temp_dir = std::env::temp_dir().join("pasture_test_split_writer_with_few_open_files");
+        std::fs::create_dir_all(&temp_dir)?;
+        defer! {
+            std::fs::remove_dir_all(&temp_dir).expect("Could not remove test directory");
+        }
+
+        let points = LASReader::from_path(get_test_las_path(0))?.read(10)?;
+        let buffers = Arc::new(Mutex::new(BTreeMap::new()));
+        let writer_buffers = buffers.clone();
+        let mut writer = SplitWriter::by_key(move |key: &usize, output: &SplitOutput| {
+            // With spill files, the writers are created with the statistics of all points of their key
+            assert!(output.point_count > 0);
+            let (writer, points) = BufferWriter::new();
+            writer_buffers.lock().unwrap().insert(*key, points);
+            Ok(writer)
+        })
+        .with_max_open_files(1)
+        .with_temp_dir(&temp_dir);
+        // The keys are written alternately, so that each write has to reopen a closed spill file
+        for index in 0..points.len() {
+            writer.write_to(
+                index % 3,
+                &copy_points(points.as_ref(), &[index], points.point_layout()),
+            )?;
+        }
+        assert_eq!(3, writer.len());
+        assert_eq!(4, writer.outputs()[&0].point_count);
+        assert!(buffers.lock().unwrap().is_empty());
+
+        let mut finished = vec![];
+        let outputs = writer.finish(|key, _| {
+            finished.push(*key);
+            Ok(())
+        })?;
+        assert_eq!(vec![0, 1, 2], finished);
+        for (key, output) in outputs.iter() {
+            let expected_positions = points
+                .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                .skip(*key)
+                .step_by(3)
+                .collect::<Vec<_>>();
+            assert_eq!(expected_positions.len(), output.point_count);
+            let bounds = output.bounds.as_ref().unwrap();
+            for position in expected_positions.iter() {
+                assert!(bounds.contains(&Point3::from(*position)));
+            }
+
+            let buffers = buffers.lock().unwrap();
+            let written = buffers[key].lock().unwrap();
+            let written = written.as_ref().unwrap();
+            assert_eq!(
+                expected_positions,
+                written
+                    .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                    .collect::<Vec<_>>()
+            );
+        }
+        // All spill files are removed
+        assert_eq!(0, std::fs::read_dir(&temp_dir)?.count());
+        Ok(())
+    }
+}
@@ -7,5 +7,83 @@ pub use self::writer::*;
 mod seek;
 pub use self::seek::*;
 
+mod capabilities;
+pub use self::capabilities::*;
+
 mod io_factory;
 pub use self::io_factory::*;
+
+mod progress;
+pub use self::progress::*;
+
+mod error;
+pub use self::error::*;
+
+mod byte_order;
+pub use self::byte_order::*;
+
+mod estimate;
+pub use self::estimate::*;
+
+mod codec;
+pub use self::codec::*;
+
+mod chunk_statistics;
+pub use self::chunk_statistics::*;
+
+mod digest;
+pub use self::digest::*;
+
+mod provenance;
+pub use self::provenance::*;
+
+mod external_sort;
+pub use self::external_sort::*;
+
+mod spatial_query;
+pub use self::spatial_query::*;
+
+mod dataset;
+pub use self::dataset::*;
+
+mod octree_builder;
+pub use self::octree_builder::*;
+
+mod octree_hierarchy;
+pub use self::octree_hierarchy::*;
+
+mod block_cache;
+pub use self::block_cache::*;
+
+mod range_source;
+pub use self::range_source::*;
+
+mod read_ahead;
+pub use self::read_ahead::*;
+
+mod roundtrip;
+pub use self::roundtrip::*;
+
+mod buffer_builder;
+pub use self::buffer_builder::*;
+
+mod read_all;
+pub use self::read_all::*;
+
+mod write_all;
+pub use self::write_all::*;
+
+mod masked_writer;
+pub use self::masked_writer::*;
+
+mod coordinate_transform;
+pub use self::coordinate_transform::*;
+
+mod scan_collection;
+pub use self::scan_collection::*;
+
+mod point_chunk;
+pub use self::point_chunk::*;
+
+mod streaming;
+pub use self::streaming::*;
@@ -0,0 +1,443 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{BufWriter, Read, Write},
+    path::PathBuf,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use pasture_core::{
+    containers::{InterleavedPointView, InterleavedVecPointStorage, PointBuffer, PointBufferExt},
+    layout::{attributes::POSITION_3D, PointLayout},
+    math::AABB,
+    nalgebra::Vector3,
+};
+
+use super::{external_sort::RunFiles, OctreeHierarchy, PastureIoError, Result};
+
+/// Counter to give the spill files of all `StreamingOctreeBuilder`s of this process unique names
+static NEXT_BUILDER_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Key of a node in an octree, as (depth, x, y, z), following the EPT naming scheme
+pub type OctreeNodeKey = (u32, u64, u64, u64);
+
+/// Returns the bounds of the node with the given key within the octree with the given `cube` bounds
+pub fn octree_node_bounds(key: &OctreeNodeKey, cube: &AABB<f64>) -> AABB<f64> {
+    let (depth, x, y, z) = *key;
+    let size = cube.extent().x / (1u64 << depth) as f64;
+    let min = cube.min() + Vector3::new(x as f64 * size, y as f64 * size, z as f64 * size);
+    AABB::from_min_max_unchecked(min, min + Vector3::new(size, size, size))
+}
+
+/// Returns the index of the cell that contains `position` within a grid of `span`^3 cells over `bounds`
+pub(crate) fn cell_index(
+    position: &Vector3<f64>,
+    bounds: &AABB<f64>,
+    span: u64,
+) -> (u64, u64, u64) {
+    let size = bounds.extent().x;
+    let to_cell = |value: f64, min: f64| -> u64 {
+        let cell = ((value - min) / size * span as f64).floor();
+        (cell.max(0.0) as u64).min(span - 1)
+    };
+    (
+        to_cell(position.x, bounds.min().x),
+        to_cell(position.y, bounds.min().y),
+        to_cell(position.z, bounds.min().z),
+    )
+}
+
+/// A node of the octree while it is being built
+#[derive(Default)]
+struct BuilderNode {
+    /// Raw memory of the points of this node that are still in memory
+    points: Vec<u8>,
+    /// Total number of points of this node, including spilled points
+    point_count: usize,
+    occupied_cells: HashSet<(u64, u64, u64)>,
+    /// Index of the spill file of this node within the `RunFiles` of the builder
+    spill_file: Option<usize>,
+}
+
+/// Builds an octree from points that arrive in arbitrary order, for example from several files or from a stream, while
+/// keeping at most `max_points_in_memory` points in memory. Each node stores at most one point per cell of a regular
+/// grid with `span`^3 cells (so coarser nodes store a subsample of the points of their children), nodes at `max_depth`
+/// store all remaining points. Whenever too many points are in memory, the points of the largest nodes are appended to
+/// temporary files of these nodes. Only the sampling grids of the nodes stay in memory
+///
+/// The bounds of the octree must be known in advance, for example from the headers of the input files. After all
+/// points are pushed, [finish](StreamingOctreeBuilder::finish) returns a [StreamingOctree] from which the points of
+/// each node can be read to write them in an index format such as EPT
+///
+/// ```
+/// # use pasture_core::containers::{InterleavedVecPointStorage, PointBuffer};
+/// # use pasture_core::layout::PointType;
+/// # use pasture_core::math::AABB;
+/// # use pasture_core::nalgebra::{Point3, Vector3};
+/// # use pasture_derive::PointType;
+/// # use pasture_io::base::StreamingOctreeBuilder;
+/// #[repr(C)]
+/// #[derive(PointType, Debug, Copy, Clone)]
+/// struct Point {
+///     #[pasture(BUILTIN_POSITION_3D)]
+///     position: Vector3<f64>,
+/// }
+/// let points: InterleavedVecPointStorage = (0..100)
+///     .map(|i| Point { position: Vector3::new(i as f64 / 100.0, 0.5, 0.5) })
+///     .collect::<Vec<_>>()
+///     .into();
+/// let cube = AABB::from_min_max(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 1.0, 1.0));
+/// let mut builder = StreamingOctreeBuilder::new(Point::layout(), cube, 4, 8, 16);
+/// builder.push(&points).unwrap();
+///
+/// let octree = builder.finish();
+/// assert_eq!(100, octree.point_count());
+/// let root = octree.read_node(&(0, 0, 0, 0)).unwrap();
+/// assert_eq!(4, root.len());
+/// ```
+pub struct StreamingOctreeBuilder {
+    layout: PointLayout,
+    cube: AABB<f64>,
+    span: u64,
+    max_depth: u32,
+    max_points_in_memory: usize,
+    temp_dir: PathBuf,
+    builder_id: usize,
+    nodes: HashMap<OctreeNodeKey, BuilderNode>,
+    points_in_memory: usize,
+    point_count: usize,
+    spill_files: RunFiles,
+}
+
+impl StreamingOctreeBuilder {
+    /// Creates a new `StreamingOctreeBuilder` for points in the given `layout` within the given `cube`. Spill files are
+    /// written to the temporary directory of the system, use [with_temp_dir](StreamingOctreeBuilder::with_temp_dir) to
+    /// change this
+    ///
+    /// # Panics
+    ///
+    /// If `layout` has no `POSITION_3D` attribute, if `span` or `max_points_in_memory` is zero, or if `max_depth` is
+    /// larger than 32
+    pub fn new(
+        layout: PointLayout,
+        cube: AABB<f64>,
+        span: u64,
+        max_depth: u32,
+        max_points_in_memory: usize,
+    ) -> Self {
+        if !layout.has_attribute_with_name(POSITION_3D.name()) {
+            panic!("StreamingOctreeBuilder::new: layout contains no position attribute");
+        }
+        if span == 0 {
+            panic!("StreamingOctreeBuilder::new: span must be > 0");
+        }
+        if max_depth > 32 {
+            panic!("StreamingOctreeBuilder::new: max_depth must be <= 32");
+        }
+        if max_points_in_memory == 0 {
+            panic!("StreamingOctreeBuilder::new: max_points_in_memory must be > 0");
+        }
+        Self {
+            layout,
+            cube: cube.as_cubic(),
+            span,
+            max_depth,
+            max_points_in_memory,
+            temp_dir: std::env::temp_dir(),
+            builder_id: NEXT_BUILDER_ID.fetch_add(1, Ordering::Relaxed),
+            nodes: HashMap::new(),
+            points_in_memory: 0,
+            point_count: 0,
+            spill_files: RunFiles(vec![]),
+        }
+    }
+
+    /// Writes the spill files of this `StreamingOctreeBuilder` to the given directory instead of the temporary
+    /// directory of the system. The directory needs enough free space for all points
+    pub fn with_temp_dir<P: Into<PathBuf>>(mut self, temp_dir: P) -> Self {
+        self.temp_dir = temp_dir.into();
+        self
+    }
+
+    /// Returns the number of points that have been pushed into this `StreamingOctreeBuilder`
+    pub fn point_count(&self) -> usize {
+        self.point_count
+    }
+
+    /// Returns the number of points that are currently held in memory
+    pub fn points_in_memory(&self) -> usize {
+        self.points_in_memory
+    }
+
+    /// Pushes the given `points` into the octree
+    ///
+    /// # Errors
+    ///
+    /// If the `PointLayout` of `points` does not match the `PointLayout` of this `StreamingOctreeBuilder`, or if nodes
+    /// can't be spilled to disk, an error is returned
+    pub fn push(&mut self, points: &dyn PointBuffer) -> Result<()> {
+        if *points.point_layout() != self.layout {
+            return Err(PastureIoError::LayoutMismatch(
+                "PointLayout of the points does not match the PointLayout of the StreamingOctreeBuilder"
+                    .into(),
+            ));
+        }
+        let mut point = vec![0; self.layout.size_of_point_entry() as usize];
+        for (index, position) in points
+            .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+            .enumerate()
+        {
+            points.get_raw_point(index, &mut point);
+            self.insert_point(&point, &position);
+            if self.points_in_memory > self.max_points_in_memory {
+                self.spill()?;
+            }
+        }
+        self.point_count += points.len();
+        Ok(())
+    }
+
+    /// Finishes building the octree and returns the [StreamingOctree]
+    pub fn finish(self) -> StreamingOctree {
+        let nodes = self
+            .nodes
+            .into_iter()
+            .map(|(key, mut node)| {
+                // The sampling grids are only needed while inserting points
+                node.occupied_cells = HashSet::new();
+                (key, node)
+            })
+            .collect();
+        StreamingOctree {
+            layout: self.layout,
+            cube: self.cube,
+            point_count: self.point_count,
+            nodes,
+            spill_files: self.spill_files,
+        }
+    }
+
+    /// Inserts a single point into the octree. Starting at the root node, the point is stored in the first node whose
+    /// sampling grid cell for the point is still empty
+    fn insert_point(&mut self, point: &[u8], position: &Vector3<f64>) {
+        let mut key: OctreeNodeKey = (0, 0, 0, 0);
+        loop {
+            let bounds = octree_node_bounds(&key, &self.cube);
+            let cell = cell_index(position, &bounds, self.span);
+            let node = self.nodes.entry(key).or_default();
+            if key.0 == self.max_depth || node.occupied_cells.insert(cell) {
+                node.points.extend_from_slice(point);
+                node.point_count += 1;
+                self.points_in_memory += 1;
+                return;
+            }
+
+            let center = bounds.center();
+            let (depth, x, y, z) = key;
+            key = (
+                depth + 1,
+                2 * x + (position.x >= center.x) as u64,
+                2 * y + (position.y >= center.y) as u64,
+                2 * z + (position.z >= center.z) as u64,
+            );
+        }
+    }
+
+    /// Appends the points of the largest nodes to their spill files until at most half of `max_points_in_memory`
+    /// points are in memory. Spilling the largest nodes first keeps the number of (small) writes low, and the largest
+    /// nodes are typically the ones whose sampling grid is full, which receive no further points
+    fn spill(&mut self) -> Result<()> {
+        let point_size = self.layout.size_of_point_entry() as usize;
+        let mut nodes = self
+            .nodes
+            .iter()
+            .filter(|(_, node)| !node.points.is_empty())
+            .map(|(key, node)| (node.points.len(), *key))
+            .collect::<Vec<_>>();
+        nodes.sort_by(|a, b| b.cmp(a));
+
+        for (_, key) in nodes {
+            if self.points_in_memory <= self.max_points_in_memory / 2 {
+                break;
+            }
+            let node = self.nodes.get_mut(&key).unwrap();
+            let spill_file = match node.spill_file {
+                Some(spill_file) => spill_file,
+                None => {
+                    let (d, x, y, z) = key;
+                    let path = self.temp_dir.join(format!(
+                        "pasture-octree-{}-{}-{}-{}-{}-{}.bin",
+                        std::process::id(),
+                        self.builder_id,
+                        d,
+                        x,
+                        y,
+                        z
+                    ));
+                    // Register the file before writing, so that it is deleted even if writing fails
+                    self.spill_files.0.push(path);
+                    node.spill_file = Some(self.spill_files.0.len() - 1);
+                    self.spill_files.0.len() - 1
+                }
+            };
+            let mut writer = BufWriter::new(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.spill_files.0[spill_file])?,
+            );
+            writer.write_all(&node.points)?;
+            writer.flush()?;
+            self.points_in_memory -= node.points.len() / point_size;
+            node.points = vec![];
+        }
+        Ok(())
+    }
+}
+
+/// An octree built by a [StreamingOctreeBuilder]. The points of each node are read from disk on demand with
+/// [read_node](StreamingOctree::read_node), which can be called from multiple threads at once. The temporary files are
+/// deleted once the `StreamingOctree` is dropped
+pub struct StreamingOctree {
+    layout: PointLayout,
+    cube: AABB<f64>,
+    point_count: usize,
+    nodes: BTreeMap<OctreeNodeKey, BuilderNode>,
+    spill_files: RunFiles,
+}
+
+impl StreamingOctree {
+    /// Returns the `PointLayout` of the points in this octree
+    pub fn point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    /// Returns the cubic bounds of the root node of this octree
+    pub fn bounds(&self) -> AABB<f64> {
+        self.cube
+    }
+
+    /// Returns the total number of points in this octree
+    pub fn point_count(&self) -> usize {
+        self.point_count
+    }
+
+    /// Returns the keys and point counts of all nodes of this octree, sorted by their keys
+    pub fn hierarchy(&self) -> Vec<(OctreeNodeKey, usize)> {
+        self.nodes
+            .iter()
+            .map(|(key, node)| (*key, node.point_count))
+            .collect()
+    }
+
+    /// Returns the hierarchy of this octree, e.g. to inspect the statistics of its nodes before writing them
+    pub fn octree_hierarchy(&self) -> OctreeHierarchy {
+        OctreeHierarchy::new(self.cube, self.hierarchy())
+    }
+
+    /// Reads all points of the node with the given `key`
+    ///
+    /// # Errors
+    ///
+    /// If there is no node with the given `key`, or if its spill file can't be read, an error is returned
+    pub fn read_node(&self, key: &OctreeNodeKey) -> Result<InterleavedVecPointStorage> {
+        let node = self.nodes.get(key).ok_or_else(|| {
+            PastureIoError::InvalidArgument(format!("The octree has no node {:?}", key))
+        })?;
+        let point_size = self.layout.size_of_point_entry() as usize;
+        let mut memory = Vec::with_capacity(node.point_count * point_size);
+        if let Some(spill_file) = node.spill_file {
+            File::open(&self.spill_files.0[spill_file])?.read_to_end(&mut memory)?;
+        }
+        memory.extend_from_slice(&node.points);
+        if memory.len() != node.point_count * point_size {
+            return Err(PastureIoError::InvalidData(format!(
+                "Spill file of node {:?} does not contain all points of the node",
+                key
+            )));
+        }
+
+        let mut points =
+            InterleavedVecPointStorage::with_capacity(node.point_count, self.layout.clone());
+        points.push(&InterleavedPointView::from_raw_slice(
+            &memory,
+            self.layout.clone(),
+        ));
+        Ok(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasture_core::{layout::PointType, nalgebra::Point3};
+    use pasture_derive::PointType;
+    use rand::{prelude::StdRng, Rng, SeedableRng};
+
+    #[repr(C, packed)]
+    #[derive(Copy, Clone, PartialEq, PointType, Debug)]
+    struct TestPoint {
+        #[pasture(BUILTIN_POSITION_3D)]
+        position: Vector3<f64>,
+        #[pasture(BUILTIN_POINT_ID)]
+        id: u64,
+    }
+
+    fn build_octree(points: &[TestPoint], max_points_in_memory: usize) -> Result<StreamingOctree> {
+        let cube = AABB::from_min_max(Point3::new(0.0, 0.0, 0.0), Point3::new(8.0, 8.0, 8.0));
+        let mut builder =
+            StreamingOctreeBuilder::new(TestPoint::layout(), cube, 4, 6, max_points_in_memory);
+        for chunk in points.chunks(100) {
+            let chunk: InterleavedVecPointStorage = chunk.into();
+            builder.push(&chunk)?;
+        }
+        assert!(builder.points_in_memory() <= max_points_in_memory);
+        Ok(builder.finish())
+    }
+
+    #[test]
+    fn spilling_nodes_does_not_change_the_octree() -> Result<()> {
+        let mut rng = StdRng::seed_from_u64(7);
+        let points = (0..5000)
+            .map(|id| TestPoint {
+                position: Vector3::new(
+                    rng.gen_range(0.0..8.0),
+                    rng.gen_range(0.0..8.0),
+                    rng.gen_range(0.0..8.0),
+                ),
+                id,
+            })
+            .collect::<Vec<_>>();
+
+        let in_memory = build_octree(&points, usize::MAX)?;
+        let spilled = build_octree(&points, 500)?;
+        assert!(!spilled.spill_files.0.is_empty());
+        assert_eq!(in_memory.hierarchy(), spilled.hierarchy());
+        assert_eq!(
+            5000,
+            spilled
+                .hierarchy()
+                .iter()
+                .map(|(_, count)| count)
+                .sum::<usize>()
+        );
+
+        for (key, _) in in_memory.hierarchy() {
+            let expected = in_memory.read_node(&key)?;
+            let actual = spilled.read_node(&key)?;
+            assert_eq!(
+                expected.iter_point::<TestPoint>().collect::<Vec<_>>(),
+                actual.iter_point::<TestPoint>().collect::<Vec<_>>()
+            );
+            let bounds = octree_node_bounds(&key, &spilled.bounds());
+            assert!(actual
+                .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                .all(|position| bounds.contains(&position.into())));
+        }
+
+        let spill_files = spilled.spill_files.0.clone();
+        drop(spilled);
+        assert!(spill_files.iter().all(|path| !path.exists()));
+        Ok(())
+    }
+}
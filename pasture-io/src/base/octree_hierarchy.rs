@@ -0,0 +1,367 @@
+use std::collections::BTreeMap;
+
+use pasture_core::math::AABB;
+
+use super::{octree_node_bounds, OctreeNodeKey};
+
+/// Returns the key of the parent of the node with the given `key` within an octree, or `None` for the root node
+pub fn octree_parent_key(key: &OctreeNodeKey) -> Option<OctreeNodeKey> {
+    let (depth, x, y, z) = *key;
+    if depth == 0 {
+        None
+    } else {
+        Some((depth - 1, x / 2, y / 2, z / 2))
+    }
+}
+
+/// Returns the keys of the eight possible children of the node with the given `key` within an octree
+fn octree_child_keys(key: &OctreeNodeKey) -> impl Iterator<Item = OctreeNodeKey> {
+    let (depth, x, y, z) = *key;
+    (0..8).map(move |child| {
+        (
+            depth + 1,
+            2 * x + (child & 1),
+            2 * y + ((child >> 1) & 1),
+            2 * z + ((child >> 2) & 1),
+        )
+    })
+}
+
+/// Statistics of a single node of an [OctreeHierarchy]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OctreeNodeStatistics {
+    /// Key of the node
+    pub key: OctreeNodeKey,
+    /// Number of points of the node
+    pub point_count: usize,
+    /// Bounds of the node
+    pub bounds: AABB<f64>,
+    /// Number of points per cubic unit within the bounds of the node, zero if the bounds have no volume
+    pub density: f64,
+    /// Number of child nodes of the node
+    pub child_count: usize,
+}
+
+/// Statistics of all nodes at the same depth of an [OctreeHierarchy]
+#[derive(Debug, Clone, PartialEq)]
+pub struct OctreeLevelStatistics {
+    /// Depth of the nodes, zero for the root node
+    pub depth: u32,
+    /// Number of nodes at this depth
+    pub node_count: usize,
+    /// Total number of points of the nodes at this depth
+    pub point_count: usize,
+    /// Smallest number of points of a node at this depth
+    pub min_node_points: usize,
+    /// Largest number of points of a node at this depth
+    pub max_node_points: usize,
+    /// Mean density of the nodes at this depth, in points per cubic unit
+    pub mean_density: f64,
+}
+
+/// The hierarchy of an indexed point cloud dataset, e.g. of an EPT dataset or a COPC file: The cubic bounds of the
+/// octree and the number of points of each node. With the hierarchy, the nodes of a dataset can be inspected (see
+/// [node_statistics](OctreeHierarchy::node_statistics) and [level_statistics](OctreeHierarchy::level_statistics)) and
+/// re-chunked (see [merge_sparse_nodes](OctreeHierarchy::merge_sparse_nodes) and
+/// [truncate](OctreeHierarchy::truncate)) without touching the points
+#[derive(Debug, Clone, PartialEq)]
+pub struct OctreeHierarchy {
+    cube: AABB<f64>,
+    nodes: BTreeMap<OctreeNodeKey, usize>,
+}
+
+impl OctreeHierarchy {
+    /// Creates a new `OctreeHierarchy` for an octree with the given `cube` bounds and the given keys and point counts
+    /// of its nodes
+    pub fn new<I: IntoIterator<Item = (OctreeNodeKey, usize)>>(cube: AABB<f64>, nodes: I) -> Self {
+        Self {
+            cube,
+            nodes: nodes.into_iter().collect(),
+        }
+    }
+
+    /// Returns the cubic bounds of the root node
+    pub fn bounds(&self) -> AABB<f64> {
+        self.cube
+    }
+
+    /// Returns the total number of points of all nodes
+    pub fn point_count(&self) -> usize {
+        self.nodes.values().sum()
+    }
+
+    /// Returns the number of nodes
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns the largest depth of all nodes, or `None` if there are no nodes
+    pub fn depth(&self) -> Option<u32> {
+        self.nodes.keys().next_back().map(|(depth, _, _, _)| *depth)
+    }
+
+    /// Returns the keys and point counts of all nodes, sorted by their keys
+    pub fn nodes(&self) -> impl Iterator<Item = (OctreeNodeKey, usize)> + '_ {
+        self.nodes.iter().map(|(key, count)| (*key, *count))
+    }
+
+    /// Returns the number of points of the node with the given `key`, or `None` if there is no such node
+    pub fn node_point_count(&self, key: &OctreeNodeKey) -> Option<usize> {
+        self.nodes.get(key).copied()
+    }
+
+    /// Returns the keys of the child nodes of the node with the given `key`
+    pub fn children_of(&self, key: &OctreeNodeKey) -> Vec<OctreeNodeKey> {
+        octree_child_keys(key)
+            .filter(|child| self.nodes.contains_key(child))
+            .collect()
+    }
+
+    /// Returns the statistics of all nodes, sorted by their keys
+    pub fn node_statistics(&self) -> Vec<OctreeNodeStatistics> {
+        self.nodes
+            .iter()
+            .map(|(key, point_count)| {
+                let bounds = octree_node_bounds(key, &self.cube);
+                let volume = bounds.extent().x * bounds.extent().y * bounds.extent().z;
+                OctreeNodeStatistics {
+                    key: *key,
+                    point_count: *point_count,
+                    bounds,
+                    density: if volume > 0.0 {
+                        *point_count as f64 / volume
+                    } else {
+                        0.0
+                    },
+                    child_count: self.children_of(key).len(),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the statistics of the nodes at each depth, sorted by depth. Depths without nodes are skipped
+    pub fn level_statistics(&self) -> Vec<OctreeLevelStatistics> {
+        let mut levels: Vec<OctreeLevelStatistics> = vec![];
+        for node in self.node_statistics() {
+            let depth = node.key.0;
+            match levels.last_mut() {
+                Some(level) if level.depth == depth => {
+                    level.node_count += 1;
+                    level.point_count += node.point_count;
+                    level.min_node_points = level.min_node_points.min(node.point_count);
+                    level.max_node_points = level.max_node_points.max(node.point_count);
+                    level.mean_density += node.density;
+                }
+                _ => levels.push(OctreeLevelStatistics {
+                    depth,
+                    node_count: 1,
+                    point_count: node.point_count,
+                    min_node_points: node.point_count,
+                    max_node_points: node.point_count,
+                    mean_density: node.density,
+                }),
+            }
+        }
+        for level in levels.iter_mut() {
+            level.mean_density /= level.node_count as f64;
+        }
+        levels
+    }
+
+    /// Merges all leaf nodes with fewer than `min_points` points into their parent nodes, starting at the deepest
+    /// nodes, so that a parent whose children were all merged can be merged into its own parent in turn. Nodes with
+    /// children are never merged, since the children would no longer be reachable from the root. Many small nodes slow
+    /// down the traversal of a dataset, since each node is a separate request for a viewer or a server. Returns the
+    /// merged hierarchy together with the node that the points of each node are moved to
+    pub fn merge_sparse_nodes(&self, min_points: usize) -> HierarchyMerge {
+        self.merge_leaves_where(|_, point_count| point_count < min_points)
+    }
+
+    /// Merges all nodes that are deeper than `max_depth` into their ancestor at `max_depth`, which prunes the hierarchy
+    /// to `max_depth`. Returns the merged hierarchy together with the node that the points of each node are moved to
+    pub fn truncate(&self, max_depth: u32) -> HierarchyMerge {
+        self.merge_leaves_where(|key, _| key.0 > max_depth)
+    }
+
+    /// Merges all leaf nodes for which `should_merge` returns `true` into their parent nodes, from the deepest nodes
+    /// to the root
+    fn merge_leaves_where<F: Fn(&OctreeNodeKey, usize) -> bool>(
+        &self,
+        should_merge: F,
+    ) -> HierarchyMerge {
+        let mut nodes = self.nodes.clone();
+        let mut merged_into = BTreeMap::new();
+        for depth in (1..=self.depth().unwrap_or(0)).rev() {
+            let keys = nodes
+                .range((depth, 0, 0, 0)..(depth + 1, 0, 0, 0))
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>();
+            for key in keys {
+                let point_count = nodes[&key];
+                let is_leaf = octree_child_keys(&key).all(|child| !nodes.contains_key(&child));
+                if !is_leaf || !should_merge(&key, point_count) {
+                    continue;
+                }
+                let parent = octree_parent_key(&key).unwrap();
+                nodes.remove(&key);
+                *nodes.entry(parent).or_insert(0) += point_count;
+                merged_into.insert(key, parent);
+            }
+        }
+
+        let targets = self
+            .nodes
+            .keys()
+            .map(|key| {
+                let mut target = *key;
+                while let Some(parent) = merged_into.get(&target) {
+                    target = *parent;
+                }
+                (*key, target)
+            })
+            .collect();
+        HierarchyMerge {
+            hierarchy: OctreeHierarchy {
+                cube: self.cube,
+                nodes,
+            },
+            targets,
+        }
+    }
+}
+
+/// The result of merging nodes of an [OctreeHierarchy]: The merged hierarchy, and the node of the merged hierarchy
+/// that the points of each node of the original hierarchy are moved to. Apply it to the points of a dataset to
+/// re-chunk them, e.g. with [rechunk_ept](crate::ept::rechunk_ept)
+#[derive(Debug, Clone, PartialEq)]
+pub struct HierarchyMerge {
+    hierarchy: OctreeHierarchy,
+    targets: BTreeMap<OctreeNodeKey, OctreeNodeKey>,
+}
+
+impl HierarchyMerge {
+    /// Returns the merged hierarchy
+    pub fn hierarchy(&self) -> &OctreeHierarchy {
+        &self.hierarchy
+    }
+
+    /// Returns the node of the merged hierarchy that the points of the node with the given `key` of the original
+    /// hierarchy are moved to, or `None` if the original hierarchy has no such node
+    pub fn target_of(&self, key: &OctreeNodeKey) -> Option<OctreeNodeKey> {
+        self.targets.get(key).copied()
+    }
+
+    /// Returns the keys of all nodes of the original hierarchy whose points are moved to the node with the given
+    /// `target` key of the merged hierarchy, sorted by their keys. This includes `target` itself if the original
+    /// hierarchy has this node
+    pub fn sources_of(&self, target: &OctreeNodeKey) -> Vec<OctreeNodeKey> {
+        self.targets
+            .iter()
+            .filter(|(_, node_target)| *node_target == target)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+
+    /// Returns the number of nodes of the original hierarchy that were merged into other nodes
+    pub fn merged_node_count(&self) -> usize {
+        self.targets
+            .iter()
+            .filter(|(key, target)| key != target)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasture_core::nalgebra::Point3;
+
+    fn test_hierarchy() -> OctreeHierarchy {
+        let cube = AABB::from_min_max(Point3::new(0.0, 0.0, 0.0), Point3::new(8.0, 8.0, 8.0));
+        OctreeHierarchy::new(
+            cube,
+            vec![
+                ((0, 0, 0, 0), 100),
+                ((1, 0, 0, 0), 50),
+                ((1, 1, 1, 1), 5),
+                ((2, 0, 0, 0), 3),
+                ((2, 2, 2, 2), 40),
+                ((3, 5, 5, 5), 2),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_hierarchy_statistics() {
+        let hierarchy = test_hierarchy();
+        assert_eq!(200, hierarchy.point_count());
+        assert_eq!(Some(3), hierarchy.depth());
+        assert_eq!(
+            vec![(1, 0, 0, 0), (1, 1, 1, 1)],
+            hierarchy.children_of(&(0, 0, 0, 0))
+        );
+        assert_eq!(Some((0, 0, 0, 0)), octree_parent_key(&(1, 1, 1, 1)));
+        assert_eq!(None, octree_parent_key(&(0, 0, 0, 0)));
+
+        let nodes = hierarchy.node_statistics();
+        assert_eq!(6, nodes.len());
+        assert_eq!(100.0 / 512.0, nodes[0].density);
+        assert_eq!(2, nodes[0].child_count);
+        assert_eq!(Point3::new(4.0, 4.0, 4.0), *nodes[2].bounds.min());
+
+        let levels = hierarchy.level_statistics();
+        assert_eq!(
+            vec![1, 2, 2, 1],
+            levels
+                .iter()
+                .map(|level| level.node_count)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(55, levels[1].point_count);
+        assert_eq!(5, levels[1].min_node_points);
+        assert_eq!(50, levels[1].max_node_points);
+        assert_eq!((50.0 + 5.0) / 64.0 / 2.0, levels[1].mean_density);
+    }
+
+    #[test]
+    fn test_merge_sparse_nodes() {
+        let hierarchy = test_hierarchy();
+        let merge = hierarchy.merge_sparse_nodes(10);
+        // (3, 5, 5, 5) is merged into (2, 2, 2, 2), which is not sparse. (1, 1, 1, 1) has a child, so it stays
+        assert_eq!(
+            vec![
+                ((0, 0, 0, 0), 100),
+                ((1, 0, 0, 0), 53),
+                ((1, 1, 1, 1), 5),
+                ((2, 2, 2, 2), 42),
+            ],
+            merge.hierarchy().nodes().collect::<Vec<_>>()
+        );
+        assert_eq!(200, merge.hierarchy().point_count());
+        assert_eq!(2, merge.merged_node_count());
+        assert_eq!(Some((2, 2, 2, 2)), merge.target_of(&(3, 5, 5, 5)));
+        assert_eq!(
+            vec![(1, 0, 0, 0), (2, 0, 0, 0)],
+            merge.sources_of(&(1, 0, 0, 0))
+        );
+
+        // Merging the sparse leaves can make their parents sparse leaves
+        let merge = hierarchy.merge_sparse_nodes(60);
+        assert_eq!(
+            vec![((0, 0, 0, 0), 200)],
+            merge.hierarchy().nodes().collect::<Vec<_>>()
+        );
+        assert_eq!(Some((0, 0, 0, 0)), merge.target_of(&(3, 5, 5, 5)));
+    }
+
+    #[test]
+    fn test_truncate_hierarchy() {
+        let merge = test_hierarchy().truncate(1);
+        assert_eq!(
+            vec![((0, 0, 0, 0), 100), ((1, 0, 0, 0), 53), ((1, 1, 1, 1), 47)],
+            merge.hierarchy().nodes().collect::<Vec<_>>()
+        );
+        assert_eq!(Some((1, 1, 1, 1)), merge.target_of(&(3, 5, 5, 5)));
+        assert_eq!(0, test_hierarchy().truncate(3).merged_node_count());
+    }
+}
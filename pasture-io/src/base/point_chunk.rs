@@ -0,0 +1,410 @@
+use std::{
+    any::Any,
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    fmt::Display,
+    ops::Range,
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender},
+        Arc,
+    },
+};
+
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBuffer},
+    math::AABB,
+    meta::Metadata,
+    nalgebra::Matrix4,
+};
+use static_assertions::assert_impl_all;
+
+use super::{PointReader, Result};
+
+/// Snapshot of the `Metadata` of a point source that can be shared between threads. Since `Metadata` is neither `Send`
+/// nor `Sync`, [PointChunk]s carry a snapshot of the general metadata fields and of the textual representation of the
+/// metadata instead. Format-specific named fields are not part of the snapshot
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetadataSnapshot {
+    bounds: Option<AABB<f64>>,
+    number_of_points: Option<usize>,
+    transform: Option<Matrix4<f64>>,
+    description: String,
+}
+
+impl MetadataSnapshot {
+    /// Creates a snapshot of the given `metadata`
+    pub fn from_metadata(metadata: &dyn Metadata) -> Self {
+        Self {
+            bounds: metadata.bounds(),
+            number_of_points: metadata.number_of_points(),
+            transform: metadata.transform(),
+            description: metadata.to_string(),
+        }
+    }
+}
+
+impl Display for MetadataSnapshot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description)
+    }
+}
+
+impl Metadata for MetadataSnapshot {
+    fn bounds(&self) -> Option<AABB<f64>> {
+        self.bounds
+    }
+
+    fn number_of_points(&self) -> Option<usize> {
+        self.number_of_points
+    }
+
+    fn get_named_field(&self, _field_name: &str) -> Option<Box<dyn Any>> {
+        None
+    }
+
+    fn transform(&self) -> Option<Matrix4<f64>> {
+        self.transform
+    }
+
+    fn clone_into_box(&self) -> Box<dyn Metadata> {
+        Box::new(self.clone())
+    }
+}
+
+/// A chunk of points together with the bookkeeping that multi-threaded pipelines need: The `sequence_number` gives the
+/// order of the chunks of a source, the `source_range` gives the indices of the points within the source, and the
+/// `metadata` of the source is shared by all its chunks. `PointChunk` is `Send` and `Sync`, so it can be passed
+/// through channels, e.g. the ones created by [point_chunk_channel]
+pub struct PointChunk {
+    /// Position of this chunk within the chunks of its source, starting at zero
+    pub sequence_number: u64,
+    /// Range of the indices of the points of this chunk within their source. Stays the same if the points are
+    /// transformed or filtered
+    pub source_range: Range<usize>,
+    /// The points of this chunk
+    pub points: InterleavedVecPointStorage,
+    /// Snapshot of the metadata of the source of this chunk, if known
+    pub metadata: Option<Arc<MetadataSnapshot>>,
+}
+
+assert_impl_all!(PointChunk: Send, Sync);
+
+impl PointChunk {
+    /// Creates a new `PointChunk` for the points with the indices `source_range` within their source
+    pub fn new(
+        sequence_number: u64,
+        source_range: Range<usize>,
+        points: InterleavedVecPointStorage,
+        metadata: Option<Arc<MetadataSnapshot>>,
+    ) -> Self {
+        Self {
+            sequence_number,
+            source_range,
+            points,
+            metadata,
+        }
+    }
+
+    /// Returns a chunk with the same bookkeeping as this chunk, but with the given `points`. Use this for pipeline
+    /// stages that transform or filter the points of a chunk
+    pub fn with_points(self, points: InterleavedVecPointStorage) -> Self {
+        Self { points, ..self }
+    }
+
+    /// Returns a chunk with the same bookkeeping as this chunk and the points that `f` returns for the points of this
+    /// chunk
+    ///
+    /// # Errors
+    ///
+    /// If `f` returns an error, this error is returned
+    pub fn try_map_points<F>(self, f: F) -> Result<Self>
+    where
+        F: FnOnce(InterleavedVecPointStorage) -> Result<InterleavedVecPointStorage>,
+    {
+        let Self {
+            sequence_number,
+            source_range,
+            points,
+            metadata,
+        } = self;
+        Ok(Self {
+            sequence_number,
+            source_range,
+            points: f(points)?,
+            metadata,
+        })
+    }
+
+    /// Returns the number of points in this chunk
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns `true` if this chunk has no points
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+/// Iterator over the points of a `PointReader` as [PointChunk]s, created by [read_chunks]
+pub struct PointChunks<'a, R: PointReader + ?Sized> {
+    reader: &'a mut R,
+    chunk_size: usize,
+    metadata: Arc<MetadataSnapshot>,
+    next_sequence_number: u64,
+    next_point_index: usize,
+    done: bool,
+}
+
+impl<'a, R: PointReader + ?Sized> Iterator for PointChunks<'a, R> {
+    type Item = Result<PointChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let mut points = InterleavedVecPointStorage::with_capacity(
+            self.chunk_size,
+            self.reader.get_default_point_layout().clone(),
+        );
+        let count = match self.reader.read_into(&mut points, self.chunk_size) {
+            Ok(count) => count,
+            Err(error) => {
+                self.done = true;
+                return Some(Err(error));
+            }
+        };
+        if count == 0 {
+            self.done = true;
+            return None;
+        }
+        let chunk = PointChunk::new(
+            self.next_sequence_number,
+            self.next_point_index..self.next_point_index + count,
+            points,
+            Some(self.metadata.clone()),
+        );
+        self.next_sequence_number += 1;
+        self.next_point_index += count;
+        Some(Ok(chunk))
+    }
+}
+
+/// Reads the remaining points of `reader` as [PointChunk]s of at most `chunk_size` points in the default `PointLayout`
+/// of the reader. The chunks are numbered from zero, and their source ranges are relative to the point at which the
+/// reader was positioned when calling this function. The iteration stops after the first error
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::LASReader;
+/// # fn main() -> Result<()> {
+/// let mut reader = LASReader::from_path("in.las")?;
+/// let (sender, receiver) = point_chunk_channel(4);
+/// let worker = std::thread::spawn(move || {
+///     receiver.into_iter().map(|chunk| chunk.len()).sum::<usize>()
+/// });
+/// for chunk in read_chunks(&mut reader, 50_000) {
+///     sender.send(chunk?)?;
+/// }
+/// drop(sender);
+/// println!("{} points", worker.join().unwrap());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// If `chunk_size` is zero
+pub fn read_chunks<R: PointReader + ?Sized>(
+    reader: &mut R,
+    chunk_size: usize,
+) -> PointChunks<'_, R> {
+    if chunk_size == 0 {
+        panic!("read_chunks: chunk_size must be > 0");
+    }
+    let metadata = Arc::new(MetadataSnapshot::from_metadata(reader.get_metadata()));
+    PointChunks {
+        reader,
+        chunk_size,
+        metadata,
+        next_sequence_number: 0,
+        next_point_index: 0,
+        done: false,
+    }
+}
+
+/// Creates a bounded channel for [PointChunk]s. Sending blocks once `capacity` chunks wait in the channel, which
+/// bounds the memory of a pipeline stage that produces chunks faster than the next stage consumes them
+pub fn point_chunk_channel(capacity: usize) -> (SyncSender<PointChunk>, Receiver<PointChunk>) {
+    sync_channel(capacity)
+}
+
+/// Orders `PointChunk`s by their sequence number, smallest first in a `BinaryHeap`
+struct BySequenceNumber(PointChunk);
+
+impl PartialEq for BySequenceNumber {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.sequence_number == other.0.sequence_number
+    }
+}
+
+impl Eq for BySequenceNumber {}
+
+impl PartialOrd for BySequenceNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BySequenceNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.sequence_number.cmp(&other.0.sequence_number)
+    }
+}
+
+/// Iterator that restores the order of [PointChunk]s, created by [in_sequence_order]
+pub struct InSequenceOrder<I: Iterator<Item = PointChunk>> {
+    chunks: I,
+    pending: BinaryHeap<Reverse<BySequenceNumber>>,
+    next_sequence_number: u64,
+}
+
+impl<I: Iterator<Item = PointChunk>> InSequenceOrder<I> {
+    /// Returns the number of chunks that arrived early and wait for a chunk with a smaller sequence number
+    pub fn pending_chunks(&self) -> usize {
+        self.pending.len()
+    }
+}
+
+impl<I: Iterator<Item = PointChunk>> Iterator for InSequenceOrder<I> {
+    type Item = PointChunk;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next_is_pending = self
+                .pending
+                .peek()
+                .map(|Reverse(chunk)| chunk.0.sequence_number <= self.next_sequence_number);
+            if next_is_pending == Some(true) {
+                let Reverse(BySequenceNumber(chunk)) = self.pending.pop().unwrap();
+                self.next_sequence_number = chunk.sequence_number + 1;
+                return Some(chunk);
+            }
+            match self.chunks.next() {
+                Some(chunk) => self.pending.push(Reverse(BySequenceNumber(chunk))),
+                // A missing sequence number can't arrive anymore, so the remaining chunks are returned in order
+                None => {
+                    let Reverse(BySequenceNumber(chunk)) = self.pending.pop()?;
+                    self.next_sequence_number = chunk.sequence_number + 1;
+                    return Some(chunk);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the `chunks` ordered by their sequence numbers, starting at zero. Use this after a pipeline stage with
+/// multiple worker threads, which finish their chunks in an arbitrary order. Chunks that arrive early are kept until
+/// all chunks before them have arrived, so the number of pending chunks is bounded by the number of chunks that can
+/// overtake each other
+///
+/// ```
+/// # use pasture_core::{containers::InterleavedVecPointStorage, layout::PointLayout};
+/// # use pasture_io::base::*;
+/// let chunk = |sequence_number| {
+///     PointChunk::new(sequence_number, 0..0, InterleavedVecPointStorage::new(PointLayout::default()), None)
+/// };
+/// let ordered = in_sequence_order(vec![chunk(1), chunk(2), chunk(0)].into_iter())
+///     .map(|chunk| chunk.sequence_number)
+///     .collect::<Vec<_>>();
+/// assert_eq!(vec![0, 1, 2], ordered);
+/// ```
+pub fn in_sequence_order<I: Iterator<Item = PointChunk>>(chunks: I) -> InSequenceOrder<I> {
+    InSequenceOrder {
+        chunks,
+        pending: BinaryHeap::new(),
+        next_sequence_number: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use pasture_core::containers::PointBufferExt;
+
+    use super::*;
+    use crate::las::{get_test_las_path, LASReader, LasPointFormat0};
+
+    #[test]
+    fn test_read_chunks() -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(0))?;
+        let chunks = read_chunks(&mut reader, 4).collect::<Result<Vec<_>>>()?;
+        assert_eq!(3, chunks.len());
+        assert_eq!(
+            vec![0..4, 4..8, 8..10],
+            chunks
+                .iter()
+                .map(|chunk| chunk.source_range.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![0, 1, 2],
+            chunks
+                .iter()
+                .map(|chunk| chunk.sequence_number)
+                .collect::<Vec<_>>()
+        );
+        let metadata = chunks[0].metadata.as_ref().unwrap();
+        assert_eq!(Some(10), metadata.number_of_points());
+        assert!(Arc::ptr_eq(metadata, chunks[2].metadata.as_ref().unwrap()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_chunks_through_worker_threads() -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(0))?;
+        let expected = reader
+            .read(10)?
+            .iter_point::<LasPointFormat0>()
+            .collect::<Vec<_>>();
+        let mut reader = LASReader::from_path(get_test_las_path(0))?;
+
+        // A worker that swaps each pair of chunks, as if it had two threads that finish in the wrong order
+        let (sender, receiver) = point_chunk_channel(2);
+        let (result_sender, result_receiver) = point_chunk_channel(16);
+        let worker = thread::spawn(move || {
+            let mut previous: Option<PointChunk> = None;
+            for chunk in receiver {
+                match previous.take() {
+                    Some(previous) => {
+                        result_sender.send(chunk).unwrap();
+                        result_sender.send(previous).unwrap();
+                    }
+                    None => previous = Some(chunk),
+                }
+            }
+            if let Some(previous) = previous {
+                result_sender.send(previous).unwrap();
+            }
+        });
+        for chunk in read_chunks(&mut reader, 3) {
+            sender.send(chunk?)?;
+        }
+        drop(sender);
+        worker.join().unwrap();
+
+        let mut ordered = in_sequence_order(result_receiver.into_iter());
+        let mut actual = vec![];
+        let mut next_index = 0;
+        for chunk in &mut ordered {
+            assert_eq!(next_index, chunk.source_range.start);
+            next_index = chunk.source_range.end;
+            actual.extend(chunk.points.iter_point::<LasPointFormat0>());
+        }
+        assert_eq!(0, ordered.pending_chunks());
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+}
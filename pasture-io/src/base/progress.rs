@@ -0,0 +1,205 @@
+use std::io::SeekFrom;
+
+use pasture_core::{
+    containers::{PointBuffer, PointBufferWriteable},
+    layout::PointLayout,
+    meta::Metadata,
+    util::Progress,
+};
+
+use super::{Capabilities, PointReader, PointWriter, Result, SeekToPoint};
+
+/// Wrapper around a `PointReader` that reports the number of read points to a [Progress](pasture_core::util::Progress)
+/// and stops reading with a `PastureIoError::Other` that wraps a [Cancelled](pasture_core::util::Cancelled) error once
+/// the operation is cancelled
+///
+/// ```no_run
+/// # use pasture_core::util::*;
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::LASReader;
+/// let reader = LASReader::from_path("in.las").unwrap();
+/// let token = CancellationToken::new();
+/// let progress = Progress::default()
+///     .with_callback(|read: usize, total: Option<usize>| println!("{}/{} points", read, total.unwrap()))
+///     .with_cancellation_token(token.clone());
+/// let mut reader = ProgressReader::new(reader, progress);
+/// let points = reader.read(1000).unwrap();
+/// ```
+pub struct ProgressReader<R: PointReader> {
+    reader: R,
+    progress: Progress,
+    total: Option<usize>,
+    points_read: usize,
+}
+
+impl<R: PointReader + SeekToPoint> ProgressReader<R> {
+    /// Creates a new `ProgressReader` that wraps the given `reader`. The total number of points that is reported to
+    /// `progress` is the number of points in `reader`
+    pub fn new(mut reader: R, progress: Progress) -> Self {
+        let total = reader.point_count().ok();
+        let points_read = reader.point_index().unwrap_or(0);
+        Self {
+            reader,
+            progress,
+            total,
+            points_read,
+        }
+    }
+}
+
+impl<R: PointReader> ProgressReader<R> {
+    /// Creates a new `ProgressReader` for a `reader` that does not support seeking, together with the `total` number
+    /// of points that is reported to `progress`, if it is known
+    pub fn with_total(reader: R, progress: Progress, total: Option<usize>) -> Self {
+        Self {
+            reader,
+            progress,
+            total,
+            points_read: 0,
+        }
+    }
+
+    /// Returns a reference to the wrapped reader
+    pub fn inner(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the wrapped reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: PointReader> PointReader for ProgressReader<R> {
+    fn read(&mut self, count: usize) -> Result<Box<dyn PointBuffer>> {
+        self.progress.check_cancelled()?;
+        let points = self.reader.read(count)?;
+        self.points_read += points.len();
+        self.progress.report(self.points_read, self.total);
+        Ok(points)
+    }
+
+    fn read_into(
+        &mut self,
+        point_buffer: &mut dyn PointBufferWriteable,
+        count: usize,
+    ) -> Result<usize> {
+        self.progress.check_cancelled()?;
+        let points_read = self.reader.read_into(point_buffer, count)?;
+        self.points_read += points_read;
+        self.progress.report(self.points_read, self.total);
+        Ok(points_read)
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        self.reader.get_metadata()
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        self.reader.get_default_point_layout()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.reader.capabilities()
+    }
+}
+
+impl<R: PointReader + SeekToPoint> SeekToPoint for ProgressReader<R> {
+    fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
+        let new_position = self.reader.seek_point(position)?;
+        self.points_read = new_position;
+        Ok(new_position)
+    }
+}
+
+/// Wrapper around a `PointWriter` that reports the number of written points to a [Progress](pasture_core::util::Progress)
+/// and stops writing with a `PastureIoError::Other` that wraps a [Cancelled](pasture_core::util::Cancelled) error once
+/// the operation is cancelled. Points that were written before the cancellation stay valid and are flushed once the
+/// writer is dropped
+pub struct ProgressWriter<W: PointWriter> {
+    writer: W,
+    progress: Progress,
+    total: Option<usize>,
+    points_written: usize,
+}
+
+impl<W: PointWriter> ProgressWriter<W> {
+    /// Creates a new `ProgressWriter` that wraps the given `writer`, together with the `total` number of points that
+    /// will be written, if it is known
+    pub fn new(writer: W, progress: Progress, total: Option<usize>) -> Self {
+        Self {
+            writer,
+            progress,
+            total,
+            points_written: 0,
+        }
+    }
+
+    /// Returns a reference to the wrapped writer
+    pub fn inner(&self) -> &W {
+        &self.writer
+    }
+
+    /// Returns the wrapped writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: PointWriter> PointWriter for ProgressWriter<W> {
+    fn write(&mut self, points: &dyn PointBuffer) -> Result<()> {
+        self.progress.check_cancelled()?;
+        self.writer.write(points)?;
+        self.points_written += points.len();
+        self.progress.report(self.points_written, self.total);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        self.writer.get_default_point_layout()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        self.writer.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use pasture_core::util::{CancellationToken, Cancelled};
+
+    use super::*;
+    use crate::{
+        base::PastureIoError,
+        las::{get_test_las_path, LASReader},
+    };
+
+    #[test]
+    fn test_progress_reader_reports_and_cancels() -> Result<()> {
+        let reports = Arc::new(Mutex::new(vec![]));
+        let reports_clone = reports.clone();
+        let token = CancellationToken::new();
+        let progress = Progress::default()
+            .with_callback(move |read: usize, total: Option<usize>| {
+                reports_clone.lock().unwrap().push((read, total))
+            })
+            .with_cancellation_token(token.clone());
+
+        let mut reader = ProgressReader::new(LASReader::from_path(get_test_las_path(0))?, progress);
+        reader.read(4)?;
+        reader.read(4)?;
+        assert_eq!(vec![(4, Some(10)), (8, Some(10))], *reports.lock().unwrap());
+
+        token.cancel();
+        let error = reader.read(2).unwrap_err();
+        assert!(matches!(error, PastureIoError::Other(error) if error.is::<Cancelled>()));
+        assert_eq!(2, reports.lock().unwrap().len());
+        Ok(())
+    }
+}
@@ -0,0 +1,163 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{PastureIoError, Result};
+
+/// A single processing step in the `ProcessingHistory` of a dataset, i.e. one run of a tool or one stage of a pipeline
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessingStep {
+    /// Name of the tool or pipeline stage that performed this step, e.g. `filters.voxelgrid`
+    pub tool: String,
+    /// Version of the tool
+    pub version: String,
+    /// Parameters of the step, by their name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parameters: BTreeMap<String, String>,
+    /// Time at which the step started
+    pub started: DateTime<Utc>,
+    /// Time at which the step finished, if the step finished
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished: Option<DateTime<Utc>>,
+}
+
+impl ProcessingStep {
+    /// Creates a new `ProcessingStep` for the given `tool` and `version` that starts now
+    /// ```
+    /// # use pasture_io::base::*;
+    /// let step = ProcessingStep::start("filters.voxelgrid", "0.1.0")
+    ///     .with_parameter("cell", 0.5)
+    ///     .finish();
+    /// assert_eq!(Some("0.5"), step.parameters.get("cell").map(|value| value.as_str()));
+    /// assert!(step.finished.is_some());
+    /// ```
+    pub fn start<S: Into<String>, V: Into<String>>(tool: S, version: V) -> Self {
+        Self {
+            tool: tool.into(),
+            version: version.into(),
+            parameters: BTreeMap::new(),
+            started: Utc::now(),
+            finished: None,
+        }
+    }
+
+    /// Adds the parameter with the given `name` and `value` to this step
+    pub fn with_parameter<S: Into<String>, V: ToString>(mut self, name: S, value: V) -> Self {
+        self.parameters.insert(name.into(), value.to_string());
+        self
+    }
+
+    /// Marks this step as finished now
+    pub fn finish(mut self) -> Self {
+        self.finished = Some(Utc::now());
+        self
+    }
+}
+
+impl Display for ProcessingStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({}",
+            self.tool,
+            self.version,
+            self.started.to_rfc3339_opts(SecondsFormat::Secs, true)
+        )?;
+        if let Some(finished) = &self.finished {
+            write!(
+                f,
+                " - {}",
+                finished.to_rfc3339_opts(SecondsFormat::Secs, true)
+            )?;
+        }
+        write!(f, ")")?;
+        for (name, value) in self.parameters.iter() {
+            write!(f, " {}={}", name, value)?;
+        }
+        Ok(())
+    }
+}
+
+/// The processing history of a dataset, which documents how the dataset was produced. Each tool or pipeline stage that
+/// processes the dataset appends a `ProcessingStep`, and writers persist the history where the file format allows it
+/// (see e.g. `las_processing_history_vlr`). The history is stored as JSON
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProcessingHistory {
+    steps: Vec<ProcessingStep>,
+}
+
+impl ProcessingHistory {
+    /// Creates a new empty `ProcessingHistory`
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns all steps of this history, from the oldest to the newest step
+    pub fn steps(&self) -> &[ProcessingStep] {
+        &self.steps
+    }
+
+    /// Returns `true` if this history has no steps
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Appends the given `step` to this history
+    pub fn push(&mut self, step: ProcessingStep) {
+        self.steps.push(step);
+    }
+
+    /// Appends all steps of `other` to this history. This is used when datasets are merged, so that the history of the
+    /// result contains the steps of all inputs
+    pub fn extend(&mut self, other: &ProcessingHistory) {
+        self.steps.extend(other.steps.iter().cloned());
+    }
+
+    /// Serializes this history to JSON
+    pub fn to_json(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// Deserializes a history from the given JSON `data`, as written by `to_json`
+    ///
+    /// # Errors
+    ///
+    /// If `data` is not a valid JSON processing history
+    pub fn from_json(data: &[u8]) -> Result<Self> {
+        serde_json::from_slice(data)
+            .map_err(|e| PastureIoError::InvalidData(format!("Invalid processing history: {}", e)))
+    }
+}
+
+impl Display for ProcessingHistory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, step) in self.steps.iter().enumerate() {
+            writeln!(f, "\t{}. {}", index + 1, step)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_processing_history_json_roundtrip() -> Result<()> {
+        let mut history = ProcessingHistory::new();
+        history.push(
+            ProcessingStep::start("readers.las", "0.1.0")
+                .with_parameter("filename", "in.laz")
+                .finish(),
+        );
+        history
+            .push(ProcessingStep::start("filters.decimation", "0.1.0").with_parameter("step", 10));
+
+        let json = history.to_json()?;
+        assert_eq!(history, ProcessingHistory::from_json(&json)?);
+        assert_eq!(2, history.to_string().lines().count());
+        assert!(ProcessingHistory::from_json(b"[1, 2, 3]").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    io::{Error, ErrorKind, Read, Seek, SeekFrom},
+};
+
+/// A source of bytes that is read in byte ranges, like a remote resource that supports HTTP range requests or a
+/// memory-mapped file. Wrap a source in a [RangeReader] to read it through `Read + Seek`, and the `RangeReader` in a
+/// [CachedReader](super::CachedReader), so that each block of the cache is fetched with a single range request and
+/// repeated reads of the same byte ranges are answered from the cache
+pub trait RangeSource: Send {
+    /// Returns the length of the source in bytes
+    fn length(&mut self) -> std::io::Result<u64>;
+    /// Reads `buffer.len()` bytes starting at `offset` into `buffer`. The range is within the length of the source
+    fn read_range(&mut self, offset: u64, buffer: &mut [u8]) -> std::io::Result<()>;
+    /// Returns an ID that identifies the data of this source, so that readers of the same data can share their
+    /// cached blocks (see [source_id_for_path](super::source_id_for_path)). Sources without an ID don't share blocks
+    fn source_id(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// Returns a source ID for the resource at the given `url`, like [source_id_for_path](super::source_id_for_path)
+/// does for files. The resource must not change while its blocks are cached
+pub fn source_id_for_url(url: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    url.hash(&mut hasher);
+    // Keep the upper half of the ID range for the IDs of readers that don't share their blocks
+    hasher.finish() >> 1
+}
+
+/// `Read + Seek` adapter for a [RangeSource]. Every call to `read` is one range request, so a `RangeReader` should
+/// be read through a [CachedReader](super::CachedReader) or a `BufReader`
+pub struct RangeReader<S: RangeSource> {
+    source: S,
+    position: u64,
+    length: u64,
+}
+
+impl<S: RangeSource> RangeReader<S> {
+    /// Creates a new `RangeReader` for the given `source`
+    ///
+    /// # Errors
+    ///
+    /// If the length of `source` can't be determined, an error is returned
+    pub fn new(mut source: S) -> std::io::Result<Self> {
+        let length = source.length()?;
+        Ok(Self {
+            source,
+            position: 0,
+            length,
+        })
+    }
+
+    /// Returns the wrapped source
+    pub fn source(&self) -> &S {
+        &self.source
+    }
+
+    /// Returns the wrapped source
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S: RangeSource> Read for RangeReader<S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() || self.position >= self.length {
+            return Ok(0);
+        }
+        let count = (self.length - self.position).min(buf.len() as u64) as usize;
+        self.source.read_range(self.position, &mut buf[..count])?;
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl<S: RangeSource> Seek for RangeReader<S> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::End(offset) => self.length as i128 + offset as i128,
+            SeekFrom::Current(offset) => self.position as i128 + offset as i128,
+        };
+        if position < 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Can't seek before the start of the source",
+            ));
+        }
+        self.position = position as u64;
+        Ok(self.position)
+    }
+}
+
+/// [RangeSource] for a memory-mapped file. Requires the `mmap` feature
+#[cfg(feature = "mmap")]
+pub struct MmapSource {
+    map: memmap2::Mmap,
+    source_id: u64,
+}
+
+#[cfg(feature = "mmap")]
+impl MmapSource {
+    /// Maps the file at the given `path` into memory
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be opened or mapped, an error is returned
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Self> {
+        let file = std::fs::File::open(path.as_ref())?;
+        // Safety: The file must not be modified while it is mapped, which is the same requirement as for the blocks
+        // of the file in a `BlockCache`
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self {
+            map,
+            source_id: super::source_id_for_path(path),
+        })
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl RangeSource for MmapSource {
+    fn length(&mut self) -> std::io::Result<u64> {
+        Ok(self.map.len() as u64)
+    }
+
+    fn read_range(&mut self, offset: u64, buffer: &mut [u8]) -> std::io::Result<()> {
+        let start = offset as usize;
+        buffer.copy_from_slice(&self.map[start..start + buffer.len()]);
+        Ok(())
+    }
+
+    fn source_id(&self) -> Option<u64> {
+        Some(self.source_id)
+    }
+}
+
+/// [RangeSource] for a remote resource that is read with HTTP range requests. Requires the `http` feature
+#[cfg(feature = "http")]
+pub struct HttpSource {
+    url: String,
+}
+
+#[cfg(feature = "http")]
+impl HttpSource {
+    /// Creates a new `HttpSource` for the resource at the given `url`. The server must support range requests
+    pub fn new<U: Into<String>>(url: U) -> Self {
+        Self { url: url.into() }
+    }
+
+    /// Returns the URL of the resource
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+#[cfg(feature = "http")]
+fn http_error(error: ureq::Error) -> Error {
+    Error::new(ErrorKind::Other, error.to_string())
+}
+
+#[cfg(feature = "http")]
+impl RangeSource for HttpSource {
+    fn length(&mut self) -> std::io::Result<u64> {
+        let response = ureq::head(&self.url).call().map_err(http_error)?;
+        response
+            .header("Content-Length")
+            .and_then(|length| length.parse().ok())
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Response for {} has no valid Content-Length", self.url),
+                )
+            })
+    }
+
+    fn read_range(&mut self, offset: u64, buffer: &mut [u8]) -> std::io::Result<()> {
+        if buffer.is_empty() {
+            return Ok(());
+        }
+        let range = format!("bytes={}-{}", offset, offset + buffer.len() as u64 - 1);
+        let response = ureq::get(&self.url)
+            .set("Range", &range)
+            .call()
+            .map_err(http_error)?;
+        // 200 means that the server ignored the range and sends the whole resource
+        if response.status() != 206 {
+            return Err(Error::new(
+                ErrorKind::Other,
+                format!("Server of {} does not support range requests", self.url),
+            ));
+        }
+        response.into_reader().read_exact(buffer)
+    }
+
+    fn source_id(&self) -> Option<u64> {
+        Some(source_id_for_url(&self.url))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{BlockCache, CachedReader};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    /// In-memory `RangeSource` that counts its range requests
+    struct CountingSource {
+        data: Vec<u8>,
+        requests: Arc<AtomicUsize>,
+    }
+
+    impl RangeSource for CountingSource {
+        fn length(&mut self) -> std::io::Result<u64> {
+            Ok(self.data.len() as u64)
+        }
+
+        fn read_range(&mut self, offset: u64, buffer: &mut [u8]) -> std::io::Result<()> {
+            self.requests.fetch_add(1, Ordering::SeqCst);
+            let start = offset as usize;
+            buffer.copy_from_slice(&self.data[start..start + buffer.len()]);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_cache_hit_avoids_range_request() -> std::io::Result<()> {
+        let data = (0..1000).map(|value| value as u8).collect::<Vec<_>>();
+        let requests = Arc::new(AtomicUsize::new(0));
+        let source = CountingSource {
+            data: data.clone(),
+            requests: requests.clone(),
+        };
+        let cache = BlockCache::new_shared(256, 4096);
+        let mut reader = CachedReader::new(RangeReader::new(source)?, cache.clone())?;
+
+        let mut read = vec![0; 100];
+        reader.seek(SeekFrom::Start(300))?;
+        reader.read_exact(&mut read)?;
+        assert_eq!(&data[300..400], &read[..]);
+        // One request for the block that contains the range
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+
+        reader.seek(SeekFrom::Start(300))?;
+        reader.read_exact(&mut read)?;
+        assert_eq!(&data[300..400], &read[..]);
+        assert_eq!(1, requests.load(Ordering::SeqCst));
+        let statistics = cache.lock().unwrap().statistics();
+        assert_eq!(1, statistics.misses);
+        assert_eq!(1, statistics.hits);
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_reader() -> std::io::Result<()> {
+        let data = (0..100).map(|value| value as u8).collect::<Vec<_>>();
+        let mut reader = RangeReader::new(CountingSource {
+            data: data.clone(),
+            requests: Default::default(),
+        })?;
+        let mut read = vec![];
+        reader.seek(SeekFrom::End(-10))?;
+        reader.read_to_end(&mut read)?;
+        assert_eq!(&data[90..], &read[..]);
+        assert!(reader.seek(SeekFrom::Current(-101)).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+use std::{
+    io::{Error, ErrorKind, Read, Seek, SeekFrom},
+    sync::mpsc::{channel, sync_channel, Receiver, Sender, SyncSender, TryRecvError},
+    thread,
+};
+
+/// Default size of the blocks that a [ReadAhead] reads at once
+pub const DEFAULT_READ_AHEAD_BLOCK_SIZE: usize = 4 * 1024 * 1024;
+
+/// A block of bytes read by the background thread of a [ReadAhead]. `generation` identifies the seek operation after
+/// which the block was read, so that blocks from before a seek can be discarded. An empty block marks the end of the
+/// source
+struct Block {
+    generation: u64,
+    data: std::io::Result<Vec<u8>>,
+}
+
+/// Reads into `buffer` until it is full or the end of `read` is reached, and returns the number of bytes read
+fn read_fully<R: Read>(read: &mut R, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut total = 0;
+    while total < buffer.len() {
+        match read.read(&mut buffer[total..]) {
+            Ok(0) => break,
+            Ok(bytes) => total += bytes,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(total)
+}
+
+/// Body of the background thread of a `ReadAhead`. For each request (generation, position), reads consecutive blocks
+/// starting at position until the end of the source is reached or the next request arrives. Stops once the `ReadAhead`
+/// is dropped
+fn prefetch<R: Read + Seek>(
+    mut inner: R,
+    block_size: usize,
+    requests: Receiver<(u64, u64)>,
+    blocks: SyncSender<Block>,
+) {
+    let mut next_request = requests.recv().ok();
+    while let Some((generation, position)) = next_request.take() {
+        if let Err(e) = inner.seek(SeekFrom::Start(position)) {
+            if blocks
+                .send(Block {
+                    generation,
+                    data: Err(e),
+                })
+                .is_err()
+            {
+                return;
+            }
+        } else {
+            loop {
+                // A new request (i.e. a seek) ends the current sequential scan
+                match requests.try_recv() {
+                    Ok(request) => {
+                        next_request = Some(request);
+                        break;
+                    }
+                    Err(TryRecvError::Disconnected) => return,
+                    Err(TryRecvError::Empty) => {}
+                }
+                let mut data = vec![0; block_size];
+                let result = read_fully(&mut inner, &mut data);
+                let is_last_block = !matches!(result, Ok(bytes) if bytes > 0);
+                let data = result.map(|bytes| {
+                    data.truncate(bytes);
+                    data
+                });
+                // Blocks until the previous block is taken by the ReadAhead, which bounds the memory usage
+                if blocks.send(Block { generation, data }).is_err() {
+                    return;
+                }
+                if is_last_block {
+                    break;
+                }
+            }
+        }
+        if next_request.is_none() {
+            next_request = requests.recv().ok();
+        }
+    }
+}
+
+/// Wrapper around a `Read + Seek` source that reads the next block of the source on a background thread while the
+/// caller processes the current block (double buffering). For sequential scans, this hides the latency of the source
+/// (spinning disks, network storage) behind the decoding and processing of the points. Seeking outside of the current
+/// block discards the prefetched data and restarts reading at the new position, so `ReadAhead` works best for readers
+/// that seek rarely
+///
+/// All readers in pasture-io that read from a `Read + Seek` source can use a `ReadAhead`, for example with
+/// [LASReader::from_path_with_read_ahead](crate::las::LASReader::from_path_with_read_ahead)
+///
+/// ```no_run
+/// # use std::fs::File;
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::LASReader;
+/// let file = ReadAhead::new(File::open("in.laz").unwrap(), DEFAULT_READ_AHEAD_BLOCK_SIZE).unwrap();
+/// let mut reader = LASReader::from_read(file, true).unwrap();
+/// let points = reader.read(1000).unwrap();
+/// ```
+pub struct ReadAhead {
+    requests: Sender<(u64, u64)>,
+    blocks: Receiver<Block>,
+    generation: u64,
+    length: u64,
+    position: u64,
+    current_block: Vec<u8>,
+    offset_in_block: usize,
+    at_end: bool,
+}
+
+impl ReadAhead {
+    /// Creates a new `ReadAhead` that reads `inner` in blocks of `block_size` bytes on a background thread, starting at
+    /// the current position of `inner`. At most two blocks are read ahead of the current block
+    ///
+    /// # Errors
+    ///
+    /// If the length of `inner` can't be determined, or if the background thread can't be started, an error is
+    /// returned
+    ///
+    /// # Panics
+    ///
+    /// If `block_size` is zero
+    pub fn new<R: Read + Seek + Send + 'static>(
+        mut inner: R,
+        block_size: usize,
+    ) -> std::io::Result<Self> {
+        if block_size == 0 {
+            panic!("ReadAhead::new: block_size must be > 0");
+        }
+        let position = inner.seek(SeekFrom::Current(0))?;
+        let length = inner.seek(SeekFrom::End(0))?;
+
+        let (request_sender, request_receiver) = channel();
+        let (block_sender, block_receiver) = sync_channel(1);
+        thread::Builder::new()
+            .name("pasture-read-ahead".into())
+            .spawn(move || prefetch(inner, block_size, request_receiver, block_sender))?;
+
+        let mut read_ahead = Self {
+            requests: request_sender,
+            blocks: block_receiver,
+            generation: 0,
+            length,
+            position,
+            current_block: vec![],
+            offset_in_block: 0,
+            at_end: false,
+        };
+        read_ahead.restart_at(position)?;
+        Ok(read_ahead)
+    }
+
+    /// Discards all prefetched data and lets the background thread continue reading at `position`
+    fn restart_at(&mut self, position: u64) -> std::io::Result<()> {
+        self.generation += 1;
+        self.position = position;
+        self.current_block.clear();
+        self.offset_in_block = 0;
+        self.at_end = false;
+        self.requests
+            .send((self.generation, position))
+            .map_err(|_| Error::new(ErrorKind::BrokenPipe, "Read-ahead thread has stopped"))
+    }
+
+    /// Receives the next block of the current generation from the background thread
+    fn next_block(&mut self) -> std::io::Result<Vec<u8>> {
+        loop {
+            let block = self
+                .blocks
+                .recv()
+                .map_err(|_| Error::new(ErrorKind::BrokenPipe, "Read-ahead thread has stopped"))?;
+            if block.generation == self.generation {
+                return block.data;
+            }
+        }
+    }
+}
+
+impl Read for ReadAhead {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.offset_in_block == self.current_block.len() {
+            if self.at_end {
+                return Ok(0);
+            }
+            match self.next_block() {
+                Ok(data) => {
+                    self.at_end = data.is_empty();
+                    self.current_block = data;
+                    self.offset_in_block = 0;
+                }
+                Err(e) => {
+                    // The background thread stops its scan after an error, so the next read retries at this position
+                    self.restart_at(self.position)?;
+                    return Err(e);
+                }
+            }
+        }
+        let count = buf
+            .len()
+            .min(self.current_block.len() - self.offset_in_block);
+        buf[..count].copy_from_slice(
+            &self.current_block[self.offset_in_block..self.offset_in_block + count],
+        );
+        self.offset_in_block += count;
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+impl Seek for ReadAhead {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::End(offset) => self.length as i128 + offset as i128,
+            SeekFrom::Current(offset) => self.position as i128 + offset as i128,
+        };
+        if position < 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Can't seek before the start of the source",
+            ));
+        }
+        let position = position as u64;
+
+        // Seeking within the current block keeps the prefetched data
+        let block_start = self.position - self.offset_in_block as u64;
+        let block_end = block_start + self.current_block.len() as u64;
+        if position >= block_start && position < block_end {
+            self.offset_in_block = (position - block_start) as usize;
+            self.position = position;
+        } else if position != self.position {
+            self.restart_at(position)?;
+        }
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn read_ahead_reads_same_bytes_as_source() -> std::io::Result<()> {
+        let bytes = (0..10_000)
+            .map(|value| (value % 251) as u8)
+            .collect::<Vec<_>>();
+        let mut read_ahead = ReadAhead::new(Cursor::new(bytes.clone()), 64)?;
+
+        let mut read = vec![];
+        read_ahead.read_to_end(&mut read)?;
+        assert_eq!(bytes, read);
+        assert_eq!(0, read_ahead.read(&mut [0; 8])?);
+
+        // Seeks within the current block, backwards, and past the end
+        let mut chunk = vec![0; 100];
+        for position in &[9990, 9995, 17, 5000, 4990] {
+            read_ahead.seek(SeekFrom::Start(*position))?;
+            let count = read_fully(&mut read_ahead, &mut chunk)?;
+            let position = *position as usize;
+            assert_eq!(&bytes[position..position + count], &chunk[..count]);
+        }
+        assert_eq!(10_000, read_ahead.seek(SeekFrom::End(0))?);
+        assert_eq!(0, read_ahead.read(&mut chunk)?);
+        assert!(read_ahead.seek(SeekFrom::Current(-10_001)).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::Path;
+
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBufferExt},
+    layout::{PointLayout, PointType},
+};
+
+use super::{IOFactory, PointBufferBuilder, Result};
+
+/// Number of points that are read at once by [read_all] and [read_all_into_buffer]
+const READ_ALL_CHUNK_SIZE: usize = 100_000;
+
+/// Reads all points from the file at `path` into a buffer with the given `layout`. The format of the file is
+/// determined from its extension, as in [IOFactory::make_reader]. The reader converts the points into `layout`, so
+/// `layout` can contain a subset of the attributes of the file or attributes with different datatypes
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_core::layout::{attributes, PointLayout};
+/// # fn main() -> Result<()> {
+/// let layout = PointLayout::from_attributes(&[attributes::POSITION_3D, attributes::INTENSITY]);
+/// let points = pasture_io::read_all_into_buffer("input.las", layout)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If the format of the file is not supported, or if an I/O error occurs while reading, an error is returned
+pub fn read_all_into_buffer<P: AsRef<Path>>(
+    path: P,
+    layout: PointLayout,
+) -> Result<InterleavedVecPointStorage> {
+    let mut reader = IOFactory::default().make_reader(path.as_ref())?;
+    let remaining_points = reader.point_count()? - reader.point_index()?;
+    let mut points = PointBufferBuilder::new(layout, remaining_points).interleaved();
+    while reader.read_into(&mut points, READ_ALL_CHUNK_SIZE)? > 0 {}
+    Ok(points)
+}
+
+/// Reads all points from the file at `path` as points of type `T`. The format of the file is determined from its
+/// extension, as in [IOFactory::make_reader]
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_io::las::LasPointFormat1;
+/// # fn main() -> Result<()> {
+/// let points: Vec<LasPointFormat1> = pasture_io::read_all("input.las")?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If the format of the file is not supported, or if an I/O error occurs while reading, an error is returned
+pub fn read_all<T: PointType, P: AsRef<Path>>(path: P) -> Result<Vec<T>> {
+    let points = read_all_into_buffer(path, T::layout())?;
+    Ok(points.iter_point::<T>().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{containers::PointBuffer, layout::attributes, nalgebra::Vector3};
+
+    use super::*;
+    use crate::las::{compare_to_reference_data_range, get_test_las_path, LasPointFormat1};
+
+    #[test]
+    fn test_read_all() -> Result<()> {
+        let points: Vec<LasPointFormat1> = read_all(get_test_las_path(1))?;
+        assert_eq!(10, points.len());
+
+        let mut buffer = InterleavedVecPointStorage::new(LasPointFormat1::layout());
+        buffer.push_points(&points);
+        compare_to_reference_data_range(&buffer, 1, 0..10);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_all_into_buffer() -> Result<()> {
+        let layout = PointLayout::from_attributes(&[attributes::GPS_TIME, attributes::POSITION_3D]);
+        let buffer = read_all_into_buffer(get_test_las_path(1), layout.clone())?;
+        assert_eq!(10, buffer.len());
+        assert_eq!(&layout, buffer.point_layout());
+
+        let expected: Vec<LasPointFormat1> = read_all(get_test_las_path(1))?;
+        let positions = buffer
+            .iter_attribute::<Vector3<f64>>(&attributes::POSITION_3D)
+            .collect::<Vec<_>>();
+        let gps_times = buffer
+            .iter_attribute::<f64>(&attributes::GPS_TIME)
+            .collect::<Vec<_>>();
+        for (idx, point) in expected.iter().enumerate() {
+            assert_eq!(point.position, positions[idx]);
+            assert_eq!(point.gps_time, gps_times[idx]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_all_unsupported_format() {
+        assert!(read_all::<LasPointFormat1, _>("points.unknown").is_err());
+    }
+}
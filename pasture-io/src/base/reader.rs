@@ -1,8 +1,13 @@
-use anyhow::Result;
 use pasture_core::containers::{PointBuffer, PointBufferWriteable};
 use pasture_core::layout::PointLayout;
+use pasture_core::math::AABB;
 use pasture_core::meta::Metadata;
 
+use super::{
+    bounds_of_positions, read_sample_positions, Capabilities, Estimate, EstimateConfidence,
+    EstimateOptions, Result,
+};
+
 /// Base trait for all types that support reading point data
 pub trait PointReader {
     /// Read `count` points from this `PointReader`. Returns an opaque `PointBuffer` type filled with
@@ -22,4 +27,56 @@ pub trait PointReader {
     fn get_metadata(&self) -> &dyn Metadata;
     /// Returns the default `PointLayout` of the associated `PointReader`
     fn get_default_point_layout(&self) -> &PointLayout;
+    /// Returns the `Capabilities` of the associated `PointReader`. The default implementation supports the attributes of
+    /// the default `PointLayout` and none of the optional capabilities
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new(self.get_default_point_layout())
+    }
+
+    /// Returns an estimate of the total number of points of the associated `PointReader` without reading all points.
+    /// The default implementation uses the number of points stored in the `Metadata` and returns `None` if it is
+    /// unknown. Readers for formats without this information in their header (such as ASCII files) can override this
+    /// method to sample a fraction of the points, as controlled by `options`, and extrapolate from there
+    ///
+    /// # Errors
+    ///
+    /// If an error occurs while sampling points, an error is returned
+    fn estimate_point_count(
+        &mut self,
+        options: &EstimateOptions,
+    ) -> Result<Option<Estimate<usize>>> {
+        let _ = options;
+        Ok(self
+            .get_metadata()
+            .number_of_points()
+            .map(|count| Estimate::new(count, EstimateConfidence::Header)))
+    }
+
+    /// Returns an estimate of the bounding box of all points of the associated `PointReader` without reading all
+    /// points. Uses the bounds stored in the `Metadata` where available. Otherwise, the first points are read as a
+    /// sample, as controlled by `options`, and the bounds of the sample are returned with `EstimateConfidence::Sampled`.
+    /// Note that sampling **advances the reader**, so it should be done on a freshly opened reader that is not used
+    /// for reading afterwards. Returns `None` if the points have no positions
+    ///
+    /// # Errors
+    ///
+    /// If an error occurs while sampling points, an error is returned
+    fn estimate_bounds(
+        &mut self,
+        options: &EstimateOptions,
+    ) -> Result<Option<Estimate<AABB<f64>>>> {
+        if let Some(bounds) = self.get_metadata().bounds() {
+            return Ok(Some(Estimate::new(bounds, EstimateConfidence::Header)));
+        }
+        let sample_count = match self.get_metadata().number_of_points() {
+            Some(count) => options.sample_count(count),
+            None => options.max_sample_count,
+        };
+        let positions = match read_sample_positions(self, sample_count)? {
+            Some(positions) => positions,
+            None => return Ok(None),
+        };
+        Ok(bounds_of_positions(&positions)
+            .map(|bounds| Estimate::new(bounds, EstimateConfidence::Sampled)))
+    }
 }
@@ -0,0 +1,329 @@
+use std::fmt::Display;
+
+use pasture_core::{
+    containers::{
+        BufferComparison, ComparisonReport, InterleavedVecPointStorage, PointBuffer,
+        PointBufferWriteable,
+    },
+    layout::{PointAttributeDefinition, PointLayout},
+    math::AABB,
+};
+
+use super::{PointReader, PointWriter, Result};
+
+/// Number of points that are read at once during a round trip
+const ROUNDTRIP_CHUNK_SIZE: usize = 100_000;
+
+/// Tolerances for a round trip through a point cloud format with [check_roundtrip] or [assert_roundtrip]. By default,
+/// all attribute values and the bounds have to be preserved exactly, and all attributes of the source have to be
+/// written
+#[derive(Debug, Clone)]
+pub struct RoundtripTolerances {
+    comparison: BufferComparison,
+    bounds_tolerance: f64,
+    unsupported_attributes: Vec<String>,
+}
+
+impl RoundtripTolerances {
+    /// Creates new `RoundtripTolerances` that require an exact round trip
+    pub fn exact() -> Self {
+        Self {
+            comparison: BufferComparison::new(),
+            bounds_tolerance: 0.0,
+            unsupported_attributes: vec![],
+        }
+    }
+
+    /// Sets the maximum absolute difference at which two values of any attribute are still considered equal, e.g.
+    /// to allow for the quantization of positions in a format
+    ///
+    /// # Panics
+    ///
+    /// If `tolerance` is negative or NaN
+    pub fn with_tolerance(mut self, tolerance: f64) -> Self {
+        self.comparison = self.comparison.with_tolerance(tolerance);
+        self
+    }
+
+    /// Sets the tolerance for the attribute with the given name, overriding the tolerance set with `with_tolerance`
+    ///
+    /// # Panics
+    ///
+    /// If `tolerance` is negative or NaN
+    pub fn with_attribute_tolerance(mut self, attribute_name: &str, tolerance: f64) -> Self {
+        self.comparison = self
+            .comparison
+            .with_attribute_tolerance(attribute_name, tolerance);
+        self
+    }
+
+    /// Sets the maximum absolute difference between the bounds in the metadata of the source and of the written data
+    ///
+    /// # Panics
+    ///
+    /// If `tolerance` is negative or NaN
+    pub fn with_bounds_tolerance(mut self, tolerance: f64) -> Self {
+        if tolerance < 0.0 || tolerance.is_nan() {
+            panic!("RoundtripTolerances::with_bounds_tolerance: tolerance must be >= 0");
+        }
+        self.bounds_tolerance = tolerance;
+        self
+    }
+
+    /// Allows that the attribute with the given name is lost during the round trip, because the format can't store it
+    pub fn with_unsupported_attribute(mut self, attribute_name: &str) -> Self {
+        self.unsupported_attributes.push(attribute_name.to_owned());
+        self
+    }
+}
+
+impl Default for RoundtripTolerances {
+    fn default() -> Self {
+        Self::exact()
+    }
+}
+
+/// Result of a round trip through a point cloud format with [check_roundtrip]
+#[derive(Debug, Clone)]
+pub struct RoundtripReport {
+    /// Default `PointLayout` of the source
+    pub source_layout: PointLayout,
+    /// Default `PointLayout` of the reader for the written data
+    pub written_layout: PointLayout,
+    /// Number of points in the metadata of the source, if known
+    pub source_point_count: Option<usize>,
+    /// Number of points in the metadata of the written data, if known
+    pub written_point_count: Option<usize>,
+    /// Bounds in the metadata of the source, if known
+    pub source_bounds: Option<AABB<f64>>,
+    /// Bounds in the metadata of the written data, if known
+    pub written_bounds: Option<AABB<f64>>,
+    /// Maximum allowed difference between `source_bounds` and `written_bounds`
+    pub bounds_tolerance: f64,
+    /// Attributes of the source that are missing in the written data and are not marked as unsupported
+    pub missing_attributes: Vec<PointAttributeDefinition>,
+    /// Comparison of the points of the source (first buffer) with the points read back (second buffer). Attributes
+    /// that the written data has in addition to the attributes of the source are allowed
+    pub points: ComparisonReport,
+}
+
+impl RoundtripReport {
+    /// Does the point count in the metadata match? Unknown point counts are ignored
+    pub fn point_count_matches(&self) -> bool {
+        match (self.source_point_count, self.written_point_count) {
+            (Some(source), Some(written)) => source == written,
+            _ => true,
+        }
+    }
+
+    /// Do the bounds in the metadata match within the tolerance? Unknown bounds are ignored
+    pub fn bounds_match(&self) -> bool {
+        match (&self.source_bounds, &self.written_bounds) {
+            (Some(source), Some(written)) => {
+                (source.min() - written.min()).amax() <= self.bounds_tolerance
+                    && (source.max() - written.max()).amax() <= self.bounds_tolerance
+            }
+            _ => true,
+        }
+    }
+
+    /// Did the round trip preserve all points, attributes and metadata?
+    pub fn is_match(&self) -> bool {
+        self.missing_attributes.is_empty()
+            && self.point_count_matches()
+            && self.bounds_match()
+            && self.points.first_len == self.points.second_len
+            && self
+                .points
+                .attributes
+                .iter()
+                .all(|attribute| attribute.is_match())
+    }
+}
+
+impl Display for RoundtripReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for attribute in self.missing_attributes.iter() {
+            writeln!(f, "Attribute {} was not written", attribute)?;
+        }
+        if !self.point_count_matches() {
+            writeln!(
+                f,
+                "Point counts in the metadata differ: {:?} vs. {:?}",
+                self.source_point_count, self.written_point_count
+            )?;
+        }
+        if !self.bounds_match() {
+            writeln!(
+                f,
+                "Bounds in the metadata differ by more than {}: {:?} vs. {:?}",
+                self.bounds_tolerance, self.source_bounds, self.written_bounds
+            )?;
+        }
+        write!(f, "{}", self.points)
+    }
+}
+
+/// Reads all remaining points from `reader` in its default `PointLayout`
+fn read_all_points(reader: &mut dyn PointReader) -> Result<InterleavedVecPointStorage> {
+    let mut points = InterleavedVecPointStorage::new(reader.get_default_point_layout().clone());
+    while reader.read_into(&mut points, ROUNDTRIP_CHUNK_SIZE)? > 0 {}
+    Ok(points)
+}
+
+/// Checks that a point cloud format preserves points, attributes and metadata. All points of `source` are written with
+/// `writer`, which is then flushed and dropped. Afterwards, `reopen` has to return a reader for the written data, e.g.
+/// by opening the file that `writer` wrote to. The points and metadata of this reader are compared to the points and
+/// metadata of `source` with the given `tolerances`. This is meant for testing implementations of custom formats, see
+/// also [assert_roundtrip]
+///
+/// # Errors
+///
+/// If reading from `source`, writing with `writer` or reopening the written data fails, an error is returned
+pub fn check_roundtrip<W: PointWriter, R: PointReader, F: FnOnce() -> Result<R>>(
+    source: &mut dyn PointReader,
+    mut writer: W,
+    reopen: F,
+    tolerances: &RoundtripTolerances,
+) -> Result<RoundtripReport> {
+    let source_layout = source.get_default_point_layout().clone();
+    let source_point_count = source.get_metadata().number_of_points();
+    let source_bounds = source.get_metadata().bounds();
+    let source_points = read_all_points(source)?;
+
+    writer.write(&source_points)?;
+    writer.flush()?;
+    drop(writer);
+
+    let mut written = reopen()?;
+    let written_layout = written.get_default_point_layout().clone();
+    let written_point_count = written.get_metadata().number_of_points();
+    let written_bounds = written.get_metadata().bounds();
+    let written_points = read_all_points(&mut written)?;
+
+    let points = tolerances
+        .comparison
+        .compare(&source_points, &written_points);
+    let missing_attributes = points
+        .only_in_first
+        .iter()
+        .filter(|attribute| {
+            !tolerances
+                .unsupported_attributes
+                .iter()
+                .any(|name| name == attribute.name())
+        })
+        .cloned()
+        .collect();
+
+    Ok(RoundtripReport {
+        source_layout,
+        written_layout,
+        source_point_count,
+        written_point_count,
+        source_bounds,
+        written_bounds,
+        bounds_tolerance: tolerances.bounds_tolerance,
+        missing_attributes,
+        points,
+    })
+}
+
+/// Like [check_roundtrip], but panics with a description of all differences if the round trip does not preserve the
+/// points, attributes and metadata of `source`
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::{LASReader, LASWriter};
+/// # fn main() -> Result<()> {
+/// let mut source = LASReader::from_path("input.las")?;
+/// let writer = LASWriter::from_path_and_header("output.las", source.header().clone())?;
+/// assert_roundtrip(
+///     &mut source,
+///     writer,
+///     || LASReader::from_path("output.las"),
+///     &RoundtripTolerances::exact(),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If reading from `source`, writing with `writer` or reopening the written data fails, an error is returned
+///
+/// # Panics
+///
+/// If the round trip does not preserve all points, attributes and metadata within the given `tolerances`
+pub fn assert_roundtrip<W: PointWriter, R: PointReader, F: FnOnce() -> Result<R>>(
+    source: &mut dyn PointReader,
+    writer: W,
+    reopen: F,
+    tolerances: &RoundtripTolerances,
+) -> Result<()> {
+    let report = check_roundtrip(source, writer, reopen, tolerances)?;
+    if !report.is_match() {
+        panic!("assert_roundtrip: Round trip failed:\n{}", report);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pasture_core::layout::attributes;
+    use scopeguard::defer;
+
+    use super::*;
+    use crate::las::{get_test_las_path, LASReader, LASWriter};
+    use crate::las_rs::{point::Format, Builder};
+
+    #[test]
+    fn test_las_roundtrip() -> Result<()> {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_las_roundtrip.las");
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        let mut source = LASReader::from_path(get_test_las_path(1))?;
+        let writer = LASWriter::from_path_and_header(&test_file_path, source.header().clone())?;
+        let report = check_roundtrip(
+            &mut source,
+            writer,
+            || LASReader::from_path(&test_file_path),
+            &RoundtripTolerances::exact(),
+        )?;
+        assert!(report.is_match(), "{}", report);
+        assert_eq!(10, report.points.first_len);
+        assert_eq!(report.source_layout, report.written_layout);
+        Ok(())
+    }
+
+    #[test]
+    fn test_roundtrip_detects_lost_attributes() -> Result<()> {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_roundtrip_detects_lost_attributes.las");
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        // Format 0 has no GPS times, so writing format 1 points as format 0 loses them
+        let mut source = LASReader::from_path(get_test_las_path(1))?;
+        let mut header_builder = Builder::from((1, 4));
+        header_builder.point_format = Format::new(0)?;
+        let writer =
+            LASWriter::from_path_and_header(&test_file_path, header_builder.into_header()?)?;
+        let report = check_roundtrip(
+            &mut source,
+            writer,
+            || LASReader::from_path(&test_file_path),
+            &RoundtripTolerances::exact(),
+        )?;
+        assert!(!report.is_match());
+        assert_eq!(vec![attributes::GPS_TIME], report.missing_attributes);
+        Ok(())
+    }
+}
@@ -0,0 +1,437 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use pasture_core::{
+    containers::{
+        transform_positions, InterleavedVecPointStorage, PointBuffer, PointBufferWriteable,
+        TransformedPointBuffer,
+    },
+    layout::{
+        conversion::get_converter_for_attributes, PointAttributeDataType, PointAttributeDefinition,
+        PointLayout,
+    },
+    meta::Metadata,
+    nalgebra::Matrix4,
+};
+
+use super::{read_all_into_buffer, IOFactory, PastureIoError, Result};
+
+/// Attribute for the index of the scan in a [ScanCollection] that a point belongs to
+pub const SCAN_INDEX: PointAttributeDefinition =
+    PointAttributeDefinition::custom("ScanIndex", PointAttributeDataType::U32);
+
+/// Where the points of a [Scan] come from
+pub enum ScanSource {
+    /// The points are kept in memory
+    Buffer(InterleavedVecPointStorage),
+    /// The points are read from the file at the given path whenever they are needed
+    File(PathBuf),
+}
+
+/// A single scan of a [ScanCollection]. The positions of a scan are stored in the local frame of the scanner, the pose
+/// of the scan transforms them into the common frame of the collection
+pub struct Scan {
+    name: String,
+    pose: Matrix4<f64>,
+    metadata: Option<Box<dyn Metadata>>,
+    source: ScanSource,
+}
+
+impl Scan {
+    /// Returns the name of this scan
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the pose of this scan, i.e. the transformation from the local frame of the scan into the common frame of
+    /// the collection
+    pub fn pose(&self) -> &Matrix4<f64> {
+        &self.pose
+    }
+
+    /// Sets the pose of this scan, e.g. after registering it against the other scans
+    pub fn set_pose(&mut self, pose: Matrix4<f64>) {
+        self.pose = pose;
+    }
+
+    /// Returns the metadata of this scan. Only scans that are read from files have metadata
+    pub fn metadata(&self) -> Option<&dyn Metadata> {
+        self.metadata.as_deref()
+    }
+
+    /// Returns the source of the points of this scan
+    pub fn source(&self) -> &ScanSource {
+        &self.source
+    }
+
+    /// Transforms the positions of `points` from the local frame of this scan into the common frame of the collection
+    pub fn to_common_frame(&self, points: &mut dyn PointBufferWriteable) {
+        let point_count = points.len();
+        transform_positions(points, 0..point_count, &self.pose);
+    }
+}
+
+/// A collection of registered scans, like a terrestrial laser scanning project. Each scan keeps its own identity (name,
+/// pose, metadata and points), while the points of all scans can be accessed in the common frame of the collection,
+/// either scan by scan with [for_each_scan](ScanCollection::for_each_scan) or as a single buffer with
+/// [merge](ScanCollection::merge). Scans from files are only read when their points are requested
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_core::layout::{attributes, PointLayout};
+/// # use pasture_io::base::{ScanCollection, SCAN_INDEX};
+/// # fn main() -> Result<()> {
+/// let mut scans = ScanCollection::new();
+/// scans.add_file("Station 1", "station1.las")?;
+/// scans.add_file("Station 2", "station2.las")?;
+///
+/// let layout = PointLayout::from_attributes(&[attributes::POSITION_3D, SCAN_INDEX]);
+/// let points = scans.merge(&layout)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ScanCollection {
+    scans: Vec<Scan>,
+}
+
+impl ScanCollection {
+    /// Creates a new empty `ScanCollection`
+    pub fn new() -> Self {
+        Self { scans: vec![] }
+    }
+
+    /// Adds a scan with the points in `points` and the given `pose` and returns the index of the scan
+    pub fn add_buffer<S: Into<String>>(
+        &mut self,
+        name: S,
+        points: InterleavedVecPointStorage,
+        pose: Matrix4<f64>,
+    ) -> usize {
+        self.push_scan(Scan {
+            name: name.into(),
+            pose,
+            metadata: None,
+            source: ScanSource::Buffer(points),
+        })
+    }
+
+    /// Adds a scan with the points of the file at `path` and returns the index of the scan. The pose of the scan is
+    /// taken from the metadata of the file (see `Metadata::transform`), or is the identity if the file has no pose
+    ///
+    /// # Errors
+    ///
+    /// If the format of the file is not supported, or if the file can't be opened, an error is returned
+    pub fn add_file<S: Into<String>, P: AsRef<Path>>(&mut self, name: S, path: P) -> Result<usize> {
+        let reader = IOFactory::default().make_reader(path.as_ref())?;
+        let metadata = reader.get_metadata().clone_into_box();
+        Ok(self.push_scan(Scan {
+            name: name.into(),
+            pose: metadata.transform().unwrap_or_else(Matrix4::identity),
+            metadata: Some(metadata),
+            source: ScanSource::File(path.as_ref().to_owned()),
+        }))
+    }
+
+    /// Like [add_file](ScanCollection::add_file), but uses the given `pose` instead of the pose in the metadata of the
+    /// file, e.g. a pose from a separate registration
+    ///
+    /// # Errors
+    ///
+    /// If the format of the file is not supported, or if the file can't be opened, an error is returned
+    pub fn add_file_with_pose<S: Into<String>, P: AsRef<Path>>(
+        &mut self,
+        name: S,
+        path: P,
+        pose: Matrix4<f64>,
+    ) -> Result<usize> {
+        let index = self.add_file(name, path)?;
+        self.scans[index].set_pose(pose);
+        Ok(index)
+    }
+
+    /// Returns the number of scans in this collection
+    pub fn len(&self) -> usize {
+        self.scans.len()
+    }
+
+    /// Is this collection empty?
+    pub fn is_empty(&self) -> bool {
+        self.scans.is_empty()
+    }
+
+    /// Returns all scans of this collection
+    pub fn scans(&self) -> &[Scan] {
+        &self.scans
+    }
+
+    /// Returns the scan at `index`, if it exists
+    pub fn scan(&self, index: usize) -> Option<&Scan> {
+        self.scans.get(index)
+    }
+
+    /// Returns the scan at `index` mutably, if it exists
+    pub fn scan_mut(&mut self, index: usize) -> Option<&mut Scan> {
+        self.scans.get_mut(index)
+    }
+
+    /// Returns the index of the first scan with the given `name`, if it exists
+    pub fn find_scan(&self, name: &str) -> Option<usize> {
+        self.scans.iter().position(|scan| scan.name == name)
+    }
+
+    /// Reads the points of the scan at `index` in the given `layout`. The positions stay in the local frame of the scan,
+    /// the pose of the scan is returned as the transformation of the `TransformedPointBuffer`. For scans from memory,
+    /// attributes of `layout` that the scan doesn't have are default-initialized
+    ///
+    /// # Errors
+    ///
+    /// If there is no scan at `index`, or if an I/O error occurs while reading the scan, an error is returned
+    ///
+    /// # Panics
+    ///
+    /// If an attribute of a scan from memory can't be converted into the datatype of the attribute in `layout`
+    pub fn read_scan(
+        &self,
+        index: usize,
+        layout: &PointLayout,
+    ) -> Result<TransformedPointBuffer<InterleavedVecPointStorage>> {
+        let scan = self.scans.get(index).ok_or_else(|| {
+            PastureIoError::InvalidArgument(format!("There is no scan with index {}", index))
+        })?;
+        let points = match &scan.source {
+            ScanSource::Buffer(source) => {
+                let mut points =
+                    InterleavedVecPointStorage::with_capacity(source.len(), layout.clone());
+                points.resize(source.len());
+                copy_attributes(source, &mut points, 0);
+                points
+            }
+            ScanSource::File(path) => read_all_into_buffer(path, layout.clone())?,
+        };
+        Ok(TransformedPointBuffer::with_transform(points, scan.pose))
+    }
+
+    /// Calls `func` with the index of each scan, the scan itself and its points in the given `layout`. The pose of the
+    /// scan is already applied to the positions, so the points of all scans are in the common frame of the collection.
+    /// Only one scan is kept in memory at a time
+    ///
+    /// # Errors
+    ///
+    /// If an I/O error occurs while reading a scan, or if `func` returns an error, this error is returned
+    pub fn for_each_scan<F: FnMut(usize, &Scan, &InterleavedVecPointStorage) -> Result<()>>(
+        &self,
+        layout: &PointLayout,
+        mut func: F,
+    ) -> Result<()> {
+        for (index, scan) in self.scans.iter().enumerate() {
+            let mut points = self.read_scan(index, layout)?;
+            points.apply_transform();
+            func(index, scan, points.buffer())?;
+        }
+        Ok(())
+    }
+
+    /// Reads the points of all scans into a single buffer with the given `layout`, with all positions in the common
+    /// frame of the collection. If `layout` contains the [SCAN_INDEX] attribute, it is set to the index of the scan
+    /// that each point belongs to
+    ///
+    /// # Errors
+    ///
+    /// If an I/O error occurs while reading a scan, an error is returned
+    pub fn merge(&self, layout: &PointLayout) -> Result<InterleavedVecPointStorage> {
+        // The scan index is not part of the scans themselves, so it is filled in after copying the other attributes
+        let scan_layout = PointLayout::from_attributes(
+            &layout
+                .attributes()
+                .filter(|attribute| attribute.name() != SCAN_INDEX.name())
+                .map(PointAttributeDefinition::from)
+                .collect::<Vec<_>>(),
+        );
+        let scan_index_attribute = layout
+            .get_attribute_by_name(SCAN_INDEX.name())
+            .map(PointAttributeDefinition::from);
+
+        let mut merged = InterleavedVecPointStorage::new(layout.clone());
+        self.for_each_scan(&scan_layout, |index, _, points| {
+            let first_point = merged.len();
+            merged.resize(first_point + points.len());
+            copy_attributes(points, &mut merged, first_point);
+            if let Some(scan_index_attribute) = &scan_index_attribute {
+                set_scan_index(
+                    &mut merged,
+                    scan_index_attribute,
+                    first_point..first_point + points.len(),
+                    index as u32,
+                );
+            }
+            Ok(())
+        })?;
+        Ok(merged)
+    }
+
+    fn push_scan(&mut self, scan: Scan) -> usize {
+        self.scans.push(scan);
+        self.scans.len() - 1
+    }
+}
+
+impl Default for ScanCollection {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Copies all attributes of `target` that `source` has into the points of `target` starting at `target_start`,
+/// converting between datatypes if necessary
+fn copy_attributes(
+    source: &dyn PointBuffer,
+    target: &mut dyn PointBufferWriteable,
+    target_start: usize,
+) {
+    let target_attributes = target
+        .point_layout()
+        .attributes()
+        .map(PointAttributeDefinition::from)
+        .collect::<Vec<_>>();
+    for target_attribute in target_attributes {
+        let source_attribute = match source
+            .point_layout()
+            .get_attribute_by_name(target_attribute.name())
+        {
+            Some(attribute) => PointAttributeDefinition::from(attribute),
+            None => continue,
+        };
+        let converter = get_converter_for_attributes(&source_attribute, &target_attribute);
+        let mut source_buf = vec![0; source_attribute.size() as usize];
+        let mut target_buf = vec![0; target_attribute.size() as usize];
+        for point_index in 0..source.len() {
+            source.get_raw_attribute(point_index, &source_attribute, &mut source_buf);
+            let value = match converter {
+                Some(convert) => {
+                    unsafe {
+                        convert(&source_buf, &mut target_buf);
+                    }
+                    &target_buf
+                }
+                None => &source_buf,
+            };
+            target.set_raw_attribute(target_start + point_index, &target_attribute, value);
+        }
+    }
+}
+
+/// Sets the scan index attribute of the points in `range` of `points` to `scan_index`
+fn set_scan_index(
+    points: &mut dyn PointBufferWriteable,
+    attribute: &PointAttributeDefinition,
+    range: Range<usize>,
+    scan_index: u32,
+) {
+    // The scan index attribute might have been requested with a different datatype
+    let converter = get_converter_for_attributes(&SCAN_INDEX, attribute);
+    let source_buf = scan_index.to_le_bytes();
+    let mut target_buf = vec![0; attribute.size() as usize];
+    let value = match converter {
+        Some(convert) => {
+            unsafe {
+                convert(&source_buf, &mut target_buf);
+            }
+            &target_buf
+        }
+        None => &source_buf[..],
+    };
+    for point_index in range {
+        points.set_raw_attribute(point_index, attribute, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::PointBufferExt,
+        layout::{attributes, PointType},
+        nalgebra::{Translation3, Vector3},
+    };
+
+    use super::*;
+    use crate::las::{get_test_las_path, LasPointFormat1};
+
+    fn scan_layout() -> PointLayout {
+        PointLayout::from_attributes(&[attributes::POSITION_3D, attributes::INTENSITY, SCAN_INDEX])
+    }
+
+    #[test]
+    fn test_scan_collection_merge() -> Result<()> {
+        let mut scans = ScanCollection::new();
+        scans.add_file("Station 1", get_test_las_path(1))?;
+        scans.add_file_with_pose(
+            "Station 2",
+            get_test_las_path(1),
+            Translation3::new(100.0, 0.0, 0.0).to_homogeneous(),
+        )?;
+        assert_eq!(2, scans.len());
+        assert_eq!(Some(1), scans.find_scan("Station 2"));
+        assert!(scans.scan(0).unwrap().metadata().is_some());
+
+        let expected: Vec<LasPointFormat1> = crate::base::read_all(get_test_las_path(1))?;
+        let merged = scans.merge(&scan_layout())?;
+        assert_eq!(20, merged.len());
+        for (index, point) in expected.iter().enumerate() {
+            assert_eq!(
+                point.position,
+                merged.get_attribute::<Vector3<f64>>(&attributes::POSITION_3D, index)
+            );
+            assert_eq!(
+                point.position + Vector3::new(100.0, 0.0, 0.0),
+                merged.get_attribute::<Vector3<f64>>(&attributes::POSITION_3D, index + 10)
+            );
+            assert_eq!(
+                point.intensity,
+                merged.get_attribute::<u16>(&attributes::INTENSITY, index + 10)
+            );
+            assert_eq!(0, merged.get_attribute::<u32>(&SCAN_INDEX, index));
+            assert_eq!(1, merged.get_attribute::<u32>(&SCAN_INDEX, index + 10));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_collection_read_scan_keeps_pose() -> Result<()> {
+        let points =
+            crate::base::read_all_into_buffer(get_test_las_path(1), LasPointFormat1::layout())?;
+        let pose = Translation3::new(0.0, 0.0, -10.0).to_homogeneous();
+        let mut scans = ScanCollection::default();
+        let index = scans.add_buffer("In memory", points.clone(), pose);
+
+        let layout = PointLayout::from_attributes(&[attributes::POSITION_3D]);
+        let scan_points = scans.read_scan(index, &layout)?;
+        assert_eq!(&pose, scan_points.transform());
+        assert_eq!(&layout, scan_points.buffer().point_layout());
+        assert_eq!(
+            points
+                .iter_attribute::<Vector3<f64>>(&attributes::POSITION_3D)
+                .collect::<Vec<_>>(),
+            scan_points
+                .buffer()
+                .iter_attribute::<Vector3<f64>>(&attributes::POSITION_3D)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            points.get_attribute::<Vector3<f64>>(&attributes::POSITION_3D, 3)
+                + Vector3::new(0.0, 0.0, -10.0),
+            scan_points.world_position(3)
+        );
+
+        let mut visited_scans = vec![];
+        scans.for_each_scan(&layout, |index, scan, points| {
+            visited_scans.push((index, scan.name().to_owned(), points.len()));
+            Ok(())
+        })?;
+        assert_eq!(vec![(0, "In memory".to_owned(), 10)], visited_scans);
+
+        assert!(scans.read_scan(1, &layout).is_err());
+        Ok(())
+    }
+}
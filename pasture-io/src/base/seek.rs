@@ -1,6 +1,23 @@
-use anyhow::Result;
+use pasture_core::math::AABB;
 use std::io::SeekFrom;
 
+use super::Result;
+
+/// A contiguous range of points in a file together with the bounding box of these points, as stored in the spatial
+/// index of a file (for example the hierarchy of a COPC file)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointBlock {
+    /// Index of the first point of this block within the file
+    pub first_point: usize,
+    /// Number of points in this block
+    pub point_count: usize,
+    /// Bounding box of the points in this block
+    pub bounds: AABB<f64>,
+    /// Approximate spacing between the points of this block, if the spatial index is a level-of-detail hierarchy (as
+    /// in COPC files), where the points of coarser levels have a larger spacing
+    pub spacing: Option<f64>,
+}
+
 /// Base trait for all readers and writers that support seeking to a specific point in their
 /// underlying stream. This trait is similar to [std::io::Seek](std::io::Seek) but instead
 /// of seeking to a specific byte offset, it allows seeking to a specific point.
@@ -22,4 +39,10 @@ pub trait SeekToPoint {
         self.seek_point(SeekFrom::Start(current_pos))?;
         Ok(len)
     }
+    /// Returns the blocks of points from the spatial index of the underlying stream, sorted by the index of their first
+    /// point, or `None` if the underlying stream has no spatial index. Used by
+    /// [PointReadAndSeek::seek_to_block](crate::base::PointReadAndSeek::seek_to_block)
+    fn point_blocks(&mut self) -> Result<Option<Vec<PointBlock>>> {
+        Ok(None)
+    }
 }
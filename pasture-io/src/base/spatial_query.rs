@@ -0,0 +1,208 @@
+use std::{cmp::Ordering, io::SeekFrom};
+
+use pasture_core::{
+    containers::{
+        InterleavedPointView, InterleavedVecPointStorage, PointBuffer, PointBufferExt,
+        PointBufferWriteable, SpatialQuery, SpatialQueryable,
+    },
+    layout::{attributes::POSITION_3D, PointLayout},
+    math::AABB,
+    nalgebra::Vector3,
+};
+
+use super::{PastureIoError, PointBlock, PointReadAndSeek, Result};
+
+/// Returns the distance from `position` to the closest point within `bounds`, which is zero if `bounds` contains
+/// `position`
+pub(crate) fn distance_to_bounds(position: &Vector3<f64>, bounds: &AABB<f64>) -> f64 {
+    let closest = position.sup(&bounds.min().coords).inf(&bounds.max().coords);
+    (closest - position).norm()
+}
+
+/// Reads all points of `block` in the `layout` of the output buffer
+fn read_block<R: PointReadAndSeek + ?Sized>(
+    reader: &mut R,
+    block: &PointBlock,
+    layout: &PointLayout,
+) -> Result<InterleavedVecPointStorage> {
+    let mut points = InterleavedVecPointStorage::with_capacity(block.point_count, layout.clone());
+    reader.seek_point(SeekFrom::Start(block.first_point as u64))?;
+    reader.read_into(&mut points, block.point_count)?;
+    Ok(points)
+}
+
+/// Runs `query` on a reader with a spatial index (see [point_blocks](super::SeekToPoint::point_blocks)) by reading
+/// only those blocks of points that can contain matching points. If `resolution` is set, blocks whose spacing is
+/// smaller than `resolution` are skipped. The matching points are appended to `points`, in the order in which they are
+/// stored in the file for region queries and by ascending distance for `Nearest` queries. The read position of
+/// `reader` is restored afterwards. Returns the number of points that were appended
+///
+/// This is the implementation of [SpatialQueryable] for all readers in pasture-io, and can be used to implement
+/// [SpatialQueryable] for custom readers with a spatial index
+///
+/// # Errors
+///
+/// If `reader` has no spatial index, a `PastureIoError::UnsupportedFormat` is returned. If `points` has no
+/// `POSITION_3D` attribute, or if an error occurs while reading, an error is returned as well
+pub fn query_point_blocks<R: PointReadAndSeek + ?Sized>(
+    reader: &mut R,
+    query: &SpatialQuery,
+    resolution: Option<f64>,
+    points: &mut dyn PointBufferWriteable,
+) -> Result<usize> {
+    let blocks = reader.point_blocks()?.ok_or_else(|| {
+        PastureIoError::UnsupportedFormat(
+            "Can't run a spatial query because the file has no spatial index".into(),
+        )
+    })?;
+    let layout = points.point_layout().clone();
+    if !layout.has_attribute_with_name(POSITION_3D.name()) {
+        return Err(PastureIoError::LayoutMismatch(
+            "Can't run a spatial query for points without a POSITION_3D attribute".into(),
+        ));
+    }
+    let blocks = blocks
+        .into_iter()
+        .filter(|block| match (resolution, block.spacing) {
+            (Some(resolution), Some(spacing)) => spacing >= resolution,
+            _ => true,
+        })
+        .collect::<Vec<_>>();
+
+    let previous_point = reader.point_index()?;
+    let matches = query_blocks(
+        blocks
+            .into_iter()
+            .map(|block| (block.bounds, block))
+            .collect(),
+        query,
+        &layout,
+        |block| read_block(reader, block, &layout),
+    )?;
+    reader.seek_point(SeekFrom::Start(previous_point as u64))?;
+
+    points.push(&InterleavedPointView::from_raw_slice(
+        &matches,
+        layout.clone(),
+    ));
+    Ok(matches.len() / layout.size_of_point_entry() as usize)
+}
+
+/// Runs `query` on points that are stored in blocks with the given bounds, e.g. the blocks of a COPC file or the nodes
+/// of an EPT dataset. Only the blocks that can contain matching points are read with `read_block`, which returns the
+/// points of a block in `layout`. Returns the raw memory of the matching points, in the order of `blocks` for region
+/// queries and by ascending distance for `Nearest` queries
+pub(crate) fn query_blocks<B>(
+    blocks: Vec<(AABB<f64>, B)>,
+    query: &SpatialQuery,
+    layout: &PointLayout,
+    mut read_block: impl FnMut(&B) -> Result<InterleavedVecPointStorage>,
+) -> Result<Vec<u8>> {
+    let point_size = layout.size_of_point_entry() as usize;
+    // Raw memory of the matching points
+    let mut matches = vec![];
+    match query {
+        SpatialQuery::Nearest { position, k } => {
+            let mut blocks = blocks
+                .into_iter()
+                .map(|(bounds, block)| (distance_to_bounds(position, &bounds), block))
+                .collect::<Vec<_>>();
+            blocks.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            // (distance, raw memory) of the k closest points so far
+            let mut candidates: Vec<(f64, Vec<u8>)> = vec![];
+            for (block_distance, block) in blocks {
+                // Once there are k candidates, blocks that are farther away than the farthest candidate can be skipped
+                if candidates.len() == *k && (*k == 0 || block_distance > candidates[*k - 1].0) {
+                    break;
+                }
+                let block_points = read_block(&block)?;
+                for (index, point_position) in block_points
+                    .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+                    .enumerate()
+                {
+                    let mut point = vec![0; point_size];
+                    block_points.get_raw_point(index, &mut point);
+                    candidates.push(((point_position - position).norm(), point));
+                }
+                candidates.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                candidates.truncate(*k);
+            }
+            for (_, point) in candidates {
+                matches.extend_from_slice(&point);
+            }
+        }
+        _ => {
+            if let Some(search_bounds) = query.search_bounds() {
+                for (_, block) in blocks
+                    .iter()
+                    .filter(|(bounds, _)| bounds.intersects(&search_bounds))
+                {
+                    let block_points = read_block(block)?;
+                    for (index, point_position) in block_points
+                        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+                        .enumerate()
+                    {
+                        if query.contains(&point_position) {
+                            let start = matches.len();
+                            matches.resize(start + point_size, 0);
+                            block_points.get_raw_point(index, &mut matches[start..]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(matches)
+}
+
+impl SpatialQueryable for dyn PointReadAndSeek {
+    fn point_layout(&self) -> &PointLayout {
+        self.get_default_point_layout()
+    }
+
+    fn query(
+        &mut self,
+        query: &SpatialQuery,
+        resolution: Option<f64>,
+        points: &mut dyn PointBufferWriteable,
+    ) -> anyhow::Result<usize> {
+        Ok(query_point_blocks(self, query, resolution, points)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::las::{get_test_las_path, LASReader};
+    use pasture_core::nalgebra::Point3;
+
+    #[test]
+    fn test_distance_to_bounds() {
+        let bounds = AABB::from_min_max(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 2.0, 3.0));
+        assert_eq!(
+            0.0,
+            distance_to_bounds(&Vector3::new(0.5, 1.0, 1.5), &bounds)
+        );
+        assert_eq!(
+            2.0,
+            distance_to_bounds(&Vector3::new(0.5, 4.0, 1.5), &bounds)
+        );
+        assert_eq!(
+            5.0,
+            distance_to_bounds(&Vector3::new(-3.0, -4.0, 1.0), &bounds)
+        );
+    }
+
+    #[test]
+    fn test_query_without_spatial_index() -> Result<()> {
+        let mut reader: Box<dyn PointReadAndSeek> =
+            Box::new(LASReader::from_path(get_test_las_path(0))?);
+        let mut points = InterleavedVecPointStorage::new(reader.point_layout().clone());
+        let query = SpatialQuery::Nearest {
+            position: Vector3::zeros(),
+            k: 1,
+        };
+        assert!(reader.query(&query, None, &mut points).is_err());
+        Ok(())
+    }
+}
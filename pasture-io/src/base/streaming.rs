@@ -0,0 +1,525 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{sync_channel, Receiver, SyncSender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use pasture_core::containers::{InterleavedVecPointStorage, PointBuffer};
+
+use super::{
+    make_writer, IOFactory, PastureIoError, PointReader, PointWriter, Result, WriteOptions,
+};
+
+/// Options for streaming points from a reader through a converter into a writer with [stream_points]
+#[derive(Debug, Clone)]
+pub struct StreamingOptions {
+    chunk_size: usize,
+    chunks_in_flight: usize,
+}
+
+impl StreamingOptions {
+    /// Creates new `StreamingOptions` that read chunks of 50000 points, with at most 4 chunks in flight
+    pub fn new() -> Self {
+        Self {
+            chunk_size: 50_000,
+            chunks_in_flight: 4,
+        }
+    }
+
+    /// Sets the maximum number of points per chunk
+    ///
+    /// # Panics
+    ///
+    /// If `chunk_size` is zero
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        if chunk_size == 0 {
+            panic!("StreamingOptions::with_chunk_size: chunk_size must be > 0");
+        }
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Sets the maximum number of chunks that exist at the same time, across all stages
+    ///
+    /// # Panics
+    ///
+    /// If `chunks_in_flight` is zero
+    pub fn with_chunks_in_flight(mut self, chunks_in_flight: usize) -> Self {
+        if chunks_in_flight == 0 {
+            panic!("StreamingOptions::with_chunks_in_flight: chunks_in_flight must be > 0");
+        }
+        self.chunks_in_flight = chunks_in_flight;
+        self
+    }
+
+    /// Returns the maximum number of points per chunk
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Returns the maximum number of chunks that exist at the same time
+    pub fn chunks_in_flight(&self) -> usize {
+        self.chunks_in_flight
+    }
+}
+
+impl Default for StreamingOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Statistics of a completed [stream_points] run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StreamingSummary {
+    /// Number of points that were read
+    pub points_read: usize,
+    /// Number of points that were written, which differs from `points_read` if the converter removes or adds points
+    pub points_written: usize,
+    /// Number of chunks that passed through the stream
+    pub chunks: usize,
+    /// Largest number of chunks that existed at the same time. Never exceeds
+    /// [StreamingOptions::chunks_in_flight]
+    pub max_chunks_in_flight: usize,
+}
+
+/// Counts the chunks that currently exist in a stream
+#[derive(Default)]
+struct InFlightCounter {
+    current: AtomicUsize,
+    max: AtomicUsize,
+}
+
+impl InFlightCounter {
+    fn acquire(&self) {
+        let current = self.current.fetch_add(1, Ordering::SeqCst) + 1;
+        self.max.fetch_max(current, Ordering::SeqCst);
+    }
+
+    fn release(&self) {
+        self.current.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Body of the reader thread of [stream_points]. Each chunk needs a permit, which the writer returns once the chunk is
+/// written, so reading blocks while `chunks_in_flight` chunks exist. Returns the number of read points
+fn read_stage<R: PointReader + ?Sized>(
+    mut reader: Box<R>,
+    chunk_size: usize,
+    permits: Receiver<()>,
+    chunks: SyncSender<InterleavedVecPointStorage>,
+    counter: Arc<InFlightCounter>,
+) -> Result<usize> {
+    let layout = reader.get_default_point_layout().clone();
+    let mut points_read = 0;
+    // The permits are only gone if the writer has stopped, in which case there is nobody to read for anymore
+    while permits.recv().is_ok() {
+        counter.acquire();
+        let mut chunk = InterleavedVecPointStorage::with_capacity(chunk_size, layout.clone());
+        let count = reader.read_into(&mut chunk, chunk_size)?;
+        if count == 0 {
+            counter.release();
+            break;
+        }
+        points_read += count;
+        if chunks.send(chunk).is_err() {
+            break;
+        }
+    }
+    Ok(points_read)
+}
+
+/// Body of the converter thread of [stream_points]. The converted chunk replaces the original chunk and keeps its
+/// permit
+fn convert_stage<C>(
+    mut convert: C,
+    chunks: Receiver<InterleavedVecPointStorage>,
+    converted_chunks: SyncSender<InterleavedVecPointStorage>,
+) -> Result<()>
+where
+    C: FnMut(InterleavedVecPointStorage) -> Result<InterleavedVecPointStorage>,
+{
+    for chunk in chunks {
+        if converted_chunks.send(convert(chunk)?).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Writes the converted chunks on the calling thread of [stream_points] and returns the permit of each written chunk.
+/// Returns the writer, or `None` if no chunk was received
+fn write_stage<M, W>(
+    make_writer: M,
+    converted_chunks: &Receiver<InterleavedVecPointStorage>,
+    permits: &SyncSender<()>,
+    counter: &InFlightCounter,
+    summary: &mut StreamingSummary,
+) -> Result<Option<Box<W>>>
+where
+    M: FnOnce(&InterleavedVecPointStorage) -> Result<Box<W>>,
+    W: PointWriter + ?Sized,
+{
+    let first_chunk = match converted_chunks.recv() {
+        Ok(chunk) => chunk,
+        Err(_) => return Ok(None),
+    };
+    let mut writer = make_writer(&first_chunk)?;
+    for chunk in std::iter::once(first_chunk).chain(converted_chunks.iter()) {
+        if !chunk.is_empty() {
+            writer.write(&chunk)?;
+        }
+        summary.points_written += chunk.len();
+        summary.chunks += 1;
+        drop(chunk);
+        counter.release();
+        // The reader might already be done, in which case nobody needs the permit anymore
+        let _ = permits.send(());
+    }
+    Ok(Some(writer))
+}
+
+fn join_stage<T>(stage: JoinHandle<Result<T>>, name: &str) -> Result<T> {
+    stage.join().map_err(|_| {
+        PastureIoError::Other(format!("Streaming {} thread has panicked", name).into())
+    })?
+}
+
+/// Streams all points from a reader through `convert` into a writer, with bounded memory usage regardless of the number
+/// of points. The reader, the converter and the writer run concurrently as three stages, which pass chunks of at most
+/// `options.chunk_size()` points to each other through bounded queues. At most `options.chunks_in_flight()` chunks
+/// exist at any time: once this many chunks are read but not yet written, the reader blocks until the writer has
+/// written a chunk (backpressure). The memory for the point data is thus bounded by `chunks_in_flight * chunk_size`
+/// points, plus the output of `convert` while it still holds its input chunk
+///
+/// The reader is created on its own thread by `open_reader`, so that readers which can't be sent between threads (such
+/// as [LASReader](crate::las::LASReader)) can be streamed. `convert` runs on a second thread and can transform, filter
+/// or add points, as long as all its output chunks have the same `PointLayout`. The writer runs on the calling thread
+/// and receives the chunks in the order in which they were read. `make_writer` is called with the first converted
+/// chunk, so that the writer can be created for the `PointLayout` of the converted points. No writer is created if the
+/// reader has no points
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::LASReader;
+/// # fn main() -> Result<()> {
+/// let summary = stream_points(
+///     || Ok(Box::new(LASReader::from_path("in.las")?)),
+///     Ok,
+///     |_| IOFactory::default().make_writer("out.las".as_ref()),
+///     &StreamingOptions::default().with_chunks_in_flight(2),
+/// )?;
+/// println!("Copied {} points", summary.points_written);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If `open_reader`, reading, `convert`, `make_writer` or writing fails, the stream stops and the first error in stage
+/// order (reader, converter, writer) is returned
+pub fn stream_points<O, R, C, M, W>(
+    open_reader: O,
+    convert: C,
+    make_writer: M,
+    options: &StreamingOptions,
+) -> Result<StreamingSummary>
+where
+    O: FnOnce() -> Result<Box<R>> + Send + 'static,
+    R: PointReader + ?Sized,
+    C: FnMut(InterleavedVecPointStorage) -> Result<InterleavedVecPointStorage> + Send + 'static,
+    M: FnOnce(&InterleavedVecPointStorage) -> Result<Box<W>>,
+    W: PointWriter + ?Sized,
+{
+    let chunks_in_flight = options.chunks_in_flight;
+    let chunk_size = options.chunk_size;
+    let counter = Arc::new(InFlightCounter::default());
+
+    let (permit_sender, permit_receiver) = sync_channel(chunks_in_flight);
+    for _ in 0..chunks_in_flight {
+        permit_sender
+            .send(())
+            .expect("Permit receiver can't be gone before the reader thread starts");
+    }
+    let (chunk_sender, chunk_receiver) = sync_channel(chunks_in_flight);
+    let (converted_sender, converted_receiver) = sync_channel(chunks_in_flight);
+
+    let reader_counter = counter.clone();
+    let reader_stage = thread::Builder::new()
+        .name("pasture-stream-reader".into())
+        .spawn(move || {
+            let reader = open_reader()?;
+            read_stage(
+                reader,
+                chunk_size,
+                permit_receiver,
+                chunk_sender,
+                reader_counter,
+            )
+        })
+        .map_err(PastureIoError::Io)?;
+    let converter_stage = thread::Builder::new()
+        .name("pasture-stream-converter".into())
+        .spawn(move || convert_stage(convert, chunk_receiver, converted_sender))
+        .map_err(PastureIoError::Io)?;
+
+    let mut summary = StreamingSummary::default();
+    let write_result = write_stage(
+        make_writer,
+        &converted_receiver,
+        &permit_sender,
+        &counter,
+        &mut summary,
+    );
+
+    // Dropping the permits and the queue stops the other stages if the writer has failed
+    drop(permit_sender);
+    drop(converted_receiver);
+    let read_result = join_stage(reader_stage, "reader");
+    let convert_result = join_stage(converter_stage, "converter");
+    summary.points_read = read_result?;
+    convert_result?;
+    // The writer is flushed once it is dropped, explicitly calling `flush` is not supported for LAZ files
+    drop(write_result?);
+    summary.max_chunks_in_flight = counter.max.load(Ordering::SeqCst);
+    Ok(summary)
+}
+
+/// Streams all points from the file at `input` through `convert` into the file at `output`, using [stream_points]. The
+/// formats of the files are determined from their extensions, as in [IOFactory]. For LAS and LAZ output files, the
+/// point format is derived from the `PointLayout` of the converted points, as in [write_all](super::write_all)
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_io::base::*;
+/// # fn main() -> Result<()> {
+/// stream_file("in.laz", "out.las", Ok, &StreamingOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If one of the formats is not supported, or if an error occurs while streaming, an error is returned
+pub fn stream_file<P, Q, C>(
+    input: P,
+    output: Q,
+    convert: C,
+    options: &StreamingOptions,
+) -> Result<StreamingSummary>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+    C: FnMut(InterleavedVecPointStorage) -> Result<InterleavedVecPointStorage> + Send + 'static,
+{
+    let input: PathBuf = input.as_ref().to_owned();
+    let output = output.as_ref();
+    stream_points(
+        move || IOFactory::default().make_reader(&input),
+        convert,
+        |chunk| make_writer(output, chunk.point_layout(), &WriteOptions::default()),
+        options,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use pasture_core::{
+        containers::{PointBufferExt, PointBufferWriteable, PointBufferWriteableExt},
+        layout::{attributes::POSITION_3D, PointLayout},
+        nalgebra::Vector3,
+        util::Progress,
+    };
+    use scopeguard::defer;
+
+    use super::*;
+    use crate::{
+        base::{read_all, ProgressReader},
+        las::{get_test_las_path, LASReader, LasPointFormat0},
+    };
+
+    /// Writer that stores the size of each written chunk and writes slowly, so that the reader runs ahead
+    struct SlowWriter {
+        layout: PointLayout,
+        chunk_sizes: Arc<Mutex<Vec<usize>>>,
+    }
+
+    impl PointWriter for SlowWriter {
+        fn write(&mut self, points: &dyn PointBuffer) -> Result<()> {
+            thread::sleep(Duration::from_millis(5));
+            self.chunk_sizes.lock().unwrap().push(points.len());
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn get_default_point_layout(&self) -> &PointLayout {
+            &self.layout
+        }
+    }
+
+    #[test]
+    fn test_stream_points_bounds_chunks_in_flight() -> Result<()> {
+        let chunk_sizes = Arc::new(Mutex::new(vec![]));
+        let writer_chunk_sizes = chunk_sizes.clone();
+        let summary = stream_points(
+            || Ok(Box::new(LASReader::from_path(get_test_las_path(0))?)),
+            |chunk| {
+                let mut positions = InterleavedVecPointStorage::with_capacity(
+                    chunk.len(),
+                    PointLayout::from_attributes(&[POSITION_3D]),
+                );
+                positions.resize(chunk.len());
+                for (index, position) in chunk
+                    .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                    .enumerate()
+                {
+                    positions.set_attribute(&POSITION_3D, index, position);
+                }
+                Ok(positions)
+            },
+            |chunk| {
+                assert_eq!(
+                    &PointLayout::from_attributes(&[POSITION_3D]),
+                    chunk.point_layout()
+                );
+                Ok(Box::new(SlowWriter {
+                    layout: chunk.point_layout().clone(),
+                    chunk_sizes: writer_chunk_sizes,
+                }))
+            },
+            &StreamingOptions::default()
+                .with_chunk_size(3)
+                .with_chunks_in_flight(2),
+        )?;
+
+        assert_eq!(10, summary.points_read);
+        assert_eq!(10, summary.points_written);
+        assert_eq!(4, summary.chunks);
+        assert!(summary.max_chunks_in_flight <= 2);
+        assert_eq!(vec![3, 3, 3, 1], *chunk_sizes.lock().unwrap());
+        Ok(())
+    }
+
+    /// Writer that records how many points the reader has read while the first chunk is being written
+    struct BlockingWriter {
+        layout: PointLayout,
+        points_read: Arc<AtomicUsize>,
+        points_read_during_first_write: Arc<Mutex<Option<usize>>>,
+    }
+
+    impl PointWriter for BlockingWriter {
+        fn write(&mut self, _points: &dyn PointBuffer) -> Result<()> {
+            let mut points_read_during_first_write =
+                self.points_read_during_first_write.lock().unwrap();
+            if points_read_during_first_write.is_none() {
+                // Gives the reader enough time to run ahead as far as the backpressure allows
+                thread::sleep(Duration::from_millis(50));
+                *points_read_during_first_write = Some(self.points_read.load(Ordering::SeqCst));
+            }
+            Ok(())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn get_default_point_layout(&self) -> &PointLayout {
+            &self.layout
+        }
+    }
+
+    #[test]
+    fn test_stream_points_blocks_reader_while_writer_is_busy() -> Result<()> {
+        let points_read = Arc::new(AtomicUsize::new(0));
+        let reader_points_read = points_read.clone();
+        let writer_points_read = points_read.clone();
+        let points_read_during_first_write = Arc::new(Mutex::new(None));
+        let writer_points_read_during_first_write = points_read_during_first_write.clone();
+
+        let summary = stream_points(
+            move || {
+                let progress =
+                    Progress::default().with_callback(move |read: usize, _total: Option<usize>| {
+                        reader_points_read.store(read, Ordering::SeqCst)
+                    });
+                let reader = LASReader::from_path(get_test_las_path(0))?;
+                Ok(Box::new(ProgressReader::new(reader, progress)))
+            },
+            Ok,
+            |chunk| {
+                Ok(Box::new(BlockingWriter {
+                    layout: chunk.point_layout().clone(),
+                    points_read: writer_points_read,
+                    points_read_during_first_write: writer_points_read_during_first_write,
+                }))
+            },
+            &StreamingOptions::default()
+                .with_chunk_size(1)
+                .with_chunks_in_flight(2),
+        )?;
+
+        assert_eq!(10, summary.points_written);
+        assert_eq!(10, points_read.load(Ordering::SeqCst));
+        // With chunks of one point, the reader can't be more than `chunks_in_flight` points ahead of the writer
+        assert!(points_read_during_first_write.lock().unwrap().unwrap() <= 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stream_points_stops_on_error() {
+        let result = stream_points(
+            || Ok(Box::new(LASReader::from_path(get_test_las_path(0))?)),
+            |_| Err(PastureIoError::Other("Conversion failed".into())),
+            |_| -> Result<Box<SlowWriter>> { panic!("No chunk should reach the writer") },
+            &StreamingOptions::default().with_chunk_size(1),
+        );
+        assert!(result.is_err());
+
+        let result = stream_points(
+            || Ok(Box::new(LASReader::from_path("does_not_exist.las")?)),
+            Ok,
+            |_| -> Result<Box<SlowWriter>> { panic!("No chunk should reach the writer") },
+            &StreamingOptions::default(),
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_stream_file() -> Result<()> {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_stream_file.las");
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        let summary = stream_file(
+            get_test_las_path(0),
+            &test_file_path,
+            Ok,
+            &StreamingOptions::default()
+                .with_chunk_size(4)
+                .with_chunks_in_flight(1),
+        )?;
+        assert_eq!(10, summary.points_written);
+        assert_eq!(1, summary.max_chunks_in_flight);
+
+        let expected: Vec<LasPointFormat0> = read_all(get_test_las_path(0))?;
+        let actual: Vec<LasPointFormat0> = read_all(&test_file_path)?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+use std::path::Path;
+
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBuffer, PointBufferWriteable},
+    layout::{PointLayout, PointType},
+};
+
+use super::{IOFactory, PointWriter, Result};
+use crate::{
+    las::{las_point_format_from_point_layout, LASWriter},
+    las_rs::{point::Format, Builder},
+};
+
+/// Options for writing points with [write_all]
+#[derive(Debug, Clone)]
+pub struct WriteOptions {
+    chunk_size: usize,
+    las_point_format: Option<Format>,
+}
+
+impl WriteOptions {
+    /// Creates new `WriteOptions` that write chunks of 50000 points. For LAS and LAZ files, the point format that
+    /// preserves the most attributes of the written points is used
+    pub fn new() -> Self {
+        Self {
+            chunk_size: 50_000,
+            las_point_format: None,
+        }
+    }
+
+    /// Sets the number of points that are buffered before they are passed to the writer
+    ///
+    /// # Panics
+    ///
+    /// If `chunk_size` is zero
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        if chunk_size == 0 {
+            panic!("WriteOptions::with_chunk_size: chunk_size must be > 0");
+        }
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Uses the given point format for LAS and LAZ files instead of the format that is derived from the written points
+    pub fn with_las_point_format(mut self, format: Format) -> Self {
+        self.las_point_format = Some(format);
+        self
+    }
+
+    /// Returns the number of points that are buffered before they are passed to the writer
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Creates a writer for the file at `path`. LAS and LAZ files get the point format from `options` or the point format
+/// that preserves the most attributes of `layout`, all other formats are created by the default `IOFactory`
+pub(crate) fn make_writer(
+    path: &Path,
+    layout: &PointLayout,
+    options: &WriteOptions,
+) -> Result<Box<dyn PointWriter>> {
+    let is_las = path
+        .extension()
+        .and_then(|ex| ex.to_str())
+        .map(|ex| ex.eq_ignore_ascii_case("las") || ex.eq_ignore_ascii_case("laz"))
+        .unwrap_or(false);
+    if !is_las {
+        let factory: IOFactory = Default::default();
+        return factory.make_writer(path);
+    }
+
+    let mut header_builder = Builder::from((1, 4));
+    header_builder.point_format = options
+        .las_point_format
+        .unwrap_or_else(|| las_point_format_from_point_layout(layout));
+    let header = header_builder.into_header()?;
+    Ok(Box::new(LASWriter::from_path_and_header(path, header)?))
+}
+
+/// Writes all points of the iterator `points` to the file at `path` and returns the number of written points. The
+/// format of the file is determined from its extension, as in [IOFactory::make_writer], and the points are passed to
+/// the writer in chunks, so the iterator does not have to fit into memory. For LAS and LAZ files, the point format is
+/// derived from the `PointLayout` of `T`, unless `options` specify a point format
+///
+/// ```no_run
+/// # use anyhow::Result;
+/// # use pasture_io::base::WriteOptions;
+/// # use pasture_io::las::LasPointFormat1;
+/// # fn main() -> Result<()> {
+/// let points: Vec<LasPointFormat1> = pasture_io::read_all("input.las")?;
+/// let ground_points = points.into_iter().filter(|point| point.classification == 2);
+/// pasture_io::write_all("ground.las", ground_points, &WriteOptions::default())?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If the format of the file is not supported, or if an I/O error occurs while writing, an error is returned
+pub fn write_all<T: PointType, P: AsRef<Path>, I: IntoIterator<Item = T>>(
+    path: P,
+    points: I,
+    options: &WriteOptions,
+) -> Result<usize> {
+    let layout = T::layout();
+    // The writer is flushed once it is dropped, explicitly calling `flush` is not supported for LAZ files
+    let mut writer = make_writer(path.as_ref(), &layout, options)?;
+    let mut chunk = InterleavedVecPointStorage::with_capacity(options.chunk_size, layout);
+    let mut written_points = 0;
+    for point in points {
+        chunk.push_point(point);
+        if chunk.len() == options.chunk_size {
+            writer.write(&chunk)?;
+            written_points += chunk.len();
+            chunk.clear();
+        }
+    }
+    if !chunk.is_empty() {
+        writer.write(&chunk)?;
+        written_points += chunk.len();
+    }
+    Ok(written_points)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use scopeguard::defer;
+
+    use super::*;
+    use crate::{
+        base::read_all,
+        las::{get_test_las_path, LASReader, LasPointFormat0, LasPointFormat1},
+    };
+
+    #[test]
+    fn test_write_all_las() -> Result<()> {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_write_all_las.las");
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        let expected: Vec<LasPointFormat1> = read_all(get_test_las_path(1))?;
+        let written = write_all(
+            &test_file_path,
+            expected.iter().copied(),
+            &WriteOptions::default().with_chunk_size(3),
+        )?;
+        assert_eq!(expected.len(), written);
+
+        let reader = LASReader::from_path(&test_file_path)?;
+        assert_eq!(Format::new(1)?, *reader.header().point_format());
+        drop(reader);
+
+        let actual: Vec<LasPointFormat1> = read_all(&test_file_path)?;
+        assert_eq!(expected, actual);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_all_with_las_point_format() -> Result<()> {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_write_all_with_las_point_format.las");
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        let points: Vec<LasPointFormat0> = read_all(get_test_las_path(0))?;
+        write_all(
+            &test_file_path,
+            points,
+            &WriteOptions::default().with_las_point_format(Format::new(1)?),
+        )?;
+
+        let reader = LASReader::from_path(&test_file_path)?;
+        assert_eq!(Format::new(1)?, *reader.header().point_format());
+        assert_eq!(10, reader.header().number_of_points());
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_all_unsupported_format() {
+        assert!(write_all(
+            "points.unknown",
+            Vec::<LasPointFormat1>::new(),
+            &WriteOptions::default()
+        )
+        .is_err());
+    }
+}
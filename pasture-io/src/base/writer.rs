@@ -1,6 +1,7 @@
-use anyhow::Result;
 use pasture_core::{containers::PointBuffer, layout::PointLayout};
 
+use super::{Capabilities, Result};
+
 /// Base trait for all types that support writing point data
 pub trait PointWriter {
     /// Write the points in the given `PointBuffer` to the associated `PointWriter`.
@@ -11,4 +12,9 @@ pub trait PointWriter {
 
     /// Returns the default `PointLayout` of the associated `PointWriter`
     fn get_default_point_layout(&self) -> &PointLayout;
+    /// Returns the `Capabilities` of the associated `PointWriter`. The default implementation supports the attributes of
+    /// the default `PointLayout` and none of the optional capabilities
+    fn capabilities(&self) -> Capabilities {
+        Capabilities::new(self.get_default_point_layout())
+    }
 }
@@ -0,0 +1,341 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use pasture_core::{
+    containers::{InterleavedPointView, InterleavedVecPointStorage, PointBuffer, PointBufferExt},
+    layout::{attributes::POSITION_3D, PointLayout},
+    math::AABB,
+    nalgebra::Vector3,
+};
+use serde_json::{json, Value};
+
+use super::{
+    ept_hierarchy::{
+        ensure_laszip_data, ept_bounds, read_json, write_json, EPT_DATA_DIR, EPT_METADATA_FILE,
+    },
+    ept_node_name, read_ept_hierarchy, write_ept_hierarchy,
+};
+use crate::{
+    base::{
+        cell_index, octree_node_bounds, OctreeHierarchy, OctreeNodeKey, PastureIoError,
+        PointReader, PointWriter, Result,
+    },
+    las::{LASReader, LASWriter},
+};
+
+/// Points that are inserted into a node of the target dataset while appending
+#[derive(Default)]
+struct PendingNode {
+    /// Data files of the appended dataset whose points are inserted into this node
+    files: Vec<PathBuf>,
+    /// Raw memory of the points that didn't fit into the sampling grid of the parent node
+    points: Vec<u8>,
+}
+
+/// A node of the target dataset whose points are re-partitioned
+struct NodeInsertion {
+    key: OctreeNodeKey,
+    bounds: AABB<f64>,
+    span: u64,
+    keeps_all_points: bool,
+    occupied_cells: HashSet<(u64, u64, u64)>,
+    points: Vec<u8>,
+}
+
+impl NodeInsertion {
+    fn new(key: OctreeNodeKey, cube: &AABB<f64>, span: u64, max_depth: u32) -> Self {
+        Self {
+            key,
+            bounds: octree_node_bounds(&key, cube),
+            span,
+            keeps_all_points: key.0 >= max_depth,
+            occupied_cells: HashSet::new(),
+            points: vec![],
+        }
+    }
+
+    /// Inserts `points` into this node. A point is stored in this node if its cell of the sampling grid is still empty
+    /// (or if `keep_all` is set, e.g. for the existing points of the node), otherwise it is passed on to the pending
+    /// points of the child node that contains it, like in `StreamingOctreeBuilder`
+    fn insert(
+        &mut self,
+        points: &dyn PointBuffer,
+        keep_all: bool,
+        pending: &mut BTreeMap<OctreeNodeKey, PendingNode>,
+    ) {
+        let mut point = vec![0; points.point_layout().size_of_point_entry() as usize];
+        let center = self.bounds.center();
+        for (index, position) in points
+            .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+            .enumerate()
+        {
+            points.get_raw_point(index, &mut point);
+            let cell = cell_index(&position, &self.bounds, self.span);
+            if self.occupied_cells.insert(cell) || keep_all || self.keeps_all_points {
+                self.points.extend_from_slice(&point);
+                continue;
+            }
+            let (depth, x, y, z) = self.key;
+            let child = (
+                depth + 1,
+                2 * x + (position.x >= center.x) as u64,
+                2 * y + (position.y >= center.y) as u64,
+                2 * z + (position.z >= center.z) as u64,
+            );
+            pending
+                .entry(child)
+                .or_default()
+                .points
+                .extend_from_slice(&point);
+        }
+    }
+}
+
+fn read_node_file(path: &Path, layout: &PointLayout) -> Result<InterleavedVecPointStorage> {
+    let mut reader = LASReader::from_path(path)?;
+    let point_count = reader.remaining_points();
+    let mut points = InterleavedVecPointStorage::with_capacity(point_count, layout.clone());
+    reader.read_into(&mut points, point_count)?;
+    Ok(points)
+}
+
+/// Appends the points of the EPT dataset in `other_dir` to the EPT dataset in `ept_dir`, e.g. to add the scans of a new
+/// acquisition campaign to an existing index, and returns the new hierarchy of the dataset in `ept_dir`
+///
+/// Instead of indexing all points again, only the nodes that receive points are re-partitioned: The points of a node of
+/// `other_dir` are inserted into the sampling grid of the node of `ept_dir` at the same position, and points whose
+/// grid cell is already occupied are passed on to the child nodes, which are re-partitioned the same way. Nodes that
+/// only exist in one of the datasets are kept as they are (or copied, if the datasets have the same schema), so the
+/// effort depends on the overlap of the datasets. This requires that both datasets have the same cubic `bounds` and
+/// `span`, e.g. because the second one was indexed with the bounds of the first one. Otherwise, all points of
+/// `other_dir` are inserted starting at the root node, which keeps the points that are passed on to the next level of
+/// the octree in memory
+///
+/// The points of `other_dir` must be within the bounds of `ept_dir`, since the octree can't grow without indexing all
+/// points again. The dataset in `ept_dir` is inconsistent while this function runs, so it should not be served at the
+/// same time. To merge two datasets into a new one, copy the first one and append the second one to the copy
+///
+/// # Errors
+///
+/// If the data type of one of the datasets is not `laszip`, if the datasets have different spatial reference systems,
+/// if the points of `other_dir` are outside of the bounds of `ept_dir`, or if an I/O error occurs while reading or
+/// writing the points or the hierarchy, an error is returned
+pub fn append_ept<P: AsRef<Path>, Q: AsRef<Path>>(
+    ept_dir: P,
+    other_dir: Q,
+) -> Result<OctreeHierarchy> {
+    let ept_dir = ept_dir.as_ref();
+    let other_dir = other_dir.as_ref();
+    let metadata_path = ept_dir.join(EPT_METADATA_FILE);
+    let mut metadata = read_json(&metadata_path)?;
+    let other_metadata = read_json(&other_dir.join(EPT_METADATA_FILE))?;
+    ensure_laszip_data(&metadata, "Appending to")?;
+    ensure_laszip_data(&other_metadata, "Appending")?;
+    if metadata.get("srs") != other_metadata.get("srs") {
+        return Err(PastureIoError::InvalidArgument(format!(
+            "EPT datasets {} and {} have different spatial reference systems",
+            ept_dir.display(),
+            other_dir.display()
+        )));
+    }
+    let span = metadata
+        .get("span")
+        .and_then(Value::as_u64)
+        .filter(|span| *span > 0)
+        .ok_or_else(|| PastureIoError::InvalidData("EPT metadata has no valid 'span'".into()))?;
+
+    let hierarchy = read_ept_hierarchy(ept_dir)?;
+    let other_hierarchy = read_ept_hierarchy(other_dir)?;
+    let cube = hierarchy.bounds();
+    let other_bounds = ept_bounds(&other_metadata, "boundsConforming")
+        .or_else(|_| ept_bounds(&other_metadata, "bounds"))?;
+    if !cube.contains(&other_bounds.min()) || !cube.contains(&other_bounds.max()) {
+        return Err(PastureIoError::InvalidArgument(format!(
+            "The points of EPT dataset {} are outside of the bounds of EPT dataset {}",
+            other_dir.display(),
+            ept_dir.display()
+        )));
+    }
+    // Nodes with the same key cover the same space only if the octrees have the same bounds and sampling grids, and
+    // their data files can only be copied if the points have the same schema
+    let is_aligned = other_hierarchy.bounds() == cube
+        && other_metadata.get("span").and_then(Value::as_u64) == Some(span);
+    let can_copy_nodes = is_aligned && other_metadata.get("schema") == metadata.get("schema");
+    let max_depth = hierarchy
+        .depth()
+        .into_iter()
+        .chain(other_hierarchy.depth())
+        .max()
+        .unwrap_or(0);
+
+    let data_dir = ept_dir.join(EPT_DATA_DIR);
+    let data_path = |key: &OctreeNodeKey| data_dir.join(format!("{}.laz", ept_node_name(key)));
+    // The root node of the target dataset determines the point format and the header of all nodes that are written
+    let (header, layout) = {
+        let reader = LASReader::from_path(data_path(&(0, 0, 0, 0)))?;
+        (
+            reader.header().clone(),
+            reader.get_default_point_layout().clone(),
+        )
+    };
+
+    let mut pending: BTreeMap<OctreeNodeKey, PendingNode> = BTreeMap::new();
+    for (key, _) in other_hierarchy.nodes() {
+        let path = other_dir
+            .join(EPT_DATA_DIR)
+            .join(format!("{}.laz", ept_node_name(&key)));
+        // Nodes without points may have no data file
+        if path.exists() {
+            let target = if is_aligned { key } else { (0, 0, 0, 0) };
+            pending.entry(target).or_default().files.push(path);
+        }
+    }
+
+    let mut nodes = hierarchy.nodes().collect::<BTreeMap<_, _>>();
+    // Keys are ordered by depth first, so all parents of a node are processed before the node itself
+    while let Some(key) = pending.keys().next().copied() {
+        let node = pending.remove(&key).unwrap();
+        let target_path = data_path(&key);
+        let is_new_node = !nodes.contains_key(&key) || !target_path.exists();
+        if can_copy_nodes && is_new_node && node.points.is_empty() && node.files.len() == 1 {
+            fs::copy(&node.files[0], &target_path).map_err(PastureIoError::Io)?;
+            nodes.insert(key, other_hierarchy.node_point_count(&key).unwrap_or(0));
+            continue;
+        }
+
+        let mut insertion = NodeInsertion::new(key, &cube, span, max_depth);
+        if !is_new_node {
+            insertion.insert(&read_node_file(&target_path, &layout)?, true, &mut pending);
+        }
+        for path in node.files.iter() {
+            insertion.insert(&read_node_file(path, &layout)?, false, &mut pending);
+        }
+        insertion.insert(
+            &InterleavedPointView::from_raw_slice(&node.points, layout.clone()),
+            false,
+            &mut pending,
+        );
+
+        let point_count = insertion.points.len() / layout.size_of_point_entry() as usize;
+        let mut points = InterleavedVecPointStorage::with_capacity(point_count, layout.clone());
+        points.push(&InterleavedPointView::from_raw_slice(
+            &insertion.points,
+            layout.clone(),
+        ));
+        // Write to a temporary file first, so that a failed write doesn't destroy the existing points of the node
+        let temp_path = data_dir.join(format!("{}.appended.laz", ept_node_name(&key)));
+        {
+            let mut writer = LASWriter::from_path_and_header(&temp_path, header.clone())?;
+            writer.write(&points)?;
+            writer.flush()?;
+        }
+        fs::rename(&temp_path, &target_path).map_err(PastureIoError::Io)?;
+        nodes.insert(key, point_count);
+    }
+
+    if let Ok(bounds) = ept_bounds(&metadata, "boundsConforming") {
+        let bounds = AABB::union(&bounds, &other_bounds);
+        metadata["boundsConforming"] = json!([
+            bounds.min().x,
+            bounds.min().y,
+            bounds.min().z,
+            bounds.max().x,
+            bounds.max().y,
+            bounds.max().z
+        ]);
+        write_json(&metadata_path, &metadata, true)?;
+    }
+    let hierarchy = OctreeHierarchy::new(cube, nodes);
+    write_ept_hierarchy(ept_dir, &hierarchy)?;
+    Ok(hierarchy)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ept::write_test_ept,
+        las::{get_test_las_path, test_data_classifications},
+    };
+    use pasture_core::{layout::attributes::CLASSIFICATION, nalgebra::Point3};
+    use scopeguard::defer;
+
+    fn test_append_ept(name: &str, other_cube_scale: f64) -> Result<()> {
+        let temp_dir = std::env::temp_dir().join(name);
+        defer! {
+            std::fs::remove_dir_all(&temp_dir).expect("Could not remove test directory");
+        }
+
+        let mut reader = LASReader::from_path(get_test_las_path(0))?;
+        let header = reader.header().clone();
+        let layout = reader.get_default_point_layout().clone();
+        let bounds = header.bounds();
+        let conforming_bounds = AABB::from_min_max_unchecked(
+            Point3::new(bounds.min.x, bounds.min.y, bounds.min.z),
+            Point3::new(bounds.max.x, bounds.max.y, bounds.max.z),
+        );
+        let cube = conforming_bounds.as_cubic();
+        let other_cube =
+            AABB::from_min_max_unchecked(cube.min(), cube.min() + cube.extent() * other_cube_scale);
+
+        let mut first_points = InterleavedVecPointStorage::new(layout.clone());
+        reader.read_into(&mut first_points, 5)?;
+        let mut other_points = InterleavedVecPointStorage::new(layout.clone());
+        reader.read_into(&mut other_points, 5)?;
+        let ept_dir = temp_dir.join("first");
+        let other_dir = temp_dir.join("other");
+        write_test_ept(&ept_dir, &first_points, &cube, &conforming_bounds, &header)?;
+        write_test_ept(
+            &other_dir,
+            &other_points,
+            &other_cube,
+            &conforming_bounds,
+            &header,
+        )?;
+
+        // Appending inserts the points in the same way as indexing all points at once, first and other points in order
+        let hierarchy = append_ept(&ept_dir, &other_dir)?;
+        let mut all_points = InterleavedVecPointStorage::new(layout.clone());
+        LASReader::from_path(get_test_las_path(0))?.read_into(&mut all_points, 10)?;
+        let expected_hierarchy = write_test_ept(
+            &temp_dir.join("expected"),
+            &all_points,
+            &cube,
+            &conforming_bounds,
+            &header,
+        )?;
+        assert_eq!(expected_hierarchy, hierarchy);
+        assert_eq!(hierarchy, read_ept_hierarchy(&ept_dir)?);
+        assert_eq!(10, read_json(&ept_dir.join(EPT_METADATA_FILE))?["points"]);
+
+        let mut classifications = vec![];
+        for (key, point_count) in hierarchy.nodes() {
+            let points = read_node_file(
+                &ept_dir
+                    .join(EPT_DATA_DIR)
+                    .join(format!("{}.laz", ept_node_name(&key))),
+                &layout,
+            )?;
+            assert_eq!(point_count, points.len());
+            classifications.extend(points.iter_attribute::<u8>(&CLASSIFICATION));
+        }
+        let mut expected_classifications = test_data_classifications();
+        expected_classifications.sort_unstable();
+        classifications.sort_unstable();
+        assert_eq!(expected_classifications, classifications);
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_aligned_ept() -> Result<()> {
+        test_append_ept("pasture_test_append_aligned_ept", 1.0)
+    }
+
+    #[test]
+    fn test_append_ept_with_other_bounds() -> Result<()> {
+        test_append_ept("pasture_test_append_ept_with_other_bounds", 2.0)
+    }
+}
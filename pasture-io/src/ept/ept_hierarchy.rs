@@ -0,0 +1,328 @@
+use std::{
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::Path,
+};
+
+use pasture_core::{containers::InterleavedVecPointStorage, math::AABB, nalgebra::Point3};
+use serde_json::Value;
+
+use crate::{
+    base::{
+        HierarchyMerge, OctreeHierarchy, OctreeNodeKey, PastureIoError, PointReader, PointWriter,
+        Result,
+    },
+    las::{LASReader, LASWriter},
+};
+
+/// Name of the metadata file of an EPT dataset
+pub(super) const EPT_METADATA_FILE: &str = "ept.json";
+/// Name of the directory with the hierarchy files of an EPT dataset
+pub(super) const EPT_HIERARCHY_DIR: &str = "ept-hierarchy";
+/// Name of the directory with the point data files of an EPT dataset
+pub(super) const EPT_DATA_DIR: &str = "ept-data";
+
+/// Returns the name of the node with the given `key` in the files of an EPT dataset, e.g. `1-0-1-0`
+pub fn ept_node_name(key: &OctreeNodeKey) -> String {
+    let (depth, x, y, z) = *key;
+    format!("{}-{}-{}-{}", depth, x, y, z)
+}
+
+/// Parses the `name` of a node of an EPT dataset, e.g. `1-0-1-0`, into its key
+///
+/// # Errors
+///
+/// If `name` doesn't consist of four non-negative integers separated by `-`, an error is returned
+pub fn parse_ept_node_name(name: &str) -> Result<OctreeNodeKey> {
+    let parts = name
+        .split('-')
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| PastureIoError::InvalidData(format!("Invalid EPT node name '{}'", name)))?;
+    match parts.as_slice() {
+        [depth, x, y, z] if *depth <= u32::MAX as u64 => Ok((*depth as u32, *x, *y, *z)),
+        _ => Err(PastureIoError::InvalidData(format!(
+            "Invalid EPT node name '{}'",
+            name
+        ))),
+    }
+}
+
+pub(super) fn read_json(path: &Path) -> Result<Value> {
+    let file = File::open(path).map_err(PastureIoError::Io)?;
+    serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+        PastureIoError::InvalidData(format!("Invalid JSON in {}: {}", path.display(), e))
+    })
+}
+
+pub(super) fn write_json(path: &Path, value: &Value, pretty: bool) -> Result<()> {
+    let writer = BufWriter::new(File::create(path).map_err(PastureIoError::Io)?);
+    if pretty {
+        serde_json::to_writer_pretty(writer, value)?;
+    } else {
+        serde_json::to_writer(writer, value)?;
+    }
+    Ok(())
+}
+
+/// Returns the bounds with the given `name` from the EPT metadata, e.g. the cubic `bounds` of the octree
+pub(super) fn ept_bounds(metadata: &Value, name: &str) -> Result<AABB<f64>> {
+    let bounds = metadata
+        .get(name)
+        .and_then(Value::as_array)
+        .map(|bounds| bounds.iter().filter_map(Value::as_f64).collect::<Vec<_>>())
+        .unwrap_or_default();
+    if bounds.len() != 6 {
+        return Err(PastureIoError::InvalidData(format!(
+            "EPT metadata has no valid '{}'",
+            name
+        )));
+    }
+    Ok(AABB::from_min_max_unchecked(
+        Point3::new(bounds[0], bounds[1], bounds[2]),
+        Point3::new(bounds[3], bounds[4], bounds[5]),
+    ))
+}
+
+/// Returns an error if the points of the EPT dataset with the given `metadata` are not stored as LAZ files, which are
+/// the only data type that `operation` supports
+pub(super) fn ensure_laszip_data(metadata: &Value, operation: &str) -> Result<()> {
+    match metadata.get("dataType").and_then(Value::as_str) {
+        Some("laszip") => Ok(()),
+        data_type => Err(PastureIoError::UnsupportedFormat(format!(
+            "{} EPT datasets with data type {} is not supported, only laszip",
+            operation,
+            data_type.unwrap_or("(none)")
+        ))),
+    }
+}
+
+/// Reads the octree hierarchy of the EPT dataset in the directory `ept_dir`, i.e. the bounds of the octree from the
+/// `ept.json` file and the point counts of all nodes from the hierarchy files, including the hierarchy files of
+/// subtrees. Only the metadata is read, not the points
+///
+/// # Errors
+///
+/// If the metadata or a hierarchy file can't be read or is invalid, an error is returned
+pub fn read_ept_hierarchy<P: AsRef<Path>>(ept_dir: P) -> Result<OctreeHierarchy> {
+    let ept_dir = ept_dir.as_ref();
+    let cube = ept_bounds(&read_json(&ept_dir.join(EPT_METADATA_FILE))?, "bounds")?;
+    let mut nodes = vec![];
+    let mut pages = vec![(0, 0, 0, 0)];
+    while let Some(page) = pages.pop() {
+        let path = ept_dir
+            .join(EPT_HIERARCHY_DIR)
+            .join(format!("{}.json", ept_node_name(&page)));
+        let entries = read_json(&path)?;
+        let entries = entries.as_object().ok_or_else(|| {
+            PastureIoError::InvalidData(format!(
+                "EPT hierarchy file {} is no JSON object",
+                path.display()
+            ))
+        })?;
+        for (name, point_count) in entries {
+            let key = parse_ept_node_name(name)?;
+            match point_count.as_i64() {
+                // The hierarchy of this subtree is stored in a separate file
+                Some(-1) if key != page => pages.push(key),
+                Some(point_count) if point_count >= 0 => nodes.push((key, point_count as usize)),
+                _ => {
+                    return Err(PastureIoError::InvalidData(format!(
+                        "Invalid point count {} of node {} in EPT hierarchy file {}",
+                        point_count,
+                        name,
+                        path.display()
+                    )))
+                }
+            }
+        }
+    }
+    Ok(OctreeHierarchy::new(cube, nodes))
+}
+
+/// Writes `hierarchy` as the hierarchy of the EPT dataset in the directory `ept_dir`. All nodes are stored in a single
+/// hierarchy file, which replaces all existing hierarchy files, and the number of points in `ept.json` is updated
+///
+/// # Errors
+///
+/// If `ept.json` can't be read, or if an I/O error occurs while writing the files, an error is returned
+pub fn write_ept_hierarchy<P: AsRef<Path>>(ept_dir: P, hierarchy: &OctreeHierarchy) -> Result<()> {
+    let ept_dir = ept_dir.as_ref();
+    let metadata_path = ept_dir.join(EPT_METADATA_FILE);
+    let mut metadata = read_json(&metadata_path)?;
+    match metadata.as_object_mut() {
+        Some(metadata) => {
+            metadata.insert("points".into(), hierarchy.point_count().into());
+        }
+        None => {
+            return Err(PastureIoError::InvalidData(format!(
+                "EPT metadata in {} is no JSON object",
+                metadata_path.display()
+            )))
+        }
+    }
+
+    let hierarchy_dir = ept_dir.join(EPT_HIERARCHY_DIR);
+    fs::create_dir_all(&hierarchy_dir).map_err(PastureIoError::Io)?;
+    for entry in fs::read_dir(&hierarchy_dir).map_err(PastureIoError::Io)? {
+        let path = entry.map_err(PastureIoError::Io)?.path();
+        if path.extension().map(|extension| extension == "json") == Some(true) {
+            fs::remove_file(&path).map_err(PastureIoError::Io)?;
+        }
+    }
+    let entries = hierarchy
+        .nodes()
+        .map(|(key, point_count)| (ept_node_name(&key), point_count.into()))
+        .collect::<serde_json::Map<_, _>>();
+    write_json(
+        &hierarchy_dir.join(format!("{}.json", ept_node_name(&(0, 0, 0, 0)))),
+        &Value::Object(entries),
+        false,
+    )?;
+    write_json(&metadata_path, &metadata, true)
+}
+
+/// Re-chunks the EPT dataset in the directory `ept_dir` in place, according to a `merge` of its hierarchy (see
+/// [OctreeHierarchy::merge_sparse_nodes] and [OctreeHierarchy::truncate]): The points of all nodes that are merged into
+/// another node are appended to the points of this node, the data files of the merged nodes are deleted, and the
+/// hierarchy is replaced with the merged hierarchy. Only the nodes that change are read and written. The dataset is
+/// inconsistent while this function runs, so it should not be served at the same time
+///
+/// ```no_run
+/// # use pasture_io::ept::{read_ept_hierarchy, rechunk_ept};
+/// # fn main() -> anyhow::Result<()> {
+/// let hierarchy = read_ept_hierarchy("dataset")?;
+/// let merge = hierarchy.merge_sparse_nodes(1000);
+/// println!("Merging {} nodes", merge.merged_node_count());
+/// rechunk_ept("dataset", &merge)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If the data type of the dataset is not `laszip`, or if an I/O error occurs while reading or writing the points or
+/// the hierarchy, an error is returned
+pub fn rechunk_ept<P: AsRef<Path>>(ept_dir: P, merge: &HierarchyMerge) -> Result<()> {
+    let ept_dir = ept_dir.as_ref();
+    ensure_laszip_data(&read_json(&ept_dir.join(EPT_METADATA_FILE))?, "Re-chunking")?;
+
+    let data_dir = ept_dir.join(EPT_DATA_DIR);
+    let data_path = |key: &OctreeNodeKey| data_dir.join(format!("{}.laz", ept_node_name(key)));
+    for (target, _) in merge.hierarchy().nodes() {
+        let sources = merge.sources_of(&target);
+        if sources == [target] {
+            continue;
+        }
+
+        let mut points: Option<InterleavedVecPointStorage> = None;
+        let mut header = None;
+        // Nodes without points may have no data file
+        for path in sources.iter().map(data_path).filter(|path| path.exists()) {
+            let mut reader = LASReader::from_path(&path)?;
+            let point_count = reader.remaining_points();
+            let points = points.get_or_insert_with(|| {
+                InterleavedVecPointStorage::new(reader.get_default_point_layout().clone())
+            });
+            header.get_or_insert_with(|| reader.header().clone());
+            reader.read_into(points, point_count)?;
+        }
+        let (points, header) = match (points, header) {
+            (Some(points), Some(header)) => (points, header),
+            _ => continue,
+        };
+
+        // Write the merged node to a temporary file first, since the target node may be one of the sources
+        let temp_path = data_dir.join(format!("{}.rechunked.laz", ept_node_name(&target)));
+        {
+            let mut writer = LASWriter::from_path_and_header(&temp_path, header)?;
+            writer.write(&points)?;
+            writer.flush()?;
+        }
+        fs::rename(&temp_path, data_path(&target)).map_err(PastureIoError::Io)?;
+        for source in sources.iter().filter(|source| **source != target) {
+            let path = data_path(source);
+            if path.exists() {
+                fs::remove_file(&path).map_err(PastureIoError::Io)?;
+            }
+        }
+    }
+
+    write_ept_hierarchy(ept_dir, merge.hierarchy())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::las::{get_test_las_path, test_data_classifications};
+    use pasture_core::{containers::PointBufferExt, layout::attributes::CLASSIFICATION};
+    use scopeguard::defer;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_ept_node_names() -> Result<()> {
+        assert_eq!((1, 0, 1, 0), parse_ept_node_name("1-0-1-0")?);
+        assert_eq!("3-7-0-2", ept_node_name(&(3, 7, 0, 2)));
+        assert!(parse_ept_node_name("1-0-1").is_err());
+        assert!(parse_ept_node_name("1-0-a-0").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_rechunk_ept() -> Result<()> {
+        let ept_dir = std::env::temp_dir().join("pasture_test_rechunk_ept");
+        defer! {
+            std::fs::remove_dir_all(&ept_dir).expect("Could not remove test directory");
+        }
+        fs::create_dir_all(ept_dir.join(EPT_DATA_DIR))?;
+        fs::create_dir_all(ept_dir.join(EPT_HIERARCHY_DIR))?;
+
+        // The 10 test points are distributed over three nodes, whose hierarchy is stored in two files
+        let mut reader = LASReader::from_path(get_test_las_path(0))?;
+        let header = reader.header().clone();
+        for (name, point_count) in [("0-0-0-0", 6), ("1-0-0-0", 3), ("2-0-0-0", 1)].iter() {
+            let points = reader.read(*point_count)?;
+            let path = ept_dir.join(EPT_DATA_DIR).join(format!("{}.laz", name));
+            let mut writer = LASWriter::from_path_and_header(path, header.clone())?;
+            writer.write(points.as_ref())?;
+            writer.flush()?;
+        }
+        let metadata = json!({"bounds": [0, 0, 0, 16, 16, 16], "dataType": "laszip", "points": 10});
+        write_json(&ept_dir.join(EPT_METADATA_FILE), &metadata, true)?;
+        let hierarchy_dir = ept_dir.join(EPT_HIERARCHY_DIR);
+        write_json(
+            &hierarchy_dir.join("0-0-0-0.json"),
+            &json!({"0-0-0-0": 6, "1-0-0-0": -1}),
+            false,
+        )?;
+        write_json(
+            &hierarchy_dir.join("1-0-0-0.json"),
+            &json!({"1-0-0-0": 3, "2-0-0-0": 1}),
+            false,
+        )?;
+
+        let hierarchy = read_ept_hierarchy(&ept_dir)?;
+        assert_eq!(
+            vec![((0, 0, 0, 0), 6), ((1, 0, 0, 0), 3), ((2, 0, 0, 0), 1)],
+            hierarchy.nodes().collect::<Vec<_>>()
+        );
+        assert_eq!(16.0, hierarchy.bounds().extent().x);
+
+        let merge = hierarchy.merge_sparse_nodes(5);
+        rechunk_ept(&ept_dir, &merge)?;
+        assert_eq!(merge.hierarchy(), &read_ept_hierarchy(&ept_dir)?);
+        assert_eq!(1, fs::read_dir(ept_dir.join(EPT_DATA_DIR))?.count());
+        assert_eq!(1, fs::read_dir(&hierarchy_dir)?.count());
+
+        let mut reader = LASReader::from_path(ept_dir.join(EPT_DATA_DIR).join("0-0-0-0.laz"))?;
+        let mut points = InterleavedVecPointStorage::new(reader.get_default_point_layout().clone());
+        assert_eq!(10, reader.read_into(&mut points, 10)?);
+        assert_eq!(
+            test_data_classifications(),
+            points
+                .iter_attribute::<u8>(&CLASSIFICATION)
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+}
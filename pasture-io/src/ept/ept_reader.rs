@@ -0,0 +1,291 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use pasture_core::{
+    containers::{
+        InterleavedPointView, InterleavedVecPointStorage, PointBufferWriteable, SpatialQuery,
+        SpatialQueryable,
+    },
+    layout::{attributes::POSITION_3D, PointLayout},
+};
+use serde_json::Value;
+
+use super::{
+    ept_hierarchy::{ensure_laszip_data, read_json, EPT_DATA_DIR, EPT_METADATA_FILE},
+    ept_node_name, read_ept_hierarchy,
+};
+use crate::{
+    base::{
+        octree_node_bounds, query_blocks, BlockCache, OctreeHierarchy, OctreeNodeKey,
+        PastureIoError, PointReader, Result,
+    },
+    las::LASReader,
+};
+
+/// Reader for EPT datasets, which answers [SpatialQuery]s by reading only the data files of those nodes of the octree
+/// that can contain matching points
+///
+/// The points of a node at depth `d` are sampled on a grid with `span` cells along each axis of the node, so their
+/// spacing is the width of the `bounds` of the dataset divided by `span * 2^d`. Queries with a `resolution` skip all
+/// nodes whose spacing is smaller than `resolution`, like for COPC files
+pub struct EPTReader {
+    ept_dir: PathBuf,
+    hierarchy: OctreeHierarchy,
+    span: u64,
+    layout: PointLayout,
+    cache: Option<Arc<Mutex<BlockCache>>>,
+}
+
+impl EPTReader {
+    /// Opens the EPT dataset in the directory `ept_dir` and reads its metadata and hierarchy. The points are read
+    /// lazily by the queries
+    ///
+    /// # Errors
+    ///
+    /// If the metadata or the hierarchy can't be read or are invalid, if the data type of the dataset is not
+    /// `laszip`, or if the data file of the root node can't be opened, an error is returned
+    pub fn from_path<P: AsRef<Path>>(ept_dir: P) -> Result<Self> {
+        let ept_dir = ept_dir.as_ref().to_owned();
+        let metadata = read_json(&ept_dir.join(EPT_METADATA_FILE))?;
+        ensure_laszip_data(&metadata, "Reading")?;
+        let span = metadata
+            .get("span")
+            .and_then(Value::as_u64)
+            .filter(|span| *span > 0)
+            .ok_or_else(|| {
+                PastureIoError::InvalidData("EPT metadata has no valid 'span'".into())
+            })?;
+        let hierarchy = read_ept_hierarchy(&ept_dir)?;
+        // All data files of a dataset have the same point format, so the root node determines the layout
+        let layout = LASReader::from_path(node_path(&ept_dir, &(0, 0, 0, 0)))?
+            .get_default_point_layout()
+            .clone();
+        Ok(Self {
+            ept_dir,
+            hierarchy,
+            span,
+            layout,
+            cache: None,
+        })
+    }
+
+    /// Reads the data files of the nodes through the given `cache` (see
+    /// [from_path_with_cache](LASReader::from_path_with_cache)), so that repeated queries that touch the same nodes
+    /// don't read their data files again
+    pub fn with_cache(mut self, cache: Arc<Mutex<BlockCache>>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Returns the octree hierarchy of the dataset
+    pub fn hierarchy(&self) -> &OctreeHierarchy {
+        &self.hierarchy
+    }
+
+    /// Returns the spacing between the points of the nodes at the given `depth`
+    pub fn node_spacing(&self, depth: u32) -> f64 {
+        self.hierarchy.bounds().extent().x / (self.span as f64 * (1u64 << depth) as f64)
+    }
+
+    /// Reads all points of the node with the given `key` in the default `PointLayout` of the dataset
+    ///
+    /// # Errors
+    ///
+    /// If the data file of the node can't be read, an error is returned
+    pub fn read_node(&self, key: &OctreeNodeKey) -> Result<InterleavedVecPointStorage> {
+        read_node_in_layout(&self.ept_dir, key, &self.layout, self.cache.as_ref())
+    }
+}
+
+fn node_path(ept_dir: &Path, key: &OctreeNodeKey) -> PathBuf {
+    ept_dir
+        .join(EPT_DATA_DIR)
+        .join(format!("{}.laz", ept_node_name(key)))
+}
+
+fn read_node_in_layout(
+    ept_dir: &Path,
+    key: &OctreeNodeKey,
+    layout: &PointLayout,
+    cache: Option<&Arc<Mutex<BlockCache>>>,
+) -> Result<InterleavedVecPointStorage> {
+    let path = node_path(ept_dir, key);
+    let mut reader = match cache {
+        Some(cache) => LASReader::from_path_with_cache(path, cache.clone())?,
+        None => LASReader::from_path(path)?,
+    };
+    let point_count = reader.remaining_points();
+    let mut points = InterleavedVecPointStorage::with_capacity(point_count, layout.clone());
+    reader.read_into(&mut points, point_count)?;
+    Ok(points)
+}
+
+impl SpatialQueryable for EPTReader {
+    fn point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    fn query(
+        &mut self,
+        query: &SpatialQuery,
+        resolution: Option<f64>,
+        points: &mut dyn PointBufferWriteable,
+    ) -> anyhow::Result<usize> {
+        let layout = points.point_layout().clone();
+        if !layout.has_attribute_with_name(POSITION_3D.name()) {
+            return Err(PastureIoError::LayoutMismatch(
+                "Can't run a spatial query for points without a POSITION_3D attribute".into(),
+            )
+            .into());
+        }
+        let cube = self.hierarchy.bounds();
+        // Nodes without points may have no data file
+        let nodes = self
+            .hierarchy
+            .nodes()
+            .filter(|(key, point_count)| {
+                *point_count > 0
+                    && resolution.map_or(true, |resolution| self.node_spacing(key.0) >= resolution)
+            })
+            .map(|(key, _)| (octree_node_bounds(&key, &cube), key))
+            .collect();
+        let ept_dir = &self.ept_dir;
+        let cache = self.cache.as_ref();
+        let matches = query_blocks(nodes, query, &layout, |key| {
+            read_node_in_layout(ept_dir, key, &layout, cache)
+        })?;
+
+        points.push(&InterleavedPointView::from_raw_slice(
+            &matches,
+            layout.clone(),
+        ));
+        Ok(matches.len() / layout.size_of_point_entry() as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ept::write_test_ept, las::get_test_las_path};
+    use pasture_core::{
+        containers::{PointBuffer, PointBufferExt},
+        math::AABB,
+        nalgebra::{Point3, Vector3},
+    };
+    use scopeguard::defer;
+
+    #[test]
+    fn test_query_ept() -> Result<()> {
+        let ept_dir = std::env::temp_dir().join("pasture_test_query_ept");
+        defer! {
+            std::fs::remove_dir_all(&ept_dir).expect("Could not remove test directory");
+        }
+
+        let mut las_reader = LASReader::from_path(get_test_las_path(0))?;
+        let header = las_reader.header().clone();
+        let mut all_points =
+            InterleavedVecPointStorage::new(las_reader.get_default_point_layout().clone());
+        las_reader.read_into(&mut all_points, 10)?;
+        let bounds = header.bounds();
+        let conforming_bounds = AABB::from_min_max_unchecked(
+            Point3::new(bounds.min.x, bounds.min.y, bounds.min.z),
+            Point3::new(bounds.max.x, bounds.max.y, bounds.max.z),
+        );
+        let cube = conforming_bounds.as_cubic();
+        write_test_ept(&ept_dir, &all_points, &cube, &conforming_bounds, &header)?;
+
+        let mut reader = EPTReader::from_path(&ept_dir)?;
+        assert_eq!(cube.extent().x, reader.node_spacing(0));
+        assert_eq!(cube.extent().x / 2.0, reader.node_spacing(1));
+
+        let positions = |points: &InterleavedVecPointStorage| {
+            points
+                .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                .collect::<Vec<_>>()
+        };
+        let mut points = InterleavedVecPointStorage::new(reader.point_layout().clone());
+        assert_eq!(
+            10,
+            reader.query(&SpatialQuery::Bounds(cube), None, &mut points)?
+        );
+        let mut expected_positions = positions(&all_points);
+        let mut actual_positions = positions(&points);
+        let by_coordinates =
+            |a: &Vector3<f64>, b: &Vector3<f64>| a.as_slice().partial_cmp(b.as_slice()).unwrap();
+        expected_positions.sort_by(by_coordinates);
+        actual_positions.sort_by(by_coordinates);
+        assert_eq!(expected_positions, actual_positions);
+
+        // Only the root node has a spacing of the full width of the cube
+        let mut root_points = InterleavedVecPointStorage::new(reader.point_layout().clone());
+        assert_eq!(
+            1,
+            reader.query(
+                &SpatialQuery::Bounds(cube),
+                Some(cube.extent().x),
+                &mut root_points
+            )?
+        );
+        assert_eq!(
+            positions(&reader.read_node(&(0, 0, 0, 0))?),
+            positions(&root_points)
+        );
+
+        let position = expected_positions[3];
+        let mut expected_nearest = positions(&all_points);
+        expected_nearest.sort_by(|a, b| {
+            (a - position)
+                .norm()
+                .partial_cmp(&(b - position).norm())
+                .unwrap()
+        });
+        let mut nearest = InterleavedVecPointStorage::new(reader.point_layout().clone());
+        reader.query(
+            &SpatialQuery::Nearest { position, k: 3 },
+            None,
+            &mut nearest,
+        )?;
+        assert_eq!(3, nearest.len());
+        assert_eq!(expected_nearest[..3].to_vec(), positions(&nearest));
+        Ok(())
+    }
+
+    #[test]
+    fn test_query_ept_with_cache() -> Result<()> {
+        let ept_dir = std::env::temp_dir().join("pasture_test_query_ept_with_cache");
+        defer! {
+            std::fs::remove_dir_all(&ept_dir).expect("Could not remove test directory");
+        }
+
+        let mut las_reader = LASReader::from_path(get_test_las_path(0))?;
+        let header = las_reader.header().clone();
+        let mut all_points =
+            InterleavedVecPointStorage::new(las_reader.get_default_point_layout().clone());
+        las_reader.read_into(&mut all_points, 10)?;
+        let bounds = header.bounds();
+        let conforming_bounds = AABB::from_min_max_unchecked(
+            Point3::new(bounds.min.x, bounds.min.y, bounds.min.z),
+            Point3::new(bounds.max.x, bounds.max.y, bounds.max.z),
+        );
+        let cube = conforming_bounds.as_cubic();
+        write_test_ept(&ept_dir, &all_points, &cube, &conforming_bounds, &header)?;
+
+        let cache = BlockCache::new_shared(256, 1024 * 1024);
+        let mut reader = EPTReader::from_path(&ept_dir)?.with_cache(cache.clone());
+        let mut points = InterleavedVecPointStorage::new(reader.point_layout().clone());
+        reader.query(&SpatialQuery::Bounds(cube), None, &mut points)?;
+        let misses = cache.lock().unwrap().statistics().misses;
+        assert!(misses > 0);
+
+        // The second query reads all data files from the cache
+        let mut repeated_points = InterleavedVecPointStorage::new(reader.point_layout().clone());
+        reader.query(&SpatialQuery::Bounds(cube), None, &mut repeated_points)?;
+        assert_eq!(10, repeated_points.len());
+        let statistics = cache.lock().unwrap().statistics();
+        assert_eq!(misses, statistics.misses);
+        assert!(statistics.hits >= misses);
+        Ok(())
+    }
+}
@@ -0,0 +1,13 @@
+mod ept_hierarchy;
+pub use self::ept_hierarchy::*;
+
+mod ept_append;
+pub use self::ept_append::*;
+
+mod ept_reader;
+pub use self::ept_reader::*;
+
+#[cfg(test)]
+mod test_util;
+#[cfg(test)]
+pub(crate) use self::test_util::*;
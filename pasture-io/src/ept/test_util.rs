@@ -0,0 +1,57 @@
+use std::{fs, path::Path};
+
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBuffer},
+    math::AABB,
+};
+use serde_json::json;
+
+use super::{
+    ept_hierarchy::{write_json, EPT_DATA_DIR, EPT_METADATA_FILE},
+    ept_node_name, write_ept_hierarchy,
+};
+use crate::{
+    base::{OctreeHierarchy, PointWriter, Result, StreamingOctreeBuilder},
+    las::LASWriter,
+};
+
+/// Indexes `points` into an EPT dataset in `ept_dir` with a span of 1 and a maximum depth of 1, so that the root
+/// node stores one point and the nodes at depth 1 store all other points
+pub(crate) fn write_test_ept(
+    ept_dir: &Path,
+    points: &InterleavedVecPointStorage,
+    cube: &AABB<f64>,
+    conforming_bounds: &AABB<f64>,
+    header: &las::Header,
+) -> Result<OctreeHierarchy> {
+    fs::create_dir_all(ept_dir.join(EPT_DATA_DIR))?;
+    let mut builder =
+        StreamingOctreeBuilder::new(points.point_layout().clone(), *cube, 1, 1, usize::MAX);
+    builder.push(points)?;
+    let octree = builder.finish();
+    for (key, _) in octree.hierarchy() {
+        let path = ept_dir
+            .join(EPT_DATA_DIR)
+            .join(format!("{}.laz", ept_node_name(&key)));
+        let mut writer = LASWriter::from_path_and_header(path, header.clone())?;
+        writer.write(&octree.read_node(&key)?)?;
+        writer.flush()?;
+    }
+
+    let metadata = json!({
+        "bounds": [cube.min().x, cube.min().y, cube.min().z, cube.max().x, cube.max().y, cube.max().z],
+        "boundsConforming": [
+            conforming_bounds.min().x, conforming_bounds.min().y, conforming_bounds.min().z,
+            conforming_bounds.max().x, conforming_bounds.max().y, conforming_bounds.max().z
+        ],
+        "dataType": "laszip",
+        "points": octree.point_count(),
+        "schema": [],
+        "span": 1,
+        "srs": {},
+    });
+    write_json(&ept_dir.join(EPT_METADATA_FILE), &metadata, true)?;
+    let hierarchy = octree.octree_hierarchy();
+    write_ept_hierarchy(ept_dir, &hierarchy)?;
+    Ok(hierarchy)
+}
@@ -0,0 +1,318 @@
+use std::{
+    io::{Seek, Write},
+    sync::{
+        mpsc::{channel, sync_channel, Receiver, Sender, SyncSender},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+};
+
+use laz::{LasZipCompressor, LazVlr, ParLasZipCompressor};
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+use super::map_laz_err;
+use crate::base::{PastureIoError, Result};
+
+/// The compressor of a `BackgroundCompressor`. Which compressor is used is decided when the first points are
+/// compressed, because the chunks that a `LasZipCompressor` has started can't be continued by another compressor
+enum Compressor<T: Write + Seek + Send + 'static> {
+    /// No points were compressed yet
+    Pending(T, LazVlr),
+    /// Compresses the chunks one after another
+    Sequential(LasZipCompressor<'static, T>),
+    /// Compresses the complete chunks of each call in parallel on the given thread pool and writes them in order,
+    /// together with the chunk table
+    Parallel(ParLasZipCompressor<T>, Arc<ThreadPool>),
+}
+
+impl<T: Write + Seek + Send + 'static> Compressor<T> {
+    /// Switches a `Pending` compressor to a `Sequential` compressor
+    fn into_started(self) -> Result<Self> {
+        match self {
+            Compressor::Pending(output, vlr) => Ok(Compressor::Sequential(
+                LasZipCompressor::new(output, vlr).map_err(map_laz_err)?,
+            )),
+            started => Ok(started),
+        }
+    }
+
+    fn compress_many(&mut self, points: &[u8]) -> Result<()> {
+        match self {
+            Compressor::Pending(..) => {
+                panic!("Compressor::compress_many: Compressor must be started first")
+            }
+            Compressor::Sequential(compressor) => compressor.compress_many(points)?,
+            Compressor::Parallel(compressor, pool) => pool
+                .install(|| compressor.compress_many(points))
+                .map_err(map_laz_err)?,
+        }
+        Ok(())
+    }
+
+    fn done(&mut self) -> Result<()> {
+        match self {
+            Compressor::Pending(..) => {
+                panic!("Compressor::done: Compressor must be started first")
+            }
+            Compressor::Sequential(compressor) => compressor.done()?,
+            Compressor::Parallel(compressor, pool) => {
+                pool.install(|| compressor.done()).map_err(map_laz_err)?
+            }
+        }
+        Ok(())
+    }
+
+    fn output_mut(&mut self) -> &mut T {
+        match self {
+            Compressor::Pending(output, _) => output,
+            Compressor::Sequential(compressor) => compressor.get_mut(),
+            Compressor::Parallel(compressor, _) => compressor.get_mut(),
+        }
+    }
+}
+
+/// Body of the compression thread of a `BackgroundCompressor`. Compresses the chunks in the order in which they are
+/// sent and returns the emptied buffers for reuse. Returns the compressor once all chunks are compressed, or the first
+/// error
+fn compress_chunks<T: Write + Seek + Send + 'static>(
+    mut compressor: Compressor<T>,
+    chunks: Receiver<Vec<u8>>,
+    free_buffers: Sender<Vec<u8>>,
+) -> Result<Compressor<T>> {
+    for chunk in chunks {
+        compressor.compress_many(&chunk)?;
+        // The writer might already be gone, in which case the buffer is simply dropped
+        let _ = free_buffers.send(chunk);
+    }
+    Ok(compressor)
+}
+
+struct CompressionThread<T: Write + Seek + Send + 'static> {
+    chunks: SyncSender<Vec<u8>>,
+    free_buffers: Receiver<Vec<u8>>,
+    worker: JoinHandle<Result<Compressor<T>>>,
+}
+
+/// Wrapper around the LAZ compressor of a file that can compress the point records on a background thread, so that
+/// the writer can encode the next points while the previous points are compressed. The point records are passed to
+/// the thread through a bounded queue, so the writer blocks once too many chunks are waiting for compression
+///
+/// With [start](BackgroundCompressor::start), all chunks are compressed by the same `LasZipCompressor` on one thread.
+/// With [start_parallel](BackgroundCompressor::start_parallel), the background thread hands the LAZ chunks of each
+/// batch of point records to a pool of worker threads and writes the compressed chunks in order
+pub(crate) struct BackgroundCompressor<T: Write + Seek + Send + 'static> {
+    compressor: Option<Compressor<T>>,
+    thread: Option<CompressionThread<T>>,
+    /// Point records that are collected until they fill one LAZ chunk per worker thread, in parallel mode
+    batch: Vec<u8>,
+    batch_size: usize,
+}
+
+impl<T: Write + Seek + Send + 'static> BackgroundCompressor<T> {
+    /// Creates a new `BackgroundCompressor` that writes the compressed point records to `output`. Compresses on the
+    /// calling thread until `start` or `start_parallel` is called
+    pub fn new(output: T, vlr: LazVlr) -> Self {
+        Self {
+            compressor: Some(Compressor::Pending(output, vlr)),
+            thread: None,
+            batch: vec![],
+            batch_size: 0,
+        }
+    }
+
+    /// Moves the compression to a background thread. At most `max_queued_chunks` chunks of point records wait for
+    /// compression at any time. Does nothing if the compression already runs on a background thread
+    ///
+    /// # Errors
+    ///
+    /// If the background thread can't be started, an error is returned
+    ///
+    /// # Panics
+    ///
+    /// If `max_queued_chunks` is zero
+    pub fn start(&mut self, max_queued_chunks: usize) -> Result<()> {
+        if max_queued_chunks == 0 {
+            panic!("BackgroundCompressor::start: max_queued_chunks must be > 0");
+        }
+        if self.thread.is_some() {
+            return Ok(());
+        }
+        let compressor = self
+            .compressor
+            .take()
+            .ok_or_else(Self::unavailable_error)?
+            .into_started()?;
+        self.spawn(compressor, max_queued_chunks)
+    }
+
+    /// Moves the compression to a background thread, which compresses the LAZ chunks on a pool of `num_threads` worker
+    /// threads. The point records of `record_length` bytes each are collected into batches of one LAZ chunk per worker,
+    /// and at most `max_queued_batches` batches wait for compression at any time. Does nothing if the compression
+    /// already runs on a background thread
+    ///
+    /// # Errors
+    ///
+    /// If points were compressed before, an error is returned, because the chunk that the sequential compressor has
+    /// started can't be continued in parallel. If the threads can't be started, an error is returned as well
+    ///
+    /// # Panics
+    ///
+    /// If `num_threads`, `max_queued_batches` or `record_length` is zero
+    pub fn start_parallel(
+        &mut self,
+        num_threads: usize,
+        max_queued_batches: usize,
+        record_length: usize,
+    ) -> Result<()> {
+        if num_threads == 0 {
+            panic!("BackgroundCompressor::start_parallel: num_threads must be > 0");
+        }
+        if max_queued_batches == 0 {
+            panic!("BackgroundCompressor::start_parallel: max_queued_batches must be > 0");
+        }
+        if record_length == 0 {
+            panic!("BackgroundCompressor::start_parallel: record_length must be > 0");
+        }
+        if self.thread.is_some() {
+            return Ok(());
+        }
+        let (output, vlr) = match self.compressor.take() {
+            Some(Compressor::Pending(output, vlr)) => (output, vlr),
+            Some(started) => {
+                self.compressor = Some(started);
+                return Err(PastureIoError::InvalidArgument(
+                    "Parallel LAZ compression must be started before the first points are written"
+                        .into(),
+                ));
+            }
+            None => return Err(Self::unavailable_error()),
+        };
+        self.batch_size = num_threads * vlr.chunk_size() as usize * record_length;
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|index| format!("pasture-laz-compression-{}", index))
+            .build()
+            .map_err(|e| PastureIoError::Other(Box::new(e)))?;
+        let compressor = ParLasZipCompressor::new(output, vlr).map_err(map_laz_err)?;
+        self.spawn(
+            Compressor::Parallel(compressor, Arc::new(pool)),
+            max_queued_batches,
+        )
+    }
+
+    fn spawn(&mut self, compressor: Compressor<T>, max_queued_chunks: usize) -> Result<()> {
+        let (chunk_sender, chunk_receiver) = sync_channel(max_queued_chunks);
+        let (free_buffer_sender, free_buffer_receiver) = channel();
+        let worker = thread::Builder::new()
+            .name("pasture-laz-compression".into())
+            .spawn(move || compress_chunks(compressor, chunk_receiver, free_buffer_sender))
+            .map_err(PastureIoError::Io)?;
+        self.thread = Some(CompressionThread {
+            chunks: chunk_sender,
+            free_buffers: free_buffer_receiver,
+            worker,
+        });
+        Ok(())
+    }
+
+    /// Compresses the given point records, either directly or on the background thread
+    ///
+    /// # Errors
+    ///
+    /// If an error occurs during compression, an error is returned. With a background thread, errors are reported on
+    /// the call after the chunk that failed
+    pub fn compress_many(&mut self, points: &[u8]) -> Result<()> {
+        if self.thread.is_none() {
+            return self.compressor_mut()?.compress_many(points);
+        }
+        if self.batch_size == 0 {
+            return self.send(points);
+        }
+        self.batch.extend_from_slice(points);
+        if self.batch.len() >= self.batch_size {
+            let batch = std::mem::take(&mut self.batch);
+            self.send(&batch)?;
+            self.batch = batch;
+            self.batch.clear();
+        }
+        Ok(())
+    }
+
+    /// Sends the given point records to the background thread
+    fn send(&mut self, points: &[u8]) -> Result<()> {
+        let thread = self
+            .thread
+            .as_ref()
+            .expect("BackgroundCompressor::send: No background thread");
+        let mut chunk = thread.free_buffers.try_recv().unwrap_or_default();
+        chunk.clear();
+        chunk.extend_from_slice(points);
+        // Sending only fails if the thread stopped because of an error, which `stop` returns
+        if thread.chunks.send(chunk).is_err() {
+            self.stop()?;
+            return Err(PastureIoError::Other(
+                "LAZ compression thread has stopped".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Waits until all point records are compressed and stops the background thread, if there is one. Afterwards,
+    /// compression continues on the calling thread, in parallel if it was started with `start_parallel`
+    ///
+    /// # Errors
+    ///
+    /// If an error occurred on the background thread, an error is returned
+    pub fn stop(&mut self) -> Result<()> {
+        if !self.batch.is_empty() {
+            let batch = std::mem::take(&mut self.batch);
+            self.send(&batch)?;
+        }
+        if let Some(thread) = self.thread.take() {
+            drop(thread.chunks);
+            let compressor = thread.worker.join().map_err(|_| {
+                PastureIoError::Other("LAZ compression thread has panicked".into())
+            })??;
+            self.compressor = Some(compressor);
+        }
+        Ok(())
+    }
+
+    /// Compresses the last chunk and writes the chunk table, after all point records are compressed (see `stop`)
+    ///
+    /// # Errors
+    ///
+    /// If an error occurred during compression, an error is returned
+    pub fn done(&mut self) -> Result<()> {
+        self.compressor_mut()?.done()
+    }
+
+    /// Returns the underlying writer after all point records are compressed (see `stop`)
+    ///
+    /// # Errors
+    ///
+    /// If an error occurred on the background thread, an error is returned
+    pub fn output_mut(&mut self) -> Result<&mut T> {
+        self.stop()?;
+        Ok(self
+            .compressor
+            .as_mut()
+            .ok_or_else(Self::unavailable_error)?
+            .output_mut())
+    }
+
+    /// Returns the started compressor after all point records are compressed (see `stop`)
+    fn compressor_mut(&mut self) -> Result<&mut Compressor<T>> {
+        self.stop()?;
+        let compressor = self
+            .compressor
+            .take()
+            .ok_or_else(Self::unavailable_error)?
+            .into_started()?;
+        Ok(self.compressor.insert(compressor))
+    }
+
+    fn unavailable_error() -> PastureIoError {
+        PastureIoError::Other("LAZ compressor is not available after a compression error".into())
+    }
+}
@@ -0,0 +1,143 @@
+use futures_util::io::{AsyncRead, AsyncReadExt};
+use las_rs::Header;
+use pasture_core::containers::InterleavedVecPointStorage;
+
+use super::{LASDecoder, LASDecoderEvent};
+use crate::base::{PastureIoError, Result};
+
+/// Number of bytes that an [AsyncLASReader] reads from its source at once
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Asynchronous adapter on top of [LASDecoder], which reads an uncompressed LAS file from any `AsyncRead`, e.g. a
+/// network stream. It doesn't depend on a specific async runtime
+///
+/// ```
+/// # use futures_util::io::AsyncRead;
+/// # use pasture_io::las::AsyncLASReader;
+/// # async fn read(source: impl AsyncRead + Unpin) -> anyhow::Result<()> {
+/// let mut reader = AsyncLASReader::new(source).await?;
+/// println!("{}", reader.header().number_of_points());
+/// while let Some(_points) = reader.read_points().await? {
+///     /* Process points */
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncLASReader<R: AsyncRead + Unpin> {
+    source: R,
+    decoder: LASDecoder,
+    header: Header,
+    buffer: Vec<u8>,
+    has_ended: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncLASReader<R> {
+    /// Creates a new `AsyncLASReader` that reads the LAS file from `source` and reads its header
+    ///
+    /// # Errors
+    ///
+    /// If reading from `source` fails, if `source` does not contain a valid LAS file or ends before the end of the
+    /// header, or if the file is compressed, an error is returned
+    pub async fn new(mut source: R) -> Result<Self> {
+        let mut decoder = LASDecoder::new();
+        let mut buffer = vec![0; READ_BUFFER_SIZE];
+        next_event(&mut source, &mut decoder, &mut buffer).await?;
+        let header = decoder
+            .header()
+            .expect("The first event of a LASDecoder is the header")
+            .clone();
+        Ok(Self {
+            source,
+            decoder,
+            header,
+            buffer,
+            has_ended: false,
+        })
+    }
+
+    /// Returns the header of the LAS file
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Reads and decodes the next points of the LAS file, in the default `PointLayout` of its point format. Returns
+    /// `None` once all points were read
+    ///
+    /// # Errors
+    ///
+    /// If reading from the source fails, or if it ends before all points were read, an error is returned
+    pub async fn read_points(&mut self) -> Result<Option<InterleavedVecPointStorage>> {
+        if self.has_ended {
+            return Ok(None);
+        }
+        match next_event(&mut self.source, &mut self.decoder, &mut self.buffer).await? {
+            LASDecoderEvent::Points(points) => Ok(Some(points)),
+            LASDecoderEvent::Header | LASDecoderEvent::End => {
+                self.has_ended = true;
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Pushes bytes from `source` into `decoder` until the next event occurs
+async fn next_event<R: AsyncRead + Unpin>(
+    source: &mut R,
+    decoder: &mut LASDecoder,
+    buffer: &mut [u8],
+) -> Result<LASDecoderEvent> {
+    loop {
+        if let Some(event) = decoder.next_event()? {
+            return Ok(event);
+        }
+        let count = source.read(buffer).await?;
+        if count == 0 {
+            return Err(PastureIoError::InvalidData(
+                "LAS file ends before all of its points".into(),
+            ));
+        }
+        decoder.push(&buffer[..count]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::FutureExt;
+    use pasture_core::containers::{PointBuffer, PointBufferWriteable};
+
+    use super::*;
+    use crate::las::{
+        compare_to_reference_data, get_test_las_path, las_header_length, test_data_point_count,
+    };
+
+    #[test]
+    fn test_async_las_reader() -> Result<()> {
+        let bytes = std::fs::read(get_test_las_path(1))?;
+        // Reading from a slice never waits, so the futures complete on the first poll
+        let mut reader = AsyncLASReader::new(bytes.as_slice())
+            .now_or_never()
+            .unwrap()?;
+        assert_eq!(
+            test_data_point_count(),
+            reader.header().number_of_points() as usize
+        );
+
+        let mut points = None;
+        while let Some(new_points) = reader.read_points().now_or_never().unwrap()? {
+            points
+                .get_or_insert_with(|| {
+                    InterleavedVecPointStorage::new(new_points.point_layout().clone())
+                })
+                .push(&new_points);
+        }
+        let points = points.unwrap();
+        assert_eq!(test_data_point_count(), points.len());
+        compare_to_reference_data(&points, 1);
+
+        // A file that ends in the middle of the first point record
+        let truncated = &bytes[..las_header_length(&bytes)?.unwrap() + 1];
+        let mut reader = AsyncLASReader::new(truncated).now_or_never().unwrap()?;
+        assert!(reader.read_points().now_or_never().unwrap().is_err());
+        Ok(())
+    }
+}
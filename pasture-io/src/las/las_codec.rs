@@ -0,0 +1,359 @@
+use std::io::Cursor;
+
+use las_rs::{Builder, Header, Vlr};
+use pasture_core::containers::{
+    InterleavedPointBufferMut, InterleavedVecPointStorage, PointBufferWriteable,
+};
+
+use super::{read_raw_las_header, LASRecordDecoder};
+use crate::base::{PastureIoError, Result};
+
+/// Size of the header of LAS 1.0 to 1.2 files, which is the smallest possible LAS header. [las_header_length] needs at
+/// least this many bytes from the start of a LAS file
+pub const MIN_LAS_HEADER_LENGTH: usize = 227;
+
+/// Default maximum number of points per [LASDecoderEvent::Points] event of a [LASDecoder]
+pub const DEFAULT_MAX_POINTS_PER_EVENT: usize = 50_000;
+
+/// Returns the number of bytes at the start of a LAS file that contain the header and all VLRs, i.e. the offset to the
+/// point records, given the first bytes of the file. Returns `None` if `bytes` is shorter than
+/// [MIN_LAS_HEADER_LENGTH], in which case more bytes have to be read first
+///
+/// # Errors
+///
+/// If `bytes` is not the start of a LAS file
+pub fn las_header_length(bytes: &[u8]) -> Result<Option<usize>> {
+    if bytes.len() < MIN_LAS_HEADER_LENGTH {
+        return Ok(None);
+    }
+    if &bytes[0..4] != b"LASF" {
+        return Err(PastureIoError::CorruptHeader {
+            offset: 0,
+            message: "File signature 'LASF' not found".into(),
+        });
+    }
+    // The offset to the point data is stored at byte offset 96 of the LAS header
+    let offset_to_point_data = u32::from_le_bytes([bytes[96], bytes[97], bytes[98], bytes[99]]);
+    if (offset_to_point_data as usize) < MIN_LAS_HEADER_LENGTH {
+        return Err(PastureIoError::CorruptHeader {
+            offset: 96,
+            message: format!("Invalid offset to point data {}", offset_to_point_data),
+        });
+    }
+    Ok(Some(offset_to_point_data as usize))
+}
+
+/// Parses the LAS header and all VLRs from `bytes`, which have to contain at least the first
+/// [las_header_length] bytes of a LAS file. This works on memory only, so that the header can be read from any source,
+/// e.g. a network stream or a buffer in a browser
+///
+/// # Errors
+///
+/// If `bytes` does not contain a valid LAS header with all of its VLRs, an error is returned
+pub fn parse_las_header(bytes: &[u8]) -> Result<Header> {
+    let mut cursor = Cursor::new(bytes);
+    let raw_header = read_raw_las_header(&mut cursor)?;
+    let number_of_vlrs = raw_header.number_of_variable_length_records;
+    let mut header_builder = Builder::new(raw_header)?;
+    for _ in 0..number_of_vlrs {
+        let vlr_offset = cursor.position();
+        let vlr = las_rs::raw::Vlr::read_from(&mut cursor, false)
+            .map(Vlr::new)
+            .map_err(|err| PastureIoError::CorruptHeader {
+                offset: vlr_offset,
+                message: err.to_string(),
+            })?;
+        header_builder.vlrs.push(vlr);
+    }
+    Ok(header_builder.into_header()?)
+}
+
+/// Encodes the given LAS `header` and its VLRs into the bytes that precede the point records of a LAS file. Together
+/// with [LASRecordEncoder](super::LASRecordEncoder), this writes LAS files without any I/O. EVLRs are not included, as
+/// they are stored after the point records
+///
+/// # Errors
+///
+/// If `header` can't be represented as a LAS header, e.g. because one of its VLRs is too large
+pub fn encode_las_header(header: &Header) -> Result<Vec<u8>> {
+    let raw_header = header.clone().into_raw()?;
+    let offset_to_point_data = raw_header.offset_to_point_data as usize;
+    let mut bytes = Vec::with_capacity(offset_to_point_data);
+    raw_header.write_to(&mut bytes)?;
+    for vlr in header.vlrs() {
+        if vlr.has_large_data() {
+            return Err(PastureIoError::UnsupportedFormat(format!(
+                "VLR '{}' is too large for the LAS header, it has to be stored as an EVLR",
+                vlr.description
+            )));
+        }
+        vlr.clone().into_raw(false)?.write_to(&mut bytes)?;
+    }
+    bytes.resize(offset_to_point_data, 0);
+    Ok(bytes)
+}
+
+/// Events of a [LASDecoder]
+pub enum LASDecoderEvent {
+    /// The header and all VLRs were decoded and are available through [header](LASDecoder::header)
+    Header,
+    /// The next points of the file, in the default `PointLayout` of its point format
+    Points(InterleavedVecPointStorage),
+    /// All points of the file were decoded. All following bytes, e.g. EVLRs, are ignored
+    End,
+}
+
+enum DecoderState {
+    Header,
+    Points {
+        decoder: LASRecordDecoder,
+        remaining_points: usize,
+    },
+    End,
+}
+
+/// Decoder for uncompressed LAS files that doesn't do any I/O itself ("sans-io"). The caller pushes the bytes of a LAS
+/// file in arbitrary pieces with [push](LASDecoder::push), wherever they come from, and pulls the decoded header and
+/// points with [next_event](LASDecoder::next_event). This makes it possible to decode LAS files from sources that are
+/// neither `Read` nor `Seek`, such as network streams, async readers or `fetch` responses in WebAssembly.
+/// [LASReader](super::LASReader) is the synchronous adapter that reads from files, `AsyncLASReader` (behind the `async`
+/// feature) the asynchronous adapter that reads from any `futures::io::AsyncRead`
+///
+/// Compressed LAZ files are not supported, because the LAZ chunk table is stored at the end of the file
+/// ```
+/// # use pasture_io::las::*;
+/// # fn decode(stream: impl Iterator<Item = Vec<u8>>) -> anyhow::Result<()> {
+/// let mut decoder = LASDecoder::new();
+/// for bytes in stream {
+///     decoder.push(&bytes);
+///     while let Some(event) = decoder.next_event()? {
+///         match event {
+///             LASDecoderEvent::Header => println!("{}", decoder.header().unwrap().number_of_points()),
+///             LASDecoderEvent::Points(_points) => { /* Process points */ }
+///             LASDecoderEvent::End => return Ok(()),
+///         }
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct LASDecoder {
+    bytes: Vec<u8>,
+    /// Number of bytes at the start of `bytes` that were already decoded
+    consumed: usize,
+    state: DecoderState,
+    header: Option<Header>,
+    max_points_per_event: usize,
+    ignore_point_count: bool,
+}
+
+impl LASDecoder {
+    /// Creates a new `LASDecoder` that expects the first byte of a LAS file next
+    pub fn new() -> Self {
+        Self {
+            bytes: vec![],
+            consumed: 0,
+            state: DecoderState::Header,
+            header: None,
+            max_points_per_event: DEFAULT_MAX_POINTS_PER_EVENT,
+            ignore_point_count: false,
+        }
+    }
+
+    /// Sets the maximum number of points per [LASDecoderEvent::Points] event
+    ///
+    /// # Panics
+    ///
+    /// If `max_points_per_event` is zero
+    pub fn with_max_points_per_event(mut self, max_points_per_event: usize) -> Self {
+        if max_points_per_event == 0 {
+            panic!("LASDecoder::with_max_points_per_event: max_points_per_event must be greater than zero");
+        }
+        self.max_points_per_event = max_points_per_event;
+        self
+    }
+
+    /// Ignores the number of points in the header and decodes points for as long as bytes are pushed, so the
+    /// [LASDecoderEvent::End] event never occurs. This is for LAS files that are still being written, whose header
+    /// doesn't contain the final number of points yet. All bytes after the header are decoded as point records, so
+    /// files with EVLRs can't be decoded this way
+    pub fn with_unknown_point_count(mut self) -> Self {
+        self.ignore_point_count = true;
+        self
+    }
+
+    /// Appends the next `bytes` of the LAS file to the input of this decoder
+    pub fn push(&mut self, bytes: &[u8]) {
+        if matches!(self.state, DecoderState::End) {
+            return;
+        }
+        // The decoded bytes are dropped once per push instead of once per event, so that the remaining bytes are not
+        // moved for every event
+        self.bytes.drain(..self.consumed);
+        self.consumed = 0;
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    /// Returns the header of the LAS file, once the [LASDecoderEvent::Header] event occurred
+    pub fn header(&self) -> Option<&Header> {
+        self.header.as_ref()
+    }
+
+    /// Returns the number of points that were not decoded yet, or `None` if the header was not decoded yet or if the
+    /// number of points is unknown (see [with_unknown_point_count](LASDecoder::with_unknown_point_count))
+    pub fn remaining_points(&self) -> Option<usize> {
+        match &self.state {
+            DecoderState::Header => None,
+            DecoderState::Points { .. } if self.ignore_point_count => None,
+            DecoderState::Points {
+                remaining_points, ..
+            } => Some(*remaining_points),
+            DecoderState::End => Some(0),
+        }
+    }
+
+    /// Returns the next event, or `None` if more bytes have to be pushed before the next event can occur
+    ///
+    /// # Errors
+    ///
+    /// If the pushed bytes are no valid LAS file, or if the file is compressed, an error is returned
+    pub fn next_event(&mut self) -> Result<Option<LASDecoderEvent>> {
+        match &mut self.state {
+            DecoderState::Header => {
+                let input = &self.bytes[self.consumed..];
+                let header_length = match las_header_length(input)? {
+                    Some(header_length) if header_length <= input.len() => header_length,
+                    _ => return Ok(None),
+                };
+                // Compressed LAZ files set the high bits of the point data format ID at byte offset 104
+                if input[104] & 0xC0 != 0 {
+                    return Err(PastureIoError::UnsupportedFormat(
+                        "LASDecoder can't decode compressed LAZ files".into(),
+                    ));
+                }
+                let header = parse_las_header(&input[..header_length])?;
+                let decoder = LASRecordDecoder::from_header(&header)?;
+                self.consumed += header_length;
+                let remaining_points = if self.ignore_point_count {
+                    usize::MAX
+                } else {
+                    header.number_of_points() as usize
+                };
+                self.state = DecoderState::Points {
+                    decoder,
+                    remaining_points,
+                };
+                self.header = Some(header);
+                Ok(Some(LASDecoderEvent::Header))
+            }
+            DecoderState::Points {
+                decoder,
+                remaining_points,
+            } => {
+                if *remaining_points == 0 {
+                    self.state = DecoderState::End;
+                    self.bytes = vec![];
+                    self.consumed = 0;
+                    return Ok(Some(LASDecoderEvent::End));
+                }
+                let input = &self.bytes[self.consumed..];
+                let count = (input.len() / decoder.record_length())
+                    .min(*remaining_points)
+                    .min(self.max_points_per_event);
+                if count == 0 {
+                    return Ok(None);
+                }
+                let mut points = InterleavedVecPointStorage::with_capacity(
+                    count,
+                    decoder.point_layout().clone(),
+                );
+                points.resize(count);
+                let record_bytes = count * decoder.record_length();
+                decoder.decode(&input[..record_bytes], points.get_raw_points_mut(0..count));
+                self.consumed += record_bytes;
+                *remaining_points -= count;
+                Ok(Some(LASDecoderEvent::Points(points)))
+            }
+            DecoderState::End => Ok(None),
+        }
+    }
+}
+
+impl Default for LASDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::containers::PointBuffer;
+
+    use super::*;
+    use crate::las::{
+        compare_to_reference_data, get_test_las_path, get_test_laz_path, test_data_point_count,
+    };
+
+    #[test]
+    fn test_las_decoder_with_small_pieces() -> Result<()> {
+        for format in 0..=10 {
+            let bytes = std::fs::read(get_test_las_path(format))?;
+            let mut decoder = LASDecoder::new().with_max_points_per_event(3);
+            let mut points = None;
+            let mut has_ended = false;
+            for piece in bytes.chunks(13) {
+                decoder.push(piece);
+                while let Some(event) = decoder.next_event()? {
+                    match event {
+                        LASDecoderEvent::Header => {
+                            let header = decoder.header().unwrap();
+                            assert_eq!(format, header.point_format().to_u8()?);
+                            points = Some(InterleavedVecPointStorage::new(
+                                LASRecordDecoder::from_header(header)?
+                                    .point_layout()
+                                    .clone(),
+                            ));
+                        }
+                        LASDecoderEvent::Points(new_points) => {
+                            assert!(new_points.len() <= 3);
+                            points.as_mut().unwrap().push(&new_points);
+                        }
+                        LASDecoderEvent::End => has_ended = true,
+                    }
+                }
+            }
+            assert!(has_ended);
+            assert_eq!(Some(0), decoder.remaining_points());
+            let points = points.unwrap();
+            assert_eq!(test_data_point_count(), points.len());
+            compare_to_reference_data(&points, format);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_header_roundtrip() -> Result<()> {
+        let bytes = std::fs::read(get_test_las_path(6))?;
+        assert_eq!(None, las_header_length(&bytes[..100])?);
+        let header_length = las_header_length(&bytes)?.unwrap();
+        let header = parse_las_header(&bytes[..header_length])?;
+
+        let encoded = encode_las_header(&header)?;
+        let decoded = parse_las_header(&encoded)?;
+        assert_eq!(header.point_format(), decoded.point_format());
+        assert_eq!(header.number_of_points(), decoded.number_of_points());
+        assert_eq!(header.bounds(), decoded.bounds());
+        assert_eq!(header.vlrs(), decoded.vlrs());
+
+        assert!(las_header_length(&[0; MIN_LAS_HEADER_LENGTH]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_las_decoder_rejects_laz() -> Result<()> {
+        let mut decoder = LASDecoder::new();
+        decoder.push(&std::fs::read(get_test_laz_path(0))?);
+        assert!(decoder.next_event().is_err());
+        Ok(())
+    }
+}
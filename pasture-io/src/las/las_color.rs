@@ -0,0 +1,220 @@
+use std::ops::Range;
+
+use pasture_core::{
+    containers::{PointBufferExt, PointBufferWriteable, PointBufferWriteableExt},
+    layout::{attributes::COLOR_RGB, PointAttributeDataType},
+    nalgebra::Vector3,
+};
+
+/// Bit depth of RGB colors. The LAS specification requires colors to be stored with 16 bits per channel, but many
+/// producers store 8-bit values instead, which appear nearly black when they are interpreted as 16-bit values. Used
+/// by `LASReader::set_color_bit_depth` and `LASWriter::set_color_bit_depth`
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ColorBitDepth {
+    /// Detect the bit depth from the color values, see `detect_color_bit_depth`
+    Auto,
+    /// 8 bits per channel, i.e. values in `[0;255]`
+    EightBit,
+    /// 16 bits per channel, i.e. values in `[0;65535]`, as required by the LAS specification
+    SixteenBit,
+}
+
+/// Detects the bit depth of the given `colors`. If all channels of all colors are at most 255, the colors are assumed
+/// to be 8-bit colors, otherwise 16-bit colors. This is a heuristic and will wrongly report `ColorBitDepth::EightBit`
+/// for 16-bit colors that are all very dark, so it should be applied to as many colors as possible. Returns
+/// `ColorBitDepth::SixteenBit` if `colors` is empty
+///
+/// ```
+/// # use pasture_core::nalgebra::Vector3;
+/// # use pasture_io::las::{detect_color_bit_depth, ColorBitDepth};
+/// let colors = vec![Vector3::new(255, 128, 0), Vector3::new(12, 34, 56)];
+/// assert_eq!(ColorBitDepth::EightBit, detect_color_bit_depth(colors));
+/// ```
+pub fn detect_color_bit_depth<I: IntoIterator<Item = Vector3<u16>>>(colors: I) -> ColorBitDepth {
+    let mut is_empty = true;
+    for color in colors {
+        is_empty = false;
+        if color.x > 255 || color.y > 255 || color.z > 255 {
+            return ColorBitDepth::SixteenBit;
+        }
+    }
+    if is_empty {
+        ColorBitDepth::SixteenBit
+    } else {
+        ColorBitDepth::EightBit
+    }
+}
+
+/// Converts an 8-bit color into a 16-bit color. Each channel is multiplied by 257, so that 255 maps to 65535
+pub fn color_8_to_16_bit(color: Vector3<u16>) -> Vector3<u16> {
+    color.map(|channel| channel.min(255) * 257)
+}
+
+/// Converts a 16-bit color into an 8-bit color by keeping the upper 8 bits of each channel
+pub fn color_16_to_8_bit(color: Vector3<u16>) -> Vector3<u16> {
+    color.map(|channel| channel >> 8)
+}
+
+/// Converts the `COLOR_RGB` attribute of the points in `range` of `buffer` from the given `bit_depth` to 16 bits per
+/// channel. If `bit_depth` is `ColorBitDepth::Auto`, the bit depth is detected from the colors in `range` first.
+/// Returns the bit depth that the colors were converted from. Buffers without a `COLOR_RGB` attribute of type
+/// `Vector3<u16>` are not changed
+///
+/// # Panics
+///
+/// If `range` is out of bounds for `buffer`
+pub fn normalize_color_bit_depth(
+    buffer: &mut dyn PointBufferWriteable,
+    range: Range<usize>,
+    bit_depth: ColorBitDepth,
+) -> ColorBitDepth {
+    let has_colors = buffer
+        .point_layout()
+        .get_attribute_by_name(COLOR_RGB.name())
+        .map(|attribute| attribute.datatype() == PointAttributeDataType::Vec3u16)
+        .unwrap_or(false);
+    if !has_colors {
+        return bit_depth;
+    }
+
+    let bit_depth = match bit_depth {
+        ColorBitDepth::Auto => detect_color_bit_depth(
+            range
+                .clone()
+                .map(|index| buffer.get_attribute::<Vector3<u16>>(&COLOR_RGB, index)),
+        ),
+        other => other,
+    };
+    if bit_depth == ColorBitDepth::EightBit {
+        for index in range {
+            let color = buffer.get_attribute::<Vector3<u16>>(&COLOR_RGB, index);
+            buffer.set_attribute(&COLOR_RGB, index, color_8_to_16_bit(color));
+        }
+    }
+    bit_depth
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{
+        base::{PointReader, PointWriter, Result},
+        las::{LASReader, LASWriter, LasPointFormat2},
+    };
+    use las_rs::{point::Format, Builder};
+    use pasture_core::containers::InterleavedVecPointStorage;
+    use scopeguard::defer;
+
+    fn test_points() -> InterleavedVecPointStorage {
+        vec![
+            LasPointFormat2 {
+                position: Vector3::new(1.0, 2.0, 3.0),
+                color_rgb: Vector3::new(255, 128, 0),
+                ..Default::default()
+            },
+            LasPointFormat2 {
+                position: Vector3::new(4.0, 5.0, 6.0),
+                color_rgb: Vector3::new(1, 2, 3),
+                ..Default::default()
+            },
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn read_colors(reader: &mut LASReader) -> Result<Vec<Vector3<u16>>> {
+        let points = reader.read(2)?;
+        Ok(points.iter_attribute::<Vector3<u16>>(&COLOR_RGB).collect())
+    }
+
+    #[test]
+    fn test_detect_color_bit_depth() {
+        assert_eq!(
+            ColorBitDepth::EightBit,
+            detect_color_bit_depth(vec![Vector3::new(0, 255, 17)])
+        );
+        assert_eq!(
+            ColorBitDepth::SixteenBit,
+            detect_color_bit_depth(vec![Vector3::new(0, 255, 17), Vector3::new(256, 0, 0)])
+        );
+        assert_eq!(ColorBitDepth::SixteenBit, detect_color_bit_depth(vec![]));
+    }
+
+    #[test]
+    fn test_color_conversion() {
+        assert_eq!(
+            Vector3::new(0, 257, 65535),
+            color_8_to_16_bit(Vector3::new(0, 1, 255))
+        );
+        assert_eq!(
+            Vector3::new(0, 1, 255),
+            color_16_to_8_bit(Vector3::new(0, 257, 65535))
+        );
+    }
+
+    #[test]
+    fn test_normalize_color_bit_depth() {
+        let mut points = test_points();
+        assert_eq!(
+            ColorBitDepth::EightBit,
+            normalize_color_bit_depth(&mut points, 1..2, ColorBitDepth::Auto)
+        );
+        let colors = points
+            .iter_attribute::<Vector3<u16>>(&COLOR_RGB)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![Vector3::new(255, 128, 0), Vector3::new(257, 514, 771)],
+            colors
+        );
+
+        // The colors of the first point are now 16-bit, so nothing is converted
+        assert_eq!(
+            ColorBitDepth::SixteenBit,
+            normalize_color_bit_depth(&mut points, 0..2, ColorBitDepth::Auto)
+        );
+    }
+
+    #[test]
+    fn test_read_and_write_8_bit_colors() -> Result<()> {
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_read_and_write_8_bit_colors.las");
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+        let expected_16_bit_colors =
+            vec![Vector3::new(65535, 32896, 0), Vector3::new(257, 514, 771)];
+
+        let mut header_builder = Builder::from((1, 4));
+        header_builder.point_format = Format::new(2)?;
+        let header = header_builder.into_header()?;
+        {
+            let mut writer = LASWriter::from_path_and_header(&test_file_path, header.clone())?;
+            writer.write(&test_points())?;
+        }
+
+        // The file contains 8-bit colors, which are only converted on read if requested
+        let mut reader = LASReader::from_path(&test_file_path)?;
+        assert_eq!(
+            vec![Vector3::new(255, 128, 0), Vector3::new(1, 2, 3)],
+            read_colors(&mut reader)?
+        );
+        let mut reader = LASReader::from_path(&test_file_path)?;
+        reader.set_color_bit_depth(ColorBitDepth::Auto);
+        assert_eq!(expected_16_bit_colors, read_colors(&mut reader)?);
+
+        // Converting on write instead yields a file with 16-bit colors
+        {
+            let mut writer = LASWriter::from_path_and_header(&test_file_path, header)?;
+            writer.set_color_bit_depth(ColorBitDepth::Auto);
+            writer.write(&test_points())?;
+        }
+        let mut reader = LASReader::from_path(&test_file_path)?;
+        assert_eq!(expected_16_bit_colors, read_colors(&mut reader)?);
+        let mut reader = LASReader::from_path(&test_file_path)?;
+        reader.set_color_bit_depth(ColorBitDepth::Auto);
+        assert_eq!(expected_16_bit_colors, read_colors(&mut reader)?);
+        Ok(())
+    }
+}
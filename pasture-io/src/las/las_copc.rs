@@ -0,0 +1,322 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use las_rs::Vlr;
+use pasture_core::{
+    math::AABB,
+    nalgebra::{Point3, Vector3},
+};
+
+use super::read_las_header;
+use crate::base::{OctreeHierarchy, OctreeNodeKey, PastureIoError, PointBlock, Result};
+
+/// User ID of the VLRs defined by the COPC specification
+pub(crate) const COPC_USER_ID: &str = "copc";
+/// Record ID of the COPC info VLR
+pub(crate) const COPC_INFO_RECORD_ID: u16 = 1;
+/// Size of a single entry in a page of the COPC hierarchy
+const COPC_HIERARCHY_ENTRY_SIZE: u64 = 32;
+
+/// The parts of the COPC info VLR that are required for reading the COPC hierarchy
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CopcInfo {
+    pub center: Vector3<f64>,
+    pub halfsize: f64,
+    pub spacing: f64,
+    pub root_hierarchy_offset: u64,
+    pub root_hierarchy_size: u64,
+}
+
+impl CopcInfo {
+    /// Parses the `CopcInfo` from the data of the COPC info VLR
+    pub fn from_vlr_data(data: &[u8]) -> Result<Self> {
+        let mut cursor = Cursor::new(data);
+        let center = Vector3::new(
+            cursor.read_f64::<LittleEndian>()?,
+            cursor.read_f64::<LittleEndian>()?,
+            cursor.read_f64::<LittleEndian>()?,
+        );
+        let halfsize = cursor.read_f64::<LittleEndian>()?;
+        let spacing = cursor.read_f64::<LittleEndian>()?;
+        let root_hierarchy_offset = cursor.read_u64::<LittleEndian>()?;
+        let root_hierarchy_size = cursor.read_u64::<LittleEndian>()?;
+        Ok(Self {
+            center,
+            halfsize,
+            spacing,
+            root_hierarchy_offset,
+            root_hierarchy_size,
+        })
+    }
+
+    /// Returns the bounds of the octree node with the given key, consisting of the level and the x, y, z index of the
+    /// node within this level
+    pub fn node_bounds(&self, level: i32, x: i32, y: i32, z: i32) -> AABB<f64> {
+        let node_size = 2.0 * self.halfsize / (1u64 << level) as f64;
+        let root_min = self.center - Vector3::new(self.halfsize, self.halfsize, self.halfsize);
+        let min = root_min + Vector3::new(x as f64, y as f64, z as f64) * node_size;
+        let max = min + Vector3::new(node_size, node_size, node_size);
+        AABB::from_min_max_unchecked(Point3::from(min), Point3::from(max))
+    }
+
+    /// Returns the spacing between the points of the octree nodes at the given level
+    pub fn node_spacing(&self, level: i32) -> f64 {
+        self.spacing / (1u64 << level) as f64
+    }
+}
+
+/// Is the given VLR the COPC info VLR?
+pub(crate) fn is_copc_info_vlr(vlr: &Vlr) -> bool {
+    vlr.user_id == COPC_USER_ID && vlr.record_id == COPC_INFO_RECORD_ID
+}
+
+/// An entry of the COPC hierarchy for an octree node that contains points
+#[derive(Debug, Clone, PartialEq)]
+struct CopcNodeEntry {
+    key: OctreeNodeKey,
+    offset: u64,
+    point_count: usize,
+}
+
+/// Reads all pages of the COPC hierarchy described by `info` from `read` and returns the entries of all octree nodes
+/// that contain points
+fn read_copc_node_entries<R: Read + Seek>(
+    read: &mut R,
+    info: &CopcInfo,
+) -> Result<Vec<CopcNodeEntry>> {
+    let mut nodes = vec![];
+    let mut pages = vec![(info.root_hierarchy_offset, info.root_hierarchy_size)];
+    while let Some((page_offset, page_size)) = pages.pop() {
+        if page_size % COPC_HIERARCHY_ENTRY_SIZE != 0 {
+            return Err(PastureIoError::CorruptHeader {
+                offset: page_offset,
+                message: format!(
+                    "Size of COPC hierarchy page ({} bytes) is no multiple of the entry size",
+                    page_size
+                ),
+            });
+        }
+        read.seek(SeekFrom::Start(page_offset))?;
+        for _ in 0..(page_size / COPC_HIERARCHY_ENTRY_SIZE) {
+            let level = read.read_i32::<LittleEndian>()?;
+            let x = read.read_i32::<LittleEndian>()?;
+            let y = read.read_i32::<LittleEndian>()?;
+            let z = read.read_i32::<LittleEndian>()?;
+            let offset = read.read_u64::<LittleEndian>()?;
+            let byte_size = read.read_i32::<LittleEndian>()?;
+            let point_count = read.read_i32::<LittleEndian>()?;
+            if level < 0 || x < 0 || y < 0 || z < 0 {
+                return Err(PastureIoError::CorruptHeader {
+                    offset: page_offset,
+                    message: format!(
+                        "Invalid key {}-{}-{}-{} in COPC hierarchy page",
+                        level, x, y, z
+                    ),
+                });
+            }
+            match point_count {
+                -1 => pages.push((offset, byte_size as u64)),
+                count if count > 0 => nodes.push(CopcNodeEntry {
+                    key: (level as u32, x as u64, y as u64, z as u64),
+                    offset,
+                    point_count: count as usize,
+                }),
+                _ => (),
+            }
+        }
+    }
+    Ok(nodes)
+}
+
+/// Reads all pages of the COPC hierarchy described by `info` from `read` and returns one `PointBlock` for each octree
+/// node that contains points. The points of each node are stored as one LAZ chunk, so the index of the first point of
+/// a node is the sum of the point counts of all nodes whose data is stored before it in the file
+pub(crate) fn read_copc_point_blocks<R: Read + Seek>(
+    read: &mut R,
+    info: &CopcInfo,
+) -> Result<Vec<PointBlock>> {
+    let mut nodes = read_copc_node_entries(read, info)?;
+    nodes.sort_by_key(|node| node.offset);
+    let mut first_point = 0;
+    Ok(nodes
+        .into_iter()
+        .map(|node| {
+            let (level, x, y, z) = node.key;
+            let block = PointBlock {
+                first_point,
+                point_count: node.point_count,
+                bounds: info.node_bounds(level as i32, x as i32, y as i32, z as i32),
+                spacing: Some(info.node_spacing(level as i32)),
+            };
+            first_point += node.point_count;
+            block
+        })
+        .collect())
+}
+
+/// Reads the octree hierarchy of the COPC file in `read`, i.e. the bounds of the octree and the number of points of
+/// all nodes that contain points. Only the header and the hierarchy pages are read, not the points
+///
+/// # Errors
+///
+/// If `read` is no LAS file, if it has no COPC info VLR, or if its hierarchy is invalid, an error is returned
+pub fn read_copc_hierarchy<R: Read + Seek>(read: &mut R) -> Result<OctreeHierarchy> {
+    let (header, _) = read_las_header(read)?;
+    let info = match header.vlrs().iter().find(|vlr| is_copc_info_vlr(*vlr)) {
+        Some(vlr) => CopcInfo::from_vlr_data(&vlr.data)?,
+        None => {
+            return Err(PastureIoError::UnsupportedFormat(
+                "File is no COPC file, since it has no COPC info VLR".into(),
+            ))
+        }
+    };
+    let halfsize = Vector3::new(info.halfsize, info.halfsize, info.halfsize);
+    let cube = AABB::from_min_max_unchecked(
+        Point3::from(info.center - halfsize),
+        Point3::from(info.center + halfsize),
+    );
+    let nodes = read_copc_node_entries(read, &info)?
+        .into_iter()
+        .map(|node| (node.key, node.point_count));
+    Ok(OctreeHierarchy::new(cube, nodes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::{PointReadAndSeek, PointReader, SeekToPoint},
+        las::{get_test_las_path, test_copc_nodes, write_test_copc_file, LASReader},
+    };
+    use byteorder::WriteBytesExt;
+    use pasture_core::{
+        containers::{PointBuffer, PointBufferExt},
+        layout::attributes::{CLASSIFICATION, GPS_TIME, POSITION_3D},
+    };
+    use scopeguard::defer;
+
+    fn write_entry(data: &mut Vec<u8>, key: [i32; 4], offset: u64, byte_size: i32, count: i32) {
+        for value in key.iter() {
+            data.write_i32::<LittleEndian>(*value).unwrap();
+        }
+        data.write_u64::<LittleEndian>(offset).unwrap();
+        data.write_i32::<LittleEndian>(byte_size).unwrap();
+        data.write_i32::<LittleEndian>(count).unwrap();
+    }
+
+    #[test]
+    fn test_read_copc_point_blocks() -> Result<()> {
+        let info = CopcInfo {
+            center: Vector3::new(0.0, 0.0, 0.0),
+            halfsize: 8.0,
+            spacing: 2.0,
+            root_hierarchy_offset: 0,
+            root_hierarchy_size: 3 * COPC_HIERARCHY_ENTRY_SIZE,
+        };
+        // Root page with the root node, one child node and a reference to a child page. Point data offsets are chosen
+        // so that the data of the child node comes before the data of the root node
+        let mut data = vec![];
+        write_entry(&mut data, [0, 0, 0, 0], 2000, 100, 10);
+        write_entry(&mut data, [1, 1, 0, 0], 1000, 100, 5);
+        write_entry(&mut data, [1, 0, 1, 1], 96, 32, -1);
+        // Child page with one node
+        write_entry(&mut data, [2, 0, 3, 3], 3000, 100, 7);
+
+        let blocks = read_copc_point_blocks(&mut Cursor::new(data), &info)?;
+        let expected = vec![
+            PointBlock {
+                first_point: 0,
+                point_count: 5,
+                bounds: info.node_bounds(1, 1, 0, 0),
+                spacing: Some(1.0),
+            },
+            PointBlock {
+                first_point: 5,
+                point_count: 10,
+                bounds: info.node_bounds(0, 0, 0, 0),
+                spacing: Some(2.0),
+            },
+            PointBlock {
+                first_point: 15,
+                point_count: 7,
+                bounds: info.node_bounds(2, 0, 3, 3),
+                spacing: Some(0.5),
+            },
+        ];
+        assert_eq!(expected, blocks);
+
+        let child_bounds = info.node_bounds(1, 1, 0, 0);
+        assert_eq!(Point3::new(0.0, -8.0, -8.0), *child_bounds.min());
+        assert_eq!(Point3::new(8.0, 0.0, 0.0), *child_bounds.max());
+        Ok(())
+    }
+
+    #[test]
+    fn test_seek_to_block_in_copc_file() -> Result<()> {
+        for format in [6, 7].iter() {
+            let path =
+                std::env::temp_dir().join(format!("pasture_test_seek_copc_{}.copc.laz", format));
+            write_test_copc_file(&path, *format)?;
+            defer! {
+                std::fs::remove_file(&path).expect("Could not remove test file");
+            }
+
+            let mut reader = LASReader::from_path(&path)?;
+            let blocks = reader
+                .point_blocks()?
+                .expect("COPC file has no point blocks");
+            let info = CopcInfo {
+                center: Vector3::new(4.0, 4.0, 4.0),
+                halfsize: 4.0,
+                spacing: 2.0,
+                root_hierarchy_offset: 0,
+                root_hierarchy_size: 0,
+            };
+            assert_eq!(
+                vec![
+                    (0, 4, info.node_bounds(0, 0, 0, 0)),
+                    (4, 3, info.node_bounds(1, 0, 0, 0)),
+                    (7, 2, info.node_bounds(1, 1, 1, 1))
+                ],
+                blocks
+                    .iter()
+                    .map(|block| (block.first_point, block.point_count, block.bounds))
+                    .collect::<Vec<_>>()
+            );
+
+            // The root node intersects all bounds, after it the second child is the next matching block
+            let bounds = AABB::from_min_max(Point3::new(5.5, 5.5, 5.5), Point3::new(7.5, 7.5, 7.5));
+            assert_eq!(Some(blocks[0].clone()), reader.seek_to_block(&bounds)?);
+            assert_eq!(4, reader.read(4)?.len());
+            assert_eq!(Some(blocks[2].clone()), reader.seek_to_block(&bounds)?);
+            assert_eq!(7, reader.point_index()?);
+
+            let points = reader.read(2)?;
+            assert_eq!(
+                vec![3, 3],
+                points
+                    .iter_attribute::<u8>(&CLASSIFICATION)
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(
+                vec![7.0, 8.0],
+                points.iter_attribute::<f64>(&GPS_TIME).collect::<Vec<_>>()
+            );
+            for (position, expected) in points
+                .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                .zip(test_copc_nodes()[2].1.iter())
+            {
+                assert!((position - expected).amax() < 1e-9);
+            }
+            assert_eq!(None, reader.seek_to_block(&bounds)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_copc_hierarchy_of_non_copc_file() -> Result<()> {
+        let mut file = std::fs::File::open(get_test_las_path(0))?;
+        assert!(read_copc_hierarchy(&mut file).is_err());
+        Ok(())
+    }
+}
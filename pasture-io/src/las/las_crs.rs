@@ -0,0 +1,152 @@
+use las::{Header, Vlr};
+
+/// User ID of all VLRs that contain coordinate reference system information
+pub const LAS_PROJECTION_USER_ID: &str = "LASF_Projection";
+/// Record ID of the GeoTIFF GeoKeyDirectoryTag VLR
+pub const GEO_KEY_DIRECTORY_RECORD_ID: u16 = 34735;
+/// Record ID of the OGC coordinate system WKT VLR
+pub const OGC_WKT_RECORD_ID: u16 = 2112;
+
+const GT_MODEL_TYPE_GEO_KEY: u16 = 1024;
+const GEOGRAPHIC_TYPE_GEO_KEY: u16 = 2048;
+const PROJECTED_CS_TYPE_GEO_KEY: u16 = 3072;
+const MODEL_TYPE_PROJECTED: u16 = 1;
+const MODEL_TYPE_GEOGRAPHIC: u16 = 2;
+
+/// Returns `true` if the given `vlr` contains coordinate reference system information
+pub fn is_crs_vlr(vlr: &Vlr) -> bool {
+    vlr.user_id == LAS_PROJECTION_USER_ID
+}
+
+/// Tries to determine the EPSG code of the coordinate reference system of a LAS file from the GeoKeyDirectoryTag VLR
+/// in the given `header`. Both projected and geographic coordinate reference systems are supported. Returns `None`
+/// if there is no GeoKeyDirectoryTag VLR, or if it contains no EPSG code
+pub fn epsg_code_from_las_header(header: &Header) -> Option<u16> {
+    let vlr = header
+        .vlrs()
+        .iter()
+        .chain(header.evlrs().iter())
+        .find(|vlr| is_crs_vlr(vlr) && vlr.record_id == GEO_KEY_DIRECTORY_RECORD_ID)?;
+    let values = vlr
+        .data
+        .chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .collect::<Vec<_>>();
+    if values.len() < 4 {
+        return None;
+    }
+
+    // The GeoKeyDirectory starts with a 4-entry header, where the last entry is the number of keys. Each key has 4 entries:
+    // KeyID, TIFFTagLocation, Count and Value_Offset. A TIFFTagLocation of 0 means that the value is stored in Value_Offset
+    let number_of_keys = values[3] as usize;
+    let keys = values[4..].chunks_exact(4).take(number_of_keys);
+    let mut geographic_code = None;
+    for key in keys {
+        if key[1] != 0 {
+            continue;
+        }
+        match key[0] {
+            PROJECTED_CS_TYPE_GEO_KEY => return Some(key[3]),
+            GEOGRAPHIC_TYPE_GEO_KEY => geographic_code = Some(key[3]),
+            _ => (),
+        }
+    }
+    geographic_code
+}
+
+/// Returns the WKT description of the coordinate reference system of a LAS file from the OGC WKT VLR in the given
+/// `header`, if there is such a VLR
+pub fn wkt_from_las_header(header: &Header) -> Option<String> {
+    let vlr = header
+        .vlrs()
+        .iter()
+        .chain(header.evlrs().iter())
+        .find(|vlr| is_crs_vlr(vlr) && vlr.record_id == OGC_WKT_RECORD_ID)?;
+    let wkt = String::from_utf8_lossy(&vlr.data)
+        .trim_end_matches('\0')
+        .to_owned();
+    Some(wkt)
+}
+
+/// Creates a GeoKeyDirectoryTag VLR for the coordinate reference system with the given `epsg_code`. `is_geographic`
+/// determines whether the EPSG code refers to a geographic (latitude/longitude) or a projected coordinate reference system
+/// ```
+/// # use pasture_io::las::*;
+/// let vlr = las_crs_vlr_from_epsg_code(25832, false);
+/// assert_eq!(vlr.user_id, LAS_PROJECTION_USER_ID);
+/// assert_eq!(vlr.record_id, GEO_KEY_DIRECTORY_RECORD_ID);
+/// ```
+pub fn las_crs_vlr_from_epsg_code(epsg_code: u16, is_geographic: bool) -> Vlr {
+    let (model_type, crs_key) = if is_geographic {
+        (MODEL_TYPE_GEOGRAPHIC, GEOGRAPHIC_TYPE_GEO_KEY)
+    } else {
+        (MODEL_TYPE_PROJECTED, PROJECTED_CS_TYPE_GEO_KEY)
+    };
+    let values: [u16; 12] = [
+        1,
+        1,
+        0,
+        2,
+        GT_MODEL_TYPE_GEO_KEY,
+        0,
+        1,
+        model_type,
+        crs_key,
+        0,
+        1,
+        epsg_code,
+    ];
+    let data = values
+        .iter()
+        .flat_map(|value| value.to_le_bytes().to_vec())
+        .collect::<Vec<_>>();
+
+    Vlr {
+        user_id: LAS_PROJECTION_USER_ID.to_owned(),
+        record_id: GEO_KEY_DIRECTORY_RECORD_ID,
+        description: "GeoKeyDirectoryTag".to_owned(),
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use las::Builder;
+
+    use super::*;
+
+    #[test]
+    fn test_epsg_code_roundtrip() {
+        for (epsg_code, is_geographic) in [(25832, false), (4326, true)].iter() {
+            let mut builder = Builder::from((1, 4));
+            builder
+                .vlrs
+                .push(las_crs_vlr_from_epsg_code(*epsg_code, *is_geographic));
+            let header = builder.into_header().unwrap();
+            assert_eq!(Some(*epsg_code), epsg_code_from_las_header(&header));
+        }
+    }
+
+    #[test]
+    fn test_no_crs_in_header() {
+        let header = Builder::from((1, 4)).into_header().unwrap();
+        assert_eq!(None, epsg_code_from_las_header(&header));
+        assert_eq!(None, wkt_from_las_header(&header));
+    }
+
+    #[test]
+    fn test_wkt_from_las_header() {
+        let wkt = "PROJCS[\"ETRS89 / UTM zone 32N\"]";
+        let mut builder = Builder::from((1, 4));
+        let mut data = wkt.as_bytes().to_vec();
+        data.push(0);
+        builder.vlrs.push(Vlr {
+            user_id: LAS_PROJECTION_USER_ID.to_owned(),
+            record_id: OGC_WKT_RECORD_ID,
+            description: "OGC WKT".to_owned(),
+            data,
+        });
+        let header = builder.into_header().unwrap();
+        assert_eq!(Some(wkt.to_owned()), wkt_from_las_header(&header));
+    }
+}
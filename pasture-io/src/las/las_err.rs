@@ -1,7 +1,7 @@
-use anyhow::anyhow;
+use crate::base::PastureIoError;
 
-/// Maps the internal error type of the laz-rs crate to an `anyhow::Error`. Unfortunately, the laz-rs error type
+/// Maps the internal error type of the laz-rs crate to a `PastureIoError`. Unfortunately, the laz-rs error type
 /// does not implement the `Error` trait :(
-pub(crate) fn map_laz_err(laz_err: laz::LasZipError) -> anyhow::Error {
-    anyhow!("LasZip error: {}", laz_err.to_string())
+pub(crate) fn map_laz_err(laz_err: laz::LasZipError) -> PastureIoError {
+    PastureIoError::Other(format!("LasZip error: {}", laz_err).into())
 }
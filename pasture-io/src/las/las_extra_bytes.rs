@@ -0,0 +1,239 @@
+use std::convert::TryInto;
+
+use las::{Builder, Header, Vlr};
+use pasture_core::layout::PointAttributeDataType;
+
+use crate::base::{PastureIoError, Result};
+
+/// User ID of the Extra Bytes VLR
+pub const EXTRA_BYTES_USER_ID: &str = "LASF_Spec";
+/// Record ID of the Extra Bytes VLR
+pub const EXTRA_BYTES_RECORD_ID: u16 = 4;
+
+const EXTRA_BYTES_DESCRIPTOR_SIZE: usize = 192;
+const EXTRA_BYTES_NAME_SIZE: usize = 32;
+const EXTRA_BYTES_DESCRIPTION_SIZE: usize = 32;
+
+/// Description of a single attribute that is stored in the extra bytes of the point records of a LAS file, as defined
+/// by the Extra Bytes VLR of the LAS 1.4 specification. Only scalar attributes are supported
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtraBytesDescriptor {
+    /// Name of the attribute. When writing, the point attribute with this name is stored in the extra bytes
+    pub name: String,
+    /// Datatype of the attribute
+    pub datatype: PointAttributeDataType,
+    /// Optional description of the attribute
+    pub description: String,
+}
+
+impl ExtraBytesDescriptor {
+    /// Creates a new `ExtraBytesDescriptor` with the given `name` and `datatype` and an empty description
+    pub fn new(name: &str, datatype: PointAttributeDataType) -> Self {
+        Self {
+            name: name.to_owned(),
+            datatype,
+            description: String::new(),
+        }
+    }
+}
+
+fn las_data_type_from_datatype(datatype: PointAttributeDataType) -> Result<u8> {
+    match datatype {
+        PointAttributeDataType::U8 => Ok(1),
+        PointAttributeDataType::I8 => Ok(2),
+        PointAttributeDataType::U16 => Ok(3),
+        PointAttributeDataType::I16 => Ok(4),
+        PointAttributeDataType::U32 => Ok(5),
+        PointAttributeDataType::I32 => Ok(6),
+        PointAttributeDataType::U64 => Ok(7),
+        PointAttributeDataType::I64 => Ok(8),
+        PointAttributeDataType::F32 => Ok(9),
+        PointAttributeDataType::F64 => Ok(10),
+        other => Err(PastureIoError::UnsupportedFormat(format!(
+            "Datatype {} is not supported in LAS extra bytes",
+            other
+        ))),
+    }
+}
+
+fn datatype_from_las_data_type(las_data_type: u8) -> Result<PointAttributeDataType> {
+    match las_data_type {
+        1 => Ok(PointAttributeDataType::U8),
+        2 => Ok(PointAttributeDataType::I8),
+        3 => Ok(PointAttributeDataType::U16),
+        4 => Ok(PointAttributeDataType::I16),
+        5 => Ok(PointAttributeDataType::U32),
+        6 => Ok(PointAttributeDataType::I32),
+        7 => Ok(PointAttributeDataType::U64),
+        8 => Ok(PointAttributeDataType::I64),
+        9 => Ok(PointAttributeDataType::F32),
+        10 => Ok(PointAttributeDataType::F64),
+        other => Err(PastureIoError::UnsupportedFormat(format!(
+            "Extra bytes data type {} is not supported",
+            other
+        ))),
+    }
+}
+
+fn write_fixed_size_string(s: &str, size: usize, data: &mut Vec<u8>) {
+    let bytes = s.as_bytes();
+    let len = bytes.len().min(size);
+    data.extend_from_slice(&bytes[..len]);
+    data.resize(data.len() + size - len, 0);
+}
+
+fn read_fixed_size_string(data: &[u8]) -> String {
+    String::from_utf8_lossy(data)
+        .trim_end_matches('\0')
+        .to_owned()
+}
+
+/// Creates an Extra Bytes VLR for the given `descriptors`
+///
+/// # Errors
+///
+/// If any of the `descriptors` has a datatype that is not supported in LAS extra bytes (i.e. a vector datatype)
+pub fn extra_bytes_vlr(descriptors: &[ExtraBytesDescriptor]) -> Result<Vlr> {
+    let mut data = Vec::with_capacity(descriptors.len() * EXTRA_BYTES_DESCRIPTOR_SIZE);
+    for descriptor in descriptors {
+        // reserved
+        data.extend_from_slice(&[0, 0]);
+        data.push(las_data_type_from_datatype(descriptor.datatype)?);
+        // options: no_data, min, max, scale and offset are all unused
+        data.push(0);
+        write_fixed_size_string(&descriptor.name, EXTRA_BYTES_NAME_SIZE, &mut data);
+        // unused, no_data, min, max, scale, offset
+        data.resize(data.len() + 4 + 5 * 24, 0);
+        write_fixed_size_string(
+            &descriptor.description,
+            EXTRA_BYTES_DESCRIPTION_SIZE,
+            &mut data,
+        );
+    }
+
+    Ok(Vlr {
+        user_id: EXTRA_BYTES_USER_ID.to_owned(),
+        record_id: EXTRA_BYTES_RECORD_ID,
+        description: "Extra Bytes".to_owned(),
+        data,
+    })
+}
+
+/// Returns the descriptors of all attributes in the Extra Bytes VLR of the given `header`. If there is no Extra Bytes
+/// VLR, an empty vector is returned
+///
+/// # Errors
+///
+/// If the Extra Bytes VLR is malformed or contains attributes with unsupported data types (e.g. the deprecated array
+/// types or undocumented extra bytes)
+pub fn extra_bytes_descriptors_from_las_header(
+    header: &Header,
+) -> Result<Vec<ExtraBytesDescriptor>> {
+    let vlr = match header
+        .vlrs()
+        .iter()
+        .chain(header.evlrs().iter())
+        .find(|vlr| vlr.user_id == EXTRA_BYTES_USER_ID && vlr.record_id == EXTRA_BYTES_RECORD_ID)
+    {
+        Some(vlr) => vlr,
+        None => return Ok(vec![]),
+    };
+    if vlr.data.len() % EXTRA_BYTES_DESCRIPTOR_SIZE != 0 {
+        return Err(PastureIoError::InvalidData(format!(
+            "Size of Extra Bytes VLR ({} bytes) is no multiple of {}",
+            vlr.data.len(),
+            EXTRA_BYTES_DESCRIPTOR_SIZE
+        )));
+    }
+
+    vlr.data
+        .chunks_exact(EXTRA_BYTES_DESCRIPTOR_SIZE)
+        .map(|record| {
+            let datatype = datatype_from_las_data_type(record[2])?;
+            let name = read_fixed_size_string(&record[4..4 + EXTRA_BYTES_NAME_SIZE]);
+            let description_start = EXTRA_BYTES_DESCRIPTOR_SIZE - EXTRA_BYTES_DESCRIPTION_SIZE;
+            let description = read_fixed_size_string(&record[description_start..]);
+            Ok(ExtraBytesDescriptor {
+                name,
+                datatype,
+                description,
+            })
+        })
+        .collect()
+}
+
+/// Returns a copy of the given `header` with additional extra bytes for the given `descriptors`. The Extra Bytes VLR
+/// of the new header contains all existing descriptors of `header` followed by `descriptors`, and the point record
+/// length is increased accordingly
+///
+/// ```
+/// # use pasture_io::las::*;
+/// # use pasture_core::layout::PointAttributeDataType;
+/// let header = pasture_io::las_rs::Builder::from((1, 4)).into_header().unwrap();
+/// let descriptor = ExtraBytesDescriptor::new("HeightAboveGround", PointAttributeDataType::F64);
+/// let new_header = add_extra_bytes_to_las_header(&header, &[descriptor.clone()]).unwrap();
+/// assert_eq!(8, new_header.point_format().extra_bytes);
+/// assert_eq!(vec![descriptor], extra_bytes_descriptors_from_las_header(&new_header).unwrap());
+/// ```
+///
+/// # Errors
+///
+/// If the existing Extra Bytes VLR of `header` can't be parsed, or if any of the `descriptors` has an unsupported
+/// datatype
+pub fn add_extra_bytes_to_las_header(
+    header: &Header,
+    descriptors: &[ExtraBytesDescriptor],
+) -> Result<Header> {
+    let mut all_descriptors = extra_bytes_descriptors_from_las_header(header)?;
+    all_descriptors.extend_from_slice(descriptors);
+    let additional_extra_bytes: u64 = descriptors
+        .iter()
+        .map(|descriptor| descriptor.datatype.size())
+        .sum();
+
+    let mut builder = Builder::new(header.clone().into_raw()?)?;
+    builder.point_format.extra_bytes += additional_extra_bytes.try_into()?;
+    builder.vlrs = header
+        .vlrs()
+        .iter()
+        .filter(|vlr| {
+            !(vlr.user_id == EXTRA_BYTES_USER_ID && vlr.record_id == EXTRA_BYTES_RECORD_ID)
+        })
+        .cloned()
+        .collect();
+    builder.vlrs.push(extra_bytes_vlr(&all_descriptors)?);
+    builder.evlrs = header.evlrs().to_vec();
+    Ok(builder.into_header()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extra_bytes_vlr_roundtrip() {
+        let descriptors = vec![
+            ExtraBytesDescriptor::new("HeightAboveGround", PointAttributeDataType::F64),
+            ExtraBytesDescriptor {
+                name: "Confidence".to_owned(),
+                datatype: PointAttributeDataType::U8,
+                description: "Classification confidence".to_owned(),
+            },
+        ];
+        let mut builder = Builder::from((1, 4));
+        builder.point_format.extra_bytes = 9;
+        builder.vlrs.push(extra_bytes_vlr(&descriptors).unwrap());
+        let header = builder.into_header().unwrap();
+
+        assert_eq!(
+            descriptors,
+            extra_bytes_descriptors_from_las_header(&header).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_vector_datatype_is_unsupported() {
+        let descriptor = ExtraBytesDescriptor::new("Normal", PointAttributeDataType::Vec3f32);
+        assert!(extra_bytes_vlr(&[descriptor]).is_err());
+    }
+}
@@ -1,10 +1,11 @@
-use anyhow::{anyhow, Result};
 use las::point::Format;
 use pasture_core::{
     layout::attributes,
     layout::{PointLayout, PointType},
 };
 
+use crate::base::{PastureIoError, Result};
+
 use super::{
     LasPointFormat0, LasPointFormat1, LasPointFormat10, LasPointFormat2, LasPointFormat3,
     LasPointFormat4, LasPointFormat5, LasPointFormat6, LasPointFormat7, LasPointFormat8,
@@ -38,7 +39,10 @@ pub fn point_layout_from_las_point_format(format: &Format) -> Result<PointLayout
         8 => Ok(LasPointFormat8::layout()),
         9 => Ok(LasPointFormat9::layout()),
         10 => Ok(LasPointFormat10::layout()),
-        _ => Err(anyhow!("Unsupported LAS point format {}", format_number)),
+        _ => Err(PastureIoError::UnsupportedFormat(format!(
+            "Unsupported LAS point format {}",
+            format_number
+        ))),
     }
 }
 
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+
+use las_rs::{point::Format, Header};
+use pasture_core::{
+    containers::{PerAttributePointBufferMut, PerAttributeVecPointStorage, PointBufferWriteable},
+    layout::{attributes, PointAttributeDefinition, PointLayout, PrimitiveType},
+    nalgebra::Vector3,
+    util::view_raw_bytes_mut,
+};
+
+use super::point_layout_from_las_point_format;
+use crate::base::{convert_attribute_byte_order, ByteOrder, PastureIoError, Result};
+
+/// How a single attribute is encoded within a raw LAS point record
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum RawAttributeEncoding {
+    /// The position, stored as three `i32` values in local space
+    Position,
+    /// Little-endian value of the attribute's datatype at the given byte offset within the point record
+    Bytes { offset: usize },
+    /// Bit field within the byte at the given offset, extracted as `(byte >> shift) & mask`
+    Bits { offset: usize, shift: u8, mask: u8 },
+}
+
+/// Returns the `RawAttributeEncoding` of the attribute with the given name in LAS point records of the given `format`,
+/// or `None` if the format does not contain this attribute
+fn raw_attribute_encoding(format: &Format, attribute_name: &str) -> Option<RawAttributeEncoding> {
+    let bits = |offset, shift, mask| {
+        Some(RawAttributeEncoding::Bits {
+            offset,
+            shift,
+            mask,
+        })
+    };
+    let bytes = |offset| Some(RawAttributeEncoding::Bytes { offset });
+
+    if attribute_name == attributes::POSITION_3D.name() {
+        return Some(RawAttributeEncoding::Position);
+    }
+    if attribute_name == attributes::INTENSITY.name() {
+        return bytes(12);
+    }
+
+    // Offset of the first attribute after the attributes that all point formats share
+    let mut offset = if format.is_extended {
+        match attribute_name {
+            name if name == attributes::RETURN_NUMBER.name() => return bits(14, 0, 0b1111),
+            name if name == attributes::NUMBER_OF_RETURNS.name() => return bits(14, 4, 0b1111),
+            name if name == attributes::CLASSIFICATION_FLAGS.name() => return bits(15, 0, 0b1111),
+            name if name == attributes::SCANNER_CHANNEL.name() => return bits(15, 4, 0b11),
+            name if name == attributes::SCAN_DIRECTION_FLAG.name() => return bits(15, 6, 0b1),
+            name if name == attributes::EDGE_OF_FLIGHT_LINE.name() => return bits(15, 7, 0b1),
+            name if name == attributes::CLASSIFICATION.name() => return bytes(16),
+            name if name == attributes::USER_DATA.name() => return bytes(17),
+            name if name == attributes::SCAN_ANGLE.name() => return bytes(18),
+            name if name == attributes::POINT_SOURCE_ID.name() => return bytes(20),
+            _ => 22,
+        }
+    } else {
+        match attribute_name {
+            name if name == attributes::RETURN_NUMBER.name() => return bits(14, 0, 0b111),
+            name if name == attributes::NUMBER_OF_RETURNS.name() => return bits(14, 3, 0b111),
+            name if name == attributes::SCAN_DIRECTION_FLAG.name() => return bits(14, 6, 0b1),
+            name if name == attributes::EDGE_OF_FLIGHT_LINE.name() => return bits(14, 7, 0b1),
+            name if name == attributes::CLASSIFICATION.name() => return bytes(15),
+            name if name == attributes::SCAN_ANGLE_RANK.name() => return bytes(16),
+            name if name == attributes::USER_DATA.name() => return bytes(17),
+            name if name == attributes::POINT_SOURCE_ID.name() => return bytes(18),
+            _ => 20,
+        }
+    };
+
+    if format.has_gps_time {
+        if attribute_name == attributes::GPS_TIME.name() {
+            return bytes(offset);
+        }
+        offset += 8;
+    }
+    if format.has_color {
+        if attribute_name == attributes::COLOR_RGB.name() {
+            return bytes(offset);
+        }
+        offset += 6;
+    }
+    if format.has_nir {
+        if attribute_name == attributes::NIR.name() {
+            return bytes(offset);
+        }
+        offset += 2;
+    }
+    if format.has_waveform {
+        match attribute_name {
+            name if name == attributes::WAVE_PACKET_DESCRIPTOR_INDEX.name() => {
+                return bytes(offset)
+            }
+            name if name == attributes::WAVEFORM_DATA_OFFSET.name() => return bytes(offset + 1),
+            name if name == attributes::WAVEFORM_PACKET_SIZE.name() => return bytes(offset + 9),
+            name if name == attributes::RETURN_POINT_WAVEFORM_LOCATION.name() => {
+                return bytes(offset + 13)
+            }
+            name if name == attributes::WAVEFORM_PARAMETERS.name() => return bytes(offset + 17),
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Raw LAS point records whose attributes are decoded lazily, one attribute at a time. Each attribute is decoded the
+/// first time that it is accessed and then cached, so only the attributes that are actually used are ever decoded.
+/// This is much faster than decoding all attributes if only one or two of them are needed, for example when
+/// exploring the classifications or GPS times of a file. Create `LazyLASPoints` using `LASReader::read_lazy`
+///
+/// All attributes are decoded into the datatypes of the default `PointLayout` of the LAS point format, see
+/// `point_layout()`
+pub struct LazyLASPoints {
+    raw_points: Vec<u8>,
+    point_count: usize,
+    size_of_point_in_file: usize,
+    format: Format,
+    format_id: u8,
+    point_layout: PointLayout,
+    point_scales: Vector3<f64>,
+    point_offsets: Vector3<f64>,
+    decoded_attributes: HashMap<String, Vec<u8>>,
+}
+
+impl LazyLASPoints {
+    /// Creates new `LazyLASPoints` from the given raw point records, as read by `LASReader::read_raw_points`, and the
+    /// header of the LAS file that the records were read from
+    ///
+    /// # Errors
+    ///
+    /// If the point format in `header` is not supported, an error is returned
+    ///
+    /// # Panics
+    ///
+    /// If the size of `raw_points` is no multiple of the point record length in `header`
+    pub fn new(raw_points: Vec<u8>, header: &Header) -> Result<Self> {
+        let format = *header.point_format();
+        let size_of_point_in_file = format.len() as usize;
+        if raw_points.len() % size_of_point_in_file != 0 {
+            panic!(
+                "LazyLASPoints::new: Size of raw points ({} bytes) is no multiple of the point record length ({} bytes)",
+                raw_points.len(),
+                size_of_point_in_file
+            );
+        }
+        let transforms = header.transforms();
+        Ok(Self {
+            point_count: raw_points.len() / size_of_point_in_file,
+            raw_points,
+            size_of_point_in_file,
+            point_layout: point_layout_from_las_point_format(&format)?,
+            format_id: format.to_u8()?,
+            format,
+            point_scales: Vector3::new(transforms.x.scale, transforms.y.scale, transforms.z.scale),
+            point_offsets: Vector3::new(
+                transforms.x.offset,
+                transforms.y.offset,
+                transforms.z.offset,
+            ),
+            decoded_attributes: HashMap::new(),
+        })
+    }
+
+    /// Returns the number of points
+    pub fn len(&self) -> usize {
+        self.point_count
+    }
+
+    /// Returns `true` if there are no points
+    pub fn is_empty(&self) -> bool {
+        self.point_count == 0
+    }
+
+    /// Returns the default `PointLayout` of the LAS point format of the points. This contains all attributes that can
+    /// be decoded, in the datatypes that they are decoded into
+    pub fn point_layout(&self) -> &PointLayout {
+        &self.point_layout
+    }
+
+    /// Returns `true` if the given `attribute` has already been decoded
+    pub fn is_decoded(&self, attribute: &PointAttributeDefinition) -> bool {
+        self.decoded_attributes.contains_key(attribute.name())
+    }
+
+    /// Returns the values of the given `attribute` for all points as tightly packed memory in native byte order,
+    /// decoding them first if this attribute is accessed for the first time
+    ///
+    /// # Errors
+    ///
+    /// If `attribute` is not part of `point_layout()`, a `PastureIoError::LayoutMismatch` is returned
+    pub fn attribute_data(&mut self, attribute: &PointAttributeDefinition) -> Result<&[u8]> {
+        if !self.decoded_attributes.contains_key(attribute.name()) {
+            let decoded = self.decode_attribute(attribute)?;
+            self.decoded_attributes
+                .insert(attribute.name().to_owned(), decoded);
+        }
+        Ok(&self.decoded_attributes[attribute.name()])
+    }
+
+    /// Returns the values of the given `attribute` for all points, decoding them first if this attribute is accessed
+    /// for the first time
+    ///
+    /// # Errors
+    ///
+    /// If `attribute` is not part of `point_layout()`, a `PastureIoError::LayoutMismatch` is returned
+    ///
+    /// # Panics
+    ///
+    /// If `T` does not match the datatype of `attribute` in `point_layout()`
+    pub fn attribute_values<T: PrimitiveType + Default>(
+        &mut self,
+        attribute: &PointAttributeDefinition,
+    ) -> Result<Vec<T>> {
+        let datatype = self.layout_attribute(attribute)?.datatype();
+        if T::data_type() != datatype {
+            panic!(
+                "LazyLASPoints::attribute_values: Type {} does not match datatype {} of attribute {}",
+                T::data_type(),
+                datatype,
+                attribute.name()
+            );
+        }
+        let data = self.attribute_data(attribute)?;
+        Ok(data
+            .chunks_exact(datatype.size() as usize)
+            .map(|value_data| {
+                let mut value: T = Default::default();
+                unsafe { view_raw_bytes_mut(&mut value) }.copy_from_slice(value_data);
+                value
+            })
+            .collect())
+    }
+
+    /// Converts these points into a `PerAttributeVecPointStorage` with the default `PointLayout` of the LAS point
+    /// format, decoding all attributes that have not been accessed so far
+    pub fn into_per_attribute_buffer(mut self) -> Result<PerAttributeVecPointStorage> {
+        let point_count = self.point_count;
+        let mut buffer = PerAttributeVecPointStorage::new(self.point_layout.clone());
+        buffer.resize(point_count);
+        let attributes = self
+            .point_layout
+            .attributes()
+            .map(|attribute| attribute.into())
+            .collect::<Vec<PointAttributeDefinition>>();
+        for attribute in attributes {
+            let data = self.attribute_data(&attribute)?;
+            buffer
+                .get_raw_attribute_range_mut(0..point_count, &attribute)
+                .copy_from_slice(data);
+        }
+        Ok(buffer)
+    }
+
+    fn layout_attribute(
+        &self,
+        attribute: &PointAttributeDefinition,
+    ) -> Result<PointAttributeDefinition> {
+        self.point_layout
+            .get_attribute_by_name(attribute.name())
+            .map(|attribute| attribute.into())
+            .ok_or_else(|| {
+                PastureIoError::LayoutMismatch(format!(
+                    "Attribute {} is not part of LAS point format {}",
+                    attribute.name(),
+                    self.format_id
+                ))
+            })
+    }
+
+    fn decode_attribute(&self, attribute: &PointAttributeDefinition) -> Result<Vec<u8>> {
+        let layout_attribute = self.layout_attribute(attribute)?;
+        let encoding = raw_attribute_encoding(&self.format, attribute.name())
+            .expect("No encoding for attribute of the default LAS point layout");
+        let records = self.raw_points.chunks_exact(self.size_of_point_in_file);
+
+        let decoded = match encoding {
+            RawAttributeEncoding::Position => {
+                let mut decoded = Vec::with_capacity(self.point_count * 24);
+                for record in records {
+                    for component in 0..3 {
+                        let start = component * 4;
+                        let mut local = [0; 4];
+                        local.copy_from_slice(&record[start..start + 4]);
+                        let global = i32::from_le_bytes(local) as f64
+                            * self.point_scales[component]
+                            + self.point_offsets[component];
+                        decoded.extend_from_slice(&global.to_ne_bytes());
+                    }
+                }
+                decoded
+            }
+            RawAttributeEncoding::Bytes { offset } => {
+                let size = layout_attribute.size() as usize;
+                let mut decoded = Vec::with_capacity(self.point_count * size);
+                for record in records {
+                    decoded.extend_from_slice(&record[offset..offset + size]);
+                }
+                convert_attribute_byte_order(
+                    &mut decoded,
+                    layout_attribute.datatype(),
+                    ByteOrder::LittleEndian,
+                    ByteOrder::NATIVE,
+                );
+                decoded
+            }
+            RawAttributeEncoding::Bits {
+                offset,
+                shift,
+                mask,
+            } => records
+                .map(|record| (record[offset] >> shift) & mask)
+                .collect(),
+        };
+        Ok(decoded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        base::PointReader,
+        las::{get_test_las_path, get_test_laz_path, LASReader},
+    };
+    use pasture_core::containers::PointBuffer;
+
+    #[test]
+    fn test_lazy_points_match_regular_reading() -> Result<()> {
+        for format in 0..=10 {
+            let mut paths = vec![get_test_las_path(format)];
+            // The LAZ reader does not support waveforms yet
+            if format <= 3 || (6..=8).contains(&format) {
+                paths.push(get_test_laz_path(format));
+            }
+            for path in paths {
+                let mut reader = LASReader::from_path(&path)?;
+                let mut lazy_points = reader.read_lazy(10)?;
+                assert_eq!(10, lazy_points.len());
+
+                let mut reader = LASReader::from_path(&path)?;
+                let expected = reader.read(10)?;
+                let expected = expected
+                    .as_interleaved()
+                    .expect("LASReader::read must return an interleaved buffer");
+
+                assert!(!lazy_points.is_decoded(&attributes::CLASSIFICATION));
+                lazy_points.attribute_data(&attributes::CLASSIFICATION)?;
+                assert!(lazy_points.is_decoded(&attributes::CLASSIFICATION));
+                assert!(!lazy_points.is_decoded(&attributes::POSITION_3D));
+
+                let points = lazy_points.into_per_attribute_buffer()?;
+                assert_eq!(expected.point_layout(), points.point_layout());
+                for attribute in expected.point_layout().attributes() {
+                    let attribute: PointAttributeDefinition = attribute.into();
+                    let size = attribute.size() as usize;
+                    for point_index in 0..10 {
+                        let mut expected_value = vec![0; size];
+                        let mut actual_value = vec![0; size];
+                        expected.get_raw_attribute(point_index, &attribute, &mut expected_value);
+                        points.get_raw_attribute(point_index, &attribute, &mut actual_value);
+                        assert_eq!(
+                            expected_value,
+                            actual_value,
+                            "Attribute {} of point {} differs for format {}",
+                            attribute.name(),
+                            point_index,
+                            format
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_lazy_attribute_values() -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(1))?;
+        let mut lazy_points = reader.read_lazy(10)?;
+        let gps_times = lazy_points.attribute_values::<f64>(&attributes::GPS_TIME)?;
+        assert_eq!(
+            vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0],
+            gps_times
+        );
+        assert!(lazy_points.attribute_data(&attributes::NIR).is_err());
+        Ok(())
+    }
+}
@@ -1,11 +1,13 @@
 use std::{any::Any, convert::TryInto, fmt::Display, path::Path};
 
-use anyhow::{anyhow, Result};
 use chrono::Datelike;
 use las::{Bounds, Header};
 use las_rs::{Vector, Vlr};
 use pasture_core::{math::AABB, meta::Metadata, nalgebra::Point3};
 
+use super::processing_history_from_las_header;
+use crate::base::{PastureIoError, Result};
+
 /// Contains constants for possible named fields in a `LASMetadata` structure
 pub mod named_fields {
     /// File source ID as per the LAS 1.4 specification
@@ -20,6 +22,8 @@ pub mod named_fields {
     pub const FILE_CREATION_DAY_OF_YEAR: &'static str = "LASFIELD_FileCreationDayOfYear";
     /// Year in which the file was created
     pub const FILE_CREATION_YEAR: &'static str = "LASFIELD_FileCreationYear";
+    /// Processing history (a `ProcessingHistory`) from the processing history VLR
+    pub const PROCESSING_HISTORY: &'static str = "LASFIELD_ProcessingHistory";
 
     //TODO More fields
 }
@@ -52,10 +56,12 @@ pub fn path_is_compressed_las_file<P: AsRef<Path>>(path: P) -> Result<bool> {
     path.as_ref()
         .extension()
         .map(|extension| extension == "laz")
-        .ok_or(anyhow!(
-            "Could not determine file extension of file {}",
-            path.as_ref().display()
-        ))
+        .ok_or_else(|| {
+            PastureIoError::UnsupportedFormat(format!(
+                "Could not determine file extension of file {}",
+                path.as_ref().display()
+            ))
+        })
 }
 
 fn display_vlr(vlr: &Vlr, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -217,6 +223,15 @@ impl Display for LASMetadata {
                     display_vlr(evlr, f)?;
                 }
             }
+
+            match processing_history_from_las_header(las_header) {
+                Ok(history) if history.is_empty() => (),
+                Ok(history) => {
+                    writeln!(f, "Processing history")?;
+                    write!(f, "{}", history)?;
+                }
+                Err(_) => writeln!(f, "Invalid processing history")?,
+            }
         }
 
         Ok(())
@@ -267,6 +282,12 @@ impl Metadata for LASMetadata {
                 .raw_las_header
                 .as_ref()
                 .map(|header| -> Box<dyn Any> { Box::new(header.version().to_string()) }),
+            named_fields::PROCESSING_HISTORY => self
+                .raw_las_header
+                .as_ref()
+                .and_then(|header| processing_history_from_las_header(header).ok())
+                .filter(|history| !history.is_empty())
+                .map(|history| -> Box<dyn Any> { Box::new(history) }),
             _ => None,
         }
     }
@@ -0,0 +1,622 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{channel, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use las_rs::Header;
+use laz::{
+    decoders::ArithmeticDecoder, decompressors::IntegerDecompressorBuilder, las::laszip::LazVlr,
+};
+use pasture_core::{
+    containers::{
+        InterleavedPointBuffer, InterleavedPointView, InterleavedVecPointStorage, PointBuffer,
+        PointBufferWriteable,
+    },
+    layout::PointLayout,
+    meta::Metadata,
+};
+
+use super::{is_laszip_vlr, map_laz_err, read_raw_las_header, LASReader};
+use crate::base::{Capabilities, PastureIoError, PointReader, Result, SeekToPoint};
+
+/// Number of points per task for uncompressed LAS files, which is the default chunk size of LAZ
+pub const DEFAULT_POINTS_PER_TASK: usize = 50_000;
+
+/// Reads the number of points in each chunk of a LAZ file from its chunk table. Files with fixed-size chunks only
+/// store the number of chunks, files with variable-size chunks store the number of points and bytes of each chunk
+fn read_chunk_point_counts<R: Read + Seek>(mut read: R, vlr: &LazVlr) -> Result<Vec<u64>> {
+    let offset_to_point_data = read_raw_las_header(&mut read)?.offset_to_point_data as i64;
+    read.seek(SeekFrom::Start(offset_to_point_data as u64))?;
+    let mut chunk_table_offset = read.read_i64::<LittleEndian>()?;
+    // Writers that can't seek back store -1 and write the offset to the last 8 bytes of the file instead
+    if chunk_table_offset == -1 {
+        read.seek(SeekFrom::End(-8))?;
+        chunk_table_offset = read.read_i64::<LittleEndian>()?;
+    }
+    if chunk_table_offset <= offset_to_point_data {
+        return Err(PastureIoError::InvalidData(format!(
+            "Invalid offset {} of the LAZ chunk table",
+            chunk_table_offset
+        )));
+    }
+    read.seek(SeekFrom::Start(chunk_table_offset as u64))?;
+    let _version = read.read_u32::<LittleEndian>()?;
+    let number_of_chunks = read.read_u32::<LittleEndian>()? as usize;
+    // Files with variable-size chunks store u32::MAX as their chunk size
+    if vlr.chunk_size() != u32::MAX {
+        return Ok(vec![vlr.chunk_size() as u64; number_of_chunks]);
+    }
+
+    // The point and byte counts are compressed as the differences to the counts of the previous chunk, the byte
+    // counts have to be decoded as well to keep the decoder in sync
+    let mut decoder = ArithmeticDecoder::new(&mut read);
+    decoder.read_init_bytes()?;
+    let mut decompressor = IntegerDecompressorBuilder::new()
+        .bits(32)
+        .contexts(2)
+        .build_initialized();
+    let mut point_counts = Vec::with_capacity(number_of_chunks);
+    let mut previous_point_count = 0;
+    let mut previous_byte_count = 0;
+    for _ in 0..number_of_chunks {
+        previous_point_count = decompressor.decompress(&mut decoder, previous_point_count, 0)?;
+        previous_byte_count = decompressor.decompress(&mut decoder, previous_byte_count, 1)?;
+        point_counts.push(previous_point_count as u32 as u64);
+    }
+    Ok(point_counts)
+}
+
+/// Returns the first point of each task for tasks with the given numbers of points, followed by `point_count`. Tasks
+/// after `point_count` are dropped, and the last task ends at `point_count`
+fn task_starts_from_counts(counts: impl Iterator<Item = u64>, point_count: usize) -> Vec<usize> {
+    let mut task_starts = vec![0];
+    if point_count == 0 {
+        return task_starts;
+    }
+    let mut next_start = 0;
+    // Empty chunks would be empty tasks, so they are skipped
+    for count in counts.filter(|count| *count > 0) {
+        next_start += count as usize;
+        if next_start >= point_count {
+            break;
+        }
+        task_starts.push(next_start);
+    }
+    task_starts.push(point_count);
+    task_starts
+}
+
+/// Returns the task starts for tasks of `points_per_task` points each (see `task_starts_from_counts`)
+fn uniform_task_starts(points_per_task: usize, point_count: usize) -> Vec<usize> {
+    let number_of_tasks = (point_count + points_per_task - 1) / points_per_task;
+    task_starts_from_counts(
+        std::iter::repeat(points_per_task as u64).take(number_of_tasks),
+        point_count,
+    )
+}
+
+/// A range of points that a worker thread of a `ParallelLAZReader` decompresses. `generation` identifies the seek
+/// operation after which the task was scheduled, so that results from before a seek can be discarded
+struct Task {
+    generation: u64,
+    index: usize,
+    first_point: usize,
+    count: usize,
+    layout: PointLayout,
+}
+
+struct TaskResult {
+    generation: u64,
+    index: usize,
+    points: Result<InterleavedVecPointStorage>,
+}
+
+fn run_task(reader: &mut LASReader, task: &Task) -> Result<InterleavedVecPointStorage> {
+    reader.seek_point(SeekFrom::Start(task.first_point as u64))?;
+    let mut points = InterleavedVecPointStorage::with_capacity(task.count, task.layout.clone());
+    reader.read_into(&mut points, task.count)?;
+    Ok(points)
+}
+
+/// Body of a worker thread of a `ParallelLAZReader`. Each worker has its own reader for the file, so that the chunks
+/// of the file are decompressed independently. Stops once the `ParallelLAZReader` is dropped
+fn decompress_tasks(path: PathBuf, tasks: Arc<Mutex<Receiver<Task>>>, results: Sender<TaskResult>) {
+    let mut reader = LASReader::from_path(&path);
+    loop {
+        // The lock is only held while waiting for the next task, not while decompressing it
+        let task = match tasks.lock().unwrap().recv() {
+            Ok(task) => task,
+            Err(_) => return,
+        };
+        let points = match &mut reader {
+            Ok(reader) => run_task(reader, &task),
+            Err(e) => Err(PastureIoError::Other(
+                format!("Could not open {}: {}", path.display(), e).into(),
+            )),
+        };
+        let result = TaskResult {
+            generation: task.generation,
+            index: task.index,
+            points,
+        };
+        if results.send(result).is_err() {
+            return;
+        }
+    }
+}
+
+/// Reader for LAZ files that decompresses multiple chunks of the file in parallel on a pool of worker threads. The
+/// chunks of a LAZ file are compressed independently of each other, so reading scales with the number of cores. Each
+/// worker decompresses one chunk at a time, the sizes of the chunks are read from the chunk table of the file. The
+/// decompressed chunks are returned in the order of the file, and at most a fixed number of chunks is decompressed
+/// ahead of the current point, which bounds the memory usage if the points are processed slower than they are
+/// decompressed (see [with_max_chunks_in_flight](ParallelLAZReader::with_max_chunks_in_flight))
+///
+/// Reading uncompressed LAS files works as well, but rarely benefits from multiple threads
+///
+/// ```no_run
+/// # use pasture_io::base::PointReader;
+/// # use pasture_io::las::ParallelLAZReader;
+/// let mut reader = ParallelLAZReader::from_path("in.laz", 8).unwrap();
+/// let points = reader.read(1_000_000).unwrap();
+/// ```
+pub struct ParallelLAZReader {
+    reader: LASReader<'static>,
+    point_count: usize,
+    /// First point of each task, followed by `point_count`
+    task_starts: Vec<usize>,
+    max_chunks_in_flight: usize,
+    tasks: Sender<Task>,
+    results: Receiver<TaskResult>,
+    generation: u64,
+    layout: PointLayout,
+    current_point_index: usize,
+    next_task_to_schedule: usize,
+    finished_tasks: BTreeMap<usize, InterleavedVecPointStorage>,
+}
+
+impl ParallelLAZReader {
+    /// Creates a new `ParallelLAZReader` for the LAS/LAZ file at the given `path`, which decompresses the file with
+    /// `num_threads` worker threads. Each worker decompresses one LAZ chunk at a time, and up to two chunks per worker
+    /// are decompressed ahead of the current point
+    ///
+    /// # Errors
+    ///
+    /// If `path` does not exist, cannot be opened or does not point to a valid LAS/LAZ file, if the chunk table of a
+    /// LAZ file can't be read, or if the worker threads can't be started, an error is returned
+    ///
+    /// # Panics
+    ///
+    /// If `num_threads` is zero
+    pub fn from_path<P: AsRef<Path>>(path: P, num_threads: usize) -> Result<Self> {
+        if num_threads == 0 {
+            panic!("ParallelLAZReader::from_path: num_threads must be > 0");
+        }
+        let reader = LASReader::from_path(path.as_ref())?;
+        let point_count = reader.header().number_of_points() as usize;
+        let task_starts = match reader
+            .header()
+            .vlrs()
+            .iter()
+            .find(|vlr| is_laszip_vlr(*vlr))
+        {
+            Some(vlr) => {
+                let vlr = LazVlr::from_buffer(&vlr.data).map_err(map_laz_err)?;
+                let file = BufReader::new(File::open(path.as_ref()).map_err(PastureIoError::Io)?);
+                let chunk_point_counts = read_chunk_point_counts(file, &vlr)?;
+                task_starts_from_counts(chunk_point_counts.into_iter(), point_count)
+            }
+            None => uniform_task_starts(DEFAULT_POINTS_PER_TASK, point_count),
+        };
+
+        let (task_sender, task_receiver) = channel();
+        let (result_sender, result_receiver) = channel();
+        let task_receiver = Arc::new(Mutex::new(task_receiver));
+        for _ in 0..num_threads {
+            let path = path.as_ref().to_owned();
+            let tasks = task_receiver.clone();
+            let results = result_sender.clone();
+            thread::Builder::new()
+                .name("pasture-laz-decompression".into())
+                .spawn(move || decompress_tasks(path, tasks, results))
+                .map_err(PastureIoError::Io)?;
+        }
+
+        Ok(Self {
+            point_count,
+            layout: reader.get_default_point_layout().clone(),
+            reader,
+            task_starts,
+            max_chunks_in_flight: 2 * num_threads,
+            tasks: task_sender,
+            results: result_receiver,
+            generation: 0,
+            current_point_index: 0,
+            next_task_to_schedule: 0,
+            finished_tasks: BTreeMap::new(),
+        })
+    }
+
+    /// Sets the maximum number of chunks that are decompressed ahead of the current point. Larger values keep the
+    /// worker threads busy if the time to decompress a chunk varies, but need more memory
+    ///
+    /// # Panics
+    ///
+    /// If `max_chunks_in_flight` is zero
+    pub fn with_max_chunks_in_flight(mut self, max_chunks_in_flight: usize) -> Self {
+        if max_chunks_in_flight == 0 {
+            panic!(
+                "ParallelLAZReader::with_max_chunks_in_flight: max_chunks_in_flight must be > 0"
+            );
+        }
+        self.max_chunks_in_flight = max_chunks_in_flight;
+        self
+    }
+
+    /// Sets the number of points that a worker thread decompresses at once. By default, each task is one LAZ chunk of
+    /// the file, as given by its chunk table. Other values work as well, but the workers have to decompress the chunks
+    /// that overlap the boundaries of the tasks twice
+    ///
+    /// # Panics
+    ///
+    /// If `points_per_task` is zero
+    pub fn with_points_per_task(mut self, points_per_task: usize) -> Self {
+        if points_per_task == 0 {
+            panic!("ParallelLAZReader::with_points_per_task: points_per_task must be > 0");
+        }
+        self.task_starts = uniform_task_starts(points_per_task, self.point_count);
+        self.restart();
+        self
+    }
+
+    /// Returns the LAS header of the file
+    pub fn header(&self) -> &Header {
+        self.reader.header()
+    }
+
+    /// Returns the number of remaining points that can be read
+    pub fn remaining_points(&self) -> usize {
+        self.point_count - self.current_point_index
+    }
+
+    /// Returns the index of the task that contains the point with the given `index`, or the number of tasks if `index`
+    /// is the end of the file
+    fn task_of_point(&self, index: usize) -> usize {
+        match self.task_starts.binary_search(&index) {
+            // The end of the file might also be the start of an empty task
+            Ok(task) => task.min(self.task_starts.len() - 1),
+            Err(next_task) => next_task - 1,
+        }
+    }
+
+    /// Discards all decompressed and scheduled chunks, so that decompression restarts at the current point
+    fn restart(&mut self) {
+        self.generation += 1;
+        self.finished_tasks.clear();
+        self.next_task_to_schedule = self.task_of_point(self.current_point_index);
+    }
+
+    /// Schedules the tasks up to `max_chunks_in_flight` tasks after the task of the current point
+    fn schedule_tasks(&mut self) {
+        let number_of_tasks = self.task_starts.len() - 1;
+        let current_task = self.task_of_point(self.current_point_index);
+        let last_task = number_of_tasks.min(current_task + self.max_chunks_in_flight);
+        while self.next_task_to_schedule < last_task {
+            let first_point = self.task_starts[self.next_task_to_schedule];
+            let task = Task {
+                generation: self.generation,
+                index: self.next_task_to_schedule,
+                first_point,
+                count: self.task_starts[self.next_task_to_schedule + 1] - first_point,
+                layout: self.layout.clone(),
+            };
+            // Sending only fails if all workers have stopped, which is reported when receiving the results
+            let _ = self.tasks.send(task);
+            self.next_task_to_schedule += 1;
+        }
+    }
+
+    /// Waits until the task with the given `index` of the current generation is finished
+    fn wait_for_task(&mut self, index: usize) -> Result<()> {
+        while !self.finished_tasks.contains_key(&index) {
+            let result = self.results.recv().map_err(|_| {
+                PastureIoError::Other("All LAZ decompression threads have stopped".into())
+            })?;
+            if result.generation != self.generation || result.index < index {
+                continue;
+            }
+            match result.points {
+                Ok(points) => {
+                    self.finished_tasks.insert(result.index, points);
+                }
+                Err(e) => {
+                    // The failed task is scheduled again on the next read
+                    self.restart();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl PointReader for ParallelLAZReader {
+    fn read(&mut self, count: usize) -> Result<Box<dyn PointBuffer>> {
+        let count = count.min(self.remaining_points());
+        let mut buffer = InterleavedVecPointStorage::with_capacity(
+            count,
+            self.get_default_point_layout().clone(),
+        );
+        self.read_into(&mut buffer, count)?;
+        Ok(Box::new(buffer))
+    }
+
+    fn read_into(
+        &mut self,
+        point_buffer: &mut dyn PointBufferWriteable,
+        count: usize,
+    ) -> Result<usize> {
+        // The workers decompress into the layout of the buffer, so chunks with another layout can't be used
+        if *point_buffer.point_layout() != self.layout {
+            self.layout = point_buffer.point_layout().clone();
+            self.restart();
+        }
+
+        let num_points_to_read = count.min(self.remaining_points());
+        let mut points_read = 0;
+        while points_read < num_points_to_read {
+            self.schedule_tasks();
+            let task_index = self.task_of_point(self.current_point_index);
+            self.wait_for_task(task_index)?;
+
+            let chunk = &self.finished_tasks[&task_index];
+            let offset_in_chunk = self.current_point_index - self.task_starts[task_index];
+            let count_from_chunk =
+                (chunk.len() - offset_in_chunk).min(num_points_to_read - points_read);
+            point_buffer.push(&InterleavedPointView::from_raw_slice(
+                chunk.get_raw_points_ref(offset_in_chunk..offset_in_chunk + count_from_chunk),
+                self.layout.clone(),
+            ));
+            if offset_in_chunk + count_from_chunk == chunk.len() {
+                self.finished_tasks.remove(&task_index);
+            }
+            self.current_point_index += count_from_chunk;
+            points_read += count_from_chunk;
+        }
+        Ok(points_read)
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        self.reader.get_metadata()
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        self.reader.get_default_point_layout()
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            seekable: true,
+            ..Capabilities::new(self.get_default_point_layout())
+        }
+    }
+}
+
+impl SeekToPoint for ParallelLAZReader {
+    fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
+        let new_position = match position {
+            SeekFrom::Start(from_start) => from_start as i64,
+            SeekFrom::End(from_end) => self.point_count as i64 + from_end,
+            SeekFrom::Current(from_current) => self.current_point_index as i64 + from_current,
+        };
+        if new_position < 0 {
+            panic!("ParallelLAZReader::seek_point: It is an error to seek to a point position smaller than zero!");
+        }
+        let new_position = (new_position as usize).min(self.point_count);
+
+        // Seeking forward within the scheduled chunks keeps them, all other seeks restart the decompression
+        let current_task = self.task_of_point(self.current_point_index);
+        let new_task = self.task_of_point(new_position);
+        self.current_point_index = new_position;
+        if new_task < current_task || new_task >= self.next_task_to_schedule {
+            self.restart();
+        } else {
+            self.finished_tasks = self.finished_tasks.split_off(&new_task);
+        }
+        Ok(self.current_point_index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::las::{get_test_laz_path, test_data_point_count};
+    use las_rs::{raw, Vlr};
+    use laz::{las::laszip::LazVlrBuilder, LasZipCompressor, LazItemRecordBuilder};
+    use pasture_core::layout::attributes::{INTENSITY, POSITION_3D};
+    use scopeguard::defer;
+    use std::io::{Cursor, Write};
+
+    /// Size of the point records of the test files with point format 1
+    const RECORD_LENGTH: usize = 28;
+
+    /// Writes the points of the test LAZ file with point format 1 to a new LAZ file at `path`, with LAZ chunks of the
+    /// given sizes. All chunks except the last must have the same size if `variable_size` is false
+    fn write_test_laz_with_chunks(
+        path: &Path,
+        chunk_sizes: &[usize],
+        variable_size: bool,
+    ) -> Result<()> {
+        let input = std::fs::read(get_test_laz_path(1))?;
+        let mut records = vec![0; test_data_point_count() * RECORD_LENGTH];
+        LASReader::from_path(get_test_laz_path(1))?
+            .read_raw_points(&mut records, test_data_point_count())?;
+
+        let items = LazItemRecordBuilder::default_for_point_format_id(1, 0).map_err(map_laz_err)?;
+        let vlr = if variable_size {
+            LazVlrBuilder::new(items).with_variable_chunk_size().build()
+        } else {
+            LazVlrBuilder::new(items)
+                .with_fixed_chunk_size(chunk_sizes[0] as u32)
+                .build()
+        };
+        let mut vlr_data = Cursor::new(vec![]);
+        vlr.write_to(&mut vlr_data)?;
+        let vlr_data = vlr_data.into_inner();
+
+        // Copy the header and the VLRs, with the new chunk size in the LASzip VLR
+        let mut cursor = Cursor::new(&input);
+        let header = read_raw_las_header(&mut cursor)?;
+        let mut output = input[..header.offset_to_point_data as usize].to_vec();
+        cursor.set_position(header.header_size as u64);
+        for _ in 0..header.number_of_variable_length_records {
+            let raw_vlr = raw::Vlr::read_from(&mut cursor, false)?;
+            let data_end = cursor.position() as usize;
+            if is_laszip_vlr(&Vlr::new(raw_vlr.clone())) {
+                assert_eq!(raw_vlr.data.len(), vlr_data.len());
+                output[data_end - vlr_data.len()..data_end].copy_from_slice(&vlr_data);
+            }
+        }
+
+        let mut output = Cursor::new(output);
+        output.set_position(header.offset_to_point_data as u64);
+        {
+            let mut compressor = LasZipCompressor::new(&mut output, vlr).map_err(map_laz_err)?;
+            let mut first_point = 0;
+            for chunk_size in chunk_sizes {
+                let chunk = &records
+                    [first_point * RECORD_LENGTH..(first_point + chunk_size) * RECORD_LENGTH];
+                compressor.compress_many(chunk)?;
+                if variable_size {
+                    compressor.finish_current_chunk()?;
+                }
+                first_point += chunk_size;
+            }
+            assert_eq!(test_data_point_count(), first_point);
+            compressor.done()?;
+        }
+        File::create(path)?.write_all(output.get_ref())?;
+        Ok(())
+    }
+
+    /// Reads the file at `path` with a `ParallelLAZReader` in small steps, which cross the chunk boundaries, and with
+    /// seeks, and compares the points to the points of a sequential reader
+    fn check_parallel_reader_with_chunks(
+        path: &Path,
+        expected_task_starts: &[usize],
+    ) -> Result<()> {
+        let expected = raw_points(LASReader::from_path(path)?.read(10)?.as_ref());
+        let mut reader = ParallelLAZReader::from_path(path, 2)?;
+        assert_eq!(expected_task_starts, &reader.task_starts[..]);
+
+        let mut actual = vec![];
+        for _ in 0..4 {
+            actual.extend(raw_points(reader.read(3)?.as_ref()));
+        }
+        assert_eq!(expected, actual);
+        assert_eq!(0, reader.remaining_points());
+
+        for position in &[9, 2, 4, 3] {
+            reader.seek_point(SeekFrom::Start(*position))?;
+            let start = *position as usize * RECORD_LENGTH;
+            assert_eq!(
+                &expected[start..],
+                &raw_points(reader.read(10)?.as_ref())[..]
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_laz_reader_with_multiple_chunks() -> Result<()> {
+        let path =
+            std::env::temp_dir().join("pasture_test_parallel_laz_reader_with_multiple_chunks.laz");
+        defer! {
+            std::fs::remove_file(&path).expect("Removing test file failed!");
+        }
+        write_test_laz_with_chunks(&path, &[4, 4, 2], false)?;
+        check_parallel_reader_with_chunks(&path, &[0, 4, 8, 10])
+    }
+
+    #[test]
+    fn test_parallel_laz_reader_with_variable_size_chunks() -> Result<()> {
+        let path = std::env::temp_dir()
+            .join("pasture_test_parallel_laz_reader_with_variable_size_chunks.laz");
+        defer! {
+            std::fs::remove_file(&path).expect("Removing test file failed!");
+        }
+        write_test_laz_with_chunks(&path, &[3, 5, 1, 1], true)?;
+        check_parallel_reader_with_chunks(&path, &[0, 3, 8, 9, 10])
+    }
+
+    #[test]
+    fn test_task_starts_from_counts() {
+        assert_eq!(
+            vec![0, 3, 8, 10],
+            task_starts_from_counts(vec![3, 0, 5, 50].into_iter(), 10)
+        );
+        assert_eq!(vec![0, 4, 8, 10], uniform_task_starts(4, 10));
+        assert_eq!(vec![0], uniform_task_starts(4, 0));
+    }
+
+    fn raw_points(points: &dyn PointBuffer) -> Vec<u8> {
+        let mut data = vec![0; points.len() * points.point_layout().size_of_point_entry() as usize];
+        points.get_raw_points(0..points.len(), &mut data);
+        data
+    }
+
+    #[test]
+    fn test_parallel_laz_reader_matches_sequential_reader() -> Result<()> {
+        for format in 0..=3 {
+            let path = get_test_laz_path(format);
+            let expected = LASReader::from_path(&path)?.read(10)?;
+            let point_size = expected.point_layout().size_of_point_entry() as usize;
+            let expected = raw_points(expected.as_ref());
+
+            let mut reader = ParallelLAZReader::from_path(&path, 2)?
+                .with_points_per_task(3)
+                .with_max_chunks_in_flight(2);
+            let mut actual = vec![];
+            for _ in 0..3 {
+                actual.extend(raw_points(reader.read(4)?.as_ref()));
+            }
+            assert_eq!(expected, actual);
+            assert_eq!(0, reader.remaining_points());
+
+            reader.seek_point(SeekFrom::Start(7))?;
+            assert_eq!(
+                &expected[7 * point_size..],
+                &raw_points(reader.read(10)?.as_ref())[..]
+            );
+            reader.seek_point(SeekFrom::Start(1))?;
+            reader.seek_point(SeekFrom::Current(4))?;
+            assert_eq!(
+                &expected[5 * point_size..6 * point_size],
+                &raw_points(reader.read(1)?.as_ref())[..]
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_laz_reader_with_custom_layout() -> Result<()> {
+        let layout = PointLayout::from_attributes(&[INTENSITY, POSITION_3D]);
+        let path = get_test_laz_path(0);
+        let mut expected = InterleavedVecPointStorage::new(layout.clone());
+        LASReader::from_path(&path)?.read_into(&mut expected, 10)?;
+
+        let mut reader = ParallelLAZReader::from_path(&path, 3)?.with_points_per_task(4);
+        let mut actual = InterleavedVecPointStorage::new(layout);
+        assert_eq!(10, reader.read_into(&mut actual, 20)?);
+        assert_eq!(raw_points(&expected), raw_points(&actual));
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use las::{Builder, Header, Vlr};
+
+use crate::base::{PastureIoError, ProcessingHistory, Result};
+
+/// User ID of the VLR that contains the `ProcessingHistory` of a LAS file
+pub const PROCESSING_HISTORY_USER_ID: &str = "pasture";
+/// Record ID of the VLR that contains the `ProcessingHistory` of a LAS file
+pub const PROCESSING_HISTORY_RECORD_ID: u16 = 1;
+
+/// Returns `true` if the given `vlr` contains a `ProcessingHistory`
+pub fn is_processing_history_vlr(vlr: &Vlr) -> bool {
+    vlr.user_id == PROCESSING_HISTORY_USER_ID && vlr.record_id == PROCESSING_HISTORY_RECORD_ID
+}
+
+/// Returns the `ProcessingHistory` that is stored in the VLRs or extended VLRs of the given `header`. If there is no
+/// processing history VLR, an empty history is returned
+///
+/// # Errors
+///
+/// If the processing history VLR is malformed
+pub fn processing_history_from_las_header(header: &Header) -> Result<ProcessingHistory> {
+    match header
+        .vlrs()
+        .iter()
+        .chain(header.evlrs().iter())
+        .find(|vlr| is_processing_history_vlr(vlr))
+    {
+        Some(vlr) => ProcessingHistory::from_json(&vlr.data),
+        None => Ok(ProcessingHistory::new()),
+    }
+}
+
+/// Creates a VLR that contains the given `history`
+/// ```
+/// # use pasture_io::base::*;
+/// # use pasture_io::las::*;
+/// let mut history = ProcessingHistory::new();
+/// history.push(ProcessingStep::start("filters.head", "0.1.0").finish());
+/// let vlr = las_processing_history_vlr(&history).unwrap();
+/// assert!(is_processing_history_vlr(&vlr));
+/// ```
+///
+/// # Errors
+///
+/// If the serialized history does not fit into a VLR, which holds at most 65535 bytes
+pub fn las_processing_history_vlr(history: &ProcessingHistory) -> Result<Vlr> {
+    let data = history.to_json()?;
+    if data.len() > u16::MAX as usize {
+        return Err(PastureIoError::InvalidArgument(format!(
+            "Processing history with {} steps is too large for a VLR ({} bytes)",
+            history.steps().len(),
+            data.len()
+        )));
+    }
+    Ok(Vlr {
+        user_id: PROCESSING_HISTORY_USER_ID.to_owned(),
+        record_id: PROCESSING_HISTORY_RECORD_ID,
+        description: "Processing history".to_owned(),
+        data,
+    })
+}
+
+/// Stores the given `history` in the VLRs of the LAS file that is built by `builder`. An existing processing history
+/// VLR is replaced
+///
+/// # Errors
+///
+/// If the serialized history does not fit into a VLR
+pub fn set_processing_history_in_las_builder(
+    builder: &mut Builder,
+    history: &ProcessingHistory,
+) -> Result<()> {
+    let vlr = las_processing_history_vlr(history)?;
+    builder.vlrs.retain(|vlr| !is_processing_history_vlr(vlr));
+    builder.evlrs.retain(|vlr| !is_processing_history_vlr(vlr));
+    builder.vlrs.push(vlr);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::base::ProcessingStep;
+
+    use super::*;
+
+    #[test]
+    fn test_processing_history_roundtrip() -> Result<()> {
+        let header = Builder::from((1, 4)).into_header()?;
+        assert!(processing_history_from_las_header(&header)?.is_empty());
+
+        let mut history = ProcessingHistory::new();
+        history.push(
+            ProcessingStep::start("filters.crop", "0.1.0")
+                .with_parameter("bounds", "([0, 1], [0, 1])")
+                .finish(),
+        );
+        let mut builder = Builder::from((1, 4));
+        set_processing_history_in_las_builder(&mut builder, &ProcessingHistory::new())?;
+        set_processing_history_in_las_builder(&mut builder, &history)?;
+        assert_eq!(1, builder.vlrs.len());
+
+        let header = builder.into_header()?;
+        assert_eq!(history, processing_history_from_las_header(&header)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_malformed_processing_history_vlr() -> Result<()> {
+        let mut builder = Builder::from((1, 4));
+        builder.vlrs.push(Vlr {
+            user_id: PROCESSING_HISTORY_USER_ID.to_owned(),
+            record_id: PROCESSING_HISTORY_RECORD_ID,
+            description: "Processing history".to_owned(),
+            data: b"not a processing history".to_vec(),
+        });
+        let header = builder.into_header()?;
+        assert!(matches!(
+            processing_history_from_las_header(&header),
+            Err(PastureIoError::InvalidData(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,283 @@
+//! Contains types that match the binary layout of the point records of each of the LAS point formats
+
+use las::point::Format;
+use pasture_core::{
+    layout::{PointAttributeDataType, PointAttributeDefinition, PointLayout, PointType},
+    nalgebra::Vector3,
+};
+use pasture_derive::PointType;
+use static_assertions::const_assert_eq;
+
+use crate::base::{PastureIoError, Result};
+
+/// Attribute for the X coordinate of a LAS point record, as stored in the file (i.e. before applying the scale and offset)
+pub const LAS_RAW_X: PointAttributeDefinition =
+    PointAttributeDefinition::custom("LASRawX", PointAttributeDataType::I32);
+/// Attribute for the Y coordinate of a LAS point record, as stored in the file (i.e. before applying the scale and offset)
+pub const LAS_RAW_Y: PointAttributeDefinition =
+    PointAttributeDefinition::custom("LASRawY", PointAttributeDataType::I32);
+/// Attribute for the Z coordinate of a LAS point record, as stored in the file (i.e. before applying the scale and offset)
+pub const LAS_RAW_Z: PointAttributeDefinition =
+    PointAttributeDefinition::custom("LASRawZ", PointAttributeDataType::I32);
+/// Attribute for the byte with the return number, number of returns, scan direction flag and edge of flight line of
+/// the LAS point formats 0 to 5
+pub const LAS_BIT_FIELDS: PointAttributeDefinition =
+    PointAttributeDefinition::custom("LASBitFields", PointAttributeDataType::U8);
+/// Attribute for the two bytes with the return number, number of returns, classification flags, scanner channel, scan
+/// direction flag and edge of flight line of the LAS point formats 6 to 10
+pub const LAS_EXTENDED_BIT_FIELDS: PointAttributeDefinition =
+    PointAttributeDefinition::custom("LASExtendedBitFields", PointAttributeDataType::U16);
+
+/// Point type that matches the binary layout of the point records of LAS point format 0 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat0 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASBitFields")] pub bit_fields: u8,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE_RANK)] pub scan_angle_rank: i8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat0>(), 20);
+
+/// Point type that matches the binary layout of the point records of LAS point format 1 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat1 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASBitFields")] pub bit_fields: u8,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE_RANK)] pub scan_angle_rank: i8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+    #[pasture(BUILTIN_GPS_TIME)] pub gps_time: f64,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat1>(), 28);
+
+/// Point type that matches the binary layout of the point records of LAS point format 2 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat2 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASBitFields")] pub bit_fields: u8,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE_RANK)] pub scan_angle_rank: i8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+    #[pasture(BUILTIN_COLOR_RGB)] pub color_rgb: Vector3<u16>,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat2>(), 26);
+
+/// Point type that matches the binary layout of the point records of LAS point format 3 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat3 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASBitFields")] pub bit_fields: u8,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE_RANK)] pub scan_angle_rank: i8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+    #[pasture(BUILTIN_GPS_TIME)] pub gps_time: f64,
+    #[pasture(BUILTIN_COLOR_RGB)] pub color_rgb: Vector3<u16>,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat3>(), 34);
+
+/// Point type that matches the binary layout of the point records of LAS point format 4 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat4 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASBitFields")] pub bit_fields: u8,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE_RANK)] pub scan_angle_rank: i8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+    #[pasture(BUILTIN_GPS_TIME)] pub gps_time: f64,
+    #[pasture(BUILTIN_WAVE_PACKET_DESCRIPTOR_INDEX)] pub wave_packet_descriptor_index: u8,
+    #[pasture(BUILTIN_WAVEFORM_DATA_OFFSET)] pub byte_offset_to_waveform_data: u64,
+    #[pasture(BUILTIN_WAVEFORM_PACKET_SIZE)] pub waveform_packet_size: u32,
+    #[pasture(BUILTIN_RETURN_POINT_WAVEFORM_LOCATION)] pub return_point_waveform_location: f32,
+    #[pasture(BUILTIN_WAVEFORM_PARAMETERS)] pub waveform_parameters: Vector3<f32>,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat4>(), 57);
+
+/// Point type that matches the binary layout of the point records of LAS point format 5 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat5 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASBitFields")] pub bit_fields: u8,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE_RANK)] pub scan_angle_rank: i8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+    #[pasture(BUILTIN_GPS_TIME)] pub gps_time: f64,
+    #[pasture(BUILTIN_COLOR_RGB)] pub color_rgb: Vector3<u16>,
+    #[pasture(BUILTIN_WAVE_PACKET_DESCRIPTOR_INDEX)] pub wave_packet_descriptor_index: u8,
+    #[pasture(BUILTIN_WAVEFORM_DATA_OFFSET)] pub byte_offset_to_waveform_data: u64,
+    #[pasture(BUILTIN_WAVEFORM_PACKET_SIZE)] pub waveform_packet_size: u32,
+    #[pasture(BUILTIN_RETURN_POINT_WAVEFORM_LOCATION)] pub return_point_waveform_location: f32,
+    #[pasture(BUILTIN_WAVEFORM_PARAMETERS)] pub waveform_parameters: Vector3<f32>,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat5>(), 63);
+
+/// Point type that matches the binary layout of the point records of LAS point format 6 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat6 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASExtendedBitFields")] pub bit_fields: u16,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE)] pub scan_angle: i16,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+    #[pasture(BUILTIN_GPS_TIME)] pub gps_time: f64,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat6>(), 30);
+
+/// Point type that matches the binary layout of the point records of LAS point format 7 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat7 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASExtendedBitFields")] pub bit_fields: u16,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE)] pub scan_angle: i16,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+    #[pasture(BUILTIN_GPS_TIME)] pub gps_time: f64,
+    #[pasture(BUILTIN_COLOR_RGB)] pub color_rgb: Vector3<u16>,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat7>(), 36);
+
+/// Point type that matches the binary layout of the point records of LAS point format 8 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat8 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASExtendedBitFields")] pub bit_fields: u16,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE)] pub scan_angle: i16,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+    #[pasture(BUILTIN_GPS_TIME)] pub gps_time: f64,
+    #[pasture(BUILTIN_COLOR_RGB)] pub color_rgb: Vector3<u16>,
+    #[pasture(BUILTIN_NIR)] pub nir: u16,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat8>(), 38);
+
+/// Point type that matches the binary layout of the point records of LAS point format 9 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat9 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASExtendedBitFields")] pub bit_fields: u16,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE)] pub scan_angle: i16,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+    #[pasture(BUILTIN_GPS_TIME)] pub gps_time: f64,
+    #[pasture(BUILTIN_WAVE_PACKET_DESCRIPTOR_INDEX)] pub wave_packet_descriptor_index: u8,
+    #[pasture(BUILTIN_WAVEFORM_DATA_OFFSET)] pub byte_offset_to_waveform_data: u64,
+    #[pasture(BUILTIN_WAVEFORM_PACKET_SIZE)] pub waveform_packet_size: u32,
+    #[pasture(BUILTIN_RETURN_POINT_WAVEFORM_LOCATION)] pub return_point_waveform_location: f32,
+    #[pasture(BUILTIN_WAVEFORM_PARAMETERS)] pub waveform_parameters: Vector3<f32>,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat9>(), 59);
+
+/// Point type that matches the binary layout of the point records of LAS point format 10 without extra bytes
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, PartialEq, Default, PointType)]
+pub struct LasRawPointFormat10 {
+    #[pasture(attribute = "LASRawX")] pub x: i32,
+    #[pasture(attribute = "LASRawY")] pub y: i32,
+    #[pasture(attribute = "LASRawZ")] pub z: i32,
+    #[pasture(BUILTIN_INTENSITY)] pub intensity: u16,
+    #[pasture(attribute = "LASExtendedBitFields")] pub bit_fields: u16,
+    #[pasture(BUILTIN_CLASSIFICATION)] pub classification: u8,
+    #[pasture(BUILTIN_USER_DATA)] pub user_data: u8,
+    #[pasture(BUILTIN_SCAN_ANGLE)] pub scan_angle: i16,
+    #[pasture(BUILTIN_POINT_SOURCE_ID)] pub point_source_id: u16,
+    #[pasture(BUILTIN_GPS_TIME)] pub gps_time: f64,
+    #[pasture(BUILTIN_COLOR_RGB)] pub color_rgb: Vector3<u16>,
+    #[pasture(BUILTIN_NIR)] pub nir: u16,
+    #[pasture(BUILTIN_WAVE_PACKET_DESCRIPTOR_INDEX)] pub wave_packet_descriptor_index: u8,
+    #[pasture(BUILTIN_WAVEFORM_DATA_OFFSET)] pub byte_offset_to_waveform_data: u64,
+    #[pasture(BUILTIN_WAVEFORM_PACKET_SIZE)] pub waveform_packet_size: u32,
+    #[pasture(BUILTIN_RETURN_POINT_WAVEFORM_LOCATION)] pub return_point_waveform_location: f32,
+    #[pasture(BUILTIN_WAVEFORM_PARAMETERS)] pub waveform_parameters: Vector3<f32>,
+}
+
+const_assert_eq!(std::mem::size_of::<LasRawPointFormat10>(), 67);
+
+/// Returns the `PointLayout` that matches the binary layout of the point records of the given LAS point format, i.e.
+/// the layout of the `LasRawPointFormat` types. In contrast to `point_layout_from_las_point_format`, positions are
+/// stored as three separate `i32` attributes (`LAS_RAW_X` etc.) without scale and offset, and the bit fields are
+/// stored as a single attribute (`LAS_BIT_FIELDS` or `LAS_EXTENDED_BIT_FIELDS`). Extra bytes are not part of the layout
+///
+/// # Errors
+///
+/// Returns an error if `format` is an invalid LAS point format
+pub fn raw_point_layout_from_las_point_format(format: &Format) -> Result<PointLayout> {
+    let format_number = format.to_u8()?;
+
+    match format_number {
+        0 => Ok(LasRawPointFormat0::layout()),
+        1 => Ok(LasRawPointFormat1::layout()),
+        2 => Ok(LasRawPointFormat2::layout()),
+        3 => Ok(LasRawPointFormat3::layout()),
+        4 => Ok(LasRawPointFormat4::layout()),
+        5 => Ok(LasRawPointFormat5::layout()),
+        6 => Ok(LasRawPointFormat6::layout()),
+        7 => Ok(LasRawPointFormat7::layout()),
+        8 => Ok(LasRawPointFormat8::layout()),
+        9 => Ok(LasRawPointFormat9::layout()),
+        10 => Ok(LasRawPointFormat10::layout()),
+        _ => Err(PastureIoError::UnsupportedFormat(format!(
+            "Unsupported LAS point format {}",
+            format_number
+        ))),
+    }
+}
@@ -1,16 +1,46 @@
 use std::{
     fs::File,
-    io::{BufReader, Read, Seek},
+    io::{BufReader, Cursor, Read, Seek},
+};
+use std::{
+    io::SeekFrom,
+    path::Path,
+    sync::{Arc, Mutex},
 };
-use std::{io::SeekFrom, path::Path};
 
-use anyhow::Result;
 use las_rs::Header;
 
-use crate::base::{PointReader, SeekToPoint};
-use pasture_core::{containers::PointBufferWriteable, layout::PointLayout, meta::Metadata};
+#[cfg(feature = "http")]
+use crate::base::HttpSource;
+#[cfg(feature = "mmap")]
+use crate::base::MmapSource;
+use crate::base::{
+    query_point_blocks, source_id_for_path, BlockCache, CachedReader, Capabilities, ContentDigest,
+    CoordinateTransform, DigestAlgorithm, Digester, PastureIoError, PointBlock, PointReader,
+    RangeReader, RangeSource, ReadAhead, Result, SeekToPoint, DEFAULT_READ_AHEAD_BLOCK_SIZE,
+};
+use pasture_core::{
+    containers::{
+        InterleavedPointView, InterleavedVecPointStorage, LocalFrame, PointBuffer, PointBufferExt,
+        PointBufferWriteable, SpatialQuery, SpatialQueryable,
+    },
+    layout::{
+        attributes::{COLOR_RGB, POSITION_3D},
+        PointAttributeDataType, PointLayout,
+    },
+    meta::Metadata,
+    nalgebra::Vector3,
+};
+
+use super::{
+    detect_color_bit_depth, normalize_color_bit_depth, path_is_compressed_las_file,
+    raw_point_layout_from_las_point_format, ColorBitDepth, LASReaderBase, LazyLASPoints,
+    RawLASReader, RawLAZReader,
+};
 
-use super::{path_is_compressed_las_file, LASReaderBase, RawLASReader, RawLAZReader};
+/// Number of points at the start of the file whose colors are inspected to detect the bit depth of the colors if
+/// `ColorBitDepth::Auto` is used
+const COLOR_BIT_DEPTH_SAMPLE_SIZE: usize = 100_000;
 
 trait AnyLASReader: PointReader + SeekToPoint + LASReaderBase {}
 
@@ -19,6 +49,11 @@ impl<T: PointReader + SeekToPoint + LASReaderBase> AnyLASReader for T {}
 /// `PointReader` implementation for LAS/LAZ files
 pub struct LASReader<'a> {
     raw_reader: Box<dyn AnyLASReader + 'a>,
+    color_bit_depth: ColorBitDepth,
+    detected_color_bit_depth: Option<ColorBitDepth>,
+    local_frame: Option<LocalFrame>,
+    coordinate_transform: Option<CoordinateTransform>,
+    decimation: usize,
 }
 
 impl<'a> LASReader<'a> {
@@ -37,10 +72,101 @@ impl<'a> LASReader<'a> {
     /// If `path` does not exist, cannot be opened or does not point to a valid LAS/LAZ file, an error is returned.
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self> {
         let is_compressed = path_is_compressed_las_file(path.as_ref())?;
-        let file = BufReader::new(File::open(path)?);
+        let file = BufReader::new(File::open(path).map_err(PastureIoError::Io)?);
         Self::from_read(file, is_compressed)
     }
 
+    /// Creates a new `LASReader` like [from_path](LASReader::from_path), but reads the file through the given
+    /// `cache` (see [CachedReader]). All readers of the same file that use the same cache share the cached parts of
+    /// the file, so repeated spatial queries on a COPC file don't read the same byte ranges again
+    ///
+    /// # Errors
+    ///
+    /// If `path` does not exist, cannot be opened or does not point to a valid LAS/LAZ file, an error is returned.
+    pub fn from_path_with_cache<P: AsRef<Path>>(
+        path: P,
+        cache: Arc<Mutex<BlockCache>>,
+    ) -> Result<Self> {
+        let is_compressed = path_is_compressed_las_file(path.as_ref())?;
+        let source_id = source_id_for_path(path.as_ref());
+        let file = File::open(path).map_err(PastureIoError::Io)?;
+        let cached_file = CachedReader::new(file, cache)
+            .map_err(PastureIoError::Io)?
+            .with_source_id(source_id);
+        Self::from_read(cached_file, is_compressed)
+    }
+
+    /// Creates a new `LASReader` that reads the LAS/LAZ file from the given `source` through the given `cache` (see
+    /// [RangeSource]). Each block of the file is fetched from `source` with a single range request, and readers of
+    /// sources with the same [source_id](RangeSource::source_id) share the cached blocks, so repeated spatial queries
+    /// on a remote COPC file don't fetch the same byte ranges again
+    ///
+    /// # Errors
+    ///
+    /// If the length of `source` can't be determined or `source` does not contain a valid LAS/LAZ file, an error is
+    /// returned
+    pub fn from_source_with_cache<S: RangeSource + 'a>(
+        source: S,
+        is_compressed: bool,
+        cache: Arc<Mutex<BlockCache>>,
+    ) -> Result<Self> {
+        let source_id = source.source_id();
+        let range_reader = RangeReader::new(source).map_err(PastureIoError::Io)?;
+        let mut cached_source =
+            CachedReader::new(range_reader, cache).map_err(PastureIoError::Io)?;
+        if let Some(source_id) = source_id {
+            cached_source = cached_source.with_source_id(source_id);
+        }
+        Self::from_read(cached_source, is_compressed)
+    }
+
+    /// Creates a new `LASReader` for the LAS/LAZ file at the given `url`, which is read with HTTP range requests
+    /// through the given `cache` (see [from_source_with_cache](LASReader::from_source_with_cache)). Like for
+    /// [from_path](LASReader::from_path), URLs with the extension `.laz` are assumed to be compressed. Requires the
+    /// `http` feature
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be requested, the server doesn't support range requests or the file is no valid LAS/LAZ
+    /// file, an error is returned
+    #[cfg(feature = "http")]
+    pub fn from_url_with_cache(url: &str, cache: Arc<Mutex<BlockCache>>) -> Result<Self> {
+        let url_path = url.split(|c| c == '?' || c == '#').next().unwrap_or(url);
+        let is_compressed = path_is_compressed_las_file(url_path)?;
+        Self::from_source_with_cache(HttpSource::new(url), is_compressed, cache)
+    }
+
+    /// Creates a new `LASReader` like [from_path_with_cache](LASReader::from_path_with_cache), but maps the file into
+    /// memory instead of reading it. Requires the `mmap` feature
+    ///
+    /// # Errors
+    ///
+    /// If `path` does not exist, cannot be mapped or does not point to a valid LAS/LAZ file, an error is returned.
+    #[cfg(feature = "mmap")]
+    pub fn from_path_mmap_with_cache<P: AsRef<Path>>(
+        path: P,
+        cache: Arc<Mutex<BlockCache>>,
+    ) -> Result<Self> {
+        let is_compressed = path_is_compressed_las_file(path.as_ref())?;
+        let source = MmapSource::open(path).map_err(PastureIoError::Io)?;
+        Self::from_source_with_cache(source, is_compressed, cache)
+    }
+
+    /// Creates a new `LASReader` like [from_path](LASReader::from_path), but reads the next part of the file on a
+    /// background thread while the current points are decoded and processed (see [ReadAhead]). This speeds up
+    /// sequential reads from spinning disks and network storage
+    ///
+    /// # Errors
+    ///
+    /// If `path` does not exist, cannot be opened or does not point to a valid LAS/LAZ file, an error is returned.
+    pub fn from_path_with_read_ahead<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let is_compressed = path_is_compressed_las_file(path.as_ref())?;
+        let file = File::open(path).map_err(PastureIoError::Io)?;
+        let read_ahead =
+            ReadAhead::new(file, DEFAULT_READ_AHEAD_BLOCK_SIZE).map_err(PastureIoError::Io)?;
+        Self::from_read(read_ahead, is_compressed)
+    }
+
     /// Creates a new `LASReader` from the given `read`. This method has to know whether
     /// the `read` points to a compressed LAZ file or a regular LAS file.
     ///
@@ -55,9 +181,117 @@ impl<'a> LASReader<'a> {
         };
         Ok(Self {
             raw_reader: raw_reader,
+            color_bit_depth: ColorBitDepth::SixteenBit,
+            detected_color_bit_depth: None,
+            local_frame: None,
+            coordinate_transform: None,
+            decimation: 1,
         })
     }
 
+    /// Creates a new `LASReader` for a LAS/LAZ file that is stored in memory, for example after fetching it over the
+    /// network in a browser. Whether the file is compressed is determined from the point data format in the header,
+    /// as compressed LAZ files set the high bits of the point data format ID. This does not access the filesystem,
+    /// so it also works on targets without one, such as `wasm32-unknown-unknown`
+    ///
+    /// # Errors
+    ///
+    /// If `bytes` does not contain a valid LAS/LAZ file, an error is returned.
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self> {
+        // The point data format ID is stored at byte offset 104 of the LAS header
+        let point_format_id = *bytes
+            .get(104)
+            .ok_or_else(|| PastureIoError::CorruptHeader {
+                offset: 0,
+                message: format!(
+                    "Data is too small to contain a LAS header ({} bytes)",
+                    bytes.len()
+                ),
+            })?;
+        let is_compressed = point_format_id & 0xC0 != 0;
+        Self::from_read(Cursor::new(bytes), is_compressed)
+    }
+
+    /// Sets the bit depth of the colors in the LAS file. Colors are always returned with 16 bits per channel, so if
+    /// the file stores 8-bit colors, they are scaled up to 16 bits during reading. With `ColorBitDepth::Auto`, the bit
+    /// depth is detected from the colors of the first points in the file (see `detect_color_bit_depth`). The default
+    /// value is `ColorBitDepth::SixteenBit`, which returns the colors as they are stored in the file
+    pub fn set_color_bit_depth(&mut self, color_bit_depth: ColorBitDepth) {
+        self.color_bit_depth = color_bit_depth;
+        self.detected_color_bit_depth = None;
+    }
+
+    /// Returns the bit depth of the colors in the LAS file, as set by `set_color_bit_depth`
+    pub fn color_bit_depth(&self) -> ColorBitDepth {
+        self.color_bit_depth
+    }
+
+    /// Sets the local coordinate frame for reading positions with single precision. If a `LocalFrame` is set,
+    /// `read_into` stores the positions relative to the origin of the frame for all buffers whose `POSITION_3D`
+    /// attribute has the datatype `Vec3f32` (see `LocalFrame::local_layout`). Without a `LocalFrame`, the world space
+    /// positions are converted to `Vec3f32` directly, which loses precision for large coordinates. Buffers with the
+    /// default `Vec3f64` positions are not affected
+    pub fn set_local_frame(&mut self, local_frame: Option<LocalFrame>) {
+        self.local_frame = local_frame;
+    }
+
+    /// Returns the local coordinate frame for reading positions with single precision, as set by `set_local_frame`
+    pub fn local_frame(&self) -> Option<&LocalFrame> {
+        self.local_frame.as_ref()
+    }
+
+    /// Sets a transformation of the positions that is applied while reading, e.g. to swap axes or to convert
+    /// coordinates in US survey feet to meters (see [CoordinateTransform]). The transformation is applied to the world
+    /// space positions, before they are moved into the local frame (see `set_local_frame`). Buffers with the layout of
+    /// the point records (see `raw_point_layout`) get the untransformed records, and the bounds in the header are not
+    /// transformed either
+    pub fn set_coordinate_transform(&mut self, coordinate_transform: Option<CoordinateTransform>) {
+        self.coordinate_transform = coordinate_transform;
+    }
+
+    /// Returns the transformation of the positions that is applied while reading, as set by `set_coordinate_transform`
+    pub fn coordinate_transform(&self) -> Option<&CoordinateTransform> {
+        self.coordinate_transform.as_ref()
+    }
+
+    /// Sets the decimation step for reading previews of large files. After each point that is read, the next `step - 1`
+    /// point records are skipped during decoding, so only every `step`-th point of the file is returned. For LAZ files,
+    /// the skipped records still have to be decompressed, but they are neither decoded nor stored. The decimation applies
+    /// to all methods that read points, including `read_raw_points`, and `remaining_points` returns the number of points
+    /// that are left to read with this decimation. Positions for `seek_point` are still the indices of the point
+    /// records in the file, and spatial queries always read all points of the matching blocks. The default `step` is 1,
+    /// which reads all points
+    ///
+    /// ```no_run
+    /// # use pasture_core::containers::*;
+    /// # use pasture_io::base::*;
+    /// # use pasture_io::las::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// let mut reader = LASReader::from_path("massive.laz")?;
+    /// reader.set_decimation(100);
+    /// let count = reader.remaining_points();
+    /// let preview = reader.read(count)?;
+    /// println!("Preview with {} points", preview.len());
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `step` is zero
+    pub fn set_decimation(&mut self, step: usize) {
+        if step == 0 {
+            panic!("LASReader::set_decimation: step must be greater than zero");
+        }
+        self.decimation = step;
+        self.raw_reader.set_decimation(step);
+    }
+
+    /// Returns the decimation step, as set by `set_decimation`
+    pub fn decimation(&self) -> usize {
+        self.decimation
+    }
+
     pub fn remaining_points(&mut self) -> usize {
         self.raw_reader.remaining_points()
     }
@@ -66,11 +300,184 @@ impl<'a> LASReader<'a> {
     pub fn header(&self) -> &Header {
         self.raw_reader.header()
     }
+
+    /// Reads the next `count` point records into `buffer` exactly as they are stored in the file, without parsing them
+    /// into point attributes. For LAZ files, the records are decompressed. This is useful for copying points between
+    /// LAS files without any decoding and encoding overhead, e.g. together with `LASWriter::write_raw_points`. The size
+    /// of each point record is given by `header().point_format().len()`. Returns the number of point records that were
+    /// read, which is less than `count` if there are fewer than `count` points remaining
+    ///
+    /// # Errors
+    ///
+    /// If an I/O error occurs while reading or decompressing the point records, an error is returned
+    ///
+    /// # Panics
+    ///
+    /// If `buffer` is too small to hold the point records that are read
+    pub fn read_raw_points(&mut self, buffer: &mut [u8], count: usize) -> Result<usize> {
+        self.raw_reader.read_raw_points(buffer, count)
+    }
+
+    /// Returns the `PointLayout` of the point records in the file (see `raw_point_layout_from_las_point_format`), if the
+    /// records can be interpreted as points of this layout without any conversion. This is not possible if the records
+    /// have extra bytes, or on big-endian targets, since LAS stores all values in little-endian byte order
+    pub fn raw_point_layout(&self) -> Option<PointLayout> {
+        if !cfg!(target_endian = "little") {
+            return None;
+        }
+        let format = self.header().point_format();
+        if format.extra_bytes != 0 {
+            return None;
+        }
+        raw_point_layout_from_las_point_format(format).ok()
+    }
+
+    /// Reads the next `count` point records into `buffer` and returns an `InterleavedPointView` of the records, whose
+    /// `PointLayout` matches the binary layout of the records (see `raw_point_layout`). The records are not converted,
+    /// so for LAS files, this only copies the records from the file into `buffer`. The view can be read as one of the
+    /// `LasRawPointFormat` types. Returns fewer than `count` points if there are fewer than `count` points remaining
+    ///
+    /// ```no_run
+    /// # use pasture_core::containers::*;
+    /// # use pasture_io::las::*;
+    /// let mut reader = LASReader::from_path("in.las").unwrap();
+    /// let mut records = vec![];
+    /// let points = reader.read_raw_view(&mut records, 1000).unwrap();
+    /// for point in points.iter_point_ref::<LasRawPointFormat0>() {
+    ///     let x = point.x;
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// If the point records can't be interpreted without conversion (see `raw_point_layout`), or if an I/O error occurs
+    /// while reading or decompressing the point records, an error is returned
+    pub fn read_raw_view<'b>(
+        &mut self,
+        buffer: &'b mut Vec<u8>,
+        count: usize,
+    ) -> Result<InterleavedPointView<'b>> {
+        let layout = self.raw_point_layout().ok_or_else(|| {
+            PastureIoError::UnsupportedFormat(
+                "Point records with extra bytes or on big-endian targets can't be read without conversion"
+                    .into(),
+            )
+        })?;
+        let count = count.min(self.remaining_points());
+        let size_of_point_in_file = layout.size_of_point_entry() as usize;
+        buffer.resize(count * size_of_point_in_file, 0);
+        let points_read = self.read_raw_points(buffer, count)?;
+        buffer.truncate(points_read * size_of_point_in_file);
+        Ok(InterleavedPointView::from_raw_slice(buffer, layout))
+    }
+
+    /// Reads the next `count` points as `LazyLASPoints`, which only decode an attribute when it is accessed for the
+    /// first time. This is faster than `read` if only a few attributes of the points are needed. Returns fewer than
+    /// `count` points if there are fewer than `count` points remaining
+    ///
+    /// # Errors
+    ///
+    /// If an I/O error occurs while reading or decompressing the point records, an error is returned
+    pub fn read_lazy(&mut self, count: usize) -> Result<LazyLASPoints> {
+        let count = count.min(self.remaining_points());
+        let size_of_point_in_file = self.header().point_format().len() as usize;
+        let mut raw_points = vec![0; count * size_of_point_in_file];
+        let points_read = self.read_raw_points(&mut raw_points, count)?;
+        raw_points.truncate(points_read * size_of_point_in_file);
+        LazyLASPoints::new(raw_points, self.header())
+    }
+
+    /// Computes the digest of the remaining point records exactly as they are stored in the file (for LAZ files, of the
+    /// decompressed records), without parsing them into point attributes. The digest identifies the point data of a
+    /// LAS file independent of its header and VLRs, and a LAS file and its compressed LAZ version have the same digest.
+    /// Afterwards, there are no remaining points
+    ///
+    /// # Errors
+    ///
+    /// If an I/O error occurs while reading or decompressing the point records, an error is returned
+    pub fn point_record_digest(&mut self, algorithm: DigestAlgorithm) -> Result<ContentDigest> {
+        const CHUNK_SIZE: usize = 50_000;
+        let size_of_point_in_file = self.header().point_format().len() as usize;
+        let mut raw_points = vec![0; CHUNK_SIZE * size_of_point_in_file];
+        let mut digester = Digester::new(algorithm);
+        loop {
+            let points_read = self.read_raw_points(&mut raw_points, CHUNK_SIZE)?;
+            if points_read == 0 {
+                break;
+            }
+            digester.update(&raw_points[..points_read * size_of_point_in_file]);
+        }
+        Ok(digester.finish())
+    }
+
+    /// Returns the bit depth of the colors in the file, detecting it from the colors of the first points in the file
+    /// for `ColorBitDepth::Auto`. The current point position is not changed
+    fn resolve_color_bit_depth(&mut self) -> Result<ColorBitDepth> {
+        if self.color_bit_depth != ColorBitDepth::Auto {
+            return Ok(self.color_bit_depth);
+        }
+        if let Some(detected) = self.detected_color_bit_depth {
+            return Ok(detected);
+        }
+
+        let current_point = self.raw_reader.point_index()?;
+        self.raw_reader.seek_point(SeekFrom::Start(0))?;
+        let mut colors = InterleavedVecPointStorage::with_capacity(
+            COLOR_BIT_DEPTH_SAMPLE_SIZE,
+            PointLayout::from_attributes(&[COLOR_RGB]),
+        );
+        let read_result = self
+            .raw_reader
+            .read_into(&mut colors, COLOR_BIT_DEPTH_SAMPLE_SIZE);
+        self.raw_reader
+            .seek_point(SeekFrom::Start(current_point as u64))?;
+        read_result?;
+
+        let detected = detect_color_bit_depth(colors.iter_attribute::<Vector3<u16>>(&COLOR_RGB));
+        self.detected_color_bit_depth = Some(detected);
+        Ok(detected)
+    }
+
+    /// Reads points with positions in world space into `point_buffer`
+    fn read_into_world_space(
+        &mut self,
+        point_buffer: &mut dyn PointBufferWriteable,
+        count: usize,
+    ) -> Result<usize> {
+        let first_new_point = point_buffer.len();
+        let points_read = self.raw_reader.read_into(point_buffer, count)?;
+        if self.color_bit_depth != ColorBitDepth::SixteenBit
+            && self.header().point_format().has_color
+        {
+            let color_bit_depth = self.resolve_color_bit_depth()?;
+            normalize_color_bit_depth(
+                point_buffer,
+                first_new_point..first_new_point + points_read,
+                color_bit_depth,
+            );
+        }
+        if let Some(coordinate_transform) = &self.coordinate_transform {
+            coordinate_transform
+                .apply_to_buffer(point_buffer, first_new_point..first_new_point + points_read);
+        }
+        Ok(points_read)
+    }
 }
 
 impl<'a> PointReader for LASReader<'a> {
-    fn read(&mut self, count: usize) -> Result<Box<dyn pasture_core::containers::PointBuffer>> {
-        self.raw_reader.read(count)
+    fn read(&mut self, count: usize) -> Result<Box<dyn PointBuffer>> {
+        if self.color_bit_depth == ColorBitDepth::SixteenBit && self.coordinate_transform.is_none()
+        {
+            return self.raw_reader.read(count);
+        }
+        let count = count.min(self.remaining_points());
+        let mut buffer = InterleavedVecPointStorage::with_capacity(
+            count,
+            self.get_default_point_layout().clone(),
+        );
+        self.read_into(&mut buffer, count)?;
+        Ok(Box::new(buffer))
     }
 
     fn read_into(
@@ -78,7 +485,38 @@ impl<'a> PointReader for LASReader<'a> {
         point_buffer: &mut dyn PointBufferWriteable,
         count: usize,
     ) -> Result<usize> {
-        self.raw_reader.read_into(point_buffer, count)
+        // Buffers with the layout of the point records get the records as they are stored in the file
+        if let Some(raw_layout) = self.raw_point_layout() {
+            if *point_buffer.point_layout() == raw_layout {
+                let mut records = vec![];
+                let points = self.read_raw_view(&mut records, count)?;
+                point_buffer.push(&points);
+                return Ok(points.len());
+            }
+        }
+
+        let local_frame = match self.local_frame {
+            Some(local_frame) => local_frame,
+            None => return self.read_into_world_space(point_buffer, count),
+        };
+        let has_local_positions = point_buffer
+            .point_layout()
+            .get_attribute_by_name(POSITION_3D.name())
+            .map(|attribute| attribute.datatype() == PointAttributeDataType::Vec3f32)
+            .unwrap_or(false);
+        if !has_local_positions {
+            return self.read_into_world_space(point_buffer, count);
+        }
+
+        // Converting the positions to single precision before moving them into the local frame would lose precision,
+        // so the points are read with double precision positions first
+        let mut world_space_points = InterleavedVecPointStorage::with_capacity(
+            count.min(self.remaining_points()),
+            LocalFrame::global_layout(point_buffer.point_layout()),
+        );
+        let points_read = self.read_into_world_space(&mut world_space_points, count)?;
+        local_frame.append_local(&world_space_points, point_buffer);
+        Ok(points_read)
     }
 
     fn get_metadata(&self) -> &dyn Metadata {
@@ -88,10 +526,434 @@ impl<'a> PointReader for LASReader<'a> {
     fn get_default_point_layout(&self) -> &PointLayout {
         self.raw_reader.get_default_point_layout()
     }
+
+    fn capabilities(&self) -> Capabilities {
+        self.raw_reader.capabilities()
+    }
 }
 
 impl<'a> SeekToPoint for LASReader<'a> {
     fn seek_point(&mut self, position: SeekFrom) -> Result<usize> {
         self.raw_reader.seek_point(position)
     }
+
+    fn point_blocks(&mut self) -> Result<Option<Vec<PointBlock>>> {
+        self.raw_reader.point_blocks()
+    }
+}
+
+/// Spatial queries on LAS files with a spatial index, i.e. COPC files. See [query_point_blocks]
+impl<'a> SpatialQueryable for LASReader<'a> {
+    fn point_layout(&self) -> &PointLayout {
+        self.get_default_point_layout()
+    }
+
+    fn query(
+        &mut self,
+        query: &SpatialQuery,
+        resolution: Option<f64>,
+        points: &mut dyn PointBufferWriteable,
+    ) -> anyhow::Result<usize> {
+        // The blocks are read by their exact number of point records, which doesn't work with decimation
+        self.raw_reader.set_decimation(1);
+        let result = query_point_blocks(self, query, resolution, points);
+        self.raw_reader.set_decimation(self.decimation);
+        Ok(result?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base::{AxisMapping, LengthUnit};
+    use crate::las::{
+        get_test_las_path, get_test_laz_path, test_data_classifications, test_data_point_count,
+        write_test_copc_file, LasPointFormat0, LasPointFormat1, LasRawPointFormat0,
+    };
+    use pasture_core::{
+        containers::{
+            InterleavedPointBuffer, InterleavedPointBufferExt, PerAttributeVecPointStorage,
+        },
+        layout::{
+            attributes::{CLASSIFICATION, INTENSITY},
+            PointType,
+        },
+        math::AABB,
+        nalgebra::Point3,
+    };
+    use scopeguard::defer;
+
+    #[test]
+    fn test_read_from_bytes() -> Result<()> {
+        for path in &[get_test_las_path(0), get_test_laz_path(0)] {
+            let bytes = std::fs::read(path)?;
+            let mut reader = LASReader::from_bytes(&bytes)?;
+            let points_from_bytes = reader.read(10)?;
+
+            let mut file_reader = LASReader::from_path(path)?;
+            let points_from_file = file_reader.read(10)?;
+
+            let size_of_points =
+                10 * points_from_file.point_layout().size_of_point_entry() as usize;
+            let mut expected = vec![0; size_of_points];
+            let mut actual = vec![0; size_of_points];
+            points_from_file.get_raw_points(0..10, &mut expected);
+            points_from_bytes.get_raw_points(0..10, &mut actual);
+            assert_eq!(expected, actual);
+        }
+
+        assert!(LASReader::from_bytes(&[0; 16]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_capabilities() -> Result<()> {
+        for path in &[get_test_las_path(0), get_test_laz_path(0)] {
+            let capabilities = LASReader::from_path(path)?.capabilities();
+            assert!(capabilities.seekable);
+            // The test files have no spatial index
+            assert!(!capabilities.filter_pushdown);
+            assert!(!capabilities.appendable);
+            assert!(capabilities.supports_layout(&LasPointFormat0::layout()));
+            assert!(capabilities.supports_attribute(&INTENSITY));
+        }
+
+        // COPC files have a spatial index, which can be used for pushing down spatial filters
+        let path = std::env::temp_dir().join("pasture_test_capabilities.copc.laz");
+        write_test_copc_file(&path, 6)?;
+        defer! {
+            std::fs::remove_file(&path).expect("Could not remove test file");
+        }
+        let mut reader = LASReader::from_path(&path)?;
+        assert!(reader.capabilities().filter_pushdown);
+        assert_eq!(
+            3,
+            reader
+                .point_blocks()?
+                .map(|blocks| blocks.len())
+                .unwrap_or(0)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_spatial_query_on_copc_file() -> Result<()> {
+        let path = std::env::temp_dir().join("pasture_test_spatial_query.copc.laz");
+        write_test_copc_file(&path, 7)?;
+        defer! {
+            std::fs::remove_file(&path).expect("Could not remove test file");
+        }
+        let mut reader = LASReader::from_path(&path)?;
+        let mut query =
+            |query: &SpatialQuery, resolution: Option<f64>| -> Result<Vec<Vector3<f64>>> {
+                let mut points = InterleavedVecPointStorage::new(reader.point_layout().clone());
+                let count = reader.query(query, resolution, &mut points)?;
+                assert_eq!(count, points.len());
+                Ok(points
+                    .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                    .collect())
+            };
+        let assert_positions = |expected: &[Vector3<f64>], actual: &[Vector3<f64>]| {
+            assert_eq!(expected.len(), actual.len());
+            for (expected, actual) in expected.iter().zip(actual.iter()) {
+                assert!((expected - actual).amax() < 1e-9);
+            }
+        };
+
+        // The first child node doesn't intersect the bounds and is never read
+        let bounds = SpatialQuery::Bounds(AABB::from_min_max(
+            Point3::new(4.5, 4.5, 4.5),
+            Point3::new(7.5, 7.5, 7.5),
+        ));
+        assert_positions(
+            &[
+                Vector3::new(7.0, 7.0, 7.0),
+                Vector3::new(5.0, 5.0, 5.0),
+                Vector3::new(6.0, 6.0, 6.0),
+            ],
+            &query(&bounds, None)?,
+        );
+        // Only the root node has a spacing of at least 1.5
+        assert_positions(&[Vector3::new(7.0, 7.0, 7.0)], &query(&bounds, Some(1.5))?);
+
+        let nearest = SpatialQuery::Nearest {
+            position: Vector3::zeros(),
+            k: 2,
+        };
+        assert_positions(
+            &[Vector3::new(0.5, 0.5, 0.5), Vector3::new(1.0, 1.0, 1.0)],
+            &query(&nearest, None)?,
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_with_decimation() -> Result<()> {
+        for path in &[get_test_las_path(1), get_test_laz_path(1)] {
+            let expected_points = LASReader::from_path(path)?
+                .read(test_data_point_count())?
+                .iter_point::<LasPointFormat1>()
+                .step_by(3)
+                .collect::<Vec<_>>();
+
+            let mut reader = LASReader::from_path(path)?;
+            reader.set_decimation(3);
+            assert_eq!(3, reader.decimation());
+            assert_eq!(expected_points.len(), reader.remaining_points());
+            let points = reader.read(100)?;
+            assert_eq!(
+                expected_points,
+                points.iter_point::<LasPointFormat1>().collect::<Vec<_>>()
+            );
+            assert_eq!(0, reader.remaining_points());
+
+            // Reading into a custom layout skips the same point records, starting at the current point
+            reader.seek_point(SeekFrom::Start(1))?;
+            assert_eq!(3, reader.remaining_points());
+            let mut classifications =
+                PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[CLASSIFICATION]));
+            assert_eq!(2, reader.read_into(&mut classifications, 2)?);
+            assert_eq!(1, reader.remaining_points());
+            assert_eq!(7, reader.point_index()?);
+            assert_eq!(
+                vec![1, 4],
+                classifications
+                    .iter_attribute::<u8>(&CLASSIFICATION)
+                    .collect::<Vec<_>>()
+            );
+
+            // Raw point records are decimated as well. The classification is stored at byte 15 of format 1 records
+            reader.seek_point(SeekFrom::Start(2))?;
+            let mut records = vec![0; 3 * 28];
+            assert_eq!(3, reader.read_raw_points(&mut records, 10)?);
+            let raw_classifications = records
+                .chunks_exact(28)
+                .map(|record| record[15])
+                .collect::<Vec<_>>();
+            let expected_classifications = test_data_classifications();
+            assert_eq!(
+                vec![
+                    expected_classifications[2],
+                    expected_classifications[5],
+                    expected_classifications[8]
+                ],
+                raw_classifications
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_decimation_of_zero_panics() {
+        let mut reader = LASReader::from_path(get_test_las_path(0)).unwrap();
+        reader.set_decimation(0);
+    }
+
+    #[test]
+    fn test_point_record_digest_of_las_and_laz() -> Result<()> {
+        for format in 0..4 {
+            let mut las_reader = LASReader::from_path(get_test_las_path(format))?;
+            let mut laz_reader = LASReader::from_path(get_test_laz_path(format))?;
+            let las_digest = las_reader.point_record_digest(DigestAlgorithm::Sha256)?;
+            assert_eq!(32, las_digest.bytes().len());
+            assert_eq!(0, las_reader.remaining_points());
+            assert_eq!(
+                las_digest,
+                laz_reader.point_record_digest(DigestAlgorithm::Sha256)?
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_with_block_cache() -> Result<()> {
+        let cache = BlockCache::new_shared(256, 64 * 1024);
+        for path in &[get_test_las_path(0), get_test_laz_path(0)] {
+            let mut expected = vec![0; 10 * 20];
+            LASReader::from_path(path)?.read_raw_points(&mut expected, 10)?;
+
+            for _ in 0..2 {
+                let mut reader = LASReader::from_path_with_cache(path, cache.clone())?;
+                let mut actual = vec![0; 10 * 20];
+                reader.read_raw_points(&mut actual, 10)?;
+                assert_eq!(expected, actual);
+            }
+        }
+        // The second reader of each file reads only cached blocks
+        let statistics = cache.lock().unwrap().statistics();
+        assert!(statistics.hits >= statistics.misses);
+        assert_eq!(0, statistics.evictions);
+        Ok(())
+    }
+
+    /// In-memory `RangeSource` that counts its range requests
+    struct CountingSource {
+        data: Arc<Vec<u8>>,
+        requests: Arc<Mutex<usize>>,
+    }
+
+    impl RangeSource for CountingSource {
+        fn length(&mut self) -> std::io::Result<u64> {
+            Ok(self.data.len() as u64)
+        }
+
+        fn read_range(&mut self, offset: u64, buffer: &mut [u8]) -> std::io::Result<()> {
+            *self.requests.lock().unwrap() += 1;
+            let start = offset as usize;
+            buffer.copy_from_slice(&self.data[start..start + buffer.len()]);
+            Ok(())
+        }
+
+        fn source_id(&self) -> Option<u64> {
+            Some(1)
+        }
+    }
+
+    #[test]
+    fn test_read_from_source_with_cache() -> Result<()> {
+        let data = Arc::new(std::fs::read(get_test_laz_path(1))?);
+        let requests = Arc::new(Mutex::new(0));
+        let cache = BlockCache::new_shared(256, 1024 * 1024);
+        let read_points = || -> Result<Vec<u8>> {
+            let source = CountingSource {
+                data: data.clone(),
+                requests: requests.clone(),
+            };
+            let mut reader = LASReader::from_source_with_cache(source, true, cache.clone())?;
+            let mut points = vec![0; 10 * 28];
+            reader.read_raw_points(&mut points, 10)?;
+            Ok(points)
+        };
+
+        let expected = read_points()?;
+        let requests_of_first_reader = *requests.lock().unwrap();
+        assert!(requests_of_first_reader > 0);
+        // All blocks of the second reader of the same source are in the cache, so it doesn't fetch any range
+        assert_eq!(expected, read_points()?);
+        assert_eq!(requests_of_first_reader, *requests.lock().unwrap());
+        let statistics = cache.lock().unwrap().statistics();
+        assert_eq!(requests_of_first_reader as u64, statistics.misses);
+        assert!(statistics.hits >= statistics.misses);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_with_read_ahead() -> Result<()> {
+        for path in &[get_test_las_path(1), get_test_laz_path(1)] {
+            let mut expected = vec![0; 10 * 28];
+            LASReader::from_path(path)?.read_raw_points(&mut expected, 10)?;
+
+            let mut reader = LASReader::from_path_with_read_ahead(path)?;
+            let mut actual = vec![0; 10 * 28];
+            assert_eq!(10, reader.read_raw_points(&mut actual, 10)?);
+            assert_eq!(expected, actual);
+
+            reader.seek_point(SeekFrom::Start(3))?;
+            let mut actual = vec![0; 28];
+            reader.read_raw_points(&mut actual, 1)?;
+            assert_eq!(&expected[3 * 28..4 * 28], &actual[..]);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_raw_view() -> Result<()> {
+        for path in &[get_test_las_path(0), get_test_laz_path(0)] {
+            let mut reader = LASReader::from_path(path)?;
+            let raw_layout = reader.raw_point_layout().unwrap();
+            assert_eq!(LasRawPointFormat0::layout(), raw_layout);
+            let scale_x = reader.header().transforms().x.scale;
+            let offset_x = reader.header().transforms().x.offset;
+            let points = reader.read(10)?;
+
+            let mut reader = LASReader::from_path(path)?;
+            let mut records = vec![];
+            let raw_points = reader.read_raw_view(&mut records, 100)?;
+            assert_eq!(10, raw_points.len());
+            for (point, raw_point) in points
+                .iter_point::<LasPointFormat0>()
+                .zip(raw_points.iter_point_ref::<LasRawPointFormat0>())
+            {
+                let x = raw_point.x as f64 * scale_x + offset_x;
+                assert_eq!({ point.position }.x, x);
+                assert_eq!({ point.intensity }, { raw_point.intensity });
+                assert_eq!(point.return_number, raw_point.bit_fields & 0b111);
+                assert_eq!({ point.point_source_id }, { raw_point.point_source_id });
+            }
+
+            // read_into with the raw layout copies the records
+            let mut reader = LASReader::from_path(path)?;
+            let mut raw_buffer = InterleavedVecPointStorage::new(raw_layout);
+            assert_eq!(10, reader.read_into(&mut raw_buffer, 10)?);
+            assert_eq!(&records[..], raw_buffer.get_raw_points_ref(0..10));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_into_local_frame() -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(0))?;
+        let point_count = reader.remaining_points();
+        let mut world_space_points = InterleavedVecPointStorage::with_capacity(
+            point_count,
+            reader.get_default_point_layout().clone(),
+        );
+        reader.read_into(&mut world_space_points, point_count)?;
+
+        let local_frame = LocalFrame::new(Vector3::new(1.5, 2.5, -0.5));
+        let local_layout = LocalFrame::local_layout(reader.get_default_point_layout());
+        let mut reader = LASReader::from_path(get_test_las_path(0))?;
+        reader.set_local_frame(Some(local_frame));
+        let mut local_points = InterleavedVecPointStorage::with_capacity(point_count, local_layout);
+        assert_eq!(
+            point_count,
+            reader.read_into(&mut local_points, point_count)?
+        );
+
+        let expected_positions = world_space_points
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .map(|position| local_frame.to_local(&position))
+            .collect::<Vec<_>>();
+        let local_positions = local_points
+            .iter_attribute::<Vector3<f32>>(&LocalFrame::local_position_attribute())
+            .collect::<Vec<_>>();
+        assert_eq!(expected_positions, local_positions);
+        assert_eq!(
+            world_space_points
+                .iter_attribute::<u16>(&INTENSITY)
+                .collect::<Vec<_>>(),
+            local_points
+                .iter_attribute::<u16>(&INTENSITY)
+                .collect::<Vec<_>>()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_with_coordinate_transform() -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(0))?;
+        let expected_positions = reader
+            .read(10)?
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .map(|position| Vector3::new(position.x, -position.z, position.y) * 0.3048)
+            .collect::<Vec<_>>();
+
+        let transform = CoordinateTransform::identity()
+            .with_axis_mapping(AxisMapping::y_up_to_z_up())
+            .with_unit_conversion(LengthUnit::InternationalFeet, LengthUnit::Meters);
+        for path in &[get_test_las_path(0), get_test_laz_path(0)] {
+            let mut reader = LASReader::from_path(path)?;
+            reader.set_coordinate_transform(Some(transform));
+            let points = reader.read(10)?;
+            let positions = points
+                .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                .collect::<Vec<_>>();
+            assert_eq!(expected_positions.len(), positions.len());
+            for (expected, actual) in expected_positions.iter().zip(positions.iter()) {
+                assert!((expected - actual).amax() < 1e-9);
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,556 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Cursor, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use las_rs::{point::Format, raw, Version, Vlr};
+use laz::{
+    las::laszip::{LASZIP_DESCRIPTION, LASZIP_RECORD_ID, LASZIP_USER_ID},
+    LasZipCompressor, LasZipDecompressor, LazItemRecordBuilder, LazVlr,
+};
+
+use crate::base::{PastureIoError, Result};
+
+use super::{is_laszip_vlr, map_laz_err, path_is_compressed_las_file, read_raw_las_header};
+
+/// Number of point records that are rewritten at once
+const REWRITE_CHUNK_SIZE: usize = 50_000;
+/// Bit of the global encoding field that indicates that the CRS is stored as WKT, which requires LAS 1.4
+const GLOBAL_ENCODING_WKT_BIT: u16 = 1 << 4;
+/// Size of the header of a regular VLR in bytes
+const VLR_HEADER_SIZE: u32 = 54;
+
+/// Options for rewriting a LAS/LAZ file with `rewrite_las`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LASRewriteOptions {
+    /// LAS version of the rewritten file as (major, minor). If this is `None`, the version of the input file is kept
+    pub version: Option<(u8, u8)>,
+    /// Compress the rewritten file using LASzip?
+    pub compressed: bool,
+}
+
+/// Returns the size of the LAS header in bytes (excluding any user-defined bytes) for the given LAS version
+fn header_size_for_version(version: Version) -> u16 {
+    match version.minor {
+        0..=2 => 227,
+        3 => 235,
+        _ => 375,
+    }
+}
+
+/// Returns the minimum minor LAS version that supports the given point record format
+fn min_minor_version_for_point_format(format: u8) -> u8 {
+    match format {
+        0..=1 => 0,
+        2..=3 => 2,
+        4..=5 => 3,
+        _ => 4,
+    }
+}
+
+/// Reads the next `count` raw VLRs from `read`
+fn read_raw_vlrs<R: Read + Seek>(
+    read: &mut R,
+    count: usize,
+    extended: bool,
+) -> Result<Vec<raw::Vlr>> {
+    (0..count)
+        .map(|_| -> Result<raw::Vlr> {
+            let vlr_offset = read.seek(SeekFrom::Current(0))?;
+            raw::Vlr::read_from(&mut *read, extended).map_err(|err| PastureIoError::CorruptHeader {
+                offset: vlr_offset,
+                message: err.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Creates the LASzip VLR for point records with the given format and length
+fn make_laszip_vlr(point_format: u8, point_record_length: u16) -> Result<(LazVlr, raw::Vlr)> {
+    let num_extra_bytes = point_record_length
+        .checked_sub(Format::new(point_format)?.len())
+        .ok_or_else(|| PastureIoError::CorruptHeader {
+            // The point data record length is stored at byte offset 105 in the LAS header
+            offset: 105,
+            message: format!(
+                "Point data record length {} is too small for point record format {}",
+                point_record_length, point_format
+            ),
+        })?;
+    let laz_items =
+        LazItemRecordBuilder::default_for_point_format_id(point_format, num_extra_bytes)
+            .map_err(map_laz_err)?;
+    let laz_vlr = LazVlr::from_laz_items(laz_items);
+    let mut laz_vlr_data = Cursor::new(Vec::<u8>::new());
+    laz_vlr.write_to(&mut laz_vlr_data)?;
+    let raw_vlr = Vlr {
+        user_id: LASZIP_USER_ID.to_owned(),
+        record_id: LASZIP_RECORD_ID,
+        description: LASZIP_DESCRIPTION.to_owned(),
+        data: laz_vlr_data.into_inner(),
+    }
+    .into_raw(false)?;
+    Ok((laz_vlr, raw_vlr))
+}
+
+/// Copies `count` point records with a size of `point_record_length` bytes each in chunks from `read_point_records` to
+/// `write_point_records`
+fn copy_point_records(
+    count: usize,
+    point_record_length: usize,
+    mut read_point_records: impl FnMut(&mut [u8]) -> std::io::Result<()>,
+    mut write_point_records: impl FnMut(&[u8]) -> std::io::Result<()>,
+) -> Result<()> {
+    let mut chunk = vec![];
+    let mut remaining_points = count;
+    while remaining_points > 0 {
+        let points_in_chunk = remaining_points.min(REWRITE_CHUNK_SIZE);
+        chunk.resize(points_in_chunk * point_record_length, 0);
+        read_point_records(&mut chunk)?;
+        write_point_records(&chunk)?;
+        remaining_points -= points_in_chunk;
+    }
+    Ok(())
+}
+
+/// Sets the point counts of the `header` for its LAS version. The LAS 1.4 point counts are used if the version supports
+/// them, the legacy point counts are only set if the point record format and the number of points allow it
+fn set_point_counts(
+    header: &mut raw::Header,
+    number_of_point_records: u64,
+    number_of_points_by_return: [u64; 15],
+) -> Result<()> {
+    let point_format = header.point_data_record_format & 0b1111;
+    let fits_legacy_fields = point_format < 6 && number_of_point_records <= u32::MAX as u64;
+    if header.version.minor >= 4 {
+        header.large_file = Some(Default::default());
+        let large_file = header.large_file.as_mut().unwrap();
+        large_file.number_of_point_records = number_of_point_records;
+        large_file.number_of_points_by_return = number_of_points_by_return;
+    } else {
+        header.large_file = None;
+        if !fits_legacy_fields {
+            return Err(PastureIoError::UnsupportedFormat(format!(
+                "{} points can't be stored in a LAS {} file",
+                number_of_point_records, header.version
+            )));
+        }
+    }
+
+    if fits_legacy_fields {
+        header.number_of_point_records = number_of_point_records as u32;
+        for (legacy_count, count) in header
+            .number_of_points_by_return
+            .iter_mut()
+            .zip(number_of_points_by_return.iter())
+        {
+            *legacy_count = *count as u32;
+        }
+    } else {
+        header.number_of_point_records = 0;
+        header.number_of_points_by_return = [0; 5];
+    }
+    Ok(())
+}
+
+/// Losslessly rewrites the LAS/LAZ file in `read` into `write`, optionally changing the LAS version and compression.
+/// Unlike reading the points into a buffer and writing them with a `LASWriter`, this copies the point records
+/// byte-for-byte, so positions are not converted to `f64` and back, and all extra bytes are kept. The header fields
+/// (including the global encoding flags, bounds and GUID), the VLRs (except for the LASzip VLR, which is recreated
+/// as needed), the user-defined bytes after the header and the VLRs, as well as all EVLRs are preserved as well. The
+/// points are streamed in chunks, so files of any size can be rewritten
+///
+/// ```no_run
+/// # use std::{fs::File, io::{BufReader, BufWriter}};
+/// # use pasture_io::las::{rewrite_las, LASRewriteOptions};
+/// # fn main() -> anyhow::Result<()> {
+/// // Compress a LAS 1.2 file to a LAS 1.4 LAZ file
+/// let input = BufReader::new(File::open("in.las")?);
+/// let output = BufWriter::new(File::create("out.laz")?);
+/// let options = LASRewriteOptions {
+///     version: Some((1, 4)),
+///     compressed: true,
+/// };
+/// rewrite_las(input, false, output, &options)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If `read` does not contain a valid LAS/LAZ file, an error is returned. If the file can't be represented in the
+/// requested LAS version without losing information (e.g. because the version does not support the point record
+/// format, EVLRs or WKT coordinate reference systems), an error is returned as well.
+pub fn rewrite_las<R: Read + Seek + Send, W: Write + Seek + Send>(
+    mut read: R,
+    input_is_compressed: bool,
+    mut write: W,
+    options: &LASRewriteOptions,
+) -> Result<()> {
+    let input_header = read_raw_las_header(&mut read)?;
+    let point_format = input_header.point_data_record_format & 0b0011_1111;
+    let point_record_length = input_header.point_data_record_length;
+    let (number_of_point_records, number_of_points_by_return) = match &input_header.large_file {
+        Some(large_file) if large_file.number_of_point_records > 0 => (
+            large_file.number_of_point_records,
+            large_file.number_of_points_by_return,
+        ),
+        _ => {
+            let mut number_of_points_by_return = [0; 15];
+            for (count, legacy_count) in number_of_points_by_return
+                .iter_mut()
+                .zip(input_header.number_of_points_by_return.iter())
+            {
+                *count = *legacy_count as u64;
+            }
+            (
+                input_header.number_of_point_records as u64,
+                number_of_points_by_return,
+            )
+        }
+    };
+
+    // Read all VLRs and the user-defined bytes between the VLRs and the point records. The LASzip VLR is dropped, because
+    // it is recreated if the output is compressed
+    let vlrs = read_raw_vlrs(
+        &mut read,
+        input_header.number_of_variable_length_records as usize,
+        false,
+    )?;
+    let mut laszip_vlr = None;
+    let mut regular_vlrs = vec![];
+    for vlr in vlrs {
+        let parsed_vlr = Vlr::new(vlr.clone());
+        if is_laszip_vlr(&parsed_vlr) {
+            laszip_vlr = Some(LazVlr::from_buffer(&parsed_vlr.data).map_err(map_laz_err)?);
+        } else {
+            regular_vlrs.push(vlr);
+        }
+    }
+    let end_of_vlrs = read.seek(SeekFrom::Current(0))?;
+    let vlr_padding_size = (input_header.offset_to_point_data as u64).saturating_sub(end_of_vlrs);
+    let mut vlr_padding = vec![0; vlr_padding_size as usize];
+    read.read_exact(&mut vlr_padding)?;
+
+    // Read the EVLRs. In LAS 1.3, the waveform data packet record is the only EVLR
+    let start_of_waveform_data = input_header
+        .start_of_waveform_data_packet_record
+        .unwrap_or(0);
+    let mut evlr_offsets = vec![];
+    let mut evlrs = vec![];
+    match &input_header.evlr {
+        Some(evlr) if evlr.number_of_evlrs > 0 => {
+            read.seek(SeekFrom::Start(evlr.start_of_first_evlr))?;
+            for _ in 0..evlr.number_of_evlrs {
+                evlr_offsets.push(read.seek(SeekFrom::Current(0))?);
+                evlrs.extend(read_raw_vlrs(&mut read, 1, true)?);
+            }
+        }
+        _ if start_of_waveform_data > 0 => {
+            read.seek(SeekFrom::Start(start_of_waveform_data))?;
+            evlr_offsets.push(start_of_waveform_data);
+            evlrs.extend(read_raw_vlrs(&mut read, 1, true)?);
+        }
+        _ => (),
+    }
+    let waveform_evlr_index = evlr_offsets
+        .iter()
+        .position(|offset| start_of_waveform_data > 0 && *offset == start_of_waveform_data);
+
+    // Build the header of the output file
+    let mut header = input_header.clone();
+    if let Some((major, minor)) = options.version {
+        if major != 1 || minor > 4 {
+            return Err(PastureIoError::UnsupportedVersion {
+                major: major as u32,
+                minor: minor as u32,
+            });
+        }
+        header.version = Version::new(major, minor);
+    }
+    let version = header.version;
+    if version.minor < min_minor_version_for_point_format(point_format) {
+        return Err(PastureIoError::UnsupportedFormat(format!(
+            "Point record format {} is not supported by LAS {}",
+            point_format, version
+        )));
+    }
+    if version.minor < 4 && (header.global_encoding & GLOBAL_ENCODING_WKT_BIT) != 0 {
+        return Err(PastureIoError::UnsupportedFormat(format!(
+            "WKT coordinate reference systems are not supported by LAS {}",
+            version
+        )));
+    }
+    let evlrs_are_supported = match version.minor {
+        0..=2 => evlrs.is_empty(),
+        3 => evlrs.is_empty() || (evlrs.len() == 1 && waveform_evlr_index == Some(0)),
+        _ => true,
+    };
+    if !evlrs_are_supported {
+        return Err(PastureIoError::UnsupportedFormat(format!(
+            "The {} EVLR(s) of the input file can't be stored in a LAS {} file",
+            evlrs.len(),
+            version
+        )));
+    }
+
+    header.point_data_record_format = if options.compressed {
+        point_format | 0x80
+    } else {
+        point_format
+    };
+    header.header_size = header_size_for_version(version) + header.padding.len() as u16;
+    header.start_of_waveform_data_packet_record = if version.minor >= 3 { Some(0) } else { None };
+    header.evlr = if version.minor >= 4 {
+        Some(Default::default())
+    } else {
+        None
+    };
+    set_point_counts(
+        &mut header,
+        number_of_point_records,
+        number_of_points_by_return,
+    )?;
+
+    let laszip = if options.compressed {
+        Some(make_laszip_vlr(point_format, point_record_length)?)
+    } else {
+        None
+    };
+    let output_vlrs = regular_vlrs
+        .iter()
+        .chain(laszip.as_ref().map(|(_, vlr)| vlr))
+        .collect::<Vec<_>>();
+    header.number_of_variable_length_records = output_vlrs.len() as u32;
+    header.offset_to_point_data = header.header_size as u32
+        + output_vlrs
+            .iter()
+            .map(|vlr| VLR_HEADER_SIZE + vlr.data.len() as u32)
+            .sum::<u32>()
+        + vlr_padding.len() as u32;
+
+    header.write_to(&mut write)?;
+    for vlr in output_vlrs {
+        vlr.write_to(&mut write)?;
+    }
+    write.write_all(&vlr_padding)?;
+
+    // Copy the point records in chunks
+    read.seek(SeekFrom::Start(input_header.offset_to_point_data as u64))?;
+    let read_point_records: Box<dyn FnMut(&mut [u8]) -> std::io::Result<()> + '_> =
+        if input_is_compressed {
+            let laszip_vlr = laszip_vlr.ok_or_else(|| PastureIoError::CorruptHeader {
+                offset: 0,
+                message: "LAZ variable length record not found in file!".into(),
+            })?;
+            let mut decompressor =
+                LasZipDecompressor::new(&mut read, laszip_vlr).map_err(map_laz_err)?;
+            Box::new(move |chunk| decompressor.decompress_many(chunk))
+        } else {
+            Box::new(|chunk| read.read_exact(chunk))
+        };
+    match &laszip {
+        Some((laz_vlr, _)) => {
+            let mut compressor =
+                LasZipCompressor::new(&mut write, laz_vlr.clone()).map_err(map_laz_err)?;
+            copy_point_records(
+                number_of_point_records as usize,
+                point_record_length as usize,
+                read_point_records,
+                |chunk| compressor.compress_many(chunk),
+            )?;
+            compressor.done()?;
+        }
+        None => copy_point_records(
+            number_of_point_records as usize,
+            point_record_length as usize,
+            read_point_records,
+            |chunk| write.write_all(chunk),
+        )?,
+    }
+
+    // Write the EVLRs after the point records and update their offsets in the header
+    if !evlrs.is_empty() {
+        let start_of_first_evlr = write.seek(SeekFrom::Current(0))?;
+        let mut evlr_start = start_of_first_evlr;
+        for (index, evlr) in evlrs.iter().enumerate() {
+            if Some(index) == waveform_evlr_index {
+                header.start_of_waveform_data_packet_record = Some(evlr_start);
+            }
+            evlr.write_to(&mut write)?;
+            evlr_start = write.seek(SeekFrom::Current(0))?;
+        }
+        if let Some(evlr) = header.evlr.as_mut() {
+            evlr.start_of_first_evlr = start_of_first_evlr;
+            evlr.number_of_evlrs = evlrs.len() as u32;
+        }
+
+        let end_of_file = write.seek(SeekFrom::Current(0))?;
+        write.seek(SeekFrom::Start(0))?;
+        header.write_to(&mut write)?;
+        write.seek(SeekFrom::Start(end_of_file))?;
+    }
+
+    write.flush()?;
+    Ok(())
+}
+
+/// Losslessly rewrites the LAS/LAZ file at `input_path` into a new file at `output_path` using `rewrite_las`, optionally
+/// changing the LAS `version`. Whether the files are compressed is determined from their file extensions, so this can
+/// e.g. be used to compress a LAS file into a LAZ file without changing any point values
+///
+/// # Errors
+///
+/// If the input file can't be read or the output file can't be written, an error is returned. See `rewrite_las` for
+/// all other error conditions
+pub fn rewrite_las_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    input_path: P,
+    output_path: Q,
+    version: Option<(u8, u8)>,
+) -> Result<()> {
+    let input_is_compressed = path_is_compressed_las_file(input_path.as_ref())?;
+    let options = LASRewriteOptions {
+        version,
+        compressed: path_is_compressed_las_file(output_path.as_ref())?,
+    };
+    if input_path.as_ref() == output_path.as_ref() {
+        return Err(PastureIoError::InvalidArgument(format!(
+            "Can't rewrite file {} in place",
+            input_path.as_ref().display()
+        )));
+    }
+
+    let read = BufReader::new(File::open(input_path).map_err(PastureIoError::Io)?);
+    let write = BufWriter::new(File::create(output_path).map_err(PastureIoError::Io)?);
+    rewrite_las(read, input_is_compressed, write, &options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::las::{get_test_las_path, get_test_laz_path};
+
+    /// Reads the header and the raw point records from the given LAS/LAZ data
+    fn read_header_and_point_records(
+        data: &[u8],
+        is_compressed: bool,
+    ) -> Result<(raw::Header, Vec<u8>)> {
+        let mut read = Cursor::new(data);
+        let header = read_raw_las_header(&mut read)?;
+        let vlrs = read_raw_vlrs(
+            &mut read,
+            header.number_of_variable_length_records as usize,
+            false,
+        )?;
+        let number_of_point_records = header
+            .large_file
+            .as_ref()
+            .map(|large_file| large_file.number_of_point_records)
+            .unwrap_or(header.number_of_point_records as u64);
+        let mut points =
+            vec![0; number_of_point_records as usize * header.point_data_record_length as usize];
+
+        read.seek(SeekFrom::Start(header.offset_to_point_data as u64))?;
+        if is_compressed {
+            let laszip_vlr = vlrs
+                .into_iter()
+                .map(Vlr::new)
+                .find(is_laszip_vlr)
+                .expect("LASzip VLR not found");
+            let laz_vlr = LazVlr::from_buffer(&laszip_vlr.data).map_err(map_laz_err)?;
+            let mut decompressor = LasZipDecompressor::new(read, laz_vlr).map_err(map_laz_err)?;
+            decompressor.decompress_many(&mut points)?;
+        } else {
+            read.read_exact(&mut points)?;
+        }
+        Ok((header, points))
+    }
+
+    fn rewrite(data: &[u8], is_compressed: bool, options: &LASRewriteOptions) -> Result<Vec<u8>> {
+        let mut output = Cursor::new(vec![]);
+        rewrite_las(Cursor::new(data), is_compressed, &mut output, options)?;
+        Ok(output.into_inner())
+    }
+
+    #[test]
+    fn test_rewrite_las_compression_roundtrip() -> Result<()> {
+        for format in 0..=3 {
+            let input = std::fs::read(get_test_las_path(format))?;
+            let (expected_header, expected_points) = read_header_and_point_records(&input, false)?;
+
+            let compressed = rewrite(
+                &input,
+                false,
+                &LASRewriteOptions {
+                    version: None,
+                    compressed: true,
+                },
+            )?;
+            let (header, points) = read_header_and_point_records(&compressed, true)?;
+            assert_eq!(expected_points, points);
+            assert_eq!(expected_header.global_encoding, header.global_encoding);
+            assert_eq!(expected_header.guid, header.guid);
+            assert_eq!(expected_header.x_scale_factor, header.x_scale_factor);
+            assert_eq!(expected_header.x_offset, header.x_offset);
+            assert_eq!(expected_header.min_x, header.min_x);
+            assert_eq!(expected_header.max_z, header.max_z);
+
+            let decompressed = rewrite(&compressed, true, &Default::default())?;
+            let (_, points) = read_header_and_point_records(&decompressed, false)?;
+            assert_eq!(expected_points, points);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewrite_las_change_version() -> Result<()> {
+        let input = std::fs::read(get_test_laz_path(1))?;
+        let (_, expected_points) = read_header_and_point_records(&input, true)?;
+
+        let las_1_2 = rewrite(
+            &input,
+            true,
+            &LASRewriteOptions {
+                version: Some((1, 2)),
+                compressed: false,
+            },
+        )?;
+        let (header, points) = read_header_and_point_records(&las_1_2, false)?;
+        assert_eq!(Version::new(1, 2), header.version);
+        assert_eq!(227, header.header_size);
+        assert_eq!(10, header.number_of_point_records);
+        assert!(header.large_file.is_none());
+        assert_eq!(expected_points, points);
+
+        let las_1_4 = rewrite(
+            &las_1_2,
+            false,
+            &LASRewriteOptions {
+                version: Some((1, 4)),
+                compressed: false,
+            },
+        )?;
+        let (header, points) = read_header_and_point_records(&las_1_4, false)?;
+        assert_eq!(Version::new(1, 4), header.version);
+        assert_eq!(10, header.large_file.unwrap().number_of_point_records);
+        assert_eq!(expected_points, points);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rewrite_las_unsupported_version() -> Result<()> {
+        // Point record format 6 requires LAS 1.4
+        let input = std::fs::read(get_test_las_path(6))?;
+        let result = rewrite(
+            &input,
+            false,
+            &LASRewriteOptions {
+                version: Some((1, 2)),
+                compressed: false,
+            },
+        );
+        assert!(result.is_err());
+        Ok(())
+    }
+}
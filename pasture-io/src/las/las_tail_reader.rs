@@ -0,0 +1,276 @@
+use std::{
+    fs::File,
+    io::{ErrorKind, Read},
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use las_rs::Header;
+use pasture_core::containers::{InterleavedVecPointStorage, PointBuffer, PointBufferWriteable};
+
+use super::{LASDecoder, LASDecoderEvent, LASMetadata};
+use crate::base::{MetadataSnapshot, PastureIoError, PointChunk, Result};
+
+/// Default time that a [LASTailReader] waits before it checks its source for new bytes again, if there were none
+pub const DEFAULT_TAIL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Number of bytes that a [LASTailReader] reads from its source at once
+const TAIL_READ_SIZE: usize = 64 * 1024;
+
+/// Reader for LAS files that are still being written, e.g. by a scanner during an ongoing scan. It tails the file like
+/// `tail -f` and yields the newly appended points as [PointChunk]s, one per time window, which is what near-real-time
+/// monitoring of a scan needs. Each call to `next` waits until the time window has passed and returns all points that
+/// were appended in the meantime. Windows without new points don't produce empty chunks, instead the reader keeps
+/// waiting. The iteration ends after the source had no new bytes for the idle timeout (see
+/// [with_idle_timeout](LASTailReader::with_idle_timeout)), or never if there is no idle timeout.
+///
+/// The source can be any `Read`, e.g. a `File` that grows or a `TcpStream`. A read that returns no bytes means that no
+/// new bytes are available yet, so the reader polls the source again after the poll interval. Sockets should have a
+/// read timeout (see `TcpStream::set_read_timeout`), so that reads don't block for longer than the time window.
+///
+/// The number of points in the header is ignored, since writers usually update it only once the file is complete (see
+/// [LASDecoder::with_unknown_point_count]). Only uncompressed LAS files without EVLRs are supported
+/// ```no_run
+/// # use std::time::Duration;
+/// # use pasture_io::las::LASTailReader;
+/// # fn main() -> anyhow::Result<()> {
+/// let reader = LASTailReader::from_path("scan_in_progress.las", Duration::from_secs(1))?
+///     .with_idle_timeout(Some(Duration::from_secs(30)));
+/// for chunk in reader {
+///     let chunk = chunk?;
+///     println!("{} new points, {} in total", chunk.len(), chunk.source_range.end);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct LASTailReader<R: Read> {
+    source: R,
+    decoder: LASDecoder,
+    window: Duration,
+    poll_interval: Duration,
+    idle_timeout: Option<Duration>,
+    last_new_bytes: Instant,
+    read_buffer: Vec<u8>,
+    pending_points: Option<InterleavedVecPointStorage>,
+    metadata: Option<Arc<MetadataSnapshot>>,
+    next_sequence_number: u64,
+    next_point_index: usize,
+    done: bool,
+}
+
+impl LASTailReader<File> {
+    /// Creates a new `LASTailReader` that tails the LAS file at the given `path` and yields the points that were
+    /// appended during each time `window`. The file is read from the start, so the first chunk contains all points that
+    /// were in the file already
+    ///
+    /// # Errors
+    ///
+    /// If `path` does not exist or cannot be opened, an error is returned
+    pub fn from_path<P: AsRef<Path>>(path: P, window: Duration) -> Result<Self> {
+        let file = File::open(path).map_err(PastureIoError::Io)?;
+        Ok(Self::new(file, window))
+    }
+}
+
+impl<R: Read> LASTailReader<R> {
+    /// Creates a new `LASTailReader` that reads a LAS file from `source`, starting with its header, and yields the
+    /// points that were appended during each time `window`. By default, the reader polls the source every
+    /// [DEFAULT_TAIL_POLL_INTERVAL] and has no idle timeout
+    pub fn new(source: R, window: Duration) -> Self {
+        Self {
+            source,
+            decoder: LASDecoder::new().with_unknown_point_count(),
+            window,
+            poll_interval: DEFAULT_TAIL_POLL_INTERVAL,
+            idle_timeout: None,
+            last_new_bytes: Instant::now(),
+            read_buffer: vec![0; TAIL_READ_SIZE],
+            pending_points: None,
+            metadata: None,
+            next_sequence_number: 0,
+            next_point_index: 0,
+            done: false,
+        }
+    }
+
+    /// Sets the time that the reader waits before it checks the source for new bytes again, if there were none
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the time after which the iteration ends if the source had no new bytes. With `None`, the reader waits for
+    /// new bytes forever
+    pub fn with_idle_timeout(mut self, idle_timeout: Option<Duration>) -> Self {
+        self.idle_timeout = idle_timeout;
+        self
+    }
+
+    /// Returns the header of the LAS file, once it was read
+    pub fn header(&self) -> Option<&Header> {
+        self.decoder.header()
+    }
+
+    /// Returns the number of points that were yielded so far
+    pub fn points_read(&self) -> usize {
+        self.next_point_index
+    }
+
+    /// Reads all bytes that are currently available from the source and decodes them. Returns `false` if no bytes were
+    /// available
+    fn read_available_bytes(&mut self) -> Result<bool> {
+        let mut has_new_bytes = false;
+        loop {
+            match self.source.read(&mut self.read_buffer) {
+                Ok(0) => break,
+                Ok(bytes) => {
+                    self.decoder.push(&self.read_buffer[..bytes]);
+                    has_new_bytes = true;
+                    if bytes < self.read_buffer.len() {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut => {
+                    break
+                }
+                Err(e) => return Err(PastureIoError::Io(e)),
+            }
+        }
+
+        while let Some(event) = self.decoder.next_event()? {
+            match event {
+                LASDecoderEvent::Header => {
+                    let header = self.decoder.header().unwrap();
+                    let metadata = LASMetadata::from(header);
+                    self.metadata = Some(Arc::new(MetadataSnapshot::from_metadata(&metadata)));
+                }
+                LASDecoderEvent::Points(points) => match &mut self.pending_points {
+                    Some(pending_points) => pending_points.push(&points),
+                    None => self.pending_points = Some(points),
+                },
+                LASDecoderEvent::End => {}
+            }
+        }
+        Ok(has_new_bytes)
+    }
+
+    /// Returns the pending points as the next chunk, if there are any
+    fn take_chunk(&mut self) -> Option<PointChunk> {
+        let points = self.pending_points.take()?;
+        let count = points.len();
+        let chunk = PointChunk::new(
+            self.next_sequence_number,
+            self.next_point_index..self.next_point_index + count,
+            points,
+            self.metadata.clone(),
+        );
+        self.next_sequence_number += 1;
+        self.next_point_index += count;
+        Some(chunk)
+    }
+}
+
+impl<R: Read> Iterator for LASTailReader<R> {
+    type Item = Result<PointChunk>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let window_start = Instant::now();
+        loop {
+            match self.read_available_bytes() {
+                Ok(true) => self.last_new_bytes = Instant::now(),
+                Ok(false) => {}
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+
+            let now = Instant::now();
+            let is_idle = self
+                .idle_timeout
+                .map(|idle_timeout| now.duration_since(self.last_new_bytes) >= idle_timeout)
+                .unwrap_or(false);
+            if is_idle {
+                self.done = true;
+                return self.take_chunk().map(Ok);
+            }
+            if now.duration_since(window_start) >= self.window && self.pending_points.is_some() {
+                return self.take_chunk().map(Ok);
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::las::{get_test_las_path, las_header_length, test_data_classifications};
+    use pasture_core::{containers::PointBufferExt, layout::attributes::CLASSIFICATION};
+
+    /// A source whose content grows while it is read, like a file that is still being written
+    struct GrowingSource {
+        bytes: Arc<Mutex<Vec<u8>>>,
+        position: usize,
+    }
+
+    impl Read for GrowingSource {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let bytes = self.bytes.lock().unwrap();
+            let count = buf.len().min(bytes.len() - self.position);
+            buf[..count].copy_from_slice(&bytes[self.position..self.position + count]);
+            self.position += count;
+            Ok(count)
+        }
+    }
+
+    #[test]
+    fn test_tail_growing_las_file() -> Result<()> {
+        let file = std::fs::read(get_test_las_path(0))?;
+        let header_length = las_header_length(&file)?.unwrap();
+        // Point format 0 has 20 bytes per record. The last record is incomplete at first
+        let first_part = header_length + 3 * 20 + 7;
+
+        let bytes = Arc::new(Mutex::new(file[..first_part].to_vec()));
+        let mut reader = LASTailReader::new(
+            GrowingSource {
+                bytes: bytes.clone(),
+                position: 0,
+            },
+            Duration::from_millis(5),
+        )
+        .with_poll_interval(Duration::from_millis(1))
+        .with_idle_timeout(Some(Duration::from_millis(50)));
+
+        let first_chunk = reader.next().unwrap()?;
+        assert!(reader.header().is_some());
+        assert_eq!(0, first_chunk.sequence_number);
+        assert_eq!(0..3, first_chunk.source_range);
+        assert!(first_chunk.metadata.is_some());
+
+        bytes.lock().unwrap().extend_from_slice(&file[first_part..]);
+        let second_chunk = reader.next().unwrap()?;
+        assert_eq!(1, second_chunk.sequence_number);
+        assert_eq!(3..10, second_chunk.source_range);
+        assert_eq!(10, reader.points_read());
+
+        let classifications = first_chunk
+            .points
+            .iter_attribute::<u8>(&CLASSIFICATION)
+            .chain(second_chunk.points.iter_attribute::<u8>(&CLASSIFICATION))
+            .collect::<Vec<_>>();
+        assert_eq!(test_data_classifications(), classifications);
+
+        // No new bytes until the idle timeout
+        assert!(reader.next().is_none());
+        Ok(())
+    }
+}
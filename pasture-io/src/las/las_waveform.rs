@@ -0,0 +1,273 @@
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    fs::File,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use las::Header;
+
+use super::{read_raw_las_header, LASReader};
+use crate::base::{PastureIoError, Result};
+
+/// User ID of the Wave Packet Descriptor VLRs
+pub const WAVE_PACKET_DESCRIPTOR_USER_ID: &str = "LASF_Spec";
+/// Record ID of the first Wave Packet Descriptor VLR. The descriptor with index `i` has the record ID
+/// `WAVE_PACKET_DESCRIPTOR_RECORD_ID_OFFSET + i`
+pub const WAVE_PACKET_DESCRIPTOR_RECORD_ID_OFFSET: u16 = 99;
+
+const WAVE_PACKET_DESCRIPTOR_SIZE: usize = 26;
+/// Bit of the global encoding in the LAS header that is set if the waveform data is stored within the LAS file
+const GLOBAL_ENCODING_WAVEFORM_INTERNAL: u16 = 0b10;
+
+/// Description of the digitized waveforms of a LAS file, as defined by a Wave Packet Descriptor VLR of the LAS 1.3
+/// specification. The waveform of each point refers to a descriptor through its `WAVE_PACKET_DESCRIPTOR_INDEX`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WavePacketDescriptor {
+    /// Number of bits per sample (8, 16 or 32)
+    pub bits_per_sample: u8,
+    /// Compression of the waveform samples. Only uncompressed samples (zero) are supported
+    pub compression_type: u8,
+    /// Number of samples of each waveform
+    pub number_of_samples: u32,
+    /// Time between two samples, in picoseconds
+    pub temporal_sample_spacing: u32,
+    /// Gain to convert the digitized samples into volts
+    pub digitizer_gain: f64,
+    /// Offset to convert the digitized samples into volts
+    pub digitizer_offset: f64,
+}
+
+impl WavePacketDescriptor {
+    fn from_vlr_data(data: &[u8]) -> Result<Self> {
+        if data.len() != WAVE_PACKET_DESCRIPTOR_SIZE {
+            return Err(PastureIoError::InvalidData(format!(
+                "Size of Wave Packet Descriptor VLR ({} bytes) must be {}",
+                data.len(),
+                WAVE_PACKET_DESCRIPTOR_SIZE
+            )));
+        }
+        Ok(Self {
+            bits_per_sample: data[0],
+            compression_type: data[1],
+            number_of_samples: u32::from_le_bytes(data[2..6].try_into()?),
+            temporal_sample_spacing: u32::from_le_bytes(data[6..10].try_into()?),
+            digitizer_gain: f64::from_le_bytes(data[10..18].try_into()?),
+            digitizer_offset: f64::from_le_bytes(data[18..26].try_into()?),
+        })
+    }
+
+    /// Returns the data of the VLR for this descriptor
+    pub fn to_vlr_data(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(WAVE_PACKET_DESCRIPTOR_SIZE);
+        data.push(self.bits_per_sample);
+        data.push(self.compression_type);
+        data.extend_from_slice(&self.number_of_samples.to_le_bytes());
+        data.extend_from_slice(&self.temporal_sample_spacing.to_le_bytes());
+        data.extend_from_slice(&self.digitizer_gain.to_le_bytes());
+        data.extend_from_slice(&self.digitizer_offset.to_le_bytes());
+        data
+    }
+}
+
+/// Returns all Wave Packet Descriptors in the VLRs of the given `header`, by their index. If there are no descriptors,
+/// an empty map is returned
+///
+/// # Errors
+///
+/// If a Wave Packet Descriptor VLR is malformed
+pub fn wave_packet_descriptors_from_las_header(
+    header: &Header,
+) -> Result<HashMap<u8, WavePacketDescriptor>> {
+    header
+        .vlrs()
+        .iter()
+        .filter(|vlr| {
+            vlr.user_id == WAVE_PACKET_DESCRIPTOR_USER_ID
+                && (WAVE_PACKET_DESCRIPTOR_RECORD_ID_OFFSET + 1
+                    ..=WAVE_PACKET_DESCRIPTOR_RECORD_ID_OFFSET + 255)
+                    .contains(&vlr.record_id)
+        })
+        .map(|vlr| {
+            let index = (vlr.record_id - WAVE_PACKET_DESCRIPTOR_RECORD_ID_OFFSET) as u8;
+            Ok((index, WavePacketDescriptor::from_vlr_data(&vlr.data)?))
+        })
+        .collect()
+}
+
+/// Reads the digitized waveforms of the points of a LAS file with one of the point formats 4, 5, 9 or 10. The waveforms
+/// are located through the `WAVE_PACKET_DESCRIPTOR_INDEX`, `WAVEFORM_DATA_OFFSET` and `WAVEFORM_PACKET_SIZE`
+/// attributes of the points
+pub struct WaveformDataReader<R: Read + Seek> {
+    source: R,
+    data_start: u64,
+    descriptors: HashMap<u8, WavePacketDescriptor>,
+}
+
+impl WaveformDataReader<BufReader<File>> {
+    /// Creates a `WaveformDataReader` for the LAS file at `path`. The waveform data is read from the Waveform Data
+    /// Packets record within the file, or from the `.wdp` file next to it, depending on the global encoding of the LAS
+    /// header
+    ///
+    /// # Errors
+    ///
+    /// If the LAS file or the `.wdp` file can't be opened, or if the LAS file has no Wave Packet Descriptors
+    pub fn from_las_path<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let descriptors =
+            wave_packet_descriptors_from_las_header(LASReader::from_path(path)?.header())?;
+        if descriptors.is_empty() {
+            return Err(PastureIoError::UnsupportedFormat(format!(
+                "{} has no Wave Packet Descriptors",
+                path.display()
+            )));
+        }
+
+        let mut file = BufReader::new(File::open(path).map_err(PastureIoError::Io)?);
+        let raw_header = read_raw_las_header(&mut file)?;
+        if raw_header.global_encoding & GLOBAL_ENCODING_WAVEFORM_INTERNAL != 0 {
+            let data_start = raw_header
+                .start_of_waveform_data_packet_record
+                .ok_or_else(|| {
+                    PastureIoError::InvalidData(format!(
+                        "{} has internal waveform data, but no Waveform Data Packets record",
+                        path.display()
+                    ))
+                })?;
+            Ok(Self::new(file, data_start, descriptors))
+        } else {
+            let wdp_file = File::open(path.with_extension("wdp")).map_err(PastureIoError::Io)?;
+            Ok(Self::new(BufReader::new(wdp_file), 0, descriptors))
+        }
+    }
+}
+
+impl<R: Read + Seek> WaveformDataReader<R> {
+    /// Creates a `WaveformDataReader` that reads waveform data from `source`. The waveform data offsets of the points
+    /// are relative to `data_start`, which is the start of the Waveform Data Packets record (including its header)
+    pub fn new(source: R, data_start: u64, descriptors: HashMap<u8, WavePacketDescriptor>) -> Self {
+        Self {
+            source,
+            data_start,
+            descriptors,
+        }
+    }
+
+    /// Returns the Wave Packet Descriptors by their index
+    pub fn descriptors(&self) -> &HashMap<u8, WavePacketDescriptor> {
+        &self.descriptors
+    }
+
+    /// Reads the waveform with the given wave packet `descriptor_index`, `byte_offset` and `packet_size` (the values
+    /// of the waveform attributes of a point) and returns its samples in volts
+    ///
+    /// # Errors
+    ///
+    /// If there is no descriptor with `descriptor_index`, if the descriptor uses compression or an unsupported number
+    /// of bits per sample, or if an I/O error occurs
+    pub fn read_waveform(
+        &mut self,
+        descriptor_index: u8,
+        byte_offset: u64,
+        packet_size: u32,
+    ) -> Result<Vec<f64>> {
+        let descriptor = *self.descriptors.get(&descriptor_index).ok_or_else(|| {
+            PastureIoError::InvalidArgument(format!(
+                "There is no Wave Packet Descriptor {}",
+                descriptor_index
+            ))
+        })?;
+        if descriptor.compression_type != 0 {
+            return Err(PastureIoError::UnsupportedFormat(format!(
+                "Waveform compression type {}",
+                descriptor.compression_type
+            )));
+        }
+        let bytes_per_sample = match descriptor.bits_per_sample {
+            8 | 16 | 32 => descriptor.bits_per_sample as usize / 8,
+            bits => {
+                return Err(PastureIoError::UnsupportedFormat(format!(
+                    "Waveforms with {} bits per sample",
+                    bits
+                )))
+            }
+        };
+
+        let mut data = vec![0; packet_size as usize];
+        self.source
+            .seek(SeekFrom::Start(self.data_start + byte_offset))
+            .map_err(PastureIoError::Io)?;
+        self.source
+            .read_exact(&mut data)
+            .map_err(PastureIoError::Io)?;
+
+        Ok(data
+            .chunks_exact(bytes_per_sample)
+            .take(descriptor.number_of_samples as usize)
+            .map(|sample| {
+                let raw_sample = match bytes_per_sample {
+                    1 => sample[0] as f64,
+                    2 => u16::from_le_bytes([sample[0], sample[1]]) as f64,
+                    _ => u32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]) as f64,
+                };
+                descriptor.digitizer_offset + descriptor.digitizer_gain * raw_sample
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use las::{Builder, Vlr};
+
+    use super::*;
+
+    fn test_descriptor() -> WavePacketDescriptor {
+        WavePacketDescriptor {
+            bits_per_sample: 16,
+            compression_type: 0,
+            number_of_samples: 4,
+            temporal_sample_spacing: 1000,
+            digitizer_gain: 0.5,
+            digitizer_offset: -1.0,
+        }
+    }
+
+    #[test]
+    fn test_wave_packet_descriptors_from_las_header() -> Result<()> {
+        let mut builder = Builder::from((1, 4));
+        builder.vlrs.push(Vlr {
+            user_id: WAVE_PACKET_DESCRIPTOR_USER_ID.to_owned(),
+            record_id: WAVE_PACKET_DESCRIPTOR_RECORD_ID_OFFSET + 3,
+            description: "Wave Packet Descriptor".to_owned(),
+            data: test_descriptor().to_vlr_data(),
+        });
+        let header = builder.into_header()?;
+
+        let descriptors = wave_packet_descriptors_from_las_header(&header)?;
+        assert_eq!(1, descriptors.len());
+        assert_eq!(Some(&test_descriptor()), descriptors.get(&3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_waveform() -> Result<()> {
+        // A header of 10 bytes, followed by two waveforms with four 16-bit samples each
+        let mut data = vec![0_u8; 10];
+        for sample in [0_u16, 2, 10, 4, 6, 8, 100, 2].iter() {
+            data.extend_from_slice(&sample.to_le_bytes());
+        }
+        let mut descriptors = HashMap::new();
+        descriptors.insert(1, test_descriptor());
+        let mut reader = WaveformDataReader::new(Cursor::new(data), 10, descriptors);
+
+        assert_eq!(vec![2.0, 3.0, 49.0, 0.0], reader.read_waveform(1, 8, 8)?);
+        assert_eq!(vec![-1.0, 0.0, 4.0, 1.0], reader.read_waveform(1, 0, 8)?);
+        assert!(reader.read_waveform(2, 0, 8).is_err());
+        assert!(reader.read_waveform(1, 8, 16).is_err());
+        Ok(())
+    }
+}
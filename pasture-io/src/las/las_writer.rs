@@ -1,23 +1,50 @@
-use std::{fs::File, io::BufWriter, io::Seek, io::Write, path::Path};
-
-use anyhow::Result;
-use pasture_core::{containers::PointBuffer, layout::PointLayout};
-
-use crate::base::PointWriter;
-
-use super::{path_is_compressed_las_file, RawLASWriter, RawLAZWriter};
-
-/// `PointWriter` implementation for LAS/LAZ files
+use std::{
+    fs::File,
+    io::BufWriter,
+    io::Seek,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, LocalFrame, PointBuffer, PointBufferWriteable},
+    layout::{
+        attributes::{COLOR_RGB, POSITION_3D},
+        PointAttributeDataType, PointLayout,
+    },
+};
+
+use crate::base::{Capabilities, PastureIoError, PointWriter, Result};
+
+use super::{
+    normalize_color_bit_depth, path_is_compressed_las_file, ColorBitDepth, LASWriterBase,
+    RawLASWriter, RawLAZWriter,
+};
+
+trait AnyLASWriter: PointWriter + LASWriterBase {}
+
+impl<T: PointWriter + LASWriterBase> AnyLASWriter for T {}
+
+/// `PointWriter` implementation for LAS/LAZ files. Dropping the `LASWriter` writes the final header, so that the file
+/// is valid even if the points are not written completely, e.g. because the pipeline that produces the points fails.
+/// Use [finalize](LASWriter::finalize) to handle errors while writing the final header, or
+/// [abort](LASWriter::abort) to remove the partially written file instead
 pub struct LASWriter {
-    writer: Box<dyn PointWriter>,
+    writer: Box<dyn AnyLASWriter>,
+    color_bit_depth: ColorBitDepth,
+    detected_color_bit_depth: Option<ColorBitDepth>,
+    local_frame: Option<LocalFrame>,
+    path: Option<PathBuf>,
 }
 
 impl LASWriter {
     /// Creates a new 'LASWriter` from the given path and LAS header
     pub fn from_path_and_header<P: AsRef<Path>>(path: P, header: las::Header) -> Result<Self> {
         let is_compressed = path_is_compressed_las_file(path.as_ref())?;
-        let writer = BufWriter::new(File::create(path)?);
-        Self::from_writer_and_header(writer, header, is_compressed)
+        let writer = BufWriter::new(File::create(path.as_ref()).map_err(PastureIoError::Io)?);
+        let mut las_writer = Self::from_writer_and_header(writer, header, is_compressed)?;
+        las_writer.path = Some(path.as_ref().to_owned());
+        Ok(las_writer)
     }
 
     /// Creates a new 'LASWriter` from the given writer and LAS header
@@ -26,18 +53,173 @@ impl LASWriter {
         header: las::Header,
         is_compressed: bool,
     ) -> Result<Self> {
-        let raw_writer: Box<dyn PointWriter> = if is_compressed {
+        let raw_writer: Box<dyn AnyLASWriter> = if is_compressed {
             Box::new(RawLAZWriter::from_write_and_header(writer, header)?)
         } else {
             Box::new(RawLASWriter::from_write_and_header(writer, header)?)
         };
-        Ok(Self { writer: raw_writer })
+        Ok(Self {
+            writer: raw_writer,
+            color_bit_depth: ColorBitDepth::SixteenBit,
+            detected_color_bit_depth: None,
+            local_frame: None,
+            path: None,
+        })
+    }
+
+    /// Sets the bit depth of the colors in the point buffers that are passed to `write`. LAS files always store
+    /// colors with 16 bits per channel, so 8-bit colors are scaled up to 16 bits during writing. With
+    /// `ColorBitDepth::Auto`, the bit depth is detected from the colors of the first non-empty buffer that is written
+    /// (see `detect_color_bit_depth`). The default value is `ColorBitDepth::SixteenBit`, which writes the colors as
+    /// they are
+    pub fn set_color_bit_depth(&mut self, color_bit_depth: ColorBitDepth) {
+        self.color_bit_depth = color_bit_depth;
+        self.detected_color_bit_depth = None;
+    }
+
+    /// Returns the bit depth of the colors in the point buffers that are passed to `write`, as set by
+    /// `set_color_bit_depth`
+    pub fn color_bit_depth(&self) -> ColorBitDepth {
+        self.color_bit_depth
+    }
+
+    /// Sets the local coordinate frame of the point buffers that are passed to `write`. If a `LocalFrame` is set, the
+    /// positions of all buffers whose `POSITION_3D` attribute has the datatype `Vec3f32` are interpreted relative to the
+    /// origin of the frame and converted into world space before writing. Buffers with the default `Vec3f64` positions
+    /// are not affected
+    pub fn set_local_frame(&mut self, local_frame: Option<LocalFrame>) {
+        self.local_frame = local_frame;
+    }
+
+    /// Returns the local coordinate frame of the point buffers that are passed to `write`, as set by `set_local_frame`
+    pub fn local_frame(&self) -> Option<&LocalFrame> {
+        self.local_frame.as_ref()
+    }
+
+    /// Writes the given raw LAS point records without parsing them, e.g. point records that were read with
+    /// `LASReader::read_raw_points`. The point records are copied as-is, so they must be in the point record format
+    /// of the header of this `LASWriter` and use its scale and offset. The bounds and point counts of the header are
+    /// updated from the point records
+    ///
+    /// # Errors
+    ///
+    /// If the size of `raw_points` is no multiple of the point record length of the header, or if an I/O error
+    /// occurs, an error is returned
+    pub fn write_raw_points(&mut self, raw_points: &[u8]) -> Result<()> {
+        self.writer.write_raw_points(raw_points)
+    }
+
+    /// Compresses the points on a background thread while the next points are encoded, with at most
+    /// `max_queued_chunks` chunks of points waiting for compression. Once the queue is full, `write` blocks until the
+    /// background thread catches up, which bounds the memory usage. Only affects LAZ files, uncompressed LAS files are
+    /// written as before
+    ///
+    /// There is a single background thread, which compresses the LAZ chunks one after another. This overlaps the
+    /// encoding with the compression, but if compressing is slower than encoding, writing is still limited by the
+    /// speed of a single thread. Use [start_parallel_compression](LASWriter::start_parallel_compression) to compress
+    /// on multiple threads
+    ///
+    /// # Errors
+    ///
+    /// If the background thread can't be started, an error is returned. Errors during compression are returned by the
+    /// next call to `write`
+    ///
+    /// # Panics
+    ///
+    /// If `max_queued_chunks` is zero
+    pub fn start_background_compression(&mut self, max_queued_chunks: usize) -> Result<()> {
+        self.writer.start_background_compression(max_queued_chunks)
+    }
+
+    /// Compresses the points on a pool of `num_threads` worker threads while the next points are encoded. The points
+    /// are collected into batches of one LAZ chunk per worker, the workers compress the chunks of a batch in parallel
+    /// and the compressed chunks are written in order. At most `max_queued_batches` batches wait for compression, once
+    /// the queue is full, `write` blocks until the workers catch up. Only affects LAZ files, uncompressed LAS files are
+    /// written as before
+    ///
+    /// # Errors
+    ///
+    /// If points were written before, an error is returned, because the LAZ chunk that was started on the current
+    /// thread can't be continued on the worker threads. If the threads can't be started, an error is returned as well.
+    /// Errors during compression are returned by the next call to `write`
+    ///
+    /// # Panics
+    ///
+    /// If `num_threads` or `max_queued_batches` is zero
+    pub fn start_parallel_compression(
+        &mut self,
+        num_threads: usize,
+        max_queued_batches: usize,
+    ) -> Result<()> {
+        self.writer
+            .start_parallel_compression(num_threads, max_queued_batches)
+    }
+
+    /// Finishes the file by writing the final header with the point counts and bounds of all written points. This is
+    /// what dropping the `LASWriter` does as well, but errors are returned instead of causing a panic
+    ///
+    /// # Errors
+    ///
+    /// If an I/O error occurs while writing the header or while compressing the last points, an error is returned
+    pub fn finalize(mut self) -> Result<()> {
+        self.writer.finalize()
+    }
+
+    /// Stops writing without finishing the file. If this `LASWriter` was created with `from_path_and_header`, the
+    /// partially written file is removed, so that no incomplete file is left behind when the points can't be produced
+    /// completely. For other writers, the written data is left as it is
+    ///
+    /// # Errors
+    ///
+    /// If the file can't be removed, an error is returned
+    pub fn abort(mut self) -> Result<()> {
+        self.writer.discard();
+        let path = self.path.take();
+        // Closes the file before removing it
+        drop(self);
+        if let Some(path) = path {
+            std::fs::remove_file(path).map_err(PastureIoError::Io)?;
+        }
+        Ok(())
     }
 }
 
 impl PointWriter for LASWriter {
     fn write(&mut self, points: &dyn PointBuffer) -> Result<()> {
-        self.writer.write(points)
+        let has_local_positions = points
+            .point_layout()
+            .get_attribute_by_name(POSITION_3D.name())
+            .map(|attribute| attribute.datatype() == PointAttributeDataType::Vec3f32)
+            .unwrap_or(false);
+        let world_space_points;
+        let points = match &self.local_frame {
+            Some(local_frame) if has_local_positions => {
+                world_space_points = local_frame.buffer_to_global(points);
+                &world_space_points as &dyn PointBuffer
+            }
+            _ => points,
+        };
+
+        if self.color_bit_depth == ColorBitDepth::SixteenBit
+            || !points
+                .point_layout()
+                .has_attribute_with_name(COLOR_RGB.name())
+        {
+            return self.writer.write(points);
+        }
+
+        let mut converted_points =
+            InterleavedVecPointStorage::with_capacity(points.len(), points.point_layout().clone());
+        converted_points.push(points);
+        let color_bit_depth = self
+            .detected_color_bit_depth
+            .unwrap_or(self.color_bit_depth);
+        let color_bit_depth =
+            normalize_color_bit_depth(&mut converted_points, 0..points.len(), color_bit_depth);
+        if !points.is_empty() {
+            self.detected_color_bit_depth = Some(color_bit_depth);
+        }
+        self.writer.write(&converted_points)
     }
 
     fn flush(&mut self) -> Result<()> {
@@ -47,6 +229,10 @@ impl PointWriter for LASWriter {
     fn get_default_point_layout(&self) -> &PointLayout {
         self.writer.get_default_point_layout()
     }
+
+    fn capabilities(&self) -> Capabilities {
+        self.writer.capabilities()
+    }
 }
 
 #[cfg(test)]
@@ -55,7 +241,8 @@ mod tests {
 
     use las::{point::Format, Builder};
     use pasture_core::{
-        containers::InterleavedVecPointStorage, containers::PointBufferExt, layout::PointType,
+        containers::InterleavedVecPointStorage, containers::PointBufferExt,
+        layout::attributes::POSITION_3D, layout::PointAttributeDataType, layout::PointType,
         nalgebra::Vector3,
     };
     use scopeguard::defer;
@@ -63,8 +250,9 @@ mod tests {
     use crate::{
         base::PointReader,
         las::{
-            LASReader, LasPointFormat0, LasPointFormat1, LasPointFormat2, LasPointFormat3,
-            LasPointFormat4, LasPointFormat5,
+            add_extra_bytes_to_las_header, get_test_las_path, ExtraBytesDescriptor, LASReader,
+            LasPointFormat0, LasPointFormat1, LasPointFormat2, LasPointFormat3, LasPointFormat4,
+            LasPointFormat5,
         },
     };
     use pasture_derive::PointType;
@@ -1068,4 +1256,314 @@ mod tests {
 
         Ok(())
     }
+
+    #[repr(C, packed)]
+    #[derive(Debug, Clone, Copy, PointType)]
+    struct TestPointWithExtraBytes {
+        #[pasture(BUILTIN_POSITION_3D)]
+        pub position: Vector3<f64>,
+        #[pasture(attribute = "HeightAboveGround")]
+        pub height_above_ground: f64,
+    }
+
+    #[test]
+    fn test_write_las_extra_bytes() -> Result<()> {
+        let source_points = vec![
+            TestPointWithExtraBytes {
+                position: Vector3::new(1.0, 2.0, 3.0),
+                height_above_ground: 0.5,
+            },
+            TestPointWithExtraBytes {
+                position: Vector3::new(4.0, 5.0, 6.0),
+                height_above_ground: 12.25,
+            },
+        ];
+        let source_point_buffer = prepare_point_buffer(&source_points);
+
+        for extension in ["las", "laz"].iter() {
+            let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            test_file_path.push(format!("test_write_las_extra_bytes.{}", extension));
+
+            defer! {
+                std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+            }
+
+            let header = add_extra_bytes_to_las_header(
+                &Builder::from((1, 4)).into_header()?,
+                &[ExtraBytesDescriptor::new(
+                    "HeightAboveGround",
+                    PointAttributeDataType::F64,
+                )],
+            )?;
+
+            {
+                let mut writer = LASWriter::from_path_and_header(&test_file_path, header)?;
+                writer.write(&source_point_buffer)?;
+            }
+
+            {
+                use las::Read;
+
+                let mut reader = las::Reader::from_path(&test_file_path)?;
+                let extra_bytes = reader
+                    .points()
+                    .map(|point| point.map(|point| point.extra_bytes))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let expected_extra_bytes = source_points
+                    .iter()
+                    .map(|point| ({ point.height_above_ground }).to_le_bytes().to_vec())
+                    .collect::<Vec<_>>();
+                assert_eq!(expected_extra_bytes, extra_bytes);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_raw_points() -> Result<()> {
+        for format in 0..=3 {
+            let mut reader = LASReader::from_path(get_test_las_path(format))?;
+            let source_header = reader.header().clone();
+            let point_record_length = source_header.point_format().len() as usize;
+            let point_count = source_header.number_of_points() as usize;
+            let mut source_points = vec![0; point_count * point_record_length];
+            assert_eq!(
+                point_count,
+                reader.read_raw_points(&mut source_points, point_count)?
+            );
+
+            for extension in ["las", "laz"].iter() {
+                let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+                test_file_path.push(format!("test_write_raw_points_{}.{}", format, extension));
+
+                defer! {
+                    std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+                }
+
+                {
+                    let mut writer =
+                        LASWriter::from_path_and_header(&test_file_path, source_header.clone())?;
+                    // Write in two parts to make sure that the point counts are accumulated
+                    let split = 3 * point_record_length;
+                    writer.write_raw_points(&source_points[..split])?;
+                    writer.write_raw_points(&source_points[split..])?;
+                }
+
+                {
+                    let mut reader = LASReader::from_path(&test_file_path)?;
+                    assert_eq!(point_count as u64, reader.header().number_of_points());
+                    let bounds = reader.header().bounds();
+                    let positions = LASReader::from_path(get_test_las_path(format))?
+                        .read(point_count)?
+                        .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+                        .collect::<Vec<_>>();
+                    assert_eq!(
+                        positions.iter().map(|p| p.x).fold(f64::MAX, f64::min),
+                        bounds.min.x
+                    );
+                    assert_eq!(
+                        positions.iter().map(|p| p.z).fold(f64::MIN, f64::max),
+                        bounds.max.z
+                    );
+
+                    let mut read_points = vec![0; point_count * point_record_length];
+                    assert_eq!(
+                        point_count,
+                        reader.read_raw_points(&mut read_points, point_count + 1)?
+                    );
+                    assert_eq!(source_points, read_points);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_with_background_compression() -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(1))?;
+        let header = reader.header().clone();
+        let points = reader.read(10)?;
+
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_write_with_background_compression.laz");
+
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        {
+            let mut writer = LASWriter::from_path_and_header(&test_file_path, header)?;
+            writer.start_background_compression(1)?;
+            for _ in 0..3 {
+                writer.write(points.as_ref())?;
+            }
+        }
+
+        {
+            let mut reader = LASReader::from_path(&test_file_path)?;
+            assert_eq!(30, reader.header().number_of_points());
+            let read_points = reader.read(30)?;
+            let expected = points.iter_point::<LasPointFormat1>().collect::<Vec<_>>();
+            let actual = read_points
+                .iter_point::<LasPointFormat1>()
+                .collect::<Vec<_>>();
+            for chunk in actual.chunks(10) {
+                assert_eq!(expected, chunk);
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_with_parallel_compression() -> Result<()> {
+        let mut reader = LASReader::from_path(get_test_las_path(1))?;
+        let header = reader.header().clone();
+        let points = reader.read(10)?;
+        // More than one batch of 50k points per worker, so that full batches and the rest are compressed
+        let repetitions = 12_000;
+
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_write_with_parallel_compression.laz");
+
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        {
+            let mut writer = LASWriter::from_path_and_header(&test_file_path, header.clone())?;
+            writer.start_parallel_compression(2, 1)?;
+            for _ in 0..repetitions {
+                writer.write(points.as_ref())?;
+            }
+            writer.finalize()?;
+        }
+
+        {
+            let mut reader = LASReader::from_path(&test_file_path)?;
+            assert_eq!(10 * repetitions, reader.header().number_of_points());
+            let read_points = reader.read(10 * repetitions as usize)?;
+            let expected = points.iter_point::<LasPointFormat1>().collect::<Vec<_>>();
+            let actual = read_points
+                .iter_point::<LasPointFormat1>()
+                .collect::<Vec<_>>();
+            for chunk in actual.chunks(10) {
+                assert_eq!(expected, chunk);
+            }
+        }
+
+        // Parallel compression can't continue the chunk that was started on the current thread
+        let mut writer = LASWriter::from_path_and_header(&test_file_path, header)?;
+        writer.write(points.as_ref())?;
+        assert!(matches!(
+            writer.start_parallel_compression(2, 1),
+            Err(PastureIoError::InvalidArgument(_))
+        ));
+        writer.finalize()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_from_local_frame() -> Result<()> {
+        let source_points = get_test_points_las_format_0()
+            .into_iter()
+            .map(|mut point| {
+                point.position = point.position + Vector3::new(300_000.125, 1_000_000.5, 12.25);
+                point
+            })
+            .collect::<Vec<_>>();
+        let source_point_buffer = prepare_point_buffer(&source_points);
+        let local_frame = LocalFrame::new(Vector3::new(300_000.0, 1_000_000.0, 0.0));
+        let local_point_buffer = local_frame.buffer_to_local(&source_point_buffer);
+
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_write_from_local_frame.las");
+
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        let mut las_header_builder = Builder::from((1, 4));
+        las_header_builder.point_format = Format::new(0)?;
+
+        {
+            let mut writer = LASWriter::from_path_and_header(
+                &test_file_path,
+                las_header_builder.into_header().unwrap(),
+            )?;
+            writer.set_local_frame(Some(local_frame));
+            writer.write(&local_point_buffer)?;
+        }
+
+        {
+            let mut reader = LASReader::from_path(&test_file_path)?;
+            let read_points_buffer = reader.read(source_points.len())?;
+            let read_points: Vec<LasPointFormat0> = read_points_buffer.iter_point().collect();
+
+            assert_eq!(read_points, source_points);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_raw_points_with_wrong_size() -> Result<()> {
+        let mut writer = LASWriter::from_writer_and_header(
+            std::io::Cursor::new(vec![]),
+            Builder::from((1, 4)).into_header()?,
+            false,
+        )?;
+        assert!(writer.write_raw_points(&[0; 7]).is_err());
+        Ok(())
+    }
+    #[test]
+    fn test_finalize_laz() -> Result<()> {
+        let source_points = get_test_points_las_format_0();
+        let source_point_buffer = prepare_point_buffer(&source_points);
+
+        let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        test_file_path.push("test_finalize_laz.laz");
+        defer! {
+            std::fs::remove_file(&test_file_path).expect("Removing test file failed!");
+        }
+
+        let mut las_header_builder = Builder::from((1, 4));
+        las_header_builder.point_format = Format::new(0)?;
+        let mut writer =
+            LASWriter::from_path_and_header(&test_file_path, las_header_builder.into_header()?)?;
+        writer.write(&source_point_buffer)?;
+        writer.finalize()?;
+
+        let mut reader = LASReader::from_path(&test_file_path)?;
+        assert_eq!(source_points.len(), reader.remaining_points());
+        let read_points: Vec<LasPointFormat0> =
+            reader.read(source_points.len())?.iter_point().collect();
+        assert_eq!(source_points, read_points);
+        Ok(())
+    }
+
+    #[test]
+    fn test_abort_removes_file() -> Result<()> {
+        let source_points = get_test_points_las_format_0();
+        let source_point_buffer = prepare_point_buffer(&source_points);
+
+        for extension in &["las", "laz"] {
+            let mut test_file_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+            test_file_path.push(format!("test_abort_removes_file.{}", extension));
+
+            let mut las_header_builder = Builder::from((1, 4));
+            las_header_builder.point_format = Format::new(0)?;
+            let mut writer = LASWriter::from_path_and_header(
+                &test_file_path,
+                las_header_builder.into_header()?,
+            )?;
+            writer.write(&source_point_buffer)?;
+            writer.abort()?;
+            assert!(!test_file_path.exists());
+        }
+        Ok(())
+    }
 }
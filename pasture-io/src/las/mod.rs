@@ -10,15 +10,66 @@ pub use self::las_layout::*;
 mod las_types;
 pub use self::las_types::*;
 
+mod las_raw_types;
+pub use self::las_raw_types::*;
+
 mod las_metadata;
 pub use self::las_metadata::*;
 
+mod las_crs;
+pub use self::las_crs::*;
+
+mod las_provenance;
+pub use self::las_provenance::*;
+
+mod las_extra_bytes;
+pub use self::las_extra_bytes::*;
+
+mod las_rewrite;
+pub use self::las_rewrite::*;
+
+mod las_color;
+pub use self::las_color::*;
+
+mod las_lazy;
+pub use self::las_lazy::*;
+
+mod las_parallel_reader;
+pub use self::las_parallel_reader::*;
+
+mod las_waveform;
+pub use self::las_waveform::*;
+
+mod record_decoder;
+pub use self::record_decoder::*;
+
+mod record_encoder;
+pub use self::record_encoder::*;
+
+mod las_codec;
+pub use self::las_codec::*;
+
+#[cfg(feature = "async")]
+mod las_async_reader;
+#[cfg(feature = "async")]
+pub use self::las_async_reader::*;
+
+mod las_tail_reader;
+pub use self::las_tail_reader::*;
+
+mod las_copc;
+pub use self::las_copc::read_copc_hierarchy;
+pub(crate) use self::las_copc::*;
+
 mod raw_readers;
 pub(crate) use self::raw_readers::*;
 
 mod raw_writers;
 pub(crate) use self::raw_writers::*;
 
+mod background_compressor;
+pub(crate) use self::background_compressor::*;
+
 #[cfg(test)]
 mod test_util;
 #[cfg(test)]
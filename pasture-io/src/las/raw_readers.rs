@@ -1,9 +1,8 @@
 use std::io::{Cursor, Read, Seek, SeekFrom};
 
-use anyhow::{anyhow, Result};
-use byteorder::{LittleEndian, NativeEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{LittleEndian, ReadBytesExt};
 use las_rs::{point::Format, Header};
-use las_rs::{raw, Builder, Vlr};
+use las_rs::{raw, Vlr};
 use laz::{
     las::laszip::{LASZIP_RECORD_ID, LASZIP_USER_ID},
     LasZipDecompressor,
@@ -21,13 +20,53 @@ use pasture_core::{
 };
 
 use super::{
-    map_laz_err, point_layout_from_las_point_format, BitAttributes, BitAttributesExtended,
-    BitAttributesRegular, LASMetadata,
+    is_copc_info_vlr, las_header_length, map_laz_err, parse_las_header,
+    point_layout_from_las_point_format, read_copc_point_blocks, BitAttributes,
+    BitAttributesExtended, BitAttributesRegular, CopcInfo, LASMetadata, LASRecordDecoder,
+    MIN_LAS_HEADER_LENGTH,
 };
-use crate::base::{PointReader, SeekToPoint};
+use crate::base::{Capabilities, PastureIoError, PointBlock, PointReader, Result, SeekToPoint};
+
+/// Reads the raw LAS header from the start of `read`. Malformed headers and unsupported LAS versions are reported as
+/// a `PastureIoError`
+pub(crate) fn read_raw_las_header<T: Read>(read: &mut T) -> Result<raw::Header> {
+    let raw_header = raw::Header::read_from(read).map_err(|err| PastureIoError::CorruptHeader {
+        offset: 0,
+        message: err.to_string(),
+    })?;
+    if raw_header.version.major != 1 || raw_header.version.minor > 4 {
+        return Err(PastureIoError::UnsupportedVersion {
+            major: raw_header.version.major as u32,
+            minor: raw_header.version.minor as u32,
+        });
+    }
+    Ok(raw_header)
+}
+
+/// Reads the LAS header and all VLRs from the start of `read` and parses them with [parse_las_header]. Returns the
+/// header and the offset to the point records, at which `read` is positioned afterwards
+pub(crate) fn read_las_header<T: Read>(read: &mut T) -> Result<(Header, u64)> {
+    fn read_header_bytes<T: Read>(read: &mut T, bytes: &mut [u8]) -> Result<()> {
+        read.read_exact(bytes).map_err(|err| match err.kind() {
+            std::io::ErrorKind::UnexpectedEof => PastureIoError::CorruptHeader {
+                offset: 0,
+                message: "File is too small to contain the LAS header".into(),
+            },
+            _ => PastureIoError::Io(err),
+        })?;
+        Ok(())
+    }
+
+    let mut bytes = vec![0; MIN_LAS_HEADER_LENGTH];
+    read_header_bytes(read, &mut bytes)?;
+    let header_length = las_header_length(&bytes)?.unwrap();
+    bytes.resize(header_length, 0);
+    read_header_bytes(read, &mut bytes[MIN_LAS_HEADER_LENGTH..])?;
+    Ok((parse_las_header(&bytes)?, header_length as u64))
+}
 
 /// Is the given VLR the LASzip VLR? Function taken from the `las` crate because it is not exported there
-fn is_laszip_vlr(vlr: &Vlr) -> bool {
+pub(crate) fn is_laszip_vlr(vlr: &Vlr) -> bool {
     if &vlr.user_id == LASZIP_USER_ID && vlr.record_id == LASZIP_RECORD_ID {
         true
     } else {
@@ -36,9 +75,39 @@ fn is_laszip_vlr(vlr: &Vlr) -> bool {
 }
 
 pub(crate) trait LASReaderBase {
-    /// Returns the remaining number of points in the underyling `LASReaderBase`
+    /// Returns the remaining number of points in the underyling `LASReaderBase`, taking the decimation into account
     fn remaining_points(&self) -> usize;
     fn header(&self) -> &Header;
+    /// Reads the next `count` point records into `buffer` exactly as they are stored in the LAS file, i.e. without
+    /// parsing them. Returns the number of point records that were read
+    fn read_raw_points(&mut self, buffer: &mut [u8], count: usize) -> Result<usize>;
+    /// Sets the decimation step, i.e. after each point record that is read, the next `step - 1` point records are
+    /// skipped. A `step` of 1 reads all point records
+    fn set_decimation(&mut self, step: usize);
+}
+
+/// Returns the number of points that are read from `remaining_records` point records with the given decimation `step`
+fn decimated_point_count(remaining_records: usize, step: usize) -> usize {
+    (remaining_records + step - 1) / step
+}
+
+/// Returns the number of point records that are skipped after reading the record at `point_index` with the given
+/// decimation `step`, which is less than `step - 1` at the end of the file
+fn records_to_skip(point_index: usize, point_count: usize, step: usize) -> usize {
+    usize::min(step - 1, point_count - point_index - 1)
+}
+
+/// Returns the number of bytes that `read_raw_points` reads for `count` point records of `size_of_point_in_file`
+/// bytes each. Panics if `buffer_size` is too small
+fn raw_points_size(count: usize, size_of_point_in_file: u64, buffer_size: usize) -> usize {
+    let num_bytes = count * size_of_point_in_file as usize;
+    if buffer_size < num_bytes {
+        panic!(
+            "read_raw_points: Buffer is too small for {} point records ({} bytes required, but buffer has {} bytes)",
+            count, num_bytes, buffer_size
+        );
+    }
+    num_bytes
 }
 
 pub(crate) struct RawLASReader<T: Read + Seek> {
@@ -50,28 +119,26 @@ pub(crate) struct RawLASReader<T: Read + Seek> {
     point_scales: Vector3<f64>,
     offset_to_first_point_in_file: u64,
     size_of_point_in_file: u64,
+    record_decoder: LASRecordDecoder,
+    decimation: usize,
     //TODO Add an option to not convert the position fields into world space
 }
 
 impl<T: Read + Seek> RawLASReader<T> {
     pub fn from_read(mut read: T) -> Result<Self> {
-        let raw_header = raw::Header::read_from(&mut read)?;
-        let offset_to_first_point_in_file = raw_header.offset_to_point_data as u64;
-        let size_of_point_in_file = raw_header.point_data_record_length as u64;
+        let (header, offset_to_first_point_in_file) = read_las_header(&mut read)?;
+        let size_of_point_in_file = header.point_format().len() as u64;
+        let transforms = header.transforms();
         let point_offsets = Vector3::new(
-            raw_header.x_offset,
-            raw_header.y_offset,
-            raw_header.z_offset,
-        );
-        let point_scales = Vector3::new(
-            raw_header.x_scale_factor,
-            raw_header.y_scale_factor,
-            raw_header.z_scale_factor,
+            transforms.x.offset,
+            transforms.y.offset,
+            transforms.z.offset,
         );
+        let point_scales = Vector3::new(transforms.x.scale, transforms.y.scale, transforms.z.scale);
 
-        let header = Header::from_raw(raw_header)?;
         let metadata: LASMetadata = header.clone().into();
         let point_layout = point_layout_from_las_point_format(header.point_format())?;
+        let record_decoder = LASRecordDecoder::from_header(&header)?;
 
         read.seek(SeekFrom::Start(offset_to_first_point_in_file as u64))?;
 
@@ -84,115 +151,49 @@ impl<T: Read + Seek> RawLASReader<T> {
             point_scales,
             offset_to_first_point_in_file,
             size_of_point_in_file,
+            record_decoder,
+            decimation: 1,
         })
     }
 
+    /// Reads the next `count` point records into `buffer`, skipping records according to the decimation, and advances
+    /// the current point index past all records that were read or skipped
+    fn read_records(&mut self, buffer: &mut [u8], count: usize) -> Result<()> {
+        let size_of_point_in_file = self.size_of_point_in_file as usize;
+        if self.decimation == 1 {
+            self.reader
+                .read_exact(&mut buffer[0..count * size_of_point_in_file])?;
+            self.current_point_index += count;
+            return Ok(());
+        }
+
+        for record in buffer.chunks_exact_mut(size_of_point_in_file).take(count) {
+            self.reader.read_exact(record)?;
+            let skipped_records = records_to_skip(
+                self.current_point_index,
+                self.metadata.point_count(),
+                self.decimation,
+            );
+            self.reader.seek(SeekFrom::Current(
+                (skipped_records * size_of_point_in_file) as i64,
+            ))?;
+            self.current_point_index += 1 + skipped_records;
+        }
+        Ok(())
+    }
+
     fn read_chunk_default_layout(
         &mut self,
         chunk_buffer: &mut [u8],
+        record_buffer: &mut [u8],
         num_points_in_chunk: usize,
     ) -> Result<()> {
-        let mut buffer_cursor = Cursor::new(chunk_buffer);
-
-        let format = Format::new(self.metadata.point_format())?;
-
-        let offset_to_first_point_in_file = self.reader.seek(SeekFrom::Current(0))?;
-
-        for point_index in 0..num_points_in_chunk {
-            // Point size might be larger than what the format indicates due to extra bytes. Extra bytes are not
-            // supported by pasture at the moment, so we skip over them
-            let start_of_source_point =
-                offset_to_first_point_in_file + point_index as u64 * self.size_of_point_in_file;
-            self.reader.seek(SeekFrom::Start(start_of_source_point))?;
-
-            // XYZ
-            let local_x = self.reader.read_i32::<LittleEndian>()?;
-            let local_y = self.reader.read_i32::<LittleEndian>()?;
-            let local_z = self.reader.read_i32::<LittleEndian>()?;
-            let global_x = (local_x as f64 * self.point_scales.x) + self.point_offsets.x;
-            let global_y = (local_y as f64 * self.point_scales.y) + self.point_offsets.y;
-            let global_z = (local_z as f64 * self.point_scales.z) + self.point_offsets.z;
-            buffer_cursor.write_f64::<NativeEndian>(global_x)?;
-            buffer_cursor.write_f64::<NativeEndian>(global_y)?;
-            buffer_cursor.write_f64::<NativeEndian>(global_z)?;
-
-            // Intensity
-            buffer_cursor.write_i16::<NativeEndian>(self.reader.read_i16::<LittleEndian>()?)?;
-
-            // Bit attributes
-            if self.metadata.point_format() > 5 {
-                let bit_attributes_first_byte = self.reader.read_u8()?;
-                let bit_attributes_second_byte = self.reader.read_u8()?;
-
-                let return_number = bit_attributes_first_byte & 0b1111;
-                let number_of_returns = (bit_attributes_first_byte >> 4) & 0b1111;
-                let classification_flags = bit_attributes_second_byte & 0b1111;
-                let scanner_channel = (bit_attributes_second_byte >> 4) & 0b11;
-                let scan_direction_flag = (bit_attributes_second_byte >> 6) & 0b1;
-                let edge_of_flight_line = (bit_attributes_second_byte >> 7) & 0b1;
-
-                buffer_cursor.write_u8(return_number)?;
-                buffer_cursor.write_u8(number_of_returns)?;
-                buffer_cursor.write_u8(classification_flags)?;
-                buffer_cursor.write_u8(scanner_channel)?;
-                buffer_cursor.write_u8(scan_direction_flag)?;
-                buffer_cursor.write_u8(edge_of_flight_line)?;
-            } else {
-                let bit_attributes = self.reader.read_u8()?;
-                let return_number = bit_attributes & 0b111;
-                let number_of_returns = (bit_attributes >> 3) & 0b111;
-                let scan_direction_flag = (bit_attributes >> 6) & 0b1;
-                let edge_of_flight_line = (bit_attributes >> 7) & 0b1;
-
-                buffer_cursor.write_u8(return_number)?;
-                buffer_cursor.write_u8(number_of_returns)?;
-                buffer_cursor.write_u8(scan_direction_flag)?;
-                buffer_cursor.write_u8(edge_of_flight_line)?;
-            }
-
-            // Classification
-            buffer_cursor.write_u8(self.reader.read_u8()?)?;
-
-            // User data in format > 5, scan angle rank in format <= 5
-            buffer_cursor.write_u8(self.reader.read_u8()?)?;
-
-            if self.metadata.point_format() <= 5 {
-                // User data
-                buffer_cursor.write_u8(self.reader.read_u8()?)?;
-            } else {
-                // Scan angle
-                buffer_cursor.write_i16::<NativeEndian>(self.reader.read_i16::<LittleEndian>()?)?;
-            }
-
-            // Point source ID
-            buffer_cursor.write_u16::<NativeEndian>(self.reader.read_u16::<LittleEndian>()?)?;
-
-            // Format 0 is done here, the other formats are handled now
-
-            if format.has_gps_time {
-                buffer_cursor.write_f64::<NativeEndian>(self.reader.read_f64::<LittleEndian>()?)?;
-            }
-
-            if format.has_color {
-                buffer_cursor.write_u16::<NativeEndian>(self.reader.read_u16::<LittleEndian>()?)?;
-                buffer_cursor.write_u16::<NativeEndian>(self.reader.read_u16::<LittleEndian>()?)?;
-                buffer_cursor.write_u16::<NativeEndian>(self.reader.read_u16::<LittleEndian>()?)?;
-            }
-
-            if format.has_nir {
-                buffer_cursor.write_u16::<NativeEndian>(self.reader.read_u16::<LittleEndian>()?)?;
-            }
-
-            if format.has_waveform {
-                buffer_cursor.write_u8(self.reader.read_u8()?)?;
-                buffer_cursor.write_u64::<NativeEndian>(self.reader.read_u64::<LittleEndian>()?)?;
-                buffer_cursor.write_u32::<NativeEndian>(self.reader.read_u32::<LittleEndian>()?)?;
-                buffer_cursor.write_f32::<NativeEndian>(self.reader.read_f32::<LittleEndian>()?)?;
-                buffer_cursor.write_f32::<NativeEndian>(self.reader.read_f32::<LittleEndian>()?)?;
-                buffer_cursor.write_f32::<NativeEndian>(self.reader.read_f32::<LittleEndian>()?)?;
-                buffer_cursor.write_f32::<NativeEndian>(self.reader.read_f32::<LittleEndian>()?)?;
-            }
-        }
+        // Point size might be larger than what the format indicates due to extra bytes, which are not part of the
+        // default layout, so the decoder skips over them
+        let bytes_in_chunk = num_points_in_chunk * self.size_of_point_in_file as usize;
+        self.read_records(record_buffer, num_points_in_chunk)?;
+        self.record_decoder
+            .decode(&record_buffer[0..bytes_in_chunk], chunk_buffer);
 
         Ok(())
     }
@@ -350,8 +351,8 @@ impl<T: Read + Seek> RawLASReader<T> {
         let mut source_reader = Cursor::new(source_data);
 
         for point_index in 0..num_points_in_chunk {
-            // Point size might be larger than what the format indicates due to extra bytes. Extra bytes are not
-            // supported by pasture at the moment, so we skip over them
+            // Point size might be larger than what the format indicates due to extra bytes. Extra bytes are described
+            // by the Extra Bytes VLR (see `las_extra_bytes`) and are not read here, so we skip over them
             let start_of_source_point = point_index as u64 * self.size_of_point_in_file;
             source_reader.seek(SeekFrom::Start(start_of_source_point))?;
 
@@ -612,13 +613,18 @@ impl<T: Read + Seek> RawLASReader<T> {
         let chunk_bytes = point_size as usize * chunk_size;
         let num_chunks = (num_points_to_read + chunk_size - 1) / chunk_size;
         let mut points_chunk: Vec<u8> = vec![0; chunk_bytes];
+        let mut records_chunk: Vec<u8> = vec![0; self.size_of_point_in_file as usize * chunk_size];
 
         for chunk_index in 0..num_chunks {
             let points_in_chunk =
                 std::cmp::min(chunk_size, num_points_to_read - (chunk_index * chunk_size));
             let bytes_in_chunk = points_in_chunk * point_size;
 
-            self.read_chunk_default_layout(&mut points_chunk[..], points_in_chunk)?;
+            self.read_chunk_default_layout(
+                &mut points_chunk[..],
+                &mut records_chunk[..],
+                points_in_chunk,
+            )?;
 
             point_buffer.push(&InterleavedPointView::from_raw_slice(
                 &points_chunk[0..bytes_in_chunk],
@@ -626,8 +632,6 @@ impl<T: Read + Seek> RawLASReader<T> {
             ));
         }
 
-        self.current_point_index += num_points_to_read;
-
         Ok(num_points_to_read)
     }
 
@@ -658,7 +662,7 @@ impl<T: Read + Seek> RawLASReader<T> {
             let bytes_in_chunk = points_in_chunk * point_size;
             let chunk_size_in_file = points_in_chunk * self.size_of_point_in_file as usize;
 
-            self.reader.read_exact(&mut buffer[0..chunk_size_in_file])?;
+            self.read_records(&mut buffer, points_in_chunk)?;
 
             self.read_chunk_custom_layout(
                 &mut buffer[0..chunk_size_in_file],
@@ -673,8 +677,6 @@ impl<T: Read + Seek> RawLASReader<T> {
             ));
         }
 
-        self.current_point_index += num_points_to_read;
-
         Ok(num_points_to_read)
     }
 
@@ -752,12 +754,30 @@ impl<T: Read + Seek> RawLASReader<T> {
 
 impl<T: Read + Seek> LASReaderBase for RawLASReader<T> {
     fn remaining_points(&self) -> usize {
-        self.metadata.point_count() - self.current_point_index
+        decimated_point_count(
+            self.metadata.point_count() - self.current_point_index,
+            self.decimation,
+        )
     }
 
     fn header(&self) -> &Header {
         self.metadata.raw_las_header().unwrap()
     }
+
+    fn read_raw_points(&mut self, buffer: &mut [u8], count: usize) -> Result<usize> {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        let num_bytes =
+            raw_points_size(num_points_to_read, self.size_of_point_in_file, buffer.len());
+        let position_within_file = self.offset_to_first_point_in_file
+            + self.current_point_index as u64 * self.size_of_point_in_file;
+        self.reader.seek(SeekFrom::Start(position_within_file))?;
+        self.read_records(&mut buffer[..num_bytes], num_points_to_read)?;
+        Ok(num_points_to_read)
+    }
+
+    fn set_decimation(&mut self, step: usize) {
+        self.decimation = step;
+    }
 }
 
 impl<T: Read + Seek> PointReader for RawLASReader<T> {
@@ -790,6 +810,13 @@ impl<T: Read + Seek> PointReader for RawLASReader<T> {
     fn get_default_point_layout(&self) -> &PointLayout {
         &self.layout
     }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            seekable: true,
+            ..Capabilities::new(&self.layout)
+        }
+    }
 }
 
 impl<T: Read + Seek> SeekToPoint for RawLASReader<T> {
@@ -824,54 +851,50 @@ pub(crate) struct RawLAZReader<'a, T: Read + Seek + Send + 'a> {
     point_offsets: Vector3<f64>,
     point_scales: Vector3<f64>,
     size_of_point_in_file: u64,
+    record_decoder: LASRecordDecoder,
+    point_blocks: Option<Vec<PointBlock>>,
+    decimation: usize,
 }
 
 impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
     pub fn from_read(mut read: T) -> Result<Self> {
-        let raw_header = raw::Header::read_from(&mut read)?;
-        let offset_to_first_point_in_file = raw_header.offset_to_point_data as u64;
-        let size_of_point_in_file = raw_header.point_data_record_length as u64;
-        let number_of_vlrs = raw_header.number_of_variable_length_records;
+        // TODO Read EVLRs
+        let (header, offset_to_first_point_in_file) = read_las_header(&mut read)?;
+        let size_of_point_in_file = header.point_format().len() as u64;
+        let transforms = header.transforms();
         let point_offsets = Vector3::new(
-            raw_header.x_offset,
-            raw_header.y_offset,
-            raw_header.z_offset,
-        );
-        let point_scales = Vector3::new(
-            raw_header.x_scale_factor,
-            raw_header.y_scale_factor,
-            raw_header.z_scale_factor,
+            transforms.x.offset,
+            transforms.y.offset,
+            transforms.z.offset,
         );
+        let point_scales = Vector3::new(transforms.x.scale, transforms.y.scale, transforms.z.scale);
 
-        let mut header_builder = Builder::new(raw_header)?;
-        // Read VLRs
-        for _ in 0..number_of_vlrs {
-            let vlr = las_rs::raw::Vlr::read_from(&mut read, false).map(Vlr::new)?;
-            header_builder.vlrs.push(vlr);
-        }
-        // TODO Read EVLRs
-
-        let header = header_builder.into_header()?;
         if header.point_format().has_waveform {
-            return Err(anyhow!(
-                "Compressed LAZ files with wave packet data are currently not supported!"
-            ));
-        }
-        if header.point_format().is_extended {
-            return Err(anyhow!(
-                "Compressed LAZ files with extended formats (6-10) are currently not supported!"
+            return Err(PastureIoError::UnsupportedFormat(
+                "Compressed LAZ files with wave packet data are currently not supported!".into(),
             ));
         }
 
         let metadata: LASMetadata = header.clone().into();
         let point_layout = point_layout_from_las_point_format(header.point_format())?;
+        let record_decoder = LASRecordDecoder::from_header(&header)?;
+
+        // COPC files store the points in the nodes of an octree, whose hierarchy serves as a spatial index
+        let point_blocks = match header.vlrs().iter().find(|vlr| is_copc_info_vlr(*vlr)) {
+            Some(vlr) => {
+                let copc_info = CopcInfo::from_vlr_data(&vlr.data)?;
+                Some(read_copc_point_blocks(&mut read, &copc_info)?)
+            }
+            None => None,
+        };
 
         read.seek(SeekFrom::Start(offset_to_first_point_in_file as u64))?;
 
         let laszip_vlr = match header.vlrs().iter().find(|vlr| is_laszip_vlr(*vlr)) {
-            None => Err(anyhow!(
-                "RawLAZReader::new: LAZ variable length record not found in file!"
-            )),
+            None => Err(PastureIoError::CorruptHeader {
+                offset: 0,
+                message: "RawLAZReader::new: LAZ variable length record not found in file!".into(),
+            }),
             Some(ref vlr) => {
                 let laz_record =
                     laz::las::laszip::LazVlr::from_buffer(&vlr.data).map_err(map_laz_err)?;
@@ -888,9 +911,40 @@ impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
             point_offsets,
             point_scales,
             size_of_point_in_file,
+            record_decoder,
+            point_blocks,
+            decimation: 1,
         })
     }
 
+    /// Decompresses the next `count` point records into `buffer`, skipping records according to the decimation, and
+    /// advances the current point index past all records that were read or skipped. Skipped records still have to be
+    /// decompressed, but they are not decoded
+    fn read_records(&mut self, buffer: &mut [u8], count: usize) -> Result<()> {
+        let size_of_point_in_file = self.size_of_point_in_file as usize;
+        if self.decimation == 1 {
+            self.reader
+                .decompress_many(&mut buffer[0..count * size_of_point_in_file])?;
+            self.current_point_index += count;
+            return Ok(());
+        }
+
+        let mut skipped_records_buffer = vec![0; (self.decimation - 1) * size_of_point_in_file];
+        for record in buffer.chunks_exact_mut(size_of_point_in_file).take(count) {
+            self.reader.decompress_many(record)?;
+            let skipped_records = records_to_skip(
+                self.current_point_index,
+                self.metadata.point_count(),
+                self.decimation,
+            );
+            self.reader.decompress_many(
+                &mut skipped_records_buffer[0..skipped_records * size_of_point_in_file],
+            )?;
+            self.current_point_index += 1 + skipped_records;
+        }
+        Ok(())
+    }
+
     fn read_chunk_default_layout(
         &mut self,
         chunk_buffer: &mut [u8],
@@ -898,137 +952,13 @@ impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
         num_points_in_chunk: usize,
     ) -> Result<()> {
         let bytes_in_chunk = num_points_in_chunk * self.size_of_point_in_file as usize;
-        let las_format = Format::new(self.metadata.point_format())?;
-
-        self.reader
-            .decompress_many(&mut decompression_buffer[0..bytes_in_chunk])?;
-        let mut decompression_chunk_cursor = Cursor::new(decompression_buffer);
-        let mut target_chunk_cursor = Cursor::new(chunk_buffer);
-
-        // Convert the decompressed points - which have XYZ as u32 - into the target layout
-        for point_index in 0..num_points_in_chunk {
-            // Point size might be larger than what the format indicates due to extra bytes. Extra bytes are not
-            // supported by pasture at the moment, so we skip over them
-            let start_of_point_in_decompressed_data =
-                point_index as u64 * self.size_of_point_in_file;
-            decompression_chunk_cursor
-                .seek(SeekFrom::Start(start_of_point_in_decompressed_data))?;
-
-            let local_x = decompression_chunk_cursor.read_i32::<LittleEndian>()?;
-            let local_y = decompression_chunk_cursor.read_i32::<LittleEndian>()?;
-            let local_z = decompression_chunk_cursor.read_i32::<LittleEndian>()?;
-            let global_x = (local_x as f64 * self.point_scales.x) + self.point_offsets.x;
-            let global_y = (local_y as f64 * self.point_scales.y) + self.point_offsets.y;
-            let global_z = (local_z as f64 * self.point_scales.z) + self.point_offsets.z;
-            target_chunk_cursor.write_f64::<NativeEndian>(global_x)?;
-            target_chunk_cursor.write_f64::<NativeEndian>(global_y)?;
-            target_chunk_cursor.write_f64::<NativeEndian>(global_z)?;
-
-            // Intensity
-            target_chunk_cursor.write_i16::<NativeEndian>(
-                decompression_chunk_cursor.read_i16::<LittleEndian>()?,
-            )?;
-
-            // Bit attributes
-            if las_format.is_extended {
-                let bit_attributes_first_byte = decompression_chunk_cursor.read_u8()?;
-                let bit_attributes_second_byte = decompression_chunk_cursor.read_u8()?;
-
-                let return_number = bit_attributes_first_byte & 0b1111;
-                let number_of_returns = (bit_attributes_first_byte >> 4) & 0b1111;
-                let classification_flags = bit_attributes_second_byte & 0b1111;
-                let scanner_channel = (bit_attributes_second_byte >> 4) & 0b11;
-                let scan_direction_flag = (bit_attributes_second_byte >> 6) & 0b1;
-                let edge_of_flight_line = (bit_attributes_second_byte >> 7) & 0b1;
-
-                target_chunk_cursor.write_u8(return_number)?;
-                target_chunk_cursor.write_u8(number_of_returns)?;
-                target_chunk_cursor.write_u8(classification_flags)?;
-                target_chunk_cursor.write_u8(scanner_channel)?;
-                target_chunk_cursor.write_u8(scan_direction_flag)?;
-                target_chunk_cursor.write_u8(edge_of_flight_line)?;
-            } else {
-                let bit_attributes = decompression_chunk_cursor.read_u8()?;
-                let return_number = bit_attributes & 0b111;
-                let number_of_returns = (bit_attributes >> 3) & 0b111;
-                let scan_direction_flag = (bit_attributes >> 6) & 0b1;
-                let edge_of_flight_line = (bit_attributes >> 7) & 0b1;
-
-                target_chunk_cursor.write_u8(return_number)?;
-                target_chunk_cursor.write_u8(number_of_returns)?;
-                target_chunk_cursor.write_u8(scan_direction_flag)?;
-                target_chunk_cursor.write_u8(edge_of_flight_line)?;
-            }
-
-            // Classification
-            target_chunk_cursor.write_u8(decompression_chunk_cursor.read_u8()?)?;
-
-            // User data in format > 5, scan angle rank in format <= 5
-            target_chunk_cursor.write_u8(decompression_chunk_cursor.read_u8()?)?;
-
-            if self.metadata.point_format() <= 5 {
-                // User data
-                target_chunk_cursor.write_u8(decompression_chunk_cursor.read_u8()?)?;
-            } else {
-                // Scan angle
-                target_chunk_cursor.write_i16::<NativeEndian>(
-                    decompression_chunk_cursor.read_i16::<LittleEndian>()?,
-                )?;
-            }
-
-            // Point source ID
-            target_chunk_cursor.write_u16::<NativeEndian>(
-                decompression_chunk_cursor.read_u16::<LittleEndian>()?,
-            )?;
 
-            // Format 0 is done here, the other formats are handled now
+        self.read_records(decompression_buffer, num_points_in_chunk)?;
 
-            if las_format.has_gps_time {
-                target_chunk_cursor.write_f64::<NativeEndian>(
-                    decompression_chunk_cursor.read_f64::<LittleEndian>()?,
-                )?;
-            }
-
-            if las_format.has_color {
-                target_chunk_cursor.write_u16::<NativeEndian>(
-                    decompression_chunk_cursor.read_u16::<LittleEndian>()?,
-                )?;
-                target_chunk_cursor.write_u16::<NativeEndian>(
-                    decompression_chunk_cursor.read_u16::<LittleEndian>()?,
-                )?;
-                target_chunk_cursor.write_u16::<NativeEndian>(
-                    decompression_chunk_cursor.read_u16::<LittleEndian>()?,
-                )?;
-            }
-
-            if las_format.has_nir {
-                target_chunk_cursor.write_u16::<NativeEndian>(
-                    decompression_chunk_cursor.read_u16::<LittleEndian>()?,
-                )?;
-            }
-
-            if las_format.has_waveform {
-                target_chunk_cursor.write_u8(decompression_chunk_cursor.read_u8()?)?;
-                target_chunk_cursor.write_u64::<NativeEndian>(
-                    decompression_chunk_cursor.read_u64::<LittleEndian>()?,
-                )?;
-                target_chunk_cursor.write_u32::<NativeEndian>(
-                    decompression_chunk_cursor.read_u32::<LittleEndian>()?,
-                )?;
-                target_chunk_cursor.write_f32::<NativeEndian>(
-                    decompression_chunk_cursor.read_f32::<LittleEndian>()?,
-                )?;
-                target_chunk_cursor.write_f32::<NativeEndian>(
-                    decompression_chunk_cursor.read_f32::<LittleEndian>()?,
-                )?;
-                target_chunk_cursor.write_f32::<NativeEndian>(
-                    decompression_chunk_cursor.read_f32::<LittleEndian>()?,
-                )?;
-                target_chunk_cursor.write_f32::<NativeEndian>(
-                    decompression_chunk_cursor.read_f32::<LittleEndian>()?,
-                )?;
-            }
-        }
+        // Convert the decompressed points - which have XYZ as u32 - into the target layout. Point size might be larger
+        // than what the format indicates due to extra bytes, the decoder skips over them
+        self.record_decoder
+            .decode(&decompression_buffer[0..bytes_in_chunk], chunk_buffer);
 
         Ok(())
     }
@@ -1141,10 +1071,7 @@ impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
 
         let target_point_size = target_layout.size_of_point_entry() as usize;
 
-        self.reader.decompress_many(
-            &mut decompression_buffer
-                [0..(num_points_in_chunk * self.size_of_point_in_file as usize)],
-        )?;
+        self.read_records(decompression_buffer, num_points_in_chunk)?;
         let mut decompressed_data = Cursor::new(decompression_buffer);
 
         fn run_parser<T>(
@@ -1178,8 +1105,8 @@ impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
         }
 
         for point_index in 0..num_points_in_chunk {
-            // Point size might be larger than what the format indicates due to extra bytes. Extra bytes are not
-            // supported by pasture at the moment, so we skip over them
+            // Point size might be larger than what the format indicates due to extra bytes. Extra bytes are described
+            // by the Extra Bytes VLR (see `las_extra_bytes`) and are not read here, so we skip over them
             let start_of_point_in_decompressed_data =
                 point_index as u64 * self.size_of_point_in_file;
             decompressed_data.seek(SeekFrom::Start(start_of_point_in_decompressed_data))?;
@@ -1460,8 +1387,6 @@ impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
             ));
         }
 
-        self.current_point_index += num_points_to_read;
-
         Ok(num_points_to_read)
     }
 
@@ -1504,8 +1429,6 @@ impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
             ));
         }
 
-        self.current_point_index += num_points_to_read;
-
         Ok(num_points_to_read)
     }
 
@@ -1579,12 +1502,27 @@ impl<'a, T: Read + Seek + Send + 'a> RawLAZReader<'a, T> {
 
 impl<'a, T: Read + Seek + Send + 'a> LASReaderBase for RawLAZReader<'a, T> {
     fn remaining_points(&self) -> usize {
-        self.metadata.point_count() - self.current_point_index
+        decimated_point_count(
+            self.metadata.point_count() - self.current_point_index,
+            self.decimation,
+        )
     }
 
     fn header(&self) -> &Header {
         self.metadata.raw_las_header().unwrap()
     }
+
+    fn read_raw_points(&mut self, buffer: &mut [u8], count: usize) -> Result<usize> {
+        let num_points_to_read = usize::min(count, self.remaining_points());
+        let num_bytes =
+            raw_points_size(num_points_to_read, self.size_of_point_in_file, buffer.len());
+        self.read_records(&mut buffer[..num_bytes], num_points_to_read)?;
+        Ok(num_points_to_read)
+    }
+
+    fn set_decimation(&mut self, step: usize) {
+        self.decimation = step;
+    }
 }
 
 impl<'a, T: Read + Seek + Send + 'a> PointReader for RawLAZReader<'a, T> {
@@ -1617,6 +1555,14 @@ impl<'a, T: Read + Seek + Send + 'a> PointReader for RawLAZReader<'a, T> {
     fn get_default_point_layout(&self) -> &PointLayout {
         &self.layout
     }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            seekable: true,
+            filter_pushdown: self.point_blocks.is_some(),
+            ..Capabilities::new(&self.layout)
+        }
+    }
 }
 
 impl<'a, T: Read + Seek + Send + 'a> SeekToPoint for RawLAZReader<'a, T> {
@@ -1639,6 +1585,9 @@ impl<'a, T: Read + Seek + Send + 'a> SeekToPoint for RawLAZReader<'a, T> {
 
         Ok(self.current_point_index)
     }
+    fn point_blocks(&mut self) -> Result<Option<Vec<PointBlock>>> {
+        Ok(self.point_blocks.clone())
+    }
 }
 
 #[cfg(test)]
@@ -1901,13 +1850,12 @@ mod tests {
     test_read_with_format!(laz_format_1, 1, RawLAZReader, get_test_laz_path);
     test_read_with_format!(laz_format_2, 2, RawLAZReader, get_test_laz_path);
     test_read_with_format!(laz_format_3, 3, RawLAZReader, get_test_laz_path);
+    test_read_with_format!(laz_format_6, 6, RawLAZReader, get_test_laz_path);
+    test_read_with_format!(laz_format_7, 7, RawLAZReader, get_test_laz_path);
+    test_read_with_format!(laz_format_8, 8, RawLAZReader, get_test_laz_path);
     // Formats 4,5,9,10 have wave packet data, which is currently unsupported by laz-rs
-    // Format 6,7,8 seem to be unsupported by LASzip and give weird results with laz-rs (e.g. seek does not work correctly)
     // test_read_with_format!(laz_format_4, 4, RawLAZReader);
     // test_read_with_format!(laz_format_5, 5, RawLAZReader);
-    // test_read_with_format!(laz_format_6, 6, RawLAZReader, get_test_laz_path);
-    // test_read_with_format!(laz_format_7, 7, RawLAZReader, get_test_laz_path);
-    // test_read_with_format!(laz_format_8, 8, RawLAZReader, get_test_laz_path);
     // test_read_with_format!(laz_format_9, 9, RawLAZReader);
     // test_read_with_format!(laz_format_10, 10, RawLAZReader);
 
@@ -1,19 +1,23 @@
 use std::{
     collections::HashMap,
-    io::{Cursor, SeekFrom},
+    io::{Cursor, SeekFrom, Write},
 };
 
-use anyhow::{anyhow, Result};
-use byteorder::{LittleEndian, NativeEndian, ReadBytesExt, WriteBytesExt};
+use byteorder::{ByteOrder, LittleEndian, NativeEndian, ReadBytesExt, WriteBytesExt};
 use las_rs::{point::Format, Builder, Vlr};
 use laz::{
     las::laszip::LASZIP_DESCRIPTION, las::laszip::LASZIP_RECORD_ID, las::laszip::LASZIP_USER_ID,
-    LasZipCompressor, LazItemRecordBuilder, LazVlr,
+    LazItemRecordBuilder, LazVlr,
+};
+use pasture_core::{
+    containers::PointBuffer,
+    layout::{PointAttributeDefinition, PointLayout},
+    nalgebra::Vector3,
 };
-use pasture_core::{containers::PointBuffer, layout::PointLayout, nalgebra::Vector3};
 
-use crate::base::PointWriter;
+use crate::base::{Capabilities, PastureIoError, PointWriter, Result};
 
+use super::{extra_bytes_descriptors_from_las_header, ExtraBytesDescriptor};
 use super::{
     get_classification_flags_reader, get_classification_reader, get_color_reader,
     get_edge_of_flight_line_reader, get_extended_scan_angle_rank_reader, get_gps_time_reader,
@@ -23,7 +27,8 @@ use super::{
     get_user_data_reader, get_wave_packet_descriptor_index_reader, get_waveform_data_offset_reader,
     get_waveform_packet_size_reader, get_waveform_parameters_reader, map_laz_err,
     point_layout_from_las_point_format, write_las_bit_attributes, write_position_as_las_position,
-    BitAttributes, BitAttributesExtended, BitAttributesRegular,
+    BackgroundCompressor, BitAttributes, BitAttributesExtended, BitAttributesRegular,
+    LASRecordEncoder,
 };
 
 /// Update the bounds in the given `las_header` by including the given `new_position`
@@ -93,12 +98,189 @@ fn finalize_las_header(las_header: &mut las::raw::Header) {
     }
 }
 
+/// Updates the bounds and point counts in the given `las_header` by including the given raw LAS point records, which
+/// must be in the point record format of `las_header`
+fn update_las_header_from_raw_points(
+    raw_points: &[u8],
+    las_header: &mut las::raw::Header,
+) -> Result<()> {
+    let point_record_length = las_header.point_data_record_length as usize;
+    if raw_points.len() % point_record_length != 0 {
+        return Err(PastureIoError::LayoutMismatch(format!(
+            "Size of raw points ({} bytes) is no multiple of the LAS point record length ({} bytes)",
+            raw_points.len(),
+            point_record_length
+        )));
+    }
+    let format = Format::new(las_header.point_data_record_format)?;
+
+    let mut points_by_return: HashMap<u8, u64> = HashMap::new();
+    for point_record in raw_points.chunks_exact(point_record_length) {
+        let mut point_read = Cursor::new(point_record);
+        let local_x = point_read.read_i32::<LittleEndian>()?;
+        let local_y = point_read.read_i32::<LittleEndian>()?;
+        let local_z = point_read.read_i32::<LittleEndian>()?;
+        let world_space_position = Vector3::new(
+            local_x as f64 * las_header.x_scale_factor + las_header.x_offset,
+            local_y as f64 * las_header.y_scale_factor + las_header.y_offset,
+            local_z as f64 * las_header.z_scale_factor + las_header.z_offset,
+        );
+        update_bounds_in_las_header(&world_space_position, las_header);
+
+        // The return number is stored in the lower bits of the byte after the intensity
+        let _intensity = point_read.read_u16::<LittleEndian>()?;
+        let bit_attributes = point_read.read_u8()?;
+        let return_number = if format.is_extended {
+            bit_attributes & 0b1111
+        } else {
+            bit_attributes & 0b111
+        };
+        if return_number > 0 {
+            *points_by_return.entry(return_number).or_insert(0) += 1;
+        }
+    }
+
+    update_point_counts_in_las_header(
+        raw_points.len() / point_record_length,
+        &points_by_return,
+        las_header,
+    );
+    Ok(())
+}
+
+/// Updates the bounds in the given `las_header` and the counts in `points_by_return` by including the given `points`,
+/// which must be in the default `PointLayout` of the point record format of `las_header`
+fn update_las_header_from_points(
+    points: &[u8],
+    size_of_single_point: usize,
+    points_by_return: &mut HashMap<u8, u64>,
+    las_header: &mut las::raw::Header,
+) {
+    for point in points.chunks_exact(size_of_single_point) {
+        let world_space_position = Vector3::new(
+            NativeEndian::read_f64(&point[0..]),
+            NativeEndian::read_f64(&point[8..]),
+            NativeEndian::read_f64(&point[16..]),
+        );
+        update_bounds_in_las_header(&world_space_position, las_header);
+        // The return number follows the position and the intensity in the default layouts of all point formats
+        if let Some(count) = points_by_return.get_mut(&point[26]) {
+            *count += 1;
+        }
+    }
+}
+
+/// Returns the encoder for points in the default `PointLayout` of the point record format of the given LAS header
+fn record_encoder_for_las_header(las_header: &las::raw::Header) -> Result<LASRecordEncoder> {
+    // las-rs encodes the information about compression in the higher bits of the point_data_record_format
+    LASRecordEncoder::new(
+        las_header.point_data_record_format & 0b1111,
+        las_header.point_data_record_length as usize,
+        Vector3::new(
+            las_header.x_scale_factor,
+            las_header.y_scale_factor,
+            las_header.z_scale_factor,
+        ),
+        Vector3::new(
+            las_header.x_offset,
+            las_header.y_offset,
+            las_header.z_offset,
+        ),
+    )
+}
+
+/// Returns the number of extra bytes per point record for the given LAS header
+fn number_of_extra_bytes(las_header: &las::raw::Header) -> Result<usize> {
+    let format = Format::new(las_header.point_data_record_format)?;
+    Ok(las_header.point_data_record_length as usize - format.len() as usize)
+}
+
+/// Returns, for each of the extra bytes `descriptors`, the size of the attribute in bytes together with the matching
+/// point attribute within `point_layout`, if there is one. An attribute matches if it has the same name and datatype
+/// as the descriptor
+fn get_extra_bytes_attributes(
+    descriptors: &[ExtraBytesDescriptor],
+    point_layout: &PointLayout,
+) -> Vec<(usize, Option<PointAttributeDefinition>)> {
+    descriptors
+        .iter()
+        .map(|descriptor| {
+            let attribute = point_layout
+                .get_attribute_by_name(&descriptor.name)
+                .filter(|attribute| attribute.datatype() == descriptor.datatype)
+                .map(PointAttributeDefinition::from);
+            (descriptor.datatype.size() as usize, attribute)
+        })
+        .collect()
+}
+
+/// Writes the extra bytes of the point at `point_index` within `points`. Extra bytes that are described by `attributes`
+/// are taken from the matching point attribute, all other extra bytes are written as zeros
+fn write_extra_bytes<W: std::io::Write>(
+    points: &dyn PointBuffer,
+    point_index: usize,
+    attributes: &[(usize, Option<PointAttributeDefinition>)],
+    num_extra_bytes: usize,
+    attribute_buffer: &mut Vec<u8>,
+    writer: &mut W,
+) -> Result<()> {
+    let mut bytes_written = 0;
+    for (size, attribute) in attributes {
+        if bytes_written + size > num_extra_bytes {
+            break;
+        }
+        attribute_buffer.clear();
+        attribute_buffer.resize(*size, 0);
+        if let Some(attribute) = attribute {
+            points.get_raw_attribute(point_index, attribute, attribute_buffer);
+            // Point attributes are stored in native endianness, but LAS is little-endian. Extra bytes are always scalars
+            if cfg!(target_endian = "big") {
+                attribute_buffer.reverse();
+            }
+        }
+        writer.write_all(attribute_buffer)?;
+        bytes_written += size;
+    }
+    for _ in bytes_written..num_extra_bytes {
+        writer.write_u8(0)?;
+    }
+    Ok(())
+}
+
+pub(crate) trait LASWriterBase {
+    /// Writes the given raw LAS point records, which must be in the point record format of the file, without parsing
+    /// them. The bounds and point counts in the header are updated from the point records
+    fn write_raw_points(&mut self, raw_points: &[u8]) -> Result<()>;
+    /// Compresses the point records on a background thread with at most `max_queued_chunks` chunks waiting for
+    /// compression. Writers for uncompressed files ignore this
+    fn start_background_compression(&mut self, max_queued_chunks: usize) -> Result<()> {
+        let _ = max_queued_chunks;
+        Ok(())
+    }
+    /// Compresses the point records on a pool of `num_threads` worker threads with at most `max_queued_batches`
+    /// batches of one chunk per worker waiting for compression. Writers for uncompressed files ignore this
+    fn start_parallel_compression(
+        &mut self,
+        num_threads: usize,
+        max_queued_batches: usize,
+    ) -> Result<()> {
+        let _ = (num_threads, max_queued_batches);
+        Ok(())
+    }
+    /// Writes the final header and the extended VLRs and flushes the underlying writer. Afterwards, dropping the writer
+    /// does nothing. Unlike dropping the writer, errors are returned instead of causing a panic
+    fn finalize(&mut self) -> Result<()>;
+    /// Stops writing without finalizing the file, so that dropping the writer does not write anything anymore
+    fn discard(&mut self);
+}
+
 pub(crate) struct RawLASWriter<T: std::io::Write + std::io::Seek> {
     writer: T,
     default_layout: PointLayout,
     current_header: las::raw::Header,
     evlrs: Vec<las::raw::Vlr>,
     _point_start_index: u64,
+    extra_bytes: Vec<ExtraBytesDescriptor>,
     requires_flush: bool,
 }
 
@@ -125,10 +307,23 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
             || raw_header.y_scale_factor == 0.0
             || raw_header.z_scale_factor == 0.0
         {
-            return Err(anyhow!("RawLASWriter::from_write_and_header: Scale factors in LAS header must not be zero!"));
+            // The scale factors start at byte offset 131 in the LAS header
+            return Err(PastureIoError::CorruptHeader {
+                offset: 131,
+                message: "RawLASWriter::from_write_and_header: Scale factors in LAS header must not be zero!".into(),
+            });
         }
 
-        raw_header.write_to(&mut write)?;
+        // The header that is written now is only replaced once the writer is flushed, so it must describe a valid file
+        // without points in case the writer is never flushed
+        let mut placeholder_header = raw_header.clone();
+        placeholder_header.min_x = 0.0;
+        placeholder_header.min_y = 0.0;
+        placeholder_header.min_z = 0.0;
+        placeholder_header.max_x = 0.0;
+        placeholder_header.max_y = 0.0;
+        placeholder_header.max_z = 0.0;
+        placeholder_header.write_to(&mut write)?;
         for vlr in header.vlrs().iter() {
             if vlr.has_large_data() {
                 panic!("RawLASWriter::from_write_and_header: Header with large VLRs is currently unsupported! Please add any large VLRs to the 'evlrs' parameter of the header!");
@@ -150,6 +345,7 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
                 .map(|evlr| evlr.clone().into_raw(true))
                 .collect::<Result<Vec<_>, _>>()?,
             _point_start_index: point_start_index,
+            extra_bytes: extra_bytes_descriptors_from_las_header(&header).unwrap_or_default(),
             requires_flush: true,
         })
     }
@@ -186,8 +382,9 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
         let num_points_in_chunk = 50_000;
         let num_chunks = (points.len() + (num_points_in_chunk - 1)) / num_points_in_chunk;
         let mut chunk_buffer: Vec<u8> = vec![0; num_points_in_chunk * size_of_single_point];
-
-        let source_format = Format::new(self.current_header.point_data_record_format)?;
+        // Points in the default layout have no attributes for the extra bytes, the encoder sets them to zero
+        let encoder = record_encoder_for_las_header(&self.current_header)?;
+        let mut las_point_buffer: Vec<u8> = vec![0; num_points_in_chunk * encoder.record_length()];
 
         let mut points_by_return: HashMap<u8, u64> = HashMap::new();
         let max_return_number = if self.current_header.large_file.is_some() {
@@ -205,122 +402,25 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
                 points.len() - (chunk_index * num_points_in_chunk),
             );
             let start_point_index = chunk_index * num_points_in_chunk;
+            let bytes_in_cur_chunk = points_in_cur_chunk * size_of_single_point;
             points.get_raw_points(
                 start_point_index..(start_point_index + points_in_cur_chunk),
-                &mut chunk_buffer[..points_in_cur_chunk * size_of_single_point],
+                &mut chunk_buffer[..bytes_in_cur_chunk],
             );
-            let mut point_read = Cursor::new(chunk_buffer);
-
-            // Read all the attributes from the raw memory inside `points` and transform them into the format that LAS expects
-            for _ in 0..points_in_cur_chunk {
-                let pos_x = point_read.read_f64::<NativeEndian>()?;
-                let pos_y = point_read.read_f64::<NativeEndian>()?;
-                let pos_z = point_read.read_f64::<NativeEndian>()?;
-                let world_space_position = Vector3::new(pos_x, pos_y, pos_z);
-                write_position_as_las_position(
-                    &world_space_position,
-                    &self.current_header,
-                    &mut self.writer,
-                )?;
-                update_bounds_in_las_header(&world_space_position, &mut self.current_header);
-
-                let intensity = point_read.read_u16::<NativeEndian>()?;
-                self.writer.write_u16::<LittleEndian>(intensity)?;
-
-                let bit_attributes = if source_format.is_extended {
-                    let return_number = point_read.read_u8()?;
-                    points_by_return
-                        .get_mut(&return_number)
-                        .map(|count| *count += 1);
-                    let number_of_returns = point_read.read_u8()?;
-                    let classification_flags = point_read.read_u8()?;
-                    let scanner_channel = point_read.read_u8()?;
-                    let scan_direction_flag = point_read.read_u8()?;
-                    let edge_of_flight_line = point_read.read_u8()?;
-                    BitAttributes::Extended(BitAttributesExtended {
-                        return_number,
-                        number_of_returns,
-                        classification_flags,
-                        scanner_channel,
-                        scan_direction_flag,
-                        edge_of_flight_line,
-                    })
-                } else {
-                    let return_number = point_read.read_u8()?;
-                    points_by_return
-                        .get_mut(&return_number)
-                        .map(|count| *count += 1);
-                    let number_of_returns = point_read.read_u8()?;
-                    let scan_direction_flag = point_read.read_u8()?;
-                    let edge_of_flight_line = point_read.read_u8()?;
-                    BitAttributes::Regular(BitAttributesRegular {
-                        return_number,
-                        number_of_returns,
-                        scan_direction_flag,
-                        edge_of_flight_line,
-                    })
-                };
-                write_las_bit_attributes(bit_attributes, &mut self.writer)?;
-
-                let classification = point_read.read_u8()?;
-                self.writer.write_u8(classification)?;
-
-                if source_format.is_extended {
-                    let user_data = point_read.read_u8()?;
-                    let scan_angle = point_read.read_i16::<NativeEndian>()?;
-
-                    self.writer.write_u8(user_data)?;
-                    self.writer.write_i16::<LittleEndian>(scan_angle)?;
-                } else {
-                    let scan_angle = point_read.read_i8()?;
-                    let user_data = point_read.read_u8()?;
-
-                    self.writer.write_i8(scan_angle)?;
-                    self.writer.write_u8(user_data)?;
-                }
-
-                let point_source_id = point_read.read_u16::<NativeEndian>()?;
-                self.writer.write_u16::<LittleEndian>(point_source_id)?;
-
-                if source_format.has_gps_time {
-                    let gps_time = point_read.read_f64::<NativeEndian>()?;
-                    self.writer.write_f64::<LittleEndian>(gps_time)?;
-                }
-
-                if source_format.has_color {
-                    let r = point_read.read_u16::<NativeEndian>()?;
-                    let g = point_read.read_u16::<NativeEndian>()?;
-                    let b = point_read.read_u16::<NativeEndian>()?;
-                    self.writer.write_u16::<LittleEndian>(r)?;
-                    self.writer.write_u16::<LittleEndian>(g)?;
-                    self.writer.write_u16::<LittleEndian>(b)?;
-                }
-
-                if source_format.has_nir {
-                    let nir = point_read.read_u16::<NativeEndian>()?;
-                    self.writer.write_u16::<LittleEndian>(nir)?;
-                }
-
-                if source_format.has_waveform {
-                    let wave_descriptor = point_read.read_u8()?;
-                    let wave_data_offset = point_read.read_u64::<NativeEndian>()?;
-                    let wave_packet_size = point_read.read_u32::<NativeEndian>()?;
-                    let wave_return_point = point_read.read_f32::<NativeEndian>()?;
-                    let px = point_read.read_f32::<NativeEndian>()?;
-                    let py = point_read.read_f32::<NativeEndian>()?;
-                    let pz = point_read.read_f32::<NativeEndian>()?;
-
-                    self.writer.write_u8(wave_descriptor)?;
-                    self.writer.write_u64::<LittleEndian>(wave_data_offset)?;
-                    self.writer.write_u32::<LittleEndian>(wave_packet_size)?;
-                    self.writer.write_f32::<LittleEndian>(wave_return_point)?;
-                    self.writer.write_f32::<LittleEndian>(px)?;
-                    self.writer.write_f32::<LittleEndian>(py)?;
-                    self.writer.write_f32::<LittleEndian>(pz)?;
-                }
-            }
 
-            chunk_buffer = point_read.into_inner();
+            let bytes_in_current_las_chunk = points_in_cur_chunk * encoder.record_length();
+            encoder.encode(
+                &chunk_buffer[..bytes_in_cur_chunk],
+                &mut las_point_buffer[..bytes_in_current_las_chunk],
+            )?;
+            update_las_header_from_points(
+                &chunk_buffer[..bytes_in_cur_chunk],
+                size_of_single_point,
+                &mut points_by_return,
+                &mut self.current_header,
+            );
+            self.writer
+                .write_all(&las_point_buffer[..bytes_in_current_las_chunk])?;
         }
 
         update_point_counts_in_las_header(
@@ -344,6 +444,10 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
         let mut chunk_buffer: Vec<u8> = vec![0; num_points_in_chunk * size_of_single_point];
 
         let target_format = Format::new(self.current_header.point_data_record_format)?;
+        let num_extra_bytes = number_of_extra_bytes(&self.current_header)?;
+        let extra_bytes_attributes =
+            get_extra_bytes_attributes(&self.extra_bytes, points.point_layout());
+        let mut extra_bytes_buffer = vec![];
 
         let mut points_by_return: HashMap<u8, u64> = HashMap::new();
         let max_return_number = if self.current_header.large_file.is_some() {
@@ -571,6 +675,15 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
                     self.writer.write_f32::<LittleEndian>(params.y)?;
                     self.writer.write_f32::<LittleEndian>(params.z)?;
                 }
+
+                write_extra_bytes(
+                    points,
+                    start_point_index + point_index,
+                    &extra_bytes_attributes,
+                    num_extra_bytes,
+                    &mut extra_bytes_buffer,
+                    &mut self.writer,
+                )?;
             }
 
             chunk_buffer = point_read.into_inner();
@@ -589,11 +702,20 @@ impl<T: std::io::Write + std::io::Seek> RawLASWriter<T> {
 
 impl<T: std::io::Write + std::io::Seek> PointWriter for RawLASWriter<T> {
     fn write(&mut self, points: &dyn PointBuffer) -> Result<()> {
-        if *points.point_layout() == self.default_layout {
+        // If writing fails midway, the point records of this call are dropped, so that the file only contains complete
+        // point records that match the header. Subsequent points overwrite the dropped point records
+        let start_position = self.writer.seek(SeekFrom::Current(0))?;
+        let previous_header = self.current_header.clone();
+        let result = if *points.point_layout() == self.default_layout {
             self.write_points_default_layout(points)
         } else {
             self.write_points_custom_layout(points)
+        };
+        if result.is_err() {
+            self.writer.seek(SeekFrom::Start(start_position))?;
+            self.current_header = previous_header;
         }
+        result
     }
 
     fn flush(&mut self) -> Result<()> {
@@ -614,31 +736,63 @@ impl<T: std::io::Write + std::io::Seek> PointWriter for RawLASWriter<T> {
     fn get_default_point_layout(&self) -> &PointLayout {
         &self.default_layout
     }
+
+    fn capabilities(&self) -> Capabilities {
+        // The header is rewritten on every flush, so more points can be written afterwards
+        Capabilities {
+            appendable: true,
+            ..Capabilities::new(&self.default_layout)
+        }
+    }
+}
+
+impl<T: std::io::Write + std::io::Seek> LASWriterBase for RawLASWriter<T> {
+    fn write_raw_points(&mut self, raw_points: &[u8]) -> Result<()> {
+        if raw_points.is_empty() {
+            return Ok(());
+        }
+        update_las_header_from_raw_points(raw_points, &mut self.current_header)?;
+        self.writer.write_all(raw_points)?;
+        self.requires_flush = true;
+        Ok(())
+    }
+
+    fn finalize(&mut self) -> Result<()> {
+        self.flush()?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn discard(&mut self) {
+        self.requires_flush = false;
+    }
 }
 
 impl<T: std::io::Write + std::io::Seek> Drop for RawLASWriter<T> {
     fn drop(&mut self) {
-        self.flush()
-            .expect("RawLASWriter::drop: Could not flush point data");
+        let result = self.flush();
+        // Panicking while the thread already panics would abort the process, e.g. while a pipeline that writes the
+        // points unwinds
+        if !std::thread::panicking() {
+            result.expect("RawLASWriter::drop: Could not flush point data");
+        }
     }
 }
 
 pub(crate) struct RawLAZWriter<T: std::io::Write + std::io::Seek + Send + 'static> {
-    writer: LasZipCompressor<'static, T>,
+    writer: BackgroundCompressor<T>,
     default_layout: PointLayout,
     current_header: las::raw::Header,
     evlrs: Vec<las::raw::Vlr>,
+    extra_bytes: Vec<ExtraBytesDescriptor>,
     requires_flush: bool,
+    finished: bool,
 }
 
 impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
     pub fn from_write_and_header(mut write: T, header: las::Header) -> Result<Self> {
         let default_layout = point_layout_from_las_point_format(header.point_format())?;
 
-        if header.point_format().extra_bytes != 0 {
-            panic!("Extra bytes in LAZ point records are currently unsupported!");
-        }
-
         let mut raw_header = header.clone().into_raw()?;
         // raw_header.version = Version::new(1, 2);
         raw_header.number_of_point_records = 0;
@@ -656,7 +810,11 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
             || raw_header.y_scale_factor == 0.0
             || raw_header.z_scale_factor == 0.0
         {
-            return Err(anyhow!("RawLASWriter::from_write_and_header: Scale factors in LAS header must not be zero!"));
+            // The scale factors start at byte offset 131 in the LAS header
+            return Err(PastureIoError::CorruptHeader {
+                offset: 131,
+                message: "RawLASWriter::from_write_and_header: Scale factors in LAS header must not be zero!".into(),
+            });
         }
 
         // Create LAZ VLR in addition to the other VLRs in the header
@@ -691,10 +849,8 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
             write.write_all(&header.vlr_padding())?;
         }
 
-        let laz_writer = LasZipCompressor::new(write, raw_laz_vlr).map_err(map_laz_err)?;
-
         Ok(Self {
-            writer: laz_writer,
+            writer: BackgroundCompressor::new(write, raw_laz_vlr),
             default_layout,
             current_header: header_with_laz_vlr.into_raw()?,
             evlrs: header
@@ -702,7 +858,9 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
                 .iter()
                 .map(|evlr| evlr.clone().into_raw(true))
                 .collect::<Result<Vec<_>, _>>()?,
+            extra_bytes: extra_bytes_descriptors_from_las_header(&header).unwrap_or_default(),
             requires_flush: false,
+            finished: false,
         })
     }
 
@@ -718,10 +876,9 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
         let num_points_in_chunk = 50_000;
         let num_chunks = (points.len() + (num_points_in_chunk - 1)) / num_points_in_chunk;
         let mut chunk_buffer: Vec<u8> = vec![0; num_points_in_chunk * size_of_single_point];
-        let mut las_point_buffer: Vec<u8> =
-            vec![0; num_points_in_chunk * self.current_header.point_data_record_length as usize];
-
-        let source_format = Format::new(self.current_header.point_data_record_format)?;
+        // Points in the default layout have no attributes for the extra bytes, the encoder sets them to zero
+        let encoder = record_encoder_for_las_header(&self.current_header)?;
+        let mut las_point_buffer: Vec<u8> = vec![0; num_points_in_chunk * encoder.record_length()];
 
         let mut points_by_return: HashMap<u8, u64> = HashMap::new();
         let max_return_number = if self.current_header.large_file.is_some() {
@@ -739,129 +896,25 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
                 points.len() - (chunk_index * num_points_in_chunk),
             );
             let start_point_index = chunk_index * num_points_in_chunk;
+            let bytes_in_cur_chunk = points_in_cur_chunk * size_of_single_point;
             points.get_raw_points(
                 start_point_index..(start_point_index + points_in_cur_chunk),
-                &mut chunk_buffer,
+                &mut chunk_buffer[..bytes_in_cur_chunk],
             );
-            let mut point_read = Cursor::new(chunk_buffer);
-            let mut las_point_write = Cursor::new(las_point_buffer);
-
-            // Read all the attributes from the raw memory inside `points` and transform them into the format that LAS expects
-            for _ in 0..points_in_cur_chunk {
-                let pos_x = point_read.read_f64::<NativeEndian>()?;
-                let pos_y = point_read.read_f64::<NativeEndian>()?;
-                let pos_z = point_read.read_f64::<NativeEndian>()?;
-                let world_space_position = Vector3::new(pos_x, pos_y, pos_z);
-                write_position_as_las_position(
-                    &world_space_position,
-                    &self.current_header,
-                    &mut las_point_write,
-                )?;
-                update_bounds_in_las_header(&world_space_position, &mut self.current_header);
-
-                let intensity = point_read.read_u16::<NativeEndian>()?;
-                las_point_write.write_u16::<LittleEndian>(intensity)?;
-
-                let bit_attributes = if source_format.is_extended {
-                    let return_number = point_read.read_u8()?;
-                    points_by_return
-                        .get_mut(&return_number)
-                        .map(|count| *count += 1);
-                    let number_of_returns = point_read.read_u8()?;
-                    let classification_flags = point_read.read_u8()?;
-                    let scanner_channel = point_read.read_u8()?;
-                    let scan_direction_flag = point_read.read_u8()?;
-                    let edge_of_flight_line = point_read.read_u8()?;
-                    BitAttributes::Extended(BitAttributesExtended {
-                        return_number,
-                        number_of_returns,
-                        classification_flags,
-                        scanner_channel,
-                        scan_direction_flag,
-                        edge_of_flight_line,
-                    })
-                } else {
-                    let return_number = point_read.read_u8()?;
-                    points_by_return
-                        .get_mut(&return_number)
-                        .map(|count| *count += 1);
-                    let number_of_returns = point_read.read_u8()?;
-                    let scan_direction_flag = point_read.read_u8()?;
-                    let edge_of_flight_line = point_read.read_u8()?;
-                    BitAttributes::Regular(BitAttributesRegular {
-                        return_number,
-                        number_of_returns,
-                        scan_direction_flag,
-                        edge_of_flight_line,
-                    })
-                };
-                write_las_bit_attributes(bit_attributes, &mut las_point_write)?;
-
-                let classification = point_read.read_u8()?;
-                las_point_write.write_u8(classification)?;
-
-                if source_format.is_extended {
-                    let user_data = point_read.read_u8()?;
-                    let scan_angle = point_read.read_i16::<NativeEndian>()?;
-
-                    las_point_write.write_u8(user_data)?;
-                    las_point_write.write_i16::<LittleEndian>(scan_angle)?;
-                } else {
-                    let scan_angle = point_read.read_i8()?;
-                    let user_data = point_read.read_u8()?;
 
-                    las_point_write.write_i8(scan_angle)?;
-                    las_point_write.write_u8(user_data)?;
-                }
-
-                let point_source_id = point_read.read_u16::<NativeEndian>()?;
-                las_point_write.write_u16::<LittleEndian>(point_source_id)?;
-
-                if source_format.has_gps_time {
-                    let gps_time = point_read.read_f64::<NativeEndian>()?;
-                    las_point_write.write_f64::<LittleEndian>(gps_time)?;
-                }
-
-                if source_format.has_color {
-                    let r = point_read.read_u16::<NativeEndian>()?;
-                    let g = point_read.read_u16::<NativeEndian>()?;
-                    let b = point_read.read_u16::<NativeEndian>()?;
-                    las_point_write.write_u16::<LittleEndian>(r)?;
-                    las_point_write.write_u16::<LittleEndian>(g)?;
-                    las_point_write.write_u16::<LittleEndian>(b)?;
-                }
-
-                if source_format.has_nir {
-                    let nir = point_read.read_u16::<NativeEndian>()?;
-                    las_point_write.write_u16::<LittleEndian>(nir)?;
-                }
-
-                if source_format.has_waveform {
-                    let wave_descriptor = point_read.read_u8()?;
-                    let wave_data_offset = point_read.read_u64::<NativeEndian>()?;
-                    let wave_packet_size = point_read.read_u32::<NativeEndian>()?;
-                    let wave_return_point = point_read.read_f32::<NativeEndian>()?;
-                    let px = point_read.read_f32::<NativeEndian>()?;
-                    let py = point_read.read_f32::<NativeEndian>()?;
-                    let pz = point_read.read_f32::<NativeEndian>()?;
-
-                    las_point_write.write_u8(wave_descriptor)?;
-                    las_point_write.write_u64::<LittleEndian>(wave_data_offset)?;
-                    las_point_write.write_u32::<LittleEndian>(wave_packet_size)?;
-                    las_point_write.write_f32::<LittleEndian>(wave_return_point)?;
-                    las_point_write.write_f32::<LittleEndian>(px)?;
-                    las_point_write.write_f32::<LittleEndian>(py)?;
-                    las_point_write.write_f32::<LittleEndian>(pz)?;
-                }
-            }
-
-            las_point_buffer = las_point_write.into_inner();
-            let bytes_in_current_las_chunk =
-                points_in_cur_chunk * self.current_header.point_data_record_length as usize;
+            let bytes_in_current_las_chunk = points_in_cur_chunk * encoder.record_length();
+            encoder.encode(
+                &chunk_buffer[..bytes_in_cur_chunk],
+                &mut las_point_buffer[..bytes_in_current_las_chunk],
+            )?;
+            update_las_header_from_points(
+                &chunk_buffer[..bytes_in_cur_chunk],
+                size_of_single_point,
+                &mut points_by_return,
+                &mut self.current_header,
+            );
             self.writer
                 .compress_many(&las_point_buffer[..bytes_in_current_las_chunk])?;
-
-            chunk_buffer = point_read.into_inner();
         }
 
         update_point_counts_in_las_header(
@@ -887,6 +940,10 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
             vec![0; num_points_in_chunk * self.current_header.point_data_record_length as usize];
 
         let target_format = Format::new(self.current_header.point_data_record_format)?;
+        let num_extra_bytes = number_of_extra_bytes(&self.current_header)?;
+        let extra_bytes_attributes =
+            get_extra_bytes_attributes(&self.extra_bytes, points.point_layout());
+        let mut extra_bytes_buffer = vec![];
 
         let mut points_by_return: HashMap<u8, u64> = HashMap::new();
         let max_return_number = if self.current_header.large_file.is_some() {
@@ -1111,6 +1168,15 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
                     las_point_write.write_f32::<LittleEndian>(params.y)?;
                     las_point_write.write_f32::<LittleEndian>(params.z)?;
                 }
+
+                write_extra_bytes(
+                    points,
+                    start_point_index + point_index,
+                    &extra_bytes_attributes,
+                    num_extra_bytes,
+                    &mut extra_bytes_buffer,
+                    &mut las_point_write,
+                )?;
             }
 
             las_point_buffer = las_point_write.into_inner();
@@ -1136,7 +1202,7 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
     fn write_header(&mut self) -> Result<()> {
         finalize_las_header(&mut self.current_header);
 
-        let mut raw_writer = self.writer.get_mut();
+        let mut raw_writer = self.writer.output_mut()?;
 
         let current_position = raw_writer.seek(SeekFrom::Current(0))?;
         raw_writer.seek(SeekFrom::Start(0))?;
@@ -1148,7 +1214,7 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
 
     /// Writes the extended VLRs to the end of the file
     fn write_evlrs(&mut self) -> Result<()> {
-        let mut raw_writer = self.writer.get_mut();
+        let mut raw_writer = self.writer.output_mut()?;
         // Assumes that self.writer is at the end of the file!
         for evlr in self.evlrs.iter() {
             evlr.write_to(&mut raw_writer)?;
@@ -1156,10 +1222,18 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> RawLAZWriter<T> {
         Ok(())
     }
 
-    fn do_flush(&mut self) {
-        self.writer.done().expect("Could not flush LAZ contents");
-        self.write_evlrs().expect("Could not write LAZ EVLRs");
-        self.write_header().expect("Could not write LAZ header");
+    /// Finishes the compression and writes the extended VLRs and the final header. Does nothing if the file is already
+    /// finished
+    fn finish(&mut self) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        self.writer.done()?;
+        self.write_evlrs()?;
+        self.write_header()?;
+        self.writer.output_mut()?.flush()?;
+        Ok(())
     }
 }
 
@@ -1181,9 +1255,48 @@ impl<T: std::io::Write + std::io::Seek + Send + 'static> PointWriter for RawLAZW
     }
 }
 
+impl<T: std::io::Write + std::io::Seek + Send + 'static> LASWriterBase for RawLAZWriter<T> {
+    fn write_raw_points(&mut self, raw_points: &[u8]) -> Result<()> {
+        if raw_points.is_empty() {
+            return Ok(());
+        }
+        update_las_header_from_raw_points(raw_points, &mut self.current_header)?;
+        self.writer.compress_many(raw_points)?;
+        Ok(())
+    }
+
+    fn start_background_compression(&mut self, max_queued_chunks: usize) -> Result<()> {
+        self.writer.start(max_queued_chunks)
+    }
+
+    fn start_parallel_compression(
+        &mut self,
+        num_threads: usize,
+        max_queued_batches: usize,
+    ) -> Result<()> {
+        self.writer.start_parallel(
+            num_threads,
+            max_queued_batches,
+            self.current_header.point_data_record_length as usize,
+        )
+    }
+
+    fn finalize(&mut self) -> Result<()> {
+        self.finish()
+    }
+
+    fn discard(&mut self) {
+        self.finished = true;
+    }
+}
+
 impl<T: std::io::Write + std::io::Seek + Send + 'static> Drop for RawLAZWriter<T> {
     fn drop(&mut self) {
-        self.do_flush()
+        let result = self.finish();
+        // Panicking while the thread already panics would abort the process
+        if !std::thread::panicking() {
+            result.expect("RawLAZWriter::drop: Could not finish LAZ file");
+        }
     }
 }
 
@@ -1597,4 +1710,66 @@ mod tests {
 
         writer.flush().unwrap_or_default();
     }
+
+    /// Writer that fails once more than `limit` bytes would be written
+    struct LimitedWriter {
+        inner: Cursor<Vec<u8>>,
+        limit: u64,
+    }
+
+    impl std::io::Write for LimitedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.inner.position() + buf.len() as u64 > self.limit {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "Write limit exceeded",
+                ));
+            }
+            self.inner.write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl std::io::Seek for LimitedWriter {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_raw_las_writer_drops_points_of_failed_write() -> Result<()> {
+        let test_data = get_test_points_in_las_format(0)?;
+        let test_points = test_data
+            .iter_point::<LasPointFormat0>()
+            .collect::<Vec<_>>();
+        let mut first_points = InterleavedVecPointStorage::new(LasPointFormat0::layout());
+        first_points.push_points(&test_points[..4]);
+
+        let mut header_builder = Builder::from((1, 4));
+        header_builder.point_format = Format::new(0)?;
+        let header = header_builder.into_header()?;
+        // Fails in the middle of the sixth point record
+        let point_data_start = header.clone().into_raw()?.offset_to_point_data as u64;
+        let mut target = LimitedWriter {
+            inner: Cursor::new(vec![]),
+            limit: point_data_start + 5 * 20 + 10,
+        };
+        {
+            let mut writer = RawLASWriter::from_write_and_header(&mut target, header)?;
+            writer.write(&first_points)?;
+            assert!(writer.write(test_data.as_ref()).is_err());
+        }
+
+        let mut reader = LASReader::from_read(Cursor::new(target.inner.into_inner()), false)?;
+        assert_eq!(4, reader.remaining_points());
+        let read_points = reader
+            .read(4)?
+            .iter_point::<LasPointFormat0>()
+            .collect::<Vec<_>>();
+        assert_eq!(&test_points[..4], read_points.as_slice());
+        Ok(())
+    }
 }
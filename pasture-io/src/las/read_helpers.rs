@@ -1,6 +1,5 @@
 use std::io::Cursor;
 
-use anyhow::Result;
 use byteorder::{NativeEndian, ReadBytesExt};
 use pasture_core::{
     layout::attributes,
@@ -10,6 +9,8 @@ use pasture_core::{
     util::view_raw_bytes_mut,
 };
 
+use crate::base::Result;
+
 /// ReaderFn is a helper function that allows reading a single value of a specific point attribute from an arbitrary
 /// buffer, applying all necessary conversions or falling back to default values if required. This abstraction is
 /// necessary to deal with the general case of an arbitrary source point layout in the LASWriter that has to be
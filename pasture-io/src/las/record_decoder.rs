@@ -0,0 +1,338 @@
+use las_rs::Header;
+use pasture_core::{
+    layout::{PointLayout, PointType},
+    nalgebra::Vector3,
+};
+
+use super::{
+    LasPointFormat0, LasPointFormat1, LasPointFormat10, LasPointFormat2, LasPointFormat3,
+    LasPointFormat4, LasPointFormat5, LasPointFormat6, LasPointFormat7, LasPointFormat8,
+    LasPointFormat9,
+};
+use crate::base::{PastureIoError, Result};
+
+/// Compile-time description of a LAS point record format. It is implemented for the point types of all LAS point
+/// formats (`LasPointFormat0` to `LasPointFormat10`), and [decode_las_records] uses it to generate a decoder without any
+/// per-point branches for each format
+pub trait LASRecordFormat: PointType {
+    /// Size of a point record of this format in bytes, without extra bytes
+    const RECORD_LENGTH: usize;
+    /// Is this one of the extended formats 6 to 10?
+    const IS_EXTENDED: bool;
+    /// Do records of this format have a GPS time?
+    const HAS_GPS_TIME: bool;
+    /// Do records of this format have RGB colors?
+    const HAS_COLOR: bool;
+    /// Do records of this format have a NIR channel?
+    const HAS_NIR: bool;
+    /// Do records of this format have wave packet information?
+    const HAS_WAVEFORM: bool;
+}
+
+macro_rules! impl_las_record_format {
+    ($type:ty, $length:expr, $extended:expr, $gps_time:expr, $color:expr, $nir:expr, $waveform:expr) => {
+        impl LASRecordFormat for $type {
+            const RECORD_LENGTH: usize = $length;
+            const IS_EXTENDED: bool = $extended;
+            const HAS_GPS_TIME: bool = $gps_time;
+            const HAS_COLOR: bool = $color;
+            const HAS_NIR: bool = $nir;
+            const HAS_WAVEFORM: bool = $waveform;
+        }
+    };
+}
+
+impl_las_record_format!(LasPointFormat0, 20, false, false, false, false, false);
+impl_las_record_format!(LasPointFormat1, 28, false, true, false, false, false);
+impl_las_record_format!(LasPointFormat2, 26, false, false, true, false, false);
+impl_las_record_format!(LasPointFormat3, 34, false, true, true, false, false);
+impl_las_record_format!(LasPointFormat4, 57, false, true, false, false, true);
+impl_las_record_format!(LasPointFormat5, 63, false, true, true, false, true);
+impl_las_record_format!(LasPointFormat6, 30, true, true, false, false, false);
+impl_las_record_format!(LasPointFormat7, 36, true, true, true, false, false);
+impl_las_record_format!(LasPointFormat8, 38, true, true, true, true, false);
+impl_las_record_format!(LasPointFormat9, 59, true, true, false, false, true);
+impl_las_record_format!(LasPointFormat10, 67, true, true, true, true, true);
+
+#[inline(always)]
+fn copy_u16(source: &[u8], target: &mut [u8]) {
+    target[..2].copy_from_slice(&u16::from_le_bytes([source[0], source[1]]).to_ne_bytes());
+}
+
+#[inline(always)]
+fn copy_u32(source: &[u8], target: &mut [u8]) {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&source[..4]);
+    target[..4].copy_from_slice(&u32::from_le_bytes(bytes).to_ne_bytes());
+}
+
+#[inline(always)]
+fn copy_u64(source: &[u8], target: &mut [u8]) {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&source[..8]);
+    target[..8].copy_from_slice(&u64::from_le_bytes(bytes).to_ne_bytes());
+}
+
+#[inline(always)]
+fn write_world_space_coordinate(source: &[u8], scale: f64, offset: f64, target: &mut [u8]) {
+    let local = i32::from_le_bytes([source[0], source[1], source[2], source[3]]);
+    target[..8].copy_from_slice(&(local as f64 * scale + offset).to_ne_bytes());
+}
+
+/// Decodes a single point record. All offsets are constant for a given `F`, so after monomorphization this is a
+/// sequence of loads, shifts and stores without any branches
+#[inline(always)]
+fn decode_record<F: LASRecordFormat>(
+    record: &[u8],
+    scales: &Vector3<f64>,
+    offsets: &Vector3<f64>,
+    point: &mut [u8],
+) {
+    write_world_space_coordinate(&record[0..], scales.x, offsets.x, &mut point[0..]);
+    write_world_space_coordinate(&record[4..], scales.y, offsets.y, &mut point[8..]);
+    write_world_space_coordinate(&record[8..], scales.z, offsets.z, &mut point[16..]);
+    copy_u16(&record[12..], &mut point[24..]);
+
+    let (mut source, mut target) = if F::IS_EXTENDED {
+        let low_byte = record[14];
+        let high_byte = record[15];
+        point[26] = low_byte & 0b1111;
+        point[27] = low_byte >> 4;
+        point[28] = high_byte & 0b1111;
+        point[29] = (high_byte >> 4) & 0b11;
+        point[30] = (high_byte >> 6) & 0b1;
+        point[31] = high_byte >> 7;
+        // Classification, user data, scan angle and point source ID
+        point[32] = record[16];
+        point[33] = record[17];
+        copy_u16(&record[18..], &mut point[34..]);
+        copy_u16(&record[20..], &mut point[36..]);
+        (22, 38)
+    } else {
+        let byte = record[14];
+        point[26] = byte & 0b111;
+        point[27] = (byte >> 3) & 0b111;
+        point[28] = (byte >> 6) & 0b1;
+        point[29] = byte >> 7;
+        // Classification, scan angle rank, user data and point source ID
+        point[30] = record[15];
+        point[31] = record[16];
+        point[32] = record[17];
+        copy_u16(&record[18..], &mut point[33..]);
+        (20, 35)
+    };
+
+    if F::HAS_GPS_TIME {
+        copy_u64(&record[source..], &mut point[target..]);
+        source += 8;
+        target += 8;
+    }
+    if F::HAS_COLOR {
+        copy_u16(&record[source..], &mut point[target..]);
+        copy_u16(&record[source + 2..], &mut point[target + 2..]);
+        copy_u16(&record[source + 4..], &mut point[target + 4..]);
+        source += 6;
+        target += 6;
+    }
+    if F::HAS_NIR {
+        copy_u16(&record[source..], &mut point[target..]);
+        source += 2;
+        target += 2;
+    }
+    if F::HAS_WAVEFORM {
+        point[target] = record[source];
+        copy_u64(&record[source + 1..], &mut point[target + 1..]);
+        copy_u32(&record[source + 9..], &mut point[target + 9..]);
+        for parameter in 0..4 {
+            let offset = 13 + 4 * parameter;
+            copy_u32(&record[source + offset..], &mut point[target + offset..]);
+        }
+    }
+}
+
+/// Decodes all point records in `records` into `points`, which uses the memory layout of the point type `F` (i.e. the
+/// default `PointLayout` of the LAS format). Each record is `record_length` bytes long, which can be larger than
+/// `F::RECORD_LENGTH` if the records have extra bytes, which are skipped. Positions are converted into world space with
+/// the given `scales` and `offsets`. Returns the number of decoded points
+///
+/// This processes whole blocks of records instead of reading each field through a `Read` implementation, which is
+/// considerably faster
+///
+/// # Panics
+///
+/// If `record_length` is smaller than `F::RECORD_LENGTH`, or if `points` is too small to hold all points
+pub fn decode_las_records<F: LASRecordFormat>(
+    records: &[u8],
+    record_length: usize,
+    scales: &Vector3<f64>,
+    offsets: &Vector3<f64>,
+    points: &mut [u8],
+) -> usize {
+    if record_length < F::RECORD_LENGTH {
+        panic!(
+            "decode_las_records: Record length {} is too small for the point format (at least {} bytes required)",
+            record_length,
+            F::RECORD_LENGTH
+        );
+    }
+    let point_size = std::mem::size_of::<F>();
+    let count = records.len() / record_length;
+    if points.len() < count * point_size {
+        panic!(
+            "decode_las_records: Buffer is too small for {} points ({} bytes required, but buffer has {} bytes)",
+            count,
+            count * point_size,
+            points.len()
+        );
+    }
+    for (record, point) in records
+        .chunks_exact(record_length)
+        .zip(points.chunks_exact_mut(point_size))
+    {
+        decode_record::<F>(record, scales, offsets, point);
+    }
+    count
+}
+
+type DecodeFn = fn(&[u8], usize, &Vector3<f64>, &Vector3<f64>, &mut [u8]) -> usize;
+
+/// Decoder for the point records of a specific LAS file, which selects the specialized decoder ([decode_las_records])
+/// for the point format of the file. Decodes into the default `PointLayout` of the point format
+#[derive(Clone)]
+pub struct LASRecordDecoder {
+    decode_fn: DecodeFn,
+    layout: PointLayout,
+    record_length: usize,
+    scales: Vector3<f64>,
+    offsets: Vector3<f64>,
+}
+
+impl LASRecordDecoder {
+    /// Creates a new `LASRecordDecoder` for records of the given LAS `point_format` with `record_length` bytes (which
+    /// includes extra bytes), whose positions are converted into world space with the given `scales` and `offsets`
+    ///
+    /// # Errors
+    ///
+    /// If `point_format` is not one of the LAS point formats 0 to 10, or if `record_length` is too small for the format,
+    /// an error is returned
+    pub fn new(
+        point_format: u8,
+        record_length: usize,
+        scales: Vector3<f64>,
+        offsets: Vector3<f64>,
+    ) -> Result<Self> {
+        let (decode_fn, layout, min_record_length): (DecodeFn, _, _) = match point_format {
+            0 => Self::decoder::<LasPointFormat0>(),
+            1 => Self::decoder::<LasPointFormat1>(),
+            2 => Self::decoder::<LasPointFormat2>(),
+            3 => Self::decoder::<LasPointFormat3>(),
+            4 => Self::decoder::<LasPointFormat4>(),
+            5 => Self::decoder::<LasPointFormat5>(),
+            6 => Self::decoder::<LasPointFormat6>(),
+            7 => Self::decoder::<LasPointFormat7>(),
+            8 => Self::decoder::<LasPointFormat8>(),
+            9 => Self::decoder::<LasPointFormat9>(),
+            10 => Self::decoder::<LasPointFormat10>(),
+            _ => {
+                return Err(PastureIoError::UnsupportedFormat(format!(
+                    "Unsupported LAS point format {}",
+                    point_format
+                )))
+            }
+        };
+        if record_length < min_record_length {
+            return Err(PastureIoError::UnsupportedFormat(format!(
+                "Point records of LAS point format {} need at least {} bytes, but have {} bytes",
+                point_format, min_record_length, record_length
+            )));
+        }
+        Ok(Self {
+            decode_fn,
+            layout,
+            record_length,
+            scales,
+            offsets,
+        })
+    }
+
+    /// Creates a new `LASRecordDecoder` for the point records of the LAS file with the given `header`
+    ///
+    /// # Errors
+    ///
+    /// If the point format of `header` is not supported, an error is returned
+    pub fn from_header(header: &Header) -> Result<Self> {
+        let format = header.point_format();
+        let transforms = header.transforms();
+        Self::new(
+            format.to_u8()?,
+            format.len() as usize,
+            Vector3::new(transforms.x.scale, transforms.y.scale, transforms.z.scale),
+            Vector3::new(
+                transforms.x.offset,
+                transforms.y.offset,
+                transforms.z.offset,
+            ),
+        )
+    }
+
+    fn decoder<F: LASRecordFormat>() -> (DecodeFn, PointLayout, usize) {
+        (decode_las_records::<F>, F::layout(), F::RECORD_LENGTH)
+    }
+
+    /// Returns the `PointLayout` of the decoded points
+    pub fn point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    /// Returns the length of a single point record in bytes, including extra bytes
+    pub fn record_length(&self) -> usize {
+        self.record_length
+    }
+
+    /// Decodes all point records in `records` into `points` (see [decode_las_records]) and returns the number of
+    /// decoded points
+    ///
+    /// # Panics
+    ///
+    /// If `points` is too small to hold all points
+    pub fn decode(&self, records: &[u8], points: &mut [u8]) -> usize {
+        (self.decode_fn)(
+            records,
+            self.record_length,
+            &self.scales,
+            &self.offsets,
+            points,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::las::{compare_to_reference_data_range, get_test_las_path, LASReader};
+    use pasture_core::containers::InterleavedPointView;
+
+    #[test]
+    fn test_decode_all_formats() -> Result<()> {
+        for format in 0..=10 {
+            let mut reader = LASReader::from_path(get_test_las_path(format))?;
+            let decoder = LASRecordDecoder::from_header(reader.header())?;
+            let mut records = vec![0; 10 * decoder.record_length()];
+            assert_eq!(10, reader.read_raw_points(&mut records, 10)?);
+
+            let point_size = decoder.point_layout().size_of_point_entry() as usize;
+            let mut points = vec![0; 10 * point_size];
+            assert_eq!(10, decoder.decode(&records, &mut points));
+            let points =
+                InterleavedPointView::from_raw_slice(&points, decoder.point_layout().clone());
+            compare_to_reference_data_range(&points, format, 0..10);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_decoder_rejects_short_records() {
+        assert!(LASRecordDecoder::new(1, 27, Vector3::repeat(1.0), Vector3::zeros()).is_err());
+        assert!(LASRecordDecoder::new(11, 100, Vector3::repeat(1.0), Vector3::zeros()).is_err());
+    }
+}
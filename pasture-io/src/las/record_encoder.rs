@@ -0,0 +1,317 @@
+use std::convert::TryFrom;
+
+use las_rs::Header;
+use pasture_core::{layout::PointLayout, nalgebra::Vector3};
+
+use super::{
+    LASRecordFormat, LasPointFormat0, LasPointFormat1, LasPointFormat10, LasPointFormat2,
+    LasPointFormat3, LasPointFormat4, LasPointFormat5, LasPointFormat6, LasPointFormat7,
+    LasPointFormat8, LasPointFormat9,
+};
+use crate::base::{PastureIoError, Result};
+
+#[inline(always)]
+fn store_u16(source: &[u8], target: &mut [u8]) {
+    target[..2].copy_from_slice(&u16::from_ne_bytes([source[0], source[1]]).to_le_bytes());
+}
+
+#[inline(always)]
+fn store_u32(source: &[u8], target: &mut [u8]) {
+    let mut bytes = [0; 4];
+    bytes.copy_from_slice(&source[..4]);
+    target[..4].copy_from_slice(&u32::from_ne_bytes(bytes).to_le_bytes());
+}
+
+#[inline(always)]
+fn store_u64(source: &[u8], target: &mut [u8]) {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&source[..8]);
+    target[..8].copy_from_slice(&u64::from_ne_bytes(bytes).to_le_bytes());
+}
+
+#[inline(always)]
+fn store_local_space_coordinate(
+    source: &[u8],
+    scale: f64,
+    offset: f64,
+    target: &mut [u8],
+) -> Result<()> {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&source[..8]);
+    let world = f64::from_ne_bytes(bytes);
+    let local = i32::try_from(((world - offset) / scale) as i64).map_err(|_| {
+        PastureIoError::InvalidData(format!(
+            "Coordinate {} is out of bounds given the LAS offset {} and scale {}",
+            world, offset, scale
+        ))
+    })?;
+    target[..4].copy_from_slice(&local.to_le_bytes());
+    Ok(())
+}
+
+/// Encodes a single point into a point record. This is the inverse of the decoding in `decode_las_records`
+#[inline(always)]
+fn encode_record<F: LASRecordFormat>(
+    point: &[u8],
+    scales: &Vector3<f64>,
+    offsets: &Vector3<f64>,
+    record: &mut [u8],
+) -> Result<()> {
+    store_local_space_coordinate(&point[0..], scales.x, offsets.x, &mut record[0..])?;
+    store_local_space_coordinate(&point[8..], scales.y, offsets.y, &mut record[4..])?;
+    store_local_space_coordinate(&point[16..], scales.z, offsets.z, &mut record[8..])?;
+    store_u16(&point[24..], &mut record[12..]);
+
+    let (mut source, mut target) = if F::IS_EXTENDED {
+        record[14] = (point[26] & 0b1111) | (point[27] & 0b1111) << 4;
+        record[15] = (point[28] & 0b1111)
+            | (point[29] & 0b11) << 4
+            | (point[30] & 0b1) << 6
+            | (point[31] & 0b1) << 7;
+        // Classification, user data, scan angle and point source ID
+        record[16] = point[32];
+        record[17] = point[33];
+        store_u16(&point[34..], &mut record[18..]);
+        store_u16(&point[36..], &mut record[20..]);
+        (38, 22)
+    } else {
+        record[14] = (point[26] & 0b111)
+            | (point[27] & 0b111) << 3
+            | (point[28] & 0b1) << 6
+            | (point[29] & 0b1) << 7;
+        // Classification, scan angle rank, user data and point source ID
+        record[15] = point[30];
+        record[16] = point[31];
+        record[17] = point[32];
+        store_u16(&point[33..], &mut record[18..]);
+        (35, 20)
+    };
+
+    if F::HAS_GPS_TIME {
+        store_u64(&point[source..], &mut record[target..]);
+        source += 8;
+        target += 8;
+    }
+    if F::HAS_COLOR {
+        store_u16(&point[source..], &mut record[target..]);
+        store_u16(&point[source + 2..], &mut record[target + 2..]);
+        store_u16(&point[source + 4..], &mut record[target + 4..]);
+        source += 6;
+        target += 6;
+    }
+    if F::HAS_NIR {
+        store_u16(&point[source..], &mut record[target..]);
+        source += 2;
+        target += 2;
+    }
+    if F::HAS_WAVEFORM {
+        record[target] = point[source];
+        store_u64(&point[source + 1..], &mut record[target + 1..]);
+        store_u32(&point[source + 9..], &mut record[target + 9..]);
+        for parameter in 0..4 {
+            let offset = 13 + 4 * parameter;
+            store_u32(&point[source + offset..], &mut record[target + offset..]);
+        }
+    }
+    Ok(())
+}
+
+/// Encodes all `points`, which use the memory layout of the point type `F` (i.e. the default `PointLayout` of the LAS
+/// format), into point records in `records`. Each record is `record_length` bytes long, which can be larger than
+/// `F::RECORD_LENGTH` if the records have extra bytes, which are set to zero. Positions are converted from world space
+/// into the local space of the LAS file with the given `scales` and `offsets`, truncated towards zero. Returns
+/// the number of encoded points. This is the inverse of [decode_las_records](super::decode_las_records)
+///
+/// # Errors
+///
+/// If a position can't be represented in the local space of the LAS file with the given `scales` and `offsets`
+///
+/// # Panics
+///
+/// If `record_length` is smaller than `F::RECORD_LENGTH`, or if `records` is too small to hold all point records
+pub fn encode_las_records<F: LASRecordFormat>(
+    points: &[u8],
+    record_length: usize,
+    scales: &Vector3<f64>,
+    offsets: &Vector3<f64>,
+    records: &mut [u8],
+) -> Result<usize> {
+    if record_length < F::RECORD_LENGTH {
+        panic!(
+            "encode_las_records: Record length {} is too small for the point format (at least {} bytes required)",
+            record_length,
+            F::RECORD_LENGTH
+        );
+    }
+    let point_size = std::mem::size_of::<F>();
+    let count = points.len() / point_size;
+    if records.len() < count * record_length {
+        panic!(
+            "encode_las_records: Buffer is too small for {} point records ({} bytes required, but buffer has {} bytes)",
+            count,
+            count * record_length,
+            records.len()
+        );
+    }
+    for (point, record) in points
+        .chunks_exact(point_size)
+        .zip(records.chunks_exact_mut(record_length))
+    {
+        encode_record::<F>(point, scales, offsets, record)?;
+        for extra_byte in &mut record[F::RECORD_LENGTH..] {
+            *extra_byte = 0;
+        }
+    }
+    Ok(count)
+}
+
+type EncodeFn = fn(&[u8], usize, &Vector3<f64>, &Vector3<f64>, &mut [u8]) -> Result<usize>;
+
+/// Encoder for the point records of a specific LAS file, which selects the specialized encoder ([encode_las_records])
+/// for the point format of the file. Encodes points in the default `PointLayout` of the point format. This is the
+/// counterpart of [LASRecordDecoder](super::LASRecordDecoder)
+#[derive(Clone)]
+pub struct LASRecordEncoder {
+    encode_fn: EncodeFn,
+    layout: PointLayout,
+    record_length: usize,
+    scales: Vector3<f64>,
+    offsets: Vector3<f64>,
+}
+
+impl LASRecordEncoder {
+    /// Creates a new `LASRecordEncoder` for records of the given LAS `point_format` with `record_length` bytes (which
+    /// includes extra bytes), whose positions are converted into local space with the given `scales` and `offsets`
+    ///
+    /// # Errors
+    ///
+    /// If `point_format` is not one of the LAS point formats 0 to 10, or if `record_length` is too small for the format,
+    /// an error is returned
+    pub fn new(
+        point_format: u8,
+        record_length: usize,
+        scales: Vector3<f64>,
+        offsets: Vector3<f64>,
+    ) -> Result<Self> {
+        let (encode_fn, layout, min_record_length): (EncodeFn, _, _) = match point_format {
+            0 => Self::encoder::<LasPointFormat0>(),
+            1 => Self::encoder::<LasPointFormat1>(),
+            2 => Self::encoder::<LasPointFormat2>(),
+            3 => Self::encoder::<LasPointFormat3>(),
+            4 => Self::encoder::<LasPointFormat4>(),
+            5 => Self::encoder::<LasPointFormat5>(),
+            6 => Self::encoder::<LasPointFormat6>(),
+            7 => Self::encoder::<LasPointFormat7>(),
+            8 => Self::encoder::<LasPointFormat8>(),
+            9 => Self::encoder::<LasPointFormat9>(),
+            10 => Self::encoder::<LasPointFormat10>(),
+            _ => {
+                return Err(PastureIoError::UnsupportedFormat(format!(
+                    "Unsupported LAS point format {}",
+                    point_format
+                )))
+            }
+        };
+        if record_length < min_record_length {
+            return Err(PastureIoError::UnsupportedFormat(format!(
+                "Point records of LAS point format {} need at least {} bytes, but have {} bytes",
+                point_format, min_record_length, record_length
+            )));
+        }
+        Ok(Self {
+            encode_fn,
+            layout,
+            record_length,
+            scales,
+            offsets,
+        })
+    }
+
+    /// Creates a new `LASRecordEncoder` for the point records of the LAS file with the given `header`
+    ///
+    /// # Errors
+    ///
+    /// If the point format of `header` is not supported, an error is returned
+    pub fn from_header(header: &Header) -> Result<Self> {
+        let format = header.point_format();
+        let transforms = header.transforms();
+        Self::new(
+            format.to_u8()?,
+            format.len() as usize,
+            Vector3::new(transforms.x.scale, transforms.y.scale, transforms.z.scale),
+            Vector3::new(
+                transforms.x.offset,
+                transforms.y.offset,
+                transforms.z.offset,
+            ),
+        )
+    }
+
+    fn encoder<F: LASRecordFormat>() -> (EncodeFn, PointLayout, usize) {
+        (encode_las_records::<F>, F::layout(), F::RECORD_LENGTH)
+    }
+
+    /// Returns the `PointLayout` of the points that this encoder expects
+    pub fn point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+
+    /// Returns the length of a single point record in bytes, including extra bytes
+    pub fn record_length(&self) -> usize {
+        self.record_length
+    }
+
+    /// Encodes all `points` into point records in `records` (see [encode_las_records]) and returns the number of
+    /// encoded points
+    ///
+    /// # Errors
+    ///
+    /// If a position is out of bounds for the scales and offsets of this encoder
+    ///
+    /// # Panics
+    ///
+    /// If `records` is too small to hold all point records
+    pub fn encode(&self, points: &[u8], records: &mut [u8]) -> Result<usize> {
+        (self.encode_fn)(
+            points,
+            self.record_length,
+            &self.scales,
+            &self.offsets,
+            records,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::las::{get_test_las_path, LASReader, LASRecordDecoder};
+
+    #[test]
+    fn test_encode_all_formats() -> Result<()> {
+        for format in 0..=10 {
+            let mut reader = LASReader::from_path(get_test_las_path(format))?;
+            let decoder = LASRecordDecoder::from_header(reader.header())?;
+            let encoder = LASRecordEncoder::from_header(reader.header())?;
+            let mut records = vec![0; 10 * decoder.record_length()];
+            assert_eq!(10, reader.read_raw_points(&mut records, 10)?);
+
+            let point_size = decoder.point_layout().size_of_point_entry() as usize;
+            let mut points = vec![0; 10 * point_size];
+            decoder.decode(&records, &mut points);
+            let mut encoded_records = vec![0xff; records.len()];
+            assert_eq!(10, encoder.encode(&points, &mut encoded_records)?);
+            assert_eq!(records, encoded_records, "Format {}", format);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_encoder_rejects_out_of_bounds_positions() -> Result<()> {
+        let encoder = LASRecordEncoder::new(0, 20, Vector3::repeat(0.001), Vector3::zeros())?;
+        let mut point = vec![0; encoder.point_layout().size_of_point_entry() as usize];
+        point[..8].copy_from_slice(&1e10f64.to_ne_bytes());
+        assert!(encoder.encode(&point, &mut [0; 20]).is_err());
+        Ok(())
+    }
+}
@@ -1,7 +1,15 @@
-use std::{ops::Range, path::PathBuf};
+use std::{
+    io::{Cursor, Seek, SeekFrom, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+};
 
-use anyhow::Result;
+use byteorder::{LittleEndian, WriteBytesExt};
 use las_rs::point::Format;
+use laz::{
+    las::laszip::{LASZIP_RECORD_ID, LASZIP_USER_ID},
+    LasZipCompressor, LazItemRecordBuilder, LazVlrBuilder,
+};
 use pasture_core::{
     containers::PointBuffer,
     containers::{PerAttributeVecPointStorage, PointBufferExt},
@@ -10,7 +18,8 @@ use pasture_core::{
     nalgebra::{Point3, Vector3},
 };
 
-use super::point_layout_from_las_point_format;
+use super::{map_laz_err, point_layout_from_las_point_format, COPC_INFO_RECORD_ID, COPC_USER_ID};
+use crate::base::{OctreeNodeKey, Result};
 
 /// Returns the path to a LAS test file with the given `format`
 pub(crate) fn get_test_las_path(format: u8) -> PathBuf {
@@ -565,3 +574,188 @@ pub(crate) fn epsilon_compare_point3f64(expected: &Point3<f64>, actual: &Point3<
     let dz = (expected.z - actual.z).abs();
     dx <= EPSILON && dy <= EPSILON && dz <= EPSILON
 }
+
+/// Scale factor of the coordinates in the test COPC file
+const TEST_COPC_SCALE: f64 = 0.001;
+
+/// Returns the nodes of the octree in the test COPC file written by [write_test_copc_file], in the order in which they
+/// are stored in the file, together with the positions of their points. The octree spans the cube [0; 8]^3
+pub(crate) fn test_copc_nodes() -> Vec<(OctreeNodeKey, Vec<Vector3<f64>>)> {
+    vec![
+        (
+            (0, 0, 0, 0),
+            vec![
+                Vector3::new(1.0, 1.0, 1.0),
+                Vector3::new(7.0, 7.0, 7.0),
+                Vector3::new(1.0, 7.0, 1.0),
+                Vector3::new(7.0, 1.0, 7.0),
+            ],
+        ),
+        (
+            (1, 0, 0, 0),
+            vec![
+                Vector3::new(0.5, 0.5, 0.5),
+                Vector3::new(1.5, 1.5, 1.5),
+                Vector3::new(2.5, 2.5, 2.5),
+            ],
+        ),
+        (
+            (1, 1, 1, 1),
+            vec![Vector3::new(5.0, 5.0, 5.0), Vector3::new(6.0, 6.0, 6.0)],
+        ),
+    ]
+}
+
+fn write_padded_string(data: &mut Vec<u8>, value: &str, length: usize) {
+    let mut bytes = value.as_bytes().to_vec();
+    bytes.resize(length, 0);
+    data.extend_from_slice(&bytes);
+}
+
+fn write_vlr_header(data: &mut Vec<u8>, user_id: &str, record_id: u16, record_length: usize) {
+    data.write_u16::<LittleEndian>(0).unwrap();
+    write_padded_string(data, user_id, 16);
+    data.write_u16::<LittleEndian>(record_id).unwrap();
+    data.write_u16::<LittleEndian>(record_length as u16)
+        .unwrap();
+    write_padded_string(data, "", 32);
+}
+
+/// Writes a COPC file with the extended point `format` (6, 7 or 8) to `path` in the same way as other COPC writers do:
+/// A LAS 1.4 header whose first VLR is the COPC info VLR, one variable-sized LAZ chunk per octree node, and the COPC
+/// hierarchy in an EVLR. The nodes and positions are the ones from [test_copc_nodes]. The classification of each point
+/// is the index of its node plus one, and the GPS time is the index of the point
+pub(crate) fn write_test_copc_file(path: &Path, format: u8) -> Result<()> {
+    let record_length: usize = match format {
+        6 => 30,
+        7 => 36,
+        8 => 38,
+        _ => panic!("write_test_copc_file: format must be 6, 7 or 8"),
+    };
+    let nodes = test_copc_nodes();
+    let point_count = nodes
+        .iter()
+        .map(|(_, positions)| positions.len())
+        .sum::<usize>();
+
+    let laz_items =
+        LazItemRecordBuilder::default_for_point_format_id(format, 0).map_err(map_laz_err)?;
+    let laz_vlr = LazVlrBuilder::new(laz_items)
+        .with_variable_chunk_size()
+        .build();
+    let mut laz_vlr_data = vec![];
+    laz_vlr.write_to(&mut laz_vlr_data)?;
+    const COPC_INFO_SIZE: usize = 160;
+    let offset_to_point_data = 375 + 54 + COPC_INFO_SIZE + 54 + laz_vlr_data.len();
+
+    // The header and the VLRs are written once the offsets of the chunks and the hierarchy are known
+    let mut write = Cursor::new(vec![0; offset_to_point_data]);
+    write.seek(SeekFrom::End(0))?;
+    let mut compressor = LasZipCompressor::new(write, laz_vlr).map_err(map_laz_err)?;
+    // The first chunk starts after the offset to the chunk table
+    let mut chunk_start = offset_to_point_data as u64 + 8;
+    let mut hierarchy = vec![];
+    let mut point_index = 0;
+    for (node_index, ((depth, x, y, z), positions)) in nodes.iter().enumerate() {
+        let mut records = vec![];
+        for position in positions {
+            for coordinate in position.iter() {
+                records.write_i32::<LittleEndian>((coordinate / TEST_COPC_SCALE).round() as i32)?;
+            }
+            records.write_u16::<LittleEndian>(0)?; // intensity
+            records.write_u8(0b0001_0001)?; // return 1 of 1
+            records.write_u8(0)?; // flags
+            records.write_u8(node_index as u8 + 1)?; // classification
+            records.write_u8(0)?; // user data
+            records.write_i16::<LittleEndian>(0)?; // scan angle
+            records.write_u16::<LittleEndian>(0)?; // point source ID
+            records.write_f64::<LittleEndian>(point_index as f64)?;
+            records.resize(records.len() + record_length - 30, 0);
+            point_index += 1;
+        }
+        compressor.compress_many(&records)?;
+        compressor.finish_current_chunk()?;
+        let chunk_end = compressor.get_mut().seek(SeekFrom::Current(0))?;
+
+        for value in [*depth as i32, *x as i32, *y as i32, *z as i32].iter() {
+            hierarchy.write_i32::<LittleEndian>(*value)?;
+        }
+        hierarchy.write_u64::<LittleEndian>(chunk_start)?;
+        hierarchy.write_i32::<LittleEndian>((chunk_end - chunk_start) as i32)?;
+        hierarchy.write_i32::<LittleEndian>(positions.len() as i32)?;
+        chunk_start = chunk_end;
+    }
+    compressor.done()?;
+    let mut write = compressor.into_inner();
+
+    // The hierarchy EVLR follows the chunk table
+    let start_of_first_evlr = write.seek(SeekFrom::End(0))?;
+    write.write_u16::<LittleEndian>(0)?;
+    let mut user_id = vec![];
+    write_padded_string(&mut user_id, COPC_USER_ID, 16);
+    write.write_all(&user_id)?;
+    write.write_u16::<LittleEndian>(1000)?;
+    write.write_u64::<LittleEndian>(hierarchy.len() as u64)?;
+    write.write_all(&[0; 32])?;
+    write.write_all(&hierarchy)?;
+
+    let mut header = vec![];
+    header.extend_from_slice(b"LASF");
+    header.write_u16::<LittleEndian>(0)?; // file source ID
+    header.write_u16::<LittleEndian>(0)?; // global encoding
+    header.extend_from_slice(&[0; 16]); // GUID
+    header.extend_from_slice(&[1, 4]); // version
+    write_padded_string(&mut header, "pasture", 32);
+    write_padded_string(&mut header, "pasture", 32);
+    header.write_u16::<LittleEndian>(1)?; // creation day
+    header.write_u16::<LittleEndian>(2021)?; // creation year
+    header.write_u16::<LittleEndian>(375)?;
+    header.write_u32::<LittleEndian>(offset_to_point_data as u32)?;
+    header.write_u32::<LittleEndian>(2)?; // number of VLRs
+    header.write_u8(format | 0x80)?; // bit 7 marks compressed point data
+    header.write_u16::<LittleEndian>(record_length as u16)?;
+    header.extend_from_slice(&[0; 24]); // legacy point counts, which are zero for extended formats
+    for _ in 0..3 {
+        header.write_f64::<LittleEndian>(TEST_COPC_SCALE)?;
+    }
+    header.extend_from_slice(&[0; 24]); // offsets
+    for _ in 0..3 {
+        header.write_f64::<LittleEndian>(7.0)?;
+        header.write_f64::<LittleEndian>(0.5)?;
+    }
+    header.write_u64::<LittleEndian>(0)?; // start of waveform data
+    header.write_u64::<LittleEndian>(start_of_first_evlr)?;
+    header.write_u32::<LittleEndian>(1)?; // number of EVLRs
+    header.write_u64::<LittleEndian>(point_count as u64)?;
+    header.write_u64::<LittleEndian>(point_count as u64)?; // all points are first returns
+    header.extend_from_slice(&[0; 14 * 8]);
+
+    write_vlr_header(
+        &mut header,
+        COPC_USER_ID,
+        COPC_INFO_RECORD_ID,
+        COPC_INFO_SIZE,
+    );
+    for value in [4.0, 4.0, 4.0, 4.0, 2.0].iter() {
+        header.write_f64::<LittleEndian>(*value)?;
+    }
+    header.write_u64::<LittleEndian>(start_of_first_evlr + 60)?;
+    header.write_u64::<LittleEndian>(hierarchy.len() as u64)?;
+    header.write_f64::<LittleEndian>(0.0)?;
+    header.write_f64::<LittleEndian>((point_count - 1) as f64)?;
+    header.extend_from_slice(&[0; 11 * 8]);
+
+    write_vlr_header(
+        &mut header,
+        LASZIP_USER_ID,
+        LASZIP_RECORD_ID,
+        laz_vlr_data.len(),
+    );
+    header.extend_from_slice(&laz_vlr_data);
+    assert_eq!(offset_to_point_data, header.len());
+
+    write.seek(SeekFrom::Start(0))?;
+    write.write_all(&header)?;
+    std::fs::write(path, write.into_inner())?;
+    Ok(())
+}
@@ -1,10 +1,10 @@
 use std::{convert::TryInto, io::Write};
 
-use anyhow::Result;
 use byteorder::{LittleEndian, WriteBytesExt};
 use pasture_core::nalgebra::Vector3;
 
 use super::BitAttributes;
+use crate::base::Result;
 
 /// Writes the given world space position as a LAS position to the given `writer`
 pub(crate) fn write_position_as_las_position<T: Write>(
@@ -4,5 +4,11 @@ pub extern crate las as las_rs;
 
 pub mod ascii;
 pub mod base;
+pub mod ept;
 pub mod las;
+#[cfg(feature = "sensors")]
+pub mod sensors;
 pub mod tiles3d;
+pub mod trajectory;
+
+pub use self::base::{read_all, read_all_into_buffer, write_all};
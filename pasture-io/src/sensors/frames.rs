@@ -0,0 +1,377 @@
+use std::{collections::VecDeque, ops::Range};
+
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBuffer, PointBufferExt, PointBufferWriteable},
+    layout::attributes::{GPS_TIME, POSITION_3D},
+    nalgebra::Vector3,
+};
+
+use crate::base::{PastureIoError, PointReader, Result};
+
+/// Number of points that a [FrameIterator] reads from its reader at once
+const FRAME_READ_SIZE: usize = 10_000;
+
+/// Criterion by which a [FrameIterator] splits the points of a spinning lidar into frames
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameSplit {
+    /// A new frame starts whenever the points cross the given azimuth in degrees, i.e. once per revolution of the
+    /// sensor. The azimuth is computed from the `POSITION_3D` of the points in the coordinate frame of the sensor,
+    /// counter-clockwise from the x axis, so it works for both directions of rotation. Points at the origin of the
+    /// sensor don't affect the split. Since points at the cut azimuth can flicker back and forth between both sides, a
+    /// frame ends only after it rotated by at least half a revolution
+    AzimuthWrap { cut_azimuth: f64 },
+    /// A new frame starts every `interval` seconds of the `GPS_TIME` of the points. Frames are aligned to multiples of
+    /// the interval, e.g. to full seconds for an interval of 1 second. Points whose time lies before the current frame
+    /// stay in the current frame
+    TimeInterval(f64),
+}
+
+/// The points of one frame of a spinning lidar, e.g. of one revolution, as yielded by [FrameIterator]
+pub struct Frame {
+    /// Position of this frame within the frames of its source, starting at zero
+    pub index: u64,
+    /// Range of the indices of the points of this frame within their source
+    pub source_range: Range<usize>,
+    /// The points of this frame
+    pub points: InterleavedVecPointStorage,
+    /// Smallest `GPS_TIME` of the points of this frame, if the points have a `GPS_TIME`
+    pub start_time: Option<f64>,
+    /// Largest `GPS_TIME` of the points of this frame, if the points have a `GPS_TIME`
+    pub end_time: Option<f64>,
+}
+
+impl Frame {
+    /// Returns the number of points in this frame
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns `true` if this frame has no points
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+/// State of a [FrameSplit] for the points of the current frame
+#[derive(Default)]
+struct SplitState {
+    previous_azimuth: Option<f64>,
+    rotation: f64,
+    time_slot: Option<f64>,
+}
+
+impl SplitState {
+    /// Returns `true` if the point with the given `position` and `gps_time` starts a new frame
+    fn starts_new_frame(
+        &mut self,
+        split: FrameSplit,
+        position: Vector3<f64>,
+        gps_time: Option<f64>,
+    ) -> bool {
+        match split {
+            FrameSplit::AzimuthWrap { cut_azimuth } => {
+                if position.x == 0.0 && position.y == 0.0 {
+                    return false;
+                }
+                let azimuth =
+                    (position.y.atan2(position.x).to_degrees() - cut_azimuth).rem_euclid(360.0);
+                let previous_azimuth = match self.previous_azimuth.replace(azimuth) {
+                    Some(previous_azimuth) => previous_azimuth,
+                    None => return false,
+                };
+                // A jump by more than half a revolution between two points means that they are on different sides of
+                // the cut azimuth
+                let delta = azimuth - previous_azimuth;
+                let crosses_cut = delta.abs() > 180.0;
+                let step = if crosses_cut {
+                    delta - 360.0 * delta.signum()
+                } else {
+                    delta
+                };
+                if crosses_cut && self.rotation.abs() > 180.0 {
+                    self.rotation = 0.0;
+                    return true;
+                }
+                self.rotation += step;
+                false
+            }
+            FrameSplit::TimeInterval(interval) => {
+                let time_slot = match gps_time {
+                    Some(gps_time) => (gps_time / interval).floor(),
+                    None => return false,
+                };
+                match self.time_slot {
+                    Some(current_slot) if time_slot <= current_slot => false,
+                    Some(_) => {
+                        self.time_slot = Some(time_slot);
+                        true
+                    }
+                    None => {
+                        self.time_slot = Some(time_slot);
+                        false
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Iterator that groups the points of a spinning lidar into [Frame]s, e.g. one frame per revolution, which is the unit
+/// of processing for SLAM and motion compensation. The points can come from any `PointReader`, e.g. from a
+/// [LidarSensorReader](super::LidarSensorReader) for live data or from a `LASReader` for recorded data, as long as
+/// the points are in the order in which they were measured. The frames are in the default `PointLayout` of the reader.
+///
+/// The iteration ends once the reader returns no more points. For a live sensor, this is the case if no packets
+/// arrive within the timeout of its packet source. The last frame is usually incomplete, as is the first frame,
+/// unless the recording starts at the cut azimuth
+/// ```no_run
+/// # use std::time::Duration;
+/// # use pasture_io::sensors::*;
+/// # fn main() -> anyhow::Result<()> {
+/// let source = UdpPacketSource::bind("0.0.0.0:2368", Duration::from_secs(1))?;
+/// let mut reader = LidarSensorReader::new(source, VelodyneDecoder::new(VelodyneModel::Vlp16));
+/// for frame in FrameIterator::new(&mut reader, FrameSplit::AzimuthWrap { cut_azimuth: 180.0 })? {
+///     let frame = frame?;
+///     println!("Frame {} has {} points", frame.index, frame.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct FrameIterator<'a, R: PointReader + ?Sized> {
+    reader: &'a mut R,
+    split: FrameSplit,
+    state: SplitState,
+    has_gps_time: bool,
+    current_frame: InterleavedVecPointStorage,
+    current_times: Option<(f64, f64)>,
+    finished_frames: VecDeque<Frame>,
+    next_index: u64,
+    next_point_index: usize,
+    done: bool,
+}
+
+impl<'a, R: PointReader + ?Sized> FrameIterator<'a, R> {
+    /// Creates a new `FrameIterator` that reads the remaining points of `reader` and splits them into frames
+    /// according to `split`
+    ///
+    /// # Errors
+    ///
+    /// If the default `PointLayout` of `reader` has no `POSITION_3D` attribute for [FrameSplit::AzimuthWrap], or no
+    /// `GPS_TIME` attribute for [FrameSplit::TimeInterval], an error is returned
+    ///
+    /// # Panics
+    ///
+    /// If the interval of [FrameSplit::TimeInterval] is not positive
+    pub fn new(reader: &'a mut R, split: FrameSplit) -> Result<Self> {
+        let layout = reader.get_default_point_layout().clone();
+        let has_gps_time = layout.has_attribute_with_name(GPS_TIME.name());
+        match split {
+            FrameSplit::AzimuthWrap { .. } => {
+                if !layout.has_attribute_with_name(POSITION_3D.name()) {
+                    return Err(PastureIoError::LayoutMismatch(
+                        "Splitting points into frames by azimuth requires the POSITION_3D attribute"
+                            .into(),
+                    ));
+                }
+            }
+            FrameSplit::TimeInterval(interval) => {
+                if interval.is_nan() || interval <= 0.0 {
+                    panic!("FrameIterator::new: The interval must be greater than zero");
+                }
+                if !has_gps_time {
+                    return Err(PastureIoError::LayoutMismatch(
+                        "Splitting points into frames by time requires the GPS_TIME attribute"
+                            .into(),
+                    ));
+                }
+            }
+        }
+        Ok(Self {
+            reader,
+            split,
+            state: Default::default(),
+            has_gps_time,
+            current_frame: InterleavedVecPointStorage::new(layout),
+            current_times: None,
+            finished_frames: VecDeque::new(),
+            next_index: 0,
+            next_point_index: 0,
+            done: false,
+        })
+    }
+
+    /// Returns the number of frames that were yielded so far
+    pub fn frames_read(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Moves the points of the current frame into a finished frame
+    fn finish_frame(&mut self) {
+        let layout = self.current_frame.point_layout().clone();
+        let points = std::mem::replace(
+            &mut self.current_frame,
+            InterleavedVecPointStorage::new(layout),
+        );
+        let count = points.len();
+        let times = self.current_times.take();
+        self.finished_frames.push_back(Frame {
+            index: self.next_index,
+            source_range: self.next_point_index..self.next_point_index + count,
+            points,
+            start_time: times.map(|(start, _)| start),
+            end_time: times.map(|(_, end)| end),
+        });
+        self.next_index += 1;
+        self.next_point_index += count;
+    }
+
+    /// Reads the next points from the reader and adds them to the current frame, finishing frames at the splits.
+    /// Returns `false` if the reader had no more points
+    fn read_points(&mut self) -> Result<bool> {
+        let mut points = InterleavedVecPointStorage::with_capacity(
+            FRAME_READ_SIZE,
+            self.current_frame.point_layout().clone(),
+        );
+        if self.reader.read_into(&mut points, FRAME_READ_SIZE)? == 0 {
+            return Ok(false);
+        }
+
+        let positions: Vec<Vector3<f64>> = match self.split {
+            FrameSplit::AzimuthWrap { .. } => points.iter_attribute_as(&POSITION_3D).collect(),
+            FrameSplit::TimeInterval(_) => vec![Vector3::zeros(); points.len()],
+        };
+        let gps_times: Vec<Option<f64>> = if self.has_gps_time {
+            points.iter_attribute_as(&GPS_TIME).map(Some).collect()
+        } else {
+            vec![None; points.len()]
+        };
+
+        let mut frame_start = 0;
+        for (index, (position, gps_time)) in positions.into_iter().zip(gps_times).enumerate() {
+            if self.state.starts_new_frame(self.split, position, gps_time) {
+                self.current_frame.push(&points.slice(frame_start..index));
+                frame_start = index;
+                self.finish_frame();
+            }
+            if let Some(gps_time) = gps_time {
+                self.current_times = Some(match self.current_times {
+                    Some((start, end)) => (start.min(gps_time), end.max(gps_time)),
+                    None => (gps_time, gps_time),
+                });
+            }
+        }
+        self.current_frame
+            .push(&points.slice(frame_start..points.len()));
+        Ok(true)
+    }
+}
+
+impl<'a, R: PointReader + ?Sized> Iterator for FrameIterator<'a, R> {
+    type Item = Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.finished_frames.is_empty() && !self.done {
+            match self.read_points() {
+                Ok(true) => {}
+                Ok(false) => {
+                    self.done = true;
+                    if !self.current_frame.is_empty() {
+                        self.finish_frame();
+                    }
+                }
+                Err(error) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+        self.finished_frames.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::sensors::{LidarPacketDecoder, LidarPoint, LidarSensorReader, StreamPacketSource};
+    use byteorder::{ByteOrder, LittleEndian};
+
+    /// Decoder for test packets with the azimuth in degrees and the time of a single point as two `f64` values
+    struct AzimuthDecoder;
+
+    impl LidarPacketDecoder for AzimuthDecoder {
+        fn sensor_name(&self) -> &str {
+            "Test"
+        }
+
+        fn packet_size(&self) -> usize {
+            16
+        }
+
+        fn decode_packet(&mut self, packet: &[u8], points: &mut Vec<LidarPoint>) -> Result<usize> {
+            let azimuth = LittleEndian::read_f64(&packet[0..]).to_radians();
+            points.push(LidarPoint {
+                position: Vector3::new(azimuth.cos(), azimuth.sin(), 0.0),
+                gps_time: LittleEndian::read_f64(&packet[8..]),
+                range: 1.0,
+                intensity: 0,
+                ring: 0,
+            });
+            Ok(1)
+        }
+    }
+
+    fn test_reader(
+        azimuths: &[f64],
+    ) -> LidarSensorReader<StreamPacketSource<Cursor<Vec<u8>>>, AzimuthDecoder> {
+        let mut packets = vec![0; azimuths.len() * 16];
+        for (index, azimuth) in azimuths.iter().enumerate() {
+            LittleEndian::write_f64(&mut packets[index * 16..], *azimuth);
+            LittleEndian::write_f64(&mut packets[index * 16 + 8..], index as f64 * 0.125);
+        }
+        LidarSensorReader::new(
+            StreamPacketSource::new(Cursor::new(packets), 16),
+            AzimuthDecoder,
+        )
+    }
+
+    #[test]
+    fn test_split_frames_by_azimuth() -> Result<()> {
+        // Three revolutions in steps of 30 degrees, starting shortly before the cut azimuth. The points after the
+        // first cut flicker back across the cut azimuth
+        let mut azimuths = (0..30)
+            .map(|index| 350.0 + 30.0 * index as f64)
+            .collect::<Vec<_>>();
+        azimuths.splice(14..14, vec![5.0, 355.0, 15.0]);
+        let mut reader = test_reader(&azimuths);
+        let frames = FrameIterator::new(&mut reader, FrameSplit::AzimuthWrap { cut_azimuth: 0.0 })?
+            .collect::<Result<Vec<_>>>()?;
+
+        assert_eq!(
+            vec![0..13, 13..28, 28..33],
+            frames
+                .iter()
+                .map(|frame| frame.source_range.clone())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![0, 1, 2],
+            frames.iter().map(|frame| frame.index).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(13.0 * 0.125), frames[1].start_time);
+        assert_eq!(Some(27.0 * 0.125), frames[1].end_time);
+        assert_eq!(15, frames[1].len());
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_frames_by_time() -> Result<()> {
+        let mut reader = test_reader(&[0.0; 10]);
+        let frames = FrameIterator::new(&mut reader, FrameSplit::TimeInterval(0.5))?
+            .map(|frame| frame.map(|frame| frame.len()))
+            .collect::<Result<Vec<_>>>()?;
+        assert_eq!(vec![4, 4, 2], frames);
+        Ok(())
+    }
+}
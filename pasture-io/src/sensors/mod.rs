@@ -0,0 +1,16 @@
+//! Readers for live lidar sensors that receive the data packets of the sensors over UDP or TCP, for capturing points
+//! with pasture in robotics applications. Requires the `sensors` feature
+mod packet_source;
+pub use self::packet_source::*;
+
+mod sensor_reader;
+pub use self::sensor_reader::*;
+
+mod velodyne;
+pub use self::velodyne::*;
+
+mod ouster;
+pub use self::ouster::*;
+
+mod frames;
+pub use self::frames::*;
@@ -0,0 +1,244 @@
+use std::f64::consts::PI;
+
+use byteorder::{ByteOrder, LittleEndian};
+use pasture_core::nalgebra::Vector3;
+use serde_json::Value;
+
+use super::{ring_numbers, LidarPacketDecoder, LidarPoint};
+use crate::base::{PastureIoError, Result};
+
+/// Columns (measurement blocks) per lidar packet of Ouster sensors
+pub const OUSTER_COLUMNS_PER_PACKET: usize = 16;
+
+/// Size of the header of a column: timestamp, measurement ID, frame ID and encoder count
+const COLUMN_HEADER_SIZE: usize = 16;
+/// Size of the data of a single pixel: range, reflectivity, signal, near-IR and 2 unused bytes
+const PIXEL_SIZE: usize = 12;
+/// Size of the status at the end of a column
+const COLUMN_STATUS_SIZE: usize = 4;
+/// Status of a column with valid data
+const COLUMN_VALID: u32 = 0xFFFF_FFFF;
+/// Number of encoder ticks per revolution of the sensor
+const ENCODER_TICKS_PER_REVOLUTION: f64 = 90_112.0;
+/// The range is stored in the lower 20 bits of the first 4 bytes of a pixel, in millimeters
+const RANGE_MASK: u32 = 0x000F_FFFF;
+
+/// Intrinsic parameters of an Ouster sensor that are needed to compute the positions of the points, as stored in the
+/// metadata JSON of the sensor (see [from_json](OusterSensorInfo::from_json))
+#[derive(Debug, Clone, PartialEq)]
+pub struct OusterSensorInfo {
+    /// Elevation angle of each beam in degrees, starting with the highest beam
+    pub beam_altitude_angles: Vec<f64>,
+    /// Azimuth offset of each beam in degrees
+    pub beam_azimuth_angles: Vec<f64>,
+    /// Distance between the origin of the lidar and the origin of the beams in millimeters
+    pub lidar_origin_to_beam_origin_mm: f64,
+}
+
+impl OusterSensorInfo {
+    /// Parses the metadata JSON of an Ouster sensor, as returned by the HTTP API or the `get_sensor_info` and
+    /// `get_beam_intrinsics` TCP commands. The beam intrinsics can be at the top level of the JSON or in a
+    /// `beam_intrinsics` object. Without `lidar_origin_to_beam_origin_mm`, the distance is zero
+    ///
+    /// # Errors
+    ///
+    /// If `json` is not valid JSON, or if the beam angles are missing or invalid, an error is returned
+    pub fn from_json(json: &str) -> Result<Self> {
+        let json: Value = serde_json::from_str(json)?;
+        let intrinsics = json.get("beam_intrinsics").unwrap_or(&json);
+        let angles = |key: &str| -> Result<Vec<f64>> {
+            intrinsics
+                .get(key)
+                .and_then(Value::as_array)
+                .ok_or_else(|| {
+                    PastureIoError::InvalidData(format!("Ouster metadata has no '{}'", key))
+                })?
+                .iter()
+                .map(|angle| {
+                    angle.as_f64().ok_or_else(|| {
+                        PastureIoError::InvalidData(format!("Invalid value {} in '{}'", angle, key))
+                    })
+                })
+                .collect()
+        };
+        let info = Self {
+            beam_altitude_angles: angles("beam_altitude_angles")?,
+            beam_azimuth_angles: angles("beam_azimuth_angles")?,
+            lidar_origin_to_beam_origin_mm: intrinsics
+                .get("lidar_origin_to_beam_origin_mm")
+                .and_then(Value::as_f64)
+                .unwrap_or(0.0),
+        };
+        if info.beam_altitude_angles.len() != info.beam_azimuth_angles.len() {
+            return Err(PastureIoError::InvalidData(format!(
+                "Ouster metadata has {} beam altitude angles, but {} beam azimuth angles",
+                info.beam_altitude_angles.len(),
+                info.beam_azimuth_angles.len()
+            )));
+        }
+        Ok(info)
+    }
+
+    /// Returns the number of channels (beams) of the sensor
+    pub fn channels(&self) -> usize {
+        self.beam_altitude_angles.len()
+    }
+}
+
+/// [LidarPacketDecoder] for the lidar packets of Ouster sensors in the legacy packet format, which all sensors support.
+/// Each packet has [OUSTER_COLUMNS_PER_PACKET] columns, each with the measurements of all channels at one azimuth.
+/// Columns with an invalid status are skipped. Positions are computed as described in the software user manual of
+/// Ouster, with the x axis pointing forward at an encoder count of zero and the z axis up. The time of a point is the
+/// timestamp of its column in seconds, whose origin depends on the timestamp mode of the sensor
+pub struct OusterDecoder {
+    info: OusterSensorInfo,
+    name: String,
+    rings: Vec<u16>,
+}
+
+impl OusterDecoder {
+    /// Creates a new `OusterDecoder` for a sensor with the given intrinsic parameters
+    ///
+    /// # Panics
+    ///
+    /// If `info` has no channels, or a different number of beam altitude and azimuth angles
+    pub fn new(info: OusterSensorInfo) -> Self {
+        if info.channels() == 0 || info.beam_azimuth_angles.len() != info.channels() {
+            panic!("OusterDecoder::new: Invalid beam angles in OusterSensorInfo");
+        }
+        Self {
+            name: format!("Ouster ({} channels)", info.channels()),
+            rings: ring_numbers(&info.beam_altitude_angles),
+            info,
+        }
+    }
+
+    /// Returns the intrinsic parameters of the sensor
+    pub fn sensor_info(&self) -> &OusterSensorInfo {
+        &self.info
+    }
+
+    fn column_size(&self) -> usize {
+        COLUMN_HEADER_SIZE + self.info.channels() * PIXEL_SIZE + COLUMN_STATUS_SIZE
+    }
+}
+
+impl LidarPacketDecoder for OusterDecoder {
+    fn sensor_name(&self) -> &str {
+        &self.name
+    }
+
+    fn packet_size(&self) -> usize {
+        OUSTER_COLUMNS_PER_PACKET * self.column_size()
+    }
+
+    fn decode_packet(&mut self, packet: &[u8], points: &mut Vec<LidarPoint>) -> Result<usize> {
+        if packet.len() != self.packet_size() {
+            return Err(PastureIoError::InvalidData(format!(
+                "Lidar packets of an Ouster sensor with {} channels have {} bytes, but the packet has {} bytes",
+                self.info.channels(),
+                self.packet_size(),
+                packet.len()
+            )));
+        }
+
+        let beam_origin = self.info.lidar_origin_to_beam_origin_mm / 1000.0;
+        let count = points.len();
+        for column in packet.chunks_exact(self.column_size()) {
+            let status = LittleEndian::read_u32(&column[column.len() - COLUMN_STATUS_SIZE..]);
+            if status != COLUMN_VALID {
+                continue;
+            }
+            let timestamp = LittleEndian::read_u64(&column[0..]);
+            let encoder_count = LittleEndian::read_u32(&column[12..]) as f64;
+            let encoder_angle = 2.0 * PI * (1.0 - encoder_count / ENCODER_TICKS_PER_REVOLUTION);
+
+            for channel in 0..self.info.channels() {
+                let pixel = &column[COLUMN_HEADER_SIZE + channel * PIXEL_SIZE..];
+                let range_mm = LittleEndian::read_u32(pixel) & RANGE_MASK;
+                if range_mm == 0 {
+                    continue;
+                }
+                let reflectivity = LittleEndian::read_u16(&pixel[4..]);
+
+                let range = range_mm as f64 / 1000.0;
+                let azimuth = encoder_angle - self.info.beam_azimuth_angles[channel].to_radians();
+                let altitude = self.info.beam_altitude_angles[channel].to_radians();
+                let beam_range = range - beam_origin;
+                points.push(LidarPoint {
+                    position: Vector3::new(
+                        beam_range * azimuth.cos() * altitude.cos()
+                            + beam_origin * encoder_angle.cos(),
+                        beam_range * azimuth.sin() * altitude.cos()
+                            + beam_origin * encoder_angle.sin(),
+                        beam_range * altitude.sin(),
+                    ),
+                    gps_time: timestamp as f64 * 1e-9,
+                    range: range as f32,
+                    intensity: reflectivity,
+                    ring: self.rings[channel],
+                });
+            }
+        }
+        Ok(points.len() - count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_info() -> OusterSensorInfo {
+        OusterSensorInfo::from_json(
+            r#"{ "beam_intrinsics": { "beam_altitude_angles": [10.0, -10.0], "beam_azimuth_angles": [0.0, 2.0] } }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_ouster_sensor_info() -> Result<()> {
+        let info = OusterSensorInfo::from_json(
+            r#"{ "beam_altitude_angles": [1.0, 0.0, -1.0], "beam_azimuth_angles": [3.0, 0.0, -3.0],
+                 "lidar_origin_to_beam_origin_mm": 12.163 }"#,
+        )?;
+        assert_eq!(3, info.channels());
+        assert_eq!(12.163, info.lidar_origin_to_beam_origin_mm);
+        assert_eq!(0.0, test_info().lidar_origin_to_beam_origin_mm);
+
+        assert!(OusterSensorInfo::from_json(r#"{ "beam_altitude_angles": [1.0] }"#).is_err());
+        assert!(OusterSensorInfo::from_json(
+            r#"{ "beam_altitude_angles": [1.0], "beam_azimuth_angles": [] }"#
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_ouster_packet() -> Result<()> {
+        let mut decoder = OusterDecoder::new(test_info());
+        let column_size = decoder.column_size();
+        let mut packet = vec![0; decoder.packet_size()];
+        assert_eq!(16 * (16 + 2 * 12 + 4), packet.len());
+
+        // First column at encoder count 0 with a valid status, second column a quarter turn later with an invalid status
+        LittleEndian::write_u64(&mut packet[0..], 2_500_000_000);
+        LittleEndian::write_u32(&mut packet[16..], 1000);
+        LittleEndian::write_u16(&mut packet[20..], 7);
+        LittleEndian::write_u32(&mut packet[column_size - 4..], COLUMN_VALID);
+        LittleEndian::write_u32(&mut packet[column_size + 12..], 90_112 / 4);
+        LittleEndian::write_u32(&mut packet[column_size + 16..], 1000);
+
+        let mut points = vec![];
+        assert_eq!(1, decoder.decode_packet(&packet, &mut points)?);
+        let point = points[0];
+        let altitude = 10.0f64.to_radians();
+        assert!((point.position - Vector3::new(altitude.cos(), 0.0, altitude.sin())).amax() < 1e-9);
+        assert_eq!(1.0, point.range);
+        assert_eq!(7, point.intensity);
+        assert_eq!(1, point.ring);
+        assert_eq!(2.5, point.gps_time);
+
+        assert!(decoder.decode_packet(&packet[1..], &mut points).is_err());
+        Ok(())
+    }
+}
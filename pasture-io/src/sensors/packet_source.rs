@@ -0,0 +1,164 @@
+use std::{
+    io::{ErrorKind, Read},
+    net::{TcpStream, ToSocketAddrs, UdpSocket},
+    time::Duration,
+};
+
+use crate::base::{PastureIoError, Result};
+
+/// Returns true if `error` means that no data arrived within the read timeout of a socket
+fn is_timeout(error: &std::io::Error) -> bool {
+    error.kind() == ErrorKind::WouldBlock || error.kind() == ErrorKind::TimedOut
+}
+
+/// Source of the data packets of a live lidar sensor
+pub trait PacketSource {
+    /// Receives the next packet into `buffer` and returns its size in bytes. Returns `None` if no packet arrived within
+    /// the timeout of the source, or if the source has ended
+    ///
+    /// # Errors
+    ///
+    /// If an I/O error occurs while receiving the packet, an error is returned
+    fn receive_packet(&mut self, buffer: &mut [u8]) -> Result<Option<usize>>;
+}
+
+/// [PacketSource] that receives UDP datagrams, which is how Velodyne and Ouster sensors send their data packets. Each
+/// datagram is one packet
+pub struct UdpPacketSource {
+    socket: UdpSocket,
+}
+
+impl UdpPacketSource {
+    /// Creates a new `UdpPacketSource` that listens on the given `address`, e.g. `0.0.0.0:2368` for the default data
+    /// port of Velodyne sensors or `0.0.0.0:7502` for Ouster sensors. `receive_packet` returns `None` if no packet
+    /// arrives within `timeout`
+    ///
+    /// # Errors
+    ///
+    /// If the socket can't be bound to `address`, an error is returned
+    pub fn bind<A: ToSocketAddrs>(address: A, timeout: Duration) -> Result<Self> {
+        let socket = UdpSocket::bind(address).map_err(PastureIoError::Io)?;
+        socket
+            .set_read_timeout(Some(timeout))
+            .map_err(PastureIoError::Io)?;
+        Ok(Self { socket })
+    }
+
+    /// Creates a new `UdpPacketSource` from a `socket` that was configured by the caller, e.g. to join a multicast group
+    pub fn from_socket(socket: UdpSocket) -> Self {
+        Self { socket }
+    }
+}
+
+impl PacketSource for UdpPacketSource {
+    fn receive_packet(&mut self, buffer: &mut [u8]) -> Result<Option<usize>> {
+        match self.socket.recv(buffer) {
+            Ok(size) => Ok(Some(size)),
+            Err(error) if is_timeout(&error) => Ok(None),
+            Err(error) => Err(PastureIoError::Io(error)),
+        }
+    }
+}
+
+/// [PacketSource] that reads packets of a fixed size from a byte stream, e.g. a `TcpStream` from a gateway that forwards
+/// the packets of a sensor, or a `File` with recorded packets. If the stream has a read timeout, packets that are only
+/// partially received when the timeout expires are completed by the next call to `receive_packet`
+pub struct StreamPacketSource<R: Read> {
+    stream: R,
+    packet: Vec<u8>,
+    received_bytes: usize,
+}
+
+impl StreamPacketSource<TcpStream> {
+    /// Creates a new `StreamPacketSource` that connects to the given `address` and reads packets of `packet_size` bytes.
+    /// `receive_packet` returns `None` if no complete packet arrives within `timeout`
+    ///
+    /// # Errors
+    ///
+    /// If the connection can't be established, an error is returned
+    ///
+    /// # Panics
+    ///
+    /// If `packet_size` is zero
+    pub fn connect<A: ToSocketAddrs>(
+        address: A,
+        packet_size: usize,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let stream = TcpStream::connect(address).map_err(PastureIoError::Io)?;
+        stream
+            .set_read_timeout(Some(timeout))
+            .map_err(PastureIoError::Io)?;
+        Ok(Self::new(stream, packet_size))
+    }
+}
+
+impl<R: Read> StreamPacketSource<R> {
+    /// Creates a new `StreamPacketSource` that reads packets of `packet_size` bytes from `stream`
+    ///
+    /// # Panics
+    ///
+    /// If `packet_size` is zero
+    pub fn new(stream: R, packet_size: usize) -> Self {
+        if packet_size == 0 {
+            panic!("StreamPacketSource::new: packet_size must be greater than zero");
+        }
+        Self {
+            stream,
+            packet: vec![0; packet_size],
+            received_bytes: 0,
+        }
+    }
+}
+
+impl<R: Read> PacketSource for StreamPacketSource<R> {
+    fn receive_packet(&mut self, buffer: &mut [u8]) -> Result<Option<usize>> {
+        while self.received_bytes < self.packet.len() {
+            match self.stream.read(&mut self.packet[self.received_bytes..]) {
+                Ok(0) if self.received_bytes == 0 => return Ok(None),
+                Ok(0) => {
+                    return Err(PastureIoError::InvalidData(format!(
+                        "Stream ended within a packet ({} of {} bytes received)",
+                        self.received_bytes,
+                        self.packet.len()
+                    )))
+                }
+                Ok(bytes) => self.received_bytes += bytes,
+                Err(error) if error.kind() == ErrorKind::Interrupted => {}
+                Err(error) if is_timeout(&error) => return Ok(None),
+                Err(error) => return Err(PastureIoError::Io(error)),
+            }
+        }
+        self.received_bytes = 0;
+        let size = self.packet.len();
+        buffer[..size].copy_from_slice(&self.packet);
+        Ok(Some(size))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn test_stream_packet_source() -> Result<()> {
+        let bytes = (0..10).collect::<Vec<u8>>();
+        let mut source = StreamPacketSource::new(Cursor::new(&bytes[..9]), 3);
+        let mut packet = [0; 3];
+        assert_eq!(Some(3), source.receive_packet(&mut packet)?);
+        assert_eq!([0, 1, 2], packet);
+        assert_eq!(Some(3), source.receive_packet(&mut packet)?);
+        assert_eq!(Some(3), source.receive_packet(&mut packet)?);
+        assert_eq!([6, 7, 8], packet);
+        assert_eq!(None, source.receive_packet(&mut packet)?);
+
+        let mut source = StreamPacketSource::new(Cursor::new(&bytes[..]), 3);
+        for _ in 0..3 {
+            source.receive_packet(&mut packet)?;
+        }
+        assert!(source.receive_packet(&mut packet).is_err());
+        Ok(())
+    }
+}
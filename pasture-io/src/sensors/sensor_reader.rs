@@ -0,0 +1,318 @@
+use std::fmt::Display;
+
+use pasture_core::{
+    containers::{
+        InterleavedPointBuffer, InterleavedVecPointStorage, PointBuffer, PointBufferWriteable,
+    },
+    layout::{
+        conversion::get_converter_for_attributes, PointAttributeDefinition, PointLayout, PointType,
+    },
+    math::AABB,
+    meta::Metadata,
+    nalgebra::Vector3,
+};
+use pasture_derive::PointType;
+
+use super::PacketSource;
+use crate::base::{PointReader, Result};
+
+/// A point measured by a live lidar sensor. Positions are in meters in the coordinate frame of the sensor. The
+/// `gps_time` is the time of the measurement in seconds as reported by the sensor, e.g. the seconds since the top of the
+/// hour for Velodyne sensors, which are in sync with GPS time if the sensor gets a PPS signal
+#[repr(C)]
+#[derive(PointType, Debug, Copy, Clone, PartialEq)]
+pub struct LidarPoint {
+    #[pasture(BUILTIN_POSITION_3D)]
+    pub position: Vector3<f64>,
+    #[pasture(BUILTIN_GPS_TIME)]
+    pub gps_time: f64,
+    #[pasture(BUILTIN_RANGE)]
+    pub range: f32,
+    #[pasture(BUILTIN_INTENSITY)]
+    pub intensity: u16,
+    #[pasture(BUILTIN_RING)]
+    pub ring: u16,
+}
+
+/// Decoder for the data packets of a specific lidar sensor
+pub trait LidarPacketDecoder {
+    /// Returns the name of the sensor model, e.g. `"Velodyne VLP-16"`
+    fn sensor_name(&self) -> &str;
+    /// Returns the size of a data packet in bytes. Packets of other sizes, e.g. position or IMU packets, are ignored
+    fn packet_size(&self) -> usize;
+    /// Decodes the data `packet` and appends its points to `points`. Measurements without a return are skipped. Returns
+    /// the number of points that were appended
+    ///
+    /// # Errors
+    ///
+    /// If `packet` is no valid data packet of the sensor, an error is returned
+    fn decode_packet(&mut self, packet: &[u8], points: &mut Vec<LidarPoint>) -> Result<usize>;
+}
+
+/// Returns the ring number of each beam, given the elevation angles of the beams. Rings are numbered from the lowest to
+/// the highest beam
+pub(crate) fn ring_numbers(elevations: &[f64]) -> Vec<u16> {
+    elevations
+        .iter()
+        .map(|elevation| elevations.iter().filter(|other| *other < elevation).count() as u16)
+        .collect()
+}
+
+/// `Metadata` of a live lidar sensor. The number of points and the bounds are unknown, since the sensor keeps sending
+/// points
+#[derive(Debug, Clone)]
+pub struct SensorMetadata {
+    sensor_name: String,
+}
+
+impl SensorMetadata {
+    /// Creates new `SensorMetadata` for the sensor with the given name
+    pub fn new<S: Into<String>>(sensor_name: S) -> Self {
+        Self {
+            sensor_name: sensor_name.into(),
+        }
+    }
+
+    /// Returns the name of the sensor model
+    pub fn sensor_name(&self) -> &str {
+        &self.sensor_name
+    }
+}
+
+impl Display for SensorMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Live sensor: {}", self.sensor_name)
+    }
+}
+
+impl Metadata for SensorMetadata {
+    fn bounds(&self) -> Option<AABB<f64>> {
+        None
+    }
+
+    fn number_of_points(&self) -> Option<usize> {
+        None
+    }
+
+    fn get_named_field(&self, _field_name: &str) -> Option<Box<dyn std::any::Any>> {
+        None
+    }
+
+    fn clone_into_box(&self) -> Box<dyn Metadata> {
+        Box::new(self.clone())
+    }
+}
+
+/// `PointReader` for live lidar sensors, which receives data packets from a [PacketSource] and decodes them with a
+/// [LidarPacketDecoder] into [LidarPoint]s. `read` and `read_into` wait for packets until `count` points are decoded,
+/// and return fewer points if the source has no packets within its timeout. Since a sensor sends points until it is
+/// stopped, reading 0 points means that no packets arrived, not that all points were read
+/// ```no_run
+/// # use std::time::Duration;
+/// # use pasture_io::base::*;
+/// # use pasture_io::sensors::*;
+/// # fn main() -> anyhow::Result<()> {
+/// let source = UdpPacketSource::bind("0.0.0.0:2368", Duration::from_secs(1))?;
+/// let mut reader = LidarSensorReader::new(source, VelodyneDecoder::new(VelodyneModel::Vlp16));
+/// for chunk in read_chunks(&mut reader, 30_000) {
+///     let chunk = chunk?;
+///     println!("Received {} points", chunk.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct LidarSensorReader<S: PacketSource, D: LidarPacketDecoder> {
+    source: S,
+    decoder: D,
+    layout: PointLayout,
+    metadata: SensorMetadata,
+    packet: Vec<u8>,
+    decoded_points: Vec<LidarPoint>,
+    ignored_packets: usize,
+}
+
+impl<S: PacketSource, D: LidarPacketDecoder> LidarSensorReader<S, D> {
+    /// Creates a new `LidarSensorReader` that receives packets from `source` and decodes them with `decoder`
+    pub fn new(source: S, decoder: D) -> Self {
+        let metadata = SensorMetadata::new(decoder.sensor_name());
+        Self {
+            source,
+            decoder,
+            layout: LidarPoint::layout(),
+            metadata,
+            // Large enough for any UDP datagram, so that larger packets are not truncated to the expected packet size
+            packet: vec![0; 65_536],
+            decoded_points: vec![],
+            ignored_packets: 0,
+        }
+    }
+
+    /// Returns the number of received packets that were ignored because they had the wrong size
+    pub fn ignored_packets(&self) -> usize {
+        self.ignored_packets
+    }
+
+    /// Receives and decodes packets until at least `count` points are decoded or no packet arrives in time
+    fn decode_points(&mut self, count: usize) -> Result<()> {
+        while self.decoded_points.len() < count {
+            match self.source.receive_packet(&mut self.packet)? {
+                Some(size) if size == self.decoder.packet_size() => {
+                    self.decoder
+                        .decode_packet(&self.packet[..size], &mut self.decoded_points)?;
+                }
+                Some(_) => self.ignored_packets += 1,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: PacketSource, D: LidarPacketDecoder> PointReader for LidarSensorReader<S, D> {
+    fn read(&mut self, count: usize) -> Result<Box<dyn PointBuffer>> {
+        let mut buffer = InterleavedVecPointStorage::with_capacity(count, self.layout.clone());
+        self.read_into(&mut buffer, count)?;
+        Ok(Box::new(buffer))
+    }
+
+    fn read_into(
+        &mut self,
+        point_buffer: &mut dyn PointBufferWriteable,
+        count: usize,
+    ) -> Result<usize> {
+        self.decode_points(count)?;
+        let points_read = usize::min(count, self.decoded_points.len());
+        let mut points =
+            InterleavedVecPointStorage::with_capacity(points_read, self.layout.clone());
+        points.push_points(&self.decoded_points[..points_read]);
+        self.decoded_points.drain(..points_read);
+        if *point_buffer.point_layout() == self.layout {
+            point_buffer.push(&points);
+            return Ok(points_read);
+        }
+
+        // Copy all attributes that the target layout has, converting their datatypes if necessary
+        let first_point = point_buffer.len();
+        point_buffer.resize(first_point + points_read);
+        let point_size = self.layout.size_of_point_entry() as usize;
+        let raw_points = points.get_raw_points_ref(0..points_read);
+        let target_layout = point_buffer.point_layout().clone();
+        for source_attribute in self.layout.attributes() {
+            let target_attribute =
+                match target_layout.get_attribute_by_name(source_attribute.name()) {
+                    Some(attribute) => PointAttributeDefinition::from(attribute),
+                    None => continue,
+                };
+            let converter =
+                get_converter_for_attributes(&source_attribute.into(), &target_attribute);
+            let source_range = source_attribute.offset() as usize
+                ..(source_attribute.offset() + source_attribute.size()) as usize;
+            let mut converted_value = vec![0; target_attribute.size() as usize];
+            for (index, point) in raw_points.chunks_exact(point_size).enumerate() {
+                let value = &point[source_range.clone()];
+                let value = match converter {
+                    Some(convert) => {
+                        unsafe {
+                            convert(value, &mut converted_value);
+                        }
+                        &converted_value[..]
+                    }
+                    None => value,
+                };
+                point_buffer.set_raw_attribute(first_point + index, &target_attribute, value);
+            }
+        }
+        Ok(points_read)
+    }
+
+    fn get_metadata(&self) -> &dyn Metadata {
+        &self.metadata
+    }
+
+    fn get_default_point_layout(&self) -> &PointLayout {
+        &self.layout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::{base::PastureIoError, sensors::StreamPacketSource};
+    use pasture_core::{
+        containers::{PerAttributeVecPointStorage, PointBufferExt},
+        layout::{
+            attributes::{GPS_TIME, INTENSITY, RING},
+            PointAttributeDataType,
+        },
+    };
+
+    /// Decoder for test packets of 4 bytes, where each byte that is not zero is a point with this intensity
+    struct TestDecoder;
+
+    impl LidarPacketDecoder for TestDecoder {
+        fn sensor_name(&self) -> &str {
+            "Test"
+        }
+
+        fn packet_size(&self) -> usize {
+            4
+        }
+
+        fn decode_packet(&mut self, packet: &[u8], points: &mut Vec<LidarPoint>) -> Result<usize> {
+            if packet.len() != 4 {
+                return Err(PastureIoError::InvalidData("Invalid packet".into()));
+            }
+            let count = points.len();
+            points.extend(
+                packet
+                    .iter()
+                    .filter(|byte| **byte != 0)
+                    .map(|byte| LidarPoint {
+                        position: Vector3::zeros(),
+                        gps_time: *byte as f64,
+                        range: 0.0,
+                        intensity: *byte as u16,
+                        ring: 0,
+                    }),
+            );
+            Ok(points.len() - count)
+        }
+    }
+
+    #[test]
+    fn test_ring_numbers() {
+        assert_eq!(vec![1, 2, 0], ring_numbers(&[0.0, 15.0, -15.0]));
+    }
+
+    #[test]
+    fn test_lidar_sensor_reader() -> Result<()> {
+        let packets = vec![1, 2, 0, 3, 4, 5, 6, 7, 0, 0, 0, 8];
+        let source = StreamPacketSource::new(Cursor::new(packets), 4);
+        let mut reader = LidarSensorReader::new(source, TestDecoder);
+        assert_eq!(None, reader.get_metadata().number_of_points());
+
+        let points = reader.read(2)?;
+        assert_eq!(
+            vec![1, 2],
+            points.iter_attribute::<u16>(&INTENSITY).collect::<Vec<_>>()
+        );
+
+        let mut custom_points = PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[
+            RING,
+            GPS_TIME.with_custom_datatype(PointAttributeDataType::F32),
+        ]));
+        assert_eq!(4, reader.read_into(&mut custom_points, 4)?);
+        assert_eq!(
+            vec![3.0, 4.0, 5.0, 6.0],
+            custom_points
+                .iter_attribute::<f32>(&GPS_TIME.with_custom_datatype(PointAttributeDataType::F32))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(2, reader.read(10)?.len());
+        assert_eq!(0, reader.read(10)?.len());
+        Ok(())
+    }
+}
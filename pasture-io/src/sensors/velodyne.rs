@@ -0,0 +1,309 @@
+use byteorder::{ByteOrder, LittleEndian};
+use pasture_core::nalgebra::Vector3;
+
+use super::{ring_numbers, LidarPacketDecoder, LidarPoint};
+use crate::base::{PastureIoError, Result};
+
+/// Size of a data packet of Velodyne sensors in bytes, without the UDP header
+pub const VELODYNE_PACKET_SIZE: usize = 1206;
+
+/// Data blocks per packet, each with an azimuth and 32 measurements
+const BLOCKS_PER_PACKET: usize = 12;
+const BLOCK_SIZE: usize = 100;
+const MEASUREMENTS_PER_BLOCK: usize = 32;
+/// Flag at the start of each data block, stored as the bytes `0xFF 0xEE`
+const BLOCK_FLAG: u16 = 0xEEFF;
+/// Offsets of the timestamp, return mode and product ID after the data blocks
+const TIMESTAMP_OFFSET: usize = 1200;
+const RETURN_MODE_OFFSET: usize = 1204;
+const PRODUCT_ID_OFFSET: usize = 1205;
+/// Return mode in which consecutive pairs of data blocks contain two returns of the same firing
+const DUAL_RETURN_MODE: u8 = 0x39;
+/// Time between two firings of a firing sequence in microseconds
+const FIRING_TIME: f64 = 2.304;
+/// Time between two firing sequences in microseconds
+const SEQUENCE_TIME: f64 = 55.296;
+
+const VLP16_ELEVATION_ANGLES: [f64; 16] = [
+    -15.0, 1.0, -13.0, 3.0, -11.0, 5.0, -9.0, 7.0, -7.0, 9.0, -5.0, 11.0, -3.0, 13.0, -1.0, 15.0,
+];
+
+const VLP32C_ELEVATION_ANGLES: [f64; 32] = [
+    -25.0, -1.0, -1.667, -15.639, -11.31, 0.0, -0.667, -8.843, -7.254, 0.333, -0.333, -6.148,
+    -5.333, 1.333, 0.667, -4.0, -4.667, 1.667, 1.0, -3.667, -3.333, 3.333, 2.333, -2.667, -3.0,
+    7.0, 4.667, -2.333, -2.0, 15.0, 10.333, -1.333,
+];
+
+const VLP32C_AZIMUTH_OFFSETS: [f64; 32] = [
+    1.4, -4.2, 1.4, -1.4, 1.4, -1.4, 4.2, -1.4, 1.4, -4.2, 1.4, -1.4, 4.2, -1.4, 4.2, -1.4, 1.4,
+    -4.2, 1.4, -4.2, 4.2, -1.4, 1.4, -1.4, 1.4, -1.4, 1.4, -4.2, 4.2, -1.4, 1.4, -1.4,
+];
+
+/// Supported Velodyne sensor models
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VelodyneModel {
+    /// VLP-16 (Puck) with 16 lasers
+    Vlp16,
+    /// VLP-32C (Ultra Puck) with 32 lasers
+    Vlp32C,
+}
+
+impl VelodyneModel {
+    /// Returns the model with the given product ID, which is stored in the last byte of each data packet
+    pub fn from_product_id(product_id: u8) -> Option<Self> {
+        match product_id {
+            0x22 => Some(Self::Vlp16),
+            0x28 => Some(Self::Vlp32C),
+            _ => None,
+        }
+    }
+
+    /// Returns the product ID of this model
+    pub fn product_id(&self) -> u8 {
+        match self {
+            Self::Vlp16 => 0x22,
+            Self::Vlp32C => 0x28,
+        }
+    }
+
+    /// Returns the name of this model
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Vlp16 => "Velodyne VLP-16",
+            Self::Vlp32C => "Velodyne VLP-32C",
+        }
+    }
+
+    /// Returns the elevation angle of each laser in degrees, indexed by the laser ID
+    pub fn elevation_angles(&self) -> &'static [f64] {
+        match self {
+            Self::Vlp16 => &VLP16_ELEVATION_ANGLES,
+            Self::Vlp32C => &VLP32C_ELEVATION_ANGLES,
+        }
+    }
+
+    /// Returns the azimuth offset of each laser in degrees, indexed by the laser ID
+    fn azimuth_offset(&self, laser: usize) -> f64 {
+        match self {
+            Self::Vlp16 => 0.0,
+            Self::Vlp32C => VLP32C_AZIMUTH_OFFSETS[laser],
+        }
+    }
+
+    /// Returns the resolution of the distances in meters
+    fn distance_resolution(&self) -> f64 {
+        match self {
+            Self::Vlp16 => 0.002,
+            Self::Vlp32C => 0.004,
+        }
+    }
+
+    /// Returns the time that the firings of a data block take in microseconds. The 32 measurements of a VLP-16 block are
+    /// two firing sequences of all 16 lasers, the VLP-32C fires two lasers at once in a single sequence
+    fn block_time(&self) -> f64 {
+        match self {
+            Self::Vlp16 => 2.0 * SEQUENCE_TIME,
+            Self::Vlp32C => SEQUENCE_TIME,
+        }
+    }
+
+    /// Returns the laser ID and the firing time relative to the start of the block (in microseconds) of the measurement
+    /// with the given index within a data block
+    fn laser_and_firing_time(&self, measurement: usize) -> (usize, f64) {
+        match self {
+            Self::Vlp16 => (
+                measurement % 16,
+                (measurement / 16) as f64 * SEQUENCE_TIME + (measurement % 16) as f64 * FIRING_TIME,
+            ),
+            Self::Vlp32C => (measurement, (measurement / 2) as f64 * FIRING_TIME),
+        }
+    }
+}
+
+/// [LidarPacketDecoder] for the data packets of Velodyne sensors. Supports the single (strongest or last) and dual
+/// return modes. Positions follow the conventions of Velodyne, with the y axis pointing forward at an azimuth of zero,
+/// the x axis to the right and the z axis up. The azimuth of each measurement is interpolated between the azimuths of
+/// consecutive data blocks, and its time is the timestamp of the packet (in microseconds since the top of the hour) plus
+/// the firing time of the laser
+pub struct VelodyneDecoder {
+    model: VelodyneModel,
+    rings: Vec<u16>,
+}
+
+impl VelodyneDecoder {
+    /// Creates a new `VelodyneDecoder` for sensors of the given `model`
+    pub fn new(model: VelodyneModel) -> Self {
+        Self {
+            model,
+            rings: ring_numbers(model.elevation_angles()),
+        }
+    }
+
+    /// Returns the sensor model of this decoder
+    pub fn model(&self) -> VelodyneModel {
+        self.model
+    }
+}
+
+impl LidarPacketDecoder for VelodyneDecoder {
+    fn sensor_name(&self) -> &str {
+        self.model.name()
+    }
+
+    fn packet_size(&self) -> usize {
+        VELODYNE_PACKET_SIZE
+    }
+
+    fn decode_packet(&mut self, packet: &[u8], points: &mut Vec<LidarPoint>) -> Result<usize> {
+        if packet.len() != VELODYNE_PACKET_SIZE {
+            return Err(PastureIoError::InvalidData(format!(
+                "Velodyne data packets have {} bytes, but the packet has {} bytes",
+                VELODYNE_PACKET_SIZE,
+                packet.len()
+            )));
+        }
+        if let Some(model) = VelodyneModel::from_product_id(packet[PRODUCT_ID_OFFSET]) {
+            if model != self.model {
+                return Err(PastureIoError::InvalidArgument(format!(
+                    "Packet is from a {}, but the decoder is for a {}",
+                    model.name(),
+                    self.model.name()
+                )));
+            }
+        }
+
+        let timestamp = LittleEndian::read_u32(&packet[TIMESTAMP_OFFSET..]) as f64;
+        // In dual return mode, both blocks of a pair have the same azimuth and firing times
+        let block_step = if packet[RETURN_MODE_OFFSET] == DUAL_RETURN_MODE {
+            2
+        } else {
+            1
+        };
+        let mut azimuths = [0.0; BLOCKS_PER_PACKET];
+        for (block, azimuth) in azimuths.iter_mut().enumerate() {
+            let block_start = block * BLOCK_SIZE;
+            if LittleEndian::read_u16(&packet[block_start..]) != BLOCK_FLAG {
+                return Err(PastureIoError::InvalidData(format!(
+                    "Invalid flag of data block {} in Velodyne packet",
+                    block
+                )));
+            }
+            *azimuth = LittleEndian::read_u16(&packet[block_start + 2..]) as f64 / 100.0;
+        }
+
+        let block_time = self.model.block_time();
+        let count = points.len();
+        for (block, &azimuth) in azimuths.iter().enumerate() {
+            let azimuth_gap = if block + block_step < BLOCKS_PER_PACKET {
+                (azimuths[block + block_step] - azimuth + 360.0) % 360.0
+            } else if block >= block_step {
+                (azimuth - azimuths[block - block_step] + 360.0) % 360.0
+            } else {
+                0.0
+            };
+            let block_start_time = (block / block_step) as f64 * block_time;
+
+            for measurement in 0..MEASUREMENTS_PER_BLOCK {
+                let measurement_start = block * BLOCK_SIZE + 4 + measurement * 3;
+                let distance = LittleEndian::read_u16(&packet[measurement_start..]);
+                if distance == 0 {
+                    continue;
+                }
+                let reflectivity = packet[measurement_start + 2];
+
+                let (laser, firing_time) = self.model.laser_and_firing_time(measurement);
+                let azimuth = (azimuth
+                    + azimuth_gap * firing_time / block_time
+                    + self.model.azimuth_offset(laser))
+                .to_radians();
+                let elevation = self.model.elevation_angles()[laser].to_radians();
+                let range = distance as f64 * self.model.distance_resolution();
+                points.push(LidarPoint {
+                    position: Vector3::new(
+                        range * elevation.cos() * azimuth.sin(),
+                        range * elevation.cos() * azimuth.cos(),
+                        range * elevation.sin(),
+                    ),
+                    gps_time: (timestamp + block_start_time + firing_time) * 1e-6,
+                    range: range as f32,
+                    intensity: reflectivity as u16,
+                    ring: self.rings[laser],
+                });
+            }
+        }
+        Ok(points.len() - count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vlp16_packet() -> Vec<u8> {
+        let mut packet = vec![0; VELODYNE_PACKET_SIZE];
+        for block in 0..BLOCKS_PER_PACKET {
+            let block_start = block * BLOCK_SIZE;
+            LittleEndian::write_u16(&mut packet[block_start..], BLOCK_FLAG);
+            LittleEndian::write_u16(&mut packet[block_start + 2..], (block * 20) as u16);
+        }
+        // 10 meters for laser 0 in the first block
+        LittleEndian::write_u16(&mut packet[4..], 5000);
+        packet[6] = 42;
+        // 2 meters for laser 1 in the second firing sequence of the second block
+        LittleEndian::write_u16(&mut packet[BLOCK_SIZE + 4 + 17 * 3..], 1000);
+        LittleEndian::write_u32(&mut packet[TIMESTAMP_OFFSET..], 1_000_000);
+        packet[RETURN_MODE_OFFSET] = 0x37;
+        packet[PRODUCT_ID_OFFSET] = 0x22;
+        packet
+    }
+
+    #[test]
+    fn test_decode_vlp16_packet() -> Result<()> {
+        let mut decoder = VelodyneDecoder::new(VelodyneModel::Vlp16);
+        let mut points = vec![];
+        assert_eq!(2, decoder.decode_packet(&vlp16_packet(), &mut points)?);
+
+        let first = points[0];
+        let elevation = (-15.0f64).to_radians();
+        assert!(
+            (first.position - Vector3::new(0.0, 10.0 * elevation.cos(), 10.0 * elevation.sin()))
+                .amax()
+                < 1e-9
+        );
+        assert_eq!(10.0, first.range);
+        assert_eq!(42, first.intensity);
+        assert_eq!(0, first.ring);
+        assert!((first.gps_time - 1.0).abs() < 1e-12);
+
+        let second = points[1];
+        let firing_time = SEQUENCE_TIME + FIRING_TIME;
+        let azimuth = (0.2 + 0.2 * firing_time / (2.0 * SEQUENCE_TIME)).to_radians();
+        let elevation = 1.0f64.to_radians();
+        let expected_position = Vector3::new(
+            2.0 * elevation.cos() * azimuth.sin(),
+            2.0 * elevation.cos() * azimuth.cos(),
+            2.0 * elevation.sin(),
+        );
+        assert!((second.position - expected_position).amax() < 1e-9);
+        assert_eq!(8, second.ring);
+        assert!((second.gps_time - (1e6 + 2.0 * SEQUENCE_TIME + firing_time) * 1e-6).abs() < 1e-12);
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_invalid_velodyne_packets() {
+        let mut decoder = VelodyneDecoder::new(VelodyneModel::Vlp16);
+        let mut points = vec![];
+
+        let mut packet = vlp16_packet();
+        packet[PRODUCT_ID_OFFSET] = VelodyneModel::Vlp32C.product_id();
+        assert!(decoder.decode_packet(&packet, &mut points).is_err());
+
+        let mut packet = vlp16_packet();
+        packet[BLOCK_SIZE] = 0;
+        assert!(decoder.decode_packet(&packet, &mut points).is_err());
+
+        assert!(decoder.decode_packet(&packet[..1000], &mut points).is_err());
+        assert!(points.is_empty());
+    }
+}
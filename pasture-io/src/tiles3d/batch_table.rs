@@ -1,4 +1,3 @@
-use anyhow::{anyhow, bail, Result};
 use pasture_core::math::Alignable;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
@@ -10,6 +9,7 @@ use std::{
 };
 
 use super::{read_json_header, write_json_header};
+use crate::base::{PastureIoError, Result};
 
 /// A reference to data inside a BatchTable binary body
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -32,7 +32,7 @@ pub enum BatchTableEntry {
 }
 
 impl TryFrom<Value> for BatchTableEntry {
-    type Error = anyhow::Error;
+    type Error = PastureIoError;
 
     fn try_from(val: Value) -> Result<Self> {
         if val.is_array() {
@@ -44,12 +44,15 @@ impl TryFrom<Value> for BatchTableEntry {
             return Ok(BatchTableEntry::DataReference(data_reference));
         }
 
-        bail!("JSON value cannot be converted to BatchTableEntry because it is neither an array nor an object")
+        Err(PastureIoError::InvalidData(
+            "JSON value cannot be converted to BatchTableEntry because it is neither an array nor an object"
+                .into(),
+        ))
     }
 }
 
 impl TryFrom<&Value> for BatchTableEntry {
-    type Error = anyhow::Error;
+    type Error = PastureIoError;
 
     fn try_from(val: &Value) -> Result<Self> {
         if val.is_array() {
@@ -61,7 +64,10 @@ impl TryFrom<&Value> for BatchTableEntry {
             return Ok(BatchTableEntry::DataReference(data_reference));
         }
 
-        bail!("JSON value cannot be converted to BatchTableEntry because it is neither an array nor an object")
+        Err(PastureIoError::InvalidData(
+            "JSON value cannot be converted to BatchTableEntry because it is neither an array nor an object"
+                .into(),
+        ))
     }
 }
 
@@ -106,9 +112,9 @@ pub fn deser_batch_table_header<R: BufRead + Seek>(
         reader.seek(SeekFrom::Current(padding_bytes as i64))?;
     }
 
-    let batch_table_json_obj = batch_table_header_json
-        .as_object()
-        .ok_or(anyhow!("BatchTable JSON header was no JSON object"))?;
+    let batch_table_json_obj = batch_table_header_json.as_object().ok_or_else(|| {
+        PastureIoError::InvalidData("BatchTable JSON header was no JSON object".into())
+    })?;
     // Convert JSON object to `BatchTableHeader`
     Ok(batch_table_json_obj
         .iter()
@@ -1,4 +1,3 @@
-use anyhow::{anyhow, bail, Context, Result};
 use pasture_core::{
     math::Alignable,
     nalgebra::{Vector3, Vector4},
@@ -9,16 +8,23 @@ use std::{
     io::{BufRead, Seek, Write},
 };
 
+use crate::base::{PastureIoError, Result};
+
 /// Reads a JSON header (e.g. FeatureTable or BatchTable header) from the given `reader` according to the given
 /// `header_size`
 pub fn read_json_header<R: BufRead + Seek>(mut reader: R, header_size: usize) -> Result<Value> {
     let mut str_buf = vec![0; header_size];
-    reader
-        .read_exact(str_buf.as_mut_slice())
-        .context("JSON header size is larger than the remaining number of bytes in the reader")?;
-    let header_text = String::from_utf8(str_buf).context("JSON header is no valid UTF-8 text")?;
-    let json: Value =
-        serde_json::from_str(header_text.as_str()).context("Could not parse JSON header")?;
+    reader.read_exact(str_buf.as_mut_slice()).map_err(|e| {
+        PastureIoError::InvalidData(format!(
+            "JSON header size is larger than the remaining number of bytes in the reader: {}",
+            e
+        ))
+    })?;
+    let header_text = String::from_utf8(str_buf).map_err(|e| {
+        PastureIoError::InvalidData(format!("JSON header is no valid UTF-8 text: {}", e))
+    })?;
+    let json: Value = serde_json::from_str(header_text.as_str())
+        .map_err(|e| PastureIoError::InvalidData(format!("Could not parse JSON header: {}", e)))?;
 
     Ok(json)
 }
@@ -32,12 +38,11 @@ pub fn write_json_header<W: Write>(
     position_in_file: usize,
 ) -> Result<()> {
     // Convert to CString, then fill with padding bytes if required
-    let header_json =
-        serde_json::to_string(json_header).context("Could not convert JSON header to string")?;
+    let header_json = serde_json::to_string(json_header)?;
 
     writer
         .write_all(header_json.as_bytes())
-        .context("Could not write JSON header to writer")?;
+        .map_err(PastureIoError::Io)?;
 
     let current_position_in_file = position_in_file + header_json.as_bytes().len();
 
@@ -47,7 +52,7 @@ pub fn write_json_header<W: Write>(
     if num_padding_bytes > 0 {
         writer
             .write(&vec![0x20; num_padding_bytes as usize])
-            .context("Could not write padding bytes of JSON header to writer")?;
+            .map_err(PastureIoError::Io)?;
     }
 
     Ok(())
@@ -56,14 +61,18 @@ pub fn write_json_header<W: Write>(
 /// Converts an array of JSON Values into a Vector3<f32>
 pub fn json_arr_to_vec3f32(json_arr: &[Value]) -> Result<Vector3<f32>> {
     if json_arr.len() != 3 {
-        bail!(
+        return Err(PastureIoError::InvalidData(format!(
             "JSON array must have length 3 to convert to Vector3<f32> (but has length {})",
             json_arr.len()
-        )
+        )));
     }
     let vals = json_arr
         .iter()
-        .map(|v| v.as_f64().ok_or(anyhow!("Can't convert JSON value to f64")))
+        .map(|v| {
+            v.as_f64().ok_or_else(|| {
+                PastureIoError::InvalidData("Can't convert JSON value to f64".into())
+            })
+        })
         .collect::<Result<Vec<_>, _>>()?;
 
     let x = vals[0] as f32;
@@ -75,14 +84,18 @@ pub fn json_arr_to_vec3f32(json_arr: &[Value]) -> Result<Vector3<f32>> {
 
 pub fn json_arr_to_vec4u8(json_arr: &[Value]) -> Result<Vector4<u8>> {
     if json_arr.len() != 4 {
-        bail!(
+        return Err(PastureIoError::InvalidData(format!(
             "JSON array must have length 4 to convert to Vector4<u8> (but has length {})",
             json_arr.len()
-        )
+        )));
     }
     let vals = json_arr
         .iter()
-        .map(|v| v.as_u64().ok_or(anyhow!("Can't convert JSON value to u64")))
+        .map(|v| {
+            v.as_u64().ok_or_else(|| {
+                PastureIoError::InvalidData("Can't convert JSON value to u64".into())
+            })
+        })
         .collect::<Result<Vec<_>, _>>()?;
 
     let r: u8 = vals[0].try_into()?;
@@ -1,4 +1,3 @@
-use anyhow::{anyhow, Result};
 use pasture_core::math::Alignable;
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
@@ -10,6 +9,7 @@ use std::{
 };
 
 use super::{read_json_header, write_json_header};
+use crate::base::{PastureIoError, Result};
 
 /// A reference to data inside a FeatureTable binary body
 #[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
@@ -29,7 +29,7 @@ pub enum FeatureTableValue {
 }
 
 impl TryFrom<Value> for FeatureTableValue {
-    type Error = anyhow::Error;
+    type Error = PastureIoError;
 
     fn try_from(val: Value) -> Result<Self> {
         if val.is_array() {
@@ -54,7 +54,7 @@ impl TryFrom<Value> for FeatureTableValue {
 }
 
 impl TryFrom<&Value> for FeatureTableValue {
-    type Error = anyhow::Error;
+    type Error = PastureIoError;
 
     fn try_from(val: &Value) -> Result<Self> {
         if val.is_array() {
@@ -126,9 +126,9 @@ pub fn deser_feature_table_header<R: BufRead + Seek>(
         reader.seek(SeekFrom::Current(padding_bytes as i64))?;
     }
 
-    let feature_table_obj = feature_table_header_json
-        .as_object()
-        .ok_or(anyhow!("FeatureTable JSON header was no JSON object"))?;
+    let feature_table_obj = feature_table_header_json.as_object().ok_or_else(|| {
+        PastureIoError::InvalidData("FeatureTable JSON header was no JSON object".into())
+    })?;
     // Convert the object to our `FeatureTableHeader` type
     Ok(feature_table_obj
         .iter()
@@ -3,7 +3,7 @@ use std::{any::Any, fmt::Display};
 use pasture_core::{
     math::AABB,
     meta::Metadata,
-    nalgebra::{Vector3, Vector4},
+    nalgebra::{Matrix4, Translation3, Vector3, Vector4},
 };
 
 /// Metadata for .pnts files. Contains the PNTS global semantics
@@ -15,6 +15,7 @@ pub struct PntsMetadata {
     quantized_volume_scale: Option<Vector3<f32>>,
     constant_rgba: Option<Vector4<u8>>,
     batch_length: Option<usize>,
+    positions_relative_to_center: bool,
 }
 
 impl PntsMetadata {
@@ -33,6 +34,7 @@ impl PntsMetadata {
             quantized_volume_scale,
             constant_rgba,
             batch_length,
+            positions_relative_to_center: false,
         }
     }
 
@@ -43,6 +45,12 @@ impl PntsMetadata {
     pub fn rtc_center(&self) -> Option<Vector3<f32>> {
         self.rtc_center
     }
+
+    /// Sets whether the positions that are read are relative to `RTC_CENTER` (see `PntsReadPositionsMode`). In this
+    /// case, `RTC_CENTER` is returned as the transformation of the positions (see `Metadata::transform`)
+    pub(crate) fn set_positions_relative_to_center(&mut self, positions_relative_to_center: bool) {
+        self.positions_relative_to_center = positions_relative_to_center;
+    }
 }
 
 impl Metadata for PntsMetadata {
@@ -73,6 +81,20 @@ impl Metadata for PntsMetadata {
         }
     }
 
+    fn transform(&self) -> Option<Matrix4<f64>> {
+        if !self.positions_relative_to_center {
+            return None;
+        }
+        self.rtc_center.map(|rtc_center| {
+            Translation3::new(
+                rtc_center.x as f64,
+                rtc_center.y as f64,
+                rtc_center.z as f64,
+            )
+            .to_homogeneous()
+        })
+    }
+
     fn clone_into_box(&self) -> Box<dyn Metadata> {
         Box::new(self.clone())
     }
@@ -6,7 +6,6 @@ use std::{
     path::Path,
 };
 
-use anyhow::{anyhow, bail, Context, Result};
 use pasture_core::{
     containers::{
         PerAttributePointBufferMut, PerAttributeVecPointStorage, PointBuffer, PointBufferWriteable,
@@ -23,7 +22,10 @@ use pasture_core::{
 
 use crate::tiles3d::{deser_feature_table_header, FeatureTableValue, PntsHeader};
 use crate::{
-    base::{PointReader, SeekToPoint},
+    base::{
+        read_attribute_values_to_native, ByteOrder, Capabilities, PastureIoError, PointReader,
+        Result, SeekToPoint,
+    },
     tiles3d::{attributes::COLOR_RGBA, json_arr_to_vec3f32, json_arr_to_vec4u8},
 };
 
@@ -52,14 +54,18 @@ pub struct PntsReader<R: BufRead + Seek> {
 
 impl<R: BufRead + Seek> PntsReader<R> {
     pub fn from_path<P: AsRef<Path>>(path: P) -> Result<PntsReader<BufReader<File>>> {
-        let reader = BufReader::new(File::open(path)?);
+        let reader = BufReader::new(File::open(path).map_err(PastureIoError::Io)?);
         PntsReader::<BufReader<File>>::from_read(reader)
     }
 
     pub fn from_read(mut read: R) -> Result<PntsReader<R>> {
         // PNTS is little-endian, this is the default of bincode
-        let header: PntsHeader = bincode::deserialize_from(&mut read)
-            .context("Could not deserialize PNTS header from reader")?;
+        let header: PntsHeader = bincode::deserialize_from(&mut read).map_err(|e| {
+            PastureIoError::InvalidData(format!(
+                "Could not deserialize PNTS header from reader: {}",
+                e
+            ))
+        })?;
         header.verify_magic()?;
         let position_after_header = read.seek(SeekFrom::Current(0))? as usize;
         assert_eq!(position_after_header, PntsHeader::BYTE_LENGTH);
@@ -106,6 +112,10 @@ impl<R: BufRead + Seek> PntsReader<R> {
     /// Sets the `PntsReadPositionsMode` for this `PntsReader`
     pub fn set_read_positions_mode(&mut self, read_mode: PntsReadPositionsMode) {
         self.read_positions_mode = read_mode;
+        self.metadata.set_positions_relative_to_center(matches!(
+            read_mode,
+            PntsReadPositionsMode::RelativeToCenter
+        ));
     }
 
     /// Returns the `PntsReadPositionsMode` for this `PntsReader`. The default value is always `PntsReadPositionsMode::Absolute`.
@@ -131,7 +141,7 @@ impl<R: BufRead + Seek> PntsReader<R> {
                     attribute_offsets.insert(POSITION_3D.name().to_owned(), reference.byte_offset as u64);
                     layout.add_attribute(POSITION_3D.with_custom_datatype(PointAttributeDataType::Vec3f32), FieldAlignment::Packed(1));
                 },
-                _ => bail!("Found PNTS attribute POSITION ({:?}) but it was not a reference to the feature table binary!", pos_attribute),
+                _ => return Err(PastureIoError::InvalidData(format!("Found PNTS attribute POSITION ({:?}) but it was not a reference to the feature table binary!", pos_attribute))),
             }
             header.remove("POSITION");
         }
@@ -145,7 +155,7 @@ impl<R: BufRead + Seek> PntsReader<R> {
                     attribute_offsets.insert(COLOR_RGBA.name().to_owned(), reference.byte_offset as u64);
                     layout.add_attribute(COLOR_RGBA, FieldAlignment::Packed(1));
                 },
-                _ => bail!("Found PNTS attribute RGBA ({:?}) but it was not a reference to the feature table binary!", color_attribute),
+                _ => return Err(PastureIoError::InvalidData(format!("Found PNTS attribute RGBA ({:?}) but it was not a reference to the feature table binary!", color_attribute))),
             }
             header.remove("RGBA");
         }
@@ -157,7 +167,7 @@ impl<R: BufRead + Seek> PntsReader<R> {
                     attribute_offsets.insert(COLOR_RGB.name().to_owned(), reference.byte_offset as u64);
                     layout.add_attribute(COLOR_RGB.with_custom_datatype(PointAttributeDataType::Vec3u8), FieldAlignment::Packed(1));
                 },
-                _ => bail!("Found PNTS attribute RGB ({:?}) but it was not a reference to the feature table binary!", color_attribute),
+                _ => return Err(PastureIoError::InvalidData(format!("Found PNTS attribute RGB ({:?}) but it was not a reference to the feature table binary!", color_attribute))),
             }
             header.remove("RGB");
         }
@@ -171,7 +181,7 @@ impl<R: BufRead + Seek> PntsReader<R> {
                     attribute_offsets.insert(NORMAL.name().to_owned(), reference.byte_offset as u64);
                     layout.add_attribute(NORMAL ,FieldAlignment::Packed(1));
                 },
-                _ => bail!("Found PNTS attribute NORMAL ({:?}) but it was not a reference to the feature table binary!", normal_attribute),
+                _ => return Err(PastureIoError::InvalidData(format!("Found PNTS attribute NORMAL ({:?}) but it was not a reference to the feature table binary!", normal_attribute))),
             }
             header.remove("NORMAL");
         }
@@ -189,21 +199,30 @@ impl<R: BufRead + Seek> PntsReader<R> {
         let num_points = header
             .get("POINTS_LENGTH")
             .map(|entry| match entry {
-                FeatureTableValue::SingleValue(v) => v
-                    .as_u64()
-                    .map(|val| val as usize)
-                    .ok_or(anyhow!("POINTS_LENGTH value vas no integer number")),
-                _ => Err(anyhow!("POINTS_LENGTH value was no single value entry")),
+                FeatureTableValue::SingleValue(v) => {
+                    v.as_u64().map(|val| val as usize).ok_or_else(|| {
+                        PastureIoError::InvalidData(
+                            "POINTS_LENGTH value vas no integer number".into(),
+                        )
+                    })
+                }
+                _ => Err(PastureIoError::InvalidData(
+                    "POINTS_LENGTH value was no single value entry".into(),
+                )),
             })
-            .ok_or(anyhow!(
-                "Mandatory value POINTS_LENGTH not found in feature table header"
-            ))??;
+            .ok_or_else(|| {
+                PastureIoError::InvalidData(
+                    "Mandatory value POINTS_LENGTH not found in feature table header".into(),
+                )
+            })??;
 
         let rtc_center = header
             .get("RTC_CENTER")
             .map(|entry| match entry {
                 FeatureTableValue::Array(array) => json_arr_to_vec3f32(&array),
-                _ => Err(anyhow!("RTC_CENTER value was no array entry")),
+                _ => Err(PastureIoError::InvalidData(
+                    "RTC_CENTER value was no array entry".into(),
+                )),
             })
             .transpose()?;
 
@@ -211,7 +230,9 @@ impl<R: BufRead + Seek> PntsReader<R> {
             .get("QUANTIZED_VOLUME_OFFSET")
             .map(|entry| match entry {
                 FeatureTableValue::Array(array) => json_arr_to_vec3f32(&array),
-                _ => Err(anyhow!("QUANTIZED_VOLUME_OFFSET value was no array entry")),
+                _ => Err(PastureIoError::InvalidData(
+                    "QUANTIZED_VOLUME_OFFSET value was no array entry".into(),
+                )),
             })
             .transpose()?;
 
@@ -219,7 +240,9 @@ impl<R: BufRead + Seek> PntsReader<R> {
             .get("QUANTIZED_VOLUME_SCALE")
             .map(|entry| match entry {
                 FeatureTableValue::Array(array) => json_arr_to_vec3f32(&array),
-                _ => Err(anyhow!("QUANTIZED_VOLUME_SCALE value was no array entry")),
+                _ => Err(PastureIoError::InvalidData(
+                    "QUANTIZED_VOLUME_SCALE value was no array entry".into(),
+                )),
             })
             .transpose()?;
 
@@ -227,18 +250,25 @@ impl<R: BufRead + Seek> PntsReader<R> {
             .get("CONSTANT_RGBA")
             .map(|entry| match entry {
                 FeatureTableValue::Array(array) => json_arr_to_vec4u8(&array),
-                _ => Err(anyhow!("CONSTANT_RGBA value was no array entry")),
+                _ => Err(PastureIoError::InvalidData(
+                    "CONSTANT_RGBA value was no array entry".into(),
+                )),
             })
             .transpose()?;
 
         let batch_length = header
             .get("BATCH_LENGTH")
             .map(|entry| match entry {
-                FeatureTableValue::SingleValue(v) => v
-                    .as_u64()
-                    .map(|val| val as usize)
-                    .ok_or(anyhow!("BATCH_LENGTH value was no integer number")),
-                _ => Err(anyhow!("BATCH_LENGTH value was no single value entry")),
+                FeatureTableValue::SingleValue(v) => {
+                    v.as_u64().map(|val| val as usize).ok_or_else(|| {
+                        PastureIoError::InvalidData(
+                            "BATCH_LENGTH value was no integer number".into(),
+                        )
+                    })
+                }
+                _ => Err(PastureIoError::InvalidData(
+                    "BATCH_LENGTH value was no single value entry".into(),
+                )),
             })
             .transpose()?;
 
@@ -291,7 +321,9 @@ impl<R: BufRead + Seek> PointReader for PntsReader<R> {
         let remaining_points = self.metadata.points_length() - self.current_point_index;
         let num_to_read = usize::min(remaining_points, count);
         if num_to_read == 0 {
-            bail!("No points remaining in PNTS file")
+            return Err(PastureIoError::InvalidArgument(
+                "No points remaining in PNTS file".into(),
+            ));
         }
 
         let mut buffer = PerAttributeVecPointStorage::new(self.layout.clone());
@@ -307,7 +339,12 @@ impl<R: BufRead + Seek> PointReader for PntsReader<R> {
                 .seek(SeekFrom::Start(offset_to_current_point_of_attribute))?;
             let target_buffer =
                 buffer.get_raw_attribute_range_mut(0..num_to_read, &attribute.into());
-            self.reader.read_exact(target_buffer)?;
+            read_attribute_values_to_native(
+                &mut self.reader,
+                target_buffer,
+                attribute.datatype(),
+                ByteOrder::LittleEndian,
+            )?;
         }
 
         self.current_point_index += num_to_read;
@@ -327,7 +364,9 @@ impl<R: BufRead + Seek> PointReader for PntsReader<R> {
         let remaining_points = self.metadata.points_length() - self.current_point_index;
         let num_to_read = usize::min(remaining_points, count);
         if num_to_read == 0 {
-            bail!("No points remaining in PNTS file")
+            return Err(PastureIoError::InvalidArgument(
+                "No points remaining in PNTS file".into(),
+            ));
         }
 
         let target_layout = point_buffer.point_layout().clone();
@@ -352,7 +391,12 @@ impl<R: BufRead + Seek> PointReader for PntsReader<R> {
                     let mut dst_buf: Vec<u8> = vec![0; attribute.size() as usize];
                     let target_attribute_def: PointAttributeDefinition = target_attribute.into();
                     for point_index in 0..num_to_read {
-                        self.reader.read_exact(src_buf.as_mut_slice())?;
+                        read_attribute_values_to_native(
+                            &mut self.reader,
+                            src_buf.as_mut_slice(),
+                            attribute.datatype(),
+                            ByteOrder::LittleEndian,
+                        )?;
                         unsafe {
                             conversion_fn(src_buf.as_slice(), dst_buf.as_mut_slice());
                         }
@@ -366,7 +410,12 @@ impl<R: BufRead + Seek> PointReader for PntsReader<R> {
                     let mut buf: Vec<u8> = vec![0; attribute.size() as usize];
                     let target_attribute_def: PointAttributeDefinition = target_attribute.into();
                     for point_index in 0..num_to_read {
-                        self.reader.read_exact(buf.as_mut_slice())?;
+                        read_attribute_values_to_native(
+                            &mut self.reader,
+                            buf.as_mut_slice(),
+                            attribute.datatype(),
+                            ByteOrder::LittleEndian,
+                        )?;
                         point_buffer.set_raw_attribute(
                             point_index,
                             &target_attribute_def,
@@ -393,6 +442,13 @@ impl<R: BufRead + Seek> PointReader for PntsReader<R> {
     fn get_default_point_layout(&self) -> &PointLayout {
         &self.layout
     }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities {
+            seekable: true,
+            ..Capabilities::new(self.get_default_point_layout())
+        }
+    }
 }
 
 impl<R: BufRead + Seek> SeekToPoint for PntsReader<R> {
@@ -421,7 +477,7 @@ mod tests {
     use crate::{base::PointWriter, tiles3d::PntsWriter};
 
     use super::*;
-    use pasture_core::{containers::PointBufferExt, layout::PointType};
+    use pasture_core::{containers::PointBufferExt, layout::PointType, nalgebra::Translation3};
     use pasture_derive::PointType;
 
     #[repr(C, packed)]
@@ -461,6 +517,7 @@ mod tests {
 
             let actual_points = points.iter_point::<TestPoint>().collect::<Vec<_>>();
             assert_eq!(test_points_global, actual_points);
+            assert_eq!(None, reader.get_metadata().transform());
         }
 
         cursor.seek(SeekFrom::Start(0)).unwrap();
@@ -474,6 +531,10 @@ mod tests {
 
             let actual_points = points.iter_point::<TestPoint>().collect::<Vec<_>>();
             assert_eq!(test_points, actual_points);
+            assert_eq!(
+                Some(Translation3::new(10.0, 10.0, 10.0).to_homogeneous()),
+                reader.get_metadata().transform()
+            );
         }
     }
 }
@@ -1,7 +1,8 @@
-use anyhow::{bail, Result};
 use serde::{Deserialize, Serialize};
 use static_assertions::const_assert;
 
+use crate::base::{PastureIoError, Result};
+
 pub mod attributes {
     use pasture_core::layout::{PointAttributeDataType, PointAttributeDefinition};
 
@@ -49,7 +50,10 @@ impl PntsHeader {
     /// Returns an Err if the magic bytes in this header are not correct
     pub fn verify_magic(&self) -> Result<()> {
         if self.magic != [b'p', b'n', b't', b's'] {
-            bail!("No valid PNTS file, expected first four bytes to be equal to 'pnts', but was '{:?}' instead", self.magic);
+            return Err(PastureIoError::CorruptHeader {
+                offset: 0,
+                message: format!("No valid PNTS file, expected first four bytes to be equal to 'pnts', but was '{:?}' instead", self.magic),
+            });
         }
         Ok(())
     }
@@ -1,10 +1,10 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
     convert::TryInto,
     io::{Cursor, Seek, SeekFrom, Write},
 };
 
-use anyhow::{Context, Result};
 use pasture_core::{
     containers::{
         PerAttributePointBuffer, PerAttributeVecPointStorage, PointBuffer, PointBufferWriteable,
@@ -20,7 +20,7 @@ use pasture_core::{
 use serde_json::json;
 
 use crate::{
-    base::PointWriter,
+    base::{convert_attribute_byte_order, ByteOrder, PastureIoError, PointWriter, Result},
     tiles3d::{
         attributes::COLOR_RGBA, ser_batch_table_header, ser_feature_table_header, PntsHeader,
     },
@@ -161,8 +161,7 @@ impl<W: Write + Seek> PntsWriter<W> {
             Cursor::new(&mut feature_table_blob),
             &feature_table_header,
             PntsHeader::BYTE_LENGTH,
-        )
-        .context("Error serializing FeatureTable header")?;
+        )?;
 
         let feature_table_byte_size = feature_table_blob.len();
         let feature_table_body_byte_size = self.calc_feature_table_body_length();
@@ -178,8 +177,7 @@ impl<W: Write + Seek> PntsWriter<W> {
             Cursor::new(&mut batch_table_blob),
             &batch_table_header,
             start_of_batch_table_header,
-        )
-        .context("Error serializing BatchTable header")?;
+        )?;
         let batch_table_byte_size = batch_table_blob.len();
         //TODO Support batch table body
         let batch_table_body_byte_size: usize = 0;
@@ -206,15 +204,14 @@ impl<W: Write + Seek> PntsWriter<W> {
                 .expect("Size of BatchTable binary body exceeds maximum size of 4GiB!"),
         );
 
-        bincode::serialize_into(&mut self.writer, &pnts_header)
-            .context("Error while serializing .pnts header")?;
+        bincode::serialize_into(&mut self.writer, &pnts_header)?;
         self.writer
             .write(feature_table_blob.as_slice())
-            .context("Error while writing FeatureTable header")?;
+            .map_err(PastureIoError::Io)?;
         self.write_feature_table_body()?;
         self.writer
             .write(batch_table_blob.as_slice())
-            .context("Error while writing BatchTable header")?;
+            .map_err(PastureIoError::Io)?;
         // TODO Write BatchTable binary body. For now, it doesn't exist, so we don't have to write anything
 
         self.requires_flush = false;
@@ -298,9 +295,22 @@ impl<W: Write + Seek> PntsWriter<W> {
             let attribute_data = self
                 .cached_points
                 .get_raw_attribute_range_ref(0..num_points, &attribute.into());
+            // Binary data in 3D Tiles is always little-endian
+            let attribute_data = if ByteOrder::LittleEndian.is_native() {
+                Cow::Borrowed(attribute_data)
+            } else {
+                let mut converted_data = attribute_data.to_vec();
+                convert_attribute_byte_order(
+                    &mut converted_data,
+                    attribute.datatype(),
+                    ByteOrder::NATIVE,
+                    ByteOrder::LittleEndian,
+                );
+                Cow::Owned(converted_data)
+            };
             self.writer
-                .write_all(attribute_data)
-                .context("Error while writing attribute data")?;
+                .write_all(&attribute_data)
+                .map_err(PastureIoError::Io)?;
 
             let blob_byte_size = attribute.size() as usize * self.cached_points.len();
             let num_padding_bytes =
@@ -309,7 +319,7 @@ impl<W: Write + Seek> PntsWriter<W> {
                 let padding_bytes = vec![0; num_padding_bytes];
                 self.writer
                     .write_all(padding_bytes.as_slice())
-                    .context("Error while writing padding bytes")?;
+                    .map_err(PastureIoError::Io)?;
             }
         }
 
@@ -328,7 +338,7 @@ impl<W: Write + Seek> PntsWriter<W> {
 impl<W: Write + Seek> PointWriter for PntsWriter<W> {
     fn write(&mut self, points: &dyn PointBuffer) -> Result<()> {
         if points.point_layout() != &self.expected_layout {
-            panic!("PointLayout of buffer does not match the PointLayout that this PntsReader was constructed with! Make sure that you only pass PointBuffers with the same layout as the one you used to create this PntsWriter!");
+            return Err(PastureIoError::LayoutMismatch("PointLayout of buffer does not match the PointLayout that this PntsWriter was constructed with! Make sure that you only pass PointBuffers with the same layout as the one you used to create this PntsWriter!".into()));
         }
 
         if points.point_layout() == self.cached_points.point_layout() {
@@ -404,6 +414,7 @@ mod tests {
     use crate::{base::PointReader, tiles3d::PntsReader};
 
     use super::*;
+    use anyhow::{Context, Result};
     use pasture_core::{
         containers::PointBufferExt,
         layout::PointType,
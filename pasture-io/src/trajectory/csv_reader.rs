@@ -0,0 +1,115 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::Path,
+};
+
+use pasture_core::nalgebra::Vector3;
+
+use super::{Trajectory, TrajectorySample};
+use crate::base::{PastureIoError, Result};
+
+/// Reads a trajectory from CSV data in the given `reader`. Each line contains the columns `time, x, y, z` or
+/// `time, x, y, z, roll, pitch, heading`, separated by `delimiter`, with the angles in degrees. Empty lines, lines
+/// starting with `#` and a header line (a first line that doesn't start with a number) are skipped
+///
+/// ```
+/// # use anyhow::Result;
+/// # use pasture_io::trajectory::read_trajectory_csv_from;
+/// # fn main() -> Result<()> {
+/// let csv = "time,x,y,z\n0.0,10.0,20.0,100.0\n1.0,12.0,20.0,100.0\n";
+/// let trajectory = read_trajectory_csv_from(csv.as_bytes(), ',')?;
+/// assert_eq!(2, trajectory.len());
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Errors
+///
+/// If an I/O error occurs, or if a line has the wrong number of columns or a column is not a number, an error is
+/// returned
+pub fn read_trajectory_csv_from<R: BufRead>(reader: R, delimiter: char) -> Result<Trajectory> {
+    let mut samples = vec![];
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line.map_err(PastureIoError::Io)?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let columns = line
+            .split(delimiter)
+            .map(|column| column.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>();
+        let columns = match columns {
+            Ok(columns) => columns,
+            Err(_) if line_index == 0 => continue,
+            Err(error) => {
+                return Err(PastureIoError::InvalidData(format!(
+                    "Invalid number in line {}: {}",
+                    line_index + 1,
+                    error
+                )))
+            }
+        };
+        let orientation = match columns.len() {
+            4 => Vector3::zeros(),
+            7 => Vector3::new(
+                columns[4].to_radians(),
+                columns[5].to_radians(),
+                columns[6].to_radians(),
+            ),
+            column_count => {
+                return Err(PastureIoError::InvalidData(format!(
+                    "Line {} has {} columns, but a trajectory requires 4 (time, x, y, z) or 7 (time, x, y, z, roll, pitch, heading) columns",
+                    line_index + 1,
+                    column_count
+                )))
+            }
+        };
+        samples.push(TrajectorySample {
+            time: columns[0],
+            position: Vector3::new(columns[1], columns[2], columns[3]),
+            orientation,
+        });
+    }
+    Ok(Trajectory::new(samples))
+}
+
+/// Reads a trajectory from the CSV file at `path`. See [read_trajectory_csv_from] for the supported columns
+///
+/// # Errors
+///
+/// If the file can't be opened, or if it is not a valid trajectory file, an error is returned
+pub fn read_trajectory_csv<P: AsRef<Path>>(path: P, delimiter: char) -> Result<Trajectory> {
+    let file = File::open(path.as_ref()).map_err(PastureIoError::Io)?;
+    read_trajectory_csv_from(BufReader::new(file), delimiter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_trajectory_csv_with_orientation() -> Result<()> {
+        let csv = "# Exported trajectory\n10.0; 1.0; 2.0; 3.0; 0.0; 0.0; 90.0\n\n5.0; 0.0; 0.0; 0.0; 0.0; 0.0; 180.0\n";
+        let trajectory = read_trajectory_csv_from(csv.as_bytes(), ';')?;
+        assert_eq!(2, trajectory.len());
+        let first = trajectory.samples()[0];
+        assert_eq!(5.0, first.time);
+        assert_eq!(Vector3::new(0.0, 0.0, 0.0), first.position);
+        assert!((first.orientation - Vector3::new(0.0, 0.0, std::f64::consts::PI)).norm() < 1e-9);
+        assert_eq!(
+            Vector3::new(1.0, 2.0, 3.0),
+            trajectory.samples()[1].position
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_invalid_trajectory_csv() {
+        assert!(read_trajectory_csv_from("0.0,1.0,2.0\n".as_bytes(), ',').is_err());
+        assert!(
+            read_trajectory_csv_from("0.0,1.0,2.0,3.0\n1.0,x,2.0,3.0\n".as_bytes(), ',').is_err()
+        );
+    }
+}
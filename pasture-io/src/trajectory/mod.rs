@@ -0,0 +1,8 @@
+mod trajectory_types;
+pub use self::trajectory_types::*;
+
+mod sbet_reader;
+pub use self::sbet_reader::*;
+
+mod csv_reader;
+pub use self::csv_reader::*;
@@ -0,0 +1,109 @@
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind, Read},
+    path::Path,
+};
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use pasture_core::nalgebra::Vector3;
+
+use super::{Trajectory, TrajectorySample};
+use crate::base::{PastureIoError, Result};
+
+/// Number of `f64` values in a single record of an SBET file
+const SBET_VALUES_PER_RECORD: usize = 17;
+
+/// Reads a trajectory in the SBET format (smoothed best estimate of trajectory) of Applanix POSPac from the given
+/// `reader`. An SBET file is a sequence of records with 17 little-endian `f64` values, of which the time, the
+/// position and the orientation are used. The positions are geographic: `x` is the longitude and `y` the latitude (both
+/// in degrees), `z` is the ellipsoidal height. Use [Trajectory::map_positions] to transform them into the coordinate
+/// reference system of the points before joining the trajectory
+///
+/// # Errors
+///
+/// If an I/O error occurs, or if the data ends within a record, an error is returned
+pub fn read_sbet_from<R: Read>(mut reader: R) -> Result<Trajectory> {
+    let mut samples = vec![];
+    let mut record = [0.0; SBET_VALUES_PER_RECORD];
+    loop {
+        match reader.read_f64::<LittleEndian>() {
+            Ok(time) => record[0] = time,
+            Err(error) if error.kind() == ErrorKind::UnexpectedEof => break,
+            Err(error) => return Err(PastureIoError::Io(error)),
+        }
+        if let Err(error) = reader.read_f64_into::<LittleEndian>(&mut record[1..]) {
+            if error.kind() == ErrorKind::UnexpectedEof {
+                return Err(PastureIoError::InvalidData(format!(
+                    "SBET data ends within record {}, the size of SBET data must be a multiple of {} bytes",
+                    samples.len(),
+                    SBET_VALUES_PER_RECORD * 8
+                )));
+            }
+            return Err(PastureIoError::Io(error));
+        }
+
+        // Record layout: time, latitude, longitude, altitude, x/y/z velocity, roll, pitch, heading, wander angle,
+        // x/y/z acceleration, x/y/z angular rate. Latitude and longitude are in radians
+        samples.push(TrajectorySample {
+            time: record[0],
+            position: Vector3::new(record[2].to_degrees(), record[1].to_degrees(), record[3]),
+            orientation: Vector3::new(record[7], record[8], record[9]),
+        });
+    }
+    Ok(Trajectory::new(samples))
+}
+
+/// Reads a trajectory from the SBET file at `path`. See [read_sbet_from] for details
+///
+/// # Errors
+///
+/// If the file can't be opened, or if it is not a valid SBET file, an error is returned
+pub fn read_sbet<P: AsRef<Path>>(path: P) -> Result<Trajectory> {
+    let file = File::open(path.as_ref()).map_err(PastureIoError::Io)?;
+    read_sbet_from(BufReader::new(file))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use byteorder::WriteBytesExt;
+
+    use super::*;
+
+    fn sbet_record(time: f64, latitude: f64, longitude: f64, altitude: f64) -> Vec<u8> {
+        let mut values = [0.0; SBET_VALUES_PER_RECORD];
+        values[0] = time;
+        values[1] = latitude.to_radians();
+        values[2] = longitude.to_radians();
+        values[3] = altitude;
+        values[9] = 0.5;
+        let mut bytes = vec![];
+        for value in values.iter() {
+            bytes.write_f64::<LittleEndian>(*value).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_read_sbet() -> Result<()> {
+        let mut data = sbet_record(100.0, 49.5, 8.25, 250.0);
+        data.extend(sbet_record(100.5, 49.75, 8.5, 260.0));
+
+        let trajectory = read_sbet_from(Cursor::new(data))?;
+        assert_eq!(2, trajectory.len());
+        let first = trajectory.samples()[0];
+        assert_eq!(100.0, first.time);
+        assert!((first.position - Vector3::new(8.25, 49.5, 250.0)).norm() < 1e-9);
+        assert_eq!(Vector3::new(0.0, 0.0, 0.5), first.orientation);
+        assert_eq!(Some((100.0, 100.5)), trajectory.time_range());
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_truncated_sbet() {
+        let mut data = sbet_record(100.0, 49.5, 8.25, 250.0);
+        data.truncate(100);
+        assert!(read_sbet_from(Cursor::new(data)).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use pasture_core::{
+    containers::{PointBuffer, PointBufferExt, PointBufferWriteable, PointBufferWriteableExt},
+    layout::attributes::{GPS_TIME, SENSOR_POSITION},
+    nalgebra::Vector3,
+};
+
+use crate::base::{PastureIoError, Result};
+
+/// A single sample of a sensor trajectory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrajectorySample {
+    /// Time of the sample, in the same time base as the `GPS_TIME` attribute of the points
+    pub time: f64,
+    /// Position of the sensor
+    pub position: Vector3<f64>,
+    /// Orientation of the sensor as roll, pitch and heading, in radians
+    pub orientation: Vector3<f64>,
+}
+
+/// Returns the difference `to - from` between two angles in radians, wrapped into `[-PI, PI]`
+fn angle_difference(from: f64, to: f64) -> f64 {
+    let two_pi = 2.0 * std::f64::consts::PI;
+    let difference = (to - from) % two_pi;
+    if difference > std::f64::consts::PI {
+        difference - two_pi
+    } else if difference < -std::f64::consts::PI {
+        difference + two_pi
+    } else {
+        difference
+    }
+}
+
+/// The trajectory of a moving sensor, like the aircraft of an airborne scan or the vehicle of a mobile mapping system,
+/// as a sequence of samples ordered by time. The position and orientation of the sensor at the time a point was captured
+/// are interpolated linearly between the samples, so the trajectory can be joined to points through their GPS times
+/// (see [join_sensor_positions](Trajectory::join_sensor_positions)). Trajectories can be read from SBET files with
+/// [read_sbet] and from CSV files with [read_trajectory_csv]
+///
+/// [read_sbet]: super::read_sbet
+/// [read_trajectory_csv]: super::read_trajectory_csv
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Trajectory {
+    samples: Vec<TrajectorySample>,
+}
+
+impl Trajectory {
+    /// Creates a new `Trajectory` from the given `samples`. The samples are sorted by time
+    pub fn new(mut samples: Vec<TrajectorySample>) -> Self {
+        samples.sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
+        Self { samples }
+    }
+
+    /// Returns the samples of this trajectory, sorted by time
+    pub fn samples(&self) -> &[TrajectorySample] {
+        &self.samples
+    }
+
+    /// Returns the number of samples of this trajectory
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Is this trajectory empty?
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Returns the time of the first and the last sample, or `None` if the trajectory is empty
+    pub fn time_range(&self) -> Option<(f64, f64)> {
+        match (self.samples.first(), self.samples.last()) {
+            (Some(first), Some(last)) => Some((first.time, last.time)),
+            _ => None,
+        }
+    }
+
+    /// Applies `func` to the positions of all samples, e.g. to transform the geographic positions of an SBET file into
+    /// the coordinate reference system of the points
+    pub fn map_positions<F: FnMut(Vector3<f64>) -> Vector3<f64>>(&mut self, mut func: F) {
+        for sample in self.samples.iter_mut() {
+            sample.position = func(sample.position);
+        }
+    }
+
+    /// Returns the interpolated sample at the given `time`. Returns `None` if `time` is outside of the time range of
+    /// this trajectory
+    pub fn sample_at(&self, time: f64) -> Option<TrajectorySample> {
+        let (start, end) = self.time_range()?;
+        if time < start || time > end {
+            return None;
+        }
+        let next = self.samples.partition_point(|sample| sample.time < time);
+        let after = &self.samples[next];
+        if next == 0 || after.time == time {
+            return Some(*after);
+        }
+        let before = &self.samples[next - 1];
+        let t = (time - before.time) / (after.time - before.time);
+        let orientation = Vector3::from_fn(|axis, _| {
+            before.orientation[axis]
+                + t * angle_difference(before.orientation[axis], after.orientation[axis])
+        });
+        Some(TrajectorySample {
+            time,
+            position: before.position.lerp(&after.position, t),
+            orientation,
+        })
+    }
+
+    /// Returns the interpolated position of the sensor at the given `time`. Times outside of the time range of this
+    /// trajectory are clamped to the first or last sample
+    ///
+    /// # Panics
+    ///
+    /// If the trajectory is empty
+    pub fn position_at(&self, time: f64) -> Vector3<f64> {
+        let (start, end) = self
+            .time_range()
+            .expect("Trajectory::position_at: Trajectory is empty");
+        self.sample_at(time.max(start).min(end)).unwrap().position
+    }
+
+    /// Sets the `SENSOR_POSITION` attribute of all points in `buffer` to the position of the sensor at the `GPS_TIME`
+    /// of the point. Points outside of the time range of the trajectory get the position of the first or last sample.
+    /// Returns the number of these points, which is a hint that the trajectory doesn't belong to the points
+    ///
+    /// # Errors
+    ///
+    /// If the `PointLayout` of `buffer` doesn't contain the `GPS_TIME` attribute with datatype `F64` and the
+    /// `SENSOR_POSITION` attribute with datatype `Vec3f64`, or if the trajectory is empty, an error is returned
+    pub fn join_sensor_positions(&self, buffer: &mut dyn PointBufferWriteable) -> Result<usize> {
+        if !buffer.point_layout().has_attribute(&GPS_TIME)
+            || !buffer.point_layout().has_attribute(&SENSOR_POSITION)
+        {
+            return Err(PastureIoError::LayoutMismatch(format!(
+                "Joining a trajectory requires the attributes {} and {}, but the PointLayout of the buffer is {}",
+                GPS_TIME,
+                SENSOR_POSITION,
+                buffer.point_layout()
+            )));
+        }
+        let (start, end) = match self.time_range() {
+            Some(time_range) => time_range,
+            None => {
+                return Err(PastureIoError::InvalidArgument(
+                    "Can't join an empty trajectory".into(),
+                ))
+            }
+        };
+
+        let mut points_outside = 0;
+        for index in 0..buffer.len() {
+            let gps_time = buffer.get_attribute::<f64>(&GPS_TIME, index);
+            if gps_time < start || gps_time > end {
+                points_outside += 1;
+            }
+            buffer.set_attribute(&SENSOR_POSITION, index, self.position_at(gps_time));
+        }
+        Ok(points_outside)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pasture_core::{
+        containers::PerAttributeVecPointStorage,
+        layout::{attributes::POSITION_3D, PointLayout},
+    };
+
+    use super::*;
+
+    fn sample(time: f64, position: Vector3<f64>, heading: f64) -> TrajectorySample {
+        TrajectorySample {
+            time,
+            position,
+            orientation: Vector3::new(0.0, 0.0, heading),
+        }
+    }
+
+    #[test]
+    fn test_trajectory_interpolation() {
+        let trajectory = Trajectory::new(vec![
+            sample(20.0, Vector3::new(10.0, 0.0, 100.0), -3.0),
+            sample(10.0, Vector3::new(0.0, 0.0, 100.0), 3.0),
+        ]);
+        assert_eq!(Some((10.0, 20.0)), trajectory.time_range());
+        assert_eq!(10.0, trajectory.samples()[0].time);
+
+        let interpolated = trajectory.sample_at(12.5).unwrap();
+        assert_eq!(Vector3::new(2.5, 0.0, 100.0), interpolated.position);
+        // The heading is interpolated across the discontinuity at PI
+        let two_pi = 2.0 * std::f64::consts::PI;
+        let expected_heading = 3.0 + 0.25 * (two_pi - 6.0);
+        assert!((expected_heading - interpolated.orientation.z).abs() < 1e-9);
+
+        assert_eq!(None, trajectory.sample_at(9.0));
+        assert_eq!(Vector3::new(10.0, 0.0, 100.0), trajectory.position_at(25.0));
+    }
+
+    #[test]
+    fn test_join_sensor_positions() -> Result<()> {
+        let trajectory = Trajectory::new(vec![
+            sample(0.0, Vector3::new(0.0, 0.0, 100.0), 0.0),
+            sample(10.0, Vector3::new(100.0, 0.0, 100.0), 0.0),
+        ]);
+        let mut points = PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[
+            POSITION_3D,
+            GPS_TIME,
+            SENSOR_POSITION,
+        ]));
+        points.resize(3);
+        points.set_attribute(&GPS_TIME, 0, 2.0);
+        points.set_attribute(&GPS_TIME, 1, 5.0);
+        points.set_attribute(&GPS_TIME, 2, 11.0);
+
+        assert_eq!(1, trajectory.join_sensor_positions(&mut points)?);
+        assert_eq!(
+            vec![
+                Vector3::new(20.0, 0.0, 100.0),
+                Vector3::new(50.0, 0.0, 100.0),
+                Vector3::new(100.0, 0.0, 100.0)
+            ],
+            points
+                .iter_attribute::<Vector3<f64>>(&SENSOR_POSITION)
+                .collect::<Vec<_>>()
+        );
+
+        let mut points_without_sensor_position =
+            PerAttributeVecPointStorage::new(PointLayout::from_attributes(&[GPS_TIME]));
+        assert!(trajectory
+            .join_sensor_positions(&mut points_without_sensor_position)
+            .is_err());
+        assert_eq!(0, points_without_sensor_position.len());
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use numpy::{IntoPyArray, PyArray1, PyReadonlyArray2};
+use pasture_algorithms::{
+    bounds::calculate_bounds,
+    ground::{self, GroundFilterParameters},
+    segmentation::{ransac_plane_par, ransac_plane_par_seeded},
+    voxel_grid,
+};
+use pasture_core::{
+    containers::{PerAttributeVecPointStorage, PointBuffer},
+    layout::{attributes::POSITION_3D, PointLayout},
+    nalgebra::Vector3,
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+
+/// Creates a point buffer from an array of positions with shape (N, 3)
+fn positions_to_buffer(positions: PyReadonlyArray2<f64>) -> PyResult<PerAttributeVecPointStorage> {
+    let positions = positions.as_array();
+    if positions.ncols() != 3 {
+        return Err(PyValueError::new_err(format!(
+            "Positions must have shape (N, 3), but have {} columns",
+            positions.ncols()
+        )));
+    }
+    let positions = positions
+        .outer_iter()
+        .map(|row| Vector3::new(row[0], row[1], row[2]))
+        .collect::<Vec<_>>();
+
+    let mut buffer = PerAttributeVecPointStorage::with_capacity(
+        positions.len(),
+        PointLayout::from_attributes(&[POSITION_3D]),
+    );
+    let mut pusher = buffer.begin_push_attributes();
+    pusher.push_attribute_range(&POSITION_3D, &positions);
+    pusher.done();
+    Ok(buffer)
+}
+
+/// bounds(positions)
+/// --
+///
+/// Calculates the axis-aligned bounding box of the given `positions` with shape (N, 3). Returns a tuple with the
+/// minimum and maximum corner, or None if there are no positions
+#[pyfunction]
+pub fn bounds(positions: PyReadonlyArray2<f64>) -> PyResult<Option<(Vec<f64>, Vec<f64>)>> {
+    let buffer = positions_to_buffer(positions)?;
+    Ok(calculate_bounds(&buffer).map(|bounds| {
+        let (min, max) = (bounds.min(), bounds.max());
+        (vec![min.x, min.y, min.z], vec![max.x, max.y, max.z])
+    }))
+}
+
+/// voxel_grid_filter(positions, cell_size)
+/// --
+///
+/// Overlays the `positions` with shape (N, 3) with a regular grid of cubic cells with an edge length of `cell_size`
+/// and keeps the point closest to the center of each occupied cell. Returns the indices of the kept points in
+/// ascending order
+#[pyfunction]
+pub fn voxel_grid_filter<'py>(
+    py: Python<'py>,
+    positions: PyReadonlyArray2<f64>,
+    cell_size: f64,
+) -> PyResult<&'py PyArray1<u64>> {
+    if cell_size <= 0.0 {
+        return Err(PyValueError::new_err("cell_size must be > 0"));
+    }
+    let buffer = positions_to_buffer(positions)?;
+    let indices = voxel_grid::voxel_grid_filter(&buffer, cell_size);
+    Ok(indices
+        .into_iter()
+        .map(|index| index as u64)
+        .collect::<Vec<_>>()
+        .into_pyarray(py))
+}
+
+/// classify_ground(positions, cell_size=1.0, window_radius=10, height_threshold=0.5)
+/// --
+///
+/// Classifies the `positions` with shape (N, 3) into ground and non-ground points using a morphological filter.
+/// Returns a tuple with a bool array that is True for all ground points, and an array with the height of each point
+/// above the estimated terrain surface
+#[pyfunction(cell_size = "1.0", window_radius = "10", height_threshold = "0.5")]
+pub fn classify_ground<'py>(
+    py: Python<'py>,
+    positions: PyReadonlyArray2<f64>,
+    cell_size: f64,
+    window_radius: usize,
+    height_threshold: f64,
+) -> PyResult<(&'py PyArray1<bool>, &'py PyArray1<f64>)> {
+    if cell_size <= 0.0 {
+        return Err(PyValueError::new_err("cell_size must be > 0"));
+    }
+    let buffer = positions_to_buffer(positions)?;
+    let classification = ground::classify_ground(
+        &buffer,
+        &GroundFilterParameters {
+            cell_size,
+            window_radius,
+            height_threshold,
+        },
+    );
+    Ok((
+        classification.is_ground.into_pyarray(py),
+        classification.height_above_ground.into_pyarray(py),
+    ))
+}
+
+/// ransac_plane(positions, distance_threshold, num_of_iterations=100, seed=None)
+/// --
+///
+/// Finds the plane with the most inliers within the `positions` with shape (N, 3) using RANSAC. Points with a
+/// distance of at most `distance_threshold` to the plane are inliers. Returns the indices of all inliers. If a `seed`
+/// is given, the result is the same on every run
+#[pyfunction(num_of_iterations = "100", seed = "None")]
+pub fn ransac_plane<'py>(
+    py: Python<'py>,
+    positions: PyReadonlyArray2<f64>,
+    distance_threshold: f64,
+    num_of_iterations: usize,
+    seed: Option<u64>,
+) -> PyResult<&'py PyArray1<u64>> {
+    if num_of_iterations == 0 {
+        return Err(PyValueError::new_err("num_of_iterations must be > 0"));
+    }
+    let buffer = positions_to_buffer(positions)?;
+    if buffer.len() < 3 {
+        return Err(PyValueError::new_err(
+            "At least 3 positions are required to find a plane",
+        ));
+    }
+    let (_, inliers) = match seed {
+        Some(seed) => ransac_plane_par_seeded(&buffer, distance_threshold, num_of_iterations, seed),
+        None => ransac_plane_par(&buffer, distance_threshold, num_of_iterations),
+    };
+    Ok(inliers
+        .into_iter()
+        .map(|index| index as u64)
+        .collect::<Vec<_>>()
+        .into_pyarray(py))
+}
@@ -0,0 +1,348 @@
+use std::path::Path;
+
+use anyhow::Result;
+use numpy::{ndarray::Array2, Element, IntoPyArray, PyReadonlyArray1, PyReadonlyArray2};
+use pasture_core::{
+    containers::{
+        PerAttributePointBufferMut, PerAttributeVecPointStorage, PointBuffer, PointBufferExt,
+        PointBufferWriteable,
+    },
+    layout::{
+        attributes, ArrayElement, ArrayElementType, PointAttributeDataType,
+        PointAttributeDefinition, PointLayout, PrimitiveType,
+    },
+    nalgebra::{Scalar, Vector3, Vector4},
+    util::push_raw_bytes,
+};
+use pasture_io::{
+    base::{IOFactory, PointWriter},
+    las::{las_point_format_from_point_layout, LASWriter},
+    las_rs::Builder,
+};
+use pyo3::{
+    exceptions::{PyIOError, PyValueError},
+    prelude::*,
+    types::PyDict,
+};
+
+/// The attributes that can be written from Python. Attribute names in Python are the names of these attributes
+const BUILTIN_ATTRIBUTES: &[PointAttributeDefinition] = &[
+    attributes::POSITION_3D,
+    attributes::INTENSITY,
+    attributes::RETURN_NUMBER,
+    attributes::NUMBER_OF_RETURNS,
+    attributes::CLASSIFICATION_FLAGS,
+    attributes::SCANNER_CHANNEL,
+    attributes::SCAN_DIRECTION_FLAG,
+    attributes::EDGE_OF_FLIGHT_LINE,
+    attributes::CLASSIFICATION,
+    attributes::SCAN_ANGLE_RANK,
+    attributes::SCAN_ANGLE,
+    attributes::USER_DATA,
+    attributes::POINT_SOURCE_ID,
+    attributes::COLOR_RGB,
+    attributes::GPS_TIME,
+    attributes::NIR,
+    attributes::WAVE_PACKET_DESCRIPTOR_INDEX,
+    attributes::WAVEFORM_DATA_OFFSET,
+    attributes::WAVEFORM_PACKET_SIZE,
+    attributes::RETURN_POINT_WAVEFORM_LOCATION,
+    attributes::WAVEFORM_PARAMETERS,
+    attributes::POINT_ID,
+    attributes::NORMAL,
+    attributes::SENSOR_POSITION,
+    attributes::RANGE,
+    attributes::INCIDENCE_ANGLE,
+    attributes::RING,
+    attributes::LOD_LEVEL,
+];
+
+fn to_py_err(error: anyhow::Error) -> PyErr {
+    PyIOError::new_err(error.to_string())
+}
+
+fn scalar_array<T: PrimitiveType + Element>(
+    py: Python,
+    points: &dyn PointBuffer,
+    attribute: &PointAttributeDefinition,
+) -> PyObject {
+    points
+        .iter_attribute::<T>(attribute)
+        .collect::<Vec<_>>()
+        .into_pyarray(py)
+        .to_object(py)
+}
+
+fn vector_array<T: Element>(py: Python, values: Vec<T>, components: usize) -> PyResult<PyObject> {
+    let rows = values.len() / components;
+    let array = Array2::from_shape_vec((rows, components), values)
+        .map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(array.into_pyarray(py).to_object(py))
+}
+
+fn vec3_array<T: Scalar + Copy + Element>(
+    py: Python,
+    points: &dyn PointBuffer,
+    attribute: &PointAttributeDefinition,
+) -> PyResult<PyObject>
+where
+    Vector3<T>: PrimitiveType,
+{
+    let values = points
+        .iter_attribute::<Vector3<T>>(attribute)
+        .flat_map(|v| vec![v.x, v.y, v.z])
+        .collect::<Vec<_>>();
+    vector_array(py, values, 3)
+}
+
+/// Converts the values of an array `attribute` with `len` elements in `points` into a numpy array with one row per
+/// point
+fn fixed_size_array<T: ArrayElement + Element>(
+    py: Python,
+    points: &dyn PointBuffer,
+    attribute: &PointAttributeDefinition,
+    len: usize,
+) -> PyResult<PyObject> {
+    let mut raw_value = vec![0; attribute.size() as usize];
+    let mut values = Vec::with_capacity(points.len() * len);
+    for index in 0..points.len() {
+        points.get_raw_attribute(index, attribute, &mut raw_value);
+        values.extend(
+            raw_value
+                .chunks_exact(std::mem::size_of::<T>())
+                .map(|element| unsafe { std::ptr::read_unaligned(element.as_ptr() as *const T) }),
+        );
+    }
+    vector_array(py, values, len)
+}
+
+/// Converts the values of `attribute` in `points` into a numpy array. Vector and array attributes become arrays with
+/// one row per point
+fn attribute_to_array(
+    py: Python,
+    points: &dyn PointBuffer,
+    attribute: &PointAttributeDefinition,
+) -> PyResult<PyObject> {
+    match attribute.datatype() {
+        PointAttributeDataType::U8 => Ok(scalar_array::<u8>(py, points, attribute)),
+        PointAttributeDataType::I8 => Ok(scalar_array::<i8>(py, points, attribute)),
+        PointAttributeDataType::U16 => Ok(scalar_array::<u16>(py, points, attribute)),
+        PointAttributeDataType::I16 => Ok(scalar_array::<i16>(py, points, attribute)),
+        PointAttributeDataType::U32 => Ok(scalar_array::<u32>(py, points, attribute)),
+        PointAttributeDataType::I32 => Ok(scalar_array::<i32>(py, points, attribute)),
+        PointAttributeDataType::U64 => Ok(scalar_array::<u64>(py, points, attribute)),
+        PointAttributeDataType::I64 => Ok(scalar_array::<i64>(py, points, attribute)),
+        PointAttributeDataType::F32 => Ok(scalar_array::<f32>(py, points, attribute)),
+        PointAttributeDataType::F64 => Ok(scalar_array::<f64>(py, points, attribute)),
+        PointAttributeDataType::Bool => Ok(scalar_array::<bool>(py, points, attribute)),
+        PointAttributeDataType::Vec3u8 => vec3_array::<u8>(py, points, attribute),
+        PointAttributeDataType::Vec3u16 => vec3_array::<u16>(py, points, attribute),
+        PointAttributeDataType::Vec3f32 => vec3_array::<f32>(py, points, attribute),
+        PointAttributeDataType::Vec3f64 => vec3_array::<f64>(py, points, attribute),
+        PointAttributeDataType::Vec4u8 => {
+            let values = points
+                .iter_attribute::<Vector4<u8>>(attribute)
+                .flat_map(|v| vec![v.x, v.y, v.z, v.w])
+                .collect::<Vec<_>>();
+            vector_array(py, values, 4)
+        }
+        PointAttributeDataType::Array(element, len) => {
+            let len = len as usize;
+            match element {
+                ArrayElementType::U8 => fixed_size_array::<u8>(py, points, attribute, len),
+                ArrayElementType::I8 => fixed_size_array::<i8>(py, points, attribute, len),
+                ArrayElementType::U16 => fixed_size_array::<u16>(py, points, attribute, len),
+                ArrayElementType::I16 => fixed_size_array::<i16>(py, points, attribute, len),
+                ArrayElementType::U32 => fixed_size_array::<u32>(py, points, attribute, len),
+                ArrayElementType::I32 => fixed_size_array::<i32>(py, points, attribute, len),
+                ArrayElementType::U64 => fixed_size_array::<u64>(py, points, attribute, len),
+                ArrayElementType::I64 => fixed_size_array::<i64>(py, points, attribute, len),
+                ArrayElementType::F32 => fixed_size_array::<f32>(py, points, attribute, len),
+                ArrayElementType::F64 => fixed_size_array::<f64>(py, points, attribute, len),
+                ArrayElementType::Bool => fixed_size_array::<bool>(py, points, attribute, len),
+            }
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<Box<dyn PointBuffer>> {
+    let factory: IOFactory = Default::default();
+    let mut reader = factory.make_reader(path)?;
+    let point_count = reader.point_count()?;
+    Ok(reader.read(point_count)?)
+}
+
+/// read(path)
+/// --
+///
+/// Reads all points of the point cloud file at `path`. Returns a dict that maps the name of each point attribute to a
+/// numpy array with the values of the attribute. Scalar attributes are returned as one-dimensional arrays, vector
+/// attributes as two-dimensional arrays with one row per point
+#[pyfunction]
+pub fn read<'py>(py: Python<'py>, path: &str) -> PyResult<&'py PyDict> {
+    let points = read_file(Path::new(path)).map_err(to_py_err)?;
+    let dict = PyDict::new(py);
+    for attribute in points.point_layout().attributes() {
+        let attribute: PointAttributeDefinition = attribute.into();
+        dict.set_item(
+            attribute.name(),
+            attribute_to_array(py, points.as_ref(), &attribute)?,
+        )?;
+    }
+    Ok(dict)
+}
+
+/// Returns the number of values and their raw memory if `array` is a one-dimensional array of type `T`
+fn scalar_data<T: Element + Copy>(array: &PyAny) -> Option<(usize, Vec<u8>)> {
+    let array = array.extract::<PyReadonlyArray1<T>>().ok()?;
+    let mut data = Vec::with_capacity(array.len() * std::mem::size_of::<T>());
+    for value in array.as_array().iter() {
+        push_raw_bytes(value, &mut data);
+    }
+    Some((array.len(), data))
+}
+
+/// Returns the number of rows and columns and the raw memory (in row-major order) if `array` is a two-dimensional
+/// array of type `T`
+fn vector_data<T: Element + Copy>(array: &PyAny) -> Option<(usize, usize, Vec<u8>)> {
+    let array = array.extract::<PyReadonlyArray2<T>>().ok()?;
+    let array = array.as_array();
+    let mut data = Vec::with_capacity(array.len() * std::mem::size_of::<T>());
+    for value in array.iter() {
+        push_raw_bytes(value, &mut data);
+    }
+    Some((array.nrows(), array.ncols(), data))
+}
+
+/// Converts a numpy array into the datatype, the number of points and the raw memory of the matching pasture attribute
+fn array_to_attribute_data(
+    name: &str,
+    array: &PyAny,
+) -> PyResult<(PointAttributeDataType, usize, Vec<u8>)> {
+    macro_rules! try_scalar {
+        ($t:ty, $datatype:expr) => {
+            if let Some((count, data)) = scalar_data::<$t>(array) {
+                return Ok(($datatype, count, data));
+            }
+        };
+    }
+    try_scalar!(u8, PointAttributeDataType::U8);
+    try_scalar!(i8, PointAttributeDataType::I8);
+    try_scalar!(u16, PointAttributeDataType::U16);
+    try_scalar!(i16, PointAttributeDataType::I16);
+    try_scalar!(u32, PointAttributeDataType::U32);
+    try_scalar!(i32, PointAttributeDataType::I32);
+    try_scalar!(u64, PointAttributeDataType::U64);
+    try_scalar!(i64, PointAttributeDataType::I64);
+    try_scalar!(f32, PointAttributeDataType::F32);
+    try_scalar!(f64, PointAttributeDataType::F64);
+    try_scalar!(bool, PointAttributeDataType::Bool);
+
+    let vector_datatype = if let Some((count, 3, data)) = vector_data::<u8>(array) {
+        Some((PointAttributeDataType::Vec3u8, count, data))
+    } else if let Some((count, 4, data)) = vector_data::<u8>(array) {
+        Some((PointAttributeDataType::Vec4u8, count, data))
+    } else if let Some((count, 3, data)) = vector_data::<u16>(array) {
+        Some((PointAttributeDataType::Vec3u16, count, data))
+    } else if let Some((count, 3, data)) = vector_data::<f32>(array) {
+        Some((PointAttributeDataType::Vec3f32, count, data))
+    } else if let Some((count, 3, data)) = vector_data::<f64>(array) {
+        Some((PointAttributeDataType::Vec3f64, count, data))
+    } else {
+        None
+    };
+    vector_datatype.ok_or_else(|| {
+        PyValueError::new_err(format!(
+            "Array for attribute {} has an unsupported dtype or shape. Supported are one-dimensional arrays of \
+             integers, floats and bools, as well as arrays with shape (N, 3) of uint8, uint16, float32 or float64 and \
+             arrays with shape (N, 4) of uint8",
+            name
+        ))
+    })
+}
+
+/// Creates a point buffer from a dict that maps attribute names to numpy arrays
+fn buffer_from_dict(attributes: &PyDict) -> PyResult<PerAttributeVecPointStorage> {
+    let mut attribute_data = Vec::with_capacity(attributes.len());
+    for (name, array) in attributes.iter() {
+        let name: &str = name.extract()?;
+        let attribute = BUILTIN_ATTRIBUTES
+            .iter()
+            .find(|attribute| attribute.name() == name)
+            .ok_or_else(|| {
+                PyValueError::new_err(format!(
+                    "Unknown attribute {}, only the builtin pasture attributes are supported",
+                    name
+                ))
+            })?;
+        let (datatype, count, data) = array_to_attribute_data(name, array)?;
+        attribute_data.push((attribute.with_custom_datatype(datatype), count, data));
+    }
+
+    let point_count = attribute_data
+        .first()
+        .map(|(_, count, _)| *count)
+        .unwrap_or(0);
+    if let Some((attribute, count, _)) = attribute_data
+        .iter()
+        .find(|(_, count, _)| *count != point_count)
+    {
+        return Err(PyValueError::new_err(format!(
+            "All arrays must have the same length, but the array for attribute {} has {} entries instead of {}",
+            attribute.name(),
+            count,
+            point_count
+        )));
+    }
+
+    let layout = PointLayout::from_attributes(
+        &attribute_data
+            .iter()
+            .map(|(attribute, _, _)| attribute.clone())
+            .collect::<Vec<_>>(),
+    );
+    let mut points = PerAttributeVecPointStorage::with_capacity(point_count, layout);
+    points.resize(point_count);
+    for (attribute, _, data) in attribute_data {
+        points
+            .get_raw_attribute_range_mut(0..point_count, &attribute)
+            .copy_from_slice(&data);
+    }
+    Ok(points)
+}
+
+fn make_writer(path: &Path, points: &dyn PointBuffer) -> Result<Box<dyn PointWriter>> {
+    let is_las = path
+        .extension()
+        .map(|ex| ex == "las" || ex == "laz")
+        .unwrap_or(false);
+    if !is_las {
+        let factory: IOFactory = Default::default();
+        return Ok(factory.make_writer(path)?);
+    }
+
+    // Pick the LAS point format that preserves the most attributes of the points that are written
+    let mut header_builder = Builder::from((1, 4));
+    header_builder.point_format = las_point_format_from_point_layout(points.point_layout());
+    let header = header_builder.into_header()?;
+    Ok(Box::new(LASWriter::from_path_and_header(path, header)?))
+}
+
+fn write_file(path: &Path, points: &dyn PointBuffer) -> Result<()> {
+    // The writer is flushed once it is dropped, explicitly calling `flush` is not supported for LAZ files
+    let mut writer = make_writer(path, points)?;
+    Ok(writer.write(points)?)
+}
+
+/// write(path, attributes)
+/// --
+///
+/// Writes points to the point cloud file at `path`. `attributes` is a dict that maps the names of builtin pasture
+/// attributes (e.g. `Position3D`, `Intensity` or `ColorRGB`) to numpy arrays with the same length, in the format that
+/// `read` returns. The datatype of each attribute is taken from the dtype of its array. For LAS and LAZ files, the
+/// point format that preserves the most attributes is used
+#[pyfunction]
+pub fn write(path: &str, attributes: &PyDict) -> PyResult<()> {
+    let points = buffer_from_dict(attributes)?;
+    write_file(Path::new(path), &points).map_err(to_py_err)
+}
@@ -0,0 +1,24 @@
+#![warn(clippy::all)]
+//! Python bindings for pasture.
+//!
+//! Exposes reading and writing point cloud files into numpy arrays (one array per point attribute) as well as
+//! selected algorithms of `pasture-algorithms` to Python. Build the module with [maturin](https://github.com/PyO3/maturin).
+
+use pyo3::prelude::*;
+
+// Algorithms that operate on numpy arrays of point positions.
+mod algorithms;
+// Reading and writing point cloud files from and into numpy arrays.
+mod io;
+
+/// Fast reading, writing and processing of point cloud data
+#[pymodule]
+fn pasture(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(io::read, m)?)?;
+    m.add_function(wrap_pyfunction!(io::write, m)?)?;
+    m.add_function(wrap_pyfunction!(algorithms::bounds, m)?)?;
+    m.add_function(wrap_pyfunction!(algorithms::voxel_grid_filter, m)?)?;
+    m.add_function(wrap_pyfunction!(algorithms::classify_ground, m)?)?;
+    m.add_function(wrap_pyfunction!(algorithms::ransac_plane, m)?)?;
+    Ok(())
+}
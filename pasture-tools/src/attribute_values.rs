@@ -0,0 +1,95 @@
+use std::convert::TryInto;
+
+use pasture_core::layout::PointAttributeDataType;
+
+/// Decodes the raw memory of an attribute value into its components, converted to `f64`. Scalar attributes have a
+/// single component, vector and array attributes have one component per element. The components are written into
+/// `components`, which is cleared first, so that the same `Vec` can be reused for many values
+pub fn decode_components(
+    datatype: PointAttributeDataType,
+    bytes: &[u8],
+    components: &mut Vec<f64>,
+) {
+    components.clear();
+    components.extend(pasture_core::layout::decode_components(datatype, bytes));
+}
+
+/// Formats the raw memory of a single attribute value. Vector and array values are formatted as `(x, y, z)`. Integer
+/// values are formatted without a detour over `f64`, so that large 64-bit values are printed exactly
+pub fn format_attribute_value(datatype: PointAttributeDataType, bytes: &[u8]) -> String {
+    let components: Vec<String> = match datatype {
+        PointAttributeDataType::U8
+        | PointAttributeDataType::Vec3u8
+        | PointAttributeDataType::Vec4u8 => bytes.iter().map(|b| b.to_string()).collect(),
+        PointAttributeDataType::Bool => vec![(bytes[0] != 0).to_string()],
+        PointAttributeDataType::I8 => vec![(bytes[0] as i8).to_string()],
+        PointAttributeDataType::U16 | PointAttributeDataType::Vec3u16 => bytes
+            .chunks_exact(2)
+            .map(|b| u16::from_ne_bytes(b.try_into().unwrap()).to_string())
+            .collect(),
+        PointAttributeDataType::I16 => {
+            vec![i16::from_ne_bytes(bytes.try_into().unwrap()).to_string()]
+        }
+        PointAttributeDataType::U32 => {
+            vec![u32::from_ne_bytes(bytes.try_into().unwrap()).to_string()]
+        }
+        PointAttributeDataType::I32 => {
+            vec![i32::from_ne_bytes(bytes.try_into().unwrap()).to_string()]
+        }
+        PointAttributeDataType::U64 => {
+            vec![u64::from_ne_bytes(bytes.try_into().unwrap()).to_string()]
+        }
+        PointAttributeDataType::I64 => {
+            vec![i64::from_ne_bytes(bytes.try_into().unwrap()).to_string()]
+        }
+        PointAttributeDataType::F32 | PointAttributeDataType::Vec3f32 => bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_ne_bytes(b.try_into().unwrap()).to_string())
+            .collect(),
+        PointAttributeDataType::F64 | PointAttributeDataType::Vec3f64 => bytes
+            .chunks_exact(8)
+            .map(|b| f64::from_ne_bytes(b.try_into().unwrap()).to_string())
+            .collect(),
+        PointAttributeDataType::Array(element, _) => bytes
+            .chunks_exact(element.size() as usize)
+            .map(|b| format_attribute_value(element.data_type(), b))
+            .collect(),
+    };
+    if components.len() == 1 {
+        components.into_iter().next().unwrap()
+    } else {
+        format!("({})", components.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_components() {
+        let mut components = vec![1.0, 2.0];
+        let bytes = [1_u16, 2, 3]
+            .iter()
+            .flat_map(|c| c.to_ne_bytes().to_vec())
+            .collect::<Vec<_>>();
+        decode_components(PointAttributeDataType::Vec3u16, &bytes, &mut components);
+        assert_eq!(vec![1.0, 2.0, 3.0], components);
+    }
+
+    #[test]
+    fn test_format_attribute_value() {
+        assert_eq!(
+            u64::MAX.to_string(),
+            format_attribute_value(PointAttributeDataType::U64, &u64::MAX.to_ne_bytes())
+        );
+        assert_eq!(
+            "true",
+            format_attribute_value(PointAttributeDataType::Bool, &[1])
+        );
+        assert_eq!(
+            "(1, 2, 3)",
+            format_attribute_value(PointAttributeDataType::Vec3u8, &[1, 2, 3])
+        );
+    }
+}
@@ -0,0 +1,327 @@
+#![warn(clippy::all)]
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg, ArgGroup};
+use log::info;
+use pasture_algorithms::raster::{height_above_raster, points_in_mask, Raster};
+use pasture_core::{
+    containers::{
+        InterleavedVecPointStorage, PointBuffer, PointBufferWriteable, PointBufferWriteableExt,
+    },
+    layout::attributes::CLASSIFICATION,
+};
+use pasture_io::{
+    base::{PointReader, PointWriter},
+    las::{LASReader, LASWriter},
+};
+
+/// What happens to the points that are rejected by the mask or the DEM
+enum Action {
+    Drop,
+    Classify(u8),
+}
+
+/// The test that decides which points are kept
+enum Test {
+    Mask { mask: Raster, invert: bool },
+    DemDifference { dem: Raster, min: f64, max: f64 },
+}
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    pub test: Test,
+    pub action: Action,
+}
+
+/// Parses a raster in the ESRI ASCII grid format
+fn read_ascii_grid(file: &Path) -> Result<Raster> {
+    let text = std::fs::read_to_string(file)?;
+    let mut tokens = text.split_whitespace().peekable();
+    let mut header = HashMap::new();
+    while let Some(token) = tokens.peek() {
+        if token.parse::<f64>().is_ok() {
+            break;
+        }
+        let key = tokens.next().unwrap().to_lowercase();
+        let value = tokens
+            .next()
+            .ok_or_else(|| anyhow!("Missing value for {} in {}", key, file.display()))?
+            .parse::<f64>()?;
+        header.insert(key, value);
+    }
+    let get = |key: &str| {
+        header
+            .get(key)
+            .copied()
+            .ok_or_else(|| anyhow!("Missing {} in ASCII grid {}", key, file.display()))
+    };
+
+    let size_x = get("ncols")? as usize;
+    let size_y = get("nrows")? as usize;
+    let cell_size = get("cellsize")?;
+    let min_x = match get("xllcorner") {
+        Ok(x) => x,
+        Err(_) => get("xllcenter")? - cell_size / 2.0,
+    };
+    let min_y = match get("yllcorner") {
+        Ok(y) => y,
+        Err(_) => get("yllcenter")? - cell_size / 2.0,
+    };
+    let values = tokens
+        .map(|token| token.parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    if values.len() != size_x * size_y {
+        return Err(anyhow!(
+            "ASCII grid {} contains {} values but should contain {}",
+            file.display(),
+            values.len(),
+            size_x * size_y
+        ));
+    }
+
+    Ok(Raster {
+        min_x,
+        max_y: min_y + size_y as f64 * cell_size,
+        cell_size_x: cell_size,
+        cell_size_y: cell_size,
+        size_x,
+        size_y,
+        values,
+        no_data: header.get("nodata_value").copied(),
+    })
+}
+
+/// Reads the first band of a raster in any format that GDAL supports
+#[cfg(feature = "gdal")]
+fn read_gdal_raster(file: &Path) -> Result<Raster> {
+    let dataset = gdal::Dataset::open(file)?;
+    let transform = dataset.geo_transform()?;
+    if transform[2] != 0.0 || transform[4] != 0.0 {
+        return Err(anyhow!(
+            "Raster {} is rotated, which is not supported",
+            file.display()
+        ));
+    }
+    let (size_x, size_y) = dataset.raster_size();
+    let band = dataset.rasterband(1)?;
+    let values = band.read_as::<f64>((0, 0), (size_x, size_y), (size_x, size_y))?;
+    Ok(Raster {
+        min_x: transform[0],
+        max_y: transform[3],
+        cell_size_x: transform[1],
+        cell_size_y: -transform[5],
+        size_x,
+        size_y,
+        values: values.data,
+        no_data: band.no_data_value(),
+    })
+}
+
+#[cfg(not(feature = "gdal"))]
+fn read_gdal_raster(file: &Path) -> Result<Raster> {
+    Err(anyhow!(
+        "Unsupported raster format of file {}. Only ASCII grid (.asc) files are supported, build with the 'gdal' feature to support other formats",
+        file.display()
+    ))
+}
+
+fn read_raster(file: &Path) -> Result<Raster> {
+    let is_ascii_grid = file
+        .extension()
+        .map(|ex| ex.to_string_lossy().to_lowercase() == "asc")
+        .unwrap_or(false);
+    let raster = if is_ascii_grid {
+        read_ascii_grid(file)?
+    } else {
+        read_gdal_raster(file)?
+    };
+    info!(
+        "Read {}x{} raster from {}",
+        raster.size_x,
+        raster.size_y,
+        file.display()
+    );
+    Ok(raster)
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Drops or classifies points based on a raster mask or on their vertical distance to a DEM")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("MASK")
+                .long("mask")
+                .takes_value(true)
+                .value_name("RASTER")
+                .help("Raster mask. Points in cells with a value other than zero are kept. Supports ASCII grid (.asc) files and all formats of GDAL if built with the 'gdal' feature"),
+        )
+        .arg(
+            Arg::with_name("INVERT")
+                .long("invert")
+                .help("Keep the points outside of the mask instead, e.g. to remove points within a water mask")
+                .requires("MASK"),
+        )
+        .arg(
+            Arg::with_name("DEM")
+                .long("dem")
+                .takes_value(true)
+                .value_name("RASTER")
+                .help("Digital elevation model. Points whose height above the DEM is within [--min-dz, --max-dz] are kept. Supports the same formats as --mask"),
+        )
+        .group(
+            ArgGroup::with_name("RASTER")
+                .args(&["MASK", "DEM"])
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("MIN_DZ")
+                .long("min-dz")
+                .takes_value(true)
+                .value_name("MIN_DZ")
+                .help("Minimum height above the DEM")
+                .allow_hyphen_values(true)
+                .requires("DEM"),
+        )
+        .arg(
+            Arg::with_name("MAX_DZ")
+                .long("max-dz")
+                .takes_value(true)
+                .value_name("MAX_DZ")
+                .help("Maximum height above the DEM")
+                .allow_hyphen_values(true)
+                .requires("DEM"),
+        )
+        .arg(
+            Arg::with_name("CLASSIFY")
+                .long("classify")
+                .takes_value(true)
+                .value_name("CLASS")
+                .help("Instead of dropping the rejected points, set their classification to CLASS (e.g. 7 for noise or 9 for water)"),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_file = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+
+    let test = if let Some(mask_file) = matches.value_of("MASK") {
+        Test::Mask {
+            mask: read_raster(Path::new(mask_file))?,
+            invert: matches.is_present("INVERT"),
+        }
+    } else {
+        let min = if matches.is_present("MIN_DZ") {
+            value_t!(matches, "MIN_DZ", f64)?
+        } else {
+            f64::NEG_INFINITY
+        };
+        let max = if matches.is_present("MAX_DZ") {
+            value_t!(matches, "MAX_DZ", f64)?
+        } else {
+            f64::INFINITY
+        };
+        if min > max {
+            return Err(anyhow!("--min-dz must be <= --max-dz"));
+        }
+        Test::DemDifference {
+            dem: read_raster(Path::new(matches.value_of("DEM").unwrap()))?,
+            min,
+            max,
+        }
+    };
+
+    let action = if matches.is_present("CLASSIFY") {
+        Action::Classify(value_t!(matches, "CLASSIFY", u8)?)
+    } else {
+        Action::Drop
+    };
+
+    Ok(Args {
+        input_file,
+        output_file,
+        test,
+        action,
+    })
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    let mut reader = LASReader::from_path(&args.input_file)?;
+    let point_count = reader.point_count()?;
+    let mut points = InterleavedVecPointStorage::with_capacity(
+        point_count,
+        reader.get_default_point_layout().clone(),
+    );
+    reader.read_into(&mut points, point_count)?;
+    info!("Read {} points", point_count);
+
+    let keep = match &args.test {
+        Test::Mask { mask, invert } => points_in_mask(&points, mask)
+            .into_iter()
+            .map(|in_mask| in_mask != *invert)
+            .collect::<Vec<_>>(),
+        Test::DemDifference { dem, min, max } => height_above_raster(&points, dem)
+            .into_iter()
+            .map(|height| {
+                height
+                    .map(|height| height >= *min && height <= *max)
+                    .unwrap_or(false)
+            })
+            .collect(),
+    };
+    let rejected_count = keep.iter().filter(|keep| !**keep).count();
+
+    let output_points = match args.action {
+        Action::Drop => {
+            let mut kept = InterleavedVecPointStorage::with_capacity(
+                point_count - rejected_count,
+                points.point_layout().clone(),
+            );
+            for (index, _) in keep.iter().enumerate().filter(|(_, keep)| **keep) {
+                kept.push(&points.slice(index..index + 1));
+            }
+            info!("Dropped {}/{} points", rejected_count, point_count);
+            kept
+        }
+        Action::Classify(class) => {
+            for (index, _) in keep.iter().enumerate().filter(|(_, keep)| !**keep) {
+                points.set_attribute(&CLASSIFICATION, index, class);
+            }
+            info!(
+                "Classified {}/{} points as {}",
+                rejected_count, point_count, class
+            );
+            points
+        }
+    };
+
+    let mut writer = LASWriter::from_path_and_header(&args.output_file, reader.header().clone())?;
+    writer.write(&output_points)?;
+
+    Ok(())
+}
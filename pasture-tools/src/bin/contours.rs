@@ -0,0 +1,162 @@
+#![warn(clippy::all)]
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
+use log::info;
+use pasture_algorithms::{
+    contours::{contours, Contour},
+    tin::triangulate_positions,
+};
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBuffer, PointBufferExt},
+    layout::attributes::{CLASSIFICATION, POSITION_3D},
+    nalgebra::Vector3,
+};
+use pasture_io::base::IOFactory;
+use serde_json::json;
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    pub interval: f64,
+    pub classes: Option<Vec<u8>>,
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Calculates contour lines from a Delaunay triangulation of a point cloud and writes them as GeoJSON")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output GeoJSON file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("INTERVAL")
+                .long("interval")
+                .takes_value(true)
+                .value_name("INTERVAL")
+                .help("Elevation difference between two contour lines")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("CLASS")
+                .long("class")
+                .takes_value(true)
+                .value_name("CLASS")
+                .multiple(true)
+                .use_delimiter(true)
+                .help("Only triangulate points with these classifications, e.g. 2 for ground points"),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_file = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    let interval = value_t!(matches, "INTERVAL", f64)?;
+    if interval <= 0.0 {
+        return Err(anyhow!("Interval must be > 0"));
+    }
+    let classes = match matches.values_of("CLASS") {
+        Some(values) => Some(
+            values
+                .map(|class| class.parse::<u8>())
+                .collect::<Result<Vec<_>, _>>()?,
+        ),
+        None => None,
+    };
+
+    Ok(Args {
+        input_file,
+        output_file,
+        interval,
+        classes,
+    })
+}
+
+fn contour_to_geojson(contour: &Contour) -> serde_json::Value {
+    let mut coordinates = contour
+        .points
+        .iter()
+        .map(|point| json!([point.x, point.y, point.z]))
+        .collect::<Vec<_>>();
+    // GeoJSON repeats the first position of a closed line
+    if contour.closed {
+        coordinates.push(coordinates[0].clone());
+    }
+    json!({
+        "type": "Feature",
+        "geometry": {
+            "type": "LineString",
+            "coordinates": coordinates,
+        },
+        "properties": {
+            "elevation": contour.elevation,
+            "closed": contour.closed,
+        },
+    })
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    let factory: IOFactory = Default::default();
+    let mut reader = factory.make_reader(&args.input_file)?;
+    let point_count = reader.point_count()?;
+    let mut points = InterleavedVecPointStorage::with_capacity(
+        point_count,
+        reader.get_default_point_layout().clone(),
+    );
+    reader.read_into(&mut points, point_count)?;
+    info!("Read {} points", point_count);
+
+    let positions = points.iter_attribute_as::<Vector3<f64>>(&POSITION_3D);
+    let positions: Vec<Vector3<f64>> = match &args.classes {
+        Some(classes) => {
+            if !points
+                .point_layout()
+                .has_attribute_with_name(CLASSIFICATION.name())
+            {
+                return Err(anyhow!(
+                    "File {} contains no classifications",
+                    args.input_file.display()
+                ));
+            }
+            positions
+                .zip(points.iter_attribute::<u8>(&CLASSIFICATION))
+                .filter(|(_, class)| classes.contains(class))
+                .map(|(position, _)| position)
+                .collect()
+        }
+        None => positions.collect(),
+    };
+    info!("Triangulating {} points", positions.len());
+
+    let tin = triangulate_positions(positions);
+    info!("Created TIN with {} triangles", tin.triangles.len());
+    let contours = contours(&tin, args.interval);
+    info!("Extracted {} contour lines", contours.len());
+
+    let geojson = json!({
+        "type": "FeatureCollection",
+        "features": contours.iter().map(contour_to_geojson).collect::<Vec<_>>(),
+    });
+    std::fs::write(&args.output_file, serde_json::to_string(&geojson)?)?;
+
+    Ok(())
+}
@@ -0,0 +1,410 @@
+#![warn(clippy::all)]
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::{App, Arg, ArgGroup};
+use log::info;
+use pasture_core::{
+    containers::{
+        InterleavedVecPointStorage, PointBuffer, PointBufferExt, PointBufferWriteable,
+        SpatialQuery, SpatialQueryable,
+    },
+    layout::attributes::POSITION_3D,
+    math::AABB,
+    nalgebra::{Point3, Vector3},
+};
+use pasture_io::{
+    base::{PointReader, PointWriter, ProgressReader},
+    las::{LASReader, LASWriter},
+};
+use pasture_tools::progress::make_progress;
+
+/// A 2D polygon given as a set of rings. Points are inside the polygon if they are inside an odd number of rings,
+/// which correctly handles holes and multi-polygons with disjoint parts
+struct Polygon {
+    rings: Vec<Vec<(f64, f64)>>,
+}
+
+impl Polygon {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        let mut inside = false;
+        for ring in &self.rings {
+            let mut j = ring.len() - 1;
+            for (i, &(xi, yi)) in ring.iter().enumerate() {
+                let (xj, yj) = ring[j];
+                if (yi > y) != (yj > y) && x < (xj - xi) * (y - yi) / (yj - yi) + xi {
+                    inside = !inside;
+                }
+                j = i;
+            }
+        }
+        inside
+    }
+
+    fn bounds(&self) -> AABB<f64> {
+        let mut min = Point3::new(f64::MAX, f64::MAX, f64::MIN);
+        let mut max = Point3::new(f64::MIN, f64::MIN, f64::MAX);
+        for (x, y) in self.rings.iter().flatten() {
+            min.x = min.x.min(*x);
+            min.y = min.y.min(*y);
+            max.x = max.x.max(*x);
+            max.y = max.y.max(*y);
+        }
+        AABB::from_min_max_unchecked(min, max)
+    }
+}
+
+/// The region of interest that points are cropped to
+enum Region {
+    Bounds(AABB<f64>),
+    Polygon(Polygon),
+    Circle { center: (f64, f64), radius: f64 },
+}
+
+impl Region {
+    fn contains(&self, position: &Vector3<f64>) -> bool {
+        match self {
+            Region::Bounds(bounds) => {
+                bounds.contains(&Point3::new(position.x, position.y, position.z))
+            }
+            Region::Polygon(polygon) => polygon.contains(position.x, position.y),
+            Region::Circle { center, radius } => {
+                let dx = position.x - center.0;
+                let dy = position.y - center.1;
+                dx * dx + dy * dy <= radius * radius
+            }
+        }
+    }
+
+    /// Returns the bounding box of this region. 2D regions are unbounded in the z-direction
+    fn bounds(&self) -> AABB<f64> {
+        match self {
+            Region::Bounds(bounds) => *bounds,
+            Region::Polygon(polygon) => polygon.bounds(),
+            Region::Circle { center, radius } => AABB::from_min_max_unchecked(
+                Point3::new(center.0 - radius, center.1 - radius, f64::MIN),
+                Point3::new(center.0 + radius, center.1 + radius, f64::MAX),
+            ),
+        }
+    }
+}
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    pub region: Region,
+}
+
+fn parse_numbers(s: &str) -> Result<Vec<f64>> {
+    s.split(',')
+        .map(|number| {
+            number
+                .trim()
+                .parse::<f64>()
+                .map_err(|e| anyhow!("Invalid number {}: {}", number, e))
+        })
+        .collect()
+}
+
+fn parse_bounds(s: &str) -> Result<AABB<f64>> {
+    let values = parse_numbers(s)?;
+    let (min, max) = match values.len() {
+        4 => (
+            Point3::new(values[0], values[1], f64::MIN),
+            Point3::new(values[2], values[3], f64::MAX),
+        ),
+        6 => (
+            Point3::new(values[0], values[1], values[2]),
+            Point3::new(values[3], values[4], values[5]),
+        ),
+        _ => {
+            return Err(anyhow!(
+                "Invalid bounds {}: Expected minx,miny,maxx,maxy or minx,miny,minz,maxx,maxy,maxz",
+                s
+            ))
+        }
+    };
+    if min.x > max.x || min.y > max.y || min.z > max.z {
+        return Err(anyhow!("Invalid bounds {}: Minimum must be <= maximum", s));
+    }
+    Ok(AABB::from_min_max(min, max))
+}
+
+fn parse_circle(s: &str) -> Result<Region> {
+    let values = parse_numbers(s)?;
+    if values.len() != 3 || values[2] < 0.0 {
+        return Err(anyhow!(
+            "Invalid circle {}: Expected x,y,radius with radius >= 0",
+            s
+        ));
+    }
+    Ok(Region::Circle {
+        center: (values[0], values[1]),
+        radius: values[2],
+    })
+}
+
+/// Parses a WKT `POLYGON` or `MULTIPOLYGON`. Each innermost parenthesized coordinate list is a ring
+fn parse_wkt_polygon(wkt: &str) -> Result<Polygon> {
+    let mut rings = vec![];
+    let mut rest = wkt;
+    while let Some(end) = rest.find(')') {
+        let start = rest[..end]
+            .rfind('(')
+            .ok_or_else(|| anyhow!("Invalid WKT polygon: Unbalanced parentheses"))?;
+        let ring_str = &rest[start + 1..end];
+        if !ring_str.trim().is_empty() {
+            let ring = ring_str
+                .split(',')
+                .map(|coordinate| {
+                    let values = coordinate
+                        .split_whitespace()
+                        .map(|v| v.parse::<f64>())
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if values.len() < 2 {
+                        return Err(anyhow!("Invalid WKT coordinate {}", coordinate));
+                    }
+                    Ok((values[0], values[1]))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            rings.push(ring);
+        }
+        rest = &rest[end + 1..];
+    }
+    Ok(Polygon { rings })
+}
+
+fn collect_geojson_rings(
+    value: &serde_json::Value,
+    rings: &mut Vec<Vec<(f64, f64)>>,
+) -> Result<()> {
+    if let Some(object) = value.as_object() {
+        if let Some(features) = object.get("features") {
+            for feature in features
+                .as_array()
+                .ok_or_else(|| anyhow!("Invalid GeoJSON: 'features' must be an array"))?
+            {
+                collect_geojson_rings(feature, rings)?;
+            }
+        } else if let Some(geometry) = object.get("geometry") {
+            collect_geojson_rings(geometry, rings)?;
+        } else if let Some(coordinates) = object.get("coordinates") {
+            collect_geojson_rings(coordinates, rings)?;
+        }
+        return Ok(());
+    }
+
+    let array = value
+        .as_array()
+        .ok_or_else(|| anyhow!("Invalid GeoJSON: Expected coordinate array"))?;
+    let is_ring = array
+        .first()
+        .and_then(|first| first.as_array())
+        .and_then(|first| first.first())
+        .map(|first| first.is_number())
+        .unwrap_or(false);
+    if !is_ring {
+        for element in array {
+            collect_geojson_rings(element, rings)?;
+        }
+        return Ok(());
+    }
+
+    let ring = array
+        .iter()
+        .map(|coordinate| {
+            let x = coordinate.get(0).and_then(|v| v.as_f64());
+            let y = coordinate.get(1).and_then(|v| v.as_f64());
+            match (x, y) {
+                (Some(x), Some(y)) => Ok((x, y)),
+                _ => Err(anyhow!("Invalid GeoJSON coordinate {}", coordinate)),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    rings.push(ring);
+    Ok(())
+}
+
+fn parse_polygon_file(file: &Path) -> Result<Polygon> {
+    let text = std::fs::read_to_string(file)?;
+    let is_wkt = file.extension().map(|ex| ex == "wkt").unwrap_or(false);
+    let polygon = if is_wkt {
+        parse_wkt_polygon(&text)?
+    } else {
+        let json: serde_json::Value = serde_json::from_str(&text)?;
+        let mut rings = vec![];
+        collect_geojson_rings(&json, &mut rings)?;
+        Polygon { rings }
+    };
+
+    if polygon.rings.is_empty() || polygon.rings.iter().any(|ring| ring.len() < 3) {
+        return Err(anyhow!(
+            "Polygon in file {} must have at least one ring with 3 or more vertices",
+            file.display()
+        ));
+    }
+    Ok(polygon)
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Extracts all points within a region of interest from a LAS/LAZ file")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input LAS/LAZ file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output LAS/LAZ file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("BOUNDS")
+                .long("bounds")
+                .takes_value(true)
+                .value_name("BOUNDS")
+                .allow_hyphen_values(true)
+                .help("Axis-aligned bounding box as minx,miny,maxx,maxy or minx,miny,minz,maxx,maxy,maxz"),
+        )
+        .arg(
+            Arg::with_name("POLYGON")
+                .long("polygon")
+                .takes_value(true)
+                .value_name("POLYGON")
+                .help("File containing a 2D polygon, either as WKT (.wkt) or as GeoJSON (all other extensions)"),
+        )
+        .arg(
+            Arg::with_name("CIRCLE")
+                .long("circle")
+                .takes_value(true)
+                .value_name("CIRCLE")
+                .allow_hyphen_values(true)
+                .help("2D circle as x,y,radius"),
+        )
+        .group(
+            ArgGroup::with_name("REGION")
+                .args(&["BOUNDS", "POLYGON", "CIRCLE"])
+                .required(true),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_file = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    let region = if let Some(bounds) = matches.value_of("BOUNDS") {
+        Region::Bounds(parse_bounds(bounds)?)
+    } else if let Some(polygon_file) = matches.value_of("POLYGON") {
+        Region::Polygon(parse_polygon_file(Path::new(polygon_file))?)
+    } else {
+        parse_circle(matches.value_of("CIRCLE").unwrap())?
+    };
+
+    Ok(Args {
+        input_file,
+        output_file,
+        region,
+    })
+}
+
+/// Appends all points of `points` that are inside of `region` to `cropped`
+fn crop_points(
+    points: &InterleavedVecPointStorage,
+    region: &Region,
+    region_bounds: &AABB<f64>,
+    cropped: &mut InterleavedVecPointStorage,
+) {
+    for (index, position) in points
+        .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+        .enumerate()
+    {
+        if region_bounds.contains(&Point3::new(position.x, position.y, position.z))
+            && region.contains(&position)
+        {
+            cropped.push(&points.slice(index..index + 1));
+        }
+    }
+}
+
+fn crop_file(args: &Args) -> Result<()> {
+    info!("Processing {}", args.input_file.display());
+
+    let mut reader = LASReader::from_path(&args.input_file)?;
+    let layout = reader.get_default_point_layout().clone();
+    let mut writer = LASWriter::from_path_and_header(&args.output_file, reader.header().clone())?;
+
+    // If the bounds of the file don't overlap the region, there is no need to read any points
+    let region_bounds = args.region.bounds();
+    let file_bounds = reader.get_metadata().bounds();
+    if let Some(file_bounds) = file_bounds {
+        if !file_bounds.intersects(&region_bounds) {
+            info!("File bounds do not intersect the region, no points are written");
+            return Ok(());
+        }
+    }
+
+    // Files with a spatial index (e.g. COPC files) only have to read the blocks of points that overlap the bounds of
+    // the region. Only the exact region is checked in memory then
+    if reader.capabilities().filter_pushdown {
+        info!("Reading the points within the bounds of the region through the spatial index of the file");
+        let mut candidates = InterleavedVecPointStorage::new(layout.clone());
+        reader.query(&SpatialQuery::Bounds(region_bounds), None, &mut candidates)?;
+        let mut cropped = InterleavedVecPointStorage::with_capacity(candidates.len(), layout);
+        crop_points(&candidates, &args.region, &region_bounds, &mut cropped);
+        writer.write(&cropped)?;
+        info!(
+            "Wrote {} points to {}",
+            cropped.len(),
+            args.output_file.display()
+        );
+        return Ok(());
+    }
+
+    let chunk_size = 1_000_000;
+    let mut chunk = InterleavedVecPointStorage::with_capacity(chunk_size, layout.clone());
+    let mut cropped = InterleavedVecPointStorage::with_capacity(chunk_size, layout);
+    let total_points = reader.remaining_points();
+    let (progress, progress_bar) = make_progress(total_points)?;
+    let mut reader = ProgressReader::new(reader, progress);
+    let mut points_processed = 0;
+    let mut points_written = 0;
+    while points_processed < total_points {
+        chunk.clear();
+        cropped.clear();
+        let points_in_chunk = reader.read_into(&mut chunk, chunk_size)?;
+        if points_in_chunk == 0 {
+            break;
+        }
+
+        crop_points(&chunk, &args.region, &region_bounds, &mut cropped);
+        writer.write(&cropped)?;
+
+        points_processed += points_in_chunk;
+        points_written += cropped.len();
+    }
+    progress_bar.finish();
+
+    info!(
+        "Wrote {} points to {}",
+        points_written,
+        args.output_file.display()
+    );
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+    crop_file(&args)?;
+
+    Ok(())
+}
@@ -0,0 +1,568 @@
+#![warn(clippy::all)]
+
+use std::{
+    fs::{read_dir, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
+use log::{info, warn};
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBufferExt, PointBufferWriteable},
+    layout::attributes::{POSITION_3D, RETURN_NUMBER},
+    math::AABB,
+    nalgebra::Vector3,
+};
+use pasture_io::{
+    base::PointReader,
+    las::{is_crs_vlr, las_bounds_to_pasture_bounds, LASReader, GEO_KEY_DIRECTORY_RECORD_ID},
+    las_rs::Header,
+};
+
+const GEO_DOUBLE_PARAMS_RECORD_ID: u16 = 34736;
+const GEO_ASCII_PARAMS_RECORD_ID: u16 = 34737;
+
+struct Args {
+    pub input_files: Vec<PathBuf>,
+    pub output_file: PathBuf,
+    pub pulse_output_file: Option<PathBuf>,
+    pub cell_size: f64,
+    pub min_density: Option<f64>,
+    pub min_pulse_density: Option<f64>,
+}
+
+/// Number of points and pulses per cell of a regular 2D grid. Row 0 is the southernmost row of the grid
+struct DensityGrid {
+    min_x: f64,
+    min_y: f64,
+    cell_size: f64,
+    size_x: usize,
+    size_y: usize,
+    points: Vec<u32>,
+    pulses: Vec<u32>,
+}
+
+impl DensityGrid {
+    fn new(bounds: &AABB<f64>, cell_size: f64) -> Self {
+        let size_x = ((bounds.extent().x / cell_size).ceil() as usize).max(1);
+        let size_y = ((bounds.extent().y / cell_size).ceil() as usize).max(1);
+        Self {
+            min_x: bounds.min().x,
+            min_y: bounds.min().y,
+            cell_size,
+            size_x,
+            size_y,
+            points: vec![0; size_x * size_y],
+            pulses: vec![0; size_x * size_y],
+        }
+    }
+
+    fn cell_index(&self, position: &Vector3<f64>) -> usize {
+        let x = ((position.x - self.min_x) / self.cell_size)
+            .floor()
+            .max(0.0) as usize;
+        let y = ((position.y - self.min_y) / self.cell_size)
+            .floor()
+            .max(0.0) as usize;
+        y.min(self.size_y - 1) * self.size_x + x.min(self.size_x - 1)
+    }
+
+    fn max_y(&self) -> f64 {
+        self.min_y + self.size_y as f64 * self.cell_size
+    }
+
+    /// Converts the given counts into densities (per square unit), ordered from the northernmost to the southernmost
+    /// row as is expected by raster formats
+    fn densities(&self, counts: &[u32]) -> Vec<f32> {
+        let cell_area = self.cell_size * self.cell_size;
+        (0..self.size_y)
+            .rev()
+            .flat_map(|y| counts[y * self.size_x..(y + 1) * self.size_x].iter())
+            .map(|count| (*count as f64 / cell_area) as f32)
+            .collect()
+    }
+}
+
+/// The GeoTIFF keys of the coordinate reference system of the input data, taken from the LAS projection VLRs
+#[derive(Default)]
+struct GeoKeys {
+    directory: Vec<u16>,
+    double_params: Vec<f64>,
+    ascii_params: String,
+}
+
+impl GeoKeys {
+    fn from_las_header(header: &Header) -> Self {
+        let mut geo_keys: Self = Default::default();
+        for vlr in header.vlrs().iter().filter(|vlr| is_crs_vlr(vlr)) {
+            match vlr.record_id {
+                GEO_KEY_DIRECTORY_RECORD_ID => {
+                    geo_keys.directory = vlr
+                        .data
+                        .chunks_exact(2)
+                        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+                        .collect()
+                }
+                GEO_DOUBLE_PARAMS_RECORD_ID => {
+                    geo_keys.double_params = vlr
+                        .data
+                        .chunks_exact(8)
+                        .map(|bytes| {
+                            let mut value = [0; 8];
+                            value.copy_from_slice(bytes);
+                            f64::from_le_bytes(value)
+                        })
+                        .collect()
+                }
+                GEO_ASCII_PARAMS_RECORD_ID => {
+                    geo_keys.ascii_params = String::from_utf8_lossy(&vlr.data)
+                        .trim_end_matches('\0')
+                        .to_owned()
+                }
+                _ => (),
+            }
+        }
+        geo_keys
+    }
+}
+
+fn get_all_input_files<P: AsRef<Path>>(input_path: P) -> Result<Vec<PathBuf>> {
+    let path = input_path.as_ref();
+    if !path.exists() {
+        return Err(anyhow!("Input path {} does not exist!", path.display()));
+    }
+
+    if path.is_file() {
+        return Ok(vec![path.into()]);
+    }
+
+    let mut files = vec![];
+    for entry in read_dir(path)? {
+        let file = entry?.path();
+        let is_las_file = file
+            .extension()
+            .map(|ex| ex == "las" || ex == "laz")
+            .unwrap_or(false);
+        if is_las_file {
+            files.push(file);
+        } else {
+            warn!("Skipping file {} which is no LAS/LAZ file", file.display());
+        }
+    }
+    Ok(files)
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Calculates the point and pulse density of one or more LAS/LAZ files on a regular grid")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("INPUT")
+                .help("Input files or directories. Directories are scanned (non-recursively) for LAS/LAZ files")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output raster for the point density. Supports GeoTIFF (.tif, .tiff) and ASCII grid (.asc) files")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("PULSE_OUTPUT")
+                .long("pulse-output")
+                .takes_value(true)
+                .value_name("PULSE_OUTPUT")
+                .help("Optional output raster for the pulse density, i.e. the density of first returns"),
+        )
+        .arg(
+            Arg::with_name("CELL_SIZE")
+                .long("cell-size")
+                .takes_value(true)
+                .value_name("CELL_SIZE")
+                .help("Edge length of the raster cells")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("MIN_DENSITY")
+                .long("min-density")
+                .takes_value(true)
+                .value_name("DENSITY")
+                .help("Required point density. If set, the percentage of cells that meet this density is reported"),
+        )
+        .arg(
+            Arg::with_name("MIN_PULSE_DENSITY")
+                .long("min-pulse-density")
+                .takes_value(true)
+                .value_name("DENSITY")
+                .help("Required pulse density. If set, the percentage of cells that meet this density is reported"),
+        )
+        .get_matches();
+
+    let mut input_files = vec![];
+    for input in matches.values_of("INPUT").unwrap() {
+        input_files.extend(get_all_input_files(input)?);
+    }
+    if input_files.is_empty() {
+        return Err(anyhow!("No input files found"));
+    }
+
+    let output_file = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    let pulse_output_file = matches.value_of("PULSE_OUTPUT").map(PathBuf::from);
+    for file in std::iter::once(&output_file).chain(pulse_output_file.iter()) {
+        // Fail early for unsupported output formats instead of after processing all points
+        is_geotiff(file)?;
+    }
+
+    let cell_size = value_t!(matches, "CELL_SIZE", f64)?;
+    if cell_size <= 0.0 {
+        return Err(anyhow!("Cell size must be > 0"));
+    }
+    let min_density = if matches.is_present("MIN_DENSITY") {
+        Some(value_t!(matches, "MIN_DENSITY", f64)?)
+    } else {
+        None
+    };
+    let min_pulse_density = if matches.is_present("MIN_PULSE_DENSITY") {
+        Some(value_t!(matches, "MIN_PULSE_DENSITY", f64)?)
+    } else {
+        None
+    };
+
+    Ok(Args {
+        input_files,
+        output_file,
+        pulse_output_file,
+        cell_size,
+        min_density,
+        min_pulse_density,
+    })
+}
+
+/// Returns `true` if the given raster file is a GeoTIFF file and `false` if it is an ASCII grid file
+fn is_geotiff(file: &Path) -> Result<bool> {
+    let extension = file
+        .extension()
+        .map(|ex| ex.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "tif" | "tiff" => Ok(true),
+        "asc" => Ok(false),
+        _ => Err(anyhow!(
+            "Unsupported raster format of file {}. Supported formats are GeoTIFF (.tif, .tiff) and ASCII grid (.asc)",
+            file.display()
+        )),
+    }
+}
+
+/// Calculates the bounds of all input files from their headers. Also returns the header of the first input file
+fn get_bounds(input_files: &[PathBuf]) -> Result<(AABB<f64>, Header)> {
+    let mut bounds: Option<AABB<f64>> = None;
+    let mut first_header = None;
+    for file in input_files {
+        let reader = LASReader::from_path(file)?;
+        let file_bounds = las_bounds_to_pasture_bounds(reader.header().bounds());
+        bounds = Some(match bounds {
+            None => file_bounds,
+            Some(bounds) => AABB::union(&bounds, &file_bounds),
+        });
+        first_header.get_or_insert_with(|| reader.header().clone());
+    }
+    Ok((bounds.unwrap(), first_header.unwrap()))
+}
+
+/// Counts the points and pulses of the given file per cell of `grid`. Returns `false` if the file has no return numbers,
+/// in which case no pulses are counted
+fn count_points(input_file: &Path, grid: &mut DensityGrid) -> Result<bool> {
+    info!("Processing {}", input_file.display());
+
+    let mut reader = LASReader::from_path(input_file)?;
+    let layout = reader.get_default_point_layout().clone();
+    let has_return_numbers = layout.has_attribute_with_name(RETURN_NUMBER.name());
+
+    let chunk_size = 1_000_000;
+    let mut chunk = InterleavedVecPointStorage::with_capacity(chunk_size, layout);
+    let mut cells = Vec::with_capacity(chunk_size);
+    loop {
+        chunk.clear();
+        let points_in_chunk = reader.read_into(&mut chunk, chunk_size)?;
+        if points_in_chunk == 0 {
+            break;
+        }
+
+        cells.clear();
+        for position in chunk.iter_attribute::<Vector3<f64>>(&POSITION_3D) {
+            let cell = grid.cell_index(&position);
+            grid.points[cell] += 1;
+            cells.push(cell);
+        }
+        if has_return_numbers {
+            for (cell, return_number) in
+                cells.iter().zip(chunk.iter_attribute::<u8>(&RETURN_NUMBER))
+            {
+                if return_number == 1 {
+                    grid.pulses[*cell] += 1;
+                }
+            }
+        }
+    }
+
+    Ok(has_return_numbers)
+}
+
+fn write_ascii_grid(file: &Path, grid: &DensityGrid, densities: &[f32]) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(file)?);
+    writeln!(writer, "ncols {}", grid.size_x)?;
+    writeln!(writer, "nrows {}", grid.size_y)?;
+    writeln!(writer, "xllcorner {}", grid.min_x)?;
+    writeln!(writer, "yllcorner {}", grid.min_y)?;
+    writeln!(writer, "cellsize {}", grid.cell_size)?;
+    writeln!(writer, "NODATA_value -9999")?;
+    for row in densities.chunks_exact(grid.size_x) {
+        let row = row
+            .iter()
+            .map(|density| density.to_string())
+            .collect::<Vec<_>>()
+            .join(" ");
+        writeln!(writer, "{}", row)?;
+    }
+    Ok(())
+}
+
+/// Value of a TIFF tag, together with its TIFF field type
+enum TagValue {
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    Double(Vec<f64>),
+    Ascii(String),
+}
+
+impl TagValue {
+    fn field_type(&self) -> u16 {
+        match self {
+            TagValue::Ascii(_) => 2,
+            TagValue::Short(_) => 3,
+            TagValue::Long(_) => 4,
+            TagValue::Double(_) => 12,
+        }
+    }
+
+    fn count(&self) -> usize {
+        match self {
+            TagValue::Short(values) => values.len(),
+            TagValue::Long(values) => values.len(),
+            TagValue::Double(values) => values.len(),
+            // Includes the terminating NUL character
+            TagValue::Ascii(value) => value.len() + 1,
+        }
+    }
+
+    fn to_le_bytes(&self) -> Vec<u8> {
+        match self {
+            TagValue::Short(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            TagValue::Long(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            TagValue::Double(values) => values.iter().flat_map(|v| v.to_le_bytes()).collect(),
+            TagValue::Ascii(value) => value.bytes().chain(std::iter::once(0)).collect(),
+        }
+    }
+}
+
+/// Writes the given densities as a single-band, uncompressed 32-bit float GeoTIFF file
+fn write_geotiff(
+    file: &Path,
+    grid: &DensityGrid,
+    densities: &[f32],
+    geo_keys: &GeoKeys,
+) -> Result<()> {
+    const HEADER_SIZE: usize = 8;
+    let image_data_size = densities.len() * std::mem::size_of::<f32>();
+
+    let mut tags: Vec<(u16, TagValue)> = vec![
+        (256, TagValue::Long(vec![grid.size_x as u32])),
+        (257, TagValue::Long(vec![grid.size_y as u32])),
+        // BitsPerSample
+        (258, TagValue::Short(vec![32])),
+        // Compression: none
+        (259, TagValue::Short(vec![1])),
+        // PhotometricInterpretation: BlackIsZero
+        (262, TagValue::Short(vec![1])),
+        // StripOffsets: The image data directly follows the TIFF header
+        (273, TagValue::Long(vec![HEADER_SIZE as u32])),
+        // SamplesPerPixel
+        (277, TagValue::Short(vec![1])),
+        // RowsPerStrip
+        (278, TagValue::Long(vec![grid.size_y as u32])),
+        // StripByteCounts
+        (279, TagValue::Long(vec![image_data_size as u32])),
+        // SampleFormat: IEEE floating point
+        (339, TagValue::Short(vec![3])),
+        // ModelPixelScaleTag
+        (
+            33550,
+            TagValue::Double(vec![grid.cell_size, grid.cell_size, 0.0]),
+        ),
+        // ModelTiepointTag: The upper left corner of the raster
+        (
+            33922,
+            TagValue::Double(vec![0.0, 0.0, 0.0, grid.min_x, grid.max_y(), 0.0]),
+        ),
+    ];
+    if !geo_keys.directory.is_empty() {
+        tags.push((34735, TagValue::Short(geo_keys.directory.clone())));
+    }
+    if !geo_keys.double_params.is_empty() {
+        tags.push((34736, TagValue::Double(geo_keys.double_params.clone())));
+    }
+    if !geo_keys.ascii_params.is_empty() {
+        tags.push((34737, TagValue::Ascii(geo_keys.ascii_params.clone())));
+    }
+
+    // Layout of the file: header, image data, image file directory (IFD), tag values that don't fit into the IFD
+    let ifd_offset = HEADER_SIZE + image_data_size;
+    let ifd_size = 2 + tags.len() * 12 + 4;
+    let mut ifd = Vec::with_capacity(ifd_size);
+    let mut tag_values = vec![];
+    ifd.extend_from_slice(&(tags.len() as u16).to_le_bytes());
+    for (tag, value) in tags.iter() {
+        ifd.extend_from_slice(&tag.to_le_bytes());
+        ifd.extend_from_slice(&value.field_type().to_le_bytes());
+        ifd.extend_from_slice(&(value.count() as u32).to_le_bytes());
+        let mut bytes = value.to_le_bytes();
+        if bytes.len() <= 4 {
+            bytes.resize(4, 0);
+            ifd.extend_from_slice(&bytes);
+        } else {
+            let offset = ifd_offset + ifd_size + tag_values.len();
+            ifd.extend_from_slice(&(offset as u32).to_le_bytes());
+            tag_values.extend_from_slice(&bytes);
+            // Values have to start on a word boundary
+            if tag_values.len() % 2 != 0 {
+                tag_values.push(0);
+            }
+        }
+    }
+    // No further IFDs
+    ifd.extend_from_slice(&0u32.to_le_bytes());
+
+    if ifd_offset + ifd_size + tag_values.len() > u32::MAX as usize {
+        return Err(anyhow!(
+            "Raster is too large for a TIFF file, try a larger cell size"
+        ));
+    }
+
+    let mut writer = BufWriter::new(File::create(file)?);
+    writer.write_all(b"II")?;
+    writer.write_all(&42u16.to_le_bytes())?;
+    writer.write_all(&(ifd_offset as u32).to_le_bytes())?;
+    for density in densities {
+        writer.write_all(&density.to_le_bytes())?;
+    }
+    writer.write_all(&ifd)?;
+    writer.write_all(&tag_values)?;
+    Ok(())
+}
+
+fn write_raster(file: &Path, grid: &DensityGrid, counts: &[u32], geo_keys: &GeoKeys) -> Result<()> {
+    let densities = grid.densities(counts);
+    if is_geotiff(file)? {
+        write_geotiff(file, grid, &densities, geo_keys)
+    } else {
+        write_ascii_grid(file, grid, &densities)
+    }
+}
+
+fn print_statistics(name: &str, grid: &DensityGrid, counts: &[u32], min_density: Option<f64>) {
+    let cell_area = grid.cell_size * grid.cell_size;
+    let mut densities = counts
+        .iter()
+        .filter(|count| **count > 0)
+        .map(|count| *count as f64 / cell_area)
+        .collect::<Vec<_>>();
+    let total_count: u64 = counts.iter().map(|count| *count as u64).sum();
+    println!("{}:", name);
+    println!("\tTotal: {}", total_count);
+    println!("\tNon-empty cells: {}/{}", densities.len(), counts.len());
+    if densities.is_empty() {
+        return;
+    }
+
+    densities.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let mean = densities.iter().sum::<f64>() / densities.len() as f64;
+    let median = if densities.len() % 2 == 0 {
+        (densities[densities.len() / 2 - 1] + densities[densities.len() / 2]) / 2.0
+    } else {
+        densities[densities.len() / 2]
+    };
+    println!(
+        "\tDensity of non-empty cells: min {} max {} mean {} median {}",
+        densities[0],
+        densities[densities.len() - 1],
+        mean,
+        median
+    );
+    if let Some(min_density) = min_density {
+        let cells_meeting_spec = densities
+            .iter()
+            .filter(|density| **density >= min_density)
+            .count();
+        println!(
+            "\tCells with density >= {}: {} ({:.2}% of non-empty cells)",
+            min_density,
+            cells_meeting_spec,
+            100.0 * cells_meeting_spec as f64 / densities.len() as f64
+        );
+    }
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    let (bounds, header) = get_bounds(&args.input_files)?;
+    let mut grid = DensityGrid::new(&bounds, args.cell_size);
+    info!(
+        "Calculating density on a grid with {}x{} cells",
+        grid.size_x, grid.size_y
+    );
+
+    let mut all_files_have_return_numbers = true;
+    for file in args.input_files.iter() {
+        if !count_points(file, &mut grid)? {
+            warn!(
+                "File {} has no return numbers, its points are not counted as pulses",
+                file.display()
+            );
+            all_files_have_return_numbers = false;
+        }
+    }
+
+    if args.pulse_output_file.is_some() && !all_files_have_return_numbers {
+        return Err(anyhow!(
+            "Can't calculate pulse density because not all input files have return numbers"
+        ));
+    }
+
+    let geo_keys = GeoKeys::from_las_header(&header);
+    write_raster(&args.output_file, &grid, &grid.points, &geo_keys)?;
+    if let Some(pulse_output_file) = &args.pulse_output_file {
+        write_raster(pulse_output_file, &grid, &grid.pulses, &geo_keys)?;
+    }
+
+    println!("Cell size: {}", grid.cell_size);
+    println!("Grid size: {}x{}", grid.size_x, grid.size_y);
+    print_statistics("Points", &grid, &grid.points, args.min_density);
+    if all_files_have_return_numbers {
+        print_statistics("Pulses", &grid, &grid.pulses, args.min_pulse_density);
+    }
+
+    Ok(())
+}
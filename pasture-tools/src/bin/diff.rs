@@ -0,0 +1,251 @@
+#![warn(clippy::all)]
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
+use log::info;
+use pasture_core::containers::{
+    AttributeComparison, AttributeMismatch, BufferComparison, InterleavedVecPointStorage,
+    PointBufferWriteable,
+};
+use pasture_io::base::{IOFactory, PointReadAndSeek};
+
+struct Args {
+    pub first_file: PathBuf,
+    pub second_file: PathBuf,
+    pub tolerance: f64,
+    pub attribute_tolerances: HashMap<String, f64>,
+    pub max_diffs: usize,
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Compares two point cloud files attribute-by-attribute")
+        .arg(
+            Arg::with_name("FIRST")
+                .short("a")
+                .takes_value(true)
+                .value_name("FIRST")
+                .help("First point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("SECOND")
+                .short("b")
+                .takes_value(true)
+                .value_name("SECOND")
+                .help("Second point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("TOLERANCE")
+                .long("tolerance")
+                .takes_value(true)
+                .value_name("TOLERANCE")
+                .help("Maximum absolute difference at which two attribute values are still considered equal. Defaults to 0, i.e. an exact comparison")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("ATTRIBUTE_TOLERANCE")
+                .long("attribute-tolerance")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("NAME=TOLERANCE")
+                .help("Tolerance for a single attribute, overriding --tolerance (e.g. \"Position3D=0.001\")"),
+        )
+        .arg(
+            Arg::with_name("MAX_DIFFS")
+                .long("max-diffs")
+                .takes_value(true)
+                .value_name("COUNT")
+                .help("Maximum number of mismatching values that are printed")
+                .default_value("10"),
+        )
+        .get_matches();
+
+    let first_file = PathBuf::from(matches.value_of("FIRST").unwrap());
+    let second_file = PathBuf::from(matches.value_of("SECOND").unwrap());
+    let tolerance = value_t!(matches, "TOLERANCE", f64)?;
+    if tolerance < 0.0 {
+        return Err(anyhow!("Tolerance must be >= 0"));
+    }
+    let max_diffs = value_t!(matches, "MAX_DIFFS", usize)?;
+
+    let mut attribute_tolerances = HashMap::new();
+    if let Some(values) = matches.values_of("ATTRIBUTE_TOLERANCE") {
+        for value in values {
+            let mut parts = value.splitn(2, '=');
+            let name = parts.next().unwrap().trim();
+            let attribute_tolerance = parts
+                .next()
+                .ok_or_else(|| {
+                    anyhow!(
+                        "Invalid attribute tolerance {}, expected NAME=TOLERANCE",
+                        value
+                    )
+                })?
+                .trim()
+                .parse::<f64>()?;
+            if attribute_tolerance < 0.0 {
+                return Err(anyhow!("Tolerance for attribute {} must be >= 0", name));
+            }
+            attribute_tolerances.insert(name.to_owned(), attribute_tolerance);
+        }
+    }
+
+    Ok(Args {
+        first_file,
+        second_file,
+        tolerance,
+        attribute_tolerances,
+        max_diffs,
+    })
+}
+
+fn open_file(file: &Path) -> Result<Box<dyn PointReadAndSeek>> {
+    let factory: IOFactory = Default::default();
+    Ok(factory.make_reader(file)?)
+}
+
+/// Accumulates the per-chunk comparison results of a single attribute
+fn merge_attribute_comparison(
+    total: &mut AttributeComparison,
+    chunk: &AttributeComparison,
+    chunk_offset: usize,
+) {
+    total.mismatch_count += chunk.mismatch_count;
+    total.max_difference = total.max_difference.max(chunk.max_difference);
+    if total.first_mismatch.is_none() {
+        total.first_mismatch = chunk.first_mismatch.map(|index| index + chunk_offset);
+    }
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    let mut first_reader = open_file(&args.first_file)?;
+    let mut second_reader = open_file(&args.second_file)?;
+
+    let first_count = first_reader.point_count()?;
+    let second_count = second_reader.point_count()?;
+    if first_count != second_count {
+        println!(
+            "Point counts differ: {} vs. {}. Only the first {} points are compared",
+            first_count,
+            second_count,
+            first_count.min(second_count)
+        );
+    }
+    let count = first_count.min(second_count);
+
+    let comparison = args.attribute_tolerances.iter().fold(
+        BufferComparison::new().with_tolerance(args.tolerance),
+        |comparison, (name, tolerance)| comparison.with_attribute_tolerance(name, *tolerance),
+    );
+
+    let chunk_size = 1_000_000;
+    let mut first_chunk = InterleavedVecPointStorage::with_capacity(
+        chunk_size,
+        first_reader.get_default_point_layout().clone(),
+    );
+    let mut second_chunk = InterleavedVecPointStorage::with_capacity(
+        chunk_size,
+        second_reader.get_default_point_layout().clone(),
+    );
+    let mut diffs: Vec<AttributeComparison> = vec![];
+    let mut printed_diffs = 0;
+    let mut points_processed = 0;
+    loop {
+        first_chunk.clear();
+        second_chunk.clear();
+        let points_in_chunk = chunk_size.min(count - points_processed);
+        first_reader.read_into(&mut first_chunk, points_in_chunk)?;
+        second_reader.read_into(&mut second_chunk, points_in_chunk)?;
+
+        let report = comparison
+            .clone()
+            .with_max_reported_mismatches(args.max_diffs - printed_diffs)
+            .compare(&first_chunk, &second_chunk);
+        if points_processed == 0 {
+            for attribute in report.only_in_first.iter() {
+                println!(
+                    "Attribute {} only exists in the first file",
+                    attribute.name()
+                );
+            }
+            for attribute in report.only_in_second.iter() {
+                println!(
+                    "Attribute {} only exists in the second file",
+                    attribute.name()
+                );
+            }
+            diffs = report.attributes.clone();
+            for diff in diffs.iter_mut() {
+                diff.mismatch_count = 0;
+                diff.first_mismatch = None;
+                diff.max_difference = 0.0;
+            }
+        }
+        for mismatch in report.mismatches.iter() {
+            println!(
+                "{}",
+                AttributeMismatch {
+                    point_index: mismatch.point_index + points_processed,
+                    ..mismatch.clone()
+                }
+            );
+        }
+        printed_diffs += report.mismatches.len();
+        for (total, chunk) in diffs.iter_mut().zip(report.attributes.iter()) {
+            merge_attribute_comparison(total, chunk, points_processed);
+        }
+
+        points_processed += points_in_chunk;
+        info!("{}/{} points", points_processed, count);
+        if points_processed >= count {
+            break;
+        }
+    }
+
+    println!("Summary ({} points compared)", count);
+    for diff in diffs.iter() {
+        match diff.first_mismatch {
+            None => println!(
+                "\t{}: equal (max difference {})",
+                diff.first_attribute.name(),
+                diff.max_difference
+            ),
+            Some(first_mismatch) => println!(
+                "\t{}: {} mismatches, first at point {}, max difference {} (tolerance {})",
+                diff.first_attribute.name(),
+                diff.mismatch_count,
+                first_mismatch,
+                diff.max_difference,
+                diff.tolerance
+            ),
+        }
+    }
+
+    let layouts_match = first_reader.get_default_point_layout().attributes().count() == diffs.len()
+        && second_reader
+            .get_default_point_layout()
+            .attributes()
+            .count()
+            == diffs.len();
+    let total_mismatches: usize = diffs.iter().map(|diff| diff.mismatch_count).sum();
+    if first_count != second_count || !layouts_match || total_mismatches > 0 {
+        return Err(anyhow!("Files differ"));
+    }
+
+    println!("Files are equal");
+    Ok(())
+}
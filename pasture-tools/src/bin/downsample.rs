@@ -0,0 +1,215 @@
+#![warn(clippy::all)]
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg, ArgGroup};
+use log::info;
+use pasture_algorithms::{
+    downsampling::{PoissonDiskSampler, StreamingVoxelGridSampler},
+    kdtree::DistanceMode,
+};
+use pasture_core::containers::{InterleavedVecPointStorage, PointBuffer, PointBufferWriteable};
+use pasture_io::{
+    base::{PointReader, PointWriter, ProgressReader},
+    las::{LASReader, LASWriter},
+};
+use pasture_tools::progress::make_progress;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+/// The different downsampling strategies. All strategies work on one chunk of points at a time, so arbitrarily large
+/// files can be downsampled with bounded memory
+enum Strategy {
+    VoxelGrid(StreamingVoxelGridSampler),
+    EveryNth(usize),
+    Random { ratio: f64, rng: SmallRng },
+    PoissonDisk(PoissonDiskSampler),
+}
+
+impl Strategy {
+    /// Returns the indices of all points within `chunk` that are kept. `chunk_offset` is the index of the first point
+    /// of `chunk` within the input file
+    fn sample(&mut self, chunk: &InterleavedVecPointStorage, chunk_offset: usize) -> Vec<usize> {
+        match self {
+            Strategy::VoxelGrid(sampler) => sampler.sample(chunk),
+            Strategy::EveryNth(n) => (0..chunk.len())
+                .filter(|index| (chunk_offset + index) % *n == 0)
+                .collect(),
+            Strategy::Random { ratio, rng } => {
+                (0..chunk.len()).filter(|_| rng.gen_bool(*ratio)).collect()
+            }
+            Strategy::PoissonDisk(sampler) => sampler.sample(chunk),
+        }
+    }
+}
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    pub strategy: Strategy,
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Downsamples a LAS/LAZ file using one of several thinning strategies")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input LAS/LAZ file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output LAS/LAZ file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("VOXEL")
+                .long("voxel")
+                .takes_value(true)
+                .value_name("CELL_SIZE")
+                .help("Keep the first point within each cell of a voxel grid with the given cell size"),
+        )
+        .arg(
+            Arg::with_name("EVERY_NTH")
+                .long("every-nth")
+                .takes_value(true)
+                .value_name("N")
+                .help("Keep every N-th point"),
+        )
+        .arg(
+            Arg::with_name("RANDOM")
+                .long("random")
+                .takes_value(true)
+                .value_name("PERCENTAGE")
+                .help("Keep a random subset of the given percentage (0 to 100) of all points"),
+        )
+        .arg(
+            Arg::with_name("POISSON")
+                .long("poisson")
+                .takes_value(true)
+                .value_name("RADIUS")
+                .help("Poisson disk sampling, keeping only points that have no other kept point within the given radius"),
+        )
+        .arg(
+            Arg::with_name("XY")
+                .long("xy")
+                .requires("POISSON")
+                .help("Measure the Poisson disk radius in XY only, ignoring Z"),
+        )
+        .arg(
+            Arg::with_name("SEED")
+                .long("seed")
+                .takes_value(true)
+                .value_name("SEED")
+                .help("Seed for the random number generator used by --random. If omitted, a random seed is used"),
+        )
+        .group(
+            ArgGroup::with_name("STRATEGY")
+                .args(&["VOXEL", "EVERY_NTH", "RANDOM", "POISSON"])
+                .required(true),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_file = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+
+    let strategy = if matches.is_present("VOXEL") {
+        let cell_size = value_t!(matches, "VOXEL", f64)?;
+        if cell_size <= 0.0 {
+            return Err(anyhow!("Voxel cell size must be > 0"));
+        }
+        Strategy::VoxelGrid(StreamingVoxelGridSampler::new(cell_size))
+    } else if matches.is_present("EVERY_NTH") {
+        let n = value_t!(matches, "EVERY_NTH", usize)?;
+        if n == 0 {
+            return Err(anyhow!("N must be > 0"));
+        }
+        Strategy::EveryNth(n)
+    } else if matches.is_present("RANDOM") {
+        let percentage = value_t!(matches, "RANDOM", f64)?;
+        if !(0.0..=100.0).contains(&percentage) {
+            return Err(anyhow!("Percentage must be between 0 and 100"));
+        }
+        let rng = if matches.is_present("SEED") {
+            SmallRng::seed_from_u64(value_t!(matches, "SEED", u64)?)
+        } else {
+            SmallRng::from_entropy()
+        };
+        Strategy::Random {
+            ratio: percentage / 100.0,
+            rng,
+        }
+    } else {
+        let radius = value_t!(matches, "POISSON", f64)?;
+        if radius <= 0.0 {
+            return Err(anyhow!("Poisson disk radius must be > 0"));
+        }
+        let distance_mode = if matches.is_present("XY") {
+            DistanceMode::Xy
+        } else {
+            DistanceMode::Xyz
+        };
+        Strategy::PoissonDisk(PoissonDiskSampler::new(radius).with_distance_mode(distance_mode))
+    };
+
+    Ok(Args {
+        input_file,
+        output_file,
+        strategy,
+    })
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let mut args = get_args()?;
+
+    info!("Processing {}", args.input_file.display());
+
+    let mut reader = LASReader::from_path(&args.input_file)?;
+    let layout = reader.get_default_point_layout().clone();
+    let mut writer = LASWriter::from_path_and_header(&args.output_file, reader.header().clone())?;
+
+    let chunk_size = 1_000_000;
+    let mut chunk = InterleavedVecPointStorage::with_capacity(chunk_size, layout.clone());
+    let mut sampled = InterleavedVecPointStorage::with_capacity(chunk_size, layout);
+    let total_points = reader.remaining_points();
+    let (progress, progress_bar) = make_progress(total_points)?;
+    let mut reader = ProgressReader::new(reader, progress);
+    let mut points_processed = 0;
+    let mut points_written = 0;
+    while points_processed < total_points {
+        chunk.clear();
+        sampled.clear();
+        let points_in_chunk = reader.read_into(&mut chunk, chunk_size)?;
+        if points_in_chunk == 0 {
+            break;
+        }
+
+        for index in args.strategy.sample(&chunk, points_processed) {
+            sampled.push(&chunk.slice(index..index + 1));
+        }
+        writer.write(&sampled)?;
+
+        points_processed += points_in_chunk;
+        points_written += sampled.len();
+    }
+    progress_bar.finish();
+
+    info!(
+        "Wrote {} of {} points to {}",
+        points_written,
+        total_points,
+        args.output_file.display()
+    );
+
+    Ok(())
+}
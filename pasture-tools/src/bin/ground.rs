@@ -0,0 +1,166 @@
+#![warn(clippy::all)]
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
+use log::info;
+use pasture_algorithms::ground::{classify_ground, GroundFilterParameters};
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBufferExt, PointBufferWriteableExt},
+    layout::{
+        attributes::CLASSIFICATION, FieldAlignment, PointAttributeDataType,
+        PointAttributeDefinition,
+    },
+};
+use pasture_io::{
+    base::{PointReader, PointWriter},
+    las::{add_extra_bytes_to_las_header, ExtraBytesDescriptor, LASReader, LASWriter},
+};
+
+const HEIGHT_ABOVE_GROUND_NAME: &str = "HeightAboveGround";
+const CLASSIFICATION_UNCLASSIFIED: u8 = 1;
+const CLASSIFICATION_GROUND: u8 = 2;
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    pub parameters: GroundFilterParameters,
+    pub write_height_above_ground: bool,
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Classifies ground points and optionally calculates the height above ground of all points")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("CELL_SIZE")
+                .long("cell-size")
+                .takes_value(true)
+                .value_name("CELL_SIZE")
+                .help("Size of the grid cells that are used to estimate the terrain surface")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("WINDOW")
+                .long("window")
+                .takes_value(true)
+                .value_name("CELLS")
+                .help("Radius in cells of the filter window. Should be larger than half the size of the largest non-ground objects (e.g. buildings)")
+                .default_value("10"),
+        )
+        .arg(
+            Arg::with_name("THRESHOLD")
+                .long("threshold")
+                .takes_value(true)
+                .value_name("THRESHOLD")
+                .help("Maximum height above the terrain surface at which points are classified as ground")
+                .default_value("0.5"),
+        )
+        .arg(
+            Arg::with_name("HAG")
+                .long("hag")
+                .help("Write the height above ground of all points into a 'HeightAboveGround' extra bytes attribute"),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_file = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    let cell_size = value_t!(matches, "CELL_SIZE", f64)?;
+    if cell_size <= 0.0 {
+        return Err(anyhow!("Cell size must be > 0"));
+    }
+    let window_radius = value_t!(matches, "WINDOW", usize)?;
+    let height_threshold = value_t!(matches, "THRESHOLD", f64)?;
+
+    Ok(Args {
+        input_file,
+        output_file,
+        parameters: GroundFilterParameters {
+            cell_size,
+            window_radius,
+            height_threshold,
+        },
+        write_height_above_ground: matches.is_present("HAG"),
+    })
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    let mut reader = LASReader::from_path(&args.input_file)?;
+    let height_above_ground_attribute =
+        PointAttributeDefinition::custom(HEIGHT_ABOVE_GROUND_NAME, PointAttributeDataType::F64);
+    let mut layout = reader.get_default_point_layout().clone();
+    if args.write_height_above_ground {
+        if layout.has_attribute_with_name(HEIGHT_ABOVE_GROUND_NAME) {
+            return Err(anyhow!(
+                "Input file already contains a {} attribute",
+                HEIGHT_ABOVE_GROUND_NAME
+            ));
+        }
+        layout.add_attribute(
+            height_above_ground_attribute.clone(),
+            FieldAlignment::Default,
+        );
+    }
+
+    let point_count = reader.point_count()?;
+    let mut points = InterleavedVecPointStorage::with_capacity(point_count, layout);
+    reader.read_into(&mut points, point_count)?;
+    info!("Read {} points", point_count);
+
+    let classification = classify_ground(&points, &args.parameters);
+    let mut ground_count = 0;
+    for (index, is_ground) in classification.is_ground.iter().enumerate() {
+        if *is_ground {
+            points.set_attribute(&CLASSIFICATION, index, CLASSIFICATION_GROUND);
+            ground_count += 1;
+        } else if points.get_attribute::<u8>(&CLASSIFICATION, index) == CLASSIFICATION_GROUND {
+            points.set_attribute(&CLASSIFICATION, index, CLASSIFICATION_UNCLASSIFIED);
+        }
+    }
+    info!(
+        "Classified {}/{} points as ground",
+        ground_count, point_count
+    );
+
+    let header = if args.write_height_above_ground {
+        for (index, height) in classification.height_above_ground.iter().enumerate() {
+            points.set_attribute(&height_above_ground_attribute, index, *height);
+        }
+        add_extra_bytes_to_las_header(
+            reader.header(),
+            &[ExtraBytesDescriptor::new(
+                HEIGHT_ABOVE_GROUND_NAME,
+                PointAttributeDataType::F64,
+            )],
+        )?
+    } else {
+        reader.header().clone()
+    };
+
+    let mut writer = LASWriter::from_path_and_header(&args.output_file, header)?;
+    writer.write(&points)?;
+
+    Ok(())
+}
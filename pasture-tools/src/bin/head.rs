@@ -0,0 +1,268 @@
+#![warn(clippy::all)]
+
+use std::{io::SeekFrom, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg, ArgGroup};
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBuffer, PointBufferWriteable},
+    layout::PointAttributeDefinition,
+};
+use pasture_io::base::{IOFactory, PointReadAndSeek};
+use pasture_tools::attribute_values::format_attribute_value;
+use rand::{rngs::SmallRng, seq::index, SeedableRng};
+
+/// Which points are printed
+enum Selection {
+    First,
+    Last,
+    Random(SmallRng),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum OutputFormat {
+    Table,
+    Csv,
+}
+
+struct Args {
+    pub input_file: PathBuf,
+    pub count: usize,
+    pub selection: Selection,
+    pub format: OutputFormat,
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Prints the first, last or random points of a point cloud file with all their attribute values")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("COUNT")
+                .short("n")
+                .takes_value(true)
+                .value_name("COUNT")
+                .help("Number of points to print")
+                .default_value("10"),
+        )
+        .arg(
+            Arg::with_name("TAIL")
+                .long("tail")
+                .help("Print the last points of the file instead of the first points"),
+        )
+        .arg(
+            Arg::with_name("SAMPLE")
+                .long("sample")
+                .help("Print randomly selected points (in file order) instead of the first points"),
+        )
+        .group(ArgGroup::with_name("SELECTION").args(&["TAIL", "SAMPLE"]))
+        .arg(
+            Arg::with_name("SEED")
+                .long("seed")
+                .takes_value(true)
+                .value_name("SEED")
+                .help("Seed for the random number generator of --sample, for reproducible output")
+                .requires("SAMPLE"),
+        )
+        .arg(
+            Arg::with_name("FORMAT")
+                .long("format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["table", "csv"])
+                .help("Output format")
+                .default_value("table"),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let count = value_t!(matches, "COUNT", usize)?;
+
+    let selection = if matches.is_present("TAIL") {
+        Selection::Last
+    } else if matches.is_present("SAMPLE") {
+        let rng = if matches.is_present("SEED") {
+            SmallRng::seed_from_u64(value_t!(matches, "SEED", u64)?)
+        } else {
+            SmallRng::from_entropy()
+        };
+        Selection::Random(rng)
+    } else {
+        Selection::First
+    };
+
+    let format = match matches.value_of("FORMAT").unwrap() {
+        "csv" => OutputFormat::Csv,
+        _ => OutputFormat::Table,
+    };
+
+    Ok(Args {
+        input_file,
+        count,
+        selection,
+        format,
+    })
+}
+
+/// Formats the point at `index` in `points` as a table row, prefixed with the index `point_index` of the point in the file
+fn format_point(
+    points: &InterleavedVecPointStorage,
+    index: usize,
+    point_index: usize,
+    attributes: &[PointAttributeDefinition],
+    buffer: &mut Vec<u8>,
+) -> Vec<String> {
+    let mut row = Vec::with_capacity(attributes.len() + 1);
+    row.push(point_index.to_string());
+    for attribute in attributes {
+        buffer.resize(attribute.size() as usize, 0);
+        points.get_raw_attribute(index, attribute, buffer);
+        row.push(format_attribute_value(attribute.datatype(), buffer));
+    }
+    row
+}
+
+/// Reads the selected points from `reader` and returns them as formatted rows
+fn read_rows(
+    reader: &mut dyn PointReadAndSeek,
+    args: &mut Args,
+    attributes: &[PointAttributeDefinition],
+) -> Result<Vec<Vec<String>>> {
+    let point_count = reader.point_count()?;
+    let count = args.count.min(point_count);
+    let layout = reader.get_default_point_layout().clone();
+    let mut buffer = vec![];
+
+    let first_index = match &mut args.selection {
+        Selection::First => 0,
+        Selection::Last => point_count - count,
+        Selection::Random(rng) => {
+            let mut indices = index::sample(rng, point_count, count).into_vec();
+            indices.sort_unstable();
+
+            let chunk_size = 1_000_000;
+            let mut chunk = InterleavedVecPointStorage::with_capacity(chunk_size, layout);
+            let mut rows = Vec::with_capacity(count);
+            let mut next_index = indices.iter().peekable();
+            let mut chunk_start = 0;
+            while next_index.peek().is_some() {
+                chunk.clear();
+                let points_in_chunk = reader.read_into(&mut chunk, chunk_size)?;
+                if points_in_chunk == 0 {
+                    return Err(anyhow!("Unexpected end of file"));
+                }
+                while let Some(point_index) =
+                    next_index.next_if(|index| **index < chunk_start + points_in_chunk)
+                {
+                    rows.push(format_point(
+                        &chunk,
+                        point_index - chunk_start,
+                        *point_index,
+                        attributes,
+                        &mut buffer,
+                    ));
+                }
+                chunk_start += points_in_chunk;
+            }
+            return Ok(rows);
+        }
+    };
+
+    reader.seek_point(SeekFrom::Start(first_index as u64))?;
+    let mut points = InterleavedVecPointStorage::with_capacity(count, layout);
+    reader.read_into(&mut points, count)?;
+    Ok((0..points.len())
+        .map(|index| format_point(&points, index, first_index + index, attributes, &mut buffer))
+        .collect())
+}
+
+fn print_table(header: &[String], rows: &[Vec<String>]) {
+    let widths = (0..header.len())
+        .map(|column| {
+            rows.iter()
+                .map(|row| row[column].len())
+                .chain(std::iter::once(header[column].len()))
+                .max()
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let format_row = |row: &[String]| {
+        row.iter()
+            .zip(widths.iter())
+            .map(|(value, width)| format!("{:>width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    };
+
+    println!("{}", format_row(header));
+    println!(
+        "{}",
+        widths
+            .iter()
+            .map(|width| "-".repeat(*width))
+            .collect::<Vec<_>>()
+            .join("-+-")
+    );
+    for row in rows {
+        println!("{}", format_row(row));
+    }
+}
+
+/// Quotes a CSV value if it contains separators or quotes, as is the case for vector values
+fn csv_value(value: &str) -> String {
+    if value.contains(',') || value.contains('"') {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn print_csv(header: &[String], rows: &[Vec<String>]) {
+    for row in std::iter::once(header).chain(rows.iter().map(|row| row.as_slice())) {
+        println!(
+            "{}",
+            row.iter()
+                .map(|value| csv_value(value))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+    }
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let mut args = get_args()?;
+
+    let factory: IOFactory = Default::default();
+    let mut reader = factory.make_reader(&args.input_file)?;
+    let attributes = reader
+        .get_default_point_layout()
+        .attributes()
+        .map(|attribute| attribute.into())
+        .collect::<Vec<PointAttributeDefinition>>();
+
+    let rows = read_rows(reader.as_mut(), &mut args, &attributes)?;
+
+    let header = std::iter::once("Index".to_owned())
+        .chain(
+            attributes
+                .iter()
+                .map(|attribute| attribute.name().to_owned()),
+        )
+        .collect::<Vec<_>>();
+    match args.format {
+        OutputFormat::Table => print_table(&header, &rows),
+        OutputFormat::Csv => print_csv(&header, &rows),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,433 @@
+#![warn(clippy::all)]
+
+use std::{
+    fs::{read_dir, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::mpsc::{sync_channel, SyncSender},
+};
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
+use log::{info, warn};
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBufferWriteable},
+    layout::PointLayout,
+    math::AABB,
+    nalgebra::Point3,
+};
+use pasture_io::{
+    base::{PointReader, PointWriter, StreamingOctree, StreamingOctreeBuilder},
+    las::{epsg_code_from_las_header, wkt_from_las_header, LASReader, LASWriter},
+    las_rs::Header,
+};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use serde_json::json;
+
+struct Args {
+    pub input_files: Vec<PathBuf>,
+    pub output_dir: PathBuf,
+    pub span: u64,
+    pub max_depth: u32,
+    pub chunk_size: usize,
+    pub max_points_in_memory: usize,
+    pub temp_dir: Option<PathBuf>,
+    pub threads: usize,
+}
+
+fn get_all_input_files<P: AsRef<Path>>(input_path: P) -> Result<Vec<PathBuf>> {
+    let path = input_path.as_ref();
+    if !path.exists() {
+        return Err(anyhow!("Input path {} does not exist!", path.display()));
+    }
+
+    if path.is_file() {
+        return Ok(vec![path.into()]);
+    }
+
+    let mut files = vec![];
+    for entry in read_dir(path)? {
+        let file = entry?.path();
+        let is_las_file = file
+            .extension()
+            .map(|ex| ex == "las" || ex == "laz")
+            .unwrap_or(false);
+        if is_las_file {
+            files.push(file);
+        } else {
+            warn!("Skipping file {} which is no LAS/LAZ file", file.display());
+        }
+    }
+    Ok(files)
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Builds a spatial index (octree) from one or more LAS/LAZ files")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("INPUT")
+                .help("Input files or directories. Directories are scanned (non-recursively) for LAS/LAZ files")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output directory")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("FORMAT")
+                .long("format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["ept"])
+                .default_value("ept")
+                .help("Output format of the index. Only EPT is supported, COPC and Potree need writers in pasture-io first"),
+        )
+        .arg(
+            Arg::with_name("SPAN")
+                .long("span")
+                .takes_value(true)
+                .value_name("SPAN")
+                .default_value("128")
+                .help("Resolution of the sampling grid within each node. Each node stores at most SPAN^3 points, so the point spacing in a node is its edge length divided by SPAN"),
+        )
+        .arg(
+            Arg::with_name("MAX_DEPTH")
+                .long("max-depth")
+                .takes_value(true)
+                .value_name("DEPTH")
+                .default_value("16")
+                .help("Maximum depth of the octree. Nodes at this depth store all remaining points"),
+        )
+        .arg(
+            Arg::with_name("CHUNK_SIZE")
+                .long("chunk-size")
+                .takes_value(true)
+                .value_name("CHUNK_SIZE")
+                .default_value("1000000")
+                .help("Number of points that are read from the input files at once"),
+        )
+        .arg(
+            Arg::with_name("MAX_POINTS_IN_MEMORY")
+                .long("max-points-in-memory")
+                .takes_value(true)
+                .value_name("COUNT")
+                .default_value("10000000")
+                .help("Maximum number of points that are kept in memory while building the octree. The points of the largest nodes are written to temporary files whenever there are more points in memory"),
+        )
+        .arg(
+            Arg::with_name("TEMP_DIR")
+                .long("temp-dir")
+                .takes_value(true)
+                .value_name("DIR")
+                .help("Directory for the temporary node files. Defaults to the temporary directory of the system"),
+        )
+        .arg(
+            Arg::with_name("THREADS")
+                .long("threads")
+                .takes_value(true)
+                .value_name("N")
+                .default_value("1")
+                .help("Number of threads used for reading the input files and writing the nodes of the index. With more than one thread, the points of different input files are inserted in no fixed order, so the points that are sampled into the upper nodes can differ between runs"),
+        )
+        .get_matches();
+
+    let mut input_files = vec![];
+    for input in matches.values_of("INPUT").unwrap() {
+        input_files.extend(get_all_input_files(input)?);
+    }
+    if input_files.is_empty() {
+        return Err(anyhow!("No input files found"));
+    }
+
+    let output_dir = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    let span = value_t!(matches, "SPAN", u64)?;
+    if span == 0 {
+        return Err(anyhow!("Span must be > 0"));
+    }
+    let max_depth = value_t!(matches, "MAX_DEPTH", u32)?;
+    if max_depth > 32 {
+        return Err(anyhow!("Maximum depth must be <= 32"));
+    }
+    let chunk_size = value_t!(matches, "CHUNK_SIZE", usize)?;
+    if chunk_size == 0 {
+        return Err(anyhow!("Chunk size must be > 0"));
+    }
+    let max_points_in_memory = value_t!(matches, "MAX_POINTS_IN_MEMORY", usize)?;
+    if max_points_in_memory == 0 {
+        return Err(anyhow!("Maximum number of points in memory must be > 0"));
+    }
+    let temp_dir = matches.value_of("TEMP_DIR").map(PathBuf::from);
+    let threads = value_t!(matches, "THREADS", usize)?;
+    if threads == 0 {
+        return Err(anyhow!("Number of threads must be > 0"));
+    }
+
+    Ok(Args {
+        input_files,
+        output_dir,
+        span,
+        max_depth,
+        chunk_size,
+        max_points_in_memory,
+        temp_dir,
+        threads,
+    })
+}
+
+/// Calculates the bounds of the octree, which is the smallest cube that contains the bounds of all input files. Also
+/// returns the header of the first input file, which is used as a template for the headers of all nodes
+fn get_cubic_bounds(input_files: &[PathBuf]) -> Result<(AABB<f64>, AABB<f64>, Header)> {
+    let mut bounds: Option<AABB<f64>> = None;
+    let mut template_header = None;
+    for file in input_files {
+        let reader = LASReader::from_path(file)?;
+        let header = reader.header();
+        let file_bounds = AABB::from_min_max_unchecked(
+            Point3::new(
+                header.bounds().min.x,
+                header.bounds().min.y,
+                header.bounds().min.z,
+            ),
+            Point3::new(
+                header.bounds().max.x,
+                header.bounds().max.y,
+                header.bounds().max.z,
+            ),
+        );
+        bounds = Some(match bounds {
+            None => file_bounds,
+            Some(bounds) => AABB::union(&bounds, &file_bounds),
+        });
+        template_header.get_or_insert_with(|| header.clone());
+    }
+
+    let bounds = bounds.unwrap();
+    Ok((bounds.as_cubic(), bounds, template_header.unwrap()))
+}
+
+/// Reads the points of `file` in chunks of `chunk_size` points and sends them to `sender`
+fn read_input_file(
+    file: &Path,
+    layout: &PointLayout,
+    chunk_size: usize,
+    sender: &SyncSender<Result<InterleavedVecPointStorage>>,
+) -> Result<()> {
+    info!("Processing {}", file.display());
+    let mut reader = LASReader::from_path(file)?;
+    if reader.get_default_point_layout() != layout {
+        return Err(anyhow!(
+            "File {} has a different point format than the previous input files",
+            file.display()
+        ));
+    }
+
+    loop {
+        let mut chunk = InterleavedVecPointStorage::with_capacity(chunk_size, layout.clone());
+        let points_in_chunk = reader.read_into(&mut chunk, chunk_size)?;
+        if points_in_chunk == 0 {
+            return Ok(());
+        }
+        sender
+            .send(Ok(chunk))
+            .map_err(|_| anyhow!("Building the octree was stopped"))?;
+    }
+}
+
+/// Builds the octree from all input files. The input files are read and decompressed in parallel on the
+/// `thread_pool`, which is the expensive part, while the points are inserted into the octree on the current thread
+fn build_octree(
+    args: &Args,
+    cube: &AABB<f64>,
+    layout: &PointLayout,
+    thread_pool: &ThreadPool,
+) -> Result<StreamingOctree> {
+    let mut builder = StreamingOctreeBuilder::new(
+        layout.clone(),
+        *cube,
+        args.span,
+        args.max_depth,
+        args.max_points_in_memory,
+    );
+    if let Some(temp_dir) = &args.temp_dir {
+        builder = builder.with_temp_dir(temp_dir);
+    }
+
+    thread_pool.in_place_scope(|scope| -> Result<()> {
+        // Bounds the number of chunks in memory that wait for being inserted
+        let (sender, receiver) = sync_channel(2 * args.threads);
+        scope.spawn(move |_| {
+            // Errors are sent to the receiver, which stops building the octree. Returning early from this function
+            // drops the receiver, which in turn stops all readers
+            let _ = args
+                .input_files
+                .par_iter()
+                .try_for_each_with(sender, |sender, file| {
+                    read_input_file(file, layout, args.chunk_size, sender).map_err(|error| {
+                        let _ = sender.send(Err(error));
+                    })
+                });
+        });
+        for chunk in receiver {
+            builder.push(&chunk?)?;
+        }
+        Ok(())
+    })?;
+    Ok(builder.finish())
+}
+
+/// Returns the EPT dimension name and type for the given pasture attribute
+fn ept_dimension(attribute_name: &str) -> Option<(&'static str, &'static str, usize)> {
+    // The names follow PDAL, which reads both the scan angle rank of the old LAS point formats and the extended
+    // scan angle of the point formats 6 to 10 as ScanAngleRank
+    match attribute_name {
+        "Intensity" => Some(("Intensity", "unsigned", 2)),
+        "ReturnNumber" => Some(("ReturnNumber", "unsigned", 1)),
+        "NumberOfReturns" => Some(("NumberOfReturns", "unsigned", 1)),
+        "ScanDirectionFlag" => Some(("ScanDirectionFlag", "unsigned", 1)),
+        "EdgeOfFlightLine" => Some(("EdgeOfFlightLine", "unsigned", 1)),
+        "Classification" => Some(("Classification", "unsigned", 1)),
+        "ScanAngleRank" => Some(("ScanAngleRank", "signed", 1)),
+        "ScanAngle" => Some(("ScanAngleRank", "signed", 2)),
+        "UserData" => Some(("UserData", "unsigned", 1)),
+        "PointSourceID" => Some(("PointSourceId", "unsigned", 2)),
+        "GpsTime" => Some(("GpsTime", "float", 8)),
+        "NIR" => Some(("Infrared", "unsigned", 2)),
+        _ => None,
+    }
+}
+
+fn write_ept_metadata(
+    args: &Args,
+    cube: &AABB<f64>,
+    conforming_bounds: &AABB<f64>,
+    header: &Header,
+    layout: &PointLayout,
+    octree: &StreamingOctree,
+) -> Result<()> {
+    let mut schema = vec![];
+    let raw_header = header.clone().into_raw()?;
+    let scales = [
+        raw_header.x_scale_factor,
+        raw_header.y_scale_factor,
+        raw_header.z_scale_factor,
+    ];
+    let offsets = [
+        raw_header.x_offset,
+        raw_header.y_offset,
+        raw_header.z_offset,
+    ];
+    for (idx, name) in ["X", "Y", "Z"].iter().enumerate() {
+        schema.push(json!({
+            "name": name,
+            "type": "signed",
+            "size": 4,
+            "scale": scales[idx],
+            "offset": offsets[idx],
+        }));
+    }
+    for attribute in layout.attributes() {
+        if attribute.name() == "ColorRGB" {
+            for name in ["Red", "Green", "Blue"].iter() {
+                schema.push(json!({"name": name, "type": "unsigned", "size": 2}));
+            }
+        } else if let Some((name, datatype, size)) = ept_dimension(attribute.name()) {
+            schema.push(json!({"name": name, "type": datatype, "size": size}));
+        }
+    }
+
+    let mut srs = serde_json::Map::new();
+    if let Some(epsg_code) = epsg_code_from_las_header(header) {
+        srs.insert("authority".into(), "EPSG".into());
+        srs.insert("horizontal".into(), epsg_code.to_string().into());
+    }
+    if let Some(wkt) = wkt_from_las_header(header) {
+        srs.insert("wkt".into(), wkt.into());
+    }
+
+    let ept = json!({
+        "bounds": [cube.min().x, cube.min().y, cube.min().z, cube.max().x, cube.max().y, cube.max().z],
+        "boundsConforming": [
+            conforming_bounds.min().x, conforming_bounds.min().y, conforming_bounds.min().z,
+            conforming_bounds.max().x, conforming_bounds.max().y, conforming_bounds.max().z
+        ],
+        "dataType": "laszip",
+        "hierarchyType": "json",
+        "points": octree.point_count(),
+        "schema": schema,
+        "span": args.span,
+        "srs": srs,
+        "version": "1.0.0",
+    });
+    serde_json::to_writer_pretty(
+        BufWriter::new(File::create(args.output_dir.join("ept.json"))?),
+        &ept,
+    )?;
+
+    // All nodes are stored in a single hierarchy file
+    let hierarchy = octree
+        .hierarchy()
+        .into_iter()
+        .map(|((d, x, y, z), point_count)| (format!("{}-{}-{}-{}", d, x, y, z), point_count.into()))
+        .collect::<serde_json::Map<_, _>>();
+    serde_json::to_writer(
+        BufWriter::new(File::create(
+            args.output_dir.join("ept-hierarchy").join("0-0-0-0.json"),
+        )?),
+        &hierarchy,
+    )?;
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    let (cube, conforming_bounds, header) = get_cubic_bounds(&args.input_files)?;
+    let layout = LASReader::from_path(&args.input_files[0])?
+        .get_default_point_layout()
+        .clone();
+
+    let thread_pool = ThreadPoolBuilder::new().num_threads(args.threads).build()?;
+    info!("Building octree from {} files", args.input_files.len());
+    let octree = build_octree(&args, &cube, &layout, &thread_pool)?;
+
+    std::fs::create_dir_all(args.output_dir.join("ept-data"))?;
+    std::fs::create_dir_all(args.output_dir.join("ept-hierarchy"))?;
+
+    let hierarchy = octree.hierarchy();
+    info!("Writing {} nodes", hierarchy.len());
+    thread_pool.install(|| {
+        hierarchy
+            .par_iter()
+            .map(|(key, _)| -> Result<()> {
+                let (d, x, y, z) = key;
+                let path = args
+                    .output_dir
+                    .join("ept-data")
+                    .join(format!("{}-{}-{}-{}.laz", d, x, y, z));
+                let points = octree.read_node(key)?;
+                let mut writer = LASWriter::from_path_and_header(path, header.clone())?;
+                writer.write(&points)?;
+                Ok(())
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+
+    write_ept_metadata(&args, &cube, &conforming_bounds, &header, &layout, &octree)?;
+
+    info!("Wrote EPT index to {}", args.output_dir.display());
+
+    Ok(())
+}
@@ -1,34 +1,51 @@
 use std::{
+    io::SeekFrom,
     path::{Path, PathBuf},
     time::Instant,
 };
 
-use anyhow::Result;
-use clap::{App, Arg};
-use pasture_algorithms::minmax::minmax_attribute;
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
 use pasture_core::{
     containers::InterleavedVecPointStorage,
     containers::PointBuffer,
     containers::PointBufferWriteable,
-    layout::attributes::NIR,
-    layout::attributes::NUMBER_OF_RETURNS,
-    layout::attributes::POINT_SOURCE_ID,
-    layout::attributes::RETURN_NUMBER,
-    layout::attributes::SCAN_ANGLE_RANK,
-    layout::attributes::SCAN_DIRECTION_FLAG,
-    layout::attributes::USER_DATA,
-    layout::attributes::{
-        CLASSIFICATION, COLOR_RGB, EDGE_OF_FLIGHT_LINE, GPS_TIME, INTENSITY, POSITION_3D,
-    },
     layout::PointLayout,
-    math::MinMax,
-    nalgebra::Vector3,
+    layout::{component_names, decode_component, PointAttributeDataType, PointAttributeDefinition},
+    math::{RunningStatistics, AABB},
+    meta::Metadata,
 };
-use pasture_io::base::{IOFactory, PointReadAndSeek};
+use pasture_io::ascii::AsciiReader;
+use pasture_io::base::{
+    AttributeDigester, ContentDigest, DigestAlgorithm, Estimate, EstimateOptions, IOFactory,
+    PointReadAndSeek, PointReader,
+};
+use pasture_io::las::LASReader;
+use pasture_tools::attribute_values::decode_components;
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuilder};
+use serde_json::json;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum OutputFormat {
+    Text,
+    Json,
+}
 
 struct Args {
     pub input_file: PathBuf,
     pub detailed: bool,
+    pub format: OutputFormat,
+    pub histograms: Vec<String>,
+    pub histogram_bins: usize,
+    pub threads: usize,
+    pub sample_ratio: Option<f64>,
+    pub seed: u64,
+    pub ascii_format: Option<String>,
+    pub delimiter: String,
+    pub estimate_fraction: f64,
+    pub read_ahead: bool,
+    pub digest: Option<DigestAlgorithm>,
 }
 
 fn get_args() -> Result<Args> {
@@ -48,22 +65,165 @@ fn get_args() -> Result<Args> {
             Arg::with_name("DETAILED")
                 .short("d")
                 .long("detailed")
-                .help("Output a detailed analysis of the point cloud file, showing min and max values for all point attributes")
+                .help("Output a detailed analysis of the point cloud file, showing min, max and mean values for all point attributes")
+        )
+        .arg(
+            Arg::with_name("FORMAT")
+                .long("format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["text", "json"])
+                .default_value("text")
+                .help("Output format. 'json' prints a single machine-readable JSON object to stdout")
+        )
+        .arg(
+            Arg::with_name("HISTOGRAM")
+                .long("histogram")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .value_name("ATTRIBUTE")
+                .help("Output a histogram of the values of the given scalar attribute (e.g. Classification, ReturnNumber, Intensity or GpsTime). Can be specified multiple times")
+        )
+        .arg(
+            Arg::with_name("BINS")
+                .long("bins")
+                .takes_value(true)
+                .value_name("BINS")
+                .default_value("20")
+                .help("Number of equal-width bins per histogram between the minimum and maximum value of the attribute. Integer attributes whose values span fewer integers get one bin per value")
+        )
+        .arg(
+            Arg::with_name("THREADS")
+                .long("threads")
+                .takes_value(true)
+                .value_name("N")
+                .default_value("1")
+                .help("Number of threads used for analyzing the points. The results do not depend on the number of threads")
+        )
+        .arg(
+            Arg::with_name("SAMPLE")
+                .long("sample")
+                .takes_value(true)
+                .value_name("RATIO")
+                .help("Only analyze a random subset of the points with the given ratio (between 0 and 1). This gives approximate results much faster, because the other points are skipped by seeking instead of being read. For LAZ files, seeking still decompresses the points up to the target within its compressed chunk")
+        )
+        .arg(
+            Arg::with_name("SEED")
+                .long("seed")
+                .takes_value(true)
+                .value_name("SEED")
+                .default_value("0")
+                .help("Seed for selecting the random subset of points with --sample. The same seed always gives the same results")
+        )
+        .arg(
+            Arg::with_name("ASCII_FORMAT")
+                .long("ascii-format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .help("Read the input file as an ASCII file with the given column format (e.g. 'xyzi', see AsciiReader). ASCII files have no header, so the number of points and the bounds are estimated from a sample of the file")
+        )
+        .arg(
+            Arg::with_name("DELIMITER")
+                .long("delimiter")
+                .takes_value(true)
+                .value_name("DELIMITER")
+                .default_value(", ")
+                .help("Column delimiter of ASCII files")
+        )
+        .arg(
+            Arg::with_name("ESTIMATE_FRACTION")
+                .long("estimate-fraction")
+                .takes_value(true)
+                .value_name("FRACTION")
+                .default_value("0.01")
+                .help("Fraction of an ASCII file (between 0 and 1) that is sampled to estimate the number of points and the bounds")
+        )
+        .arg(
+            Arg::with_name("READ_AHEAD")
+                .long("read-ahead")
+                .help("Read the next part of the file on a background thread while the current points are analyzed. Speeds up reading from spinning disks and network storage. Only supported for LAS/LAZ files")
+        )
+        .arg(
+            Arg::with_name("DIGEST")
+                .long("digest")
+                .takes_value(true)
+                .value_name("ALGORITHM")
+                .possible_values(&["xxhash64", "sha256"])
+                .help("Compute digests of the point records (LAS/LAZ files only) and of the values of each point attribute, e.g. to audit the integrity of archived files. The attribute digests of a file do not change when it is converted between LAS and LAZ")
         )
         .get_matches();
 
     let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
     let detailed = matches.is_present("DETAILED");
+    let format = match matches.value_of("FORMAT").unwrap() {
+        "json" => OutputFormat::Json,
+        _ => OutputFormat::Text,
+    };
+    let histograms = matches
+        .values_of("HISTOGRAM")
+        .map(|values| values.map(|value| value.to_owned()).collect())
+        .unwrap_or_default();
+    let histogram_bins = value_t!(matches, "BINS", usize)?;
+    if histogram_bins == 0 {
+        return Err(anyhow!("Number of histogram bins must be > 0"));
+    }
+    let threads = value_t!(matches, "THREADS", usize)?;
+    if threads == 0 {
+        return Err(anyhow!("Number of threads must be > 0"));
+    }
+    let sample_ratio = if matches.is_present("SAMPLE") {
+        let ratio = value_t!(matches, "SAMPLE", f64)?;
+        if ratio <= 0.0 || ratio > 1.0 {
+            return Err(anyhow!("Sample ratio must be in (0, 1]"));
+        }
+        Some(ratio)
+    } else {
+        None
+    };
+    let seed = value_t!(matches, "SEED", u64)?;
+    let ascii_format = matches
+        .value_of("ASCII_FORMAT")
+        .map(|format| format.to_owned());
+    let delimiter = matches.value_of("DELIMITER").unwrap().to_owned();
+    let estimate_fraction = value_t!(matches, "ESTIMATE_FRACTION", f64)?;
+    if estimate_fraction <= 0.0 || estimate_fraction > 1.0 {
+        return Err(anyhow!("Estimate fraction must be in (0, 1]"));
+    }
+
+    let read_ahead = matches.is_present("READ_AHEAD");
+    let digest = matches
+        .value_of("DIGEST")
+        .map(|algorithm| algorithm.parse::<DigestAlgorithm>())
+        .transpose()?;
 
     Ok(Args {
         input_file,
         detailed,
+        format,
+        histograms,
+        histogram_bins,
+        threads,
+        sample_ratio,
+        seed,
+        ascii_format,
+        delimiter,
+        estimate_fraction,
+        read_ahead,
+        digest,
     })
 }
 
-fn open_file(file: &Path) -> Result<Box<dyn PointReadAndSeek>> {
+fn open_file(file: &Path, read_ahead: bool) -> Result<Box<dyn PointReadAndSeek>> {
+    let is_las_file = file
+        .extension()
+        .map(|ex| ex == "las" || ex == "laz")
+        .unwrap_or(false);
+    if read_ahead && is_las_file {
+        return Ok(Box::new(LASReader::from_path_with_read_ahead(file)?));
+    }
     let factory: IOFactory = Default::default();
-    factory.make_reader(file)
+    Ok(factory.make_reader(file)?)
 }
 
 fn print_attributes(point_layout: &PointLayout) {
@@ -73,157 +233,764 @@ fn print_attributes(point_layout: &PointLayout) {
     }
 }
 
-macro_rules! minmax_chunk {
-    ($minmax_tuple:ident, $buffer:ident, $attribute:expr, $type:ty) => {
-        if $buffer
-            .point_layout()
-            .has_attribute_with_name($attribute.name())
+/// Minimum, maximum, mean and standard deviation of a single point attribute. Each component of a vector attribute is
+/// tracked separately
+struct AttributeStatistics {
+    attribute: PointAttributeDefinition,
+    count: usize,
+    components: Vec<RunningStatistics>,
+}
+
+impl AttributeStatistics {
+    fn new(attribute: PointAttributeDefinition) -> Self {
+        Self {
+            attribute,
+            count: 0,
+            components: vec![],
+        }
+    }
+
+    fn add(&mut self, components: &[f64]) {
+        self.components
+            .resize(components.len(), RunningStatistics::new());
+        for (statistics, value) in self.components.iter_mut().zip(components.iter()) {
+            statistics.add(*value);
+        }
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: &AttributeStatistics) {
+        self.components
+            .resize(other.components.len(), RunningStatistics::new());
+        for (statistics, other_statistics) in
+            self.components.iter_mut().zip(other.components.iter())
         {
-            let chunk_minmax: ($type, $type) = minmax_attribute(&$buffer, &$attribute).unwrap();
-            match $minmax_tuple {
-                None => $minmax_tuple = Some(chunk_minmax),
-                Some((old_min, old_max)) => {
-                    $minmax_tuple = Some((
-                        old_min.infimum(&chunk_minmax.0),
-                        old_max.supremum(&chunk_minmax.1),
-                    ));
+            statistics.merge(other_statistics);
+        }
+        self.count += other.count;
+    }
+
+    fn component_values(&self, value: impl Fn(&RunningStatistics) -> Option<f64>) -> Vec<f64> {
+        self.components
+            .iter()
+            .map(|statistics| value(statistics).unwrap_or(0.0))
+            .collect()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        json!({
+            "name": self.attribute.name(),
+            "datatype": self.attribute.datatype().to_string(),
+            "count": self.count,
+            "min": self.component_values(RunningStatistics::min),
+            "max": self.component_values(RunningStatistics::max),
+            "mean": self.component_values(RunningStatistics::mean),
+            "standard_deviation": self.component_values(RunningStatistics::standard_deviation),
+        })
+    }
+
+    fn print(&self) {
+        if self.count == 0 {
+            return;
+        }
+        for (statistics, name) in self
+            .components
+            .iter()
+            .zip(component_names(self.attribute.name(), self.components.len()).iter())
+        {
+            println!(
+                "\t{:<24}{}  {}  (mean {:.3}, std. dev. {:.3})",
+                format!("{}:", name),
+                statistics.min().unwrap_or_default(),
+                statistics.max().unwrap_or_default(),
+                statistics.mean().unwrap_or_default(),
+                statistics.standard_deviation().unwrap_or_default()
+            );
+        }
+    }
+}
+
+/// Histogram of the values of a single scalar point attribute, with equal-width bins between the minimum and maximum
+/// value of the attribute. Integer attributes whose values span fewer integers than there are bins get one bin per
+/// value instead, which gives exact counts for attributes like classifications or return numbers
+struct Histogram {
+    attribute: PointAttributeDefinition,
+    min: f64,
+    max: f64,
+    bin_width: f64,
+    one_bin_per_value: bool,
+    counts: Vec<usize>,
+}
+
+/// A single bin of a `Histogram`, covering all values in `[min, max)`. The last bin also contains `max`
+struct HistogramBin {
+    min: f64,
+    max: f64,
+    count: usize,
+}
+
+impl Histogram {
+    /// Creates an empty histogram with at most `max_bins` bins for the values of `attribute` in the given `range`. If
+    /// the `range` is `None`, there are no values and the histogram has no bins
+    fn new(
+        attribute: PointAttributeDefinition,
+        range: Option<(f64, f64)>,
+        max_bins: usize,
+    ) -> Self {
+        let (min, max) = match range {
+            Some(range) => range,
+            None => {
+                return Self {
+                    attribute,
+                    min: 0.0,
+                    max: 0.0,
+                    bin_width: 1.0,
+                    one_bin_per_value: true,
+                    counts: vec![],
                 }
             }
+        };
+        let is_integer = !matches!(
+            attribute.datatype(),
+            PointAttributeDataType::F32 | PointAttributeDataType::F64
+        );
+        let one_bin_per_value = min == max || (is_integer && max - min < max_bins as f64);
+        let (bin_width, bin_count) = if one_bin_per_value {
+            (1.0, (max - min) as usize + 1)
+        } else {
+            ((max - min) / max_bins as f64, max_bins)
+        };
+        Self {
+            attribute,
+            min,
+            max,
+            bin_width,
+            one_bin_per_value,
+            counts: vec![0; bin_count],
         }
-    };
+    }
+
+    /// Returns an empty histogram with the same bins as this histogram
+    fn empty(&self) -> Self {
+        Self {
+            attribute: self.attribute.clone(),
+            counts: vec![0; self.counts.len()],
+            ..*self
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        if value.is_nan() || self.counts.is_empty() {
+            return;
+        }
+        // Values below the minimum saturate to the first bin, the maximum itself falls into the last bin
+        let bin = ((value - self.min) / self.bin_width) as usize;
+        let last_bin = self.counts.len() - 1;
+        self.counts[std::cmp::min(bin, last_bin)] += 1;
+    }
+
+    fn merge(&mut self, other: &Histogram) {
+        for (count, other_count) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other_count;
+        }
+    }
+
+    fn bins(&self) -> Vec<HistogramBin> {
+        let last_bin = self.counts.len().saturating_sub(1);
+        self.counts
+            .iter()
+            .enumerate()
+            .map(|(bin, count)| HistogramBin {
+                min: self.min + bin as f64 * self.bin_width,
+                max: if bin == last_bin && !self.one_bin_per_value {
+                    self.max
+                } else {
+                    self.min + (bin + 1) as f64 * self.bin_width
+                },
+                count: *count,
+            })
+            .collect()
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let bins = self
+            .bins()
+            .iter()
+            .map(|bin| json!({"min": bin.min, "max": bin.max, "count": bin.count}))
+            .collect::<Vec<_>>();
+        json!({
+            "name": self.attribute.name(),
+            "bins": bins,
+        })
+    }
+
+    fn print(&self) {
+        println!("Histogram of {}", self.attribute.name());
+        let bins = self.bins();
+        let last_bin = bins.len().saturating_sub(1);
+        let rows = bins
+            .iter()
+            .enumerate()
+            .map(|(index, bin)| {
+                let label = if self.one_bin_per_value {
+                    format!("{}:", bin.min)
+                } else if index == last_bin {
+                    format!("[{}, {}]:", bin.min, bin.max)
+                } else {
+                    format!("[{}, {}):", bin.min, bin.max)
+                };
+                (label, bin.count.to_string())
+            })
+            .collect::<Vec<_>>();
+        let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+        let count_width = rows.iter().map(|(_, count)| count.len()).max().unwrap_or(0);
+        for (label, count) in rows {
+            println!(
+                "    {:<label_width$}  {:>count_width$}",
+                label,
+                count,
+                label_width = label_width,
+                count_width = count_width
+            );
+        }
+    }
 }
 
-fn analyze_file(reader: &mut dyn PointReadAndSeek) -> Result<()> {
-    print_attributes(reader.get_default_point_layout());
+/// Results of analyzing the points of a file. Each chunk of the file is analyzed into a separate `Analysis`, which are
+/// then merged in chunk order. This makes the results independent of the number of threads
+struct Analysis {
+    statistics: Vec<AttributeStatistics>,
+    histograms: Vec<Histogram>,
+}
 
-    let total_points = reader.point_count()?;
-    if total_points == 0 {
-        return Ok(());
+impl Analysis {
+    /// Creates an empty analysis with empty copies of the given `histograms`
+    fn new(layout: &PointLayout, histograms: &[Histogram]) -> Self {
+        Self {
+            statistics: layout
+                .attributes()
+                .map(|attribute| AttributeStatistics::new(attribute.into()))
+                .collect(),
+            histograms: histograms.iter().map(Histogram::empty).collect(),
+        }
+    }
+
+    /// Analyzes all points within `chunk`
+    fn add_points(&mut self, chunk: &InterleavedVecPointStorage) {
+        let mut bytes = vec![];
+        let mut components = vec![];
+
+        for attribute_statistics in self.statistics.iter_mut() {
+            bytes.resize(attribute_statistics.attribute.size() as usize, 0);
+            for point_index in 0..chunk.len() {
+                chunk.get_raw_attribute(point_index, &attribute_statistics.attribute, &mut bytes);
+                decode_components(
+                    attribute_statistics.attribute.datatype(),
+                    &bytes,
+                    &mut components,
+                );
+                attribute_statistics.add(&components);
+            }
+        }
+
+        for histogram in self.histograms.iter_mut() {
+            bytes.resize(histogram.attribute.size() as usize, 0);
+            for point_index in 0..chunk.len() {
+                chunk.get_raw_attribute(point_index, &histogram.attribute, &mut bytes);
+                histogram.add(decode_component(histogram.attribute.datatype(), &bytes));
+            }
+        }
+    }
+
+    fn merge(&mut self, other: &Analysis) {
+        for (statistics, other_statistics) in
+            self.statistics.iter_mut().zip(other.statistics.iter())
+        {
+            statistics.merge(other_statistics);
+        }
+        for (histogram, other_histogram) in self.histograms.iter_mut().zip(other.histograms.iter())
+        {
+            histogram.merge(other_histogram);
+        }
+    }
+}
+
+/// Finds the attribute for the histogram with the given name. Names are matched case-insensitively
+fn get_histogram_attribute(layout: &PointLayout, name: &str) -> Result<PointAttributeDefinition> {
+    let attribute = layout
+        .attributes()
+        .find(|attribute| attribute.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| anyhow!("Attribute {} does not exist in the input file", name))?;
+    match attribute.datatype() {
+        PointAttributeDataType::Vec3u8
+        | PointAttributeDataType::Vec3u16
+        | PointAttributeDataType::Vec3f32
+        | PointAttributeDataType::Vec3f64
+        | PointAttributeDataType::Vec4u8
+        | PointAttributeDataType::Array(_, _) => Err(anyhow!(
+            "Histograms are only supported for scalar attributes, but {} is a vector or array attribute",
+            attribute.name()
+        )),
+        _ => Ok(attribute.into()),
     }
+}
+
+/// Selects the random subset of the `num_points` points of a chunk that is analyzed with the given sampling `ratio`
+/// and returns the indices of the selected points within the chunk. The random number generator is seeded per chunk,
+/// so the subset does not depend on the order in which the chunks are processed
+fn select_points(num_points: usize, chunk_index: usize, ratio: f64, seed: u64) -> Vec<usize> {
+    let mut rng = SmallRng::seed_from_u64(seed.wrapping_add(chunk_index as u64));
+    (0..num_points).filter(|_| rng.gen_bool(ratio)).collect()
+}
 
-    let t_start = Instant::now();
+/// Reads the points with the given `indices` relative to `first_point` from `reader` into `chunk`. Each run of
+/// consecutive indices is read with a single seek, so the points in between are never read into memory
+fn read_selected_points(
+    reader: &mut dyn PointReadAndSeek,
+    first_point: usize,
+    indices: &[usize],
+    chunk: &mut InterleavedVecPointStorage,
+) -> Result<()> {
+    let mut run_start = 0;
+    while run_start < indices.len() {
+        let mut run_end = run_start + 1;
+        while run_end < indices.len() && indices[run_end] == indices[run_end - 1] + 1 {
+            run_end += 1;
+        }
+        reader.seek_point(SeekFrom::Start((first_point + indices[run_start]) as u64))?;
+        reader.read_into(chunk, run_end - run_start)?;
+        run_start = run_end;
+    }
+    Ok(())
+}
 
-    println!("Analyzing minimum and maximum values for all point attributes...");
+/// Reads the points from `reader` in chunks and calls `analyze` for each chunk. With `--sample`, each chunk only
+/// contains the random subset of its points that is analyzed. Up to `args.threads` chunks are analyzed in parallel,
+/// the results are passed to `merge` in chunk order
+fn analyze_chunks<T: Send>(
+    reader: &mut dyn PointReadAndSeek,
+    args: &Args,
+    thread_pool: &ThreadPool,
+    analyze: impl Fn(&InterleavedVecPointStorage) -> T + Sync,
+    mut merge: impl FnMut(T),
+) -> Result<()> {
+    let layout = reader.get_default_point_layout().clone();
+    let total_points = reader.point_count()?;
 
+    // Read one chunk per thread at a time, so that memory usage stays bounded
     let chunk_size = 1_000_000;
-    let mut buffer = InterleavedVecPointStorage::with_capacity(
-        chunk_size,
-        reader.get_default_point_layout().clone(),
-    );
     let num_chunks = (total_points + chunk_size - 1) / chunk_size;
-    //let num_chunks = 4;
-
-    // We investigate all builtin attributes, even though not all might be present in the file
-    let mut minmax_position = None;
-    let mut minmax_intensity = None;
-    let mut minmax_return_number = None;
-    let mut minmax_number_of_returns = None;
-    //TODO Extended bit attributes (classification flags, scanner channels)
-    let mut minmax_scan_direction_flag = None;
-    let mut minmax_edge_of_flight_line = None;
-    let mut minmax_classification = None;
-    let mut minmax_scan_angle_rank = None;
-    let mut minmax_user_data = None;
-    let mut minmax_point_source_id = None;
-    let mut minmax_color_rgb = None;
-    let mut minmax_gps_time = None;
-    let mut minmax_nir = None;
-    // TODO Waveform data
-
-    for idx in 0..num_chunks {
-        buffer.clear();
-
-        let num_points_in_chunk = std::cmp::min(chunk_size, total_points - (idx * chunk_size));
-        reader.read_into(&mut buffer, num_points_in_chunk)?;
-
-        minmax_chunk!(minmax_position, buffer, POSITION_3D, Vector3<f64>);
-        minmax_chunk!(minmax_intensity, buffer, INTENSITY, u16);
-        minmax_chunk!(minmax_return_number, buffer, RETURN_NUMBER, u8);
-        minmax_chunk!(minmax_number_of_returns, buffer, NUMBER_OF_RETURNS, u8);
-        minmax_chunk!(
-            minmax_scan_direction_flag,
-            buffer,
-            SCAN_DIRECTION_FLAG,
-            bool
-        );
-        minmax_chunk!(
-            minmax_edge_of_flight_line,
-            buffer,
-            EDGE_OF_FLIGHT_LINE,
-            bool
-        );
-        minmax_chunk!(minmax_classification, buffer, CLASSIFICATION, u8);
-        minmax_chunk!(minmax_scan_angle_rank, buffer, SCAN_ANGLE_RANK, i8);
-        minmax_chunk!(minmax_user_data, buffer, USER_DATA, u8);
-        minmax_chunk!(minmax_point_source_id, buffer, POINT_SOURCE_ID, u16);
-        minmax_chunk!(minmax_color_rgb, buffer, COLOR_RGB, Vector3<u16>);
-        minmax_chunk!(minmax_gps_time, buffer, GPS_TIME, f64);
-        minmax_chunk!(minmax_nir, buffer, NIR, u16);
-
-        // eprintln!(
-        //     "Finding minmax of chunk: {:.2}s",
-        //     inner_t_start.elapsed().as_secs_f64()
-        // );
-    }
-
-    minmax_position.map(|v| {
-        println!("\tX:                      {}  {}", v.0.x, v.1.x);
-        println!("\tY:                      {}  {}", v.0.y, v.1.y);
-        println!("\tZ:                      {}  {}", v.0.z, v.1.z);
-    });
-    minmax_intensity.map(|v| {
-        println!("\tIntensity:              {}  {}", v.0, v.1);
-    });
-    minmax_return_number.map(|v| {
-        println!("\tReturn number:          {}  {}", v.0, v.1);
-    });
-    minmax_number_of_returns.map(|v| {
-        println!("\tNumber of returns:      {}  {}", v.0, v.1);
-    });
-    minmax_scan_direction_flag.map(|v| {
-        println!("\tScan direction flag:    {}  {}", v.0 as u8, v.1 as u8);
-    });
-    minmax_edge_of_flight_line.map(|v| {
-        println!("\tEdge of flight line:    {}  {}", v.0 as u8, v.1 as u8);
-    });
-    minmax_classification.map(|v| {
-        println!("\tClassification:         {}  {}", v.0, v.1);
-    });
-    minmax_scan_angle_rank.map(|v| {
-        println!("\tScan angle rank:        {}  {}", v.0, v.1);
-    });
-    minmax_user_data.map(|v| {
-        println!("\tUser data:              {}  {}", v.0, v.1);
-    });
-    minmax_point_source_id.map(|v| {
-        println!("\tPoint source ID:        {}  {}", v.0, v.1);
-    });
-    minmax_color_rgb.map(|v| {
-        println!("\tColor R:                {}  {}", v.0.x, v.1.x);
-        println!("\tColor G:                {}  {}", v.0.y, v.1.y);
-        println!("\tColor B:                {}  {}", v.0.z, v.1.z);
-    });
-    minmax_gps_time.map(|v| {
-        println!("\tGPS time:               {}  {}", v.0, v.1);
-    });
-    minmax_nir.map(|v| {
-        println!("\tNIR:                    {}  {}", v.0, v.1);
+    let mut chunks = (0..args.threads)
+        .map(|_| InterleavedVecPointStorage::with_capacity(chunk_size, layout.clone()))
+        .collect::<Vec<_>>();
+
+    reader.seek_point(SeekFrom::Start(0))?;
+    let mut first_chunk_index = 0;
+    while first_chunk_index < num_chunks {
+        let chunks_in_batch = std::cmp::min(args.threads, num_chunks - first_chunk_index);
+        for (idx, chunk) in chunks.iter_mut().take(chunks_in_batch).enumerate() {
+            chunk.clear();
+            let chunk_index = first_chunk_index + idx;
+            let chunk_start = chunk_index * chunk_size;
+            let num_points_in_chunk = std::cmp::min(chunk_size, total_points - chunk_start);
+            match args.sample_ratio {
+                None => {
+                    reader.read_into(chunk, num_points_in_chunk)?;
+                }
+                Some(ratio) => {
+                    let indices = select_points(num_points_in_chunk, chunk_index, ratio, args.seed);
+                    read_selected_points(reader, chunk_start, &indices, chunk)?;
+                }
+            }
+        }
+
+        let chunk_results = thread_pool.install(|| {
+            chunks[..chunks_in_batch]
+                .par_iter()
+                .map(|chunk| analyze(chunk))
+                .collect::<Vec<_>>()
+        });
+        chunk_results.into_iter().for_each(&mut merge);
+
+        first_chunk_index += chunks_in_batch;
+    }
+
+    Ok(())
+}
+
+/// Returns the minimum and maximum value of each of the `attributes` for the points in `chunk`, or `None` if an
+/// attribute has no (non-NaN) values
+fn value_ranges(
+    chunk: &InterleavedVecPointStorage,
+    attributes: &[PointAttributeDefinition],
+) -> Vec<Option<(f64, f64)>> {
+    let mut bytes = vec![];
+    attributes
+        .iter()
+        .map(|attribute| {
+            bytes.resize(attribute.size() as usize, 0);
+            (0..chunk.len()).fold(None, |range, point_index| {
+                chunk.get_raw_attribute(point_index, attribute, &mut bytes);
+                let value = decode_component(attribute.datatype(), &bytes);
+                if value.is_nan() {
+                    range
+                } else {
+                    merge_ranges(range, Some((value, value)))
+                }
+            })
+        })
+        .collect()
+}
+
+fn merge_ranges(first: Option<(f64, f64)>, second: Option<(f64, f64)>) -> Option<(f64, f64)> {
+    match (first, second) {
+        (Some((min, max)), Some((other_min, other_max))) => {
+            Some((min.min(other_min), max.max(other_max)))
+        }
+        (Some(range), None) | (None, Some(range)) => Some(range),
+        (None, None) => None,
+    }
+}
+
+/// Analyzes all points of the file. The bins of the histograms are placed between the minimum and maximum value of
+/// their attributes, so if there are histograms, the file is read twice: Once to find these value ranges and once for
+/// the actual analysis
+fn analyze_file(reader: &mut dyn PointReadAndSeek, args: &Args) -> Result<Analysis> {
+    let layout = reader.get_default_point_layout().clone();
+    let histogram_attributes = args
+        .histograms
+        .iter()
+        .map(|name| get_histogram_attribute(&layout, name))
+        .collect::<Result<Vec<_>>>()?;
+    let thread_pool = ThreadPoolBuilder::new().num_threads(args.threads).build()?;
+
+    let mut ranges = vec![None; histogram_attributes.len()];
+    if !histogram_attributes.is_empty() {
+        analyze_chunks(
+            reader,
+            args,
+            &thread_pool,
+            |chunk| value_ranges(chunk, &histogram_attributes),
+            |chunk_ranges| {
+                for (range, chunk_range) in ranges.iter_mut().zip(chunk_ranges) {
+                    *range = merge_ranges(*range, chunk_range);
+                }
+            },
+        )?;
+    }
+    let histograms = histogram_attributes
+        .into_iter()
+        .zip(ranges)
+        .map(|(attribute, range)| Histogram::new(attribute, range, args.histogram_bins))
+        .collect::<Vec<_>>();
+
+    let mut analysis = Analysis::new(&layout, &histograms);
+    analyze_chunks(
+        reader,
+        args,
+        &thread_pool,
+        |chunk| {
+            let mut chunk_analysis = Analysis::new(chunk.point_layout(), &histograms);
+            chunk_analysis.add_points(chunk);
+            chunk_analysis
+        },
+        |chunk_analysis| analysis.merge(&chunk_analysis),
+    )?;
+    Ok(analysis)
+}
+
+/// Digests of the points of a file. The point record digest only exists for LAS/LAZ files
+struct Digests {
+    algorithm: DigestAlgorithm,
+    point_records: Option<ContentDigest>,
+    attributes: Vec<(PointAttributeDefinition, ContentDigest)>,
+}
+
+impl Digests {
+    fn to_json(&self) -> serde_json::Value {
+        let attributes = self
+            .attributes
+            .iter()
+            .map(|(attribute, digest)| (attribute.name().to_owned(), json!(digest.to_hex())))
+            .collect::<serde_json::Map<_, _>>();
+        json!({
+            "algorithm": self.algorithm.name(),
+            "point_records": self.point_records.as_ref().map(ContentDigest::to_hex),
+            "attributes": attributes,
+        })
+    }
+
+    fn print(&self) {
+        println!("Digests ({})", self.algorithm);
+        if let Some(point_records) = &self.point_records {
+            println!("\t{:<24}{}", "Point records:", point_records.to_hex());
+        }
+        for (attribute, digest) in self.attributes.iter() {
+            println!(
+                "\t{:<24}{}",
+                format!("{}:", attribute.name()),
+                digest.to_hex()
+            );
+        }
+    }
+}
+
+/// Computes the digests of all points in the input file. The file is read separately from the analysis, so that the
+/// digests always cover all points, even with --sample
+fn compute_digests(args: &Args, algorithm: DigestAlgorithm) -> Result<Digests> {
+    let is_las_file = args
+        .input_file
+        .extension()
+        .map(|ex| ex == "las" || ex == "laz")
+        .unwrap_or(false);
+    let point_records = if is_las_file {
+        let mut reader = LASReader::from_path(&args.input_file)?;
+        Some(reader.point_record_digest(algorithm)?)
+    } else {
+        None
+    };
+
+    let mut reader = open_file(&args.input_file, args.read_ahead)?;
+    let layout = reader.get_default_point_layout().clone();
+    let mut digester = AttributeDigester::new(layout.clone(), algorithm);
+    let chunk_size = 1_000_000;
+    let mut chunk = InterleavedVecPointStorage::with_capacity(chunk_size, layout);
+    loop {
+        chunk.clear();
+        if reader.read_into(&mut chunk, chunk_size)? == 0 {
+            break;
+        }
+        digester.update(&chunk);
+    }
+
+    Ok(Digests {
+        algorithm,
+        point_records,
+        attributes: digester.finish(),
+    })
+}
+
+fn metadata_to_json(meta: &dyn Metadata) -> serde_json::Value {
+    let bounds = meta.bounds().map(|bounds| {
+        json!({
+            "min": [bounds.min().x, bounds.min().y, bounds.min().z],
+            "max": [bounds.max().x, bounds.max().y, bounds.max().z],
+        })
     });
+    json!({
+        "number_of_points": meta.number_of_points(),
+        "bounds": bounds,
+        "description": meta.to_string(),
+    })
+}
 
-    println!("Took {:.2}s", t_start.elapsed().as_secs_f64());
+fn estimates_to_json(
+    count: &Option<Estimate<usize>>,
+    bounds: &Option<Estimate<AABB<f64>>>,
+) -> serde_json::Value {
+    json!({
+        "number_of_points": count.map(|count| json!({
+            "value": count.value,
+            "exact": count.is_exact(),
+        })),
+        "bounds": bounds.map(|bounds| json!({
+            "min": [bounds.value.min().x, bounds.value.min().y, bounds.value.min().z],
+            "max": [bounds.value.max().x, bounds.value.max().y, bounds.value.max().z],
+            "exact": bounds.is_exact(),
+        })),
+    })
+}
+
+/// Prints information about an ASCII file. ASCII files have no header, so the number of points and the bounds are
+/// estimated from a sample of the file instead
+fn print_ascii_info(args: &Args, ascii_format: &str) -> Result<()> {
+    if args.detailed || !args.histograms.is_empty() || args.digest.is_some() {
+        return Err(anyhow!(
+            "Detailed analysis, histograms and digests are not supported for ASCII files"
+        ));
+    }
+    let mut reader = AsciiReader::from_path(&args.input_file, ascii_format, &args.delimiter)?;
+    let options = EstimateOptions {
+        sample_fraction: args.estimate_fraction,
+        ..Default::default()
+    };
+    let count = reader.estimate_point_count(&options)?;
+    let bounds = reader.estimate_bounds(&options)?;
 
+    match args.format {
+        OutputFormat::Text => {
+            print_attributes(reader.get_default_point_layout());
+            match count {
+                Some(count) if count.is_exact() => println!("Number of points: {}", count.value),
+                Some(count) => println!("Number of points: ~{} (estimated)", count.value),
+                None => println!("Number of points: unknown"),
+            }
+            match bounds {
+                Some(bounds) => println!(
+                    "Bounds: {:?} - {:?}{}",
+                    bounds.value.min().coords.as_slice(),
+                    bounds.value.max().coords.as_slice(),
+                    if bounds.is_exact() {
+                        ""
+                    } else {
+                        " (estimated)"
+                    }
+                ),
+                None => println!("Bounds: unknown"),
+            }
+        }
+        OutputFormat::Json => {
+            let output = json!({
+                "file": args.input_file.display().to_string(),
+                "layout": layout_to_json(reader.get_default_point_layout()),
+                "estimates": estimates_to_json(&count, &bounds),
+            });
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
+    }
     Ok(())
 }
 
+fn layout_to_json(layout: &PointLayout) -> serde_json::Value {
+    let attributes = layout
+        .attributes()
+        .map(|attribute| {
+            json!({
+                "name": attribute.name(),
+                "datatype": attribute.datatype().to_string(),
+                "offset": attribute.offset(),
+                "size": attribute.size(),
+            })
+        })
+        .collect::<Vec<_>>();
+    json!({
+        "attributes": attributes,
+        "size_of_point_entry": layout.size_of_point_entry(),
+    })
+}
+
 fn main() -> Result<()> {
     let args = get_args()?;
-    let mut reader = open_file(&args.input_file)?;
-    let meta = reader.get_metadata();
-    println!("{}", meta);
+    if let Some(ascii_format) = &args.ascii_format {
+        return print_ascii_info(&args, ascii_format);
+    }
+    let mut reader = open_file(&args.input_file, args.read_ahead)?;
 
-    if args.detailed {
-        analyze_file(reader.as_mut())?;
+    match args.format {
+        OutputFormat::Text => {
+            println!("{}", reader.get_metadata());
+
+            if args.detailed || !args.histograms.is_empty() {
+                print_attributes(reader.get_default_point_layout());
+                println!("Analyzing all point attributes...");
+                let t_start = Instant::now();
+                let analysis = analyze_file(reader.as_mut(), &args)?;
+                if let Some(ratio) = args.sample_ratio {
+                    println!(
+                        "Approximate results from a random sample of {:.1}% of all points (seed {})",
+                        ratio * 100.0,
+                        args.seed
+                    );
+                }
+                if args.detailed {
+                    for attribute_statistics in analysis.statistics.iter() {
+                        attribute_statistics.print();
+                    }
+                }
+                for histogram in analysis.histograms.iter() {
+                    histogram.print();
+                }
+                println!("Took {:.2}s", t_start.elapsed().as_secs_f64());
+            }
+
+            if let Some(algorithm) = args.digest {
+                compute_digests(&args, algorithm)?.print();
+            }
+        }
+        OutputFormat::Json => {
+            let mut output = json!({
+                "file": args.input_file.display().to_string(),
+                "metadata": metadata_to_json(reader.get_metadata()),
+                "layout": layout_to_json(reader.get_default_point_layout()),
+            });
+            if args.detailed || !args.histograms.is_empty() {
+                let analysis = analyze_file(reader.as_mut(), &args)?;
+                if let Some(ratio) = args.sample_ratio {
+                    output["sampling"] = json!({
+                        "ratio": ratio,
+                        "seed": args.seed,
+                    });
+                }
+                if args.detailed {
+                    output["statistics"] = analysis
+                        .statistics
+                        .iter()
+                        .map(|attribute_statistics| attribute_statistics.to_json())
+                        .collect::<Vec<_>>()
+                        .into();
+                }
+                if !analysis.histograms.is_empty() {
+                    output["histograms"] = analysis
+                        .histograms
+                        .iter()
+                        .map(Histogram::to_json)
+                        .collect::<Vec<_>>()
+                        .into();
+                }
+            }
+            if let Some(algorithm) = args.digest {
+                output["digests"] = compute_digests(&args, algorithm)?.to_json();
+            }
+            println!("{}", serde_json::to_string_pretty(&output)?);
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasture_core::layout::attributes::{CLASSIFICATION, GPS_TIME};
+
+    fn bin_bounds(histogram: &Histogram) -> Vec<(f64, f64, usize)> {
+        histogram
+            .bins()
+            .iter()
+            .map(|bin| (bin.min, bin.max, bin.count))
+            .collect()
+    }
+
+    #[test]
+    fn test_histogram_one_bin_per_value() {
+        let mut histogram = Histogram::new(CLASSIFICATION, Some((1.0, 3.0)), 20);
+        for value in &[1.0, 2.0, 2.0, 3.0] {
+            histogram.add(*value);
+        }
+        assert_eq!(
+            vec![(1.0, 2.0, 1), (2.0, 3.0, 2), (3.0, 4.0, 1)],
+            bin_bounds(&histogram)
+        );
+    }
+
+    #[test]
+    fn test_histogram_equal_width_bins() {
+        let mut histogram = Histogram::new(GPS_TIME, Some((0.0, 1.0)), 4);
+        let mut other = histogram.empty();
+        for value in &[0.0, 0.1, 0.5, 0.99] {
+            histogram.add(*value);
+        }
+        other.add(1.0);
+        other.add(f64::NAN);
+        histogram.merge(&other);
+        assert_eq!(
+            vec![
+                (0.0, 0.25, 2),
+                (0.25, 0.5, 0),
+                (0.5, 0.75, 1),
+                (0.75, 1.0, 2)
+            ],
+            bin_bounds(&histogram)
+        );
+    }
+}
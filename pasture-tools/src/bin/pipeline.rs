@@ -0,0 +1,416 @@
+#![warn(clippy::all)]
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::{App, Arg};
+use log::info;
+use pasture_algorithms::voxel_grid::voxel_grid_filter;
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBuffer, PointBufferExt, PointBufferWriteable},
+    layout::attributes::POSITION_3D,
+    math::AABB,
+    nalgebra::{Point3, Vector3},
+};
+use pasture_io::{
+    base::{IOFactory, PointWriter, ProcessingHistory, ProcessingStep},
+    las::{
+        las_point_format_from_point_layout, processing_history_from_las_header,
+        set_processing_history_in_las_builder, LASReader, LASWriter,
+    },
+    las_rs::Builder,
+};
+use serde::Deserialize;
+
+/// A pipeline description, either as a bare list of stages or wrapped inside a `pipeline` object as PDAL does it
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PipelineDescription {
+    Wrapped { pipeline: Vec<StageDescription> },
+    Bare(Vec<StageDescription>),
+}
+
+/// A single stage within a pipeline description. Plain strings are interpreted as file names, which are readers if they
+/// appear before the first filter and writers otherwise. Like in PDAL, a file name that is the last of several stages is
+/// always a writer
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StageDescription {
+    Filename(PathBuf),
+    Stage(Stage),
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+#[serde(tag = "type")]
+enum Stage {
+    #[serde(rename = "readers.las", alias = "readers.laz")]
+    Reader { filename: PathBuf },
+    #[serde(
+        rename = "filters.voxelgrid",
+        alias = "filters.voxelcenternearestneighbor"
+    )]
+    VoxelGrid { cell: f64 },
+    #[serde(rename = "filters.decimation")]
+    Decimation {
+        step: usize,
+        #[serde(default)]
+        offset: usize,
+    },
+    #[serde(rename = "filters.head")]
+    Head { count: usize },
+    #[serde(rename = "filters.tail")]
+    Tail { count: usize },
+    #[serde(rename = "filters.crop")]
+    Crop { bounds: String },
+    #[serde(rename = "writers.las", alias = "writers.laz")]
+    Writer { filename: PathBuf },
+}
+
+impl Stage {
+    fn is_reader(&self) -> bool {
+        matches!(self, Stage::Reader { .. })
+    }
+
+    /// Starts the `ProcessingStep` that records this stage in the processing history of the points
+    fn start_processing_step(&self) -> ProcessingStep {
+        let step = |tool: &str| ProcessingStep::start(tool, env!("CARGO_PKG_VERSION"));
+        match self {
+            Stage::Reader { filename } => {
+                step("readers.las").with_parameter("filename", filename.display())
+            }
+            Stage::VoxelGrid { cell } => step("filters.voxelgrid").with_parameter("cell", cell),
+            Stage::Decimation { step: n, offset } => step("filters.decimation")
+                .with_parameter("step", n)
+                .with_parameter("offset", offset),
+            Stage::Head { count } => step("filters.head").with_parameter("count", count),
+            Stage::Tail { count } => step("filters.tail").with_parameter("count", count),
+            Stage::Crop { bounds } => step("filters.crop").with_parameter("bounds", bounds),
+            Stage::Writer { filename } => {
+                step("writers.las").with_parameter("filename", filename.display())
+            }
+        }
+    }
+}
+
+struct Args {
+    pub pipeline_file: PathBuf,
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Executes a PDAL-style pipeline description given as a JSON or YAML file")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Pipeline description file. Files ending in .yaml or .yml are parsed as YAML, all other files as JSON")
+                .required(true),
+        )
+        .get_matches();
+
+    let pipeline_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+
+    Ok(Args { pipeline_file })
+}
+
+fn parse_pipeline(pipeline_file: &Path) -> Result<Vec<Stage>> {
+    let text = std::fs::read_to_string(pipeline_file)?;
+    let is_yaml = pipeline_file
+        .extension()
+        .map(|ex| ex == "yaml" || ex == "yml")
+        .unwrap_or(false);
+    let description: PipelineDescription = if is_yaml {
+        serde_yaml::from_str(&text)?
+    } else {
+        serde_json::from_str(&text)?
+    };
+    Ok(stages_from_description(description))
+}
+
+fn stages_from_description(description: PipelineDescription) -> Vec<Stage> {
+    let stage_descriptions = match description {
+        PipelineDescription::Wrapped { pipeline } => pipeline,
+        PipelineDescription::Bare(stages) => stages,
+    };
+
+    let stage_count = stage_descriptions.len();
+    let mut stages: Vec<Stage> = Vec::with_capacity(stage_count);
+    for (index, stage_description) in stage_descriptions.into_iter().enumerate() {
+        let stage = match stage_description {
+            StageDescription::Stage(stage) => stage,
+            StageDescription::Filename(filename) => {
+                let is_last_of_several = stage_count > 1 && index == stage_count - 1;
+                if !is_last_of_several && stages.iter().all(|stage| stage.is_reader()) {
+                    Stage::Reader { filename }
+                } else {
+                    Stage::Writer { filename }
+                }
+            }
+        };
+        stages.push(stage);
+    }
+    stages
+}
+
+/// Parses PDAL-style bounds of the form `([xmin, xmax], [ymin, ymax])` or `([xmin, xmax], [ymin, ymax], [zmin, zmax])`.
+/// 2D bounds are unbounded in the z-direction
+fn parse_bounds(bounds: &str) -> Result<AABB<f64>> {
+    let values = bounds
+        .split(|c: char| c == ',' || c == '(' || c == ')' || c == '[' || c == ']')
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow!("Invalid bounds {}: {}", bounds, e))?;
+    let (min_z, max_z) = match values.len() {
+        4 => (f64::MIN, f64::MAX),
+        6 => (values[4], values[5]),
+        _ => {
+            return Err(anyhow!(
+                "Invalid bounds {}: Expected ([xmin, xmax], [ymin, ymax]) or ([xmin, xmax], [ymin, ymax], [zmin, zmax])",
+                bounds
+            ))
+        }
+    };
+    if values[0] > values[1] || values[2] > values[3] || min_z > max_z {
+        return Err(anyhow!(
+            "Invalid bounds {}: Minimum must be <= maximum",
+            bounds
+        ));
+    }
+    Ok(AABB::from_min_max(
+        Point3::new(values[0], values[2], min_z),
+        Point3::new(values[1], values[3], max_z),
+    ))
+}
+
+fn select_points(
+    buffer: &InterleavedVecPointStorage,
+    indices: &[usize],
+) -> InterleavedVecPointStorage {
+    let mut selected =
+        InterleavedVecPointStorage::with_capacity(indices.len(), buffer.point_layout().clone());
+    for index in indices {
+        selected.push(&buffer.slice(*index..*index + 1));
+    }
+    selected
+}
+
+fn is_las_file(file: &Path) -> bool {
+    file.extension()
+        .map(|ex| ex == "las" || ex == "laz")
+        .unwrap_or(false)
+}
+
+fn read_file(
+    file: &Path,
+    points: &mut Option<InterleavedVecPointStorage>,
+    history: &mut ProcessingHistory,
+) -> Result<()> {
+    // Steps that produced the input file come before the steps of this pipeline
+    if is_las_file(file) {
+        history.extend(&processing_history_from_las_header(
+            LASReader::from_path(file)?.header(),
+        )?);
+    }
+
+    let factory: IOFactory = Default::default();
+    let mut reader = factory.make_reader(file)?;
+    let point_count = reader.point_count()?;
+
+    let buffer = points.get_or_insert_with(|| {
+        InterleavedVecPointStorage::with_capacity(
+            point_count,
+            reader.get_default_point_layout().clone(),
+        )
+    });
+    if buffer.point_layout() != reader.get_default_point_layout() {
+        return Err(anyhow!(
+            "File {} has a different point layout than the previously read files",
+            file.display()
+        ));
+    }
+    reader.read_into(buffer, point_count)?;
+
+    info!("Read {} points from {}", point_count, file.display());
+    Ok(())
+}
+
+fn make_writer(
+    file: &Path,
+    buffer: &InterleavedVecPointStorage,
+    history: &ProcessingHistory,
+) -> Result<Box<dyn PointWriter>> {
+    if !is_las_file(file) {
+        let factory: IOFactory = Default::default();
+        return Ok(factory.make_writer(file)?);
+    }
+
+    // Pick the LAS point format that preserves the most attributes of the points that are written
+    let mut header_builder = Builder::from((1, 4));
+    header_builder.point_format = las_point_format_from_point_layout(buffer.point_layout());
+    set_processing_history_in_las_builder(&mut header_builder, history)?;
+    let header = header_builder.into_header()?;
+    Ok(Box::new(LASWriter::from_path_and_header(file, header)?))
+}
+
+fn write_file(
+    file: &Path,
+    buffer: &InterleavedVecPointStorage,
+    history: &ProcessingHistory,
+) -> Result<()> {
+    // The writer is flushed once it is dropped, explicitly calling `flush` is not supported for LAZ files
+    let mut writer = make_writer(file, buffer, history)?;
+    writer.write(buffer)?;
+
+    info!("Wrote {} points to {}", buffer.len(), file.display());
+    Ok(())
+}
+
+fn crop(buffer: &InterleavedVecPointStorage, bounds: &AABB<f64>) -> Vec<usize> {
+    let position_attribute = buffer
+        .point_layout()
+        .get_attribute_by_name(POSITION_3D.name());
+    match position_attribute {
+        None => vec![],
+        Some(attribute) if attribute.datatype() == POSITION_3D.datatype() => buffer
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .enumerate()
+            .filter(|(_, pos)| bounds.contains(&Point3::new(pos.x, pos.y, pos.z)))
+            .map(|(index, _)| index)
+            .collect(),
+        Some(_) => buffer
+            .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+            .enumerate()
+            .filter(|(_, pos)| bounds.contains(&Point3::new(pos.x, pos.y, pos.z)))
+            .map(|(index, _)| index)
+            .collect(),
+    }
+}
+
+fn run_filter(
+    stage: &Stage,
+    buffer: &InterleavedVecPointStorage,
+) -> Result<InterleavedVecPointStorage> {
+    let indices: Vec<usize> = match stage {
+        Stage::VoxelGrid { cell } => {
+            if *cell <= 0.0 {
+                return Err(anyhow!("filters.voxelgrid: cell must be > 0"));
+            }
+            voxel_grid_filter(buffer, *cell)
+        }
+        Stage::Decimation { step, offset } => {
+            if *step == 0 {
+                return Err(anyhow!("filters.decimation: step must be > 0"));
+            }
+            (*offset..buffer.len()).step_by(*step).collect()
+        }
+        Stage::Head { count } => (0..usize::min(*count, buffer.len())).collect(),
+        Stage::Tail { count } => (buffer.len().saturating_sub(*count)..buffer.len()).collect(),
+        Stage::Crop { bounds } => crop(buffer, &parse_bounds(bounds)?),
+        Stage::Reader { .. } | Stage::Writer { .. } => {
+            unreachable!("run_filter called with a reader or writer stage")
+        }
+    };
+    Ok(select_points(buffer, &indices))
+}
+
+/// Executes the given `stages`. Each reader and filter stage appends a step to the processing history of the points,
+/// which the LAS writers store in the written files. The history of a written file ends with the last stage before
+/// its writer, so it does not include the writer itself
+fn execute_pipeline(stages: &[Stage]) -> Result<()> {
+    let mut points: Option<InterleavedVecPointStorage> = None;
+    let mut history = ProcessingHistory::new();
+
+    for (index, stage) in stages.iter().enumerate() {
+        info!("Stage {}/{}: {:?}", index + 1, stages.len(), stage);
+        let step = stage.start_processing_step();
+        match stage {
+            Stage::Reader { filename } => read_file(filename, &mut points, &mut history)?,
+            Stage::Writer { filename } => {
+                let buffer = points.as_ref().ok_or_else(|| {
+                    anyhow!("Writer {} has no preceding reader", filename.display())
+                })?;
+                write_file(filename, buffer, &history)?;
+                continue;
+            }
+            _ => {
+                let buffer = points
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("Filter {:?} has no preceding reader", stage))?;
+                let filtered = run_filter(stage, buffer)?;
+                info!("{} of {} points remaining", filtered.len(), buffer.len());
+                points = Some(filtered);
+            }
+        }
+        history.push(step.finish());
+    }
+
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+    let stages = parse_pipeline(&args.pipeline_file)?;
+    if stages.is_empty() {
+        return Err(anyhow!(
+            "Pipeline {} contains no stages",
+            args.pipeline_file.display()
+        ));
+    }
+
+    execute_pipeline(&stages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_stages(json: &str) -> Result<Vec<Stage>> {
+        Ok(stages_from_description(serde_json::from_str(json)?))
+    }
+
+    #[test]
+    fn test_last_filename_is_writer() -> Result<()> {
+        assert_eq!(
+            vec![
+                Stage::Reader {
+                    filename: "in.las".into()
+                },
+                Stage::Writer {
+                    filename: "out.las".into()
+                }
+            ],
+            parse_stages(r#"["in.las", "out.las"]"#)?
+        );
+        assert_eq!(
+            vec![
+                Stage::Reader {
+                    filename: "a.las".into()
+                },
+                Stage::Reader {
+                    filename: "b.las".into()
+                },
+                Stage::Head { count: 10 },
+                Stage::Writer {
+                    filename: "out.las".into()
+                }
+            ],
+            parse_stages(
+                r#"{"pipeline": ["a.las", "b.las", {"type": "filters.head", "count": 10}, "out.las"]}"#
+            )?
+        );
+        assert_eq!(
+            vec![Stage::Reader {
+                filename: "in.las".into()
+            }],
+            parse_stages(r#"["in.las"]"#)?
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,126 @@
+#![warn(clippy::all)]
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::{App, Arg};
+use log::info;
+use pasture_algorithms::classification::{remap_classification, ClassificationMapping};
+use pasture_core::containers::InterleavedVecPointStorage;
+use pasture_io::{
+    base::{PointReader, PointWriter},
+    las::{LASReader, LASWriter},
+};
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    pub mapping: ClassificationMapping,
+}
+
+/// Loads a classification mapping. `legacy` selects the mapping from LAS 1.1-1.3 codes to the ASPRS standard classes,
+/// files ending in .json are parsed as a JSON object that maps source codes to target codes (e.g. `{"8": 2}`, with
+/// `"*"` as the default for all unmapped codes), all other files are parsed as CSV
+fn load_mapping(mapping: &str) -> Result<ClassificationMapping> {
+    if mapping == "legacy" {
+        return Ok(ClassificationMapping::legacy_las_to_asprs());
+    }
+
+    let mapping_file = PathBuf::from(mapping);
+    let text = std::fs::read_to_string(&mapping_file).map_err(|e| {
+        anyhow!(
+            "Could not read classification mapping {}: {}",
+            mapping_file.display(),
+            e
+        )
+    })?;
+    let is_json = mapping_file
+        .extension()
+        .map(|ex| ex == "json")
+        .unwrap_or(false);
+    if !is_json {
+        return ClassificationMapping::from_csv(&text);
+    }
+
+    let table: BTreeMap<String, u8> = serde_json::from_str(&text)?;
+    let mut mapping = ClassificationMapping::new();
+    for (from, to) in table {
+        if from == "*" {
+            mapping.set_default(Some(to));
+        } else {
+            let from = from
+                .parse::<u8>()
+                .map_err(|e| anyhow!("Invalid classification code {}: {}", from, e))?;
+            mapping.insert(from, to);
+        }
+    }
+    Ok(mapping)
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Translates the classification codes of a LAS/LAZ file between classification schemes")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("MAPPING")
+                .long("mapping")
+                .takes_value(true)
+                .value_name("MAPPING")
+                .help("Classification mapping. Either 'legacy' to map LAS 1.1-1.3 codes to the ASPRS standard classes of LAS 1.4, a JSON file with an object that maps source codes to target codes, or a CSV file with 'source,target' lines. A source code of '*' maps all other codes")
+                .required(true),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_file = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    let mapping = load_mapping(matches.value_of("MAPPING").unwrap())?;
+
+    Ok(Args {
+        input_file,
+        output_file,
+        mapping,
+    })
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    let mut reader = LASReader::from_path(&args.input_file)?;
+    let point_count = reader.point_count()?;
+    let mut points = InterleavedVecPointStorage::with_capacity(
+        point_count,
+        reader.get_default_point_layout().clone(),
+    );
+    reader.read_into(&mut points, point_count)?;
+    info!("Read {} points", point_count);
+
+    let changed_count = remap_classification(&mut points, &args.mapping);
+    info!(
+        "Changed the classification of {}/{} points",
+        changed_count, point_count
+    );
+
+    let mut writer = LASWriter::from_path_and_header(&args.output_file, reader.header().clone())?;
+    writer.write(&points)?;
+
+    Ok(())
+}
@@ -0,0 +1,130 @@
+#![warn(clippy::all)]
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
+use log::info;
+use pasture_algorithms::flightlines::{
+    calculate_flightline_statistics, FlightlineStatistics, FlightlineStatisticsParameters,
+};
+use pasture_core::containers::InterleavedVecPointStorage;
+use pasture_io::base::IOFactory;
+use serde_json::json;
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_file: Option<PathBuf>,
+    pub parameters: FlightlineStatisticsParameters,
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Prints a JSON report with statistics for each flightline (point source ID) of a point cloud file")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output JSON file. If omitted, the report is printed to stdout"),
+        )
+        .arg(
+            Arg::with_name("CELL_SIZE")
+                .long("cell-size")
+                .takes_value(true)
+                .value_name("CELL_SIZE")
+                .help("Size of the grid cells that are used to calculate the covered area, density and overlap")
+                .default_value("1"),
+        )
+        .arg(
+            Arg::with_name("SCAN_ANGLE_BIN")
+                .long("scan-angle-bin")
+                .takes_value(true)
+                .value_name("DEGREES")
+                .help("Width of the bins of the scan angle histogram in degrees")
+                .default_value("5"),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_file = matches.value_of("OUTPUT").map(PathBuf::from);
+    let cell_size = value_t!(matches, "CELL_SIZE", f64)?;
+    if cell_size <= 0.0 {
+        return Err(anyhow!("Cell size must be > 0"));
+    }
+    let scan_angle_bin_size = value_t!(matches, "SCAN_ANGLE_BIN", f64)?;
+    if scan_angle_bin_size <= 0.0 {
+        return Err(anyhow!("Scan angle bin size must be > 0"));
+    }
+
+    Ok(Args {
+        input_file,
+        output_file,
+        parameters: FlightlineStatisticsParameters {
+            cell_size,
+            scan_angle_bin_size,
+        },
+    })
+}
+
+fn flightline_to_json(flightline: &FlightlineStatistics) -> serde_json::Value {
+    json!({
+        "point_source_id": flightline.point_source_id,
+        "number_of_points": flightline.point_count,
+        "bounds": {
+            "min": [flightline.bounds.min().x, flightline.bounds.min().y, flightline.bounds.min().z],
+            "max": [flightline.bounds.max().x, flightline.bounds.max().y, flightline.bounds.max().z],
+        },
+        "covered_area": flightline.covered_area,
+        "density": flightline.density,
+        "gps_time": flightline.gps_time_range.map(|(min, max)| json!({"min": min, "max": max})),
+        "scan_angle_histogram": flightline
+            .scan_angle_histogram
+            .iter()
+            .map(|bin| json!({"min": bin.min, "max": bin.max, "count": bin.count}))
+            .collect::<Vec<_>>(),
+        "overlap_percentage": flightline.overlap_percentage,
+    })
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    let factory: IOFactory = Default::default();
+    let mut reader = factory.make_reader(&args.input_file)?;
+    let point_count = reader.point_count()?;
+    let mut points = InterleavedVecPointStorage::with_capacity(
+        point_count,
+        reader.get_default_point_layout().clone(),
+    );
+    reader.read_into(&mut points, point_count)?;
+    info!("Read {} points", point_count);
+
+    let flightlines = calculate_flightline_statistics(&points, &args.parameters);
+    let report = json!({
+        "file": args.input_file.display().to_string(),
+        "number_of_points": point_count,
+        "cell_size": args.parameters.cell_size,
+        "flightlines": flightlines.iter().map(flightline_to_json).collect::<Vec<_>>(),
+    });
+
+    let report = serde_json::to_string_pretty(&report)?;
+    match &args.output_file {
+        Some(output_file) => std::fs::write(output_file, report)?,
+        None => println!("{}", report),
+    }
+
+    Ok(())
+}
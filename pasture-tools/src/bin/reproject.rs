@@ -0,0 +1,196 @@
+#![warn(clippy::all)]
+
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use clap::{App, Arg};
+use log::info;
+use pasture_algorithms::reprojection::{is_geographic_crs, Reprojection};
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBufferWriteable},
+    nalgebra::Vector3,
+};
+use pasture_io::{
+    base::{PointReader, PointWriter, ProgressReader},
+    las::{
+        epsg_code_from_las_header, is_crs_vlr, las_crs_vlr_from_epsg_code, wkt_from_las_header,
+        LASReader, LASWriter,
+    },
+    las_rs::{Builder, Header},
+};
+use pasture_tools::progress::make_progress;
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    pub source_crs: Option<String>,
+    pub target_epsg_code: u16,
+}
+
+/// Parses an EPSG code given either as a plain number or in the form `EPSG:1234`
+fn parse_epsg_code(s: &str) -> Result<u16> {
+    let code = s
+        .trim()
+        .trim_start_matches("EPSG:")
+        .trim_start_matches("epsg:");
+    code.parse::<u16>()
+        .map_err(|e| anyhow!("Invalid EPSG code {}: {}", s, e))
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Reprojects the points of a LAS/LAZ file into a different coordinate reference system")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input LAS/LAZ file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output LAS/LAZ file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("SOURCE_CRS")
+                .long("s_srs")
+                .takes_value(true)
+                .value_name("SOURCE_CRS")
+                .help("Coordinate reference system of the input file (e.g. EPSG:25832). If omitted, it is determined from the VLRs of the input file"),
+        )
+        .arg(
+            Arg::with_name("TARGET_CRS")
+                .long("t_srs")
+                .takes_value(true)
+                .value_name("TARGET_CRS")
+                .help("EPSG code of the target coordinate reference system (e.g. EPSG:4326)")
+                .required(true),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_file = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    let source_crs = matches.value_of("SOURCE_CRS").map(|s| s.to_owned());
+    let target_epsg_code = parse_epsg_code(matches.value_of("TARGET_CRS").unwrap())?;
+
+    Ok(Args {
+        input_file,
+        output_file,
+        source_crs,
+        target_epsg_code,
+    })
+}
+
+fn detect_source_crs(header: &Header) -> Result<String> {
+    if let Some(epsg_code) = epsg_code_from_las_header(header) {
+        return Ok(format!("EPSG:{}", epsg_code));
+    }
+    wkt_from_las_header(header).ok_or_else(|| {
+        anyhow!("Could not determine the coordinate reference system of the input file, please specify it using --s_srs")
+    })
+}
+
+/// Creates the header for the output file. All coordinate reference system VLRs are replaced with a VLR for the target
+/// coordinate reference system and the offsets are adjusted to the reprojected bounds of the input file
+fn make_output_header(
+    input_header: &Header,
+    reprojection: &Reprojection,
+    target_epsg_code: u16,
+) -> Result<Header> {
+    let bounds = input_header.bounds();
+    let corners = [
+        Vector3::new(bounds.min.x, bounds.min.y, bounds.min.z),
+        Vector3::new(bounds.max.x, bounds.min.y, bounds.min.z),
+        Vector3::new(bounds.min.x, bounds.max.y, bounds.min.z),
+        Vector3::new(bounds.max.x, bounds.max.y, bounds.min.z),
+    ];
+    let mut min_x = f64::MAX;
+    let mut min_y = f64::MAX;
+    for corner in corners.iter() {
+        let reprojected = reprojection.reproject_position(corner)?;
+        min_x = min_x.min(reprojected.x);
+        min_y = min_y.min(reprojected.y);
+    }
+
+    let is_geographic = is_geographic_crs(&format!("EPSG:{}", target_epsg_code))?;
+    let mut raw_header = input_header.clone().into_raw()?;
+    raw_header.x_offset = min_x.floor();
+    raw_header.y_offset = min_y.floor();
+    if is_geographic {
+        // Coordinates in degrees need a much finer scale than coordinates in meters
+        raw_header.x_scale_factor = 1e-7;
+        raw_header.y_scale_factor = 1e-7;
+    }
+
+    let mut builder = Builder::new(raw_header)?;
+    builder.vlrs = input_header
+        .vlrs()
+        .iter()
+        .filter(|vlr| !is_crs_vlr(vlr))
+        .cloned()
+        .collect();
+    builder
+        .vlrs
+        .push(las_crs_vlr_from_epsg_code(target_epsg_code, is_geographic));
+    builder.evlrs = input_header
+        .evlrs()
+        .iter()
+        .filter(|vlr| !is_crs_vlr(vlr))
+        .cloned()
+        .collect();
+    Ok(builder.into_header()?)
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    info!("Processing {}", args.input_file.display());
+
+    let mut reader = LASReader::from_path(&args.input_file)?;
+    let source_crs = match &args.source_crs {
+        Some(crs) => crs.clone(),
+        None => detect_source_crs(reader.header())?,
+    };
+    let target_crs = format!("EPSG:{}", args.target_epsg_code);
+    info!("Reprojecting from {} to {}", source_crs, target_crs);
+    let reprojection = Reprojection::new(&source_crs, &target_crs)?;
+
+    let output_header = make_output_header(reader.header(), &reprojection, args.target_epsg_code)?;
+    let mut writer = LASWriter::from_path_and_header(&args.output_file, output_header)?;
+
+    let chunk_size = 1_000_000;
+    let mut chunk = InterleavedVecPointStorage::with_capacity(
+        chunk_size,
+        reader.get_default_point_layout().clone(),
+    );
+    let total_points = reader.remaining_points();
+    let (progress, progress_bar) = make_progress(total_points)?;
+    let mut reader = ProgressReader::new(reader, progress);
+    let mut points_processed = 0;
+    while points_processed < total_points {
+        chunk.clear();
+        let points_in_chunk = reader.read_into(&mut chunk, chunk_size)?;
+        if points_in_chunk == 0 {
+            break;
+        }
+
+        reprojection.reproject(&mut chunk)?;
+        writer.write(&chunk)?;
+
+        points_processed += points_in_chunk;
+    }
+    progress_bar.finish();
+
+    info!("Wrote {}", args.output_file.display());
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+#![warn(clippy::all)]
+
+use std::{convert::TryInto, path::PathBuf};
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
+use log::info;
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBufferWriteable},
+    layout::attributes::{GPS_TIME, POSITION_3D},
+    math::MortonIndex64,
+    nalgebra::Point3,
+};
+use pasture_io::{
+    base::{ExternalSorter, PointReader, PointWriter},
+    las::{LASReader, LASWriter},
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum SortOrder {
+    Morton,
+    GpsTime,
+}
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    pub order: SortOrder,
+    pub max_points_in_memory: usize,
+    pub temp_dir: Option<PathBuf>,
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Sorts the points of a LAS/LAZ file, also if the file does not fit into memory")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input LAS/LAZ file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output LAS/LAZ file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("BY")
+                .long("by")
+                .takes_value(true)
+                .value_name("BY")
+                .possible_values(&["morton", "gps-time"])
+                .default_value("morton")
+                .help("Sort order: Morton order of the positions within the bounds of the file, or GPS time"),
+        )
+        .arg(
+            Arg::with_name("MAX_POINTS_IN_MEMORY")
+                .long("max-points-in-memory")
+                .takes_value(true)
+                .value_name("MAX_POINTS_IN_MEMORY")
+                .default_value("10000000")
+                .help("Maximum number of points that are kept in memory, all other points are spilled to temporary files"),
+        )
+        .arg(
+            Arg::with_name("TEMP_DIR")
+                .long("temp-dir")
+                .takes_value(true)
+                .value_name("TEMP_DIR")
+                .help("Directory for the temporary files. Defaults to the temporary directory of the system"),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_file = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    let order = match matches.value_of("BY").unwrap() {
+        "gps-time" => SortOrder::GpsTime,
+        _ => SortOrder::Morton,
+    };
+    let max_points_in_memory = value_t!(matches, "MAX_POINTS_IN_MEMORY", usize)?;
+    if max_points_in_memory == 0 {
+        return Err(anyhow!("--max-points-in-memory must be > 0"));
+    }
+    let temp_dir = matches.value_of("TEMP_DIR").map(PathBuf::from);
+
+    Ok(Args {
+        input_file,
+        output_file,
+        order,
+        max_points_in_memory,
+        temp_dir,
+    })
+}
+
+fn read_f64(point: &[u8], offset: usize) -> f64 {
+    f64::from_le_bytes(point[offset..offset + 8].try_into().unwrap())
+}
+
+/// Maps an `f64` to a `u64` with the same order, so that it can be used as a sort key
+fn ordered_bits(value: f64) -> u64 {
+    let bits = value.to_bits();
+    if bits >> 63 == 1 {
+        !bits
+    } else {
+        bits | (1 << 63)
+    }
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    let mut reader = LASReader::from_path(&args.input_file)?;
+    let layout = reader.get_default_point_layout().clone();
+    let bounds = reader
+        .get_metadata()
+        .bounds()
+        .ok_or_else(|| anyhow!("File {} has no bounds", args.input_file.display()))?
+        // Morton indices require cubic bounds
+        .as_cubic();
+    let position_offset = layout
+        .offset_of(&POSITION_3D)
+        .ok_or_else(|| anyhow!("File {} has no positions", args.input_file.display()))?
+        as usize;
+    let gps_time_offset = layout.offset_of(&GPS_TIME).map(|offset| offset as usize);
+    if args.order == SortOrder::GpsTime && gps_time_offset.is_none() {
+        return Err(anyhow!(
+            "File {} has no GPS times",
+            args.input_file.display()
+        ));
+    }
+
+    let order = args.order;
+    let key_fn = move |point: &[u8]| match order {
+        SortOrder::Morton => {
+            let position = Point3::new(
+                read_f64(point, position_offset),
+                read_f64(point, position_offset + 8),
+                read_f64(point, position_offset + 16),
+            );
+            MortonIndex64::from_point_in_bounds(&position, &bounds).index()
+        }
+        SortOrder::GpsTime => ordered_bits(read_f64(point, gps_time_offset.unwrap())),
+    };
+    let mut sorter = ExternalSorter::new(layout.clone(), args.max_points_in_memory, key_fn);
+    if let Some(temp_dir) = &args.temp_dir {
+        sorter = sorter.with_temp_dir(temp_dir);
+    }
+
+    let chunk_size = 1_000_000;
+    let mut chunk = InterleavedVecPointStorage::with_capacity(chunk_size, layout);
+    loop {
+        chunk.clear();
+        if reader.read_into(&mut chunk, chunk_size)? == 0 {
+            break;
+        }
+        sorter.push(&chunk)?;
+    }
+    info!(
+        "Read {} points, spilled {} runs to disk",
+        sorter.point_count(),
+        sorter.run_count()
+    );
+
+    let mut writer = LASWriter::from_path_and_header(&args.output_file, reader.header().clone())?;
+    let mut merger = sorter.finish()?;
+    loop {
+        chunk.clear();
+        if merger.read_into(&mut chunk, chunk_size)? == 0 {
+            break;
+        }
+        writer.write(&chunk)?;
+    }
+    writer.flush()?;
+
+    info!("Wrote sorted points to {}", args.output_file.display());
+    Ok(())
+}
@@ -0,0 +1,359 @@
+#![warn(clippy::all)]
+
+use std::{
+    collections::HashMap,
+    convert::TryInto,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg, ArgGroup};
+use log::info;
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBuffer, PointBufferExt, PointBufferWriteable},
+    layout::{
+        attributes::POSITION_3D, PointAttributeDataType, PointAttributeDefinition, PointLayout,
+    },
+    nalgebra::Vector3,
+};
+use pasture_io::{
+    base::{PointReader, PointWriter, SplitOutput, SplitWriter},
+    las::{LASReader, LASWriter},
+};
+use pasture_tools::las_header::header_for_bounds;
+
+/// The different strategies by which an input file can be split
+enum SplitMode {
+    /// Split into files with at most the given number of points each
+    Capacity(usize),
+    /// Split into one file per distinct value of the given attribute
+    Attribute(PointAttributeDefinition),
+    /// Split into one file per cell of a regular 2D grid with the given cell size
+    Grid(f64),
+}
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_dir: PathBuf,
+    pub template: String,
+    pub capacity: Option<usize>,
+    pub attribute: Option<String>,
+    pub grid_size: Option<f64>,
+    pub max_open_files: usize,
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Splits a LAS/LAZ file into multiple files by point count, attribute value or grid cell")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input LAS/LAZ file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output directory")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("TEMPLATE")
+                .long("template")
+                .takes_value(true)
+                .value_name("TEMPLATE")
+                .help("Template for the output file names. {stem} is replaced with the file stem of the input file, {id} with the identifier of the split (file index, attribute value or grid cell)")
+                .default_value("{stem}_{id}.laz"),
+        )
+        .arg(
+            Arg::with_name("CAPACITY")
+                .long("capacity")
+                .takes_value(true)
+                .value_name("CAPACITY")
+                .help("Split into files with at most CAPACITY points each"),
+        )
+        .arg(
+            Arg::with_name("ATTRIBUTE")
+                .long("attribute")
+                .takes_value(true)
+                .value_name("ATTRIBUTE")
+                .help("Split into one file per distinct value of the given attribute (e.g. classification or point_source_id)"),
+        )
+        .arg(
+            Arg::with_name("GRID")
+                .long("grid")
+                .takes_value(true)
+                .value_name("GRID")
+                .help("Split into one file per cell of a regular 2D grid with the given cell size"),
+        )
+        .arg(
+            Arg::with_name("MAX_OPEN_FILES")
+                .long("max-open-files")
+                .takes_value(true)
+                .value_name("COUNT")
+                .help("Maximum number of temporary files that are open at the same time when splitting by attribute or grid cell")
+                .default_value("256"),
+        )
+        .group(
+            ArgGroup::with_name("MODE")
+                .args(&["CAPACITY", "ATTRIBUTE", "GRID"])
+                .required(true),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_dir = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    if !output_dir.exists() {
+        std::fs::create_dir_all(&output_dir)?;
+    }
+    let template = matches.value_of("TEMPLATE").unwrap().to_owned();
+    let capacity = if matches.is_present("CAPACITY") {
+        Some(value_t!(matches, "CAPACITY", usize)?)
+    } else {
+        None
+    };
+    let attribute = matches.value_of("ATTRIBUTE").map(|s| s.to_owned());
+    let grid_size = if matches.is_present("GRID") {
+        Some(value_t!(matches, "GRID", f64)?)
+    } else {
+        None
+    };
+    let max_open_files = value_t!(matches, "MAX_OPEN_FILES", usize)?;
+    if max_open_files == 0 {
+        return Err(anyhow!("Maximum number of open files must be > 0"));
+    }
+
+    Ok(Args {
+        input_file,
+        output_dir,
+        template,
+        capacity,
+        attribute,
+        grid_size,
+        max_open_files,
+    })
+}
+
+/// Normalizes an attribute name so that e.g. `point_source_id` matches `PointSourceID`
+fn normalize_attribute_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| *c != '_' && *c != ' ')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+fn find_attribute(layout: &PointLayout, name: &str) -> Result<PointAttributeDefinition> {
+    let normalized_name = normalize_attribute_name(name);
+    layout
+        .attributes()
+        .find(|attribute| normalize_attribute_name(attribute.name()) == normalized_name)
+        .map(|attribute| PointAttributeDefinition::custom(attribute.name(), attribute.datatype()))
+        .ok_or_else(|| anyhow!("Attribute {} not found in point layout ({})", name, layout))
+}
+
+fn get_split_mode(args: &Args, layout: &PointLayout) -> Result<SplitMode> {
+    if let Some(capacity) = args.capacity {
+        if capacity == 0 {
+            return Err(anyhow!("Capacity must be > 0"));
+        }
+        return Ok(SplitMode::Capacity(capacity));
+    }
+    if let Some(attribute_name) = &args.attribute {
+        let attribute = find_attribute(layout, attribute_name)?;
+        match attribute.datatype() {
+            PointAttributeDataType::U8
+            | PointAttributeDataType::I8
+            | PointAttributeDataType::U16
+            | PointAttributeDataType::I16
+            | PointAttributeDataType::U32
+            | PointAttributeDataType::I32
+            | PointAttributeDataType::U64
+            | PointAttributeDataType::I64
+            | PointAttributeDataType::Bool => (),
+            other => {
+                return Err(anyhow!(
+                    "Can't split by attribute {} because its datatype {} is not an integer type",
+                    attribute,
+                    other
+                ))
+            }
+        }
+        return Ok(SplitMode::Attribute(attribute));
+    }
+    let grid_size = args.grid_size.unwrap();
+    if grid_size <= 0.0 {
+        return Err(anyhow!("Grid cell size must be > 0"));
+    }
+    Ok(SplitMode::Grid(grid_size))
+}
+
+/// Converts the raw bytes of a single integer attribute value into a string
+fn integer_attribute_to_string(datatype: PointAttributeDataType, raw: &[u8]) -> String {
+    match datatype {
+        PointAttributeDataType::U8 => raw[0].to_string(),
+        PointAttributeDataType::I8 => (raw[0] as i8).to_string(),
+        PointAttributeDataType::U16 => u16::from_ne_bytes(raw.try_into().unwrap()).to_string(),
+        PointAttributeDataType::I16 => i16::from_ne_bytes(raw.try_into().unwrap()).to_string(),
+        PointAttributeDataType::U32 => u32::from_ne_bytes(raw.try_into().unwrap()).to_string(),
+        PointAttributeDataType::I32 => i32::from_ne_bytes(raw.try_into().unwrap()).to_string(),
+        PointAttributeDataType::U64 => u64::from_ne_bytes(raw.try_into().unwrap()).to_string(),
+        PointAttributeDataType::I64 => i64::from_ne_bytes(raw.try_into().unwrap()).to_string(),
+        PointAttributeDataType::Bool => (raw[0] != 0).to_string(),
+        other => panic!(
+            "integer_attribute_to_string: Unsupported datatype {}",
+            other
+        ),
+    }
+}
+
+/// Calculates the split identifier of each point in `chunk`. `chunk_offset` is the index of the first point in `chunk`
+/// within the input file
+fn split_ids(
+    mode: &SplitMode,
+    chunk: &InterleavedVecPointStorage,
+    chunk_offset: usize,
+) -> Vec<String> {
+    match mode {
+        SplitMode::Capacity(capacity) => (0..chunk.len())
+            .map(|index| ((chunk_offset + index) / capacity).to_string())
+            .collect(),
+        SplitMode::Attribute(attribute) => {
+            let mut raw_value = vec![0; attribute.size() as usize];
+            (0..chunk.len())
+                .map(|index| {
+                    chunk.get_raw_attribute(index, attribute, &mut raw_value[..]);
+                    integer_attribute_to_string(attribute.datatype(), &raw_value[..])
+                })
+                .collect()
+        }
+        SplitMode::Grid(cell_size) => chunk
+            .iter_attribute::<Vector3<f64>>(&POSITION_3D)
+            .map(|position| {
+                format!(
+                    "{}_{}",
+                    (position.x / cell_size).floor() as i64,
+                    (position.y / cell_size).floor() as i64
+                )
+            })
+            .collect(),
+    }
+}
+
+fn output_file_path(args: &Args, id: &str) -> Result<PathBuf> {
+    let stem = args
+        .input_file
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| {
+            anyhow!(
+                "Could not get file stem of file {}",
+                args.input_file.display()
+            )
+        })?;
+    let file_name = args.template.replace("{stem}", stem).replace("{id}", id);
+    Ok(args.output_dir.join(file_name))
+}
+
+fn split_file(input_file: &Path, args: &Args) -> Result<()> {
+    info!("Processing {}", input_file.display());
+
+    let chunk_size = 1_000_000;
+    let mut reader = LASReader::from_path(input_file)?;
+    let layout = reader.get_default_point_layout().clone();
+    let mode = get_split_mode(args, &layout)?;
+
+    let mut chunk = InterleavedVecPointStorage::with_capacity(chunk_size, layout.clone());
+    // When splitting by capacity, all previous files are complete once a new file is started, so only the current
+    // file is written directly. All other modes can write to any file at any time, so they go through a `SplitWriter`,
+    // which bounds the number of open files and creates the output files with a scale and offset that fit their points
+    let mut capacity_writer: Option<(String, LASWriter)> = None;
+    let header = reader.header().clone();
+    let make_writer = |id: &String, output: &SplitOutput| -> pasture_io::base::Result<LASWriter> {
+        let output_file = output_file_path(args, id)?;
+        info!("Creating {}", output_file.display());
+        let output_header = match &output.bounds {
+            Some(bounds) => header_for_bounds(&header, bounds)?,
+            None => header.clone(),
+        };
+        LASWriter::from_path_and_header(&output_file, output_header)
+    };
+    let mut split_writer = SplitWriter::by_key(make_writer)
+        .with_max_open_files(args.max_open_files)
+        .with_temp_dir(&args.output_dir);
+
+    let total_points = reader.remaining_points();
+    let mut points_processed = 0;
+    while points_processed < total_points {
+        chunk.clear();
+        let points_in_chunk = reader.read_into(&mut chunk, chunk_size)?;
+        if points_in_chunk == 0 {
+            break;
+        }
+
+        // Group the points of this chunk by their ids, keeping the groups in order of their first occurrence
+        let ids = split_ids(&mode, &chunk, points_processed);
+        let mut group_of_id: HashMap<&str, usize> = HashMap::new();
+        let mut groups: Vec<(&str, Vec<usize>)> = vec![];
+        for (index, id) in ids.iter().enumerate() {
+            let group = *group_of_id.entry(id.as_str()).or_insert_with(|| {
+                groups.push((id.as_str(), vec![]));
+                groups.len() - 1
+            });
+            groups[group].1.push(index);
+        }
+
+        for (id, indices) in groups {
+            let mut points =
+                InterleavedVecPointStorage::with_capacity(indices.len(), layout.clone());
+            for index in indices {
+                points.push(&chunk.slice(index..index + 1));
+            }
+
+            if let SplitMode::Capacity(_) = mode {
+                let is_new_file = capacity_writer
+                    .as_ref()
+                    .map_or(true, |(current_id, _)| current_id != id);
+                if is_new_file {
+                    if let Some((_, writer)) = capacity_writer.take() {
+                        writer.finalize()?;
+                    }
+                    let output_file = output_file_path(args, id)?;
+                    info!("Creating {}", output_file.display());
+                    let writer =
+                        LASWriter::from_path_and_header(&output_file, reader.header().clone())?;
+                    capacity_writer = Some((id.to_owned(), writer));
+                }
+                capacity_writer.as_mut().unwrap().1.write(&points)?;
+            } else {
+                split_writer.write_to(id.to_owned(), &points)?;
+            }
+        }
+
+        points_processed += points_in_chunk;
+        info!("{}/{} points", points_processed, total_points);
+    }
+
+    if let Some((_, writer)) = capacity_writer {
+        writer.finalize()?;
+    }
+    if !split_writer.is_empty() {
+        info!("Writing {} files", split_writer.len());
+        split_writer.finish(|_, writer| writer.finalize())?;
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+    split_file(&args.input_file, &args)?;
+
+    Ok(())
+}
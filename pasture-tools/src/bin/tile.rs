@@ -0,0 +1,381 @@
+#![warn(clippy::all)]
+
+use std::{
+    collections::BTreeMap,
+    fs::{read_dir, File},
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
+use log::{info, warn};
+use pasture_algorithms::quadtree::QuadtreeIndexedBuffer;
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBufferWriteable, SpatialQuery},
+    layout::PointLayout,
+    math::AABB,
+    nalgebra::Point3,
+};
+use pasture_io::{
+    base::{PointReader, SplitOutput, SplitWriter},
+    las::{LASReader, LASWriter},
+};
+use pasture_tools::las_header::header_for_bounds;
+
+struct Args {
+    pub input_files: Vec<PathBuf>,
+    pub output_dir: PathBuf,
+    pub tile_size: f64,
+    pub buffer: f64,
+    pub compressed: bool,
+    pub index_format: String,
+    pub max_open_files: usize,
+}
+
+/// Creates the writer for an output tile from its index in the grid and the statistics of its points
+type TileWriterFactory =
+    Box<dyn FnMut(&(i64, i64), &SplitOutput) -> pasture_io::base::Result<LASWriter>>;
+
+/// Writers for the output tiles by their index in the grid
+type TileWriters = SplitWriter<(i64, i64), LASWriter, TileWriterFactory>;
+
+fn get_all_input_files<P: AsRef<Path>>(input_path: P) -> Result<Vec<PathBuf>> {
+    let path = input_path.as_ref();
+    if !path.exists() {
+        return Err(anyhow!("Input path {} does not exist!", path.display()));
+    }
+
+    if path.is_file() {
+        return Ok(vec![path.into()]);
+    }
+
+    let mut files = vec![];
+    for entry in read_dir(path)? {
+        let file = entry?.path();
+        let is_las_file = file
+            .extension()
+            .map(|ex| ex == "las" || ex == "laz")
+            .unwrap_or(false);
+        if is_las_file {
+            files.push(file);
+        } else {
+            warn!("Skipping file {} which is no LAS/LAZ file", file.display());
+        }
+    }
+    Ok(files)
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Splits one or more LAS/LAZ files into a regular grid of tiles")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .multiple(true)
+                .value_name("INPUT")
+                .help("Input files or directories. Directories are scanned (non-recursively) for LAS/LAZ files")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output directory")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("SIZE")
+                .long("size")
+                .takes_value(true)
+                .value_name("SIZE")
+                .help("Edge length of the tiles")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("BUFFER")
+                .long("buffer")
+                .takes_value(true)
+                .value_name("BUFFER")
+                .help("Additionally include all points within this distance around each tile, so that neighbouring tiles overlap")
+                .default_value("0"),
+        )
+        .arg(
+            Arg::with_name("LAS")
+                .long("las")
+                .help("Write uncompressed LAS files instead of LAZ files"),
+        )
+        .arg(
+            Arg::with_name("INDEX_FORMAT")
+                .long("index-format")
+                .takes_value(true)
+                .value_name("FORMAT")
+                .possible_values(&["geojson", "csv"])
+                .help("Format of the tile index that contains the bounds of all tiles")
+                .default_value("geojson"),
+        )
+        .arg(
+            Arg::with_name("MAX_OPEN_FILES")
+                .long("max-open-files")
+                .takes_value(true)
+                .value_name("COUNT")
+                .help("Maximum number of temporary tile files that are open at the same time")
+                .default_value("256"),
+        )
+        .get_matches();
+
+    let mut input_files = vec![];
+    for input in matches.values_of("INPUT").unwrap() {
+        input_files.extend(get_all_input_files(input)?);
+    }
+    if input_files.is_empty() {
+        return Err(anyhow!("No input files found"));
+    }
+
+    let output_dir = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    if !output_dir.exists() {
+        std::fs::create_dir_all(&output_dir)?;
+    }
+
+    let tile_size = value_t!(matches, "SIZE", f64)?;
+    if tile_size <= 0.0 {
+        return Err(anyhow!("Tile size must be > 0"));
+    }
+    let buffer = value_t!(matches, "BUFFER", f64)?;
+    if buffer < 0.0 {
+        return Err(anyhow!("Buffer must be >= 0"));
+    }
+    let max_open_files = value_t!(matches, "MAX_OPEN_FILES", usize)?;
+    if max_open_files == 0 {
+        return Err(anyhow!("Maximum number of open files must be > 0"));
+    }
+
+    Ok(Args {
+        input_files,
+        output_dir,
+        tile_size,
+        buffer,
+        compressed: !matches.is_present("LAS"),
+        index_format: matches.value_of("INDEX_FORMAT").unwrap().to_owned(),
+        max_open_files,
+    })
+}
+
+/// Returns the range of tile indices along one axis that a coordinate falls into, including the buffer around each tile
+fn tile_range(coordinate: f64, tile_size: f64, buffer: f64) -> std::ops::RangeInclusive<i64> {
+    let first = ((coordinate - buffer) / tile_size).floor() as i64;
+    let last = ((coordinate + buffer) / tile_size).floor() as i64;
+    first..=last
+}
+
+fn tile_points(
+    input_file: &Path,
+    args: &Args,
+    output_layout: &mut Option<PointLayout>,
+    tiles: &mut Option<TileWriters>,
+) -> Result<()> {
+    info!("Processing {}", input_file.display());
+
+    let mut reader = LASReader::from_path(input_file)?;
+    let layout = reader.get_default_point_layout().clone();
+    let expected_layout = output_layout.get_or_insert_with(|| layout.clone());
+    if *expected_layout != layout {
+        return Err(anyhow!(
+            "File {} has a different point format than the previous input files",
+            input_file.display()
+        ));
+    }
+    // All tiles use the point format of the first input file, with a scale and offset that fit the bounds of the tile
+    let tiles = tiles.get_or_insert_with(|| {
+        let header = reader.header().clone();
+        let output_dir = args.output_dir.clone();
+        let extension = tile_extension(args);
+        let make_tile_writer: TileWriterFactory = Box::new(move |tile_index, tile| {
+            let tile_header = match &tile.bounds {
+                Some(bounds) => header_for_bounds(&header, bounds)?,
+                None => header.clone(),
+            };
+            LASWriter::from_path_and_header(
+                output_dir.join(tile_file_name(tile_index, extension)),
+                tile_header,
+            )
+        });
+        SplitWriter::by_key(make_tile_writer)
+            .with_max_open_files(args.max_open_files)
+            .with_temp_dir(&args.output_dir)
+    });
+
+    let chunk_size = 1_000_000;
+    let mut chunk = InterleavedVecPointStorage::with_capacity(chunk_size, layout.clone());
+    loop {
+        chunk.clear();
+        let points_in_chunk = reader.read_into(&mut chunk, chunk_size)?;
+        if points_in_chunk == 0 {
+            break;
+        }
+
+        // Points are assigned to tiles by bounds queries on a quadtree over the chunk, which only has to visit the
+        // parts of the chunk that overlap each tile
+        let indexed_chunk = QuadtreeIndexedBuffer::new(&chunk);
+        let positions = indexed_chunk.tree().positions();
+        let chunk_bounds = indexed_chunk.tree().bounds().unwrap();
+        let tiles_x = *tile_range(chunk_bounds.min().x, args.tile_size, args.buffer).start()
+            ..=*tile_range(chunk_bounds.max().x, args.tile_size, args.buffer).end();
+        let tiles_y = *tile_range(chunk_bounds.min().y, args.tile_size, args.buffer).start()
+            ..=*tile_range(chunk_bounds.max().y, args.tile_size, args.buffer).end();
+        let tile_indices = tiles_x.flat_map(|x| tiles_y.clone().map(move |y| (x, y)));
+        for tile_index in tile_indices {
+            let (min_x, min_y, max_x, max_y) = tile_bounds(&tile_index, args.tile_size);
+            let query = SpatialQuery::Bounds(AABB::from_min_max_unchecked(
+                Point3::new(min_x - args.buffer, min_y - args.buffer, f64::NEG_INFINITY),
+                Point3::new(max_x + args.buffer, max_y + args.buffer, f64::INFINITY),
+            ));
+            // The query bounds include their upper boundary, the tiles don't
+            let indices = indexed_chunk
+                .query_indices(&query)
+                .into_iter()
+                .filter(|index| {
+                    let position = &positions[*index];
+                    tile_range(position.x, args.tile_size, args.buffer).contains(&tile_index.0)
+                        && tile_range(position.y, args.tile_size, args.buffer)
+                            .contains(&tile_index.1)
+                })
+                .collect::<Vec<_>>();
+            if indices.is_empty() {
+                continue;
+            }
+
+            let mut points =
+                InterleavedVecPointStorage::with_capacity(indices.len(), layout.clone());
+            for index in indices {
+                points.push(&chunk.slice(*index..*index + 1));
+            }
+            tiles.write_to(tile_index, &points)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn tile_bounds(tile_index: &(i64, i64), tile_size: f64) -> (f64, f64, f64, f64) {
+    let min_x = tile_index.0 as f64 * tile_size;
+    let min_y = tile_index.1 as f64 * tile_size;
+    (min_x, min_y, min_x + tile_size, min_y + tile_size)
+}
+
+fn tile_extension(args: &Args) -> &'static str {
+    if args.compressed {
+        "laz"
+    } else {
+        "las"
+    }
+}
+
+/// Returns the file name of a tile relative to the output directory, which is what the tile index refers to
+fn tile_file_name(tile_index: &(i64, i64), extension: &str) -> String {
+    format!("tile_{}_{}.{}", tile_index.0, tile_index.1, extension)
+}
+
+fn write_tile_index_csv(
+    path: &Path,
+    tiles: &BTreeMap<(i64, i64), SplitOutput>,
+    tile_size: f64,
+    extension: &str,
+) -> Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    writeln!(writer, "file,point_count,min_x,min_y,max_x,max_y")?;
+    for (tile_index, tile) in tiles.iter() {
+        let (min_x, min_y, max_x, max_y) = tile_bounds(tile_index, tile_size);
+        writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            tile_file_name(tile_index, extension),
+            tile.point_count,
+            min_x,
+            min_y,
+            max_x,
+            max_y
+        )?;
+    }
+    Ok(())
+}
+
+fn write_tile_index_geojson(
+    path: &Path,
+    tiles: &BTreeMap<(i64, i64), SplitOutput>,
+    tile_size: f64,
+    extension: &str,
+) -> Result<()> {
+    let features = tiles
+        .iter()
+        .map(|(tile_index, tile)| {
+            let (min_x, min_y, max_x, max_y) = tile_bounds(tile_index, tile_size);
+            serde_json::json!({
+                "type": "Feature",
+                "properties": {
+                    "file": tile_file_name(tile_index, extension),
+                    "point_count": tile.point_count,
+                },
+                "geometry": {
+                    "type": "Polygon",
+                    "coordinates": [[
+                        [min_x, min_y],
+                        [max_x, min_y],
+                        [max_x, max_y],
+                        [min_x, max_y],
+                        [min_x, min_y],
+                    ]],
+                },
+            })
+        })
+        .collect::<Vec<_>>();
+    let feature_collection = serde_json::json!({
+        "type": "FeatureCollection",
+        "features": features,
+    });
+
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer_pretty(writer, &feature_collection)?;
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    info!("Processing {} files", args.input_files.len());
+
+    let mut output_layout = None;
+    let mut tile_writers = None;
+    for file in args.input_files.iter() {
+        tile_points(file, &args, &mut output_layout, &mut tile_writers)?;
+    }
+    info!("Writing tiles");
+    let tiles = match tile_writers {
+        Some(tile_writers) => tile_writers.finish(|_, writer| writer.finalize())?,
+        None => BTreeMap::new(),
+    };
+
+    let index_path = if args.index_format == "csv" {
+        let path = args.output_dir.join("tiles.csv");
+        write_tile_index_csv(&path, &tiles, args.tile_size, tile_extension(&args))?;
+        path
+    } else {
+        let path = args.output_dir.join("tiles.geojson");
+        write_tile_index_geojson(&path, &tiles, args.tile_size, tile_extension(&args))?;
+        path
+    };
+
+    info!(
+        "Wrote {} tiles, tile index is at {}",
+        tiles.len(),
+        index_path.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,599 @@
+#![warn(clippy::all)]
+
+use std::{collections::HashSet, path::PathBuf, time::Duration};
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
+use log::{info, warn};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Window, WindowOptions};
+use pasture_algorithms::bounds::calculate_bounds;
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBufferExt},
+    layout::attributes::{CLASSIFICATION, COLOR_RGB, INTENSITY, POSITION_3D},
+    math::AABB,
+    nalgebra::Vector3,
+};
+use pasture_io::base::IOFactory;
+use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
+/// Number of cells along each axis of the grid of the coarsest level of detail
+const LOD_GRID_SIZE: u64 = 128;
+/// Maximum number of levels of detail. All points that remain after the last level are appended in file order
+const MAX_LOD_LEVELS: u32 = 16;
+/// Vertical field of view of the camera in radians
+const FIELD_OF_VIEW: f32 = std::f32::consts::FRAC_PI_3;
+const BACKGROUND_COLOR: u32 = 0x202020;
+
+/// Colors of the standard ASPRS classes of the LAS 1.4 specification
+const CLASSIFICATION_COLORS: [u32; 19] = [
+    0xa0a0a0, // Created, never classified
+    0xc8c8c8, // Unclassified
+    0x8b5a2b, // Ground
+    0x90ee90, // Low vegetation
+    0x228b22, // Medium vegetation
+    0x006400, // High vegetation
+    0xdc3c3c, // Building
+    0xff00ff, // Low point (noise)
+    0x808080, // Model key-point
+    0x005aff, // Water
+    0x8b4513, // Rail
+    0x505050, // Road surface
+    0xc8c800, // Overlap
+    0xffd700, // Wire - guard
+    0xffa500, // Wire - conductor
+    0x800080, // Transmission tower
+    0xda70d6, // Wire-structure connector
+    0x708090, // Bridge deck
+    0xff0000, // High noise
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum ColorMode {
+    Elevation,
+    Classification,
+    Intensity,
+    Rgb,
+}
+
+struct Args {
+    pub input_file: PathBuf,
+    pub point_budget: usize,
+    pub color_mode: ColorMode,
+    pub point_size: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+/// The points that are displayed, with positions relative to the center of the bounding box of the point cloud
+struct Cloud {
+    positions: Vec<Vector3<f32>>,
+    classifications: Option<Vec<u8>>,
+    intensities: Option<Vec<u16>>,
+    colors: Option<Vec<Vector3<u16>>>,
+    bounds: AABB<f64>,
+}
+
+impl Cloud {
+    fn supports(&self, color_mode: ColorMode) -> bool {
+        match color_mode {
+            ColorMode::Elevation => true,
+            ColorMode::Classification => self.classifications.is_some(),
+            ColorMode::Intensity => self.intensities.is_some(),
+            ColorMode::Rgb => self.colors.is_some(),
+        }
+    }
+}
+
+/// An orbiting camera that looks at `target` from the given `distance`
+struct Camera {
+    target: Vector3<f32>,
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+}
+
+impl Camera {
+    /// Creates a camera that views the whole point cloud with the given bounds at an oblique angle
+    fn overview(bounds: &AABB<f64>) -> Self {
+        let radius = (bounds.extent().norm() / 2.0).max(1.0) as f32;
+        Self {
+            target: Vector3::zeros(),
+            yaw: 0.0,
+            pitch: std::f32::consts::FRAC_PI_4,
+            distance: radius / (FIELD_OF_VIEW / 2.0).sin(),
+        }
+    }
+
+    /// Returns the right and up vectors of the camera in world space
+    fn right_and_up(&self) -> (Vector3<f32>, Vector3<f32>) {
+        let (sin_yaw, cos_yaw) = self.yaw.sin_cos();
+        let (sin_pitch, cos_pitch) = self.pitch.sin_cos();
+        (
+            Vector3::new(cos_yaw, sin_yaw, 0.0),
+            Vector3::new(-sin_yaw * sin_pitch, cos_yaw * sin_pitch, cos_pitch),
+        )
+    }
+
+    fn pan(&mut self, dx: f32, dy: f32, focal_length: f32) {
+        let (right, up) = self.right_and_up();
+        let world_units_per_pixel = self.distance / focal_length;
+        self.target -= (right * dx - up * dy) * world_units_per_pixel;
+    }
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Displays a point cloud file for quick visual inspection")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("BUDGET")
+                .long("budget")
+                .takes_value(true)
+                .value_name("BUDGET")
+                .help("Maximum number of points to display. Points are selected by level of detail, so that a spatially uniform subset of the point cloud is displayed")
+                .default_value("2000000"),
+        )
+        .arg(
+            Arg::with_name("COLOR")
+                .long("color")
+                .takes_value(true)
+                .value_name("COLOR")
+                .possible_values(&["elevation", "classification", "intensity", "rgb"])
+                .help("Initial coloring of the points. Can be changed in the viewer with the keys 1-4")
+                .default_value("elevation"),
+        )
+        .arg(
+            Arg::with_name("POINT_SIZE")
+                .long("point-size")
+                .takes_value(true)
+                .value_name("POINT_SIZE")
+                .help("Size of the points in pixels")
+                .default_value("2"),
+        )
+        .arg(
+            Arg::with_name("WIDTH")
+                .long("width")
+                .takes_value(true)
+                .value_name("WIDTH")
+                .help("Width of the window in pixels")
+                .default_value("1280"),
+        )
+        .arg(
+            Arg::with_name("HEIGHT")
+                .long("height")
+                .takes_value(true)
+                .value_name("HEIGHT")
+                .help("Height of the window in pixels")
+                .default_value("720"),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let point_budget = value_t!(matches, "BUDGET", usize)?;
+    if point_budget == 0 {
+        return Err(anyhow!("Point budget must be > 0"));
+    }
+    let color_mode = match matches.value_of("COLOR").unwrap() {
+        "classification" => ColorMode::Classification,
+        "intensity" => ColorMode::Intensity,
+        "rgb" => ColorMode::Rgb,
+        _ => ColorMode::Elevation,
+    };
+    let point_size = value_t!(matches, "POINT_SIZE", usize)?.max(1);
+    let width = value_t!(matches, "WIDTH", usize)?;
+    let height = value_t!(matches, "HEIGHT", usize)?;
+
+    Ok(Args {
+        input_file,
+        point_budget,
+        color_mode,
+        point_size,
+        width,
+        height,
+    })
+}
+
+/// Orders the points by level of detail, similar to the nodes of the octree that the `index` tool builds: The first
+/// level contains at most one point per cell of a grid with `LOD_GRID_SIZE`^3 cells over the bounding box, and each
+/// following level doubles the resolution of the grid. Every prefix of the returned indices is thus a spatially
+/// uniform subset of the point cloud. Returns at most `max_points` indices
+fn lod_order(positions: &[Vector3<f64>], bounds: &AABB<f64>, max_points: usize) -> Vec<usize> {
+    let extent = bounds.extent();
+    let min = bounds.min().coords;
+    let mut order = Vec::with_capacity(max_points.min(positions.len()));
+    let mut remaining = (0..positions.len()).collect::<Vec<_>>();
+    let mut rng = SmallRng::seed_from_u64(0);
+
+    for level in 0..MAX_LOD_LEVELS {
+        if remaining.is_empty() || order.len() >= max_points {
+            break;
+        }
+        let cells_per_axis = LOD_GRID_SIZE << level;
+        let cell_of = |value: f64, min: f64, extent: f64| -> u64 {
+            if extent <= 0.0 {
+                return 0;
+            }
+            (((value - min) / extent * cells_per_axis as f64) as u64).min(cells_per_axis - 1)
+        };
+
+        let mut occupied_cells = HashSet::new();
+        let mut level_points = vec![];
+        let mut next_remaining = vec![];
+        for index in remaining {
+            let position = &positions[index];
+            let cell = (
+                cell_of(position.x, min.x, extent.x),
+                cell_of(position.y, min.y, extent.y),
+                cell_of(position.z, min.z, extent.z),
+            );
+            if occupied_cells.insert(cell) {
+                level_points.push(index);
+            } else {
+                next_remaining.push(index);
+            }
+        }
+        remaining = next_remaining;
+
+        // If only parts of this level fit into the budget, select them randomly instead of in file order, which would
+        // favor the regions that were scanned first
+        if order.len() + level_points.len() > max_points {
+            level_points.shuffle(&mut rng);
+        }
+        order.extend(level_points);
+    }
+
+    order.extend(remaining);
+    order.truncate(max_points);
+    order
+}
+
+/// Returns the values at the given `indices`
+fn select<T: Copy, I: Iterator<Item = T>>(values: I, indices: &[usize]) -> Vec<T> {
+    let values = values.collect::<Vec<_>>();
+    indices.iter().map(|index| values[*index]).collect()
+}
+
+fn load_cloud(args: &Args) -> Result<Cloud> {
+    let factory: IOFactory = Default::default();
+    let mut reader = factory.make_reader(&args.input_file)?;
+    let layout = reader.get_default_point_layout().clone();
+    if !layout.has_attribute_with_name(POSITION_3D.name()) {
+        return Err(anyhow!("Input file contains no positions"));
+    }
+
+    let point_count = reader.point_count()?;
+    let mut points = InterleavedVecPointStorage::with_capacity(point_count, layout.clone());
+    reader.read_into(&mut points, point_count)?;
+    let bounds =
+        calculate_bounds(&points).ok_or_else(|| anyhow!("Input file contains no points"))?;
+
+    let positions = points
+        .iter_attribute_as::<Vector3<f64>>(&POSITION_3D)
+        .collect::<Vec<_>>();
+    let indices = lod_order(&positions, &bounds, args.point_budget);
+    info!("Displaying {}/{} points", indices.len(), positions.len());
+
+    let center = bounds.center().coords;
+    let selected_positions = indices
+        .iter()
+        .map(|index| (positions[*index] - center).map(|v| v as f32))
+        .collect();
+    let classifications = layout
+        .has_attribute_with_name(CLASSIFICATION.name())
+        .then(|| select(points.iter_attribute_as::<u8>(&CLASSIFICATION), &indices));
+    let intensities = layout
+        .has_attribute_with_name(INTENSITY.name())
+        .then(|| select(points.iter_attribute_as::<u16>(&INTENSITY), &indices));
+    let colors = layout.has_attribute_with_name(COLOR_RGB.name()).then(|| {
+        select(
+            points.iter_attribute_as::<Vector3<u16>>(&COLOR_RGB),
+            &indices,
+        )
+    });
+
+    Ok(Cloud {
+        positions: selected_positions,
+        classifications,
+        intensities,
+        colors,
+        bounds,
+    })
+}
+
+fn to_rgb(r: u8, g: u8, b: u8) -> u32 {
+    ((r as u32) << 16) | ((g as u32) << 8) | b as u32
+}
+
+/// Maps `t` in [0;1] to a color ramp from blue over green to red
+fn color_ramp(t: f32) -> u32 {
+    const STOPS: [[f32; 3]; 5] = [
+        [0.0, 0.0, 255.0],
+        [0.0, 255.0, 255.0],
+        [0.0, 255.0, 0.0],
+        [255.0, 255.0, 0.0],
+        [255.0, 0.0, 0.0],
+    ];
+    let scaled = t.max(0.0).min(1.0) * (STOPS.len() - 1) as f32;
+    let lower = (scaled as usize).min(STOPS.len() - 2);
+    let fraction = scaled - lower as f32;
+    let channel =
+        |c: usize| (STOPS[lower][c] + (STOPS[lower + 1][c] - STOPS[lower][c]) * fraction) as u8;
+    to_rgb(channel(0), channel(1), channel(2))
+}
+
+/// Returns the values at the 2nd and 98th percentile of `values`, which makes for a more useful range for coloring
+/// than the minimum and maximum value if there are a few outliers
+fn robust_range(values: &[u16]) -> (f32, f32) {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let at = |percentile: usize| sorted[(sorted.len() - 1) * percentile / 100] as f32;
+    (at(2), at(98))
+}
+
+fn calculate_colors(cloud: &Cloud, color_mode: ColorMode) -> Vec<u32> {
+    match color_mode {
+        ColorMode::Elevation => {
+            let min_z = (cloud.bounds.min().z - cloud.bounds.center().z) as f32;
+            let range = (cloud.bounds.extent().z as f32).max(f32::EPSILON);
+            cloud
+                .positions
+                .iter()
+                .map(|position| color_ramp((position.z - min_z) / range))
+                .collect()
+        }
+        ColorMode::Classification => cloud
+            .classifications
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|class| {
+                CLASSIFICATION_COLORS
+                    .get(*class as usize)
+                    .copied()
+                    .unwrap_or(0xffffff)
+            })
+            .collect(),
+        ColorMode::Intensity => {
+            let intensities = cloud.intensities.as_ref().unwrap();
+            let (min, max) = robust_range(intensities);
+            let range = (max - min).max(1.0);
+            intensities
+                .iter()
+                .map(|intensity| {
+                    let gray = ((*intensity as f32 - min) / range).max(0.0).min(1.0) * 255.0;
+                    to_rgb(gray as u8, gray as u8, gray as u8)
+                })
+                .collect()
+        }
+        ColorMode::Rgb => {
+            let colors = cloud.colors.as_ref().unwrap();
+            // The LAS format uses 16-bit colors, but some writers store 8-bit colors in the 16-bit fields
+            let is_8_bit = colors
+                .iter()
+                .all(|color| color.x.max(color.y).max(color.z) <= 255);
+            let shift = if is_8_bit { 0 } else { 8 };
+            colors
+                .iter()
+                .map(|color| {
+                    to_rgb(
+                        (color.x >> shift) as u8,
+                        (color.y >> shift) as u8,
+                        (color.z >> shift) as u8,
+                    )
+                })
+                .collect()
+        }
+    }
+}
+
+fn focal_length(height: usize) -> f32 {
+    0.5 * height as f32 / (FIELD_OF_VIEW / 2.0).tan()
+}
+
+/// Renders the points as squares with an edge length of `point_size` pixels into `frame`
+#[allow(clippy::too_many_arguments)]
+fn render(
+    cloud: &Cloud,
+    colors: &[u32],
+    camera: &Camera,
+    point_size: usize,
+    width: usize,
+    height: usize,
+    frame: &mut [u32],
+    depth_buffer: &mut [f32],
+) {
+    frame.iter_mut().for_each(|pixel| *pixel = BACKGROUND_COLOR);
+    depth_buffer
+        .iter_mut()
+        .for_each(|depth| *depth = f32::INFINITY);
+
+    let (right, up) = camera.right_and_up();
+    let forward = up.cross(&right);
+    let focal_length = focal_length(height);
+    let near_plane = camera.distance * 1e-3;
+    let half_size = (point_size / 2) as isize;
+
+    for (position, color) in cloud.positions.iter().zip(colors.iter()) {
+        let relative = position - camera.target;
+        let depth = relative.dot(&forward) + camera.distance;
+        if depth <= near_plane {
+            continue;
+        }
+        let x = (width as f32 / 2.0 + focal_length * relative.dot(&right) / depth) as isize;
+        let y = (height as f32 / 2.0 - focal_length * relative.dot(&up) / depth) as isize;
+        for py in (y - half_size)..(y - half_size + point_size as isize) {
+            if py < 0 || py >= height as isize {
+                continue;
+            }
+            for px in (x - half_size)..(x - half_size + point_size as isize) {
+                if px < 0 || px >= width as isize {
+                    continue;
+                }
+                let pixel = py as usize * width + px as usize;
+                if depth < depth_buffer[pixel] {
+                    depth_buffer[pixel] = depth;
+                    frame[pixel] = *color;
+                }
+            }
+        }
+    }
+}
+
+fn window_title(args: &Args, cloud: &Cloud, color_mode: ColorMode) -> String {
+    format!(
+        "{} - {} points - {:?}",
+        args.input_file.display(),
+        cloud.positions.len(),
+        color_mode
+    )
+}
+
+fn print_controls() {
+    println!("Controls:");
+    println!("  Left mouse button / arrow keys  Rotate");
+    println!("  Right mouse button              Pan");
+    println!("  Mouse wheel / W, S              Zoom");
+    println!(
+        "  1, 2, 3, 4                      Color by elevation, classification, intensity, RGB"
+    );
+    println!("  R                               Reset camera");
+    println!("  Escape                          Quit");
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+    let cloud = load_cloud(&args)?;
+
+    let mut color_mode = args.color_mode;
+    if !cloud.supports(color_mode) {
+        warn!(
+            "Input file has no attribute for coloring by {:?}, coloring by elevation instead",
+            color_mode
+        );
+        color_mode = ColorMode::Elevation;
+    }
+    let mut colors = calculate_colors(&cloud, color_mode);
+
+    let mut window = Window::new(
+        &window_title(&args, &cloud, color_mode),
+        args.width,
+        args.height,
+        WindowOptions {
+            resize: true,
+            ..WindowOptions::default()
+        },
+    )?;
+    window.limit_update_rate(Some(Duration::from_micros(16600)));
+    print_controls();
+
+    let mut camera = Camera::overview(&cloud.bounds);
+    let (mut width, mut height) = (0, 0);
+    let mut frame = vec![];
+    let mut depth_buffer = vec![];
+    let mut last_mouse_position: Option<(f32, f32)> = None;
+    let mut needs_redraw = true;
+
+    while window.is_open() && !window.is_key_down(Key::Escape) {
+        let (new_width, new_height) = window.get_size();
+        if (new_width, new_height) != (width, height) {
+            width = new_width.max(1);
+            height = new_height.max(1);
+            frame = vec![BACKGROUND_COLOR; width * height];
+            depth_buffer = vec![f32::INFINITY; width * height];
+            needs_redraw = true;
+        }
+
+        let mouse_position = window.get_mouse_pos(MouseMode::Pass);
+        if let (Some((x, y)), Some((last_x, last_y))) = (mouse_position, last_mouse_position) {
+            let (dx, dy) = (x - last_x, y - last_y);
+            if dx != 0.0 || dy != 0.0 {
+                if window.get_mouse_down(MouseButton::Left) {
+                    camera.yaw -= dx * 0.01;
+                    camera.pitch += dy * 0.01;
+                    needs_redraw = true;
+                } else if window.get_mouse_down(MouseButton::Right) {
+                    camera.pan(dx, dy, focal_length(height));
+                    needs_redraw = true;
+                }
+            }
+        }
+        last_mouse_position = mouse_position;
+
+        if let Some((_, scroll)) = window.get_scroll_wheel() {
+            camera.distance *= 0.9_f32.powf(scroll.signum());
+            needs_redraw = true;
+        }
+
+        for key in window.get_keys() {
+            match key {
+                Key::Left => camera.yaw += 0.03,
+                Key::Right => camera.yaw -= 0.03,
+                Key::Up => camera.pitch += 0.03,
+                Key::Down => camera.pitch -= 0.03,
+                Key::W => camera.distance *= 0.97,
+                Key::S => camera.distance /= 0.97,
+                _ => continue,
+            }
+            needs_redraw = true;
+        }
+        camera.pitch = camera.pitch.max(-1.55).min(1.55);
+
+        if window.is_key_pressed(Key::R, KeyRepeat::No) {
+            camera = Camera::overview(&cloud.bounds);
+            needs_redraw = true;
+        }
+
+        let selected_color_mode = window
+            .get_keys_pressed(KeyRepeat::No)
+            .into_iter()
+            .filter_map(|key| match key {
+                Key::Key1 => Some(ColorMode::Elevation),
+                Key::Key2 => Some(ColorMode::Classification),
+                Key::Key3 => Some(ColorMode::Intensity),
+                Key::Key4 => Some(ColorMode::Rgb),
+                _ => None,
+            })
+            .last();
+        if let Some(selected_color_mode) = selected_color_mode {
+            if !cloud.supports(selected_color_mode) {
+                warn!(
+                    "Input file has no attribute for coloring by {:?}",
+                    selected_color_mode
+                );
+            } else if selected_color_mode != color_mode {
+                color_mode = selected_color_mode;
+                colors = calculate_colors(&cloud, color_mode);
+                window.set_title(&window_title(&args, &cloud, color_mode));
+                needs_redraw = true;
+            }
+        }
+
+        if needs_redraw {
+            render(
+                &cloud,
+                &colors,
+                &camera,
+                args.point_size,
+                width,
+                height,
+                &mut frame,
+                &mut depth_buffer,
+            );
+            needs_redraw = false;
+        }
+        window.update_with_buffer(&frame, width, height)?;
+    }
+
+    Ok(())
+}
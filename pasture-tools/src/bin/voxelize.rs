@@ -0,0 +1,123 @@
+#![warn(clippy::all)]
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, Result};
+use clap::{value_t, App, Arg};
+use log::info;
+use pasture_algorithms::voxelization::{voxelize, voxelize_with_attribute};
+use pasture_core::{
+    containers::{InterleavedVecPointStorage, PointBuffer},
+    layout::PointAttributeDefinition,
+};
+use pasture_io::base::IOFactory;
+
+struct Args {
+    pub input_file: PathBuf,
+    pub output_file: PathBuf,
+    pub voxel_size: f64,
+    pub attribute: Option<String>,
+}
+
+fn get_args() -> Result<Args> {
+    let matches = App::new("pasture playground")
+        .version("0.1")
+        .author("Pascal Bormann <pascal.bormann@igd.fraunhofer.de>")
+        .about("Voxelizes a point cloud and writes the occupied voxels with their point counts in a flat binary format")
+        .arg(
+            Arg::with_name("INPUT")
+                .short("i")
+                .takes_value(true)
+                .value_name("INPUT")
+                .help("Input point cloud file")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("OUTPUT")
+                .short("o")
+                .takes_value(true)
+                .value_name("OUTPUT")
+                .help("Output file for the voxels")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("VOXEL_SIZE")
+                .long("voxel-size")
+                .takes_value(true)
+                .value_name("VOXEL_SIZE")
+                .help("Edge length of the voxels")
+                .required(true),
+        )
+        .arg(
+            Arg::with_name("ATTRIBUTE")
+                .long("attribute")
+                .takes_value(true)
+                .value_name("ATTRIBUTE")
+                .help("Name of a scalar point attribute (e.g. Intensity) whose minimum, maximum and mean are stored per voxel"),
+        )
+        .get_matches();
+
+    let input_file = PathBuf::from(matches.value_of("INPUT").unwrap());
+    let output_file = PathBuf::from(matches.value_of("OUTPUT").unwrap());
+    let voxel_size = value_t!(matches, "VOXEL_SIZE", f64)?;
+    if voxel_size <= 0.0 {
+        return Err(anyhow!("Voxel size must be > 0"));
+    }
+    let attribute = matches.value_of("ATTRIBUTE").map(|name| name.to_owned());
+
+    Ok(Args {
+        input_file,
+        output_file,
+        voxel_size,
+        attribute,
+    })
+}
+
+fn main() -> Result<()> {
+    pretty_env_logger::init();
+
+    let args = get_args()?;
+
+    let factory: IOFactory = Default::default();
+    let mut reader = factory.make_reader(&args.input_file)?;
+    let point_count = reader.point_count()?;
+    let mut points = InterleavedVecPointStorage::with_capacity(
+        point_count,
+        reader.get_default_point_layout().clone(),
+    );
+    reader.read_into(&mut points, point_count)?;
+    info!("Read {} points", point_count);
+
+    let voxelization = match &args.attribute {
+        Some(name) => {
+            let attribute: PointAttributeDefinition = points
+                .point_layout()
+                .get_attribute_by_name(name)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "File {} has no attribute {}",
+                        args.input_file.display(),
+                        name
+                    )
+                })?
+                .into();
+            voxelize_with_attribute(&points, args.voxel_size, &attribute)?
+        }
+        None => voxelize(&points, args.voxel_size),
+    };
+    info!(
+        "{} occupied voxels with a total volume of {:.3}",
+        voxelization.len(),
+        voxelization.occupied_volume()
+    );
+
+    let mut writer = BufWriter::new(File::create(&args.output_file)?);
+    voxelization.write_binary(&mut writer)?;
+    writer.flush()?;
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use anyhow::Result;
+use pasture_core::math::AABB;
+use pasture_io::las_rs::{Builder, Header};
+
+/// Returns a copy of `header` with a scale and offset that fit the given `bounds`. The offset is the minimum of
+/// `bounds`, rounded down to whole units. The scale of `header` is kept, unless the extent of `bounds` doesn't fit
+/// into the 32-bit integer coordinates of LAS with that scale, in which case it is increased just enough
+pub fn header_for_bounds(header: &Header, bounds: &AABB<f64>) -> Result<Header> {
+    let mut raw_header = header.clone().into_raw()?;
+    let fit = |min: f64, max: f64, scale: f64| {
+        let offset = min.floor();
+        // One unit of headroom, so that rounding the scaled coordinates can't overflow
+        let min_scale = (max - offset) / (i32::MAX as f64 - 1.0);
+        (offset, scale.max(min_scale))
+    };
+    let (x_offset, x_scale) = fit(bounds.min().x, bounds.max().x, raw_header.x_scale_factor);
+    let (y_offset, y_scale) = fit(bounds.min().y, bounds.max().y, raw_header.y_scale_factor);
+    let (z_offset, z_scale) = fit(bounds.min().z, bounds.max().z, raw_header.z_scale_factor);
+    raw_header.x_offset = x_offset;
+    raw_header.y_offset = y_offset;
+    raw_header.z_offset = z_offset;
+    raw_header.x_scale_factor = x_scale;
+    raw_header.y_scale_factor = y_scale;
+    raw_header.z_scale_factor = z_scale;
+
+    let mut builder = Builder::new(raw_header)?;
+    builder.vlrs = header.vlrs().clone();
+    builder.evlrs = header.evlrs().clone();
+    Ok(builder.into_header()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pasture_core::nalgebra::Point3;
+
+    #[test]
+    fn test_header_for_bounds_increases_scale_for_large_extents() -> Result<()> {
+        let header = Builder::from((1, 4)).into_header()?;
+        let bounds = AABB::from_min_max(
+            Point3::new(-0.5, 10.0, 0.0),
+            Point3::new(1.0e7, 20.0, 1.0e8),
+        );
+        let fitted = header_for_bounds(&header, &bounds)?;
+        let transforms = fitted.transforms();
+        assert_eq!(-1.0, transforms.x.offset);
+        assert_eq!(header.transforms().x.scale, transforms.x.scale);
+        assert_eq!(10.0, transforms.y.offset);
+        assert!(transforms.z.scale > header.transforms().z.scale);
+        assert!(1.0e8 / transforms.z.scale < i32::MAX as f64);
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+#![warn(clippy::all)]
+//! Functionality that is shared between the command line tools of pasture
+
+pub mod attribute_values;
+pub mod las_header;
+pub mod progress;
@@ -0,0 +1,30 @@
+use anyhow::Result;
+use indicatif::{ProgressBar, ProgressStyle};
+use pasture_core::util::{CancellationToken, Progress};
+
+/// Creates a progress bar for `total_points` points together with a `Progress` that updates it. Pressing Ctrl-C
+/// cancels the `Progress`, in which case all points that were processed so far are still written to the output file
+///
+/// # Errors
+///
+/// If the Ctrl-C handler can't be installed, e.g. because this function was called before, an error is returned
+pub fn make_progress(total_points: usize) -> Result<(Progress, ProgressBar)> {
+    let progress_bar = ProgressBar::new(total_points as u64);
+    progress_bar.set_style(
+        ProgressStyle::default_bar()
+            .template("{elapsed_precise} [{bar:40}] {pos}/{len} points (ETA {eta})")
+            .progress_chars("=> "),
+    );
+
+    let cancellation_token = CancellationToken::new();
+    let handler_token = cancellation_token.clone();
+    ctrlc::set_handler(move || handler_token.cancel())?;
+
+    let callback_progress_bar = progress_bar.clone();
+    let progress = Progress::default()
+        .with_callback(move |processed: usize, _total: Option<usize>| {
+            callback_progress_bar.set_position(processed as u64)
+        })
+        .with_cancellation_token(cancellation_token);
+    Ok((progress, progress_bar))
+}